@@ -0,0 +1,82 @@
+//! Pitch analysis of a whole recording.
+//!
+//! Runs a detector over consecutive windows of a source until the source
+//! ends, for the `analyze` command and for checking detection offline.
+
+use super::pitch::PitchAlgorithm;
+use super::tracker::PitchFrame;
+use super::traits::AudioSource;
+use super::windowed::Windowed;
+
+/// Pitches detected over a recording.
+#[derive(Debug, Clone, Default)]
+pub struct Analysis {
+    /// Number of windows analyzed.
+    pub windows: usize,
+    /// Windows with a clear pitch, in order, dated by where they end.
+    pub detections: Vec<PitchFrame>,
+}
+
+/// Detect the pitch of each window until the source is exhausted.
+///
+/// A source that never ends, such as a microphone, is read forever.
+pub fn analyze<S: AudioSource>(
+    mut windows: Windowed<S>,
+    detector: &dyn PitchAlgorithm,
+) -> Analysis {
+    let mut analysis = Analysis::default();
+    loop {
+        if let Some(window) = windows.next_window() {
+            analysis.windows += 1;
+            if let Some(result) = detector.detect(window) {
+                analysis.detections.push(PitchFrame {
+                    end: windows.window_end(),
+                    result: Some(result),
+                });
+            }
+        } else if windows.is_exhausted() {
+            break;
+        } else {
+            // Waiting on a live source
+            std::thread::yield_now();
+        }
+    }
+    analysis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::pitch::PitchDetector;
+    use crate::audio::traits::{
+        AudioSink, PcmFormat, TestAudioSource, WavAudioSink, WavAudioSource,
+    };
+    use std::io::Cursor;
+    use std::time::Duration;
+
+    #[test]
+    fn test_finite_wav_terminates() {
+        let tone = TestAudioSource::sine(440.0, 1.1, 44100);
+        let mut cursor = Cursor::new(Vec::new());
+        let mut sink = WavAudioSink::new(&mut cursor, 44100, 1, PcmFormat::S16Le).unwrap();
+        sink.write_samples(tone.samples());
+        sink.finalize().unwrap();
+
+        cursor.set_position(0);
+        let source = WavAudioSource::new(cursor).unwrap();
+        let detector = PitchDetector::new(44100);
+        let analysis = analyze(Windowed::new(source, 11025, 11025), &detector);
+
+        // Four whole quarter-second windows; the trailing tenth is dropped
+        assert_eq!(analysis.windows, 4);
+        assert_eq!(analysis.detections.len(), 4);
+        for (i, frame) in analysis.detections.iter().enumerate() {
+            assert_eq!(frame.end, (i as u64 + 1) * 11025);
+            assert_eq!(
+                frame.time(44100),
+                Duration::from_millis(250 * (i as u64 + 1))
+            );
+            assert!((frame.result.unwrap().frequency - 440.0).abs() < 1.0);
+        }
+    }
+}
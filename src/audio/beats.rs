@@ -0,0 +1,612 @@
+//! Beat (amplitude-modulation) rate estimation.
+//!
+//! Two strings a little apart in pitch sound as one tone whose loudness
+//! swells and fades at the difference frequency. The rate is estimated by
+//! extracting the amplitude envelope and finding its period by autocorrelation.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Root-mean-square level of a block of samples.
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum: f32 = samples.iter().map(|s| s * s).sum();
+    (sum / samples.len() as f32).sqrt()
+}
+
+/// Estimate the modulation rate of an amplitude envelope.
+///
+/// `envelope_rate` is the number of envelope values per second. Returns the
+/// rate in Hz, or `None` if the envelope is too flat or not periodic within
+/// `min_rate..=max_rate`.
+pub fn envelope_beat_rate(
+    envelope: &[f32],
+    envelope_rate: f32,
+    min_rate: f32,
+    max_rate: f32,
+    min_depth: f32,
+) -> Option<f32> {
+    let n = envelope.len();
+    if n < 8 {
+        return None;
+    }
+
+    let mean = envelope.iter().sum::<f32>() / n as f32;
+    if mean <= 1e-6 {
+        return None;
+    }
+
+    // Remove the linear trend so a decaying note doesn't look periodic
+    let detrended = detrend(envelope);
+
+    let variance = detrended.iter().map(|x| x * x).sum::<f32>() / n as f32;
+    let depth = variance.sqrt() / mean;
+    if depth < min_depth {
+        return None;
+    }
+
+    // Lags to search; require at least two full beat periods in the window
+    let min_lag = ((envelope_rate / max_rate).floor() as usize).max(2);
+    let max_lag = ((envelope_rate / min_rate).ceil() as usize).min(n / 2);
+    if max_lag <= min_lag + 1 {
+        return None;
+    }
+
+    let corr: Vec<f32> = (0..=max_lag + 1)
+        .map(|lag| normalized_autocorrelation(&detrended, lag))
+        .collect();
+
+    // The first peak after the correlation has gone negative is one period
+    let start = (1..=max_lag).find(|&lag| corr[lag] < 0.0)?.max(min_lag);
+
+    let mut best_lag = None;
+    for lag in start..=max_lag {
+        if corr[lag] > corr[lag - 1] && corr[lag] >= corr[lag + 1] {
+            best_lag = Some(lag);
+            break;
+        }
+    }
+    let lag = best_lag?;
+
+    if corr[lag] < 0.5 {
+        return None;
+    }
+
+    // Parabolic interpolation for a fractional lag
+    let (s0, s1, s2) = (corr[lag - 1], corr[lag], corr[lag + 1]);
+    let denominator = s0 - 2.0 * s1 + s2;
+    let refined = if denominator.abs() > 1e-10 {
+        lag as f32 + (0.5 * (s0 - s2) / denominator).clamp(-1.0, 1.0)
+    } else {
+        lag as f32
+    };
+
+    let rate = envelope_rate / refined;
+    (min_rate..=max_rate).contains(&rate).then_some(rate)
+}
+
+/// Subtract the least-squares line from a sequence.
+fn detrend(values: &[f32]) -> Vec<f32> {
+    let n = values.len() as f32;
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = values.iter().sum::<f32>() / n;
+
+    let mut covariance = 0.0;
+    let mut variance = 0.0;
+    for (i, &y) in values.iter().enumerate() {
+        let dx = i as f32 - mean_x;
+        covariance += dx * (y - mean_y);
+        variance += dx * dx;
+    }
+    let slope = if variance > 0.0 {
+        covariance / variance
+    } else {
+        0.0
+    };
+
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, &y)| y - mean_y - slope * (i as f32 - mean_x))
+        .collect()
+}
+
+/// Pearson-style autocorrelation over the overlapping part of the sequence.
+fn normalized_autocorrelation(values: &[f32], lag: usize) -> f32 {
+    if lag >= values.len() {
+        return 0.0;
+    }
+    let a = &values[..values.len() - lag];
+    let b = &values[lag..];
+
+    let mut cross = 0.0;
+    let mut energy_a = 0.0;
+    let mut energy_b = 0.0;
+    for (x, y) in a.iter().zip(b) {
+        cross += x * y;
+        energy_a += x * x;
+        energy_b += y * y;
+    }
+
+    let norm = (energy_a * energy_b).sqrt();
+    if norm > 0.0 {
+        cross / norm
+    } else {
+        0.0
+    }
+}
+
+/// Beat-rate detector working on a window of raw samples.
+pub struct BeatDetector {
+    sample_rate: u32,
+    min_rate: f32,
+    max_rate: f32,
+    min_depth: f32,
+}
+
+impl BeatDetector {
+    /// Create a new beat detector.
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            min_rate: 0.3,
+            max_rate: 20.0,
+            min_depth: 0.05,
+        }
+    }
+
+    /// Set the range of beat rates to search, in Hz.
+    pub fn with_rate_range(mut self, min: f32, max: f32) -> Self {
+        self.min_rate = min;
+        self.max_rate = max;
+        self
+    }
+
+    /// Set the minimum modulation depth (envelope std-dev / mean).
+    pub fn with_min_depth(mut self, depth: f32) -> Self {
+        self.min_depth = depth;
+        self
+    }
+
+    /// Estimate the beat rate in Hz of the given samples.
+    ///
+    /// `fundamental` is used to size the envelope blocks to whole periods so
+    /// the waveform itself doesn't ripple through the envelope.
+    pub fn detect(&self, samples: &[f32], fundamental: f32) -> Option<f32> {
+        if fundamental <= 0.0 {
+            return None;
+        }
+
+        let block = self.block_size(fundamental);
+        if samples.len() < block * 8 {
+            return None;
+        }
+
+        let envelope: Vec<f32> = samples.chunks_exact(block).map(rms).collect();
+        let envelope_rate = self.sample_rate as f32 / block as f32;
+
+        envelope_beat_rate(
+            &envelope,
+            envelope_rate,
+            self.min_rate,
+            self.max_rate.min(envelope_rate / 4.0),
+            self.min_depth,
+        )
+    }
+
+    /// Estimate the beat rate in Hz between two notes sounded together.
+    ///
+    /// `partials` are the coincident partial numbers of the lower and upper
+    /// note (e.g. `(3, 2)` for a fifth). The signal is demodulated around the
+    /// coincident partials so only their beating shows in the envelope.
+    /// Returns `None` if no beating is found.
+    pub fn detect_interval(
+        &self,
+        samples: &[f32],
+        lower: f32,
+        upper: f32,
+        partials: (u32, u32),
+    ) -> Option<f32> {
+        if lower <= 0.0 || upper <= 0.0 || partials.0 == 0 || partials.1 == 0 {
+            return None;
+        }
+
+        // Both notes' partials lie near multiples of this frequency; blocks of
+        // whole periods of it reject every partial but the coincident ones
+        let common = lower / partials.1 as f32;
+        let block = self.block_size(common);
+        if samples.len() < block * 8 {
+            return None;
+        }
+
+        let center = (partials.0 as f32 * lower + partials.1 as f32 * upper) / 2.0;
+        let omega = std::f64::consts::TAU * center as f64 / self.sample_rate as f64;
+
+        let envelope: Vec<f32> = samples
+            .chunks_exact(block)
+            .enumerate()
+            .map(|(b, chunk)| {
+                let (mut re, mut im) = (0.0_f32, 0.0_f32);
+                for (i, &x) in chunk.iter().enumerate() {
+                    // Wrap the phase to keep precision over long windows
+                    let phase = ((omega * (b * block + i) as f64) % std::f64::consts::TAU) as f32;
+                    re += x * phase.cos();
+                    im -= x * phase.sin();
+                }
+                (re * re + im * im).sqrt() / block as f32
+            })
+            .collect();
+        let envelope_rate = self.sample_rate as f32 / block as f32;
+
+        envelope_beat_rate(
+            &envelope,
+            envelope_rate,
+            self.min_rate,
+            self.max_rate.min(envelope_rate / 4.0),
+            self.min_depth,
+        )
+    }
+
+    /// Envelope block length: whole periods of the fundamental, at least 5ms.
+    fn block_size(&self, fundamental: f32) -> usize {
+        let period = self.sample_rate as f32 / fundamental;
+        let periods = (0.005 * fundamental).ceil().max(1.0);
+        (period * periods).round().max(1.0) as usize
+    }
+}
+
+/// Flags strings that beat although their pitch is steady and in tune.
+///
+/// Fed with input levels and cents readings over time; the flag is raised
+/// once the pitch has stayed within tolerance for the minimum duration and
+/// the level envelope over that stretch shows periodic beating. The flag is
+/// sticky until [`reset`](Self::reset).
+pub struct FalseBeatDetector {
+    /// Recent (time, level) samples.
+    levels: VecDeque<(Duration, f32)>,
+    /// Time since which every reading has been within tolerance.
+    stable_since: Option<Duration>,
+    /// In-tune tolerance in cents.
+    tolerance: f32,
+    /// How long the pitch must stay in tune before judging.
+    min_duration: Duration,
+    /// How much level history to analyze.
+    window: Duration,
+    /// Whether false beats have been detected.
+    flagged: bool,
+}
+
+impl FalseBeatDetector {
+    /// Envelope resampling rate in Hz.
+    const ENVELOPE_RATE: f32 = 20.0;
+
+    /// Create a new detector with ±5 cent tolerance and a 1 s minimum.
+    pub fn new() -> Self {
+        Self {
+            levels: VecDeque::new(),
+            stable_since: None,
+            tolerance: 5.0,
+            min_duration: Duration::from_secs(1),
+            window: Duration::from_secs(3),
+            flagged: false,
+        }
+    }
+
+    /// Set the in-tune tolerance in cents.
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Set the minimum stable duration before flagging.
+    pub fn with_min_duration(mut self, duration: Duration) -> Self {
+        self.min_duration = duration;
+        self
+    }
+
+    /// Record the input level at the given time.
+    pub fn push_level(&mut self, now: Duration, level: f32) {
+        self.levels.push_back((now, level));
+        while let Some(&(t, _)) = self.levels.front() {
+            if now.saturating_sub(t) > self.window {
+                self.levels.pop_front();
+            } else {
+                break;
+            }
+        }
+        self.evaluate(now);
+    }
+
+    /// Record a cents reading (`None` when no pitch was detected).
+    pub fn push_cents(&mut self, now: Duration, cents: Option<f32>) {
+        match cents {
+            Some(c) if c.abs() <= self.tolerance => {
+                self.stable_since.get_or_insert(now);
+            }
+            _ => self.stable_since = None,
+        }
+        self.evaluate(now);
+    }
+
+    /// Whether false beats have been detected since the last reset.
+    pub fn is_flagged(&self) -> bool {
+        self.flagged
+    }
+
+    /// Clear history and the flag (e.g. for a new note or step).
+    pub fn reset(&mut self) {
+        self.levels.clear();
+        self.stable_since = None;
+        self.flagged = false;
+    }
+
+    fn evaluate(&mut self, now: Duration) {
+        if self.flagged {
+            return;
+        }
+        let Some(since) = self.stable_since else {
+            return;
+        };
+        if now.saturating_sub(since) < self.min_duration {
+            return;
+        }
+
+        let envelope = self.resampled_envelope(since);
+        if envelope_beat_rate(&envelope, Self::ENVELOPE_RATE, 0.5, 6.0, 0.05).is_some() {
+            self.flagged = true;
+        }
+    }
+
+    /// Resample the level history since `from` onto a uniform grid.
+    fn resampled_envelope(&self, from: Duration) -> Vec<f32> {
+        let points: Vec<(f32, f32)> = self
+            .levels
+            .iter()
+            .filter(|(t, _)| *t >= from)
+            .map(|(t, l)| (t.as_secs_f32(), *l))
+            .collect();
+
+        if points.len() < 2 {
+            return Vec::new();
+        }
+
+        let start = points[0].0;
+        let end = points[points.len() - 1].0;
+        let count = ((end - start) * Self::ENVELOPE_RATE) as usize + 1;
+
+        let mut envelope = Vec::with_capacity(count);
+        let mut segment = 0;
+        for i in 0..count {
+            let t = start + i as f32 / Self::ENVELOPE_RATE;
+            while segment + 2 < points.len() && points[segment + 1].0 < t {
+                segment += 1;
+            }
+            let (t0, l0) = points[segment];
+            let (t1, l1) = points[segment + 1];
+            let frac = if t1 > t0 {
+                ((t - t0) / (t1 - t0)).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            envelope.push(l0 + (l1 - l0) * frac);
+        }
+
+        envelope
+    }
+}
+
+impl Default for FalseBeatDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::traits::TestAudioSource;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// Amplitude-modulated sine: (1 + depth * sin(2π·rate·t)) * sin(2π·f·t).
+    fn modulated_sine(frequency: f32, rate: f32, depth: f32, duration_secs: f32) -> Vec<f32> {
+        let num_samples = (SAMPLE_RATE as f32 * duration_secs) as usize;
+        (0..num_samples)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                let envelope = 1.0 + depth * (2.0 * std::f32::consts::PI * rate * t).sin();
+                0.5 * envelope * (2.0 * std::f32::consts::PI * frequency * t).sin()
+            })
+            .collect()
+    }
+
+    /// Piano-like tone: six harmonic partials with falling amplitude.
+    fn harmonic_tone(frequency: f32, duration_secs: f32) -> Vec<f32> {
+        let num_samples = (SAMPLE_RATE as f32 * duration_secs) as usize;
+        (0..num_samples)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                (1..=6)
+                    .map(|k| {
+                        let phase = 2.0 * std::f32::consts::PI * frequency * k as f32 * t;
+                        phase.sin() / k as f32
+                    })
+                    .sum::<f32>()
+                    * 0.2
+            })
+            .collect()
+    }
+
+    /// Two piano-like tones played together.
+    fn interval_mix(lower: f32, upper: f32, duration_secs: f32) -> Vec<f32> {
+        harmonic_tone(lower, duration_secs)
+            .iter()
+            .zip(harmonic_tone(upper, duration_secs))
+            .map(|(a, b)| 0.5 * (a + b))
+            .collect()
+    }
+
+    /// Feed a signal to the detector the way the main loop does: every 50ms,
+    /// the level of the most recent 100ms and a fixed cents reading.
+    fn run_false_beat_detector(samples: &[f32], cents: f32) -> FalseBeatDetector {
+        let mut detector = FalseBeatDetector::new();
+        let hop = SAMPLE_RATE as usize / 20;
+        let window = SAMPLE_RATE as usize / 10;
+
+        let mut end = window;
+        while end <= samples.len() {
+            let now = Duration::from_secs_f32(end as f32 / SAMPLE_RATE as f32);
+            detector.push_level(now, rms(&samples[end - window..end]));
+            detector.push_cents(now, Some(cents));
+            end += hop;
+        }
+
+        detector
+    }
+
+    #[test]
+    fn test_rms() {
+        assert_eq!(rms(&[]), 0.0);
+        assert!((rms(&[0.5, -0.5, 0.5, -0.5]) - 0.5).abs() < 1e-6);
+
+        let source = TestAudioSource::sine(440.0, 0.1, SAMPLE_RATE);
+        let level = rms(source.samples());
+        assert!((level - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_two_tone_beat_rate() {
+        let a = TestAudioSource::sine(440.0, 3.0, SAMPLE_RATE);
+        let b = TestAudioSource::sine(441.0, 3.0, SAMPLE_RATE);
+        let mixed: Vec<f32> = a
+            .samples()
+            .iter()
+            .zip(b.samples())
+            .map(|(x, y)| 0.5 * (x + y))
+            .collect();
+
+        let detector = BeatDetector::new(SAMPLE_RATE);
+        let rate = detector.detect(&mixed, 440.5).expect("Should detect beats");
+        assert!((rate - 1.0).abs() < 0.2, "Expected ~1 beat/s, got {}", rate);
+    }
+
+    #[test]
+    fn test_modulated_tone_beat_rate() {
+        let samples = modulated_sine(261.63, 3.0, 0.4, 2.0);
+        let detector = BeatDetector::new(SAMPLE_RATE);
+        let rate = detector
+            .detect(&samples, 261.63)
+            .expect("Should detect beats");
+        assert!(
+            (rate - 3.0).abs() < 0.2,
+            "Expected ~3 beats/s, got {}",
+            rate
+        );
+    }
+
+    #[test]
+    fn test_clean_tone_has_no_beats() {
+        let source = TestAudioSource::sine(440.0, 2.0, SAMPLE_RATE);
+        let detector = BeatDetector::new(SAMPLE_RATE);
+        assert!(detector.detect(source.samples(), 440.0).is_none());
+    }
+
+    #[test]
+    fn test_decaying_tone_has_no_beats() {
+        let samples: Vec<f32> = TestAudioSource::sine(220.0, 2.0, SAMPLE_RATE)
+            .samples()
+            .iter()
+            .enumerate()
+            .map(|(i, s)| s * (-(i as f32) / SAMPLE_RATE as f32).exp())
+            .collect();
+
+        let detector = BeatDetector::new(SAMPLE_RATE);
+        assert!(detector.detect(&samples, 220.0).is_none());
+    }
+
+    #[test]
+    fn test_interval_beat_rate() {
+        let detector = BeatDetector::new(SAMPLE_RATE);
+
+        // Fifth on A3 with the 3:2 partials 2 Hz apart
+        let lower = 220.0;
+        let upper = (3.0 * lower - 2.0) / 2.0;
+        let mixed = interval_mix(lower, upper, 2.5);
+        let rate = detector
+            .detect_interval(&mixed, lower, upper, (3, 2))
+            .expect("Should detect fifth beats");
+        assert!(
+            (rate - 2.0).abs() < 0.2,
+            "Expected ~2 beats/s, got {}",
+            rate
+        );
+
+        // Major third on C4 beating at 8 Hz
+        let lower = 261.63;
+        let upper = (5.0 * lower - 8.0) / 4.0;
+        let mixed = interval_mix(lower, upper, 2.5);
+        let rate = detector
+            .detect_interval(&mixed, lower, upper, (5, 4))
+            .expect("Should detect third beats");
+        assert!(
+            (rate - 8.0).abs() < 0.5,
+            "Expected ~8 beats/s, got {}",
+            rate
+        );
+    }
+
+    #[test]
+    fn test_pure_interval_has_no_beats() {
+        let detector = BeatDetector::new(SAMPLE_RATE);
+        let mixed = interval_mix(220.0, 440.0, 2.5);
+        assert!(detector
+            .detect_interval(&mixed, 220.0, 440.0, (2, 1))
+            .is_none());
+    }
+
+    #[test]
+    fn test_false_beats_flagged_for_modulated_in_tune_tone() {
+        let samples = modulated_sine(440.0, 1.5, 0.3, 3.0);
+        let detector = run_false_beat_detector(&samples, 0.5);
+        assert!(
+            detector.is_flagged(),
+            "Beating in-tune tone should be flagged"
+        );
+    }
+
+    #[test]
+    fn test_false_beats_not_flagged_for_clean_tone() {
+        let source = TestAudioSource::sine(440.0, 3.0, SAMPLE_RATE);
+        let detector = run_false_beat_detector(source.samples(), 0.5);
+        assert!(!detector.is_flagged(), "Clean tone should not be flagged");
+    }
+
+    #[test]
+    fn test_false_beats_not_flagged_when_out_of_tune() {
+        let samples = modulated_sine(440.0, 1.5, 0.3, 3.0);
+        let detector = run_false_beat_detector(&samples, 20.0);
+        assert!(
+            !detector.is_flagged(),
+            "Beats while out of tune are ordinary beats"
+        );
+    }
+
+    #[test]
+    fn test_false_beats_require_min_duration() {
+        let samples = modulated_sine(440.0, 1.5, 0.3, 0.8);
+        let detector = run_false_beat_detector(&samples, 0.5);
+        assert!(!detector.is_flagged(), "Under 1s of stability is too short");
+    }
+
+    #[test]
+    fn test_false_beats_reset() {
+        let samples = modulated_sine(440.0, 1.5, 0.3, 3.0);
+        let mut detector = run_false_beat_detector(&samples, 0.5);
+        assert!(detector.is_flagged());
+
+        detector.reset();
+        assert!(!detector.is_flagged());
+    }
+}
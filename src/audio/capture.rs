@@ -1,76 +1,566 @@
-//! Microphone input capture using cpal.
+//! Microphone input capture using cpal, or from another audio source such
+//! as a pipe.
 
-use super::traits::AudioSource;
+use super::filter::{Filter, Gain, HighPass, HumFilter};
+use super::recorder::RecordTap;
+use super::traits::{AudioSink, AudioSource};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
 
 /// Error type for audio capture.
 #[derive(Debug, thiserror::Error)]
 pub enum CaptureError {
     #[error("No input device available")]
     NoInputDevice,
+    #[error("Audio device not found: {0}")]
+    DeviceNotFound(String),
+    #[error("Failed to enumerate devices: {0}")]
+    DevicesError(#[from] cpal::DevicesError),
     #[error("Failed to get device config: {0}")]
     ConfigError(#[from] cpal::DefaultStreamConfigError),
     #[error("Failed to build stream: {0}")]
     BuildStreamError(#[from] cpal::BuildStreamError),
     #[error("Failed to play stream: {0}")]
     PlayStreamError(#[from] cpal::PlayStreamError),
+    #[error("Failed to pause stream: {0}")]
+    PauseStreamError(#[from] cpal::PauseStreamError),
+    #[error("Input device lost: {0}")]
+    DeviceLost(String),
 }
 
+/// An audio device offered for selection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioDevice {
+    /// Device name, which cpal also uses to identify the device.
+    pub name: String,
+    /// Whether this is the host's default device.
+    pub is_default: bool,
+}
+
+impl AudioDevice {
+    /// Create a device entry.
+    pub fn new(name: impl Into<String>, is_default: bool) -> Self {
+        Self {
+            name: name.into(),
+            is_default,
+        }
+    }
+}
+
+/// Name of a device, or an empty string if it cannot be read.
+fn device_name(device: &cpal::Device) -> String {
+    device.name().unwrap_or_default()
+}
+
+/// Build the device list, marking the default device.
+fn device_list(
+    devices: impl Iterator<Item = cpal::Device>,
+    default: Option<cpal::Device>,
+) -> Vec<AudioDevice> {
+    let default = default.as_ref().map(device_name);
+    devices
+        .map(|d| device_name(&d))
+        .filter(|name| !name.is_empty())
+        .map(|name| {
+            let is_default = default.as_deref() == Some(name.as_str());
+            AudioDevice::new(name, is_default)
+        })
+        .collect()
+}
+
+/// Counters for the capture stream.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureStats {
+    /// Input callbacks received.
+    pub callbacks: u64,
+    /// Mono samples received.
+    pub samples: u64,
+    /// Stream errors reported.
+    pub errors: u64,
+    /// Input samples received at or beyond full scale, per channel.
+    pub clipped: u64,
+    /// Most recent stream error.
+    pub last_error: Option<String>,
+}
+
+/// Level at or beyond which an input sample counts as clipped.
+pub const CLIP_LEVEL: f32 = 0.999;
+
 /// Shared buffer for audio samples.
 struct SharedBuffer {
     samples: Vec<f32>,
     /// Flag to indicate new samples are available.
     new_data: bool,
+    /// Stream counters.
+    stats: CaptureStats,
+    /// Gain applied as samples arrive, ahead of the filter.
+    gain: Gain,
+    /// Filter applied as samples arrive.
+    high_pass: Option<HighPass>,
+    /// Mains hum notches applied after the high-pass.
+    hum: Option<HumFilter>,
+    /// Recording the samples are copied to.
+    tap: Option<RecordTap>,
+    /// Clipped samples since they were last taken.
+    clipped: usize,
+    /// Most samples of history kept.
+    max_samples: usize,
+    /// Why the device went away, once it has.
+    lost: Option<String>,
+    /// When the latest callback arrived, or capture started or resumed.
+    last_callback: Instant,
+    /// Whether capture is paused, so callbacks aren't expected.
+    paused: bool,
+}
+
+impl SharedBuffer {
+    /// Create a buffer keeping up to `max_samples` of history.
+    fn new(max_samples: usize) -> Self {
+        Self {
+            samples: Vec::with_capacity(max_samples),
+            new_data: false,
+            stats: CaptureStats::default(),
+            gain: Gain::default(),
+            high_pass: None,
+            hum: None,
+            tap: None,
+            clipped: 0,
+            max_samples,
+            lost: None,
+            last_callback: Instant::now(),
+            paused: false,
+        }
+    }
+
+    /// Count a stream error, noting when it means the device is gone.
+    fn stream_error(&mut self, err: cpal::StreamError) {
+        if matches!(err, cpal::StreamError::DeviceNotAvailable) {
+            self.lost = Some(err.to_string());
+        }
+        self.stats.errors += 1;
+        self.stats.last_error = Some(err.to_string());
+    }
+
+    /// Why the device is gone, if it reported so or stopped delivering
+    /// audio for `stall`.
+    fn lost(&self, stall: Duration) -> Option<String> {
+        if let Some(lost) = &self.lost {
+            return Some(lost.clone());
+        }
+        // Some hosts stop calling back without reporting an error
+        (!self.paused && self.last_callback.elapsed() >= stall)
+            .then(|| format!("no audio for {} s", stall.as_secs()))
+    }
+
+    /// Take in a callback's worth of interleaved input, mixed down to mono.
+    /// Clipping is counted per channel after the gain, before the mix can
+    /// hide it.
+    fn receive<T: Copy>(&mut self, data: &[T], channels: usize, to_f32: impl Fn(T) -> f32) {
+        let start = self.samples.len();
+        let mut clipped = 0;
+        for frame in data.chunks(channels) {
+            let mut sum = 0.0;
+            for &s in frame {
+                let s = self.gain.process(to_f32(s));
+                if s.abs() >= CLIP_LEVEL {
+                    clipped += 1;
+                }
+                sum += s;
+            }
+            self.push(sum / channels as f32);
+        }
+        self.tee(start);
+
+        // Keep buffer at reasonable size
+        if self.samples.len() > self.max_samples {
+            let excess = self.samples.len() - self.max_samples;
+            self.samples.drain(0..excess);
+        }
+
+        self.last_callback = Instant::now();
+        self.clipped += clipped;
+        self.stats.clipped += clipped as u64;
+        self.stats.callbacks += 1;
+        self.stats.samples += (data.len() / channels) as u64;
+        self.new_data = true;
+    }
+
+    /// Copy the most recent samples into `buffer` if any arrived since the
+    /// last read. Returns the count copied and the index, counted from the
+    /// start of capture, one past the newest of them.
+    fn read(&mut self, buffer: &mut [f32]) -> (usize, u64) {
+        let end = self.stats.samples;
+        // Only return samples if we have new data
+        if !self.new_data {
+            return (0, end);
+        }
+
+        // Copy the most recent samples (sliding window)
+        let available = self.samples.len();
+        let to_read = buffer.len().min(available);
+        let start = available - to_read;
+        buffer[..to_read].copy_from_slice(&self.samples[start..]);
+
+        self.new_data = false;
+        (to_read, end)
+    }
+
+    /// Append a mono sample, filtered if filters are set.
+    fn push(&mut self, sample: f32) {
+        let sample = match &mut self.high_pass {
+            Some(filter) => filter.process(sample),
+            None => sample,
+        };
+        let sample = match &mut self.hum {
+            Some(filter) => filter.process(sample),
+            None => sample,
+        };
+        self.samples.push(sample);
+    }
+
+    /// Copy the samples from `start` on to the recording, if any.
+    fn tee(&self, start: usize) {
+        if let Some(tap) = &self.tap {
+            tap.send(&self.samples[start..]);
+        }
+    }
 }
 
-/// Microphone capture using the system's default input device.
+/// Samples taken from a source per read on the reader thread.
+const SOURCE_CHUNK: usize = 512;
+
+/// What delivers the captured audio.
+enum Stream {
+    /// An input device's stream.
+    Device(cpal::Stream),
+    /// A thread reading an [`AudioSource`]; it stops once the capture is
+    /// dropped.
+    Reader,
+}
+
+/// Microphone capture from an input device.
 pub struct MicCapture {
-    _stream: cpal::Stream,
+    stream: Stream,
     buffer: Arc<Mutex<SharedBuffer>>,
     sample_rate: u32,
+    buffer_frames: Option<u32>,
+    device_name: String,
+    /// Sample index one past the newest sample of the last read.
+    read_end: u64,
 }
 
-impl MicCapture {
-    /// Create a new microphone capture instance.
-    pub fn new() -> Result<Self, CaptureError> {
-        let host = cpal::default_host();
+/// Options for opening microphone capture.
+///
+/// The device may not support what is asked for: the closest supported
+/// sample rate and buffer size are used instead, and can be read back from
+/// the capture.
+#[derive(Debug, Clone, Default)]
+pub struct MicCaptureBuilder {
+    device: Option<String>,
+    sample_rate: Option<u32>,
+    buffer_frames: Option<u32>,
+}
+
+impl MicCaptureBuilder {
+    /// Capture from the named input device, or the default if `None`.
+    pub fn with_device(mut self, name: Option<&str>) -> Self {
+        self.device = name.map(str::to_string);
+        self
+    }
+
+    /// Request a sample rate in Hz, or the device's default if `None`.
+    pub fn with_sample_rate(mut self, sample_rate: Option<u32>) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    /// Request frames per callback, or the host's choice if `None`. Smaller
+    /// buffers lower the latency.
+    pub fn with_buffer_frames(mut self, frames: Option<u32>) -> Self {
+        self.buffer_frames = frames;
+        self
+    }
 
-        let device = host
-            .default_input_device()
-            .ok_or(CaptureError::NoInputDevice)?;
+    /// Open the stream and start capturing.
+    pub fn open(self) -> Result<MicCapture, CaptureError> {
+        let host = cpal::default_host();
 
-        let config = device.default_input_config()?;
+        let device = match &self.device {
+            Some(name) => host
+                .input_devices()?
+                .find(|d| device_name(d) == *name)
+                .ok_or_else(|| CaptureError::DeviceNotFound(name.clone()))?,
+            None => host
+                .default_input_device()
+                .ok_or(CaptureError::NoInputDevice)?,
+        };
+        let device_name = device_name(&device);
+
+        let default = device.default_input_config()?;
+        let (config, buffer_size) = if self.sample_rate.is_some() || self.buffer_frames.is_some() {
+            // A device that can't list its configs still has its default
+            let supported: Vec<_> = device
+                .supported_input_configs()
+                .map(Iterator::collect)
+                .unwrap_or_default();
+            negotiate(&supported, default, self.sample_rate, self.buffer_frames)
+        } else {
+            (default, cpal::BufferSize::Default)
+        };
         let sample_rate = config.sample_rate().0;
 
-        let buffer = Arc::new(Mutex::new(SharedBuffer {
-            samples: Vec::with_capacity(sample_rate as usize), // 1 second buffer
-            new_data: false,
-        }));
+        // Keep a few seconds of history for beat analysis
+        let max_samples = sample_rate as usize * 3;
 
-        let buffer_clone = Arc::clone(&buffer);
+        let buffer = Arc::new(Mutex::new(SharedBuffer::new(max_samples)));
 
-        let stream = match config.sample_format() {
-            cpal::SampleFormat::F32 => {
-                Self::build_stream_f32(&device, &config.into(), buffer_clone)?
-            }
-            cpal::SampleFormat::I16 => {
-                Self::build_stream_i16(&device, &config.into(), buffer_clone)?
-            }
-            _ => {
-                return Err(CaptureError::BuildStreamError(
-                    cpal::BuildStreamError::StreamConfigNotSupported,
-                ));
+        let build = |buffer_size| {
+            let mut stream_config = config.config();
+            stream_config.buffer_size = buffer_size;
+            let buffer_clone = Arc::clone(&buffer);
+            match config.sample_format() {
+                cpal::SampleFormat::F32 => {
+                    MicCapture::build_stream_f32(&device, &stream_config, buffer_clone)
+                }
+                cpal::SampleFormat::I16 => {
+                    MicCapture::build_stream_i16(&device, &stream_config, buffer_clone)
+                }
+                _ => Err(cpal::BuildStreamError::StreamConfigNotSupported),
             }
         };
 
+        // Some hosts don't report a buffer range, and refuse sizes they
+        // don't like
+        let (stream, buffer_frames) = match buffer_size {
+            cpal::BufferSize::Fixed(frames) => match build(buffer_size) {
+                Ok(stream) => (stream, Some(frames)),
+                Err(_) => (build(cpal::BufferSize::Default)?, None),
+            },
+            cpal::BufferSize::Default => (build(buffer_size)?, None),
+        };
+
         stream.play()?;
 
-        Ok(Self {
-            _stream: stream,
+        Ok(MicCapture {
+            stream: Stream::Device(stream),
             buffer,
             sample_rate,
+            buffer_frames,
+            device_name,
+            read_end: 0,
+        })
+    }
+}
+
+/// Pick the stream config and buffer size closest to the requested ones
+/// among the supported configs, or fall back to the default config.
+///
+/// Configs with the default's channel count and sample format are
+/// preferred at the same sample rate.
+fn negotiate(
+    supported: &[cpal::SupportedStreamConfigRange],
+    default: cpal::SupportedStreamConfig,
+    sample_rate: Option<u32>,
+    buffer_frames: Option<u32>,
+) -> (cpal::SupportedStreamConfig, cpal::BufferSize) {
+    let usable = |format| matches!(format, cpal::SampleFormat::F32 | cpal::SampleFormat::I16);
+    let config = sample_rate
+        .and_then(|rate| {
+            let closest = |range: &cpal::SupportedStreamConfigRange| {
+                rate.clamp(range.min_sample_rate().0, range.max_sample_rate().0)
+            };
+            supported
+                .iter()
+                .filter(|range| usable(range.sample_format()))
+                .min_by_key(|range| {
+                    (
+                        closest(range).abs_diff(rate),
+                        range.channels() != default.channels(),
+                        range.sample_format() != default.sample_format(),
+                    )
+                })
+                .map(|range| (*range).with_sample_rate(cpal::SampleRate(closest(range))))
         })
+        .unwrap_or(default);
+
+    let buffer_size = match (buffer_frames, config.buffer_size()) {
+        (Some(frames), cpal::SupportedBufferSize::Range { min, max }) => {
+            cpal::BufferSize::Fixed(frames.clamp(*min, (*max).max(*min)))
+        }
+        (Some(frames), cpal::SupportedBufferSize::Unknown) => cpal::BufferSize::Fixed(frames),
+        (None, _) => cpal::BufferSize::Default,
+    };
+    (config, buffer_size)
+}
+
+impl MicCapture {
+    /// Time without callbacks after which the device counts as lost.
+    pub const STALL_TIMEOUT: Duration = Duration::from_secs(2);
+
+    /// Create a new microphone capture instance on the default input device.
+    pub fn new() -> Result<Self, CaptureError> {
+        Self::open(None)
+    }
+
+    /// List the available input devices.
+    pub fn list_devices() -> Result<Vec<AudioDevice>, CaptureError> {
+        let host = cpal::default_host();
+        Ok(device_list(
+            host.input_devices()?,
+            host.default_input_device(),
+        ))
+    }
+
+    /// Open capture on the named input device, or the default if `None`.
+    pub fn open(name: Option<&str>) -> Result<Self, CaptureError> {
+        Self::builder().with_device(name).open()
+    }
+
+    /// Capture from `source` instead of a device, e.g. raw audio piped to
+    /// stdin. The capture counts as lost once the source is exhausted.
+    pub fn from_source<S>(mut source: S, name: impl Into<String>) -> Self
+    where
+        S: AudioSource + Send + 'static,
+    {
+        let sample_rate = source.sample_rate();
+        let buffer = Arc::new(Mutex::new(SharedBuffer::new(sample_rate as usize * 3)));
+        let shared: Weak<Mutex<SharedBuffer>> = Arc::downgrade(&buffer);
+        std::thread::spawn(move || {
+            let mut chunk = vec![0.0; SOURCE_CHUNK];
+            loop {
+                let read = source.read_samples(&mut chunk);
+                let Some(buffer) = shared.upgrade() else {
+                    break;
+                };
+                let mut buf = buffer.lock().unwrap();
+                if read == 0 {
+                    if source.is_exhausted() {
+                        buf.lost = Some("end of input".to_string());
+                        break;
+                    }
+                    drop(buf);
+                    std::thread::sleep(Duration::from_millis(5));
+                } else if !buf.paused {
+                    buf.receive(&chunk[..read], 1, |s| s);
+                }
+            }
+        });
+
+        MicCapture {
+            stream: Stream::Reader,
+            buffer,
+            sample_rate,
+            buffer_frames: None,
+            device_name: name.into(),
+            read_end: 0,
+        }
+    }
+
+    /// Start configuring capture, for a sample rate or buffer size other
+    /// than the device's default.
+    pub fn builder() -> MicCaptureBuilder {
+        MicCaptureBuilder::default()
+    }
+
+    /// High-pass the input below `cutoff` Hz as it arrives, or leave it
+    /// unfiltered if `None`.
+    pub fn with_high_pass(self, cutoff: Option<f32>) -> Self {
+        let sample_rate = self.sample_rate;
+        self.buffer.lock().unwrap().high_pass =
+            cutoff.map(|cutoff| HighPass::new(cutoff, sample_rate));
+        self
+    }
+
+    /// Notch out hum at `mains` Hz and its harmonics as the input arrives,
+    /// or leave it if `None`.
+    pub fn with_hum_filter(self, mains: Option<f32>) -> Self {
+        let sample_rate = self.sample_rate;
+        self.buffer.lock().unwrap().hum = mains.map(|mains| HumFilter::new(mains, sample_rate));
+        self
+    }
+
+    /// Amplify the input by `db` decibels as it arrives, within the limits
+    /// of [`Gain`].
+    pub fn set_gain_db(&self, db: f32) {
+        let mut buf = self.buffer.lock().unwrap();
+        if buf.gain.db() != db {
+            buf.gain = Gain::from_db(db);
+        }
+    }
+
+    /// Copy the input to a recording as it arrives, after filtering, or
+    /// stop copying if `None`.
+    pub fn set_tap(&self, tap: Option<RecordTap>) {
+        self.buffer.lock().unwrap().tap = tap;
+    }
+
+    /// Stop capturing until [`Self::resume`] is called.
+    pub fn pause(&self) -> Result<(), CaptureError> {
+        if let Stream::Device(stream) = &self.stream {
+            stream.pause()?;
+        }
+        self.buffer.lock().unwrap().paused = true;
+        Ok(())
+    }
+
+    /// Restart capturing, dropping the history from before the pause.
+    pub fn resume(&self) -> Result<(), CaptureError> {
+        {
+            let mut buf = self.buffer.lock().unwrap();
+            buf.samples.clear();
+            buf.new_data = false;
+            buf.paused = false;
+            buf.last_callback = Instant::now();
+            if let Some(filter) = &mut buf.high_pass {
+                filter.reset();
+            }
+            if let Some(filter) = &mut buf.hum {
+                filter.reset();
+            }
+        }
+        if let Stream::Device(stream) = &self.stream {
+            stream.play()?;
+        }
+        Ok(())
+    }
+
+    /// Get the name of the input device.
+    pub fn device_name(&self) -> &str {
+        &self.device_name
+    }
+
+    /// Fail with [`CaptureError::DeviceLost`] once the device has been
+    /// unplugged or has stopped delivering audio.
+    pub fn check(&self) -> Result<(), CaptureError> {
+        match self.buffer.lock().unwrap().lost(Self::STALL_TIMEOUT) {
+            Some(reason) => Err(CaptureError::DeviceLost(reason)),
+            None => Ok(()),
+        }
+    }
+
+    /// Frames per callback negotiated with the device, or `None` if the
+    /// host picks.
+    pub fn buffer_frames(&self) -> Option<u32> {
+        self.buffer_frames
+    }
+
+    /// Index of the sample just after the newest one returned by the last
+    /// read, counted from the start of capture. With the sample rate this
+    /// dates the samples by when they were captured rather than read.
+    pub fn read_end(&self) -> u64 {
+        self.read_end
+    }
+
+    /// Get the stream counters.
+    pub fn stats(&self) -> CaptureStats {
+        self.buffer.lock().unwrap().stats.clone()
+    }
+
+    /// Input samples clipped since the last call. Clipping distorts the
+    /// waveform and pulls readings sharp while confidence stays high.
+    pub fn take_clipped(&self) -> usize {
+        std::mem::take(&mut self.buffer.lock().unwrap().clipped)
     }
 
     fn build_stream_f32(
@@ -79,29 +569,16 @@ impl MicCapture {
         buffer: Arc<Mutex<SharedBuffer>>,
     ) -> Result<cpal::Stream, cpal::BuildStreamError> {
         let channels = config.channels as usize;
+        let errors = Arc::clone(&buffer);
 
         device.build_input_stream(
             config,
             move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                let mut buf = buffer.lock().unwrap();
-
-                // Convert to mono and append to buffer
-                for frame in data.chunks(channels) {
-                    let mono: f32 = frame.iter().sum::<f32>() / channels as f32;
-                    buf.samples.push(mono);
-                }
-
-                // Keep buffer at reasonable size (~0.5 second for pitch detection)
-                let max_samples = 22050;
-                if buf.samples.len() > max_samples {
-                    let excess = buf.samples.len() - max_samples;
-                    buf.samples.drain(0..excess);
-                }
-
-                buf.new_data = true;
+                buffer.lock().unwrap().receive(data, channels, |s| s);
             },
-            |err| {
-                eprintln!("Audio capture error: {}", err);
+            move |err| {
+                // Never print: the TUI owns the terminal
+                errors.lock().unwrap().stream_error(err);
             },
             None,
         )
@@ -113,30 +590,19 @@ impl MicCapture {
         buffer: Arc<Mutex<SharedBuffer>>,
     ) -> Result<cpal::Stream, cpal::BuildStreamError> {
         let channels = config.channels as usize;
+        let errors = Arc::clone(&buffer);
 
         device.build_input_stream(
             config,
             move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                let mut buf = buffer.lock().unwrap();
-
-                // Convert to mono f32 and append to buffer
-                for frame in data.chunks(channels) {
-                    let mono: f32 =
-                        frame.iter().map(|&s| s as f32 / 32768.0).sum::<f32>() / channels as f32;
-                    buf.samples.push(mono);
-                }
-
-                // Keep buffer at reasonable size (~0.5 second for pitch detection)
-                let max_samples = 22050;
-                if buf.samples.len() > max_samples {
-                    let excess = buf.samples.len() - max_samples;
-                    buf.samples.drain(0..excess);
-                }
-
-                buf.new_data = true;
+                buffer
+                    .lock()
+                    .unwrap()
+                    .receive(data, channels, |s| s as f32 / 32768.0);
             },
-            |err| {
-                eprintln!("Audio capture error: {}", err);
+            move |err| {
+                // Never print: the TUI owns the terminal
+                errors.lock().unwrap().stream_error(err);
             },
             None,
         )
@@ -145,47 +611,61 @@ impl MicCapture {
 
 impl AudioSource for MicCapture {
     fn read_samples(&mut self, buffer: &mut [f32]) -> usize {
-        let mut buf = self.buffer.lock().unwrap();
-
-        // Only return samples if we have new data
-        if !buf.new_data {
-            return 0;
-        }
-
-        // Copy the most recent samples (sliding window)
-        let available = buf.samples.len();
-        let to_read = buffer.len().min(available);
-
-        if to_read > 0 {
-            // Copy from the end of the buffer (most recent samples)
-            let start = available - to_read;
-            buffer[..to_read].copy_from_slice(&buf.samples[start..]);
-        }
-
-        buf.new_data = false;
-        to_read
+        let (read, end) = self.buffer.lock().unwrap().read(buffer);
+        self.read_end = end;
+        read
     }
 
     fn sample_rate(&self) -> u32 {
         self.sample_rate
     }
+
+    fn is_exhausted(&self) -> bool {
+        // A stalled or lost device reads nothing but may come back; device
+        // errors are reported through the stats instead
+        false
+    }
 }
 
 /// Audio output sink using cpal.
+///
+/// Samples are queued interleaved: stereo on devices with two or more
+/// channels, mono otherwise.
 pub struct AudioOutput {
     _stream: cpal::Stream,
     buffer: Arc<Mutex<Vec<f32>>>,
     sample_rate: u32,
+    channels: u16,
 }
 
 impl AudioOutput {
-    /// Create a new audio output instance.
+    /// Create a new audio output instance on the default output device.
     pub fn new() -> Result<Self, CaptureError> {
+        Self::open(None)
+    }
+
+    /// List the available output devices.
+    pub fn list_devices() -> Result<Vec<AudioDevice>, CaptureError> {
+        let host = cpal::default_host();
+        Ok(device_list(
+            host.output_devices()?,
+            host.default_output_device(),
+        ))
+    }
+
+    /// Open output on the named device, or the default if `None`.
+    pub fn open(name: Option<&str>) -> Result<Self, CaptureError> {
         let host = cpal::default_host();
 
-        let device = host
-            .default_output_device()
-            .ok_or(CaptureError::NoInputDevice)?;
+        let device = match name {
+            Some(name) => host
+                .output_devices()?
+                .find(|d| device_name(d) == name)
+                .ok_or_else(|| CaptureError::DeviceNotFound(name.to_string()))?,
+            None => host
+                .default_output_device()
+                .ok_or(CaptureError::NoInputDevice)?,
+        };
 
         let config = device.default_output_config()?;
         let sample_rate = config.sample_rate().0;
@@ -193,18 +673,28 @@ impl AudioOutput {
         let buffer: Arc<Mutex<Vec<f32>>> = Arc::new(Mutex::new(Vec::new()));
         let buffer_clone = Arc::clone(&buffer);
 
-        let channels = config.channels() as usize;
+        let device_channels = config.channels() as usize;
+        let channels = device_channels.min(2);
 
         let stream = device.build_output_stream(
             &config.into(),
             move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
                 let mut buf = buffer_clone.lock().unwrap();
 
-                for frame in data.chunks_mut(channels) {
-                    let sample = if !buf.is_empty() { buf.remove(0) } else { 0.0 };
+                for frame in data.chunks_mut(device_channels) {
+                    let available = channels.min(buf.len());
+                    let mut queued = [0.0; 2];
+                    for (q, s) in queued.iter_mut().zip(buf.drain(..available)) {
+                        *q = s;
+                    }
 
-                    for s in frame.iter_mut() {
-                        *s = sample;
+                    // Mono goes to every channel; stereo to the first two
+                    for (i, s) in frame.iter_mut().enumerate() {
+                        *s = match (channels, i) {
+                            (1, _) => queued[0],
+                            (_, 0 | 1) => queued[i],
+                            _ => 0.0,
+                        };
                     }
                 }
             },
@@ -220,32 +710,271 @@ impl AudioOutput {
             _stream: stream,
             buffer,
             sample_rate,
+            channels: channels as u16,
         })
     }
 
-    /// Queue samples for playback.
+    /// Queue mono samples for playback on every channel.
     pub fn queue(&self, samples: &[f32]) {
         let mut buf = self.buffer.lock().unwrap();
-        buf.extend_from_slice(samples);
+        for &s in samples {
+            buf.extend(std::iter::repeat_n(s, self.channels as usize));
+        }
+    }
+
+    /// Drop everything queued, silencing the output.
+    pub fn clear(&self) {
+        self.buffer.lock().unwrap().clear();
+    }
+
+    /// Frames queued and not yet played.
+    pub fn queued_frames(&self) -> usize {
+        self.buffer.lock().unwrap().len() / self.channels as usize
     }
 
     /// Get the sample rate.
     pub fn sample_rate(&self) -> u32 {
         self.sample_rate
     }
+}
 
-    /// Play a sine wave at the given frequency for the given duration.
-    pub fn play_sine(&self, frequency: f32, duration: f32) -> anyhow::Result<()> {
-        let num_samples = (self.sample_rate as f32 * duration) as usize;
-        let mut samples = Vec::with_capacity(num_samples);
+impl AudioSink for AudioOutput {
+    fn write_samples(&mut self, samples: &[f32]) {
+        self.buffer.lock().unwrap().extend_from_slice(samples);
+    }
 
-        for i in 0..num_samples {
-            let t = i as f32 / self.sample_rate as f32;
-            let sample = 0.3 * (2.0 * std::f32::consts::PI * frequency * t).sin();
-            samples.push(sample);
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::traits::TestAudioSource;
+
+    /// Feed `samples` to the buffer as stereo callbacks of 512 frames.
+    fn receive_stereo(buffer: &mut SharedBuffer, samples: &[f32]) {
+        let interleaved: Vec<f32> = samples.iter().flat_map(|&s| [s, s]).collect();
+        for callback in interleaved.chunks(1024) {
+            buffer.receive(callback, 2, |s| s);
         }
+    }
 
-        self.queue(&samples);
-        Ok(())
+    #[test]
+    fn test_reads_indexed_by_capture() {
+        let mut buffer = SharedBuffer::new(44100);
+        let mut window = vec![0.0; 4096];
+        assert_eq!(buffer.read(&mut window), (0, 0));
+
+        // Callbacks of varying sizes, some read, some missed
+        let mut captured = 0u64;
+        let mut last_end = 0;
+        for (i, frames) in [480, 512, 1024, 7, 2048, 333]
+            .iter()
+            .cycle()
+            .take(30)
+            .enumerate()
+        {
+            receive_stereo(&mut buffer, &vec![0.1; *frames]);
+            captured += *frames as u64;
+            if i % 3 == 2 {
+                continue;
+            }
+            let (read, end) = buffer.read(&mut window);
+            assert!(read > 0);
+            assert_eq!(end, captured);
+            assert!(end > last_end);
+            last_end = end;
+
+            // Nothing new until the next callback
+            assert_eq!(buffer.read(&mut window), (0, end));
+        }
+    }
+
+    #[test]
+    fn test_clipped_sine_counted() {
+        let mut buffer = SharedBuffer::new(44100);
+        let clean = TestAudioSource::sine(440.0, 0.1, 44100);
+        let quiet: Vec<f32> = clean.samples().iter().map(|s| s * 0.9).collect();
+        receive_stereo(&mut buffer, &quiet);
+        assert_eq!(buffer.clipped, 0);
+
+        // Too much gain flattens the peaks at full scale
+        let hot: Vec<f32> = clean
+            .samples()
+            .iter()
+            .map(|s| (s * 2.0).clamp(-1.0, 1.0))
+            .collect();
+        receive_stereo(&mut buffer, &hot);
+        let clipped_frames = hot.iter().filter(|s| s.abs() >= CLIP_LEVEL).count();
+        assert!(clipped_frames > hot.len() / 2);
+        assert_eq!(buffer.clipped, clipped_frames * 2);
+        assert_eq!(buffer.stats.clipped, clipped_frames as u64 * 2);
+        assert_eq!(buffer.samples.len(), quiet.len() + hot.len());
+    }
+
+    fn range(
+        channels: u16,
+        rates: (u32, u32),
+        buffer: Option<(u32, u32)>,
+        format: cpal::SampleFormat,
+    ) -> cpal::SupportedStreamConfigRange {
+        let buffer_size = match buffer {
+            Some((min, max)) => cpal::SupportedBufferSize::Range { min, max },
+            None => cpal::SupportedBufferSize::Unknown,
+        };
+        cpal::SupportedStreamConfigRange::new(
+            channels,
+            cpal::SampleRate(rates.0),
+            cpal::SampleRate(rates.1),
+            buffer_size,
+            format,
+        )
+    }
+
+    /// A stereo float device defaulting to 44.1 kHz that supports 8 to
+    /// 48 kHz, plus mono 16-bit up to 96 kHz.
+    fn device() -> (
+        Vec<cpal::SupportedStreamConfigRange>,
+        cpal::SupportedStreamConfig,
+    ) {
+        use cpal::SampleFormat::{F32, I16, U16};
+        let supported = vec![
+            range(2, (8000, 48000), Some((64, 4096)), F32),
+            range(1, (8000, 96000), None, I16),
+            range(2, (8000, 192000), Some((64, 4096)), U16),
+        ];
+        let default = supported[0].with_sample_rate(cpal::SampleRate(44100));
+        (supported, default)
+    }
+
+    #[test]
+    fn test_negotiate_sample_rate() {
+        let (supported, default) = device();
+
+        let (config, buffer) = negotiate(&supported, default.clone(), None, None);
+        assert_eq!(config, default);
+        assert_eq!(buffer, cpal::BufferSize::Default);
+
+        // Supported by the default's channels and format
+        let (config, _) = negotiate(&supported, default.clone(), Some(48000), None);
+        assert_eq!(config.sample_rate().0, 48000);
+        assert_eq!(config.channels(), 2);
+        assert_eq!(config.sample_format(), cpal::SampleFormat::F32);
+
+        // Only the 16-bit config goes that high; unusable formats are ignored
+        let (config, _) = negotiate(&supported, default.clone(), Some(192000), None);
+        assert_eq!(config.sample_rate().0, 96000);
+        assert_eq!(config.sample_format(), cpal::SampleFormat::I16);
+
+        // Too low for any config: the closest one
+        let (config, _) = negotiate(&supported, default.clone(), Some(4000), None);
+        assert_eq!(config.sample_rate().0, 8000);
+        assert_eq!(config.channels(), 2);
+
+        // Nothing listed: the default
+        let (config, _) = negotiate(&[], default.clone(), Some(48000), None);
+        assert_eq!(config, default);
+    }
+
+    #[test]
+    fn test_negotiate_buffer_size() {
+        let (supported, default) = device();
+
+        let (_, buffer) = negotiate(&supported, default.clone(), None, Some(256));
+        assert_eq!(buffer, cpal::BufferSize::Fixed(256));
+        let (_, buffer) = negotiate(&supported, default.clone(), None, Some(16));
+        assert_eq!(buffer, cpal::BufferSize::Fixed(64));
+        let (_, buffer) = negotiate(&supported, default.clone(), None, Some(1 << 16));
+        assert_eq!(buffer, cpal::BufferSize::Fixed(4096));
+
+        // An unknown range is tried as asked
+        let (config, buffer) = negotiate(&supported, default, Some(96000), Some(16));
+        assert_eq!(config.sample_rate().0, 96000);
+        assert_eq!(buffer, cpal::BufferSize::Fixed(16));
+    }
+
+    #[test]
+    fn test_device_lost() {
+        let mut buffer = SharedBuffer::new(44100);
+        let stall = Duration::from_millis(50);
+        buffer.receive(&[0.0; 64], 1, |s| s);
+        assert!(buffer.lost(stall).is_none());
+
+        // Other stream errors are only counted
+        buffer.stream_error(cpal::StreamError::BackendSpecific {
+            err: cpal::BackendSpecificError {
+                description: "xrun".to_string(),
+            },
+        });
+        assert!(buffer.lost(stall).is_none());
+        assert_eq!(buffer.stats.errors, 1);
+
+        buffer.stream_error(cpal::StreamError::DeviceNotAvailable);
+        assert!(buffer.lost(stall).is_some());
+    }
+
+    #[test]
+    fn test_capture_from_source() {
+        let source = TestAudioSource::sine(440.0, 0.5, 44100);
+        let capture = MicCapture::from_source(source, "test").with_high_pass(Some(20.0));
+        assert_eq!(capture.device_name(), "test");
+        assert_eq!(capture.sample_rate(), 44100);
+
+        // Lost once the whole source has been read
+        let start = Instant::now();
+        while capture.check().is_ok() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(capture.stats().samples, 22050);
+
+        let mut capture = capture;
+        let mut window = vec![0.0; 4096];
+        assert_eq!(capture.read_samples(&mut window), 4096);
+        assert_eq!(capture.read_end(), 22050);
+    }
+
+    #[test]
+    fn test_stalled_stream_is_lost() {
+        let mut buffer = SharedBuffer::new(44100);
+        let stall = Duration::from_millis(50);
+        std::thread::sleep(stall);
+        assert!(buffer.lost(stall).is_some());
+
+        // Not while paused, nor once audio arrives again
+        buffer.paused = true;
+        assert!(buffer.lost(stall).is_none());
+        buffer.paused = false;
+        buffer.receive(&[0.0; 64], 1, |s| s);
+        assert!(buffer.lost(stall).is_none());
+    }
+
+    #[test]
+    fn test_gain_applied_before_clip_count() {
+        let mut buffer = SharedBuffer::new(44100);
+        buffer.gain = Gain::from_db(20.0);
+        buffer.receive(&[0.01, -0.05, 0.2], 1, |s| s);
+        assert!((buffer.samples[0] - 0.1).abs() < 1e-6);
+        assert!((buffer.samples[1] + 0.5).abs() < 1e-6);
+        // Pushed past full scale by the gain
+        assert_eq!(buffer.samples[2], 1.0);
+        assert_eq!(buffer.clipped, 1);
+    }
+
+    #[test]
+    fn test_clipping_on_one_channel() {
+        let mut buffer = SharedBuffer::new(44100);
+        // Full-scale int samples on the left only; the mix is half scale
+        buffer.receive(&[i16::MAX, 0, i16::MIN, 0, 100, 0], 2, |s| {
+            s as f32 / 32768.0
+        });
+        assert_eq!(buffer.clipped, 2);
+        assert!(buffer.samples.iter().all(|s| s.abs() <= 0.5));
     }
 }
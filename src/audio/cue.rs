@@ -0,0 +1,128 @@
+//! Short audible cues, so a note can be tuned without watching the screen.
+
+use std::time::Duration;
+
+use super::traits::AudioSink;
+
+/// An audible cue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cue {
+    /// The reading has been in tolerance and stable for the hold time.
+    Lock,
+    /// A note or string was confirmed.
+    Confirm,
+}
+
+impl Cue {
+    /// Pitch of the blip in Hz, well above the piano's fundamentals.
+    pub fn frequency(self) -> f32 {
+        match self {
+            Cue::Lock => 1760.0,
+            Cue::Confirm => 2637.0,
+        }
+    }
+
+    /// Length of the blip.
+    pub fn duration(self) -> Duration {
+        match self {
+            Cue::Lock => Duration::from_millis(60),
+            Cue::Confirm => Duration::from_millis(90),
+        }
+    }
+}
+
+/// Renders cues as envelope-shaped sine blips.
+#[derive(Debug, Clone, Copy)]
+pub struct CuePlayer {
+    volume: f32,
+}
+
+impl CuePlayer {
+    /// Default peak amplitude; quieter than the reference tone.
+    pub const DEFAULT_VOLUME: f32 = 0.15;
+
+    /// Rise time of the envelope; the rest of the blip decays.
+    pub const ATTACK: Duration = Duration::from_millis(5);
+
+    /// Create a player with the given peak amplitude (0.0 to 1.0).
+    pub fn new(volume: f32) -> Self {
+        Self {
+            volume: volume.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Render a cue as mono samples.
+    pub fn render(&self, cue: Cue, sample_rate: u32) -> Vec<f32> {
+        let sample_rate = sample_rate as f32;
+        let length = (cue.duration().as_secs_f32() * sample_rate) as usize;
+        let attack = (Self::ATTACK.as_secs_f32() * sample_rate).max(1.0);
+        let decay = (length as f32 - attack).max(1.0);
+
+        (0..length)
+            .map(|i| {
+                let i = i as f32;
+                // Linear rise, then a raised-cosine fall to silence
+                let envelope = if i < attack {
+                    i / attack
+                } else {
+                    0.5 + 0.5 * (std::f32::consts::PI * (i - attack) / decay).cos()
+                };
+                let phase = 2.0 * std::f32::consts::PI * cue.frequency() * i / sample_rate;
+                self.volume * envelope * phase.sin()
+            })
+            .collect()
+    }
+
+    /// Play a cue through the given sink, on every channel.
+    pub fn play<S: AudioSink>(&self, sink: &mut S, cue: Cue) {
+        let samples = self.render(cue, sink.sample_rate());
+        let channels = sink.channels() as usize;
+        let interleaved: Vec<f32> = samples
+            .iter()
+            .flat_map(|&s| std::iter::repeat_n(s, channels))
+            .collect();
+        sink.write_samples(&interleaved);
+    }
+}
+
+impl Default for CuePlayer {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_VOLUME)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::traits::TestAudioSink;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    #[test]
+    fn test_blip_shape_and_volume() {
+        let player = CuePlayer::new(0.1);
+        let blip = player.render(Cue::Lock, SAMPLE_RATE);
+        assert_eq!(blip.len(), 2646);
+
+        // Silent at both ends, peaking at the volume
+        let peak = blip.iter().fold(0.0_f32, |m, s| m.max(s.abs()));
+        assert!(peak > 0.09 && peak <= 0.1, "peak {}", peak);
+        assert!(blip[0].abs() < 1e-6);
+        assert!(blip[blip.len() - 1].abs() < 1e-3);
+
+        // The two cues differ in pitch and length
+        assert_ne!(Cue::Lock.frequency(), Cue::Confirm.frequency());
+        assert!(player.render(Cue::Confirm, SAMPLE_RATE).len() > blip.len());
+    }
+
+    #[test]
+    fn test_play_on_every_channel() {
+        let player = CuePlayer::default();
+        let mut sink = TestAudioSink::new(SAMPLE_RATE).with_channels(2);
+        player.play(&mut sink, Cue::Confirm);
+
+        let expected = player.render(Cue::Confirm, SAMPLE_RATE);
+        assert_eq!(sink.channel(0), expected);
+        assert_eq!(sink.channel(1), expected);
+    }
+}
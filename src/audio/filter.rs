@@ -0,0 +1,593 @@
+//! Filters applied to the input before pitch detection.
+
+use std::time::Duration;
+
+use super::traits::AudioSource;
+
+/// A filter run over a stream of samples, keeping its state between
+/// blocks.
+pub trait Filter {
+    /// Filter one sample.
+    fn process(&mut self, x: f32) -> f32;
+
+    /// Filter a block of samples in place, continuing from the last block.
+    fn process_in_place(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Forget the previous samples, e.g. after a gap in the input.
+    fn reset(&mut self);
+}
+
+/// One-pole high-pass filter that removes DC offset and low rumble.
+///
+/// Cheap interfaces often add an offset that skews the level and clarity of
+/// every reading; everything well below the lowest piano note can go.
+#[derive(Debug, Clone, Copy)]
+pub struct HighPass {
+    coefficient: f32,
+    /// Previous input, or `None` before the first sample.
+    x1: Option<f32>,
+    y1: f32,
+}
+
+impl HighPass {
+    /// Default cutoff in Hz, below A0.
+    pub const DEFAULT_CUTOFF: f32 = 20.0;
+
+    /// Create a filter with the -3 dB point at `cutoff` Hz.
+    pub fn new(cutoff: f32, sample_rate: u32) -> Self {
+        let rc = 1.0 / (2.0 * std::f32::consts::PI * cutoff.max(f32::MIN_POSITIVE));
+        let dt = 1.0 / sample_rate as f32;
+        Self {
+            coefficient: rc / (rc + dt),
+            x1: None,
+            y1: 0.0,
+        }
+    }
+}
+
+impl Filter for HighPass {
+    /// The first sample is taken as the offset, so a steady offset doesn't
+    /// ring in.
+    fn process(&mut self, x: f32) -> f32 {
+        let x1 = self.x1.unwrap_or(x);
+        let y = self.coefficient * (self.y1 + x - x1);
+        self.x1 = Some(x);
+        self.y1 = y;
+        y
+    }
+
+    fn reset(&mut self) {
+        self.x1 = None;
+        self.y1 = 0.0;
+    }
+}
+
+/// An audio source whose output is filtered as it is read.
+pub struct Filtered<S: AudioSource, F: Filter> {
+    source: S,
+    filter: F,
+}
+
+/// An audio source whose output is high-passed as it is read.
+pub type HighPassed<S> = Filtered<S, HighPass>;
+
+impl<S: AudioSource> HighPassed<S> {
+    /// Wrap `source`, filtering below `cutoff` Hz.
+    pub fn new(source: S, cutoff: f32) -> Self {
+        let filter = HighPass::new(cutoff, source.sample_rate());
+        Self { source, filter }
+    }
+}
+
+impl<S: AudioSource, F: Filter> Filtered<S, F> {
+    /// Wrap `source`, running its output through `filter`.
+    pub fn with_filter(source: S, filter: F) -> Self {
+        Self { source, filter }
+    }
+
+    /// Get the unfiltered source back.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S: AudioSource, F: Filter> AudioSource for Filtered<S, F> {
+    fn read_samples(&mut self, buffer: &mut [f32]) -> usize {
+        let read = self.source.read_samples(buffer);
+        self.filter.process_in_place(&mut buffer[..read]);
+        read
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.source.is_exhausted()
+    }
+}
+
+/// Fixed gain in decibels, limited to full scale.
+///
+/// Some built-in microphones are too quiet to clear the noise floor and
+/// their mixer can't always be reached from a terminal. Samples pushed past
+/// full scale are clamped, which the clipping warning then reports.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gain {
+    db: f32,
+    factor: f32,
+}
+
+impl Gain {
+    /// Lowest gain in dB.
+    pub const MIN_DB: f32 = -20.0;
+
+    /// Highest gain in dB.
+    pub const MAX_DB: f32 = 40.0;
+
+    /// Create a gain of `db` decibels, kept within [`Self::MIN_DB`] and
+    /// [`Self::MAX_DB`].
+    pub fn from_db(db: f32) -> Self {
+        let db = db.clamp(Self::MIN_DB, Self::MAX_DB);
+        Self {
+            db,
+            factor: 10.0_f32.powf(db / 20.0),
+        }
+    }
+
+    /// Gain in dB.
+    pub fn db(&self) -> f32 {
+        self.db
+    }
+
+    /// Amplify one sample.
+    pub fn process(&self, x: f32) -> f32 {
+        (x * self.factor).clamp(-1.0, 1.0)
+    }
+
+    /// Amplify a block of samples in place.
+    pub fn process_in_place(&self, samples: &mut [f32]) {
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+}
+
+impl Default for Gain {
+    fn default() -> Self {
+        Self::from_db(0.0)
+    }
+}
+
+/// Second-order notch filter that removes one frequency.
+///
+/// Coefficients follow the RBJ audio EQ cookbook. The notch is
+/// `frequency / q` wide at the -3 dB points.
+#[derive(Debug, Clone, Copy)]
+pub struct Notch {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+    /// Samples for the filter to settle after starting from silence.
+    settle_samples: usize,
+}
+
+impl Notch {
+    /// Default quality factor: about 60 cents wide.
+    pub const DEFAULT_Q: f32 = 30.0;
+
+    /// Create a notch at `frequency` Hz.
+    pub fn new(frequency: f32, sample_rate: u32, q: f32) -> Self {
+        let w0 = 2.0 * std::f64::consts::PI * frequency as f64 / sample_rate as f64;
+        let alpha = w0.sin() / (2.0 * q as f64);
+        let a0 = 1.0 + alpha;
+        let cos = w0.cos();
+
+        // The transient decays with a time constant of q / (pi * f0)
+        let tau = q as f64 / (std::f64::consts::PI * frequency.max(1.0) as f64);
+        let settle_samples = (5.0 * tau * sample_rate as f64).ceil() as usize;
+
+        Self {
+            b0: 1.0 / a0,
+            b1: -2.0 * cos / a0,
+            b2: 1.0 / a0,
+            a1: -2.0 * cos / a0,
+            a2: (1.0 - alpha) / a0,
+            settle_samples,
+        }
+    }
+
+    /// Samples the filter needs to settle before its output is clean.
+    pub fn settle_samples(&self) -> usize {
+        self.settle_samples
+    }
+
+    /// Filter a block of samples, starting from silence.
+    pub fn apply(&self, samples: &[f32]) -> Vec<f32> {
+        let mut state = NotchState::default();
+        samples.iter().map(|&x| self.step(x, &mut state)).collect()
+    }
+
+    /// Filter one sample, updating the previous inputs and outputs.
+    fn step(&self, x: f32, state: &mut NotchState) -> f32 {
+        let NotchState { x1, x2, y1, y2 } = *state;
+        let x = x as f64;
+        let y = self.b0 * x + self.b1 * x1 + self.b2 * x2 - self.a1 * y1 - self.a2 * y2;
+        *state = NotchState {
+            x1: x,
+            x2: x1,
+            y1: y,
+            y2: y1,
+        };
+        y as f32
+    }
+
+    /// Filter the last `window` samples of `history`, running the filter
+    /// over enough earlier samples to settle first.
+    pub fn apply_window(&self, history: &[f32], window: usize) -> Vec<f32> {
+        Self::apply_window_chain(std::slice::from_ref(self), history, window)
+    }
+
+    /// Filter the last `window` samples of `history` through each notch in
+    /// turn, running them over enough earlier samples for all to settle.
+    pub fn apply_window_chain(notches: &[Notch], history: &[f32], window: usize) -> Vec<f32> {
+        let window = window.min(history.len());
+        let settle: usize = notches.iter().map(|n| n.settle_samples).sum();
+        let start = history.len().saturating_sub(window + settle);
+        let mut filtered = history[start..].to_vec();
+        for notch in notches {
+            filtered = notch.apply(&filtered);
+        }
+        filtered.split_off(filtered.len() - window)
+    }
+}
+
+/// Previous inputs and outputs of a [`Notch`].
+#[derive(Debug, Clone, Copy, Default)]
+struct NotchState {
+    x1: f64,
+    x2: f64,
+    y1: f64,
+    y2: f64,
+}
+
+/// Notches at the mains frequency and its first harmonics.
+///
+/// Hum from a nearby transformer or a ground loop sits at 50 or 60 Hz,
+/// right among the bass strings, and a loud one can win over a quiet note.
+/// The notches are narrow enough that a note a semitone away still comes
+/// through.
+#[derive(Debug, Clone)]
+pub struct HumFilter {
+    mains: f32,
+    sections: Vec<(Notch, NotchState)>,
+}
+
+impl HumFilter {
+    /// Frequencies notched: the mains frequency and its next two harmonics.
+    pub const HARMONICS: u32 = 3;
+
+    /// Quality factor of each notch: under 2 Hz wide at 50 Hz.
+    pub const Q: f32 = 30.0;
+
+    /// Create a filter for hum at `mains` Hz, usually 50 or 60.
+    pub fn new(mains: f32, sample_rate: u32) -> Self {
+        let nyquist = sample_rate as f32 / 2.0;
+        let sections = (1..=Self::HARMONICS)
+            .map(|n| mains * n as f32)
+            .filter(|&frequency| frequency < nyquist)
+            .map(|frequency| {
+                (
+                    Notch::new(frequency, sample_rate, Self::Q),
+                    NotchState::default(),
+                )
+            })
+            .collect();
+        Self { mains, sections }
+    }
+
+    /// Mains frequency in Hz.
+    pub fn mains(&self) -> f32 {
+        self.mains
+    }
+}
+
+impl Filter for HumFilter {
+    fn process(&mut self, x: f32) -> f32 {
+        self.sections
+            .iter_mut()
+            .fold(x, |x, (notch, state)| notch.step(x, state))
+    }
+
+    fn reset(&mut self) {
+        for (_, state) in &mut self.sections {
+            *state = NotchState::default();
+        }
+    }
+}
+
+/// Subtracts tones of known frequency from the input.
+///
+/// The reference tone reaches the microphone changed in level and phase by
+/// the speakers and the room, but not in frequency. Fitting a sinusoid at
+/// each of its frequencies to a stretch of input recovers the level and
+/// phase, and subtracting it leaves a string even a few cents away, which
+/// a notch would take out with the tone.
+#[derive(Debug, Clone)]
+pub struct ToneCanceller {
+    frequencies: Vec<f32>,
+    sample_rate: u32,
+}
+
+impl ToneCanceller {
+    /// Input the tones are fitted over. A second tells apart a string 20
+    /// cents from a tone in the middle of the piano.
+    pub const FIT_SPAN: Duration = Duration::from_secs(1);
+
+    /// Cancel tones at `frequencies` Hz.
+    pub fn new(frequencies: Vec<f32>, sample_rate: u32) -> Self {
+        Self {
+            frequencies,
+            sample_rate,
+        }
+    }
+
+    /// The last `window` samples of `history` with the tones subtracted,
+    /// fitted over up to [`Self::FIT_SPAN`] of it.
+    pub fn apply_window(&self, history: &[f32], window: usize) -> Vec<f32> {
+        let window = window.min(history.len());
+        let span = (Self::FIT_SPAN.as_secs_f32() * self.sample_rate as f32) as usize;
+        let span = &history[history.len().saturating_sub(span.max(window))..];
+
+        // A Hann weighting keeps a string near a tone out of its fit
+        let weights: Vec<f64> = (0..span.len())
+            .map(|i| {
+                let x = std::f64::consts::PI * (i as f64 + 0.5) / span.len() as f64;
+                x.sin().powi(2)
+            })
+            .collect();
+
+        let mut residual: Vec<f64> = span.iter().map(|&x| x as f64).collect();
+        for &frequency in &self.frequencies {
+            let w = 2.0 * std::f64::consts::PI * frequency as f64 / self.sample_rate as f64;
+            // Weighted least squares for the sine and cosine parts
+            let (mut ss, mut sc, mut cc, mut xs, mut xc) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for (i, (&x, &weight)) in residual.iter().zip(&weights).enumerate() {
+                let (s, c) = (w * i as f64).sin_cos();
+                ss += weight * s * s;
+                sc += weight * s * c;
+                cc += weight * c * c;
+                xs += weight * x * s;
+                xc += weight * x * c;
+            }
+            let det = ss * cc - sc * sc;
+            if det.abs() < f64::EPSILON {
+                continue;
+            }
+            let a = (xs * cc - xc * sc) / det;
+            let b = (xc * ss - xs * sc) / det;
+            for (i, x) in residual.iter_mut().enumerate() {
+                let (s, c) = (w * i as f64).sin_cos();
+                *x -= a * s + b * c;
+            }
+        }
+        residual[residual.len() - window..]
+            .iter()
+            .map(|&x| x as f32)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::beats::rms;
+    use crate::audio::pitch::PitchDetector;
+    use crate::audio::traits::TestAudioSource;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// Read a whole source through the high-pass filter.
+    fn high_passed(samples: Vec<f32>) -> Vec<f32> {
+        let len = samples.len();
+        let mut source = HighPassed::new(
+            TestAudioSource::new(samples, SAMPLE_RATE),
+            HighPass::DEFAULT_CUTOFF,
+        );
+        let mut out = vec![0.0; len];
+        // Odd chunks to check the filter carries over between reads
+        let mut read = 0;
+        while read < len {
+            let end = (read + 1000).min(len);
+            read += source.read_samples(&mut out[read..end]);
+        }
+        out
+    }
+
+    #[test]
+    fn test_high_pass_removes_dc_offset() {
+        let clean = TestAudioSource::sine(440.0, 0.5, SAMPLE_RATE);
+        let biased: Vec<f32> = clean.samples().iter().map(|s| 0.5 * s + 0.3).collect();
+        let filtered = high_passed(biased);
+
+        let window = &filtered[filtered.len() - 4096..];
+        let mean = window.iter().sum::<f32>() / window.len() as f32;
+        assert!(mean.abs() < 0.01, "mean {}", mean);
+
+        let detector = PitchDetector::new(SAMPLE_RATE);
+        let expected = detector.detect(&clean.samples()[..4096]).unwrap();
+        let result = detector.detect(window).expect("Should detect pitch");
+        assert!(
+            (result.frequency - 440.0).abs() < 0.5,
+            "{}",
+            result.frequency
+        );
+        assert!(
+            (result.confidence - expected.confidence).abs() < 0.01,
+            "confidence {} vs {}",
+            result.confidence,
+            expected.confidence
+        );
+        assert!(
+            (result.clarity - expected.clarity).abs() < 0.01,
+            "clarity {} vs {}",
+            result.clarity,
+            expected.clarity
+        );
+    }
+
+    #[test]
+    fn test_high_pass_removes_rumble() {
+        let rumble = TestAudioSource::sine(5.0, 1.0, SAMPLE_RATE);
+        let filtered = high_passed(rumble.samples().to_vec());
+        let ratio = rms(&filtered[SAMPLE_RATE as usize / 2..]) / rms(rumble.samples());
+        assert!(ratio < 0.3, "ratio {}", ratio);
+
+        // A0 passes nearly untouched
+        let a0 = TestAudioSource::sine(27.5, 1.0, SAMPLE_RATE);
+        let filtered = high_passed(a0.samples().to_vec());
+        let ratio = rms(&filtered[SAMPLE_RATE as usize / 2..]) / rms(a0.samples());
+        assert!(ratio > 0.75, "ratio {}", ratio);
+    }
+
+    #[test]
+    fn test_gain() {
+        let mut samples = vec![0.001, -0.01, 0.05, 0.5];
+        Gain::from_db(20.0).process_in_place(&mut samples);
+        for (gained, expected) in samples.iter().zip([0.01, -0.1, 0.5, 1.0]) {
+            assert!(
+                (gained - expected).abs() < 1e-6,
+                "{} vs {}",
+                gained,
+                expected
+            );
+        }
+
+        assert_eq!(Gain::default().process(0.25), 0.25);
+        assert_eq!(Gain::from_db(100.0).db(), Gain::MAX_DB);
+        assert_eq!(Gain::from_db(-100.0).db(), Gain::MIN_DB);
+    }
+
+    #[test]
+    fn test_notch_removes_frequency() {
+        let source = TestAudioSource::sine(440.0, 1.0, SAMPLE_RATE);
+        let notch = Notch::new(440.0, SAMPLE_RATE, Notch::DEFAULT_Q);
+        let out = notch.apply_window(source.samples(), 4410);
+        assert!(rms(&out) < 0.01, "rms {}", rms(&out));
+    }
+
+    #[test]
+    fn test_notch_passes_other_frequencies() {
+        let source = TestAudioSource::sine(523.25, 1.0, SAMPLE_RATE);
+        let notch = Notch::new(440.0, SAMPLE_RATE, Notch::DEFAULT_Q);
+        let out = notch.apply_window(source.samples(), 4410);
+        let ratio = rms(&out) / rms(&source.samples()[..4410]);
+        assert!(ratio > 0.95, "ratio {}", ratio);
+    }
+
+    /// Samples through a hum filter, after the first second for it to
+    /// settle.
+    fn hum_filtered(samples: &[f32], mains: f32) -> Vec<f32> {
+        let mut filter = HumFilter::new(mains, SAMPLE_RATE);
+        let mut out = samples.to_vec();
+        for chunk in out.chunks_mut(512) {
+            filter.process_in_place(chunk);
+        }
+        out.split_off(SAMPLE_RATE as usize)
+    }
+
+    #[test]
+    fn test_hum_filter_removes_hum() {
+        let a4 = TestAudioSource::sine(440.0, 2.0, SAMPLE_RATE);
+        let hum = |i: usize, mains: f32| {
+            let phase = 2.0 * std::f32::consts::PI * mains * i as f32 / SAMPLE_RATE as f32;
+            0.6 * phase.sin() + 0.4 * (2.0 * phase).sin() + 0.3 * (3.0 * phase).sin()
+        };
+        let detector = PitchDetector::new(SAMPLE_RATE);
+        for mains in [50.0, 60.0] {
+            let noisy: Vec<f32> = a4
+                .samples()
+                .iter()
+                .enumerate()
+                .map(|(i, s)| 0.1 * s + hum(i, mains))
+                .collect();
+            let window = 4096;
+            let unfiltered = detector.detect(&noisy[noisy.len() - window..]);
+            assert!(unfiltered.is_none_or(|r| (r.frequency - 440.0).abs() > 1.0));
+
+            let filtered = hum_filtered(&noisy, mains);
+            let result = detector
+                .detect(&filtered[filtered.len() - window..])
+                .expect("Should detect pitch");
+            assert!(
+                (result.frequency - 440.0).abs() < 0.5,
+                "{} Hz mains: {}",
+                mains,
+                result.frequency
+            );
+        }
+    }
+
+    #[test]
+    fn test_hum_filter_keeps_nearby_note() {
+        // G1 is a single hertz below 50 Hz mains
+        let g1 = TestAudioSource::sine(49.0, 2.0, SAMPLE_RATE);
+        let filtered = hum_filtered(g1.samples(), 50.0);
+        let ratio = rms(&filtered) / rms(&g1.samples()[SAMPLE_RATE as usize..]);
+        assert!(ratio > 0.6, "ratio {}", ratio);
+
+        let detector = PitchDetector::new(SAMPLE_RATE);
+        let window = detector.window_for_target(49.0);
+        let result = detector
+            .detect_near(&filtered[filtered.len() - window..], 49.0)
+            .expect("Should detect G1");
+        assert!(
+            (result.frequency - 49.0).abs() < 0.1,
+            "{}",
+            result.frequency
+        );
+    }
+
+    #[test]
+    fn test_short_history() {
+        let notch = Notch::new(440.0, SAMPLE_RATE, Notch::DEFAULT_Q);
+        assert_eq!(notch.apply_window(&[0.5; 100], 4410).len(), 100);
+    }
+
+    #[test]
+    fn test_canceller_keeps_nearby_string() {
+        // Speaker bleed of A4, shifted in phase by the room, over a string
+        // 20 cents sharp
+        let string = 440.0 * 2.0_f32.powf(20.0 / 1200.0);
+        let history: Vec<f32> = (0..SAMPLE_RATE as usize * 6 / 5)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                let phase = 2.0 * std::f32::consts::PI * t;
+                0.5 * (phase * 440.0 + 1.1).sin() + 0.2 * (phase * string).sin()
+            })
+            .collect();
+        let window = SAMPLE_RATE as usize / 10;
+        let detector = PitchDetector::new(SAMPLE_RATE);
+
+        // Left alone, the bleed wins
+        let heard = detector.detect(&history[history.len() - window..]).unwrap();
+        assert!((heard.frequency - 440.0).abs() < 2.0, "{}", heard.frequency);
+
+        let canceller = ToneCanceller::new(vec![440.0], SAMPLE_RATE);
+        let cleaned = canceller.apply_window(&history, window);
+        assert_eq!(cleaned.len(), window);
+        assert!((rms(&cleaned) - 0.2 / 2.0_f32.sqrt()).abs() < 0.01);
+        let result = detector.detect(&cleaned).unwrap();
+        let cents = 1200.0 * (result.frequency / string).log2();
+        assert!(cents.abs() < 1.0, "{} cents off the string", cents);
+
+        // Too little history to fit over still comes back whole
+        assert_eq!(canceller.apply_window(&history[..100], window).len(), 100);
+    }
+}
@@ -0,0 +1,146 @@
+//! Hysteresis between having a pitch and listening.
+//!
+//! Single frames dip below the confidence threshold all the time, even on a
+//! ringing note. Requiring several confident frames to acquire a pitch and
+//! several missed ones to release it stops the meter strobing.
+
+/// Whether a pitch is held, given per-frame confidence.
+#[derive(Debug, Clone)]
+pub struct PitchLock {
+    /// Consecutive confident frames needed to acquire a pitch.
+    acquire: usize,
+    /// Consecutive missed frames needed to release it.
+    release: usize,
+    min_confidence: f32,
+    /// Consecutive frames agreeing with a change of state.
+    streak: usize,
+    locked: bool,
+}
+
+impl PitchLock {
+    /// Default confident frames needed to acquire a pitch.
+    pub const DEFAULT_ACQUIRE: usize = 2;
+
+    /// Default missed frames needed to release a pitch.
+    pub const DEFAULT_RELEASE: usize = 3;
+
+    /// Default confidence below which a frame counts as missed.
+    pub const DEFAULT_MIN_CONFIDENCE: f32 = 0.5;
+
+    /// Create a lock acquired after `acquire` confident frames and released
+    /// after `release` missed ones. 1 for both passes frames through.
+    pub fn new(acquire: usize, release: usize) -> Self {
+        Self {
+            acquire: acquire.max(1),
+            release: release.max(1),
+            min_confidence: Self::DEFAULT_MIN_CONFIDENCE,
+            streak: 0,
+            locked: false,
+        }
+    }
+
+    /// Set the confidence below which a frame counts as missed.
+    pub fn with_min_confidence(mut self, confidence: f32) -> Self {
+        self.min_confidence = confidence;
+        self
+    }
+
+    /// Change the confidence below which a frame counts as missed.
+    pub fn set_min_confidence(&mut self, confidence: f32) {
+        self.min_confidence = confidence;
+    }
+
+    /// Add a frame's confidence, or `None` if it had no pitch, and get
+    /// whether a pitch is held.
+    pub fn update(&mut self, confidence: Option<f32>) -> bool {
+        let confident = confidence.is_some_and(|c| self.accepts(c));
+        if confident == self.locked {
+            self.streak = 0;
+            return self.locked;
+        }
+
+        self.streak += 1;
+        let needed = if self.locked {
+            self.release
+        } else {
+            self.acquire
+        };
+        if self.streak >= needed {
+            self.locked = !self.locked;
+            self.streak = 0;
+        }
+        self.locked
+    }
+
+    /// Whether a frame of this confidence counts as confident.
+    pub fn accepts(&self, confidence: f32) -> bool {
+        confidence >= self.min_confidence
+    }
+
+    /// Whether a pitch is held.
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Release the pitch, e.g. when moving to another note.
+    pub fn reset(&mut self) {
+        self.locked = false;
+        self.streak = 0;
+    }
+}
+
+impl Default for PitchLock {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_ACQUIRE, Self::DEFAULT_RELEASE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drive the lock with a sequence of confidences; 0 is no pitch.
+    fn run(lock: &mut PitchLock, frames: &[f32]) -> Vec<bool> {
+        frames
+            .iter()
+            .map(|&c| lock.update((c > 0.0).then_some(c)))
+            .collect()
+    }
+
+    #[test]
+    fn test_acquire_and_release() {
+        let mut lock = PitchLock::default();
+        let held = run(
+            &mut lock,
+            &[0.9, 0.0, 0.9, 0.9, 0.9, 0.0, 0.0, 0.9, 0.0, 0.0, 0.0, 0.9],
+        );
+        assert_eq!(
+            held,
+            [
+                false, false, false, true, true, // Two in a row to acquire
+                true, true, true, // A brief dropout is bridged
+                true, true, false, // Three in a row to release
+                false,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_low_confidence_counts_as_missed() {
+        let mut lock = PitchLock::new(1, 2).with_min_confidence(0.6);
+        assert_eq!(
+            run(&mut lock, &[0.5, 0.7, 0.3, 0.55]),
+            [false, true, true, false]
+        );
+    }
+
+    #[test]
+    fn test_pass_through_and_reset() {
+        let mut lock = PitchLock::new(0, 0);
+        assert_eq!(run(&mut lock, &[0.9, 0.0, 0.9]), [true, false, true]);
+
+        lock.reset();
+        assert!(!lock.is_locked());
+        assert!(lock.update(Some(0.9)));
+    }
+}
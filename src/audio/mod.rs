@@ -1,11 +1,43 @@
 //! Audio capture, pitch detection, and reference tone generation.
 
+pub mod analysis;
+pub mod beats;
 pub mod capture;
+pub mod cue;
+pub mod filter;
+pub mod lock;
+pub mod noise;
+pub mod partials;
 pub mod pitch;
+pub mod recorder;
 pub mod reference;
+pub mod smoother;
+pub mod tracker;
 pub mod traits;
+pub mod windowed;
 
-pub use capture::{AudioOutput, CaptureError, MicCapture};
-pub use pitch::{PitchDetector, PitchResult};
-pub use reference::ReferenceTone;
-pub use traits::{AudioSink, AudioSource, TestAudioSink, TestAudioSource, WavAudioSource};
+pub use analysis::{analyze, Analysis};
+pub use beats::{BeatDetector, FalseBeatDetector};
+pub use capture::{
+    AudioDevice, AudioOutput, CaptureError, CaptureStats, MicCapture, MicCaptureBuilder,
+};
+pub use cue::{Cue, CuePlayer};
+pub use filter::{Filter, Filtered, Gain, HighPass, HighPassed, HumFilter, Notch, ToneCanceller};
+pub use lock::PitchLock;
+pub use noise::{NoiseFloor, NoiseSettings};
+pub use partials::{Partial, PartialAnalyzer, PartialResult, PartialTracker};
+pub use pitch::{
+    MpmDetector, PitchAlgorithm, PitchDetector, PitchMethod, PitchResult, WindowFunction,
+    YinScratch,
+};
+pub use recorder::{RecordTap, Recorder};
+pub use reference::{
+    MonoTone, PlayingTone, ReferenceChannels, ReferenceFeedback, ReferenceTone, SplitTone, Waveform,
+};
+pub use smoother::PitchSmoother;
+pub use tracker::{PitchFrame, PitchTracker};
+pub use traits::{
+    AudioSink, AudioSource, PcmFormat, StdinAudioSource, TestAudioSink, TestAudioSource,
+    WavAudioSink, WavAudioSource,
+};
+pub use windowed::Windowed;
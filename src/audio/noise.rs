@@ -0,0 +1,196 @@
+//! Room noise measurement.
+//!
+//! The level that counts as sound and the confidence a reading needs both
+//! depend on the room. A couple of seconds of input while nothing is played
+//! gives the noise floor, and how flat its spectrum is: broadband hiss
+//! rarely looks like a pitch, but hum from a fridge or a fan does.
+
+use std::time::Duration;
+
+use rustfft::{num_complex::Complex, FftPlanner};
+
+/// Level and character of the background noise.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseFloor {
+    /// RMS level of the noise.
+    pub rms: f32,
+    /// Spectral flatness from 0 (a pure tone) to 1 (white noise).
+    pub flatness: f32,
+}
+
+/// Detection settings suited to a noise floor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseSettings {
+    /// Input level (RMS) above which audio counts as activity.
+    pub noise_gate: f32,
+    /// Confidence below which a frame counts as no pitch.
+    pub min_confidence: f32,
+}
+
+/// FFT size of the frames the spectrum is averaged over.
+const FRAME: usize = 2048;
+
+/// Frequencies the flatness is taken over, in Hz; the range of a piano's
+/// fundamentals and strongest partials.
+const BAND: (f32, f32) = (50.0, 5000.0);
+
+impl NoiseFloor {
+    /// Input collected for a measurement.
+    pub const MEASURE_TIME: Duration = Duration::from_secs(2);
+
+    /// Ratio of the suggested gate to the noise floor (12 dB).
+    pub const GATE_MARGIN: f32 = 4.0;
+
+    /// Lowest suggested gate, for a silent room or a muted input.
+    pub const MIN_GATE: f32 = 0.002;
+
+    /// Highest suggested gate; above it a piano would barely register.
+    pub const MAX_GATE: f32 = 0.1;
+
+    /// Suggested confidence for broadband noise.
+    pub const MIN_CONFIDENCE: f32 = 0.5;
+
+    /// Suggested confidence for noise that is a pure tone.
+    pub const MAX_CONFIDENCE: f32 = 0.8;
+
+    /// Measure the noise in `samples`, recorded while nothing was played.
+    pub fn measure(samples: &[f32], sample_rate: u32) -> Self {
+        let rms = if samples.is_empty() {
+            0.0
+        } else {
+            (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+        };
+        Self {
+            rms,
+            flatness: flatness(samples, sample_rate),
+        }
+    }
+
+    /// Noise level in dBFS.
+    pub fn db(&self) -> f32 {
+        20.0 * self.rms.max(1e-6).log10()
+    }
+
+    /// Settings for this room: a gate a margin above the noise, and a
+    /// higher confidence the more tonal the noise is.
+    pub fn suggest(&self) -> NoiseSettings {
+        let tonal = 1.0 - self.flatness.clamp(0.0, 1.0);
+        NoiseSettings {
+            noise_gate: (self.rms * Self::GATE_MARGIN).clamp(Self::MIN_GATE, Self::MAX_GATE),
+            min_confidence: Self::MIN_CONFIDENCE
+                + (Self::MAX_CONFIDENCE - Self::MIN_CONFIDENCE) * tonal,
+        }
+    }
+}
+
+/// Spectral flatness of the power spectrum averaged over Hann-windowed
+/// frames: the geometric over the arithmetic mean of the bins in [`BAND`].
+/// Silence counts as flat.
+fn flatness(samples: &[f32], sample_rate: u32) -> f32 {
+    if samples.len() < FRAME {
+        return 1.0;
+    }
+    let hop = FRAME / 2;
+    let frames = (samples.len() - FRAME) / hop + 1;
+
+    let fft = FftPlanner::new().plan_fft_forward(FRAME);
+    let mut power = vec![0.0f64; FRAME / 2];
+    let mut buffer = vec![Complex::new(0.0, 0.0); FRAME];
+    for frame in 0..frames {
+        let start = frame * hop;
+        for (i, value) in buffer.iter_mut().enumerate() {
+            let phase = 2.0 * std::f32::consts::PI * i as f32 / FRAME as f32;
+            let w = 0.5 - 0.5 * phase.cos();
+            *value = Complex::new(samples[start + i] * w, 0.0);
+        }
+        fft.process(&mut buffer);
+        for (p, c) in power.iter_mut().zip(&buffer) {
+            *p += c.norm_sqr() as f64;
+        }
+    }
+
+    let hz_per_bin = sample_rate as f32 / FRAME as f32;
+    let lo = ((BAND.0 / hz_per_bin).ceil() as usize).max(1);
+    let hi = ((BAND.1 / hz_per_bin).floor() as usize).min(power.len() - 1);
+    let band = &power[lo..=hi];
+    let mean = band.iter().sum::<f64>() / band.len() as f64;
+    if mean <= 0.0 {
+        return 1.0;
+    }
+    let log_mean = band.iter().map(|p| p.max(1e-30).ln()).sum::<f64>() / band.len() as f64;
+    (log_mean.exp() / mean) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// Two seconds of uniform white noise at the given RMS level.
+    fn white_noise(rms: f32) -> Vec<f32> {
+        let mut state = 0x2545_f491_u32;
+        let n = SAMPLE_RATE as usize * 2;
+        (0..n)
+            .map(|_| {
+                // xorshift32
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let uniform = state as f32 / u32::MAX as f32 * 2.0 - 1.0;
+                uniform * rms * 3.0_f32.sqrt()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_white_noise_floor() {
+        let quiet = NoiseFloor::measure(&white_noise(0.001), SAMPLE_RATE);
+        assert!((quiet.rms - 0.001).abs() < 1e-4, "RMS {}", quiet.rms);
+        assert!((quiet.db() + 60.0).abs() < 1.0);
+        assert!(quiet.flatness > 0.9, "Flatness {}", quiet.flatness);
+
+        let suggested = quiet.suggest();
+        assert!((suggested.noise_gate - 0.004).abs() < 5e-4);
+        assert!(suggested.min_confidence < 0.55);
+
+        // A louder room needs a higher gate, within the limits
+        let loud = NoiseFloor::measure(&white_noise(0.01), SAMPLE_RATE).suggest();
+        assert!((loud.noise_gate - 0.04).abs() < 5e-3);
+        let deafening = NoiseFloor::measure(&white_noise(0.2), SAMPLE_RATE).suggest();
+        assert_eq!(deafening.noise_gate, NoiseFloor::MAX_GATE);
+    }
+
+    #[test]
+    fn test_hum_raises_confidence() {
+        // Mains hum and its harmonics over faint hiss
+        let hum: Vec<f32> = white_noise(0.0005)
+            .into_iter()
+            .enumerate()
+            .map(|(i, hiss)| {
+                let phase = 2.0 * std::f32::consts::PI * 50.0 * i as f32 / SAMPLE_RATE as f32;
+                hiss + 0.004 * (phase.sin() + 0.5 * (3.0 * phase).sin())
+            })
+            .collect();
+        let floor = NoiseFloor::measure(&hum, SAMPLE_RATE);
+        assert!(floor.flatness < 0.2, "Flatness {}", floor.flatness);
+        let suggested = floor.suggest();
+        assert!(suggested.min_confidence > 0.7);
+        assert!(suggested.min_confidence <= NoiseFloor::MAX_CONFIDENCE);
+    }
+
+    #[test]
+    fn test_silence() {
+        let floor = NoiseFloor::measure(&[0.0; 44100], SAMPLE_RATE);
+        assert_eq!(floor.rms, 0.0);
+        assert_eq!(floor.flatness, 1.0);
+        assert_eq!(
+            floor.suggest(),
+            NoiseSettings {
+                noise_gate: NoiseFloor::MIN_GATE,
+                min_confidence: NoiseFloor::MIN_CONFIDENCE,
+            }
+        );
+        assert_eq!(NoiseFloor::measure(&[], SAMPLE_RATE).rms, 0.0);
+    }
+}
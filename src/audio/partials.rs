@@ -0,0 +1,472 @@
+//! Partial tracking for notes whose fundamental is too weak to detect.
+//!
+//! The lowest strings of small pianos radiate little energy at the
+//! fundamental, so tuners listen to the partials instead. Each partial is
+//! found by scanning the spectrum around its expected frequency, and the
+//! fundamental is inferred from the strongest one.
+//!
+//! For inharmonicity and stretch work, the analyzer instead locates the
+//! first several partials of a note in an FFT spectrum.
+
+use std::ops::RangeInclusive;
+
+use rustfft::{num_complex::Complex, FftPlanner};
+
+/// Frequency of partial `n` relative to the fundamental, for a string with
+/// inharmonicity coefficient `b`.
+pub fn partial_ratio(n: u32, b: f32) -> f32 {
+    let n = n as f32;
+    n * (1.0 + b * n * n).sqrt()
+}
+
+/// One partial found in the spectrum.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Partial {
+    /// Partial number (1 = fundamental).
+    pub number: u32,
+    /// Measured frequency in Hz.
+    pub frequency: f32,
+    /// Estimated sine amplitude.
+    pub amplitude: f32,
+}
+
+/// Fundamental inferred from a partial.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PartialResult {
+    /// Inferred fundamental frequency in Hz.
+    pub frequency: f32,
+    /// Share of the signal energy in the tracked partials (0.0 to 1.0).
+    pub confidence: f32,
+    /// Partial the fundamental was inferred from.
+    pub partial: u32,
+}
+
+/// Finds partials near their expected frequencies.
+pub struct PartialTracker {
+    sample_rate: u32,
+    /// Inharmonicity coefficient of the string, if estimated.
+    inharmonicity: f32,
+}
+
+impl PartialTracker {
+    /// Partials considered when inferring the fundamental.
+    pub const PARTIALS: RangeInclusive<u32> = 2..=4;
+
+    /// How far from its expected frequency a partial is searched for.
+    pub const SEARCH_CENTS: f32 = 100.0;
+
+    /// Analysis window in seconds; long enough to resolve bass partials.
+    pub const WINDOW_SECONDS: f32 = 0.4;
+
+    /// Step of the coarse scan in cents.
+    const SCAN_STEP: f32 = 5.0;
+
+    /// Step at which refinement stops, in cents.
+    const REFINE_STEP: f32 = 0.01;
+
+    /// Create a tracker assuming harmonic partials.
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            inharmonicity: 0.0,
+        }
+    }
+
+    /// Set the inharmonicity coefficient used to place and divide partials.
+    pub fn with_inharmonicity(mut self, b: f32) -> Self {
+        self.inharmonicity = b;
+        self
+    }
+
+    /// Number of samples to analyze.
+    pub fn window_size(&self) -> usize {
+        (self.sample_rate as f32 * Self::WINDOW_SECONDS) as usize
+    }
+
+    /// Infer the fundamental near `target` from the strongest low partial.
+    pub fn detect(&self, samples: &[f32], target: f32) -> Option<PartialResult> {
+        let energy = samples.iter().map(|s| s * s).sum::<f32>() / samples.len().max(1) as f32;
+        if energy <= f32::EPSILON || target <= 0.0 {
+            return None;
+        }
+
+        let spectrum = Spectrum::new(samples, self.sample_rate);
+        let partials: Vec<Partial> = Self::PARTIALS
+            .filter_map(|n| self.find(&spectrum, target, n))
+            .collect();
+
+        let strongest = partials
+            .iter()
+            .max_by(|a, b| a.amplitude.total_cmp(&b.amplitude))?;
+        let tracked = partials
+            .iter()
+            .map(|p| p.amplitude * p.amplitude / 2.0)
+            .sum::<f32>();
+
+        Some(PartialResult {
+            frequency: strongest.frequency / partial_ratio(strongest.number, self.inharmonicity),
+            confidence: (tracked / energy).min(1.0),
+            partial: strongest.number,
+        })
+    }
+
+    /// Find partial `n` of a note whose fundamental is near `fundamental`.
+    fn find(&self, spectrum: &Spectrum, fundamental: f32, n: u32) -> Option<Partial> {
+        let expected = fundamental * partial_ratio(n, self.inharmonicity);
+        if expected >= self.sample_rate as f32 / 2.0 {
+            return None;
+        }
+        let at = |cents: f32| spectrum.magnitude(expected * 2.0_f32.powf(cents / 1200.0));
+
+        // Coarse scan; a peak at the edge lies outside the search range
+        let steps = (Self::SEARCH_CENTS / Self::SCAN_STEP) as i32;
+        let (best, _) = (-steps..=steps)
+            .map(|i| (i, at(i as f32 * Self::SCAN_STEP)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+        if best.abs() == steps {
+            return None;
+        }
+
+        // Hill-climb with a shrinking step
+        let mut cents = best as f32 * Self::SCAN_STEP;
+        let mut magnitude = at(cents);
+        let mut step = Self::SCAN_STEP / 2.0;
+        while step > Self::REFINE_STEP {
+            let (below, above) = (at(cents - step), at(cents + step));
+            if above > magnitude && above >= below {
+                (cents, magnitude) = (cents + step, above);
+            } else if below > magnitude {
+                (cents, magnitude) = (cents - step, below);
+            } else {
+                step /= 2.0;
+            }
+        }
+
+        Some(Partial {
+            number: n,
+            frequency: expected * 2.0_f32.powf(cents / 1200.0),
+            amplitude: magnitude,
+        })
+    }
+}
+
+/// Locates the partials of a note in an FFT magnitude spectrum.
+pub struct PartialAnalyzer {
+    sample_rate: u32,
+    /// Highest partial number looked for.
+    partials: u32,
+    /// How far from n × f0 a partial is searched for, in cents.
+    search_cents: f32,
+}
+
+impl PartialAnalyzer {
+    /// Partials looked for unless set otherwise.
+    pub const DEFAULT_PARTIALS: u32 = 8;
+
+    /// Default search range either side of n × f0; wide enough for the
+    /// stretch of bass strings.
+    pub const DEFAULT_SEARCH_CENTS: f32 = 60.0;
+
+    /// Magnitude relative to the strongest partial below which a peak is
+    /// taken as noise.
+    const NOISE_RATIO: f32 = 1e-3;
+
+    /// Create an analyzer for the first eight partials.
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            partials: Self::DEFAULT_PARTIALS,
+            search_cents: Self::DEFAULT_SEARCH_CENTS,
+        }
+    }
+
+    /// Set the highest partial number looked for.
+    pub fn with_partials(mut self, partials: u32) -> Self {
+        self.partials = partials;
+        self
+    }
+
+    /// Set how far from n × f0 a partial is searched for, in cents.
+    pub fn with_search_cents(mut self, cents: f32) -> Self {
+        self.search_cents = cents;
+        self
+    }
+
+    /// Find the partials of a note near `fundamental_hint`, in order. A
+    /// partial missing from the spectrum is left out.
+    pub fn analyze(&self, samples: &[f32], fundamental_hint: f32) -> Vec<Partial> {
+        if samples.len() < 2 || fundamental_hint <= 0.0 {
+            return Vec::new();
+        }
+
+        // Hann window, zero-padded to twice the next power of two
+        let size = (samples.len() * 2).next_power_of_two();
+        let n = samples.len();
+        let mut window_sum = 0.0;
+        let mut buffer: Vec<Complex<f32>> = (0..size)
+            .map(|i| {
+                let s = samples.get(i).map_or(0.0, |&s| {
+                    let phase = 2.0 * std::f32::consts::PI * i as f32 / n as f32;
+                    let w = 0.5 - 0.5 * phase.cos();
+                    window_sum += w;
+                    s * w
+                });
+                Complex::new(s, 0.0)
+            })
+            .collect();
+        FftPlanner::new()
+            .plan_fft_forward(size)
+            .process(&mut buffer);
+        let magnitude: Vec<f32> = buffer[..size / 2].iter().map(|c| c.norm()).collect();
+        let hz_per_bin = self.sample_rate as f32 / size as f32;
+
+        let partials: Vec<Partial> = (1..=self.partials)
+            .filter_map(|number| {
+                let expected = fundamental_hint * number as f32;
+                let ratio = 2.0_f32.powf(self.search_cents / 1200.0);
+                let lo = ((expected / ratio / hz_per_bin).floor() as usize).max(1);
+                let hi = ((expected * ratio / hz_per_bin).ceil() as usize).min(magnitude.len() - 2);
+                if lo >= hi {
+                    return None;
+                }
+
+                // A maximum at the edge of the range belongs to something else
+                let peak = (lo..=hi).max_by(|&a, &b| magnitude[a].total_cmp(&magnitude[b]))?;
+                if peak == lo || peak == hi {
+                    return None;
+                }
+
+                // Parabola through the log magnitudes, close to exact for a
+                // Hann-windowed sine
+                let [a, b, c] =
+                    [peak - 1, peak, peak + 1].map(|i| magnitude[i].max(f32::MIN_POSITIVE).ln());
+                let denominator = a - 2.0 * b + c;
+                let delta = if denominator.abs() < 1e-10 {
+                    0.0
+                } else {
+                    (0.5 * (a - c) / denominator).clamp(-0.5, 0.5)
+                };
+                let peak_magnitude = (b - 0.25 * (a - c) * delta).exp();
+
+                Some(Partial {
+                    number,
+                    frequency: (peak as f32 + delta) * hz_per_bin,
+                    amplitude: 2.0 * peak_magnitude / window_sum,
+                })
+            })
+            .collect();
+
+        let strongest = partials.iter().map(|p| p.amplitude).fold(0.0, f32::max);
+        partials
+            .into_iter()
+            .filter(|p| strongest > 0.0 && p.amplitude >= strongest * Self::NOISE_RATIO)
+            .collect()
+    }
+}
+
+/// Hann-windowed samples, evaluated at arbitrary frequencies.
+struct Spectrum {
+    windowed: Vec<f64>,
+    /// Sum of the window, for amplitude scaling.
+    window_sum: f64,
+    sample_rate: f64,
+}
+
+impl Spectrum {
+    fn new(samples: &[f32], sample_rate: u32) -> Self {
+        let n = samples.len();
+        let mut window_sum = 0.0;
+        let windowed = samples
+            .iter()
+            .enumerate()
+            .map(|(i, &s)| {
+                let phase = 2.0 * std::f64::consts::PI * i as f64 / n.max(2) as f64;
+                let w = 0.5 - 0.5 * phase.cos();
+                window_sum += w;
+                s as f64 * w
+            })
+            .collect();
+        Self {
+            windowed,
+            window_sum,
+            sample_rate: sample_rate as f64,
+        }
+    }
+
+    /// Amplitude of a sine at `frequency`.
+    fn magnitude(&self, frequency: f32) -> f32 {
+        let w = 2.0 * std::f64::consts::PI * frequency as f64 / self.sample_rate;
+        let (step_re, step_im) = (w.cos(), -w.sin());
+        let (mut re, mut im) = (0.0, 0.0);
+        let (mut rot_re, mut rot_im) = (1.0, 0.0);
+        for &s in &self.windowed {
+            re += s * rot_re;
+            im += s * rot_im;
+            (rot_re, rot_im) = (
+                rot_re * step_re - rot_im * step_im,
+                rot_re * step_im + rot_im * step_re,
+            );
+        }
+        (2.0 * (re * re + im * im).sqrt() / self.window_sum) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::pitch::PitchDetector;
+    use crate::audio::traits::TestAudioSource;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// A0 at `cents` off pitch with a weak fundamental, partials stretched by `b`.
+    fn weak_a0(cents: f32, b: f32) -> Vec<f32> {
+        let f0 = 27.5 * 2.0_f64.powf(cents as f64 / 1200.0);
+        // Fundamental 20 dB below the 2nd partial
+        let partials = [(1, 0.1), (2, 1.0), (3, 0.5), (4, 0.3)];
+        (0..SAMPLE_RATE as usize)
+            .map(|i| {
+                let t = i as f64 / SAMPLE_RATE as f64;
+                partials
+                    .iter()
+                    .map(|&(n, amplitude)| {
+                        let f = f0 * partial_ratio(n, b) as f64;
+                        amplitude * (2.0 * std::f64::consts::PI * f * t).sin()
+                    })
+                    .sum::<f64>() as f32
+                    * 0.5
+            })
+            .collect()
+    }
+
+    fn cents(freq: f32, target: f32) -> f32 {
+        1200.0 * (freq / target).log2()
+    }
+
+    #[test]
+    fn test_weak_fundamental_inferred_from_second_partial() {
+        let tracker = PartialTracker::new(SAMPLE_RATE);
+        for offset in [-30.0, 0.0, 12.0] {
+            let samples = weak_a0(offset, 0.0);
+            let window = &samples[samples.len() - tracker.window_size()..];
+            let result = tracker.detect(window, 27.5).unwrap();
+
+            assert_eq!(result.partial, 2);
+            let measured = cents(result.frequency, 27.5);
+            assert!(
+                (measured - offset).abs() < 1.0,
+                "expected {} got {}",
+                offset,
+                measured
+            );
+            assert!(result.confidence > 0.9, "confidence {}", result.confidence);
+        }
+    }
+
+    #[test]
+    fn test_inharmonicity_accounted_for() {
+        let b = 0.0004;
+        let samples = weak_a0(5.0, b);
+        let tracker = PartialTracker::new(SAMPLE_RATE);
+        let window = &samples[samples.len() - tracker.window_size()..];
+
+        // Dividing a stretched partial by its number reads sharp
+        let naive = tracker.detect(window, 27.5).unwrap();
+        assert!(cents(naive.frequency, 27.5) > 6.0);
+
+        let result = tracker.with_inharmonicity(b).detect(window, 27.5).unwrap();
+        assert!((cents(result.frequency, 27.5) - 5.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_analyzer_locates_partials() {
+        let source = TestAudioSource::sine_with_harmonics(
+            220.0,
+            &[(2.0, 0.5), (3.0, 0.3), (4.0, 0.2)],
+            0.2,
+            SAMPLE_RATE,
+        );
+        let hint = PitchDetector::new(SAMPLE_RATE)
+            .detect(source.samples())
+            .expect("Should detect pitch")
+            .frequency;
+
+        let partials = PartialAnalyzer::new(SAMPLE_RATE).analyze(source.samples(), hint);
+        let numbers: Vec<u32> = partials.iter().map(|p| p.number).collect();
+        assert_eq!(numbers, [1, 2, 3, 4]);
+        for (partial, amplitude) in partials[1..].iter().zip([0.5, 0.3, 0.2]) {
+            let expected = 220.0 * partial.number as f32;
+            let off = cents(partial.frequency, expected);
+            assert!(off.abs() < 1.0, "partial {}: {} cents", partial.number, off);
+            // The source is normalized, so compare with the fundamental
+            let relative = partial.amplitude / partials[0].amplitude;
+            assert!(
+                (relative - amplitude).abs() < 0.02,
+                "partial {}: amplitude {}",
+                partial.number,
+                relative
+            );
+        }
+    }
+
+    #[test]
+    fn test_piano_like_partials() {
+        let b = 0.0005;
+        let source = TestAudioSource::piano_like(55.0, b, 12, 1.0, SAMPLE_RATE);
+        let samples = &source.samples()[SAMPLE_RATE as usize / 20..];
+
+        // Each partial where an inharmonicity estimate would expect it
+        let partials = PartialAnalyzer::new(SAMPLE_RATE).analyze(samples, 55.0);
+        assert_eq!(partials.len(), 8);
+        for partial in &partials {
+            let off = cents(partial.frequency, 55.0 * partial_ratio(partial.number, b));
+            assert!(off.abs() < 0.5, "partial {}: {} cents", partial.number, off);
+        }
+
+        // Knowing the inharmonicity, the tracker recovers the fundamental
+        let tracker = PartialTracker::new(SAMPLE_RATE).with_inharmonicity(b);
+        let result = tracker
+            .detect(&samples[..tracker.window_size()], 55.0)
+            .unwrap();
+        assert!(cents(result.frequency, 55.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_analyzer_follows_stretched_partials() {
+        let b = 0.0004;
+        let samples = weak_a0(0.0, b);
+        let partials = PartialAnalyzer::new(SAMPLE_RATE).analyze(&samples, 27.5);
+
+        for n in 2..=4 {
+            let partial = partials
+                .iter()
+                .find(|p| p.number == n)
+                .unwrap_or_else(|| panic!("partial {} missing", n));
+            let expected = 27.5 * partial_ratio(n, b);
+            let off = cents(partial.frequency, expected);
+            assert!(off.abs() < 2.0, "partial {}: {} cents", n, off);
+        }
+        // Nothing was synthesized above the 4th
+        assert!(partials.iter().all(|p| p.number <= 4));
+    }
+
+    #[test]
+    fn test_analyzer_silence() {
+        let analyzer = PartialAnalyzer::new(SAMPLE_RATE);
+        assert!(analyzer.analyze(&[0.0; 4096], 110.0).is_empty());
+        assert!(analyzer.analyze(&[], 110.0).is_empty());
+    }
+
+    #[test]
+    fn test_silence_and_noise() {
+        let tracker = PartialTracker::new(SAMPLE_RATE);
+        assert!(tracker.detect(&[0.0; 4096], 27.5).is_none());
+
+        // A tone far from the expected partials carries no confidence
+        let samples: Vec<f32> = (0..tracker.window_size())
+            .map(|i| (2.0 * std::f32::consts::PI * 440.0 * i as f32 / SAMPLE_RATE as f32).sin())
+            .collect();
+        let confidence = tracker.detect(&samples, 27.5).map_or(0.0, |r| r.confidence);
+        assert!(confidence < 0.1, "confidence {}", confidence);
+    }
+}
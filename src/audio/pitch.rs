@@ -1,7 +1,15 @@
-//! YIN pitch detection algorithm.
+//! Pitch detection algorithms.
 //!
-//! Implementation based on:
+//! Implementations based on:
 //! de Cheveigné, A., & Kawahara, H. (2002). "YIN, a fundamental frequency estimator for speech and music."
+//! McLeod, P., & Wyvill, G. (2005). "A smarter way to find pitch."
+
+use std::ops::RangeInclusive;
+
+use rustfft::{num_complex::Complex, FftPlanner};
+use serde::{Deserialize, Serialize};
+
+use super::beats::rms;
 
 /// Pitch detection result.
 #[derive(Debug, Clone, Copy)]
@@ -10,12 +18,250 @@ pub struct PitchResult {
     pub frequency: f32,
     /// Confidence score (0.0 to 1.0, higher is better).
     pub confidence: f32,
+    /// Chosen period in samples (after interpolation).
+    pub tau: f32,
+    /// RMS level of the analyzed samples.
+    pub rms: f32,
+    /// How periodic the samples are at the chosen period (0.0 to 1.0),
+    /// before any confidence boost: 1 minus the CMND dip for YIN, the NSDF
+    /// peak for MPM.
+    pub clarity: f32,
+    /// Whether another period, unrelated to the detected one, fits nearly as
+    /// well: two notes are likely sounding.
+    pub ambiguous: bool,
+}
+
+/// Semitones either side of a target the search is clamped to.
+const TARGET_RANGE_SEMITONES: f32 = 6.0;
+
+/// Periods of the lowest searched frequency analyzed for a target.
+const TARGET_PERIODS: f32 = 4.0;
+
+/// Fewest samples analyzed for a target.
+const MIN_TARGET_WINDOW: usize = 1024;
+
+/// Periodicity (1 - CMND, or NSDF peak) a competing period needs to count
+/// as another note sounding.
+const AMBIGUOUS_CLARITY: f32 = 0.75;
+
+/// How much less periodic than the detected one a competing period may be.
+const AMBIGUOUS_MARGIN: f32 = 0.1;
+
+/// Deviation from a whole-number ratio within which two periods are
+/// harmonically related.
+const HARMONIC_TOLERANCE: f32 = 0.05;
+
+/// Largest ratio between periods compared; beyond it the error in the
+/// detected period adds up too far to tell.
+const AMBIGUOUS_MAX_RATIO: f32 = 8.0;
+
+/// Whether a candidate period that isn't harmonically related to the
+/// detected `tau` is nearly as periodic. Candidates are (lag, clarity).
+fn is_ambiguous(
+    candidates: impl IntoIterator<Item = (usize, f32)>,
+    tau: f32,
+    clarity: f32,
+) -> bool {
+    candidates.into_iter().any(|(lag, candidate)| {
+        let ratio = (lag as f32).max(tau) / (lag as f32).min(tau);
+        candidate >= AMBIGUOUS_CLARITY
+            && candidate >= clarity - AMBIGUOUS_MARGIN
+            && ratio <= AMBIGUOUS_MAX_RATIO
+            && (ratio - ratio.round()).abs() > HARMONIC_TOLERANCE
+    })
+}
+
+/// Harmonic-to-noise ratio in dB at which the periodicity no longer lowers
+/// the confidence.
+const FULL_CONFIDENCE_HNR_DB: f32 = 20.0;
+
+/// Confidence from how clean the detected period is (`clarity`) and how
+/// much of the signal's energy repeats at it (`periodicity`, 0 to 1).
+///
+/// Background noise with some periodicity can give a clean-looking dip;
+/// the share of periodic energy, as a harmonic-to-noise ratio, keeps such
+/// detections from scoring high. A clean tone scores 1, one 10 dB above
+/// broadband noise about 0.65.
+fn calibrated_confidence(clarity: f32, periodicity: f32) -> f32 {
+    let periodicity = periodicity.clamp(1e-6, 1.0 - 1e-6);
+    let hnr_db = 10.0 * (periodicity / (1.0 - periodicity)).log10();
+    let score = (hnr_db / FULL_CONFIDENCE_HNR_DB).clamp(0.0, 1.0);
+    (clarity.clamp(0.0, 1.0) * score).sqrt()
+}
+
+/// A pitch detection algorithm.
+pub trait PitchAlgorithm {
+    /// Detect the pitch of the audio samples, if there is a clear one.
+    fn detect(&self, samples: &[f32]) -> Option<PitchResult>;
+
+    /// Detect a pitch within six semitones of `target_hz`.
+    fn detect_near(&self, samples: &[f32], target_hz: f32) -> Option<PitchResult>;
+
+    /// Like [`detect`](Self::detect), reusing `scratch` where the algorithm
+    /// can.
+    fn detect_into(&self, samples: &[f32], _scratch: &mut YinScratch) -> Option<PitchResult> {
+        self.detect(samples)
+    }
+
+    /// Like [`detect_near`](Self::detect_near), reusing `scratch` where
+    /// the algorithm can.
+    fn detect_near_into(
+        &self,
+        samples: &[f32],
+        target_hz: f32,
+        _scratch: &mut YinScratch,
+    ) -> Option<PitchResult> {
+        self.detect_near(samples, target_hz)
+    }
+
+    /// Samples needed to analyze a note near `target_hz`.
+    fn window_for_target(&self, target_hz: f32) -> usize;
+}
+
+/// Frequency range within `semitones` of a target.
+fn target_range(target_hz: f32, semitones: f32) -> (f32, f32) {
+    let ratio = 2.0_f32.powf(semitones / 12.0);
+    (target_hz / ratio, target_hz * ratio)
+}
+
+/// Enough samples for several periods of the lowest frequency searched
+/// around a target.
+fn target_window(sample_rate: u32, target_hz: f32) -> usize {
+    let (min, _) = target_range(target_hz, TARGET_RANGE_SEMITONES);
+    ((TARGET_PERIODS * sample_rate as f32 / min).ceil() as usize).max(MIN_TARGET_WINDOW)
+}
+
+/// Largest YIN lag for `min_frequency` in a window of `len` samples.
+fn max_tau(sample_rate: u32, min_frequency: f32, len: usize) -> usize {
+    (sample_rate as f32 / min_frequency).min((len / 2) as f32) as usize
+}
+
+/// Pitch detection algorithm selected in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PitchMethod {
+    /// YIN, the default.
+    #[default]
+    Yin,
+    /// McLeod Pitch Method, less prone to octave errors on muddy bass.
+    Mpm,
+}
+
+impl PitchMethod {
+    /// Create a detector using this method.
+    pub fn detector(self, sample_rate: u32) -> Box<dyn PitchAlgorithm> {
+        match self {
+            PitchMethod::Yin => Box::new(PitchDetector::new(sample_rate)),
+            PitchMethod::Mpm => Box::new(MpmDetector::new(sample_rate)),
+        }
+    }
+}
+
+/// CMND value below which a dip is taken as clean and not second-guessed
+/// for octave errors.
+const CLEAN_DIP: f32 = 0.02;
+
+/// YIN clarity considered marginal enough to cross-check with HPS.
+const HPS_MARGINAL: RangeInclusive<f32> = 0.4..=0.7;
+
+/// How close in cents the HPS estimate must be to confirm a detection.
+const HPS_AGREEMENT_CENTS: f32 = 30.0;
+
+/// Harmonics multiplied in the harmonic product spectrum.
+const HPS_HARMONICS: usize = 4;
+
+/// Largest FFT used for the harmonic product spectrum.
+const HPS_MAX_FFT: usize = 1 << 16;
+
+/// Taper applied to the terms of the YIN difference function.
+///
+/// Without one, terms near the edges of the integration window weigh as
+/// much as those in the middle, which slightly biases the dip position in
+/// short windows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WindowFunction {
+    /// Rectangular: every term weighs the same.
+    #[default]
+    None,
+    /// Hann: tapers to zero at the edges.
+    Hann,
+    /// Hamming: tapers to 0.08 at the edges.
+    Hamming,
+}
+
+impl WindowFunction {
+    /// Coefficients for a window of `len` samples, or `None` if rectangular.
+    fn coefficients(self, len: usize) -> Option<Vec<f32>> {
+        let (a0, a1) = match self {
+            WindowFunction::None => return None,
+            WindowFunction::Hann => (0.5, 0.5),
+            WindowFunction::Hamming => (0.54, 0.46),
+        };
+        let span = len.saturating_sub(1).max(1) as f32;
+        Some(
+            (0..len)
+                .map(|i| a0 - a1 * (2.0 * std::f32::consts::PI * i as f32 / span).cos())
+                .collect(),
+        )
+    }
+}
+
+/// Buffers reused between YIN detections, so detection doesn't allocate
+/// once they are large enough.
+#[derive(Debug, Clone, Default)]
+pub struct YinScratch {
+    diff: Vec<f32>,
+    cmnd: Vec<f32>,
+    /// Window function coefficients, rebuilt only when the function or
+    /// the tapered length changes.
+    taper: Vec<f32>,
+    taper_function: WindowFunction,
+}
+
+impl YinScratch {
+    /// Create buffers for lags up to `max_tau`.
+    pub fn with_capacity(max_tau: usize) -> Self {
+        Self {
+            diff: Vec::with_capacity(max_tau + 1),
+            cmnd: Vec::with_capacity(max_tau + 1),
+            ..Self::default()
+        }
+    }
+
+    /// Largest lag the buffers hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.diff
+            .capacity()
+            .min(self.cmnd.capacity())
+            .saturating_sub(1)
+    }
+
+    /// Make `taper` hold `function` over `len` samples, empty if
+    /// rectangular.
+    fn update_taper(&mut self, function: WindowFunction, len: usize) {
+        let expected = if function == WindowFunction::None {
+            0
+        } else {
+            len
+        };
+        if self.taper_function != function || self.taper.len() != expected {
+            self.taper = function.coefficients(len).unwrap_or_default();
+            self.taper_function = function;
+        }
+    }
 }
 
 /// YIN-based pitch detector.
+#[derive(Debug, Clone)]
 pub struct PitchDetector {
     sample_rate: u32,
     threshold: f32,
+    /// How deep a lower octave's dip must be relative to the detected one
+    /// to be preferred (0 disables octave correction).
+    octave_bias: f32,
+    /// Cross-check marginal detections with the harmonic product spectrum.
+    hps_verification: bool,
+    window_function: WindowFunction,
     min_frequency: f32,
     max_frequency: f32,
 }
@@ -26,17 +272,40 @@ impl PitchDetector {
         Self {
             sample_rate,
             threshold: 0.1,
+            octave_bias: 0.5,
+            hps_verification: false,
+            window_function: WindowFunction::None,
             min_frequency: 27.5,   // A0
             max_frequency: 4186.0, // C8
         }
     }
 
+    /// Prefer the octave (or twelfth) below when its dip is within `bias`
+    /// times the detected one; 0 disables the correction.
+    pub fn with_octave_bias(mut self, bias: f32) -> Self {
+        self.octave_bias = bias;
+        self
+    }
+
+    /// Cross-check detections of marginal confidence against the harmonic
+    /// product spectrum: boost them if it agrees, reject them if not.
+    pub fn with_hps_verification(mut self, enabled: bool) -> Self {
+        self.hps_verification = enabled;
+        self
+    }
+
     /// Set the confidence threshold for detection.
     pub fn with_threshold(mut self, threshold: f32) -> Self {
         self.threshold = threshold;
         self
     }
 
+    /// Set the taper applied to the difference function.
+    pub fn with_window_function(mut self, window: WindowFunction) -> Self {
+        self.window_function = window;
+        self
+    }
+
     /// Set the frequency range.
     pub fn with_frequency_range(mut self, min: f32, max: f32) -> Self {
         self.min_frequency = min;
@@ -44,68 +313,198 @@ impl PitchDetector {
         self
     }
 
+    /// Narrow the search to `semitone_window` semitones either side of
+    /// `target_hz`, so partials of other strings can't win.
+    pub fn with_target(self, target_hz: f32, semitone_window: f32) -> Self {
+        let (min, max) = target_range(target_hz, semitone_window);
+        self.with_frequency_range(min, max)
+    }
+
+    /// Samples needed to analyze a note near `target_hz`: several periods
+    /// in the bass, far fewer in the treble.
+    pub fn window_for_target(&self, target_hz: f32) -> usize {
+        target_window(self.sample_rate, target_hz)
+    }
+
+    /// Detect pitch with the search clamped to six semitones around
+    /// `target_hz`.
+    pub fn detect_near(&self, samples: &[f32], target_hz: f32) -> Option<PitchResult> {
+        self.detect_near_into(samples, target_hz, &mut YinScratch::default())
+    }
+
+    /// Detect pitch like [`detect_near`](Self::detect_near), working in
+    /// `scratch` instead of allocating.
+    pub fn detect_near_into(
+        &self,
+        samples: &[f32],
+        target_hz: f32,
+        scratch: &mut YinScratch,
+    ) -> Option<PitchResult> {
+        let range = target_range(target_hz, TARGET_RANGE_SEMITONES);
+        self.detect_in_range(samples, range, scratch)
+    }
+
+    /// Buffers sized for windows of up to `window_size` samples.
+    pub fn scratch(&self, window_size: usize) -> YinScratch {
+        YinScratch::with_capacity(self.max_tau(window_size))
+    }
+
+    /// Largest lag searched in a window of `len` samples.
+    fn max_tau(&self, len: usize) -> usize {
+        max_tau(self.sample_rate, self.min_frequency, len)
+    }
+
     /// Detect pitch from audio samples using the YIN algorithm.
     pub fn detect(&self, samples: &[f32]) -> Option<PitchResult> {
+        self.detect_into(samples, &mut YinScratch::default())
+    }
+
+    /// Detect pitch like [`detect`](Self::detect), working in `scratch`
+    /// instead of allocating.
+    pub fn detect_into(&self, samples: &[f32], scratch: &mut YinScratch) -> Option<PitchResult> {
+        let range = (self.min_frequency, self.max_frequency);
+        self.detect_in_range(samples, range, scratch)
+    }
+
+    /// Detect pitch between `min_frequency` and `max_frequency`.
+    fn detect_in_range(
+        &self,
+        samples: &[f32],
+        (min_frequency, max_frequency): (f32, f32),
+        scratch: &mut YinScratch,
+    ) -> Option<PitchResult> {
         if samples.len() < 2 {
             return None;
         }
 
         // Calculate tau range from frequency range
-        let tau_min = (self.sample_rate as f32 / self.max_frequency) as usize;
-        let tau_max =
-            (self.sample_rate as f32 / self.min_frequency).min((samples.len() / 2) as f32) as usize;
+        let tau_min = (self.sample_rate as f32 / max_frequency) as usize;
+        let tau_max = max_tau(self.sample_rate, min_frequency, samples.len());
 
         if tau_max <= tau_min || tau_max >= samples.len() / 2 {
             return None;
         }
 
         // Step 1 & 2: Calculate the difference function
-        let diff = self.difference_function(samples, tau_max);
+        self.difference_function(samples, tau_max, scratch);
 
         // Step 3: Cumulative mean normalized difference function
-        let cmnd = self.cumulative_mean_normalized_difference(&diff);
+        let YinScratch { diff, cmnd, .. } = scratch;
+        self.cumulative_mean_normalized_difference(diff, cmnd);
+        let cmnd = &cmnd[..];
 
         // Step 4: Absolute threshold
-        let tau = self.find_threshold_crossing(&cmnd, tau_min, tau_max)?;
+        let tau = self.find_threshold_crossing(cmnd, tau_min, tau_max)?;
+
+        // A dominant 2nd or 3rd partial can dip below the threshold first
+        let tau = self.correct_octave(cmnd, tau);
 
         // Step 5: Parabolic interpolation for sub-sample accuracy
-        let refined_tau = self.parabolic_interpolation(&cmnd, tau);
+        let refined_tau = self.parabolic_interpolation(cmnd, tau);
 
         // Calculate frequency
         let frequency = self.sample_rate as f32 / refined_tau;
 
-        // Calculate confidence (1 - cmnd value at the dip)
-        let confidence = 1.0 - cmnd[tau].min(1.0);
+        // Calculate confidence from the dip and the share of periodic energy
+        let clarity = 1.0 - cmnd[tau].min(1.0);
+        let periodicity = self.periodicity(samples, tau_max, tau);
+        let mut confidence = calibrated_confidence(clarity, periodicity);
+
+        // Dips elsewhere as deep as this one mean a second note
+        let dips = (tau_min.max(1)..tau_max)
+            .filter(|&t| cmnd[t] < cmnd[t - 1] && cmnd[t] <= cmnd[t + 1])
+            .map(|t| (t, 1.0 - cmnd[t]));
+        let ambiguous = is_ambiguous(dips, refined_tau, clarity);
+
+        // Weak fundamentals (e.g. felt-muted strings) give marginal dips
+        if self.hps_verification && HPS_MARGINAL.contains(&clarity) {
+            let estimate =
+                harmonic_product_spectrum(samples, self.sample_rate, min_frequency, max_frequency)?;
+            let cents = 1200.0 * (estimate / frequency).log2();
+            if cents.abs() > HPS_AGREEMENT_CENTS {
+                return None;
+            }
+            confidence += (1.0 - confidence) / 2.0;
+        }
 
         Some(PitchResult {
             frequency,
             confidence,
+            tau: refined_tau,
+            rms: rms(samples),
+            clarity,
+            ambiguous,
         })
     }
 
     /// Step 1 & 2: Calculate the difference function.
-    fn difference_function(&self, samples: &[f32], max_tau: usize) -> Vec<f32> {
-        let mut diff = vec![0.0; max_tau + 1];
+    fn difference_function(&self, samples: &[f32], max_tau: usize, scratch: &mut YinScratch) {
+        let span = samples.len() - max_tau;
+        scratch.update_taper(self.window_function, span);
+        let YinScratch {
+            diff,
+            taper: window,
+            ..
+        } = scratch;
+        diff.clear();
+        diff.resize(max_tau + 1, 0.0);
 
-        // d(tau) = sum_{j=0}^{W-1} (x_j - x_{j+tau})^2
+        // d(tau) = sum_{j=0}^{W-1} w_j (x_j - x_{j+tau})^2
         for tau in 1..=max_tau {
             let mut sum = 0.0;
-            for j in 0..(samples.len() - max_tau) {
-                let delta = samples[j] - samples[j + tau];
-                sum += delta * delta;
+            if window.is_empty() {
+                for j in 0..span {
+                    let delta = samples[j] - samples[j + tau];
+                    sum += delta * delta;
+                }
+            } else {
+                for (j, w) in window.iter().enumerate() {
+                    let delta = samples[j] - samples[j + tau];
+                    sum += w * delta * delta;
+                }
             }
             diff[tau] = sum;
         }
+    }
 
-        diff
+    /// Share of the energy that repeats after `tau` samples (0 to 1), at
+    /// the peak of the parabola through the neighbouring lags.
+    fn periodicity(&self, samples: &[f32], max_tau: usize, tau: usize) -> f32 {
+        let span = samples.len() - max_tau;
+        let at = |lag: usize| {
+            let (mut acf, mut energy) = (0.0, 0.0);
+            for j in 0..span {
+                acf += samples[j] * samples[j + lag];
+                energy += samples[j] * samples[j] + samples[j + lag] * samples[j + lag];
+            }
+            if energy > 0.0 {
+                2.0 * acf / energy
+            } else {
+                0.0
+            }
+        };
+
+        if tau == 0 || tau >= max_tau {
+            return at(tau).max(0.0);
+        }
+        let (s0, s1, s2) = (at(tau - 1), at(tau), at(tau + 1));
+        let denominator = s0 - 2.0 * s1 + s2;
+        let peak = if denominator < 0.0 {
+            let delta = (0.5 * (s0 - s2) / denominator).clamp(-1.0, 1.0);
+            s1 - 0.25 * (s0 - s2) * delta
+        } else {
+            s1
+        };
+        peak.clamp(0.0, 1.0)
     }
 
     /// Step 3: Cumulative mean normalized difference function.
-    fn cumulative_mean_normalized_difference(&self, diff: &[f32]) -> Vec<f32> {
-        let mut cmnd = vec![0.0; diff.len()];
+    fn cumulative_mean_normalized_difference(&self, diff: &[f32], cmnd: &mut Vec<f32>) {
+        cmnd.clear();
+        cmnd.resize(diff.len(), 0.0);
 
         if diff.is_empty() {
-            return cmnd;
+            return;
         }
 
         cmnd[0] = 1.0; // By definition
@@ -119,8 +518,6 @@ impl PitchDetector {
                 cmnd[tau] = 1.0;
             }
         }
-
-        cmnd
     }
 
     /// Step 4: Find the first tau where cmnd drops below threshold.
@@ -172,6 +569,44 @@ impl PitchDetector {
         }
     }
 
+    /// Prefer a multiple of `tau` whose dip is nearly as deep, unless the
+    /// dip at `tau` is already clean.
+    fn correct_octave(&self, cmnd: &[f32], tau: usize) -> usize {
+        let depth = self.dip_depth(cmnd, tau);
+        if self.octave_bias <= 0.0 || depth < CLEAN_DIP {
+            return tau;
+        }
+
+        for multiple in [2, 3] {
+            // Search the neighbourhood of the multiple for its dip
+            let lo = tau * multiple - multiple;
+            let hi = (tau * multiple + multiple).min(cmnd.len() - 1);
+            let Some(lower) = (lo..=hi).min_by(|&a, &b| cmnd[a].total_cmp(&cmnd[b])) else {
+                break;
+            };
+            if self.dip_depth(cmnd, lower) <= self.octave_bias * depth {
+                return lower;
+            }
+        }
+
+        tau
+    }
+
+    /// CMND value at the bottom of the parabola through a dip, so dips
+    /// falling between samples compare fairly.
+    fn dip_depth(&self, cmnd: &[f32], tau: usize) -> f32 {
+        if tau == 0 || tau >= cmnd.len() - 1 {
+            return cmnd[tau];
+        }
+
+        let s0 = cmnd[tau - 1];
+        let s1 = cmnd[tau];
+        let s2 = cmnd[tau + 1];
+        let delta = (self.parabolic_interpolation(cmnd, tau) - tau as f32).clamp(-1.0, 1.0);
+
+        (s1 - 0.25 * (s0 - s2) * delta).max(0.0)
+    }
+
     /// Step 5: Parabolic interpolation for sub-sample accuracy.
     fn parabolic_interpolation(&self, cmnd: &[f32], tau: usize) -> f32 {
         if tau == 0 || tau >= cmnd.len() - 1 {
@@ -195,71 +630,355 @@ impl PitchDetector {
     }
 }
 
+/// Fundamental estimated with the harmonic product spectrum: the spectrum
+/// multiplied by copies of itself compressed by 2, 3 and 4, so the partials
+/// of a note reinforce its fundamental even when that is nearly missing.
+fn harmonic_product_spectrum(
+    samples: &[f32],
+    sample_rate: u32,
+    min_frequency: f32,
+    max_frequency: f32,
+) -> Option<f32> {
+    // Zero-pad for finer bins
+    let size = (samples.len() * 4).next_power_of_two().min(HPS_MAX_FFT);
+    let n = samples.len().min(size);
+    let mut buffer: Vec<Complex<f32>> = (0..size)
+        .map(|i| {
+            let s = if i < n {
+                let phase = 2.0 * std::f32::consts::PI * i as f32 / n.max(2) as f32;
+                samples[i] * (0.5 - 0.5 * phase.cos())
+            } else {
+                0.0
+            };
+            Complex::new(s, 0.0)
+        })
+        .collect();
+    FftPlanner::new()
+        .plan_fft_forward(size)
+        .process(&mut buffer);
+
+    let magnitude: Vec<f32> = buffer[..size / 2].iter().map(|c| c.norm()).collect();
+    let peak = magnitude.iter().copied().fold(0.0, f32::max);
+    if peak <= f32::EPSILON {
+        return None;
+    }
+
+    // Sum of logs rather than a product, with a floor so an empty bin
+    // doesn't veto a note
+    let floor = peak * 1e-4;
+    let hz_per_bin = sample_rate as f32 / size as f32;
+    let first = ((min_frequency / hz_per_bin).floor() as usize).max(1);
+    let last = ((max_frequency / hz_per_bin).ceil() as usize)
+        .min((magnitude.len() - 1) / HPS_HARMONICS - 1);
+    if last <= first + 1 {
+        return None;
+    }
+    let hps: Vec<f32> = (first - 1..=last + 1)
+        .map(|bin| {
+            (1..=HPS_HARMONICS)
+                .map(|h| magnitude[bin * h].max(floor).ln())
+                .sum()
+        })
+        .collect();
+
+    let best = (1..hps.len() - 1).max_by(|&a, &b| hps[a].total_cmp(&hps[b]))?;
+
+    // Parabolic interpolation between bins
+    let (s0, s1, s2) = (hps[best - 1], hps[best], hps[best + 1]);
+    let denominator = s0 - 2.0 * s1 + s2;
+    let delta = if denominator.abs() < 1e-10 {
+        0.0
+    } else {
+        (0.5 * (s0 - s2) / denominator).clamp(-1.0, 1.0)
+    };
+
+    Some((first - 1 + best) as f32 * hz_per_bin + delta * hz_per_bin)
+}
+
+impl PitchAlgorithm for PitchDetector {
+    fn detect(&self, samples: &[f32]) -> Option<PitchResult> {
+        PitchDetector::detect(self, samples)
+    }
+
+    fn detect_near(&self, samples: &[f32], target_hz: f32) -> Option<PitchResult> {
+        PitchDetector::detect_near(self, samples, target_hz)
+    }
+
+    fn detect_into(&self, samples: &[f32], scratch: &mut YinScratch) -> Option<PitchResult> {
+        PitchDetector::detect_into(self, samples, scratch)
+    }
+
+    fn detect_near_into(
+        &self,
+        samples: &[f32],
+        target_hz: f32,
+        scratch: &mut YinScratch,
+    ) -> Option<PitchResult> {
+        PitchDetector::detect_near_into(self, samples, target_hz, scratch)
+    }
+
+    fn window_for_target(&self, target_hz: f32) -> usize {
+        PitchDetector::window_for_target(self, target_hz)
+    }
+}
+
+/// McLeod Pitch Method detector, based on the normalized square difference
+/// function (NSDF).
+///
+/// Rather than the first dip below an absolute threshold, it takes the first
+/// NSDF peak close to the highest one, which keeps it on the fundamental
+/// when a strong partial makes a later period look almost as good.
+#[derive(Debug, Clone)]
+pub struct MpmDetector {
+    sample_rate: u32,
+    /// Fraction of the highest peak the chosen peak must reach.
+    peak_ratio: f32,
+    /// Minimum clarity (NSDF value at the peak) to report a pitch.
+    min_clarity: f32,
+    min_frequency: f32,
+    max_frequency: f32,
+}
+
+impl MpmDetector {
+    /// Create a new MPM pitch detector.
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            peak_ratio: 0.93,
+            min_clarity: 0.6,
+            min_frequency: 27.5,   // A0
+            max_frequency: 4186.0, // C8
+        }
+    }
+
+    /// Set the fraction of the highest peak the chosen peak must reach.
+    pub fn with_peak_ratio(mut self, ratio: f32) -> Self {
+        self.peak_ratio = ratio;
+        self
+    }
+
+    /// Set the minimum clarity for detection.
+    pub fn with_min_clarity(mut self, clarity: f32) -> Self {
+        self.min_clarity = clarity;
+        self
+    }
+
+    /// Set the frequency range.
+    pub fn with_frequency_range(mut self, min: f32, max: f32) -> Self {
+        self.min_frequency = min;
+        self.max_frequency = max;
+        self
+    }
+
+    /// Narrow the search to `semitone_window` semitones either side of
+    /// `target_hz`.
+    pub fn with_target(self, target_hz: f32, semitone_window: f32) -> Self {
+        let (min, max) = target_range(target_hz, semitone_window);
+        self.with_frequency_range(min, max)
+    }
+
+    /// Detect pitch from audio samples using the McLeod Pitch Method.
+    pub fn detect(&self, samples: &[f32]) -> Option<PitchResult> {
+        // Calculate tau range from frequency range, with a sample to spare
+        // so a peak at the lowest frequency can still be interpolated
+        let tau_min = (self.sample_rate as f32 / self.max_frequency) as usize;
+        let tau_max = ((self.sample_rate as f32 / self.min_frequency).ceil() as usize + 1)
+            .min(samples.len() / 2);
+
+        if tau_max <= tau_min + 1 {
+            return None;
+        }
+
+        let nsdf = self.nsdf(samples, tau_max);
+        let peaks = self.key_maxima(&nsdf, tau_min);
+
+        // The first peak close to the highest one is the fundamental
+        let highest = peaks.iter().map(|&tau| nsdf[tau]).fold(0.0, f32::max);
+        let tau = peaks
+            .iter()
+            .copied()
+            .find(|&tau| nsdf[tau] >= self.peak_ratio * highest)?;
+
+        let (refined_tau, clarity) = self.parabolic_peak(&nsdf, tau);
+        if clarity < self.min_clarity {
+            return None;
+        }
+        let ambiguous = is_ambiguous(peaks.iter().map(|&p| (p, nsdf[p])), refined_tau, clarity);
+
+        Some(PitchResult {
+            frequency: self.sample_rate as f32 / refined_tau,
+            // The NSDF peak is itself the share of periodic energy
+            confidence: calibrated_confidence(clarity, clarity),
+            tau: refined_tau,
+            rms: rms(samples),
+            clarity: clarity.min(1.0),
+            ambiguous,
+        })
+    }
+
+    /// Normalized square difference function.
+    fn nsdf(&self, samples: &[f32], max_tau: usize) -> Vec<f32> {
+        let mut nsdf = vec![0.0; max_tau + 1];
+
+        // n(tau) = 2 * sum(x_j * x_{j+tau}) / sum(x_j^2 + x_{j+tau}^2)
+        for (tau, value) in nsdf.iter_mut().enumerate() {
+            let mut acf = 0.0;
+            let mut energy = 0.0;
+            for j in 0..samples.len() - tau {
+                acf += samples[j] * samples[j + tau];
+                energy += samples[j] * samples[j] + samples[j + tau] * samples[j + tau];
+            }
+            if energy > 0.0 {
+                *value = 2.0 * acf / energy;
+            }
+        }
+
+        nsdf
+    }
+
+    /// Highest point of each positive region of the NSDF, skipping the one
+    /// around zero lag.
+    fn key_maxima(&self, nsdf: &[f32], tau_min: usize) -> Vec<usize> {
+        let mut peaks = Vec::new();
+        let mut peak: Option<usize> = None;
+
+        // Skip the central peak until the NSDF first goes negative
+        let Some(start) = nsdf.iter().position(|&v| v < 0.0) else {
+            return peaks;
+        };
+
+        for tau in start..nsdf.len() - 1 {
+            if nsdf[tau] > 0.0 {
+                let is_max = nsdf[tau] >= nsdf[tau - 1] && nsdf[tau] >= nsdf[tau + 1];
+                if is_max && tau >= tau_min && peak.is_none_or(|p| nsdf[tau] > nsdf[p]) {
+                    peak = Some(tau);
+                }
+            } else if let Some(p) = peak.take() {
+                peaks.push(p);
+            }
+        }
+        // A region still open at the end of the range counts too
+        peaks.extend(peak);
+
+        peaks
+    }
+
+    /// Parabolic interpolation of a peak, returning its position and height.
+    fn parabolic_peak(&self, nsdf: &[f32], tau: usize) -> (f32, f32) {
+        let s0 = nsdf[tau - 1];
+        let s1 = nsdf[tau];
+        let s2 = nsdf[tau + 1];
+
+        let denominator = s0 - 2.0 * s1 + s2;
+        if denominator.abs() < 1e-10 {
+            return (tau as f32, s1);
+        }
+
+        let delta = (0.5 * (s0 - s2) / denominator).clamp(-1.0, 1.0);
+        (tau as f32 + delta, s1 - 0.25 * (s0 - s2) * delta)
+    }
+}
+
+impl PitchAlgorithm for MpmDetector {
+    fn detect(&self, samples: &[f32]) -> Option<PitchResult> {
+        MpmDetector::detect(self, samples)
+    }
+
+    fn detect_near(&self, samples: &[f32], target_hz: f32) -> Option<PitchResult> {
+        self.clone()
+            .with_target(target_hz, TARGET_RANGE_SEMITONES)
+            .detect(samples)
+    }
+
+    fn window_for_target(&self, target_hz: f32) -> usize {
+        target_window(self.sample_rate, target_hz)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::audio::partials::partial_ratio;
     use crate::audio::traits::TestAudioSource;
 
     const SAMPLE_RATE: u32 = 44100;
 
-    fn detect_frequency(frequency: f32) -> Option<PitchResult> {
+    /// Run a check against each algorithm.
+    fn for_each_algorithm(check: impl Fn(&str, &dyn PitchAlgorithm)) {
+        check("yin", &PitchDetector::new(SAMPLE_RATE));
+        check("mpm", &MpmDetector::new(SAMPLE_RATE));
+    }
+
+    fn detect_frequency(detector: &dyn PitchAlgorithm, frequency: f32) -> Option<PitchResult> {
         let source = TestAudioSource::sine(frequency, 0.2, SAMPLE_RATE);
-        let detector = PitchDetector::new(SAMPLE_RATE);
         detector.detect(source.samples())
     }
 
     #[test]
     fn test_detect_a4_440hz() {
-        let result = detect_frequency(440.0).expect("Should detect pitch");
-        let error = (result.frequency - 440.0).abs();
-        assert!(
-            error < 0.5,
-            "Expected ~440Hz, got {} (error: {})",
-            result.frequency,
-            error
-        );
-        assert!(
-            result.confidence > 0.9,
-            "Expected high confidence, got {}",
-            result.confidence
-        );
+        for_each_algorithm(|name, detector| {
+            let result = detect_frequency(detector, 440.0).expect("Should detect pitch");
+            let error = (result.frequency - 440.0).abs();
+            assert!(
+                error < 0.5,
+                "{}: expected ~440Hz, got {} (error: {})",
+                name,
+                result.frequency,
+                error
+            );
+            assert!(
+                result.confidence > 0.9,
+                "{}: expected high confidence, got {}",
+                name,
+                result.confidence
+            );
+        });
     }
 
     #[test]
     fn test_detect_a0_27_5hz() {
-        let result = detect_frequency(27.5).expect("Should detect pitch");
-        let error = (result.frequency - 27.5).abs();
-        assert!(
-            error < 0.5,
-            "Expected ~27.5Hz, got {} (error: {})",
-            result.frequency,
-            error
-        );
+        for_each_algorithm(|name, detector| {
+            let result = detect_frequency(detector, 27.5).expect("Should detect pitch");
+            let error = (result.frequency - 27.5).abs();
+            assert!(
+                error < 0.5,
+                "{}: expected ~27.5Hz, got {} (error: {})",
+                name,
+                result.frequency,
+                error
+            );
+        });
     }
 
     #[test]
     fn test_detect_c8_4186hz() {
-        let result = detect_frequency(4186.0).expect("Should detect pitch");
-        let error = (result.frequency - 4186.0).abs();
-        // Higher frequencies have more absolute error due to sample rate limitations
-        assert!(
-            error < 10.0,
-            "Expected ~4186Hz, got {} (error: {})",
-            result.frequency,
-            error
-        );
+        for_each_algorithm(|name, detector| {
+            let result = detect_frequency(detector, 4186.0).expect("Should detect pitch");
+            let error = (result.frequency - 4186.0).abs();
+            // Higher frequencies have more absolute error due to sample rate limitations
+            assert!(
+                error < 10.0,
+                "{}: expected ~4186Hz, got {} (error: {})",
+                name,
+                result.frequency,
+                error
+            );
+        });
     }
 
     #[test]
     fn test_detect_middle_c_261hz() {
-        let result = detect_frequency(261.63).expect("Should detect pitch");
-        let error = (result.frequency - 261.63).abs();
-        assert!(
-            error < 0.5,
-            "Expected ~261.63Hz, got {} (error: {})",
-            result.frequency,
-            error
-        );
+        for_each_algorithm(|name, detector| {
+            let result = detect_frequency(detector, 261.63).expect("Should detect pitch");
+            let error = (result.frequency - 261.63).abs();
+            assert!(
+                error < 0.5,
+                "{}: expected ~261.63Hz, got {} (error: {})",
+                name,
+                result.frequency,
+                error
+            );
+        });
     }
 
     #[test]
@@ -271,26 +990,166 @@ mod tests {
             0.2,
             SAMPLE_RATE,
         );
-        let detector = PitchDetector::new(SAMPLE_RATE);
-        let result = detector
+        for_each_algorithm(|name, detector| {
+            let result = detector
+                .detect(source.samples())
+                .expect("Should detect pitch");
+
+            let error = (result.frequency - 440.0).abs();
+            assert!(
+                error < 1.0,
+                "{}: expected ~440Hz fundamental, got {} (error: {})",
+                name,
+                result.frequency,
+                error
+            );
+        });
+    }
+
+    #[test]
+    fn test_dominant_second_partial() {
+        // A2 played hard: the 2nd partial at 5x the fundamental
+        let source = TestAudioSource::sine_with_harmonics(110.0, &[(2.0, 5.0)], 0.2, SAMPLE_RATE);
+
+        let result = PitchDetector::new(SAMPLE_RATE)
             .detect(source.samples())
             .expect("Should detect pitch");
+        assert!(
+            (result.frequency - 110.0).abs() < 0.5,
+            "Expected the 110Hz fundamental, got {}",
+            result.frequency
+        );
 
-        let error = (result.frequency - 440.0).abs();
+        // Without the correction the octave above is reported
+        let result = PitchDetector::new(SAMPLE_RATE)
+            .with_octave_bias(0.0)
+            .detect(source.samples())
+            .expect("Should detect pitch");
         assert!(
-            error < 1.0,
-            "Expected ~440Hz fundamental, got {} (error: {})",
-            result.frequency,
-            error
+            (result.frequency - 220.0).abs() < 1.0,
+            "{}",
+            result.frequency
+        );
+    }
+
+    #[test]
+    fn test_target_ignores_harmonic_outside_window() {
+        // Without octave correction the strong 2nd partial wins
+        let source = TestAudioSource::sine_with_harmonics(110.0, &[(2.0, 5.0)], 0.2, SAMPLE_RATE);
+        let detector = PitchDetector::new(SAMPLE_RATE).with_octave_bias(0.0);
+        let result = detector.clone().detect(source.samples()).unwrap();
+        assert!(
+            (result.frequency - 220.0).abs() < 1.0,
+            "{}",
+            result.frequency
+        );
+
+        let result = detector
+            .with_target(110.0, 4.0)
+            .detect(source.samples())
+            .expect("Should detect the fundamental");
+        assert!(
+            (result.frequency - 110.0).abs() < 0.5,
+            "Expected the 110Hz fundamental, got {}",
+            result.frequency
+        );
+
+        let result = MpmDetector::new(SAMPLE_RATE)
+            .with_target(110.0, 4.0)
+            .detect(source.samples())
+            .expect("Should detect the fundamental");
+        assert!(
+            (result.frequency - 110.0).abs() < 0.5,
+            "{}",
+            result.frequency
+        );
+
+        // A target an octave up finds the partial instead
+        let result = PitchDetector::new(SAMPLE_RATE)
+            .with_target(220.0, 4.0)
+            .detect(source.samples())
+            .expect("Should detect the partial");
+        assert!(
+            (result.frequency - 220.0).abs() < 1.0,
+            "{}",
+            result.frequency
+        );
+    }
+
+    /// A4 with a weaker C5 sounding alongside, as when a neighbouring key
+    /// is brushed.
+    fn two_notes(second: f32) -> Vec<f32> {
+        (0..4410)
+            .map(|i| {
+                let t = 2.0 * std::f32::consts::PI * i as f32 / SAMPLE_RATE as f32;
+                0.5 * ((440.0 * t).sin() + second * (523.25 * t).sin())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_two_notes_ambiguous() {
+        for_each_algorithm(|name, detector| {
+            for second in [1.0, 0.5] {
+                let result = detector.detect(&two_notes(second));
+                assert!(
+                    result.is_none_or(|r| r.ambiguous),
+                    "{} took {:?} for one note",
+                    name,
+                    result
+                );
+            }
+
+            let single = detector
+                .detect(&two_notes(0.0))
+                .expect("Should detect pitch");
+            assert!(!single.ambiguous, "{}: {:?}", name, single);
+        });
+    }
+
+    #[test]
+    fn test_harmonics_not_ambiguous() {
+        for_each_algorithm(|name, detector| {
+            for freq in [55.0, 110.0, 261.63, 440.0, 1046.5, 2093.0] {
+                let source = TestAudioSource::sine_with_harmonics(
+                    freq,
+                    &[(2.0, 0.7), (3.0, 0.5), (4.0, 0.3), (5.0, 0.2)],
+                    0.2,
+                    SAMPLE_RATE,
+                );
+                let result = detector
+                    .detect(source.samples())
+                    .expect("Should detect pitch");
+                assert!(!result.ambiguous, "{} at {}Hz: {:?}", name, freq, result);
+            }
+        });
+    }
+
+    #[test]
+    fn test_dominant_third_partial() {
+        let source = TestAudioSource::sine_with_harmonics(
+            110.0,
+            &[(2.0, 0.3), (3.0, 5.0)],
+            0.2,
+            SAMPLE_RATE,
+        );
+        let result = PitchDetector::new(SAMPLE_RATE)
+            .detect(source.samples())
+            .expect("Should detect pitch");
+        assert!(
+            (result.frequency - 110.0).abs() < 0.5,
+            "Expected the 110Hz fundamental, got {}",
+            result.frequency
         );
     }
 
     #[test]
     fn test_silence_returns_none() {
         let silence = vec![0.0; 4096];
-        let detector = PitchDetector::new(SAMPLE_RATE);
-        let result = detector.detect(&silence);
-        assert!(result.is_none(), "Silence should return None");
+        for_each_algorithm(|name, detector| {
+            let result = detector.detect(&silence);
+            assert!(result.is_none(), "{}: silence should return None", name);
+        });
     }
 
     #[test]
@@ -310,15 +1169,17 @@ mod tests {
             noise.push((sample + high_freq).clamp(-1.0, 1.0));
         }
 
-        let detector = PitchDetector::new(SAMPLE_RATE).with_threshold(0.1);
-        let result = detector.detect(&noise);
+        for_each_algorithm(|name, detector| {
+            let result = detector.detect(&noise);
 
-        // Noise should either return None or the detector should reject it
-        // due to the threshold (which results in None)
-        assert!(
-            result.is_none(),
-            "Noise should not produce a confident pitch detection"
-        );
+            // Noise should either return None or the detector should reject it
+            // due to the threshold (which results in None)
+            assert!(
+                result.is_none(),
+                "{}: noise should not produce a confident pitch detection",
+                name
+            );
+        });
     }
 
     #[test]
@@ -333,25 +1194,593 @@ mod tests {
         assert!(strict_detector.detect(source.samples()).is_some());
     }
 
+    /// `freq` plus `(harmonic, amplitude)` partials, computed inline so
+    /// the recorded results below don't depend on the test sources.
+    fn baseline_signal(freq: f32, partials: &[(f32, f32)], len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                let mut s = (2.0 * std::f32::consts::PI * freq * t).sin();
+                for &(h, a) in partials {
+                    s += a * (2.0 * std::f32::consts::PI * freq * h * t).sin();
+                }
+                0.5 * s
+            })
+            .collect()
+    }
+
+    type Bits = Option<(u32, u32, u32)>;
+
+    /// `(frequency, confidence, tau)` bits from `detect` and `detect_near`
+    /// as recorded with the allocating implementation, before scratch
+    /// buffers, for the plain and Hann-tapered detectors.
+    const BASELINE: [[(f32, Bits, Bits); 7]; 2] = [
+        [
+            (27.5, Some((1104942684, 1065352717, 1153982464)), None),
+            (
+                55.0,
+                Some((1113325575, 1065353190, 1145599063)),
+                Some((1113325576, 1065353189, 1145599062)),
+            ),
+            (110.0, None, Some((1121714202, 1065353202, 1137210437))),
+            (
+                261.63,
+                Some((1132646623, 1065352070, 1126731447)),
+                Some((1132646623, 1065352077, 1126731447)),
+            ),
+            (
+                440.0,
+                Some((1138492040, 1065351432, 1120432655)),
+                Some((1138491997, 1065351434, 1120432694)),
+            ),
+            (1046.5, None, Some((1149425859, 1065351185, 1109951630))),
+            (3520.0, None, Some((1163669485, 1065109450, 1095256252))),
+        ],
+        [
+            (27.5, Some((1104942684, 1065352670, 1153982464)), None),
+            (
+                55.0,
+                Some((1113325578, 1065353190, 1145599060)),
+                Some((1113325578, 1065353190, 1145599060)),
+            ),
+            (110.0, None, Some((1121714220, 1065353202, 1137210421))),
+            (
+                261.63,
+                Some((1132646626, 1065352078, 1126731444)),
+                Some((1132646626, 1065352078, 1126731444)),
+            ),
+            (
+                440.0,
+                Some((1138492040, 1065351435, 1120432655)),
+                Some((1138492040, 1065351435, 1120432655)),
+            ),
+            (1046.5, None, Some((1149425970, 1065351171, 1109951487))),
+            (3520.0, None, Some((1163669498, 1065109676, 1095256240))),
+        ],
+    ];
+
+    #[test]
+    fn test_detect_into_matches_baseline() {
+        let partials: [&[(f32, f32)]; 7] = [
+            &[(2.0, 0.8), (3.0, 0.5)],
+            &[(2.0, 1.5)],
+            &[(2.0, 0.5)],
+            &[],
+            &[(2.0, 0.5), (3.0, 0.3)],
+            &[(2.0, 0.2)],
+            &[],
+        ];
+        let lengths = [4410, 4410, 2048, 4410, 4410, 1024, 2048];
+        let detectors = [
+            PitchDetector::new(SAMPLE_RATE),
+            PitchDetector::new(SAMPLE_RATE).with_window_function(WindowFunction::Hann),
+        ];
+        let bits = |r: Option<PitchResult>| {
+            r.map(|r| {
+                (
+                    r.frequency.to_bits(),
+                    r.confidence.to_bits(),
+                    r.tau.to_bits(),
+                )
+            })
+        };
+
+        for (detector, expected) in detectors.iter().zip(&BASELINE) {
+            // One scratch across every size and target, as in the capture loop
+            let mut scratch = YinScratch::default();
+            for (i, &(freq, full, near)) in expected.iter().enumerate() {
+                let samples = baseline_signal(freq, partials[i], lengths[i]);
+                let result = detector.detect_into(&samples, &mut scratch);
+                assert_eq!(bits(result), full, "{}Hz", freq);
+                assert_eq!(bits(detector.detect(&samples)), full, "{}Hz", freq);
+                let result = detector.detect_near_into(&samples, freq, &mut scratch);
+                assert_eq!(bits(result), near, "{}Hz near", freq);
+            }
+        }
+    }
+
+    #[test]
+    fn test_scratch_does_not_grow() {
+        let detector = PitchDetector::new(SAMPLE_RATE);
+        let mut scratch = detector.scratch(4410);
+        let capacity = scratch.capacity();
+        assert_eq!(capacity, 1603);
+
+        for freq in [27.5, 110.0, 440.0, 3520.0] {
+            let source =
+                TestAudioSource::sine_with_harmonics(freq, &[(2.0, 0.5)], 0.2, SAMPLE_RATE);
+            for len in [4410, 2048, 4410] {
+                detector.detect_into(&source.samples()[..len], &mut scratch);
+            }
+        }
+        // Pre-sized for the window, so the buffers never grew
+        assert_eq!(scratch.capacity(), capacity);
+    }
+
+    #[test]
+    fn test_detect_near_reuses_taper() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<PitchDetector>();
+
+        let detector = PitchDetector::new(SAMPLE_RATE).with_window_function(WindowFunction::Hann);
+        let source = TestAudioSource::sine(110.0, 0.2, SAMPLE_RATE);
+        let window = detector.window_for_target(110.0);
+        let samples = &source.samples()[..window];
+        let mut scratch = YinScratch::default();
+
+        let narrowed = detector
+            .clone()
+            .with_target(110.0, TARGET_RANGE_SEMITONES)
+            .detect(samples)
+            .map(|r| (r.frequency, r.confidence, r.tau));
+        for _ in 0..3 {
+            let result = detector
+                .detect_near_into(samples, 110.0, &mut scratch)
+                .map(|r| (r.frequency, r.confidence, r.tau));
+            assert_eq!(result, narrowed);
+        }
+
+        // Built once and kept in the scratch buffers
+        let taper = scratch.taper.as_ptr();
+        detector.detect_near_into(samples, 110.0, &mut scratch);
+        assert_eq!(scratch.taper.as_ptr(), taper);
+        assert!(!scratch.taper.is_empty());
+    }
+
+    #[test]
+    fn test_window_function_does_not_shift_a4() {
+        let source = TestAudioSource::sine(440.0, 0.1, SAMPLE_RATE);
+        let plain = PitchDetector::new(SAMPLE_RATE)
+            .detect(source.samples())
+            .unwrap();
+        for window in [WindowFunction::Hann, WindowFunction::Hamming] {
+            let result = PitchDetector::new(SAMPLE_RATE)
+                .with_window_function(window)
+                .detect(source.samples())
+                .expect("Should detect pitch");
+            let shift = 1200.0 * (result.frequency / plain.frequency).log2();
+            assert!(shift.abs() < 0.1, "{:?} shifted by {} cents", window, shift);
+        }
+    }
+
+    #[test]
+    fn test_window_function_short_treble_window() {
+        // A7 in the short window used for treble notes
+        let source = TestAudioSource::sine(3520.0, 0.1, SAMPLE_RATE);
+        let window = PitchDetector::new(SAMPLE_RATE).window_for_target(3520.0);
+        let samples = &source.samples()[..window];
+        let error = |detector: PitchDetector| {
+            let result = detector
+                .detect_near(samples, 3520.0)
+                .expect("Should detect pitch");
+            (1200.0 * (result.frequency / 3520.0).log2()).abs()
+        };
+
+        let plain = error(PitchDetector::new(SAMPLE_RATE));
+        let hann =
+            error(PitchDetector::new(SAMPLE_RATE).with_window_function(WindowFunction::Hann));
+        assert!(hann <= plain + 0.05, "Hann {} cents, plain {}", hann, plain);
+        assert!(hann < 2.0, "{} cents", hann);
+    }
+
     #[test]
     fn test_various_frequencies() {
         // Test across the piano range
         let test_freqs = [55.0, 110.0, 220.0, 440.0, 880.0, 1760.0, 3520.0];
 
-        for &freq in &test_freqs {
-            let result =
-                detect_frequency(freq).unwrap_or_else(|| panic!("Should detect {}Hz", freq));
+        for_each_algorithm(|name, detector| {
+            for &freq in &test_freqs {
+                let result = detect_frequency(detector, freq)
+                    .unwrap_or_else(|| panic!("{}: should detect {}Hz", name, freq));
+
+                let error = (result.frequency - freq).abs();
+                let relative_error = error / freq;
+
+                assert!(
+                    relative_error < 0.01,
+                    "{}: expected {}Hz, got {} (relative error: {:.2}%)",
+                    name,
+                    freq,
+                    result.frequency,
+                    relative_error * 100.0
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn test_target_window_improves_bass() {
+        let fixed = SAMPLE_RATE as usize / 10;
+        let source = TestAudioSource::sine(27.5, 1.0, SAMPLE_RATE);
+        let samples = source.samples();
+        let cents = |result: Option<PitchResult>| {
+            let result = result.expect("Should detect pitch");
+            1200.0 * (result.frequency / 27.5).log2().abs()
+        };
+
+        for_each_algorithm(|name, detector| {
+            let adaptive = detector.window_for_target(27.5);
+            assert!(adaptive > fixed, "{}: {} samples", name, adaptive);
+            let error = cents(detector.detect_near(&samples[..adaptive], 27.5));
+            assert!(error < 0.1, "{}: {:.3} cents off", name, error);
+        });
+
+        // A fixed 100ms window holds under three periods of A0
+        let detector = PitchDetector::new(SAMPLE_RATE);
+        let fixed_error = cents(detector.detect(&samples[..fixed]));
+        let adaptive = detector.window_for_target(27.5);
+        let adaptive_error = cents(detector.detect_near(&samples[..adaptive], 27.5));
+        assert!(
+            adaptive_error < fixed_error / 10.0,
+            "{:.3} cents with the target window, {:.3} fixed",
+            adaptive_error,
+            fixed_error
+        );
+    }
+
+    #[test]
+    fn test_target_window_shortens_treble() {
+        let fixed = SAMPLE_RATE as usize / 10;
+        let source = TestAudioSource::sine(4186.0, 0.1, SAMPLE_RATE);
 
-            let error = (result.frequency - freq).abs();
-            let relative_error = error / freq;
+        for_each_algorithm(|name, detector| {
+            let adaptive = detector.window_for_target(4186.0);
+            assert!(adaptive < fixed / 4, "{}: {} samples", name, adaptive);
 
+            let result = detector
+                .detect_near(&source.samples()[..adaptive], 4186.0)
+                .expect("Should detect pitch");
             assert!(
-                relative_error < 0.01,
-                "Expected {}Hz, got {} (relative error: {:.2}%)",
-                freq,
-                result.frequency,
-                relative_error * 100.0
+                (result.frequency - 4186.0).abs() < 10.0,
+                "{}: got {}",
+                name,
+                result.frequency
             );
+        });
+    }
+
+    #[test]
+    fn test_detect_near_ignores_other_octaves() {
+        // A loud octave above the target is outside the search
+        let source = TestAudioSource::sine_with_harmonics(110.0, &[(2.0, 8.0)], 0.2, SAMPLE_RATE);
+        for_each_algorithm(|name, detector| {
+            let result = detector
+                .detect_near(source.samples(), 110.0)
+                .expect("Should detect pitch");
+            assert!(
+                (result.frequency - 110.0).abs() < 0.5,
+                "{}: got {}",
+                name,
+                result.frequency
+            );
+        });
+    }
+
+    /// Cents a detector reads a piano-like note at, relative to `f0`.
+    fn piano_like_cents(detector: &dyn PitchAlgorithm, f0: f32, b: f32, partials: u32) -> f32 {
+        let source = TestAudioSource::piano_like(f0, b, partials, 1.0, SAMPLE_RATE);
+        // Skip the first 50ms, as the app would the attack
+        let start = SAMPLE_RATE as usize / 20;
+        let window = &source.samples()[start..start + detector.window_for_target(f0)];
+        let result = detector
+            .detect_near(window, f0)
+            .expect("Should detect pitch");
+        1200.0 * (result.frequency / f0).log2()
+    }
+
+    #[test]
+    fn test_piano_like_bass() {
+        // Periodicity follows the stretched upper partials too, so the
+        // reading lands sharp of the fundamental partial. Pin how far.
+        let b = 0.0005;
+        let fundamental = 1200.0 * partial_ratio(1, b).log2();
+        for f0 in [27.5, 55.0, 110.0] {
+            for_each_algorithm(|name, detector| {
+                let cents = piano_like_cents(detector, f0, b, 12);
+                assert!(
+                    cents > fundamental && cents < 18.0,
+                    "{} at {} Hz: {:+.2} cents",
+                    name,
+                    f0,
+                    cents
+                );
+            });
+        }
+    }
+
+    #[test]
+    fn test_piano_like_treble() {
+        let b = 0.01;
+        let fundamental = 1200.0 * partial_ratio(1, b).log2();
+        for f0 in [1046.5, 2093.0, 3520.0] {
+            for_each_algorithm(|name, detector| {
+                let cents = piano_like_cents(detector, f0, b, 4);
+                assert!(
+                    cents > fundamental && cents < 65.0,
+                    "{} at {} Hz: {:+.2} cents",
+                    name,
+                    f0,
+                    cents
+                );
+            });
+        }
+    }
+
+    /// Readings in cents from `center` over 4096-sample windows a quarter
+    /// window apart.
+    fn readings(
+        detector: &dyn PitchAlgorithm,
+        source: &TestAudioSource,
+        center: f32,
+    ) -> Vec<(f32, f32)> {
+        source
+            .samples()
+            .windows(4096)
+            .step_by(1024)
+            .map(|window| {
+                let result = detector.detect(window).expect("Should detect pitch");
+                (
+                    1200.0 * (result.frequency / center).log2(),
+                    result.confidence,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_vibrato_stays_within_depth() {
+        // A wobbling unison, 8 cents either way five times a second
+        let source = TestAudioSource::vibrato(440.0, 8.0, 5.0, 1.0, SAMPLE_RATE);
+        for_each_algorithm(|name, detector| {
+            let readings = readings(detector, &source, 440.0);
+            for (cents, confidence) in &readings {
+                assert!(cents.abs() <= 8.0, "{}: {:+.2} cents", name, cents);
+                assert!(*confidence > 0.8, "{}: confidence {}", name, confidence);
+            }
+            // The window averages some of the wobble away, but not all
+            let widest = readings.iter().map(|(c, _)| c.abs()).fold(0.0, f32::max);
+            assert!(widest > 4.0, "{}: widest {:+.2} cents", name, widest);
+        });
+    }
+
+    #[test]
+    fn test_tremolo_keeps_pitch() {
+        let source = TestAudioSource::tremolo(440.0, 0.8, 6.0, 1.0, SAMPLE_RATE);
+        for_each_algorithm(|name, detector| {
+            for (cents, confidence) in readings(detector, &source, 440.0) {
+                assert!(cents.abs() < 1.0, "{}: {:+.2} cents", name, cents);
+                assert!(confidence > 0.8, "{}: confidence {}", name, confidence);
+            }
+        });
+    }
+
+    /// A felt-muted 110Hz string: the fundamental nearly cancelled, with
+    /// deterministic noise at `noise` amplitude.
+    fn muted_string(noise: f32) -> Vec<f32> {
+        let source = TestAudioSource::sine_with_harmonics(
+            110.0,
+            &[(1.0, -0.95), (2.0, 1.0), (3.0, 0.8), (4.0, 0.5)],
+            0.1,
+            SAMPLE_RATE,
+        );
+        let mut x = 98765_u64;
+        source
+            .samples()
+            .iter()
+            .map(|s| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                s + noise * ((x as f64 / u64::MAX as f64) * 2.0 - 1.0) as f32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_hps_nearly_missing_fundamental() {
+        let estimate = harmonic_product_spectrum(&muted_string(0.0), SAMPLE_RATE, 27.5, 4186.0)
+            .expect("Should estimate pitch");
+        assert!((estimate - 110.0).abs() < 0.5, "{}", estimate);
+
+        // Noise doesn't pull it off the fundamental either
+        let estimate = harmonic_product_spectrum(&muted_string(0.6), SAMPLE_RATE, 27.5, 4186.0)
+            .expect("Should estimate pitch");
+        assert!((estimate - 110.0).abs() < 0.5, "{}", estimate);
+    }
+
+    #[test]
+    fn test_hps_verification_boosts_agreeing_detection() {
+        let samples = muted_string(0.6);
+        let raw = PitchDetector::new(SAMPLE_RATE)
+            .detect_near(&samples, 110.0)
+            .expect("Should detect pitch");
+        assert!(HPS_MARGINAL.contains(&raw.clarity), "{}", raw.clarity);
+
+        let verified = PitchDetector::new(SAMPLE_RATE)
+            .with_hps_verification(true)
+            .detect_near(&samples, 110.0)
+            .expect("HPS agrees");
+        assert_eq!(verified.frequency, raw.frequency);
+        let boosted = raw.confidence + (1.0 - raw.confidence) / 2.0;
+        assert!((verified.confidence - boosted).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_hps_verification_rejects_disagreeing_detection() {
+        // Over the whole range, YIN settles on a subharmonic
+        let samples = muted_string(0.6);
+        let raw = PitchDetector::new(SAMPLE_RATE)
+            .detect(&samples)
+            .expect("Should detect pitch");
+        assert!(HPS_MARGINAL.contains(&raw.clarity), "{}", raw.clarity);
+        assert!((raw.frequency - 110.0).abs() > 10.0, "{}", raw.frequency);
+
+        let verified = PitchDetector::new(SAMPLE_RATE)
+            .with_hps_verification(true)
+            .detect(&samples);
+        assert!(verified.is_none());
+    }
+
+    #[test]
+    fn test_rms_scales_with_amplitude() {
+        let source = TestAudioSource::sine(440.0, 0.2, SAMPLE_RATE);
+        let quiet: Vec<f32> = source.samples().iter().map(|s| s * 0.1).collect();
+
+        for_each_algorithm(|name, detector| {
+            let loud = detector
+                .detect(source.samples())
+                .expect("Should detect pitch");
+            let quiet = detector.detect(&quiet).expect("Should detect pitch");
+            assert!(
+                (loud.rms - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.01,
+                "{}: {}",
+                name,
+                loud.rms
+            );
+            assert!(
+                (quiet.rms / loud.rms - 0.1).abs() < 1e-3,
+                "{}: {}",
+                name,
+                quiet.rms
+            );
+            // Quiet but clean
+            assert!(quiet.clarity > 0.9, "{}: {}", name, quiet.clarity);
+        });
+    }
+
+    #[test]
+    fn test_confidence_calibration() {
+        let source = TestAudioSource::sine(440.0, 0.1, SAMPLE_RATE);
+        let mut x = 24680_u64;
+        let mut noise = || {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            // Uniform in -1..1: power 1/3
+            ((x as f64 / u64::MAX as f64) * 2.0 - 1.0) as f32
+        };
+        // Sine power is 1/2; scale the noise for 10 dB below it
+        let scale = (0.5_f32 / 10.0 * 3.0).sqrt();
+        let snr_10db: Vec<f32> = source
+            .samples()
+            .iter()
+            .map(|s| s + scale * noise())
+            .collect();
+        let white: Vec<f32> = (0..4410).map(|_| noise()).collect();
+
+        for_each_algorithm(|name, detector| {
+            let clean = detector.detect(source.samples()).unwrap().confidence;
+            assert!(clean >= 0.9, "{}: clean sine {}", name, clean);
+
+            let noisy = detector
+                .detect(&snr_10db)
+                .expect("Should detect pitch")
+                .confidence;
+            assert!(
+                (0.5..=0.9).contains(&noisy),
+                "{}: 10 dB SNR {}",
+                name,
+                noisy
+            );
+
+            let noise = detector.detect(&white).map_or(0.0, |r| r.confidence);
+            assert!(noise <= 0.3, "{}: white noise {}", name, noise);
+        });
+    }
+
+    #[test]
+    fn test_clarity_falls_with_noise() {
+        let source = TestAudioSource::sine(220.0, 0.1, SAMPLE_RATE);
+        let noisy = |amount: f32| -> Vec<f32> {
+            let mut x = 13579_u64;
+            source
+                .samples()
+                .iter()
+                .map(|s| {
+                    x ^= x << 13;
+                    x ^= x >> 7;
+                    x ^= x << 17;
+                    s + amount * ((x as f64 / u64::MAX as f64) * 2.0 - 1.0) as f32
+                })
+                .collect()
+        };
+
+        for_each_algorithm(|name, detector| {
+            let clarity: Vec<f32> = [0.0, 0.3, 0.6]
+                .iter()
+                .map(|&amount| {
+                    detector
+                        .detect_near(&noisy(amount), 220.0)
+                        .expect("Should detect pitch")
+                        .clarity
+                })
+                .collect();
+            assert!(
+                clarity[0] > clarity[1] && clarity[1] > clarity[2],
+                "{}: {:?}",
+                name,
+                clarity
+            );
+        });
+    }
+
+    #[test]
+    fn test_mpm_min_clarity() {
+        // A sine buried in noise has a weak NSDF peak
+        let source = TestAudioSource::sine(220.0, 0.2, SAMPLE_RATE);
+        let mut x = 12345_u64;
+        let noisy: Vec<f32> = source
+            .samples()
+            .iter()
+            .map(|s| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                s + ((x as f64 / u64::MAX as f64) * 2.0 - 1.0) as f32
+            })
+            .collect();
+
+        let loose = MpmDetector::new(SAMPLE_RATE).with_min_clarity(0.3);
+        let strict = MpmDetector::new(SAMPLE_RATE).with_min_clarity(0.9);
+
+        let result = loose.detect(&noisy).expect("Should detect pitch");
+        assert!(
+            (result.frequency - 220.0).abs() < 2.0,
+            "{}",
+            result.frequency
+        );
+        assert!(strict.detect(&noisy).is_none());
+    }
+
+    #[test]
+    fn test_method_selects_detector() {
+        let source = TestAudioSource::sine(440.0, 0.2, SAMPLE_RATE);
+        for method in [PitchMethod::Yin, PitchMethod::Mpm] {
+            let detector = method.detector(SAMPLE_RATE);
+            let result = detector
+                .detect(source.samples())
+                .expect("Should detect pitch");
+            assert!((result.frequency - 440.0).abs() < 0.5, "{:?}", method);
         }
     }
 }
@@ -0,0 +1,166 @@
+//! Recording of captured audio to a sink.
+//!
+//! The capture callback mustn't wait on the disk, so samples are handed to
+//! a writer thread over a bounded queue. If the writer falls behind, the
+//! queue fills and further samples are dropped and counted instead.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use super::traits::AudioSink;
+
+/// Sending end of a recording, for the capture callback.
+#[derive(Clone)]
+pub struct RecordTap {
+    sender: SyncSender<Vec<f32>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl RecordTap {
+    /// Queue samples for writing, without blocking. Samples that don't fit
+    /// in the queue are dropped.
+    pub fn send(&self, samples: &[f32]) {
+        // An empty chunk tells the writer to stop
+        if samples.is_empty() {
+            return;
+        }
+        if self.sender.try_send(samples.to_vec()).is_err() {
+            self.dropped
+                .fetch_add(samples.len() as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Writes audio sent through its taps to a sink on a separate thread.
+pub struct Recorder<S: AudioSink + Send + 'static> {
+    tap: RecordTap,
+    thread: JoinHandle<S>,
+}
+
+impl<S: AudioSink + Send + 'static> Recorder<S> {
+    /// Default number of chunks queued before samples are dropped; about
+    /// ten seconds of typical capture callbacks.
+    pub const DEFAULT_QUEUE: usize = 1024;
+
+    /// Start writing to `sink`.
+    pub fn start(sink: S) -> Self {
+        Self::with_queue(sink, Self::DEFAULT_QUEUE)
+    }
+
+    /// Start writing to `sink`, queueing up to `chunks` chunks.
+    pub fn with_queue(mut sink: S, chunks: usize) -> Self {
+        let (sender, receiver): (_, Receiver<Vec<f32>>) = mpsc::sync_channel(chunks.max(1));
+        let thread = std::thread::spawn(move || {
+            for chunk in receiver {
+                if chunk.is_empty() {
+                    break;
+                }
+                sink.write_samples(&chunk);
+            }
+            sink
+        });
+        Self {
+            tap: RecordTap {
+                sender,
+                dropped: Arc::new(AtomicU64::new(0)),
+            },
+            thread,
+        }
+    }
+
+    /// Get a tap to send samples to the recording through.
+    pub fn tap(&self) -> RecordTap {
+        self.tap.clone()
+    }
+
+    /// Samples dropped so far because the writer fell behind.
+    pub fn dropped(&self) -> u64 {
+        self.tap.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Write the samples queued so far and get the sink back. Samples sent
+    /// through remaining taps afterwards are dropped.
+    pub fn stop(self) -> S {
+        // Queued behind the samples already sent, so they are written first
+        let _ = self.tap.sender.send(Vec::new());
+        self.thread
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::traits::{AudioSource, TestAudioSink, TestAudioSource};
+
+    #[test]
+    fn test_tee_writes_identical_samples() {
+        let mut source = TestAudioSource::sine(440.0, 0.5, 44100);
+        let recorder = Recorder::start(TestAudioSink::new(44100));
+        let tap = recorder.tap();
+
+        let mut buffer = [0.0; 441];
+        loop {
+            let read = source.read_samples(&mut buffer);
+            if read == 0 {
+                break;
+            }
+            tap.send(&buffer[..read]);
+        }
+
+        assert_eq!(recorder.dropped(), 0);
+        let sink = recorder.stop();
+        let written: Vec<u32> = sink.samples().iter().map(|s| s.to_bits()).collect();
+        let expected: Vec<u32> = source.samples().iter().map(|s| s.to_bits()).collect();
+        assert_eq!(written, expected);
+
+        // Sending after the recording stopped is harmless
+        tap.send(&[0.5]);
+    }
+
+    /// A sink that waits until told to go on before writing anything.
+    struct Stalled {
+        go: Receiver<()>,
+        written: usize,
+    }
+
+    impl AudioSink for Stalled {
+        fn write_samples(&mut self, samples: &[f32]) {
+            let _ = self.go.recv();
+            self.written += samples.len();
+        }
+
+        fn sample_rate(&self) -> u32 {
+            44100
+        }
+    }
+
+    #[test]
+    fn test_slow_sink_drops_instead_of_blocking() {
+        let (go, wait) = mpsc::channel();
+        let recorder = Recorder::with_queue(
+            Stalled {
+                go: wait,
+                written: 0,
+            },
+            2,
+        );
+        let tap = recorder.tap();
+
+        // At most one chunk being written and two queued; the rest dropped
+        for _ in 0..10 {
+            tap.send(&[0.0; 100]);
+        }
+        let dropped = recorder.dropped();
+        assert!(dropped == 700 || dropped == 800, "Dropped {}", dropped);
+
+        for _ in 0..10 {
+            go.send(()).unwrap();
+        }
+        let sink = recorder.stop();
+        assert_eq!(sink.written as u64 + dropped, 1000);
+    }
+}
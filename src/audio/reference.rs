@@ -1,35 +1,1549 @@
 //! Reference tone generation.
 
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
 use super::traits::AudioSink;
 
-/// Reference tone generator for pure sine waves.
+/// How detection handles the reference tone leaking into the microphone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReferenceFeedback {
+    /// Pause detection while the tone plays.
+    #[default]
+    Pause,
+    /// Notch the tone's frequency out of the input before detection.
+    Filter,
+    /// Subtract the tone, fitted in level and phase, from the input
+    /// before detection; keeps a string close to the tone.
+    Cancel,
+}
+
+/// Where the reference tone is played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReferenceChannels {
+    /// The target in both ears.
+    #[default]
+    Mono,
+    /// The target in the left ear and the detected pitch in the right.
+    ///
+    /// Meant for headphones, so detection keeps running while it plays.
+    Split,
+}
+
+/// Shape of the reference tone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Waveform {
+    /// A pure tone: nothing but the fundamental.
+    #[default]
+    Sine,
+    /// Odd harmonics falling off steeply; a soft, hollow tone.
+    Triangle,
+    /// Every harmonic, falling off slowly; bright and easy to hear over
+    /// a piano.
+    Sawtooth,
+    /// A few harmonics falling off like a struck string, to compare with
+    /// the piano by ear.
+    PianoLike,
+}
+
+/// Relative amplitudes of the [`Waveform::PianoLike`] harmonics.
+const PIANO_HARMONICS: [f32; 6] = [1.0, 0.6, 0.4, 0.25, 0.15, 0.1];
+
+/// Peak of the [`PIANO_HARMONICS`] summed in sine phase, which they are
+/// scaled down by.
+const PIANO_PEAK: f32 = 1.72;
+
+impl Waveform {
+    /// All waveforms, in the order they are cycled through.
+    pub const ALL: [Waveform; 4] = [
+        Waveform::Sine,
+        Waveform::Triangle,
+        Waveform::Sawtooth,
+        Waveform::PianoLike,
+    ];
+
+    /// Most harmonics generated. Triangle and sawtooth waves are cut off
+    /// here, or below Nyquist, rather than aliasing.
+    pub const MAX_HARMONICS: u32 = 16;
+
+    /// The waveform after this one, wrapping around.
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&w| w == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Key of the waveform's name in the string table.
+    pub fn label_key(self) -> &'static str {
+        match self {
+            Waveform::Sine => "waveform.sine",
+            Waveform::Triangle => "waveform.triangle",
+            Waveform::Sawtooth => "waveform.sawtooth",
+            Waveform::PianoLike => "waveform.piano_like",
+        }
+    }
+
+    /// Highest harmonic generated.
+    fn harmonics(self) -> u32 {
+        match self {
+            Waveform::Sine => 1,
+            Waveform::Triangle | Waveform::Sawtooth => Self::MAX_HARMONICS,
+            Waveform::PianoLike => PIANO_HARMONICS.len() as u32,
+        }
+    }
+
+    /// Amplitude of harmonic `k`, where 1 is the fundamental, relative to
+    /// a sine of peak 1. Negative amplitudes are inverted in phase.
+    fn amplitude(self, k: u32) -> f32 {
+        use std::f32::consts::PI;
+        match self {
+            Waveform::Sine if k == 1 => 1.0,
+            Waveform::Sine => 0.0,
+            Waveform::Triangle if k.is_multiple_of(2) => 0.0,
+            Waveform::Triangle => {
+                let sign = if k % 4 == 1 { 1.0 } else { -1.0 };
+                sign * 8.0 / (PI * PI * (k * k) as f32)
+            }
+            Waveform::Sawtooth => {
+                let sign = if k % 2 == 1 { 1.0 } else { -1.0 };
+                sign * 2.0 / (PI * k as f32)
+            }
+            Waveform::PianoLike => PIANO_HARMONICS
+                .get(k as usize - 1)
+                .map_or(0.0, |a| a / PIANO_PEAK),
+        }
+    }
+}
+
+/// Relative amplitudes of the partials of a reinforced bass note,
+/// fundamental first. The 2nd to 5th carry the note on small speakers that
+/// can't reproduce the fundamental; the ear fills it back in.
+const BASS_PARTIALS: [f32; 5] = [0.35, 1.0, 0.8, 0.6, 0.4];
+
+/// Peak of the [`BASS_PARTIALS`] summed in sine phase, which they are
+/// scaled down by.
+const BASS_PEAK: f32 = 2.62;
+
+/// Limit `harmonics` of a tone at `frequency` to those below Nyquist.
+fn below_nyquist(harmonics: u32, frequency: f32, sample_rate: u32) -> u32 {
+    let nyquist = sample_rate as f32 / 2.0;
+    let audible = (nyquist / frequency.max(1.0)).ceil() as u32 - 1;
+    harmonics.min(audible.max(1))
+}
+
+/// Reference tone generator.
+///
+/// Keeps its phase from one block to the next, so a tone rendered in
+/// blocks is continuous even if the waveform changes between them. It
+/// fades in from silence when it starts and back to silence when stopped,
+/// with raised-cosine ramps, so neither clicks.
+///
+/// Output is pulled a buffer at a time with [`fill`](Self::fill), at a
+/// frequency that glides to wherever [`set_frequency`](Self::set_frequency)
+/// last put it; [`render`](Self::render) plays a given frequency instead.
+#[derive(Debug, Clone)]
 pub struct ReferenceTone {
     sample_rate: u32,
+    waveform: Waveform,
+    /// Whether low notes get their upper partials reinforced.
+    bass_reinforcement: bool,
+    /// Phase of the fundamental in radians.
+    phase: f64,
+    /// Length of the fade in and out.
+    fade: Duration,
+    /// Progress through the fade, from 0 (silent) to 1 (full level).
+    level: f32,
+    /// Whether fading in (or playing) rather than out.
+    sounding: bool,
+    /// Volume from 0 to 1.
+    volume: f32,
+    /// Gain on its way to the volume.
+    gain: f32,
+    /// Detuning in cents of a second voice beating against the first, if
+    /// any.
+    detune: Option<f32>,
+    /// Phase of the second voice's fundamental in radians.
+    detuned_phase: f64,
+    /// Frequency [`fill`](Self::fill) plays, on its way to the target.
+    frequency: Option<f32>,
+    /// Frequency the glide is heading for.
+    target: f32,
+    /// Time constant of the glide.
+    glide: Duration,
 }
 
 impl ReferenceTone {
-    /// Create a new reference tone generator.
+    /// Notes below this frequency in Hz are reinforced with their partials.
+    pub const BASS_BELOW: f32 = 100.0;
+
+    /// Notes at or below this frequency in Hz follow the bass recipe
+    /// entirely; above it the recipe blends into the waveform.
+    pub const BASS_FULL: f32 = 55.0;
+
+    /// Default length of the fade in and out.
+    pub const DEFAULT_FADE: Duration = Duration::from_millis(20);
+
+    /// Time a change of volume is spread over, so it doesn't crackle.
+    pub const VOLUME_RAMP: Duration = Duration::from_millis(5);
+
+    /// Default time constant of the glide from one frequency to the next.
+    pub const DEFAULT_GLIDE: Duration = Duration::from_millis(30);
+
+    /// Create a new reference tone generator for sine waves, fading in
+    /// from its first sample.
     pub fn new(sample_rate: u32) -> Self {
-        Self { sample_rate }
+        Self {
+            sample_rate,
+            waveform: Waveform::default(),
+            bass_reinforcement: false,
+            phase: 0.0,
+            fade: Self::DEFAULT_FADE,
+            level: 0.0,
+            sounding: true,
+            volume: 1.0,
+            gain: 1.0,
+            detune: None,
+            detuned_phase: 0.0,
+            frequency: None,
+            target: 0.0,
+            glide: Self::DEFAULT_GLIDE,
+        }
     }
 
-    /// Generate a sine wave at the given frequency.
-    pub fn generate(&self, frequency: f32, duration_secs: f32) -> Vec<f32> {
-        let num_samples = (self.sample_rate as f32 * duration_secs) as usize;
-        let mut samples = Vec::with_capacity(num_samples);
+    /// Play `frequency` from the start when filling buffers.
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = Some(frequency);
+        self.target = frequency;
+        self
+    }
+
+    /// Glide with time constant `glide` instead of the default; zero jumps
+    /// straight to each new frequency.
+    pub fn with_glide(mut self, glide: Duration) -> Self {
+        self.glide = glide;
+        self
+    }
+
+    /// Glide to `frequency` when filling buffers. Without a frequency yet,
+    /// the tone starts there.
+    pub fn set_frequency(&mut self, frequency: f32) {
+        self.target = frequency;
+        self.frequency.get_or_insert(frequency);
+    }
+
+    /// The frequency being played when filling buffers, if set.
+    pub fn frequency(&self) -> Option<f32> {
+        self.frequency
+    }
 
-        for i in 0..num_samples {
-            let t = i as f32 / self.sample_rate as f32;
-            let sample = (2.0 * std::f32::consts::PI * frequency * t).sin();
-            samples.push(sample);
+    /// Fill `buffer` with the next samples, carrying on from the previous
+    /// call, gliding towards the frequency last set. Silent until a
+    /// frequency is set.
+    pub fn fill(&mut self, buffer: &mut [f32]) {
+        for sample in buffer {
+            *sample = self.next_glided();
         }
+    }
+
+    /// The next sample on the glide towards the frequency last set, or
+    /// silence without one.
+    fn next_glided(&mut self) -> f32 {
+        let Some(frequency) = self.frequency else {
+            return 0.0;
+        };
+        // Fraction of the way to the target covered per sample, in octaves
+        let frames = self.glide.as_secs_f32() * self.sample_rate as f32;
+        let step = if frames < 1.0 {
+            1.0
+        } else {
+            1.0 - (-1.0 / frames).exp()
+        };
+        let frequency = frequency * (self.target / frequency).powf(step);
+        self.frequency = Some(frequency);
+        self.next_sample(frequency)
+    }
+
+    /// Play a second voice `cents` away from the first with it, at the same
+    /// level, so the two beat like a string slightly out of unison.
+    pub fn with_detuned_pair(mut self, cents: f32) -> Self {
+        self.set_detuned_pair(Some(cents));
+        self
+    }
+
+    /// Add a second voice `cents` away from the first from the next sample
+    /// on, change its detuning, or drop it with `None`. It starts in phase
+    /// with the first, so adding it doesn't click.
+    pub fn set_detuned_pair(&mut self, cents: Option<f32>) {
+        if self.detune.is_none() {
+            self.detuned_phase = self.phase;
+        }
+        self.detune = cents;
+    }
+
+    /// Detuning of the second voice in cents, if there is one.
+    pub fn detuned_pair(&self) -> Option<f32> {
+        self.detune
+    }
+
+    /// Play at `volume`, from 0 to 1, from the start.
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.volume = volume.clamp(0.0, 1.0);
+        self.gain = self.volume;
+        self
+    }
+
+    /// Change the volume, from 0 to 1, ramping to it over
+    /// [`Self::VOLUME_RAMP`].
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.clamp(0.0, 1.0);
+    }
+
+    /// The volume being played at, or ramped to.
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Fade in and out over `fade` instead of the default.
+    pub fn with_fade(mut self, fade: Duration) -> Self {
+        self.fade = fade;
+        self
+    }
+
+    /// Frames the fade in or out takes.
+    pub fn fade_frames(&self) -> usize {
+        (self.fade.as_secs_f32() * self.sample_rate as f32) as usize
+    }
+
+    /// Fade in, from wherever the level is.
+    pub fn start(&mut self) {
+        self.sounding = true;
+    }
+
+    /// Fade out, from wherever the level is.
+    pub fn stop(&mut self) {
+        self.sounding = false;
+    }
+
+    /// Whether the tone has faded out completely.
+    pub fn is_stopped(&self) -> bool {
+        !self.sounding && self.level == 0.0
+    }
+
+    /// Generate this waveform instead of a sine.
+    pub fn with_waveform(mut self, waveform: Waveform) -> Self {
+        self.waveform = waveform;
+        self
+    }
+
+    /// Reinforce notes below [`Self::BASS_BELOW`] with strong 2nd to 5th
+    /// partials and a weaker fundamental, so they are audible on small
+    /// speakers.
+    pub fn with_bass_reinforcement(mut self, enabled: bool) -> Self {
+        self.bass_reinforcement = enabled;
+        self
+    }
+
+    /// Switch waveform from the next sample on.
+    pub fn set_waveform(&mut self, waveform: Waveform) {
+        self.waveform = waveform;
+    }
+
+    /// The waveform being generated.
+    pub fn waveform(&self) -> Waveform {
+        self.waveform
+    }
+
+    /// How much of a tone at `frequency` follows the bass recipe, from 0
+    /// (none) to 1.
+    fn bass_mix(&self, frequency: f32) -> f32 {
+        if !self.bass_reinforcement {
+            return 0.0;
+        }
+        ((Self::BASS_BELOW - frequency) / (Self::BASS_BELOW - Self::BASS_FULL)).clamp(0.0, 1.0)
+    }
+
+    /// Amplitude of harmonic `k` of a tone at `frequency`, relative to a
+    /// sine of peak 1.
+    fn amplitude(&self, k: u32, frequency: f32) -> f32 {
+        let bass = BASS_PARTIALS
+            .get(k as usize - 1)
+            .map_or(0.0, |a| a / BASS_PEAK);
+        let mix = self.bass_mix(frequency);
+        (1.0 - mix) * self.waveform.amplitude(k) + mix * bass
+    }
+
+    /// Highest harmonic generated for a tone at `frequency`.
+    fn harmonics(&self, frequency: f32) -> u32 {
+        let mut harmonics = self.waveform.harmonics();
+        if self.bass_mix(frequency) > 0.0 {
+            harmonics = harmonics.max(BASS_PARTIALS.len() as u32);
+        }
+        below_nyquist(harmonics, frequency, self.sample_rate)
+    }
 
+    /// Harmonics generated for a tone at `frequency`, fundamental first;
+    /// what filtering the reference out of the input has to notch.
+    pub fn harmonic_numbers(&self, frequency: f32) -> Vec<u32> {
+        (1..=self.harmonics(frequency))
+            .filter(|&k| self.amplitude(k, frequency) != 0.0)
+            .collect()
+    }
+
+    /// Value of a tone at `frequency`, `phase` radians into its cycle,
+    /// with a peak of about 1.
+    fn sample_at(&self, phase: f64, frequency: f32) -> f32 {
+        if self.waveform == Waveform::Sine && self.bass_mix(frequency) == 0.0 {
+            return phase.sin() as f32;
+        }
+        (1..=self.harmonics(frequency))
+            .map(|k| self.amplitude(k, frequency) * (k as f64 * phase).sin() as f32)
+            .sum()
+    }
+
+    /// Gain of the fade in or out at this point, from 0 to 1.
+    fn envelope(&self) -> f32 {
+        0.5 - 0.5 * (std::f32::consts::PI * self.level).cos()
+    }
+
+    /// The next sample of a tone at `frequency`, carrying on from the
+    /// previous one.
+    pub fn next_sample(&mut self, frequency: f32) -> f32 {
+        let tau = 2.0 * std::f64::consts::PI;
+        let mut value = self.sample_at(self.phase, frequency);
+        self.phase = (self.phase + tau * frequency as f64 / self.sample_rate as f64) % tau;
+        if let Some(cents) = self.detune {
+            let detuned = frequency * 2.0_f32.powf(cents / 1200.0);
+            value = 0.5 * (value + self.sample_at(self.detuned_phase, detuned));
+            self.detuned_phase =
+                (self.detuned_phase + tau * detuned as f64 / self.sample_rate as f64) % tau;
+        }
+        let sample = self.gain * self.envelope() * value;
+
+        let ramp = Self::VOLUME_RAMP.as_secs_f32() * self.sample_rate as f32;
+        let max_change = 1.0 / ramp.max(1.0);
+        self.gain += (self.volume - self.gain).clamp(-max_change, max_change);
+
+        let step = 1.0 / self.fade_frames().max(1) as f32;
+        self.level = if self.sounding {
+            (self.level + step).min(1.0)
+        } else {
+            (self.level - step).max(0.0)
+        };
+        sample
+    }
+
+    /// Render the next `frames` samples of a tone at `frequency`,
+    /// carrying on from the previous block.
+    pub fn render(&mut self, frequency: f32, frames: usize) -> Vec<f32> {
+        (0..frames).map(|_| self.next_sample(frequency)).collect()
+    }
+
+    /// Generate a tone at the given frequency, starting from zero phase,
+    /// fading in at the start and out at the end.
+    pub fn generate(&self, frequency: f32, duration_secs: f32) -> Vec<f32> {
+        let num_samples = (self.sample_rate as f32 * duration_secs) as usize;
+        let mut tone = Self {
+            phase: 0.0,
+            detuned_phase: 0.0,
+            level: 0.0,
+            sounding: true,
+            ..self.clone()
+        };
+        let fade_out = self.fade_frames().min(num_samples);
+        let mut samples = tone.render(frequency, num_samples - fade_out);
+        tone.stop();
+        samples.extend(tone.render(frequency, fade_out));
         samples
     }
 
-    /// Play a reference tone through the given sink.
+    /// Play a reference tone through the given sink, on every channel.
     pub fn play<S: AudioSink>(&self, sink: &mut S, frequency: f32, duration_secs: f32) {
         let samples = self.generate(frequency, duration_secs);
+        write_all_channels(sink, &samples);
+    }
+}
+
+/// Write mono samples to every channel of a sink.
+fn write_all_channels<S: AudioSink>(sink: &mut S, samples: &[f32]) {
+    let channels = sink.channels() as usize;
+    if channels == 1 {
+        sink.write_samples(samples);
+    } else {
+        let interleaved: Vec<f32> = samples
+            .iter()
+            .flat_map(|&s| std::iter::repeat_n(s, channels))
+            .collect();
+        sink.write_samples(&interleaved);
+    }
+}
+
+/// Frequency and timing of the target of a tone being played.
+struct Schedule {
+    /// Frequency being played.
+    frequency: f32,
+    /// Frequency to move on to once the tone has faded out.
+    next: Option<f32>,
+    /// Frames rendered since the tone started or moved to a new note.
+    position: usize,
+    /// Total frames to play from then.
+    length: usize,
+    sample_rate: u32,
+}
+
+impl Schedule {
+    fn new(sample_rate: u32, frequency: f32, duration: Duration) -> Self {
+        Self {
+            frequency,
+            next: None,
+            position: 0,
+            length: (duration.as_secs_f32() * sample_rate as f32) as usize,
+            sample_rate,
+        }
+    }
+
+    /// Frames left to play.
+    fn remaining(&self) -> usize {
+        self.length - self.position.min(self.length)
+    }
+
+    /// Move on a frame: fade `tone` out in time for the end, or back in
+    /// at the next frequency once it has faded out for it.
+    fn advance(&mut self, tone: &mut ReferenceTone) {
+        if let Some(next) = self.next {
+            if tone.is_stopped() {
+                self.frequency = next;
+                self.next = None;
+                tone.start();
+            }
+        } else if self.remaining() <= tone.fade_frames() {
+            tone.stop();
+        }
+        self.position += 1;
+    }
+
+    /// Play `frequency` for `duration` instead, fading out of the current
+    /// note and into the new one. The same note just plays on.
+    fn retarget(&mut self, tone: &mut ReferenceTone, frequency: f32, duration: Duration) {
+        let frames = (duration.as_secs_f32() * self.sample_rate as f32) as usize;
+        self.position = 0;
+        if frequency == self.next.unwrap_or(self.frequency) {
+            self.length = frames;
+            tone.start();
+        } else {
+            self.next = Some(frequency);
+            self.length = tone.fade_frames().saturating_add(frames);
+            tone.stop();
+        }
+    }
+
+    /// Fade out and end early.
+    fn stop(&mut self, tone: &mut ReferenceTone) {
+        self.next = None;
+        self.length = self.length.min(self.position + tone.fade_frames());
+        tone.stop();
+    }
+}
+
+/// A second note sounding with the target.
+struct Lower {
+    tone: ReferenceTone,
+    schedule: Schedule,
+    /// Amplitude relative to the target.
+    amplitude: f32,
+}
+
+/// The target in both ears, rendered in blocks so the waveform and the
+/// note can change while it plays.
+///
+/// A second note can sound with it, e.g. the octave below for checking
+/// the octave by ear. The two are mixed down to the level of the target
+/// alone, following the second note's fade so neither jumps in level.
+pub struct MonoTone {
+    tone: ReferenceTone,
+    schedule: Schedule,
+    lower: Option<Lower>,
+}
+
+impl MonoTone {
+    /// Default amplitude of the octave below relative to the target; a
+    /// little softer, so the target stays on top.
+    pub const OCTAVE_AMPLITUDE: f32 = 0.7;
+
+    /// Create a tone at `frequency` lasting `duration`.
+    pub fn new(sample_rate: u32, frequency: f32, duration: Duration) -> Self {
+        Self {
+            tone: ReferenceTone::new(sample_rate),
+            schedule: Schedule::new(sample_rate, frequency, duration),
+            lower: None,
+        }
+    }
+
+    /// Play a second note at `frequency` with the target, at `amplitude`
+    /// relative to it, for as long as the target.
+    pub fn with_lower(mut self, frequency: f32, amplitude: f32) -> Self {
+        let frames = self.schedule.remaining() as f64;
+        let duration = Duration::from_secs_f64(frames / self.tone.sample_rate as f64);
+        self.set_lower(Some((frequency, amplitude)), duration);
+        self
+    }
+
+    /// Fade a second note, given as frequency and relative amplitude, in
+    /// or over to a new frequency for `duration`; `None` fades it out.
+    fn set_lower(&mut self, lower: Option<(f32, f32)>, duration: Duration) {
+        match (&mut self.lower, lower) {
+            (Some(playing), Some((frequency, amplitude))) => {
+                playing
+                    .schedule
+                    .retarget(&mut playing.tone, frequency, duration);
+                playing.amplitude = amplitude;
+            }
+            (None, Some((frequency, amplitude))) => {
+                self.lower = Some(Lower {
+                    tone: ReferenceTone {
+                        phase: 0.0,
+                        level: 0.0,
+                        sounding: true,
+                        detune: None,
+                        ..self.tone.clone()
+                    },
+                    schedule: Schedule::new(self.tone.sample_rate, frequency, duration),
+                    amplitude,
+                });
+            }
+            (Some(playing), None) => playing.schedule.stop(&mut playing.tone),
+            (None, None) => {}
+        }
+    }
+
+    /// Reinforce a low note with its partials; see
+    /// [`ReferenceTone::with_bass_reinforcement`].
+    pub fn with_bass_reinforcement(mut self, enabled: bool) -> Self {
+        self.tone = self.tone.with_bass_reinforcement(enabled);
+        self
+    }
+
+    /// Fade in and out over `fade`.
+    pub fn with_fade(mut self, fade: Duration) -> Self {
+        self.tone = self.tone.with_fade(fade);
+        self
+    }
+
+    /// Play at `volume`, from 0 to 1.
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.tone = self.tone.with_volume(volume);
+        self
+    }
+
+    /// Change the volume, from 0 to 1, ramping to it.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.tone.set_volume(volume);
+        if let Some(lower) = &mut self.lower {
+            lower.tone.set_volume(volume);
+        }
+    }
+
+    /// Switch waveform from the next block on.
+    pub fn set_waveform(&mut self, waveform: Waveform) {
+        self.tone.set_waveform(waveform);
+        if let Some(lower) = &mut self.lower {
+            lower.tone.set_waveform(waveform);
+        }
+    }
+
+    /// Beat the target against a second voice `cents` away, or stop with
+    /// `None`; see [`ReferenceTone::set_detuned_pair`].
+    pub fn set_detuned_pair(&mut self, cents: Option<f32>) {
+        self.tone.set_detuned_pair(cents);
+    }
+
+    /// Fade over to `frequency` and play it for `duration`, alone.
+    pub fn retarget(&mut self, frequency: f32, duration: Duration) {
+        self.retarget_with_lower(frequency, None, duration);
+    }
+
+    /// Fade over to `frequency` and play it for `duration`, with a second
+    /// note given as frequency and relative amplitude, or alone.
+    pub fn retarget_with_lower(
+        &mut self,
+        frequency: f32,
+        lower: Option<(f32, f32)>,
+        duration: Duration,
+    ) {
+        self.schedule.retarget(&mut self.tone, frequency, duration);
+        self.set_lower(lower, duration);
+    }
+
+    /// Fade out and finish.
+    pub fn stop(&mut self) {
+        self.schedule.stop(&mut self.tone);
+        if let Some(lower) = &mut self.lower {
+            lower.schedule.stop(&mut lower.tone);
+        }
+    }
+
+    /// Whether the whole tone has been rendered.
+    pub fn is_finished(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Frames left to play of either note.
+    fn remaining(&self) -> usize {
+        let lower = self.lower.as_ref().map_or(0, |l| l.schedule.remaining());
+        self.schedule.remaining().max(lower)
+    }
+
+    /// Render up to `frames` frames.
+    pub fn render(&mut self, frames: usize) -> Vec<f32> {
+        let frames = frames.min(self.remaining());
+        let samples = (0..frames)
+            .map(|_| {
+                let mut sample = 0.0;
+                if self.schedule.remaining() > 0 {
+                    self.schedule.advance(&mut self.tone);
+                    sample = self.tone.next_sample(self.schedule.frequency);
+                }
+                let Some(lower) = &mut self.lower else {
+                    return SplitTone::AMPLITUDE * sample;
+                };
+                // Scale the mix by the second note's faded-in share of it
+                let share = lower.amplitude * lower.tone.envelope();
+                if lower.schedule.remaining() > 0 {
+                    lower.schedule.advance(&mut lower.tone);
+                    sample += lower.amplitude * lower.tone.next_sample(lower.schedule.frequency);
+                }
+                SplitTone::AMPLITUDE * sample / (1.0 + share)
+            })
+            .collect();
+        if self
+            .lower
+            .as_ref()
+            .is_some_and(|l| l.schedule.remaining() == 0)
+        {
+            self.lower = None;
+        }
+        samples
+    }
+
+    /// Render up to `frames` frames into a sink, on every channel.
+    pub fn write<S: AudioSink>(&mut self, sink: &mut S, frames: usize) {
+        let samples = self.render(frames);
+        write_all_channels(sink, &samples);
+    }
+}
+
+/// A reference tone being rendered ahead of playback.
+pub enum PlayingTone {
+    /// The target in both ears.
+    Mono(MonoTone),
+    /// The target against the detected pitch.
+    Split(SplitTone),
+}
+
+impl PlayingTone {
+    /// Start a tone at `target` lasting `duration`, played on `channels`.
+    pub fn new(
+        channels: ReferenceChannels,
+        sample_rate: u32,
+        target: f32,
+        duration: Duration,
+    ) -> Self {
+        match channels {
+            ReferenceChannels::Mono => Self::Mono(MonoTone::new(sample_rate, target, duration)),
+            ReferenceChannels::Split => Self::Split(SplitTone::new(sample_rate, target, duration)),
+        }
+    }
+
+    /// Reinforce low notes with their partials; see
+    /// [`ReferenceTone::with_bass_reinforcement`].
+    pub fn with_bass_reinforcement(self, enabled: bool) -> Self {
+        match self {
+            Self::Mono(tone) => Self::Mono(tone.with_bass_reinforcement(enabled)),
+            Self::Split(tone) => Self::Split(tone.with_bass_reinforcement(enabled)),
+        }
+    }
+
+    /// Play a second note, given as frequency and relative amplitude, with
+    /// the target. A split tone plays the target alone.
+    pub fn with_lower(self, lower: Option<(f32, f32)>) -> Self {
+        match (self, lower) {
+            (Self::Mono(tone), Some((frequency, amplitude))) => {
+                Self::Mono(tone.with_lower(frequency, amplitude))
+            }
+            (tone, _) => tone,
+        }
+    }
+
+    /// Fade in and out over `fade`.
+    pub fn with_fade(self, fade: Duration) -> Self {
+        match self {
+            Self::Mono(tone) => Self::Mono(tone.with_fade(fade)),
+            Self::Split(tone) => Self::Split(tone.with_fade(fade)),
+        }
+    }
+
+    /// Play at `volume`, from 0 to 1.
+    pub fn with_volume(self, volume: f32) -> Self {
+        match self {
+            Self::Mono(tone) => Self::Mono(tone.with_volume(volume)),
+            Self::Split(tone) => Self::Split(tone.with_volume(volume)),
+        }
+    }
+
+    /// Change the volume, from 0 to 1, ramping to it.
+    pub fn set_volume(&mut self, volume: f32) {
+        match self {
+            Self::Mono(tone) => tone.set_volume(volume),
+            Self::Split(tone) => tone.set_volume(volume),
+        }
+    }
+
+    /// Switch waveform from the next block on.
+    pub fn set_waveform(&mut self, waveform: Waveform) {
+        match self {
+            Self::Mono(tone) => tone.set_waveform(waveform),
+            Self::Split(tone) => tone.set_waveform(waveform),
+        }
+    }
+
+    /// Beat the target against a second voice `cents` away, or stop with
+    /// `None`; see [`ReferenceTone::set_detuned_pair`].
+    pub fn set_detuned_pair(&mut self, cents: Option<f32>) {
+        match self {
+            Self::Mono(tone) => tone.set_detuned_pair(cents),
+            Self::Split(tone) => tone.set_detuned_pair(cents),
+        }
+    }
+
+    /// Fade over to `target` and play it for `duration`, with a second
+    /// note given as frequency and relative amplitude, or alone. A split
+    /// tone plays the target alone.
+    pub fn retarget(&mut self, target: f32, lower: Option<(f32, f32)>, duration: Duration) {
+        match self {
+            Self::Mono(tone) => tone.retarget_with_lower(target, lower, duration),
+            Self::Split(tone) => tone.retarget(target, duration),
+        }
+    }
+
+    /// Fade out and finish.
+    pub fn stop(&mut self) {
+        match self {
+            Self::Mono(tone) => tone.stop(),
+            Self::Split(tone) => tone.stop(),
+        }
+    }
+
+    /// Set the detected frequency a split tone follows.
+    pub fn set_detected(&mut self, frequency: Option<f32>) {
+        if let Self::Split(tone) = self {
+            tone.set_detected(frequency);
+        }
+    }
+
+    /// Whether the whole tone has been rendered.
+    pub fn is_finished(&self) -> bool {
+        match self {
+            Self::Mono(tone) => tone.is_finished(),
+            Self::Split(tone) => tone.is_finished(),
+        }
+    }
+
+    /// Render up to `frames` frames into a sink.
+    pub fn write<S: AudioSink>(&mut self, sink: &mut S, frames: usize) {
+        match self {
+            Self::Mono(tone) => tone.write(sink, frames),
+            Self::Split(tone) => tone.write(sink, frames),
+        }
+    }
+}
+
+/// Split reference: the target in the left channel and an oscillator
+/// following the detected pitch in the right, so the beating between them
+/// localizes in the head.
+///
+/// Rendered in blocks so the right channel can follow detection as it plays.
+pub struct SplitTone {
+    sample_rate: u32,
+    /// Target of the left channel, and how long it plays.
+    schedule: Schedule,
+    /// Latest detected frequency.
+    detected: Option<f32>,
+    /// Smoothed gain of the right channel (0.0 to 1.0).
+    right_gain: f32,
+    left: ReferenceTone,
+    right: ReferenceTone,
+}
+
+impl SplitTone {
+    /// Peak amplitude of each channel.
+    pub const AMPLITUDE: f32 = 0.3;
+
+    /// Time constant with which the right channel follows detection.
+    pub const SMOOTHING: Duration = Duration::from_millis(30);
+
+    /// Create a split tone for `target` lasting `duration`.
+    pub fn new(sample_rate: u32, target: f32, duration: Duration) -> Self {
+        Self {
+            sample_rate,
+            schedule: Schedule::new(sample_rate, target, duration),
+            detected: None,
+            right_gain: 0.0,
+            left: ReferenceTone::new(sample_rate),
+            right: ReferenceTone::new(sample_rate).with_glide(Self::SMOOTHING),
+        }
+    }
+
+    /// Reinforce low notes with their partials, on both channels; see
+    /// [`ReferenceTone::with_bass_reinforcement`].
+    pub fn with_bass_reinforcement(mut self, enabled: bool) -> Self {
+        self.left = self.left.with_bass_reinforcement(enabled);
+        self.right = self.right.with_bass_reinforcement(enabled);
+        self
+    }
+
+    /// Fade in and out over `fade`.
+    pub fn with_fade(mut self, fade: Duration) -> Self {
+        self.left = self.left.with_fade(fade);
+        self.right = self.right.with_fade(fade);
+        self
+    }
+
+    /// Play both channels at `volume`, from 0 to 1.
+    pub fn with_volume(mut self, volume: f32) -> Self {
+        self.left = self.left.with_volume(volume);
+        self.right = self.right.with_volume(volume);
+        self
+    }
+
+    /// Change the volume of both channels, from 0 to 1, ramping to it.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.left.set_volume(volume);
+        self.right.set_volume(volume);
+    }
+
+    /// Switch waveform, on both channels, from the next block on.
+    pub fn set_waveform(&mut self, waveform: Waveform) {
+        self.left.set_waveform(waveform);
+        self.right.set_waveform(waveform);
+    }
+
+    /// Beat the target in the left channel against a second voice `cents`
+    /// away, or stop with `None`; see [`ReferenceTone::set_detuned_pair`].
+    pub fn set_detuned_pair(&mut self, cents: Option<f32>) {
+        self.left.set_detuned_pair(cents);
+    }
+
+    /// Set the detected frequency the right channel follows.
+    pub fn set_detected(&mut self, frequency: Option<f32>) {
+        self.detected = frequency;
+        if let Some(frequency) = frequency {
+            self.right.set_frequency(frequency);
+        }
+    }
+
+    /// Fade the left channel over to `target` and play it for `duration`.
+    pub fn retarget(&mut self, target: f32, duration: Duration) {
+        self.schedule.retarget(&mut self.left, target, duration);
+        self.right.start();
+    }
+
+    /// Fade out and finish.
+    pub fn stop(&mut self) {
+        self.schedule.stop(&mut self.left);
+        self.right.stop();
+    }
+
+    /// Whether the whole tone has been rendered.
+    pub fn is_finished(&self) -> bool {
+        self.schedule.remaining() == 0
+    }
+
+    /// Render up to `frames` frames as interleaved left/right samples.
+    pub fn render(&mut self, frames: usize) -> Vec<f32> {
+        let sample_rate = self.sample_rate as f32;
+        let smoothing = 1.0 - (-1.0 / (Self::SMOOTHING.as_secs_f32() * sample_rate)).exp();
+
+        let frames = frames.min(self.schedule.remaining());
+        let mut out = Vec::with_capacity(frames * 2);
+        for _ in 0..frames {
+            // The right channel glides after the detected pitch; fade out
+            // when lost
+            if self.detected.is_some() {
+                self.right_gain += (1.0 - self.right_gain) * smoothing;
+            } else {
+                self.right_gain -= self.right_gain * smoothing;
+            }
+
+            // Both channels fade out together at the end
+            if self.schedule.next.is_none() && self.schedule.remaining() <= self.right.fade_frames()
+            {
+                self.right.stop();
+            }
+            self.schedule.advance(&mut self.left);
+            let left = self.left.next_sample(self.schedule.frequency);
+            // Silent until there is a detection to follow
+            let right = self.right.next_glided();
+            out.push(Self::AMPLITUDE * left);
+            out.push(Self::AMPLITUDE * self.right_gain * right);
+        }
+        out
+    }
+
+    /// Render up to `frames` frames into a sink. Mono sinks get the two
+    /// channels summed; extra channels stay silent.
+    pub fn write<S: AudioSink>(&mut self, sink: &mut S, frames: usize) {
+        let stereo = self.render(frames);
+        let channels = sink.channels() as usize;
+        let samples: Vec<f32> = match channels {
+            1 => stereo.chunks(2).map(|f| f[0] + f[1]).collect(),
+            2 => stereo,
+            _ => stereo
+                .chunks(2)
+                .flat_map(|f| {
+                    [f[0], f[1]]
+                        .into_iter()
+                        .chain(std::iter::repeat_n(0.0, channels - 2))
+                })
+                .collect(),
+        };
         sink.write_samples(&samples);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::beats::rms;
+    use crate::audio::pitch::PitchDetector;
+    use crate::audio::traits::TestAudioSink;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// Frames per rendered block (10 ms).
+    const BLOCK: usize = 441;
+
+    /// Render a 2 s split tone for A4, with the right channel following the
+    /// scripted detection for each 10 ms block.
+    fn render_scripted<S: AudioSink>(sink: &mut S, detection: impl Fn(usize) -> Option<f32>) {
+        let mut tone = SplitTone::new(SAMPLE_RATE, 440.0, Duration::from_secs(2));
+        let mut block = 0;
+        while !tone.is_finished() {
+            tone.set_detected(detection(block));
+            tone.write(sink, BLOCK);
+            block += 1;
+        }
+    }
+
+    /// Pitch of a 100 ms stretch starting at `start_secs`.
+    fn pitch_at(samples: &[f32], start_secs: f32) -> f32 {
+        let start = (start_secs * SAMPLE_RATE as f32) as usize;
+        PitchDetector::new(SAMPLE_RATE)
+            .detect(&samples[start..start + SAMPLE_RATE as usize / 10])
+            .expect("pitch should be detected")
+            .frequency
+    }
+
+    /// 442 Hz for the first second, then 446 Hz.
+    fn script(block: usize) -> Option<f32> {
+        Some(if block < 100 { 442.0 } else { 446.0 })
+    }
+
+    #[test]
+    fn test_split_tone_channels() {
+        let mut sink = TestAudioSink::new(SAMPLE_RATE).with_channels(2);
+        render_scripted(&mut sink, script);
+
+        let left = sink.channel(0);
+        let right = sink.channel(1);
+        assert_eq!(left.len(), 2 * SAMPLE_RATE as usize);
+
+        for start in [0.5, 1.5] {
+            let freq = pitch_at(&left, start);
+            assert!((freq - 440.0).abs() < 0.5, "left {}", freq);
+        }
+        let first = pitch_at(&right, 0.5);
+        let second = pitch_at(&right, 1.5);
+        assert!((first - 442.0).abs() < 0.5, "right {}", first);
+        assert!((second - 446.0).abs() < 0.5, "right {}", second);
+    }
+
+    #[test]
+    fn test_right_channel_fades_without_detection() {
+        let mut sink = TestAudioSink::new(SAMPLE_RATE).with_channels(2);
+        render_scripted(&mut sink, |block| (block < 50).then_some(442.0));
+
+        let right = sink.channel(1);
+        let start = SAMPLE_RATE as usize;
+        assert!(rms(&right[start..start + 4410]) < 1e-3);
+        assert!(rms(&sink.channel(0)[start..start + 4410]) > 0.1);
+    }
+
+    #[test]
+    fn test_mono_sink_gets_sum() {
+        let mut stereo = TestAudioSink::new(SAMPLE_RATE).with_channels(2);
+        let mut mono = TestAudioSink::new(SAMPLE_RATE);
+        render_scripted(&mut stereo, script);
+        render_scripted(&mut mono, script);
+
+        let (left, right) = (stereo.channel(0), stereo.channel(1));
+        assert_eq!(mono.samples().len(), left.len());
+        for i in (0..left.len()).step_by(997) {
+            assert!((mono.samples()[i] - (left[i] + right[i])).abs() < 1e-6);
+        }
+    }
+
+    /// Magnitude of the component at `frequency`, as a peak amplitude.
+    /// Exact when `samples` holds whole cycles of it.
+    fn magnitude(samples: &[f32], frequency: f32) -> f32 {
+        let (mut re, mut im) = (0.0f64, 0.0f64);
+        for (i, &s) in samples.iter().enumerate() {
+            let phase =
+                2.0 * std::f64::consts::PI * frequency as f64 * i as f64 / SAMPLE_RATE as f64;
+            re += s as f64 * phase.cos();
+            im += s as f64 * phase.sin();
+        }
+        (2.0 * (re * re + im * im).sqrt() / samples.len() as f64) as f32
+    }
+
+    #[test]
+    fn test_waveform_harmonics() {
+        // Relative levels of the first seven harmonics
+        let expected: [(Waveform, [f32; 7]); 4] = [
+            (Waveform::Sine, [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+            (
+                Waveform::Triangle,
+                [1.0, 0.0, 1.0 / 9.0, 0.0, 1.0 / 25.0, 0.0, 1.0 / 49.0],
+            ),
+            (
+                Waveform::Sawtooth,
+                [
+                    1.0,
+                    1.0 / 2.0,
+                    1.0 / 3.0,
+                    1.0 / 4.0,
+                    1.0 / 5.0,
+                    1.0 / 6.0,
+                    1.0 / 7.0,
+                ],
+            ),
+            (Waveform::PianoLike, [1.0, 0.6, 0.4, 0.25, 0.15, 0.1, 0.0]),
+        ];
+        for (waveform, levels) in expected {
+            let mut sink = TestAudioSink::new(SAMPLE_RATE);
+            ReferenceTone::new(SAMPLE_RATE)
+                .with_waveform(waveform)
+                .play(&mut sink, 220.0, 1.0);
+            let samples = sink.samples();
+            assert!(samples.iter().all(|s| s.abs() < 1.2), "{:?} peak", waveform);
+
+            let freq = PitchDetector::new(SAMPLE_RATE)
+                .detect(&samples[..SAMPLE_RATE as usize / 10])
+                .expect("pitch should be detected")
+                .frequency;
+            assert!((freq - 220.0).abs() < 0.5, "{:?} at {} Hz", waveform, freq);
+
+            let fundamental = magnitude(samples, 220.0);
+            for (k, level) in levels.iter().enumerate() {
+                let relative = magnitude(samples, 220.0 * (k + 1) as f32) / fundamental;
+                assert!(
+                    (relative - level).abs() < 0.02,
+                    "{:?} harmonic {}: {} rather than {}",
+                    waveform,
+                    k + 1,
+                    relative,
+                    level
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_waveforms_stop_below_nyquist() {
+        // Only five harmonics of C8 fit below 22.05 kHz; a sixth would
+        // alias down to 18984 Hz
+        let tone = ReferenceTone::new(SAMPLE_RATE)
+            .with_waveform(Waveform::Sawtooth)
+            .generate(4186.0, 1.0);
+        assert!(magnitude(&tone, 4186.0 * 5.0) > 0.1);
+        assert!(magnitude(&tone, 18984.0) < 1e-3);
+        let harmonics = |waveform, frequency| {
+            ReferenceTone::new(SAMPLE_RATE)
+                .with_waveform(waveform)
+                .harmonic_numbers(frequency)
+        };
+        assert_eq!(harmonics(Waveform::Sawtooth, 4186.0), [1, 2, 3, 4, 5]);
+        assert_eq!(harmonics(Waveform::Sine, 440.0), [1]);
+        assert_eq!(
+            harmonics(Waveform::Triangle, 440.0),
+            [1, 3, 5, 7, 9, 11, 13, 15]
+        );
+    }
+
+    #[test]
+    fn test_bass_reinforcement() {
+        let reinforced = ReferenceTone::new(SAMPLE_RATE).with_bass_reinforcement(true);
+
+        // A0 follows the recipe: the 2nd partial strongest, a weak
+        // fundamental, and nothing above the 5th
+        let mut sink = TestAudioSink::new(SAMPLE_RATE);
+        reinforced.play(&mut sink, 27.5, 2.0);
+        let samples = sink.samples();
+        assert!(samples.iter().all(|s| s.abs() <= 1.0));
+        let second = magnitude(samples, 55.0);
+        for (k, level) in BASS_PARTIALS.iter().enumerate() {
+            let relative = magnitude(samples, 27.5 * (k + 1) as f32) / second;
+            assert!(
+                (relative - level).abs() < 0.02,
+                "partial {}: {} rather than {}",
+                k + 1,
+                relative,
+                level
+            );
+        }
+        assert!(magnitude(samples, 27.5 * 6.0) / second < 0.01);
+        assert_eq!(reinforced.harmonic_numbers(27.5), [1, 2, 3, 4, 5]);
+
+        // A4 stays a pure sine
+        let a4 = reinforced.generate(440.0, 1.0);
+        let pure = ReferenceTone::new(SAMPLE_RATE).generate(440.0, 1.0);
+        assert_eq!(a4, pure);
+        assert!(magnitude(&a4, 880.0) / magnitude(&a4, 440.0) < 0.01);
+
+        // In between, the fundamental gives way gradually
+        let share = |frequency: f32| {
+            let tone = reinforced.generate(frequency, 1.0);
+            magnitude(&tone, frequency) / magnitude(&tone, 2.0 * frequency)
+        };
+        let (a1, e2, g2) = (share(55.0), share(82.0), share(98.0));
+        assert!(a1 < e2 && e2 < g2, "{} {} {}", a1, e2, g2);
+
+        // Off, A0 is a plain sine
+        let plain = ReferenceTone::new(SAMPLE_RATE).generate(27.5, 2.0);
+        assert!(magnitude(&plain, 55.0) / magnitude(&plain, 27.5) < 0.01);
+    }
+
+    #[test]
+    fn test_waveform_switch_keeps_phase() {
+        // Switching mid-tone carries on exactly where the new waveform
+        // would have been had it played from the start
+        let mut switched = ReferenceTone::new(SAMPLE_RATE);
+        switched.render(261.6, 1000);
+        switched.set_waveform(Waveform::PianoLike);
+        let after = switched.render(261.6, 1000);
+
+        let mut piano = ReferenceTone::new(SAMPLE_RATE).with_waveform(Waveform::PianoLike);
+        let whole = piano.render(261.6, 2000);
+        for (a, b) in after.iter().zip(&whole[1000..]) {
+            assert!((a - b).abs() < 1e-5);
+        }
+
+        let mut cycle = Waveform::Sine;
+        for _ in 0..Waveform::ALL.len() {
+            cycle = cycle.next();
+        }
+        assert_eq!(cycle, Waveform::Sine);
+    }
+
+    #[test]
+    fn test_mono_tone_switches_while_playing() {
+        let mut sink = TestAudioSink::new(SAMPLE_RATE).with_channels(2);
+        let mut tone = PlayingTone::new(
+            ReferenceChannels::Mono,
+            SAMPLE_RATE,
+            440.0,
+            Duration::from_secs(1),
+        );
+        let mut block = 0;
+        while !tone.is_finished() {
+            if block == 50 {
+                tone.set_waveform(Waveform::Sawtooth);
+            }
+            tone.write(&mut sink, BLOCK);
+            block += 1;
+        }
+
+        let left = sink.channel(0);
+        assert_eq!(left, sink.channel(1));
+        assert_eq!(left.len(), SAMPLE_RATE as usize);
+        // Sine for the first half second, sawtooth after
+        let quarter = SAMPLE_RATE as usize / 4;
+        let first = &left[quarter / 2..quarter / 2 + quarter];
+        let second = &left[2 * quarter + quarter / 2..3 * quarter + quarter / 2];
+        assert!(magnitude(first, 880.0) < 1e-3);
+        let ratio = magnitude(second, 880.0) / magnitude(second, 440.0);
+        assert!((ratio - 0.5).abs() < 0.02, "ratio {}", ratio);
+    }
+
+    #[test]
+    fn test_fill_is_continuous_across_odd_buffers() {
+        let sizes = [1, 7, 13, 64, 441, 3, 1000, 17];
+        let total = 3 * SAMPLE_RATE as usize / 2;
+
+        // An octave up half a second in, between buffers
+        let mut tone = ReferenceTone::new(SAMPLE_RATE).with_frequency(440.0);
+        let mut samples = Vec::new();
+        let mut switched = None;
+        for &size in sizes.iter().cycle() {
+            if samples.len() >= total {
+                break;
+            }
+            if switched.is_none() && samples.len() >= SAMPLE_RATE as usize / 2 {
+                tone.set_frequency(880.0);
+                switched = Some(samples.len());
+            }
+            let mut buffer = vec![0.0; size];
+            tone.fill(&mut buffer);
+            samples.extend(buffer);
+        }
+        assert!((tone.frequency().unwrap() - 880.0).abs() < 0.1);
+
+        // No step steeper than a full-scale sine at the highest frequency
+        let max_slope = 2.0 * std::f32::consts::PI * 880.0 / SAMPLE_RATE as f32;
+        for (i, pair) in samples.windows(2).enumerate() {
+            let step = (pair[1] - pair[0]).abs();
+            assert!(step <= 1.01 * max_slope, "Step of {} at {}", step, i);
+        }
+
+        // Exactly what two big buffers give
+        let switched = switched.unwrap();
+        let mut tone = ReferenceTone::new(SAMPLE_RATE).with_frequency(440.0);
+        let mut whole = vec![0.0; samples.len()];
+        tone.fill(&mut whole[..switched]);
+        tone.set_frequency(880.0);
+        // Gliding there rather than jumping
+        tone.fill(&mut whole[switched..switched + 100]);
+        assert!(tone.frequency().unwrap() < 500.0);
+        tone.fill(&mut whole[switched + 100..]);
+        assert_eq!(whole, samples);
+
+        // Nothing to play before a frequency is set
+        let mut silent = ReferenceTone::new(SAMPLE_RATE);
+        let mut buffer = [1.0; 64];
+        silent.fill(&mut buffer);
+        assert_eq!(buffer, [0.0; 64]);
+    }
+
+    #[test]
+    fn test_detuned_pair_beats_at_predicted_rate() {
+        let tone = ReferenceTone::new(SAMPLE_RATE).with_detuned_pair(5.0);
+        assert_eq!(tone.detuned_pair(), Some(5.0));
+        let mut sink = TestAudioSink::new(SAMPLE_RATE);
+        tone.play(&mut sink, 440.0, 8.0);
+
+        // Level over 50 ms blocks, a whole number of cycles of A4
+        let block = SAMPLE_RATE as usize / 20;
+        let envelope: Vec<f32> = sink.samples().chunks_exact(block).map(rms).collect();
+        let max = envelope.iter().fold(0.0f32, |m, &e| m.max(e));
+        let dips: Vec<usize> = (1..envelope.len() - 1)
+            .filter(|&i| {
+                envelope[i] < 0.25 * max
+                    && envelope[i] < envelope[i - 1]
+                    && envelope[i] <= envelope[i + 1]
+            })
+            .collect();
+        assert!(dips.len() >= 8, "Dips {:?}", dips);
+
+        let span = (dips[dips.len() - 1] - dips[0]) as f32 * 0.05;
+        let rate = (dips.len() - 1) as f32 / span;
+        let predicted = 440.0 * (2.0_f32.powf(5.0 / 1200.0) - 1.0);
+        assert!(
+            (rate - predicted).abs() < 0.1 * predicted,
+            "{} beats/s, predicted {}",
+            rate,
+            predicted
+        );
+    }
+
+    #[test]
+    fn test_octave_below_sounds_with_target() {
+        // A4 and a slightly stretched A3
+        let lower = 219.8;
+        let mut tone = MonoTone::new(SAMPLE_RATE, 440.0, Duration::from_secs(2))
+            .with_lower(lower, MonoTone::OCTAVE_AMPLITUDE);
+        let mut samples = Vec::new();
+        while !tone.is_finished() {
+            samples.extend(tone.render(BLOCK));
+        }
+        assert_eq!(samples.len(), 2 * SAMPLE_RATE as usize);
+
+        // Both fundamentals at their own amplitudes, mixed down so the pair
+        // is no louder than the target alone
+        let steady = &samples[SAMPLE_RATE as usize / 2..3 * SAMPLE_RATE as usize / 2];
+        let mix = SplitTone::AMPLITUDE / (1.0 + MonoTone::OCTAVE_AMPLITUDE);
+        assert!((magnitude(steady, 440.0) - mix).abs() < 0.005);
+        let expected = MonoTone::OCTAVE_AMPLITUDE * mix;
+        assert!((magnitude(steady, lower) - expected).abs() < 0.005);
+        assert!(magnitude(steady, 330.0) < 0.005);
+        let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        assert!(peak <= SplitTone::AMPLITUDE, "Peak {}", peak);
+
+        // Dropping the octave fades it out under the target, without
+        // clipping on the way, even for a bright waveform
+        let mut tone = PlayingTone::new(
+            ReferenceChannels::Mono,
+            SAMPLE_RATE,
+            440.0,
+            Duration::from_secs(2),
+        )
+        .with_lower(Some((lower, 1.0)));
+        tone.set_waveform(Waveform::Sawtooth);
+        let mut sink = TestAudioSink::new(SAMPLE_RATE);
+        tone.write(&mut sink, SAMPLE_RATE as usize / 2);
+        tone.retarget(440.0, None, Duration::from_secs(1));
+        while !tone.is_finished() {
+            tone.write(&mut sink, BLOCK);
+        }
+        let samples = sink.samples();
+        let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+        // A band-limited sawtooth overshoots a little, alone or not
+        assert!(peak <= 1.15 * SplitTone::AMPLITUDE, "Peak {}", peak);
+        let after = &samples[SAMPLE_RATE as usize..SAMPLE_RATE as usize * 5 / 4];
+        assert!(magnitude(after, lower) < 0.005);
+        assert!(magnitude(after, 440.0) > 0.1);
+    }
+
+    /// Peak level of each whole cycle of a 1 kHz tone.
+    fn cycle_peaks(samples: &[f32]) -> Vec<f32> {
+        samples
+            .chunks_exact(SAMPLE_RATE as usize / 1000)
+            .map(|cycle| cycle.iter().fold(0.0, |peak, s| s.abs().max(peak)))
+            .collect()
+    }
+
+    #[test]
+    fn test_tone_fades_in_and_out() {
+        let mut sink = TestAudioSink::new(SAMPLE_RATE);
+        ReferenceTone::new(SAMPLE_RATE)
+            .with_fade(Duration::from_millis(10))
+            .play(&mut sink, 1000.0, 0.5);
+        let samples = sink.samples();
+        assert_eq!(samples[0], 0.0);
+        assert!(samples.last().unwrap().abs() < 1e-3);
+
+        // Over the first and last 10 ms, each cycle louder or quieter
+        // than the one before
+        let fade = SAMPLE_RATE as usize / 100;
+        let rise = cycle_peaks(&samples[..fade]);
+        let fall = cycle_peaks(&samples[samples.len() - fade..]);
+        assert!(rise.windows(2).all(|w| w[1] > w[0]), "{:?}", rise);
+        assert!(fall.windows(2).all(|w| w[1] < w[0]), "{:?}", fall);
+        assert!(rise[0] < 0.05 && *fall.last().unwrap() < 0.05);
+        // Full level in between
+        let middle = cycle_peaks(&samples[fade..samples.len() - fade]);
+        assert!(middle.iter().all(|&p| p > 0.99));
+    }
+
+    #[test]
+    fn test_stopped_tone_fades_out() {
+        let mut sink = TestAudioSink::new(SAMPLE_RATE);
+        let mut tone = MonoTone::new(SAMPLE_RATE, 1000.0, Duration::from_secs(2));
+        tone.write(&mut sink, SAMPLE_RATE as usize / 10);
+        tone.stop();
+        let mut blocks = 0;
+        while !tone.is_finished() {
+            tone.write(&mut sink, BLOCK);
+            blocks += 1;
+        }
+
+        // Stopping takes the fade rather than the rest of the tone, and
+        // ramps down to silence
+        let fade = ReferenceTone::DEFAULT_FADE.as_secs_f32() * SAMPLE_RATE as f32;
+        assert_eq!(
+            sink.samples().len(),
+            SAMPLE_RATE as usize / 10 + fade as usize
+        );
+        assert_eq!(blocks, 2);
+        let tail = cycle_peaks(&sink.samples()[SAMPLE_RATE as usize / 10..]);
+        assert!(tail.windows(2).all(|w| w[1] < w[0]), "{:?}", tail);
+        assert!(*tail.last().unwrap() < 0.01);
+    }
+
+    #[test]
+    fn test_retarget_fades_between_notes() {
+        let mut sink = TestAudioSink::new(SAMPLE_RATE);
+        let mut tone = PlayingTone::new(
+            ReferenceChannels::Mono,
+            SAMPLE_RATE,
+            440.0,
+            Duration::from_secs(1),
+        );
+        tone.write(&mut sink, SAMPLE_RATE as usize / 2);
+        tone.retarget(466.16, None, Duration::from_secs(1));
+        while !tone.is_finished() {
+            tone.write(&mut sink, BLOCK);
+        }
+
+        // Faded out to silence and back in, rather than jumping
+        let samples = sink.samples();
+        let switch = SAMPLE_RATE as usize / 2;
+        let fade = (ReferenceTone::DEFAULT_FADE.as_secs_f32() * SAMPLE_RATE as f32) as usize;
+        let quietest = samples[switch..switch + 2 * fade]
+            .iter()
+            .fold(1.0f32, |min, s| s.abs().min(min));
+        assert!(quietest < 1e-3);
+        let jump = samples
+            .windows(2)
+            .map(|w| (w[1] - w[0]).abs())
+            .fold(0.0, f32::max);
+        let slope = SplitTone::AMPLITUDE * 2.0 * std::f32::consts::PI * 466.16 / SAMPLE_RATE as f32;
+        assert!(jump <= slope * 1.01, "jump {}", jump);
+
+        // The new note plays for its full length after the fade
+        assert_eq!(samples.len(), switch + fade + SAMPLE_RATE as usize);
+        assert!((pitch_at(samples, 0.2) - 440.0).abs() < 0.5);
+        assert!((pitch_at(samples, 1.0) - 466.16).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_volume_scales_output() {
+        let full = ReferenceTone::new(SAMPLE_RATE).generate(1000.0, 0.2);
+        for volume in [0.0, 0.25, 0.7] {
+            let scaled = ReferenceTone::new(SAMPLE_RATE)
+                .with_volume(volume)
+                .generate(1000.0, 0.2);
+            for (s, f) in scaled.iter().zip(&full) {
+                assert!((s - volume * f).abs() < 1e-6);
+            }
+        }
+        assert_eq!(
+            ReferenceTone::new(SAMPLE_RATE).with_volume(1.5).volume(),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_volume_change_ramps() {
+        let mut tone = ReferenceTone::new(SAMPLE_RATE);
+        tone.render(1000.0, SAMPLE_RATE as usize / 10);
+        tone.set_volume(0.2);
+        let after = tone.render(1000.0, SAMPLE_RATE as usize / 10);
+
+        // Down to the new level within the ramp, and no faster
+        let ramp = (ReferenceTone::VOLUME_RAMP.as_secs_f32() * SAMPLE_RATE as f32) as usize;
+        let peaks = cycle_peaks(&after);
+        let cycle = SAMPLE_RATE as usize / 1000;
+        assert!(peaks[0] > 0.8, "{:?}", &peaks[..6]);
+        assert!(peaks[..ramp / cycle].windows(2).all(|w| w[1] < w[0]));
+        for peak in &peaks[ramp / cycle + 1..] {
+            assert!((peak - 0.2).abs() < 0.01, "peak {}", peak);
+        }
+
+        // Never a jump beyond the tone's own slope
+        let slope = 2.0 * std::f32::consts::PI * 1000.0 / SAMPLE_RATE as f32;
+        let jump = after
+            .windows(2)
+            .map(|w| (w[1] - w[0]).abs())
+            .fold(0.0, f32::max);
+        assert!(jump <= slope * 1.01, "jump {}", jump);
+    }
+
+    #[test]
+    fn test_tone_plays_on_every_channel() {
+        let mut sink = TestAudioSink::new(SAMPLE_RATE).with_channels(2);
+        ReferenceTone::new(SAMPLE_RATE).play(&mut sink, 440.0, 0.1);
+        assert_eq!(sink.channel(0), sink.channel(1));
+        assert_eq!(sink.channel(0).len(), 4410);
+    }
+}
@@ -0,0 +1,268 @@
+//! Median filtering of pitch results.
+//!
+//! A single spurious frame (a harmonic, a cough, a creaking chair) would
+//! make the needle lurch; the median of the last few frames ignores it.
+//!
+//! Bass strings often have a stronger second partial than fundamental, so
+//! detection can jump an octave from frame to frame. Readings an obvious
+//! octave or two away from the recent ones, or from the target, are folded
+//! back before they reach the median.
+
+use std::collections::VecDeque;
+
+use super::pitch::PitchResult;
+
+/// Median of the pitch results over the last few frames.
+pub struct PitchSmoother {
+    /// Accepted results of the last frames; `None` where a frame had no
+    /// pitch or too low a confidence.
+    history: VecDeque<Option<PitchResult>>,
+    window: usize,
+    min_confidence: f32,
+    /// Frequency of the note being tuned, if known.
+    target: Option<f32>,
+}
+
+/// Cents within which a reading a whole number of octaves from the
+/// reference counts as an octave error.
+const OCTAVE_FOLD_CENTS: f32 = 50.0;
+
+/// Most octaves a reading is folded by.
+const MAX_FOLD_OCTAVES: i32 = 2;
+
+impl PitchSmoother {
+    /// Default number of frames the median is taken over.
+    pub const DEFAULT_WINDOW: usize = 5;
+
+    /// Default confidence below which results are dropped.
+    pub const DEFAULT_MIN_CONFIDENCE: f32 = 0.5;
+
+    /// Create a smoother over `window` frames; 1 passes results through.
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            history: VecDeque::with_capacity(window),
+            window,
+            min_confidence: Self::DEFAULT_MIN_CONFIDENCE,
+            target: None,
+        }
+    }
+
+    /// Set the confidence below which results are dropped.
+    pub fn with_min_confidence(mut self, confidence: f32) -> Self {
+        self.min_confidence = confidence;
+        self
+    }
+
+    /// Change the confidence below which results are dropped.
+    pub fn set_min_confidence(&mut self, confidence: f32) {
+        self.min_confidence = confidence;
+    }
+
+    /// Set the frequency of the note being tuned, or `None` if unknown.
+    /// Octave jumps are folded towards it ahead of the recent readings.
+    pub fn set_target(&mut self, target: Option<f32>) {
+        self.target = target;
+    }
+
+    /// Number of frames the median is taken over.
+    pub fn window(&self) -> usize {
+        self.window
+    }
+
+    /// Add a frame's result and get the smoothed one, or `None` if no
+    /// frame in the window had a confident pitch.
+    ///
+    /// The smoothed result has the median frequency and the confidence,
+    /// level and clarity of the latest accepted frame. A frame an octave
+    /// or two from the target, or from the recent frames without one, is
+    /// folded to their octave first.
+    pub fn push(&mut self, result: Option<PitchResult>) -> Option<PitchResult> {
+        let result = result
+            .filter(|r| r.confidence >= self.min_confidence)
+            .map(|r| self.fold_octave(r));
+        if self.history.len() == self.window {
+            self.history.pop_front();
+        }
+        self.history.push_back(result);
+
+        let latest = self.history.iter().rev().flatten().next().copied()?;
+        let median = self.median()?;
+
+        Some(PitchResult {
+            frequency: median,
+            tau: latest.tau * latest.frequency / median,
+            ..latest
+        })
+    }
+
+    /// Median frequency of the accepted frames in the window.
+    fn median(&self) -> Option<f32> {
+        let mut freqs: Vec<f32> = self.history.iter().flatten().map(|r| r.frequency).collect();
+        if freqs.is_empty() {
+            return None;
+        }
+        freqs.sort_by(f32::total_cmp);
+        let mid = freqs.len() / 2;
+        Some(if freqs.len().is_multiple_of(2) {
+            (freqs[mid - 1] + freqs[mid]) / 2.0
+        } else {
+            freqs[mid]
+        })
+    }
+
+    /// Move a reading by whole octaves onto the target or, without one, the
+    /// recent frames, if it is close to a whole number of octaves from it.
+    fn fold_octave(&self, result: PitchResult) -> PitchResult {
+        // With a window of one there are no recent frames to agree with
+        let recent = || self.median().filter(|_| self.window > 1);
+        let Some(reference) = self.target.or_else(recent) else {
+            return result;
+        };
+        let octaves = (result.frequency / reference).log2();
+        let fold = octaves.round() as i32;
+        let off_cents = 1200.0 * (octaves - fold as f32).abs();
+        if fold == 0 || fold.abs() > MAX_FOLD_OCTAVES || off_cents > OCTAVE_FOLD_CENTS {
+            return result;
+        }
+
+        let ratio = 2.0_f32.powi(fold);
+        PitchResult {
+            frequency: result.frequency / ratio,
+            tau: result.tau * ratio,
+            ..result
+        }
+    }
+
+    /// Forget the history, e.g. when detection stops for a while.
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+}
+
+impl Default for PitchSmoother {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_WINDOW)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::pitch::PitchDetector;
+    use crate::audio::traits::TestAudioSource;
+
+    const SAMPLE_RATE: f32 = 44100.0;
+
+    fn reading(frequency: f32, confidence: f32) -> Option<PitchResult> {
+        Some(PitchResult {
+            frequency,
+            confidence,
+            tau: SAMPLE_RATE / frequency,
+            rms: 0.5,
+            clarity: confidence,
+            ambiguous: false,
+        })
+    }
+
+    fn cents(freq: f32, reference: f32) -> f32 {
+        1200.0 * (freq / reference).log2()
+    }
+
+    #[test]
+    fn test_outlier_does_not_move_reading() {
+        let mut smoother = PitchSmoother::default();
+        let jitter = [0.0, 0.01, -0.01, 0.02, -0.02];
+        let mut readings: Vec<_> = (0..20)
+            .map(|i| reading(440.0 + jitter[i % jitter.len()], 0.95))
+            .collect();
+        readings[10] = reading(880.0, 0.95);
+
+        for (i, r) in readings.into_iter().enumerate() {
+            let smoothed = smoother.push(r).expect("Should have a reading");
+            let off = cents(smoothed.frequency, 440.0).abs();
+            assert!(off < 0.1, "Frame {}: {} cents off", i, off);
+            assert!((SAMPLE_RATE / smoothed.tau - smoothed.frequency).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_low_confidence_dropped() {
+        let mut smoother = PitchSmoother::new(3);
+        assert!(smoother.push(reading(440.0, 0.2)).is_none());
+
+        smoother.push(reading(440.0, 0.9));
+        // A low-confidence frame keeps the last confident reading
+        let held = smoother.push(reading(600.0, 0.2)).unwrap();
+        assert_eq!(held.frequency, 440.0);
+        assert_eq!(held.confidence, 0.9);
+
+        // Until it leaves the window
+        smoother.push(None);
+        assert!(smoother.push(None).is_none());
+    }
+
+    #[test]
+    fn test_alternating_octaves_fold_to_history() {
+        let mut smoother = PitchSmoother::default();
+        let octaves = [55.0, 55.1, 110.2, 27.5, 110.0, 54.9, 27.45, 110.1, 55.0];
+        for (i, &freq) in octaves.iter().cycle().take(30).enumerate() {
+            let smoothed = smoother.push(reading(freq, 0.9)).unwrap();
+            let off = cents(smoothed.frequency, 55.0).abs();
+            assert!(off < 5.0, "Frame {}: {} cents off", i, off);
+            assert!((SAMPLE_RATE / smoothed.tau - smoothed.frequency).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_target_decides_octave() {
+        let mut smoother = PitchSmoother::new(3);
+        smoother.set_target(Some(55.0));
+        // The second partial wins from the very first frame
+        for freq in [110.0, 110.2, 55.1, 109.9, 27.5] {
+            let smoothed = smoother.push(reading(freq, 0.9)).unwrap();
+            assert!(cents(smoothed.frequency, 55.0).abs() < 5.0);
+        }
+
+        // A different note entirely isn't an octave error
+        smoother.set_target(None);
+        smoother.clear();
+        smoother.push(reading(55.0, 0.9));
+        let far = reading(82.4, 0.9);
+        assert_eq!(smoother.push(far).unwrap().frequency, (55.0 + 82.4) / 2.0);
+    }
+
+    #[test]
+    fn test_vibrato_centred() {
+        let source = TestAudioSource::vibrato(440.0, 8.0, 5.0, 2.0, SAMPLE_RATE as u32);
+        let detector = PitchDetector::new(SAMPLE_RATE as u32);
+        // Nine frames a quarter window apart span a cycle of the wobble
+        let mut smoother = PitchSmoother::new(9);
+        let smoothed: Vec<f32> = source
+            .samples()
+            .windows(4096)
+            .step_by(1024)
+            .map(|window| {
+                let smoothed = smoother.push(detector.detect(window)).unwrap();
+                cents(smoothed.frequency, 440.0)
+            })
+            // Once the window has filled
+            .skip(9)
+            .collect();
+
+        let mean = smoothed.iter().sum::<f32>() / smoothed.len() as f32;
+        assert!(mean.abs() < 0.5, "Mean {:+.2} cents", mean);
+        for off in smoothed {
+            assert!(off.abs() < 3.0, "{:+.2} cents", off);
+        }
+    }
+
+    #[test]
+    fn test_window_of_one_passes_through() {
+        let mut smoother = PitchSmoother::new(0);
+        assert_eq!(smoother.window(), 1);
+        assert_eq!(smoother.push(reading(440.0, 0.9)).unwrap().frequency, 440.0);
+        assert_eq!(smoother.push(reading(880.0, 0.9)).unwrap().frequency, 880.0);
+        assert!(smoother.push(None).is_none());
+    }
+}
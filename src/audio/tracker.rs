@@ -0,0 +1,489 @@
+//! Streaming pitch detection over overlapping windows.
+//!
+//! Audio arrives in chunks of whatever size the source delivers. The tracker
+//! keeps the most recent window in a ring buffer and runs detection every
+//! hop, so results come at a fixed rate regardless of chunking.
+//!
+//! The hammer's attack is broadband and says little about the string's
+//! settled pitch. With an attack delay, a pitch is only reported once the
+//! same pitch class has been heard for long enough.
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use super::pitch::{PitchAlgorithm, PitchResult, YinScratch};
+
+/// Detection over one window.
+#[derive(Debug, Clone, Copy)]
+pub struct PitchFrame {
+    /// Samples pushed up to the end of the window.
+    pub end: u64,
+    /// Detected pitch, or `None` if the window had no clear pitch.
+    pub result: Option<PitchResult>,
+}
+
+impl PitchFrame {
+    /// Time of the end of the window from the start of the stream.
+    pub fn time(&self, sample_rate: u32) -> Duration {
+        Duration::from_secs_f64(self.end as f64 / sample_rate as f64)
+    }
+}
+
+/// Runs a pitch detector on overlapping windows of streamed audio.
+pub struct PitchTracker {
+    detector: Box<dyn PitchAlgorithm>,
+    /// Window used without a target.
+    default_window: usize,
+    window_size: usize,
+    hop_size: usize,
+    /// Frequency the search is narrowed around, if any.
+    target: Option<f32>,
+    /// Most recent samples in time order, at least `history_size` of them
+    /// once enough have been pushed.
+    history: Vec<f32>,
+    history_size: usize,
+    /// Samples pushed since the last reset.
+    filled: usize,
+    /// Samples pushed since the last detection.
+    pending: usize,
+    /// Stream position one past the last sample pushed.
+    pushed: u64,
+    /// Detection buffers reused between windows.
+    scratch: YinScratch,
+    frames: VecDeque<PitchFrame>,
+    /// Samples a pitch class must be heard for before it is reported.
+    attack_samples: u64,
+    /// Pitch class being heard and the sample count it was first heard at.
+    onset: Option<(i32, u64)>,
+    /// Whether the current pitch has been heard for the attack delay.
+    settled: bool,
+}
+
+/// Pitch class of a frequency, 0 being A.
+fn pitch_class(frequency: f32) -> i32 {
+    ((12.0 * (frequency / 440.0).log2()).round() as i32).rem_euclid(12)
+}
+
+/// The last `window` samples of `history`, untouched.
+fn recent_window(history: &[f32], window: usize) -> Cow<'_, [f32]> {
+    Cow::Borrowed(&history[history.len().saturating_sub(window)..])
+}
+
+impl PitchTracker {
+    /// Default window size in samples.
+    pub const DEFAULT_WINDOW: usize = 4096;
+
+    /// Default hop between windows in samples.
+    pub const DEFAULT_HOP: usize = 1024;
+
+    /// Create a tracker running `detector` with the default window and hop.
+    pub fn new(detector: Box<dyn PitchAlgorithm>) -> Self {
+        Self {
+            detector,
+            default_window: 0,
+            window_size: 0,
+            hop_size: 0,
+            target: None,
+            history: Vec::new(),
+            history_size: 0,
+            filled: 0,
+            pending: 0,
+            pushed: 0,
+            scratch: YinScratch::default(),
+            frames: VecDeque::new(),
+            attack_samples: 0,
+            onset: None,
+            settled: false,
+        }
+        .with_window(Self::DEFAULT_WINDOW, Self::DEFAULT_HOP)
+    }
+
+    /// Set the window size and the hop between windows. The hop is kept
+    /// between one sample and the window size. Clears any buffered audio.
+    pub fn with_window(mut self, window_size: usize, hop_size: usize) -> Self {
+        self.default_window = window_size.max(1);
+        self.window_size = self.default_window;
+        self.hop_size = hop_size.clamp(1, self.default_window);
+        self.history_size = self.history_size.max(self.default_window);
+        self.reset(self.pushed);
+        self
+    }
+
+    /// Keep at least `samples` of audio, for target windows longer than
+    /// the default one and for [`push_with`](Self::push_with) steps that
+    /// look further back.
+    pub fn with_history(mut self, samples: usize) -> Self {
+        self.history_size = samples.max(self.default_window);
+        self
+    }
+
+    /// Hold back detections until the same pitch class has been heard for
+    /// `delay` of audio at `sample_rate`. Zero reports every detection.
+    pub fn with_attack_delay(mut self, delay: Duration, sample_rate: u32) -> Self {
+        self.attack_samples = (delay.as_secs_f64() * sample_rate as f64).round() as u64;
+        self
+    }
+
+    /// Window size in samples.
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    /// Hop between windows in samples.
+    pub fn hop_size(&self) -> usize {
+        self.hop_size
+    }
+
+    /// Stream position one past the last sample pushed, the `end` of a
+    /// frame detected now.
+    pub fn pushed(&self) -> u64 {
+        self.pushed
+    }
+
+    /// Narrow the search around `target`, with the window the detector
+    /// wants for it, or search the whole range with the default window.
+    /// Buffered audio is kept; a new target waits out the attack delay.
+    pub fn set_target(&mut self, target: Option<f32>) {
+        if target == self.target {
+            return;
+        }
+        self.target = target;
+        self.window_size = target.map_or(self.default_window, |target| {
+            self.detector
+                .window_for_target(target)
+                .min(self.history_size)
+        });
+        self.onset = None;
+        self.settled = false;
+    }
+
+    /// Add samples, detecting on every window completed by them.
+    pub fn push(&mut self, samples: &[f32]) {
+        self.push_with(samples, recent_window);
+    }
+
+    /// Add samples like [`push`](Self::push), detecting on what `prepare`
+    /// makes of the history and the window size, e.g. with a known tone
+    /// filtered out.
+    pub fn push_with<F>(&mut self, samples: &[f32], prepare: F)
+    where
+        F: for<'a> Fn(&'a [f32], usize) -> Cow<'a, [f32]>,
+    {
+        let mut rest = samples;
+        loop {
+            if self.filled >= self.window_size && self.pending >= self.hop_size {
+                self.pending = 0;
+                self.detect(&prepare);
+            }
+            if rest.is_empty() {
+                break;
+            }
+
+            // Up to the next window that can be detected on
+            let needed = (self.window_size.saturating_sub(self.filled))
+                .max(self.hop_size.saturating_sub(self.pending))
+                .max(1);
+            let (now, later) = rest.split_at(needed.min(rest.len()));
+            self.history.extend_from_slice(now);
+            self.filled += now.len();
+            self.pending += now.len();
+            self.pushed += now.len() as u64;
+            rest = later;
+
+            // Trimmed in bulk rather than on every push
+            if self.history.len() >= 2 * self.history_size {
+                self.history.drain(..self.history.len() - self.history_size);
+            }
+        }
+    }
+
+    /// Push the samples of `recent` not pushed yet, `recent` being the
+    /// latest audio up to stream position `end`. After a reset, or with
+    /// more missing than `recent` holds, a window's worth is pushed.
+    pub fn push_recent_with<F>(&mut self, recent: &[f32], end: u64, prepare: F)
+    where
+        F: for<'a> Fn(&'a [f32], usize) -> Cow<'a, [f32]>,
+    {
+        if self.filled == 0 || end.saturating_sub(self.pushed) > recent.len() as u64 {
+            let window = self.window_size.min(recent.len());
+            self.reset(end - window as u64);
+        }
+        let fresh = end.saturating_sub(self.pushed) as usize;
+        self.push_with(&recent[recent.len() - fresh..], prepare);
+    }
+
+    /// Take the oldest detection not yet polled.
+    pub fn poll(&mut self) -> Option<PitchFrame> {
+        self.frames.pop_front()
+    }
+
+    /// Drop buffered audio and detections, e.g. after switching notes,
+    /// and carry on counting from stream position `position`.
+    pub fn reset(&mut self, position: u64) {
+        self.history.clear();
+        self.pushed = position;
+        self.filled = 0;
+        self.pending = 0;
+        self.frames.clear();
+        self.onset = None;
+        self.settled = false;
+    }
+
+    /// Detect on the window ending with the latest sample.
+    fn detect<F>(&mut self, prepare: &F)
+    where
+        F: for<'a> Fn(&'a [f32], usize) -> Cow<'a, [f32]>,
+    {
+        let history = &self.history[self.history.len().saturating_sub(self.history_size)..];
+        let window = prepare(history, self.window_size);
+        let result = match self.target {
+            Some(target) => self
+                .detector
+                .detect_near_into(&window, target, &mut self.scratch),
+            None => self.detector.detect_into(&window, &mut self.scratch),
+        };
+        let sustained = match result {
+            Some(result) => self.sustained(result.frequency),
+            None => {
+                self.onset = None;
+                self.settled = false;
+                false
+            }
+        };
+        self.frames.push_back(PitchFrame {
+            end: self.pushed,
+            result: result.filter(|_| sustained),
+        });
+    }
+
+    /// Track how long the pitch class of a detection has been heard and
+    /// whether it has passed the attack delay. A frame without a pitch
+    /// ends the note, so the next one waits again.
+    fn sustained(&mut self, frequency: f32) -> bool {
+        let class = pitch_class(frequency);
+        match self.onset {
+            Some((heard, _)) if heard == class => {}
+            _ => {
+                self.onset = Some((class, self.pushed));
+                self.settled = false;
+            }
+        }
+        if let Some((_, since)) = self.onset {
+            self.settled |= self.pushed - since >= self.attack_samples;
+        }
+        self.settled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::pitch::PitchMethod;
+    use crate::audio::traits::TestAudioSource;
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    fn frames(tracker: &mut PitchTracker) -> Vec<PitchFrame> {
+        std::iter::from_fn(|| tracker.poll()).collect()
+    }
+
+    #[test]
+    fn test_steady_detections_from_odd_chunks() {
+        let source = TestAudioSource::sine(440.0, 1.0, SAMPLE_RATE);
+        let samples = source.samples();
+        let mut tracker = PitchTracker::new(PitchMethod::Yin.detector(SAMPLE_RATE));
+
+        let sizes = [1, 313, 977, 64, 2049, 7];
+        let mut offset = 0;
+        for size in sizes.iter().cycle() {
+            if offset >= samples.len() {
+                break;
+            }
+            let end = (offset + size).min(samples.len());
+            tracker.push(&samples[offset..end]);
+            offset = end;
+        }
+
+        let frames = frames(&mut tracker);
+        let expected =
+            (samples.len() - PitchTracker::DEFAULT_WINDOW) / PitchTracker::DEFAULT_HOP + 1;
+        assert_eq!(frames.len(), expected);
+
+        for (i, frame) in frames.iter().enumerate() {
+            let end = PitchTracker::DEFAULT_WINDOW + i * PitchTracker::DEFAULT_HOP;
+            assert_eq!(frame.end, end as u64);
+            let time = frame.time(SAMPLE_RATE).as_secs_f64();
+            assert!((time - end as f64 / SAMPLE_RATE as f64).abs() < 1e-9);
+            let result = frame.result.expect("Should detect pitch");
+            assert!(
+                (result.frequency - 440.0).abs() < 0.5,
+                "Frame {}: {}",
+                i,
+                result.frequency
+            );
+        }
+
+        // A hop apart in time
+        for pair in frames.windows(2) {
+            let step = pair[1].time(SAMPLE_RATE) - pair[0].time(SAMPLE_RATE);
+            let hop = PitchTracker::DEFAULT_HOP as f64 / SAMPLE_RATE as f64;
+            assert!((step.as_secs_f64() - hop).abs() < 1e-6);
+        }
+
+        let max_step = frames
+            .windows(2)
+            .map(|pair| {
+                (pair[1].result.unwrap().frequency - pair[0].result.unwrap().frequency).abs()
+            })
+            .fold(0.0, f32::max);
+        assert!(max_step < 0.1, "Detections jumped by {} Hz", max_step);
+    }
+
+    #[test]
+    fn test_chunking_does_not_change_results() {
+        let source = TestAudioSource::sine(261.63, 0.5, SAMPLE_RATE);
+        let samples = source.samples();
+        let tracker =
+            || PitchTracker::new(PitchMethod::Mpm.detector(SAMPLE_RATE)).with_window(2048, 512);
+
+        let mut whole = tracker();
+        whole.push(samples);
+        let mut chunked = tracker();
+        for chunk in samples.chunks(333) {
+            chunked.push(chunk);
+        }
+
+        let whole = frames(&mut whole);
+        let chunked = frames(&mut chunked);
+        assert_eq!(whole.len(), chunked.len());
+        for (a, b) in whole.iter().zip(&chunked) {
+            assert_eq!(a.end, b.end);
+            assert_eq!(a.result.map(|r| r.frequency), b.result.map(|r| r.frequency));
+        }
+    }
+
+    #[test]
+    fn test_attack_delay_after_noise() {
+        let mut samples = TestAudioSource::noise(0.05, 12345, SAMPLE_RATE)
+            .samples()
+            .to_vec();
+        let noise_len = samples.len();
+        samples.extend_from_slice(TestAudioSource::sine(440.0, 0.5, SAMPLE_RATE).samples());
+
+        let delay = Duration::from_millis(100);
+        let delay_samples = SAMPLE_RATE as u64 / 10;
+        let tracker =
+            || PitchTracker::new(PitchMethod::Yin.detector(SAMPLE_RATE)).with_window(4096, 256);
+
+        let mut immediate = tracker();
+        immediate.push(&samples);
+        let first = frames(&mut immediate)
+            .into_iter()
+            .find(|frame| frame.result.is_some())
+            .unwrap();
+        assert!(first.end < noise_len as u64 + delay_samples);
+
+        let mut delayed = tracker().with_attack_delay(delay, SAMPLE_RATE);
+        delayed.push(&samples);
+        let frames = frames(&mut delayed);
+        let first = frames
+            .iter()
+            .position(|frame| frame.result.is_some())
+            .expect("Should report the sustained pitch");
+        assert!(frames[first].end >= noise_len as u64 + delay_samples);
+        // Updates flow normally after that
+        assert!(frames[first..].iter().all(|frame| frame.result.is_some()));
+    }
+
+    #[test]
+    fn test_target_keeps_history() {
+        let source = TestAudioSource::sine(27.5, 1.0, SAMPLE_RATE);
+        let samples = source.samples();
+        let detector = PitchMethod::Yin.detector(SAMPLE_RATE);
+        let bass_window = detector.window_for_target(27.5);
+        let mut tracker = PitchTracker::new(detector)
+            .with_window(4410, 1102)
+            .with_history(SAMPLE_RATE as usize);
+
+        tracker.push(&samples[..bass_window]);
+        frames(&mut tracker);
+
+        // The longer bass window is already buffered
+        tracker.set_target(Some(27.5));
+        assert_eq!(tracker.window_size(), bass_window);
+        tracker.push(&samples[bass_window..bass_window + 1102]);
+        let frames = frames(&mut tracker);
+        // Still a hop after the last frame
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].end, 4410 + 5 * 1102);
+        let result = frames[0].result.expect("Should detect A0");
+        assert!(
+            (result.frequency - 27.5).abs() < 0.1,
+            "{}",
+            result.frequency
+        );
+
+        tracker.set_target(None);
+        assert_eq!(tracker.window_size(), 4410);
+    }
+
+    #[test]
+    fn test_push_with_prepares_window() {
+        let source = TestAudioSource::sine(440.0, 0.5, SAMPLE_RATE);
+        let mut tracker =
+            PitchTracker::new(PitchMethod::Yin.detector(SAMPLE_RATE)).with_window(2048, 1024);
+
+        // A step that silences the window leaves nothing to detect
+        tracker.push_with(source.samples(), |_, window| Cow::Owned(vec![0.0; window]));
+        let silenced = frames(&mut tracker);
+        assert!(!silenced.is_empty());
+        assert!(silenced.iter().all(|frame| frame.result.is_none()));
+        assert_eq!(tracker.pushed(), source.samples().len() as u64);
+    }
+
+    #[test]
+    fn test_silence_and_reset() {
+        let mut tracker =
+            PitchTracker::new(PitchMethod::Yin.detector(SAMPLE_RATE)).with_window(1024, 4096);
+        assert_eq!(tracker.hop_size(), 1024);
+
+        tracker.push(&[0.0; 2048]);
+        let silent = frames(&mut tracker);
+        assert_eq!(silent.len(), 2);
+        assert!(silent.iter().all(|frame| frame.result.is_none()));
+
+        tracker.push(&[0.0; 512]);
+        tracker.reset(10_000);
+        tracker.push(&[0.0; 1023]);
+        assert!(tracker.poll().is_none());
+        assert_eq!(tracker.pushed(), 11_023);
+    }
+
+    #[test]
+    fn test_push_recent_after_reset() {
+        let source = TestAudioSource::sine(440.0, 2.0, SAMPLE_RATE);
+        let samples = source.samples();
+        let mut tracker =
+            PitchTracker::new(PitchMethod::Yin.detector(SAMPLE_RATE)).with_window(4410, 1102);
+
+        // A sliding 3000-sample history read every 1000 samples
+        let mut last = 0;
+        for end in (1000..=samples.len()).step_by(1000) {
+            if end == 20_000 {
+                tracker.reset(end as u64);
+            }
+            let recent = &samples[end.saturating_sub(3000)..end];
+            tracker.push_recent_with(recent, end as u64, recent_window);
+            assert_eq!(tracker.pushed(), end as u64);
+
+            for frame in frames(&mut tracker) {
+                assert!(frame.end > last && frame.end <= end as u64);
+                last = frame.end;
+                let result = frame.result.expect("Should detect pitch");
+                assert!((result.frequency - 440.0).abs() < 0.5);
+            }
+        }
+        assert!(last > 80_000);
+    }
+}
@@ -1,23 +1,51 @@
 //! Audio I/O traits for abstraction and mocking.
 
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, Write};
+
+use super::partials::partial_ratio;
 
 /// Audio input source trait.
 pub trait AudioSource {
     /// Read samples into the buffer, returning the number of samples read.
+    /// A live source may read none while it waits for more.
     fn read_samples(&mut self, buffer: &mut [f32]) -> usize;
 
     /// Get the sample rate in Hz.
     fn sample_rate(&self) -> u32;
+
+    /// Whether the source has ended and will never read more samples.
+    /// Live sources never end.
+    fn is_exhausted(&self) -> bool {
+        false
+    }
+}
+
+impl<S: AudioSource + ?Sized> AudioSource for Box<S> {
+    fn read_samples(&mut self, buffer: &mut [f32]) -> usize {
+        (**self).read_samples(buffer)
+    }
+
+    fn sample_rate(&self) -> u32 {
+        (**self).sample_rate()
+    }
+
+    fn is_exhausted(&self) -> bool {
+        (**self).is_exhausted()
+    }
 }
 
 /// Audio output sink trait.
 pub trait AudioSink {
-    /// Write samples to the output.
+    /// Write samples to the output, interleaved when there are several channels.
     fn write_samples(&mut self, samples: &[f32]);
 
     /// Get the sample rate in Hz.
     fn sample_rate(&self) -> u32;
+
+    /// Number of interleaved channels the sink expects.
+    fn channels(&self) -> u16 {
+        1
+    }
 }
 
 /// Test audio source backed by a buffer.
@@ -51,6 +79,23 @@ impl TestAudioSource {
         Self::new(samples, sample_rate)
     }
 
+    /// Create a test source with white noise, the same for the same `seed`.
+    pub fn noise(duration_secs: f32, seed: u64, sample_rate: u32) -> Self {
+        let num_samples = (sample_rate as f32 * duration_secs) as usize;
+        let mut x = seed.max(1);
+        let samples = (0..num_samples)
+            .map(|_| {
+                // xorshift64
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                ((x as f64 / u64::MAX as f64) * 2.0 - 1.0) as f32
+            })
+            .collect();
+
+        Self::new(samples, sample_rate)
+    }
+
     /// Create a test source with a sine wave plus harmonics.
     pub fn sine_with_harmonics(
         fundamental: f32,
@@ -87,6 +132,138 @@ impl TestAudioSource {
         Self::new(samples, sample_rate)
     }
 
+    /// Create a test source resembling a struck piano string: `num_partials`
+    /// partials stretched by the inharmonicity coefficient `b`, weaker and
+    /// quicker to decay the higher they are.
+    pub fn piano_like(
+        fundamental: f32,
+        b: f32,
+        num_partials: u32,
+        duration_secs: f32,
+        sample_rate: u32,
+    ) -> Self {
+        let num_samples = (sample_rate as f32 * duration_secs) as usize;
+        let partials: Vec<(f64, f64, f64)> = (1..=num_partials)
+            .map(|n| {
+                let freq = fundamental as f64 * partial_ratio(n, b) as f64;
+                // Amplitude and decay rate per second
+                (freq, 1.0 / n as f64, 0.5 + 0.5 * n as f64)
+            })
+            .collect();
+
+        let mut samples: Vec<f32> = (0..num_samples)
+            .map(|i| {
+                let t = i as f64 / sample_rate as f64;
+                partials
+                    .iter()
+                    .map(|&(freq, amplitude, decay)| {
+                        amplitude * (-decay * t).exp() * (std::f64::consts::TAU * freq * t).sin()
+                    })
+                    .sum::<f64>() as f32
+            })
+            .collect();
+
+        // Normalize
+        let max = samples.iter().map(|s| s.abs()).fold(0.0_f32, f32::max);
+        if max > 0.0 {
+            for sample in &mut samples {
+                *sample /= max;
+            }
+        }
+
+        Self::new(samples, sample_rate)
+    }
+
+    /// Create a test source with a sine sweeping linearly in frequency
+    /// from `start_hz` to `end_hz`.
+    pub fn sweep(start_hz: f32, end_hz: f32, duration_secs: f32, sample_rate: u32) -> Self {
+        Self::chirp(duration_secs, sample_rate, |t| {
+            start_hz + (end_hz - start_hz) * t / duration_secs
+        })
+    }
+
+    /// Create a test source with a sine sweeping exponentially in
+    /// frequency, i.e. by equal musical intervals per second, from
+    /// `start_hz` to `end_hz`.
+    pub fn sweep_exponential(
+        start_hz: f32,
+        end_hz: f32,
+        duration_secs: f32,
+        sample_rate: u32,
+    ) -> Self {
+        Self::chirp(duration_secs, sample_rate, |t| {
+            start_hz * (end_hz / start_hz).powf(t / duration_secs)
+        })
+    }
+
+    /// Create a test source with a string being pulled up to pitch: it
+    /// starts `detune_cents` off `target_hz`, eases onto it over
+    /// `settle_secs` and then holds.
+    pub fn glide_to(
+        target_hz: f32,
+        detune_cents: f32,
+        settle_secs: f32,
+        duration_secs: f32,
+        sample_rate: u32,
+    ) -> Self {
+        Self::chirp(duration_secs, sample_rate, |t| {
+            let remaining = (1.0 - t / settle_secs).max(0.0);
+            target_hz * 2.0_f32.powf(detune_cents * remaining * remaining / 1200.0)
+        })
+    }
+
+    /// Create a test source with a sine wobbling `depth_cents` either side
+    /// of `center_hz`, `rate_hz` times a second.
+    pub fn vibrato(
+        center_hz: f32,
+        depth_cents: f32,
+        rate_hz: f32,
+        duration_secs: f32,
+        sample_rate: u32,
+    ) -> Self {
+        Self::chirp(duration_secs, sample_rate, |t| {
+            let cents = depth_cents * (2.0 * std::f32::consts::PI * rate_hz * t).sin();
+            center_hz * 2.0_f32.powf(cents / 1200.0)
+        })
+    }
+
+    /// Create a test source with a sine at `center_hz` whose amplitude
+    /// swells and fades `rate_hz` times a second, by `depth` (0.0 to 1.0)
+    /// of its peak.
+    pub fn tremolo(
+        center_hz: f32,
+        depth: f32,
+        rate_hz: f32,
+        duration_secs: f32,
+        sample_rate: u32,
+    ) -> Self {
+        let mut source = Self::sine(center_hz, duration_secs, sample_rate);
+        for (i, sample) in source.samples.iter_mut().enumerate() {
+            let t = i as f32 / sample_rate as f32;
+            let swell = 0.5 + 0.5 * (2.0 * std::f32::consts::PI * rate_hz * t).sin();
+            *sample *= 1.0 - depth * swell;
+        }
+        source
+    }
+
+    /// Create a test source with a sine following `frequency`, a function
+    /// of time in seconds. The phase is accumulated so it stays continuous.
+    fn chirp(duration_secs: f32, sample_rate: u32, frequency: impl Fn(f32) -> f32) -> Self {
+        let num_samples = (sample_rate as f32 * duration_secs) as usize;
+        let mut phase = 0.0_f64;
+        let samples = (0..num_samples)
+            .map(|i| {
+                let sample = phase.sin() as f32;
+                let t = i as f32 / sample_rate as f32;
+                phase += std::f64::consts::TAU * frequency(t) as f64 / sample_rate as f64;
+                phase %= std::f64::consts::TAU;
+                sample
+            })
+            .collect();
+
+        Self::new(samples, sample_rate)
+    }
+
     /// Reset position to start.
     pub fn reset(&mut self) {
         self.position = 0;
@@ -112,89 +289,401 @@ impl AudioSource for TestAudioSource {
     fn sample_rate(&self) -> u32 {
         self.sample_rate
     }
+
+    fn is_exhausted(&self) -> bool {
+        self.position >= self.samples.len()
+    }
 }
 
 /// WAV file audio source.
+///
+/// Multichannel files are read a frame at a time: the channels are averaged
+/// to mono, or one of them is taken. A looping source starts over at the
+/// end, so it never runs out.
 pub struct WavAudioSource<R: Read + Seek> {
     reader: hound::WavReader<R>,
     sample_rate: u32,
+    /// Channel to read, or `None` to average them.
+    channel: Option<usize>,
+    looping: bool,
+    /// Whether a read came up short at the end of the file.
+    ended: bool,
 }
 
 impl<R: Read + Seek + Send> WavAudioSource<R> {
     /// Create a new WAV source from a reader.
     pub fn new(reader: R) -> Result<Self, hound::Error> {
-        let wav_reader = hound::WavReader::new(reader)?;
-        let sample_rate = wav_reader.spec().sample_rate;
+        Ok(Self::from_reader(hound::WavReader::new(reader)?))
+    }
 
-        Ok(Self {
-            reader: wav_reader,
-            sample_rate,
-        })
+    fn from_reader(reader: hound::WavReader<R>) -> Self {
+        Self {
+            sample_rate: reader.spec().sample_rate,
+            reader,
+            channel: None,
+            looping: false,
+            ended: false,
+        }
+    }
+
+    /// Read only `channel` (0 = left) instead of averaging the channels.
+    /// Past the last channel, the last one is read.
+    pub fn with_channel(mut self, channel: usize) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// Number of channels in the file.
+    pub fn channels(&self) -> u16 {
+        self.reader.spec().channels
+    }
+
+    /// Length of the file in samples per channel.
+    pub fn duration_samples(&self) -> usize {
+        self.reader.duration() as usize
+    }
+
+    /// Start over at the beginning of the file.
+    pub fn reset(&mut self) -> std::io::Result<()> {
+        self.reader.seek(0)?;
+        self.ended = false;
+        Ok(())
+    }
+
+    /// Set whether reading starts over at the end of the file instead of
+    /// running out.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Whether reading starts over at the end of the file.
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    /// Read whole frames until the buffer is full or the file ends.
+    fn read_frames(&mut self, buffer: &mut [f32]) -> usize {
+        let spec = self.reader.spec();
+        let channels = spec.channels.max(1) as usize;
+        let wanted = buffer.len() * channels;
+        let samples: Box<dyn Iterator<Item = f32> + '_> = match spec.sample_format {
+            hound::SampleFormat::Float => {
+                Box::new(self.reader.samples::<f32>().take(wanted).flatten())
+            }
+            hound::SampleFormat::Int => {
+                // hound already recentres unsigned 8-bit samples on zero, and
+                // 32-bit full scale doesn't fit in an i32
+                let max_val = 2.0_f32.powi(spec.bits_per_sample as i32 - 1);
+                Box::new(
+                    self.reader
+                        .samples::<i32>()
+                        .take(wanted)
+                        .flatten()
+                        .map(move |s| s as f32 / max_val),
+                )
+            }
+        };
+
+        // One output sample per whole frame; a trailing partial frame is dropped
+        let picked = self.channel.map(|c| c.min(channels - 1));
+        let mut count = 0;
+        let mut index = 0;
+        let mut frame = 0.0;
+        for s in samples {
+            match picked {
+                Some(c) if c == index => frame = s,
+                Some(_) => {}
+                None => frame += s,
+            }
+            index += 1;
+            if index == channels {
+                buffer[count] = if picked.is_some() {
+                    frame
+                } else {
+                    frame / channels as f32
+                };
+                count += 1;
+                index = 0;
+                frame = 0.0;
+            }
+        }
+
+        count
     }
 }
 
 impl WavAudioSource<std::io::BufReader<std::fs::File>> {
     /// Open a WAV file from path.
     pub fn open(path: impl AsRef<std::path::Path>) -> Result<Self, hound::Error> {
-        let wav_reader = hound::WavReader::open(path)?;
-        let sample_rate = wav_reader.spec().sample_rate;
+        Ok(Self::from_reader(hound::WavReader::open(path)?))
+    }
+}
 
-        Ok(Self {
-            reader: wav_reader,
+impl<R: Read + Seek + Send> AudioSource for WavAudioSource<R> {
+    fn read_samples(&mut self, buffer: &mut [f32]) -> usize {
+        let mut count = self.read_frames(buffer);
+        while self.looping && count < buffer.len() {
+            // An unreadable file would otherwise loop forever
+            if self.reset().is_err() {
+                break;
+            }
+            match self.read_frames(&mut buffer[count..]) {
+                0 => break,
+                read => count += read,
+            }
+        }
+        self.ended = count < buffer.len();
+        count
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn is_exhausted(&self) -> bool {
+        self.ended
+    }
+}
+
+/// Sample encoding of raw PCM audio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum PcmFormat {
+    /// 32-bit little-endian float.
+    #[default]
+    #[value(name = "f32le")]
+    F32Le,
+    /// 16-bit little-endian signed integer.
+    #[value(name = "s16le")]
+    S16Le,
+}
+
+impl PcmFormat {
+    /// Bytes per sample.
+    pub fn bytes_per_sample(self) -> usize {
+        match self {
+            PcmFormat::F32Le => 4,
+            PcmFormat::S16Le => 2,
+        }
+    }
+
+    /// Decode one sample to the range -1.0 to 1.0.
+    fn decode(self, bytes: &[u8]) -> f32 {
+        match self {
+            PcmFormat::F32Le => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            PcmFormat::S16Le => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+        }
+    }
+}
+
+/// Mono raw PCM audio from a stream, such as another program piped into
+/// stdin.
+///
+/// Reads may end partway through a sample; the bytes are kept for the
+/// next read. Reading returns 0 once the stream ends or fails.
+pub struct StdinAudioSource<R: Read> {
+    reader: R,
+    format: PcmFormat,
+    sample_rate: u32,
+    /// Bytes read but not yet decoded, at most one partial sample once a
+    /// read completes.
+    bytes: Vec<u8>,
+    /// Whether the stream ended or failed.
+    ended: bool,
+}
+
+impl<R: Read> StdinAudioSource<R> {
+    /// Read samples in `format` at `sample_rate` from `reader`.
+    pub fn new(reader: R, format: PcmFormat, sample_rate: u32) -> Self {
+        Self {
+            reader,
+            format,
             sample_rate,
-        })
+            bytes: Vec::new(),
+            ended: false,
+        }
+    }
+
+    /// Sample encoding being read.
+    pub fn format(&self) -> PcmFormat {
+        self.format
     }
 }
 
-impl<R: Read + Seek + Send> AudioSource for WavAudioSource<R> {
+impl StdinAudioSource<std::io::Stdin> {
+    /// Read samples from the process's standard input.
+    pub fn stdin(format: PcmFormat, sample_rate: u32) -> Self {
+        Self::new(std::io::stdin(), format, sample_rate)
+    }
+}
+
+impl<R: Read> AudioSource for StdinAudioSource<R> {
     fn read_samples(&mut self, buffer: &mut [f32]) -> usize {
-        let spec = self.reader.spec();
-        let mut count = 0;
+        let size = self.format.bytes_per_sample();
+        let wanted = buffer.len() * size;
 
-        match spec.sample_format {
-            hound::SampleFormat::Float => {
-                for s in self.reader.samples::<f32>().take(buffer.len()).flatten() {
-                    buffer[count] = s;
-                    count += 1;
-                }
-            }
-            hound::SampleFormat::Int => {
-                let max_val = (1 << (spec.bits_per_sample - 1)) as f32;
-                for s in self.reader.samples::<i32>().take(buffer.len()).flatten() {
-                    buffer[count] = s as f32 / max_val;
-                    count += 1;
-                }
+        // Block until at least one whole sample has arrived, or the end
+        let mut filled = self.bytes.len();
+        self.bytes.resize(wanted.max(filled), 0);
+        while filled < size.min(wanted) && !self.ended {
+            match self.reader.read(&mut self.bytes[filled..]) {
+                Ok(0) => self.ended = true,
+                Ok(read) => filled += read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(_) => self.ended = true,
             }
         }
+        self.bytes.truncate(filled);
 
+        let count = (filled / size).min(buffer.len());
+        for (sample, bytes) in buffer.iter_mut().zip(self.bytes.chunks_exact(size)) {
+            *sample = self.format.decode(bytes);
+        }
+        self.bytes.drain(..count * size);
         count
     }
 
     fn sample_rate(&self) -> u32 {
         self.sample_rate
     }
+
+    fn is_exhausted(&self) -> bool {
+        // A partial sample left over at the end is never read
+        self.ended && self.bytes.len() < self.format.bytes_per_sample()
+    }
+}
+
+/// WAV file audio sink.
+///
+/// Write errors can't be returned from [`AudioSink::write_samples`], so the
+/// first one stops writing and is returned by [`finalize`](Self::finalize).
+pub struct WavAudioSink<W: Write + Seek> {
+    writer: hound::WavWriter<W>,
+    format: PcmFormat,
+    error: Option<hound::Error>,
+}
+
+impl<W: Write + Seek> WavAudioSink<W> {
+    /// Start a WAV of `channels` interleaved channels at `sample_rate`,
+    /// stored in `format`.
+    pub fn new(
+        writer: W,
+        sample_rate: u32,
+        channels: u16,
+        format: PcmFormat,
+    ) -> Result<Self, hound::Error> {
+        let spec = Self::spec(sample_rate, channels, format);
+        Ok(Self {
+            writer: hound::WavWriter::new(writer, spec)?,
+            format,
+            error: None,
+        })
+    }
+
+    fn spec(sample_rate: u32, channels: u16, format: PcmFormat) -> hound::WavSpec {
+        let (bits_per_sample, sample_format) = match format {
+            PcmFormat::F32Le => (32, hound::SampleFormat::Float),
+            PcmFormat::S16Le => (16, hound::SampleFormat::Int),
+        };
+        hound::WavSpec {
+            channels: channels.max(1),
+            sample_rate,
+            bits_per_sample,
+            sample_format,
+        }
+    }
+
+    /// Write the final header, returning the first error hit while writing.
+    pub fn finalize(self) -> Result<(), hound::Error> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.writer.finalize()
+    }
+}
+
+impl WavAudioSink<std::io::BufWriter<std::fs::File>> {
+    /// Create a WAV file at path.
+    pub fn create(
+        path: impl AsRef<std::path::Path>,
+        sample_rate: u32,
+        channels: u16,
+        format: PcmFormat,
+    ) -> Result<Self, hound::Error> {
+        let spec = Self::spec(sample_rate, channels, format);
+        Ok(Self {
+            writer: hound::WavWriter::create(path, spec)?,
+            format,
+            error: None,
+        })
+    }
+}
+
+impl<W: Write + Seek> AudioSink for WavAudioSink<W> {
+    fn write_samples(&mut self, samples: &[f32]) {
+        if self.error.is_some() {
+            return;
+        }
+        for &sample in samples {
+            let written = match self.format {
+                PcmFormat::F32Le => self.writer.write_sample(sample),
+                PcmFormat::S16Le => self
+                    .writer
+                    .write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16),
+            };
+            if let Err(e) = written {
+                self.error = Some(e);
+                return;
+            }
+        }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.writer.spec().sample_rate
+    }
+
+    fn channels(&self) -> u16 {
+        self.writer.spec().channels
+    }
 }
 
 /// Test audio sink that collects samples.
 pub struct TestAudioSink {
     samples: Vec<f32>,
     sample_rate: u32,
+    channels: u16,
 }
 
 impl TestAudioSink {
-    /// Create a new test sink.
+    /// Create a new mono test sink.
     pub fn new(sample_rate: u32) -> Self {
         Self {
             samples: Vec::new(),
             sample_rate,
+            channels: 1,
         }
     }
 
-    /// Get collected samples.
+    /// Set the number of interleaved channels.
+    pub fn with_channels(mut self, channels: u16) -> Self {
+        self.channels = channels.max(1);
+        self
+    }
+
+    /// Get collected samples, interleaved.
     pub fn samples(&self) -> &[f32] {
         &self.samples
     }
 
+    /// Get the samples of one channel.
+    pub fn channel(&self, index: usize) -> Vec<f32> {
+        self.samples
+            .iter()
+            .skip(index)
+            .step_by(self.channels as usize)
+            .copied()
+            .collect()
+    }
+
     /// Clear collected samples.
     pub fn clear(&mut self) {
         self.samples.clear();
@@ -209,11 +698,16 @@ impl AudioSink for TestAudioSink {
     fn sample_rate(&self) -> u32 {
         self.sample_rate
     }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::audio::pitch::PitchDetector;
 
     #[test]
     fn test_audio_source_reads_samples() {
@@ -241,6 +735,331 @@ mod tests {
         assert!(min < -0.9, "min should be close to -1.0, got {}", min);
     }
 
+    const WINDOW: usize = 4096;
+
+    /// Frequency detected in a window centred `at` seconds into `source`.
+    fn detected_at(source: &TestAudioSource, at: f32) -> f32 {
+        let center = (at * source.sample_rate() as f32) as usize;
+        let window = &source.samples()[center - WINDOW / 2..center + WINDOW / 2];
+        PitchDetector::new(source.sample_rate())
+            .detect(window)
+            .expect("Should detect pitch")
+            .frequency
+    }
+
+    /// Check that the frequency detected `at` seconds into `source` lies
+    /// within the range `frequency` covers over the window.
+    fn assert_follows(source: &TestAudioSource, at: f32, frequency: impl Fn(f32) -> f32) {
+        let half = WINDOW as f32 / 2.0 / source.sample_rate() as f32;
+        let (low, high) = (frequency(at - half), frequency(at + half));
+        let detected = detected_at(source, at);
+        assert!(
+            (low.min(high)..=low.max(high)).contains(&detected),
+            "At {}s: {} Hz, expected {}..{}",
+            at,
+            detected,
+            low,
+            high
+        );
+    }
+
+    #[test]
+    fn test_sweeps_follow_frequency() {
+        let linear = TestAudioSource::sweep(200.0, 300.0, 2.0, 44100);
+        let exponential = TestAudioSource::sweep_exponential(200.0, 400.0, 2.0, 44100);
+        let falling = TestAudioSource::sweep(1000.0, 500.0, 2.0, 44100);
+        assert_eq!(linear.samples().len(), 88200);
+
+        for at in [0.25, 0.5, 1.0, 1.5, 1.75] {
+            assert_follows(&linear, at, |t| 200.0 + 50.0 * t);
+            assert_follows(&exponential, at, |t| 200.0 * 2.0_f32.powf(t / 2.0));
+            assert_follows(&falling, at, |t| 1000.0 - 250.0 * t);
+        }
+    }
+
+    #[test]
+    fn test_glide_settles_on_target() {
+        let glide = TestAudioSource::glide_to(440.0, -50.0, 1.0, 2.0, 44100);
+        let cents = |at| 1200.0 * (detected_at(&glide, at) / 440.0).log2();
+
+        // Pulled up from well flat, without overshooting
+        let readings: Vec<f32> = [0.1, 0.4, 0.7, 1.2, 1.8].into_iter().map(cents).collect();
+        assert!(readings[0] < -35.0, "Started at {} cents", readings[0]);
+        assert!(readings.windows(2).all(|pair| pair[1] > pair[0] - 0.1));
+        assert!(readings[3].abs() < 0.5, "Settled at {} cents", readings[3]);
+        assert!(readings[4].abs() < 0.5, "Held at {} cents", readings[4]);
+    }
+
+    #[test]
+    fn test_sweep_has_no_clicks() {
+        let sweep = TestAudioSource::sweep(100.0, 2000.0, 0.5, 44100);
+        // At most 2000 Hz, a sample can move by 2π·2000/44100 of phase
+        let max_step = 2.0 * std::f32::consts::PI * 2000.0 / 44100.0;
+        let largest = sweep
+            .samples()
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).abs())
+            .fold(0.0, f32::max);
+        assert!(largest <= max_step + 1e-4, "Step of {}", largest);
+    }
+
+    #[test]
+    fn test_piano_like_decays() {
+        let source = TestAudioSource::piano_like(110.0, 0.0005, 8, 2.0, 44100);
+        assert_eq!(source.samples().len(), 88200);
+        let rms = |range: std::ops::Range<usize>| {
+            let slice = &source.samples()[range];
+            (slice.iter().map(|s| s * s).sum::<f32>() / slice.len() as f32).sqrt()
+        };
+        assert!(rms(0..4410) > 2.0 * rms(83790..88200));
+        assert!(source.samples().iter().all(|s| s.abs() <= 1.0));
+    }
+
+    /// A 16-bit WAV in memory with the given channels, interleaved.
+    fn wav(channels: &[&[f32]], sample_rate: u32) -> std::io::Cursor<Vec<u8>> {
+        encode(channels, sample_rate, 16, hound::SampleFormat::Int)
+    }
+
+    /// A WAV in memory in the given sample format.
+    fn encode(
+        channels: &[&[f32]],
+        sample_rate: u32,
+        bits_per_sample: u16,
+        sample_format: hound::SampleFormat,
+    ) -> std::io::Cursor<Vec<u8>> {
+        let spec = hound::WavSpec {
+            channels: channels.len() as u16,
+            sample_rate,
+            bits_per_sample,
+            sample_format,
+        };
+        let full_scale = 2.0_f64.powi(bits_per_sample as i32 - 1) - 1.0;
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut cursor, spec).unwrap();
+        for i in 0..channels[0].len() {
+            for channel in channels {
+                let sample = channel[i];
+                match (sample_format, bits_per_sample) {
+                    (hound::SampleFormat::Float, _) => writer.write_sample(sample),
+                    (_, 8) => writer.write_sample((sample as f64 * full_scale) as i8),
+                    (_, 16) => writer.write_sample((sample as f64 * full_scale) as i16),
+                    _ => writer.write_sample((sample as f64 * full_scale) as i32),
+                }
+                .unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+        cursor.set_position(0);
+        cursor
+    }
+
+    fn read_all(source: &mut impl AudioSource) -> Vec<f32> {
+        let mut samples = Vec::new();
+        // An odd buffer size, so reads don't line up with anything
+        let mut buffer = [0.0; 1001];
+        loop {
+            let read = source.read_samples(&mut buffer);
+            if read == 0 {
+                break samples;
+            }
+            samples.extend_from_slice(&buffer[..read]);
+        }
+    }
+
+    fn pitch_of(samples: &[f32]) -> f32 {
+        PitchDetector::new(44100)
+            .detect(&samples[..4096])
+            .expect("Should detect pitch")
+            .frequency
+    }
+
+    #[test]
+    fn test_wav_mono() {
+        let a4 = TestAudioSource::sine(440.0, 0.2, 44100);
+        let mut source = WavAudioSource::new(wav(&[a4.samples()], 44100)).unwrap();
+        assert_eq!(source.channels(), 1);
+        let samples = read_all(&mut source);
+        assert_eq!(samples.len(), a4.samples().len());
+        assert!((pitch_of(&samples) - 440.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn test_wav_sample_formats() {
+        let a4 = TestAudioSource::sine(440.0, 0.2, 44100);
+        let formats = [
+            (8, hound::SampleFormat::Int),
+            (16, hound::SampleFormat::Int),
+            (24, hound::SampleFormat::Int),
+            (32, hound::SampleFormat::Int),
+            (32, hound::SampleFormat::Float),
+        ];
+        for (bits, format) in formats {
+            let file = encode(&[a4.samples()], 44100, bits, format);
+            let samples = read_all(&mut WavAudioSource::new(file).unwrap());
+            assert_eq!(samples.len(), a4.samples().len());
+
+            // Full scale, not inverted, within a couple of steps of the format
+            let step = 2.0_f32.powi(2 - bits as i32);
+            for (read, written) in samples.iter().zip(a4.samples()) {
+                assert!(
+                    (read - written).abs() <= step,
+                    "{}-bit {:?}: {} read back as {}",
+                    bits,
+                    format,
+                    written,
+                    read
+                );
+            }
+            let pitch = pitch_of(&samples);
+            assert!((pitch - 440.0).abs() < 0.5, "{}-bit: {}", bits, pitch);
+        }
+    }
+
+    #[test]
+    fn test_wav_looping() {
+        // Exactly 44 cycles, so the loop joins up
+        let a4 = TestAudioSource::sine(440.0, 0.1, 44100);
+        let file = encode(&[a4.samples()], 44100, 32, hound::SampleFormat::Float);
+        let mut source = WavAudioSource::new(file).unwrap();
+        assert_eq!(source.duration_samples(), 4410);
+
+        source.set_looping(true);
+        let mut samples = vec![0.0; 10_000];
+        assert_eq!(source.read_samples(&mut samples), samples.len());
+        assert_eq!(&samples[4410..8820], a4.samples());
+
+        let largest = |samples: &[f32]| {
+            samples
+                .windows(2)
+                .map(|pair| (pair[1] - pair[0]).abs())
+                .fold(0.0, f32::max)
+        };
+        let wrap = largest(&samples[4405..4415]);
+        assert!(wrap <= largest(a4.samples()) + 1e-6, "Jump of {}", wrap);
+
+        // Stopping lets it run out again
+        source.set_looping(false);
+        let mut rest = vec![0.0; 10_000];
+        assert_eq!(source.read_samples(&mut rest), 4410 * 3 - 10_000);
+        source.reset().unwrap();
+        assert_eq!(source.read_samples(&mut rest), 4410);
+    }
+
+    #[test]
+    fn test_wav_stereo_downmixed() {
+        let left = TestAudioSource::sine(440.0, 0.2, 44100);
+        let right: Vec<f32> = left.samples().iter().map(|s| s * 0.5).collect();
+        let mut source = WavAudioSource::new(wav(&[left.samples(), &right], 44100)).unwrap();
+        assert_eq!(source.channels(), 2);
+        assert_eq!(source.sample_rate(), 44100);
+
+        // One sample per frame, not per channel, so not an octave flat
+        let samples = read_all(&mut source);
+        assert_eq!(samples.len(), left.samples().len());
+        assert!((pitch_of(&samples) - 440.0).abs() < 0.5);
+        assert!((samples[100] - left.samples()[100] * 0.75).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_wav_stereo_channel() {
+        let left = TestAudioSource::sine(440.0, 0.2, 44100);
+        let right = TestAudioSource::sine(330.0, 0.2, 44100);
+        let file = || wav(&[left.samples(), right.samples()], 44100);
+
+        let mut source = WavAudioSource::new(file()).unwrap().with_channel(1);
+        assert!((pitch_of(&read_all(&mut source)) - 330.0).abs() < 0.5);
+        let mut source = WavAudioSource::new(file()).unwrap().with_channel(0);
+        assert!((pitch_of(&read_all(&mut source)) - 440.0).abs() < 0.5);
+    }
+
+    /// Delivers at most `chunk` bytes per read, like a pipe.
+    struct Trickle {
+        bytes: std::io::Cursor<Vec<u8>>,
+        chunk: usize,
+    }
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(self.chunk);
+            self.bytes.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn test_raw_pcm() {
+        let a4 = TestAudioSource::sine(440.0, 0.2, 44100);
+        let f32le: Vec<u8> = a4.samples().iter().flat_map(|s| s.to_le_bytes()).collect();
+        let s16le: Vec<u8> = a4
+            .samples()
+            .iter()
+            .flat_map(|s| ((s * i16::MAX as f32) as i16).to_le_bytes())
+            .collect();
+
+        for (format, bytes) in [(PcmFormat::F32Le, f32le), (PcmFormat::S16Le, s16le)] {
+            // Reads ending partway through a sample
+            let reader = Trickle {
+                bytes: std::io::Cursor::new(bytes),
+                chunk: 333,
+            };
+            let mut source = StdinAudioSource::new(reader, format, 44100);
+            assert_eq!(source.sample_rate(), 44100);
+
+            let samples = read_all(&mut source);
+            assert_eq!(samples.len(), a4.samples().len(), "{:?}", format);
+            assert!((pitch_of(&samples) - 440.0).abs() < 0.5, "{:?}", format);
+            for (read, written) in samples.iter().zip(a4.samples()) {
+                assert!((read - written).abs() < 1e-4, "{:?}", format);
+            }
+        }
+    }
+
+    #[test]
+    fn test_raw_pcm_trailing_bytes() {
+        // Two samples and half of a third
+        let bytes: Vec<u8> = [0.5_f32, -0.25]
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .chain([0, 0])
+            .collect();
+        let mut source =
+            StdinAudioSource::new(std::io::Cursor::new(bytes), PcmFormat::F32Le, 48000);
+        let mut buffer = [0.0; 8];
+        assert_eq!(source.read_samples(&mut buffer), 2);
+        assert_eq!(&buffer[..2], &[0.5, -0.25]);
+        assert!(!source.is_exhausted());
+        assert_eq!(source.read_samples(&mut buffer), 0);
+        assert!(source.is_exhausted());
+        assert_eq!(source.read_samples(&mut buffer), 0);
+    }
+
+    #[test]
+    fn test_wav_sink_round_trip() {
+        let a4 = TestAudioSource::sine(440.0, 0.2, 48000);
+        for format in [PcmFormat::F32Le, PcmFormat::S16Le] {
+            let mut cursor = std::io::Cursor::new(Vec::new());
+            let mut sink = WavAudioSink::new(&mut cursor, 48000, 1, format).unwrap();
+            assert_eq!(sink.sample_rate(), 48000);
+            assert_eq!(sink.channels(), 1);
+            // In pieces, as a stream would
+            for chunk in a4.samples().chunks(1000) {
+                sink.write_samples(chunk);
+            }
+            sink.finalize().unwrap();
+
+            cursor.set_position(0);
+            let mut source = WavAudioSource::new(cursor).unwrap();
+            assert_eq!(source.sample_rate(), 48000);
+            assert_eq!(source.duration_samples(), a4.samples().len());
+            let samples = read_all(&mut source);
+            assert_eq!(samples.len(), a4.samples().len());
+            let pitch = PitchDetector::new(48000)
+                .detect(&samples[..4096])
+                .expect("Should detect pitch")
+                .frequency;
+            assert!((pitch - 440.0).abs() < 0.5, "{:?}: {}", format, pitch);
+        }
+    }
+
     #[test]
     fn test_audio_sink_collects() {
         let mut sink = TestAudioSink::new(44100);
@@ -248,4 +1067,13 @@ mod tests {
         sink.write_samples(&[0.3, 0.4]);
         assert_eq!(sink.samples(), &[0.1, 0.2, 0.3, 0.4]);
     }
+
+    #[test]
+    fn test_stereo_sink_splits_channels() {
+        let mut sink = TestAudioSink::new(44100).with_channels(2);
+        sink.write_samples(&[0.1, -0.1, 0.2, -0.2]);
+        assert_eq!(sink.channels(), 2);
+        assert_eq!(sink.channel(0), vec![0.1, 0.2]);
+        assert_eq!(sink.channel(1), vec![-0.1, -0.2]);
+    }
 }
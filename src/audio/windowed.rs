@@ -0,0 +1,196 @@
+//! Fixed-size, overlapping windows over an audio source.
+//!
+//! Sources return however many samples they have. Analysis wants windows of
+//! a fixed size a fixed hop apart, so the adapter buffers reads until the
+//! next window is complete.
+
+use super::traits::AudioSource;
+
+/// Reads an audio source as overlapping windows.
+pub struct Windowed<S: AudioSource> {
+    source: S,
+    window_size: usize,
+    hop_size: usize,
+    /// The current window, or the part of the next one read so far.
+    buffer: Vec<f32>,
+    /// Whether a window has been handed out, so the next one moves on a hop.
+    started: bool,
+    /// Samples read from the source in total.
+    read: u64,
+    /// Index of the sample just after the last window handed out.
+    window_end: u64,
+}
+
+impl<S: AudioSource> Windowed<S> {
+    /// Wrap `source`, producing windows of `window_size` samples `hop_size`
+    /// apart. The hop is kept between one sample and the window size.
+    pub fn new(source: S, window_size: usize, hop_size: usize) -> Self {
+        let window_size = window_size.max(1);
+        Self {
+            source,
+            window_size,
+            hop_size: hop_size.clamp(1, window_size),
+            buffer: Vec::with_capacity(window_size),
+            started: false,
+            read: 0,
+            window_end: 0,
+        }
+    }
+
+    /// Window size in samples.
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    /// Hop between windows in samples.
+    pub fn hop_size(&self) -> usize {
+        self.hop_size
+    }
+
+    /// Sample rate of the source in Hz.
+    pub fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    /// Get the next window, or `None` if the source runs out before
+    /// completing it. A live source may have more later; see
+    /// [`is_exhausted`](Self::is_exhausted).
+    pub fn next_window(&mut self) -> Option<&[f32]> {
+        if self.pending_hop() > 0 {
+            self.buffer.drain(..self.hop_size);
+        }
+
+        while self.buffer.len() < self.window_size {
+            let filled = self.buffer.len();
+            self.buffer.resize(self.window_size, 0.0);
+            let read = self.source.read_samples(&mut self.buffer[filled..]);
+            self.buffer.truncate(filled + read);
+            self.read += read as u64;
+            if read == 0 {
+                return None;
+            }
+        }
+
+        self.started = true;
+        self.window_end = self.read;
+        Some(&self.buffer)
+    }
+
+    /// Index of the sample just after the last window handed out, counted
+    /// from the start of the source.
+    pub fn window_end(&self) -> u64 {
+        self.window_end
+    }
+
+    /// Whether the source has ended without enough samples left for
+    /// another window.
+    pub fn is_exhausted(&self) -> bool {
+        self.source.is_exhausted() && self.buffer.len() - self.pending_hop() < self.window_size
+    }
+
+    /// Samples to drop from the buffer before the next window.
+    fn pending_hop(&self) -> usize {
+        if self.started && self.buffer.len() == self.window_size {
+            self.hop_size
+        } else {
+            0
+        }
+    }
+
+    /// Get the source back.
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::traits::TestAudioSource;
+
+    /// Delivers at most the next of `sizes` samples per read.
+    struct Chunked {
+        source: TestAudioSource,
+        sizes: Vec<usize>,
+        reads: usize,
+    }
+
+    impl AudioSource for Chunked {
+        fn read_samples(&mut self, buffer: &mut [f32]) -> usize {
+            let size = self.sizes[self.reads % self.sizes.len()];
+            self.reads += 1;
+            let len = buffer.len().min(size);
+            self.source.read_samples(&mut buffer[..len])
+        }
+
+        fn sample_rate(&self) -> u32 {
+            self.source.sample_rate()
+        }
+
+        fn is_exhausted(&self) -> bool {
+            self.source.is_exhausted()
+        }
+    }
+
+    #[test]
+    fn test_windows_from_awkward_chunks() {
+        let samples: Vec<f32> = (0..10_000).map(|i| i as f32).collect();
+        let source = Chunked {
+            source: TestAudioSource::new(samples.clone(), 44100),
+            sizes: vec![1, 313, 977, 64, 2049, 7],
+            reads: 0,
+        };
+        let mut windowed = Windowed::new(source, 1024, 300);
+        assert_eq!(windowed.sample_rate(), 44100);
+
+        let mut count = 0;
+        while let Some(window) = windowed.next_window() {
+            let start = count * 300;
+            assert_eq!(window, &samples[start..start + 1024], "Window {}", count);
+            assert_eq!(windowed.window_end(), (start + 1024) as u64);
+            count += 1;
+        }
+        assert_eq!(count, (10_000 - 1024) / 300 + 1);
+        // Exhausted for good
+        assert!(windowed.is_exhausted());
+        assert!(windowed.next_window().is_none());
+    }
+
+    #[test]
+    fn test_stalled_source_not_exhausted() {
+        // Every other read comes back empty, like a live source between
+        // callbacks
+        let source = Chunked {
+            source: TestAudioSource::new(vec![0.0; 1000], 44100),
+            sizes: vec![0, 300],
+            reads: 0,
+        };
+        let mut windowed = Windowed::new(source, 400, 400);
+
+        let mut windows = 0;
+        let mut stalls = 0;
+        while !windowed.is_exhausted() {
+            match windowed.next_window() {
+                Some(_) => windows += 1,
+                None => stalls += 1,
+            }
+        }
+        assert_eq!(windows, 2);
+        assert!(stalls > 2);
+    }
+
+    #[test]
+    fn test_hop_kept_within_window() {
+        let source = TestAudioSource::new((0..10).map(|i| i as f32).collect(), 44100);
+        let mut windowed = Windowed::new(source, 4, 0);
+        assert_eq!(windowed.hop_size(), 1);
+        assert_eq!(windowed.next_window(), Some(&[0.0, 1.0, 2.0, 3.0][..]));
+        assert_eq!(windowed.next_window(), Some(&[1.0, 2.0, 3.0, 4.0][..]));
+
+        let source = TestAudioSource::new((0..10).map(|i| i as f32).collect(), 44100);
+        let mut windowed = Windowed::new(source, 4, 100);
+        assert_eq!(windowed.hop_size(), 4);
+        let windows = std::iter::from_fn(|| windowed.next_window().map(<[f32]>::to_vec));
+        assert_eq!(windows.count(), 2);
+    }
+}
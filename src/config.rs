@@ -3,8 +3,25 @@
 use clap::{Parser, Subcommand};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::audio::{
+    CuePlayer, Gain, HighPass, NoiseSettings, PcmFormat, PitchLock, PitchMethod, PitchSmoother,
+    ReferenceChannels, ReferenceFeedback, ReferenceTone, Waveform,
+};
+use crate::log::Level;
+use crate::tuning::order::{OrderStrategy, StringOrder};
+use crate::tuning::scheme::parse_root;
+use crate::tuning::stretch::{OctaveType, PianoType, MAX_INTENSITY};
+use crate::tuning::temperament::DEFAULT_A4_PRESETS;
+use crate::tuning::{Instrument, Note, TemperamentScheme};
+use crate::ui::components::{DisplayUnit, Readout};
+use crate::ui::screens::setup::{MAX_OFFSET, MAX_SEMITONES};
+use crate::ui::strings::locale_from_env;
+use crate::ui::App;
 
 /// CLI Piano Tuner with guided coaching.
 #[derive(Parser, Debug)]
@@ -29,6 +46,49 @@ pub struct Args {
     /// Enable audio confirmation beep.
     #[arg(long)]
     pub beep: bool,
+
+    /// Print the status as plain lines instead of drawing screens, for
+    /// screen readers and minimal terminals.
+    #[arg(long)]
+    pub plain: bool,
+
+    /// Input device name (see the devices screen for the list).
+    #[arg(long, value_name = "NAME")]
+    pub device: Option<String>,
+
+    /// Record the microphone input of each session to a WAV file.
+    #[arg(long)]
+    pub record: bool,
+
+    /// Capture sample rate in Hz (the device's default if unset, 44100
+    /// with --stdin).
+    #[arg(long, visible_alias = "rate", value_name = "HZ")]
+    pub sample_rate: Option<u32>,
+
+    /// Read mono raw PCM audio from stdin instead of an input device.
+    #[arg(long)]
+    pub stdin: bool,
+
+    /// Sample encoding of the audio read with --stdin.
+    #[arg(long, value_enum, value_name = "FORMAT", default_value_t = PcmFormat::F32Le)]
+    pub format: PcmFormat,
+
+    /// Capture buffer size in frames; smaller lowers the latency.
+    #[arg(long, value_name = "FRAMES")]
+    pub buffer_frames: Option<u32>,
+
+    /// Notch out mains hum at this frequency in Hz (50 or 60) and its
+    /// harmonics; 0 turns it off.
+    #[arg(long, value_name = "HZ")]
+    pub hum_hz: Option<f32>,
+
+    /// Write a debug log to this file.
+    #[arg(long, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Log level for --log-file.
+    #[arg(long, value_enum, default_value_t = Level::Info)]
+    pub log_level: Level,
 }
 
 /// Subcommands.
@@ -65,9 +125,167 @@ pub struct Config {
     /// Enable beep on lock.
     #[serde(default)]
     pub beep: bool,
+    /// Peak volume of the lock and confirm beeps (0.0 to 1.0).
+    #[serde(default = "default_beep_volume")]
+    pub beep_volume: f32,
     /// Default tuning mode ("concert" or "quick").
     #[serde(default = "default_mode")]
     pub default_mode: String,
+    /// A4 presets offered on the calibration and mode select screens.
+    #[serde(default = "default_a4_presets")]
+    pub a4_presets: Vec<f32>,
+    /// Instrument selected on mode select ("piano", "harpsichord" or
+    /// "fortepiano").
+    #[serde(default)]
+    pub instrument: Instrument,
+    /// Temperament scheme, instead of the instrument's default.
+    #[serde(default)]
+    pub temperament: Option<TemperamentScheme>,
+    /// Root key the temperament scheme is laid from ("C", "G", "Bb", ...).
+    #[serde(default = "default_temperament_root")]
+    pub temperament_root: String,
+    /// Custom temperament file selected instead of the scheme.
+    #[serde(default)]
+    pub temperament_file: Option<PathBuf>,
+    /// Tune to the instrument's stretch curve (Railsback) rather than the
+    /// bare temperament.
+    #[serde(default)]
+    pub stretch: bool,
+    /// Stretch intensity as a factor of the default curve (0.0 to 1.5).
+    #[serde(default = "default_stretch_intensity")]
+    pub stretch_intensity: f32,
+    /// Type of piano, which picks its stretch preset ("spinet", "console",
+    /// "upright", "baby-grand", "semi-concert", "concert-grand" or
+    /// "legacy").
+    #[serde(default)]
+    pub piano_type: PianoType,
+    /// Name of a saved stretch curve to select instead of the piano type's
+    /// preset.
+    #[serde(default)]
+    pub stretch_curve: Option<String>,
+    /// Partials the octaves are tuned beatless at when the stretch is
+    /// worked out from the inharmonicity ("2:1", "4:2", "6:3" or
+    /// "by-register").
+    #[serde(default)]
+    pub octave_type: OctaveType,
+    /// Inharmonicity coefficient measured per note, by note name ("A0").
+    #[serde(default)]
+    pub inharmonicity: BTreeMap<String, f32>,
+    /// Whole semitones every target is transposed by, for a piano kept
+    /// off concert pitch.
+    #[serde(default)]
+    pub transpose_semitones: i32,
+    /// Cents every target is moved by on top of the semitones.
+    #[serde(default)]
+    pub transpose_cents: f32,
+    /// Order in which trichord strings are tuned.
+    #[serde(default)]
+    pub string_order: StringOrder,
+    /// Sequence the keys are tuned in ("treble-first",
+    /// "aural-temperament-first" or "chromatic").
+    #[serde(default)]
+    pub tuning_order: OrderStrategy,
+    /// Seconds to hold the last stable reading after a note decays.
+    #[serde(default = "default_hold_secs")]
+    pub hold_secs: f32,
+    /// Only confirm a sounding step once the readings are steady.
+    #[serde(default)]
+    pub require_steady: bool,
+    /// Unit of the value below the meter ("cents", "hz" or "auto").
+    #[serde(default)]
+    pub display_unit: DisplayUnit,
+    /// Decimal places of the value below the meter (0 to 2).
+    #[serde(default = "default_precision")]
+    pub precision: u8,
+    /// How detection handles the reference tone ("pause", "filter" or
+    /// "cancel").
+    #[serde(default)]
+    pub reference_feedback: ReferenceFeedback,
+    /// Where the reference tone plays ("mono" or "split").
+    #[serde(default)]
+    pub reference_channels: ReferenceChannels,
+    /// Waveform of the reference tone ("sine", "triangle", "sawtooth" or
+    /// "piano-like").
+    #[serde(default)]
+    pub reference_waveform: Waveform,
+    /// Play reference notes below 100 Hz with strong upper partials, so
+    /// they are audible on small speakers.
+    #[serde(default = "default_bass_reinforcement")]
+    pub bass_reinforcement: bool,
+    /// Volume of the reference tone (0.0 to 1.0).
+    #[serde(default = "default_reference_volume")]
+    pub reference_volume: f32,
+    /// Fade in and out of the reference tone in milliseconds (1 to 100).
+    #[serde(default = "default_reference_fade_ms")]
+    pub reference_fade_ms: u32,
+    /// Seconds after which the reference tone stops by itself (0 plays
+    /// until stopped).
+    #[serde(default = "default_reference_timeout_secs")]
+    pub reference_timeout_secs: u64,
+    /// MIDI note below which pitch is measured via the partials.
+    #[serde(default = "default_partials_below")]
+    pub partials_below: u8,
+    /// Pitch detection algorithm ("yin" or "mpm").
+    #[serde(default)]
+    pub pitch_algorithm: PitchMethod,
+    /// Frames the detected pitch is median-filtered over (1 disables).
+    #[serde(default = "default_smoothing_frames")]
+    pub smoothing_frames: usize,
+    /// Milliseconds a pitch must be heard before it is shown, so the
+    /// hammer's attack doesn't move the meter (0 shows it at once).
+    #[serde(default = "default_attack_ms")]
+    pub attack_ms: u32,
+    /// Confident frames needed before a reading is shown.
+    #[serde(default = "default_acquire_frames")]
+    pub acquire_frames: usize,
+    /// Missed frames needed before a reading is dropped.
+    #[serde(default = "default_release_frames")]
+    pub release_frames: usize,
+    /// Input level (RMS) above which audio counts as activity.
+    #[serde(default = "default_noise_gate")]
+    pub noise_gate: f32,
+    /// Confidence below which a frame counts as no pitch.
+    #[serde(default = "default_min_confidence")]
+    pub min_confidence: f32,
+    /// Cutoff in Hz of the high-pass filter on the input (0 disables).
+    #[serde(default = "default_high_pass_hz")]
+    pub high_pass_hz: f32,
+    /// Mains frequency in Hz whose hum is notched out (0 disables).
+    #[serde(default)]
+    pub hum_hz: f32,
+    /// Derive A4 from a target captured from another instrument.
+    #[serde(default)]
+    pub capture_sets_a4: bool,
+    /// Seconds without input before going idle (0 disables).
+    #[serde(default = "default_idle_secs")]
+    pub idle_secs: u64,
+    /// Exclude idle time from the session duration.
+    #[serde(default)]
+    pub pause_when_idle: bool,
+    /// Input device name (the system default if unset).
+    #[serde(default)]
+    pub input_device: Option<String>,
+    /// Capture sample rate in Hz (the device's default if unset).
+    #[serde(default)]
+    pub sample_rate: Option<u32>,
+    /// Capture buffer size in frames (the host's choice if unset).
+    #[serde(default)]
+    pub buffer_frames: Option<u32>,
+    /// Software gain on the input in dB, set with F3/F4 while tuning.
+    #[serde(default)]
+    pub input_gain_db: f32,
+    /// Record the microphone input of each session to a WAV file.
+    #[serde(default)]
+    pub record: bool,
+    /// Interface language, e.g. "de" (taken from LANG if unset).
+    #[serde(default)]
+    pub locale: Option<String>,
+    /// Name of the piano, for the session hook.
+    #[serde(default)]
+    pub piano_name: Option<String>,
+    /// Command run in the background when a session completes.
+    #[serde(default)]
+    pub on_session_complete: Option<String>,
 }
 
 fn default_a4() -> f32 {
@@ -78,17 +296,141 @@ fn default_tolerance() -> f32 {
     5.0
 }
 
+fn default_bass_reinforcement() -> bool {
+    true
+}
+
+fn default_reference_volume() -> f32 {
+    1.0
+}
+
+fn default_reference_fade_ms() -> u32 {
+    ReferenceTone::DEFAULT_FADE.as_millis() as u32
+}
+
+fn default_reference_timeout_secs() -> u64 {
+    App::DEFAULT_REFERENCE_TIMEOUT.as_secs()
+}
+
+fn default_beep_volume() -> f32 {
+    CuePlayer::DEFAULT_VOLUME
+}
+
+fn default_stretch_intensity() -> f32 {
+    1.0
+}
+
+fn default_temperament_root() -> String {
+    "C".to_string()
+}
+
 fn default_mode() -> String {
     "concert".to_string()
 }
 
+fn default_a4_presets() -> Vec<f32> {
+    DEFAULT_A4_PRESETS.to_vec()
+}
+
+fn default_hold_secs() -> f32 {
+    2.5
+}
+
+fn default_precision() -> u8 {
+    Readout::DEFAULT_PRECISION
+}
+
+fn default_smoothing_frames() -> usize {
+    PitchSmoother::DEFAULT_WINDOW
+}
+
+fn default_attack_ms() -> u32 {
+    50
+}
+
+fn default_acquire_frames() -> usize {
+    PitchLock::DEFAULT_ACQUIRE
+}
+
+fn default_release_frames() -> usize {
+    PitchLock::DEFAULT_RELEASE
+}
+
+fn default_noise_gate() -> f32 {
+    0.01
+}
+
+fn default_min_confidence() -> f32 {
+    0.5
+}
+
+fn default_high_pass_hz() -> f32 {
+    HighPass::DEFAULT_CUTOFF
+}
+
+fn default_partials_below() -> u8 {
+    24
+}
+
+fn default_idle_secs() -> u64 {
+    120
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             a4: default_a4(),
             tolerance: default_tolerance(),
             beep: false,
+            beep_volume: default_beep_volume(),
             default_mode: default_mode(),
+            a4_presets: default_a4_presets(),
+            instrument: Instrument::default(),
+            stretch: false,
+            stretch_intensity: default_stretch_intensity(),
+            piano_type: PianoType::default(),
+            stretch_curve: None,
+            transpose_semitones: 0,
+            transpose_cents: 0.0,
+            octave_type: OctaveType::default(),
+            inharmonicity: BTreeMap::new(),
+            temperament: None,
+            temperament_root: default_temperament_root(),
+            temperament_file: None,
+            string_order: StringOrder::default(),
+            tuning_order: OrderStrategy::default(),
+            hold_secs: default_hold_secs(),
+            require_steady: false,
+            display_unit: DisplayUnit::default(),
+            precision: default_precision(),
+            reference_feedback: ReferenceFeedback::default(),
+            reference_channels: ReferenceChannels::default(),
+            reference_waveform: Waveform::default(),
+            bass_reinforcement: default_bass_reinforcement(),
+            reference_volume: default_reference_volume(),
+            reference_fade_ms: default_reference_fade_ms(),
+            reference_timeout_secs: default_reference_timeout_secs(),
+            partials_below: default_partials_below(),
+            pitch_algorithm: PitchMethod::default(),
+            smoothing_frames: default_smoothing_frames(),
+            attack_ms: default_attack_ms(),
+            acquire_frames: default_acquire_frames(),
+            release_frames: default_release_frames(),
+            noise_gate: default_noise_gate(),
+            min_confidence: default_min_confidence(),
+            high_pass_hz: default_high_pass_hz(),
+            hum_hz: 0.0,
+            capture_sets_a4: false,
+            idle_secs: default_idle_secs(),
+            pause_when_idle: false,
+            input_device: None,
+            sample_rate: None,
+            buffer_frames: None,
+            input_gain_db: 0.0,
+            record: false,
+            locale: None,
+            piano_name: None,
+            on_session_complete: None,
         }
     }
 }
@@ -131,18 +473,153 @@ impl Config {
         Ok(())
     }
 
+    /// Store the input gain in the config file, leaving the rest of the
+    /// file, comments included, as it is.
+    pub fn save_input_gain(db: f32) -> anyhow::Result<()> {
+        Self::save_values(&[("input_gain_db", format!("{:.1}", db))])
+    }
+
+    /// Store the reference tone volume in the config file, like
+    /// [`save_input_gain`](Self::save_input_gain).
+    pub fn save_reference_volume(volume: f32) -> anyhow::Result<()> {
+        Self::save_values(&[("reference_volume", format!("{:.2}", volume))])
+    }
+
+    /// Store settings suggested by a room noise measurement in the config
+    /// file, like [`save_input_gain`](Self::save_input_gain).
+    pub fn save_noise_settings(settings: NoiseSettings) -> anyhow::Result<()> {
+        Self::save_values(&[
+            ("noise_gate", format!("{:.4}", settings.noise_gate)),
+            ("min_confidence", format!("{:.2}", settings.min_confidence)),
+        ])
+    }
+
+    /// Set top-level values in the config file, keeping everything else.
+    fn save_values(values: &[(&str, String)]) -> anyhow::Result<()> {
+        let path = Self::config_path()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
+
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        // A file that doesn't parse would load as the defaults; don't make
+        // that permanent by writing over it
+        toml::from_str::<Self>(&content)?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let content = values.iter().fold(content, |content, (key, value)| {
+            set_top_level(&content, key, value)
+        });
+        fs::write(&path, content)?;
+
+        Ok(())
+    }
+
     /// Merge CLI args with config, returning effective settings.
     pub fn merge_with_args(&self, args: &Args) -> EffectiveConfig {
         EffectiveConfig {
             a4: args.a4.unwrap_or(self.a4),
             tolerance: self.tolerance,
             beep: args.beep || self.beep,
+            beep_volume: self.beep_volume.clamp(0.0, 1.0),
             quick_mode: args.quick || self.default_mode == "quick",
             resume: args.resume,
+            a4_presets: self.a4_presets.clone(),
+            instrument: self.instrument,
+            temperament: self.temperament,
+            temperament_root: parse_root(&self.temperament_root).unwrap_or(0),
+            temperament_file: self.temperament_file.clone(),
+            stretch: self.stretch,
+            stretch_intensity: self.stretch_intensity.clamp(0.0, MAX_INTENSITY),
+            piano_type: self.piano_type,
+            stretch_curve: self.stretch_curve.clone(),
+            octave_type: self.octave_type,
+            inharmonicity: self
+                .inharmonicity
+                .iter()
+                .filter_map(|(name, &b)| Note::from_name(name).map(|note| (note.midi, b)))
+                .collect(),
+            transpose: (self
+                .transpose_semitones
+                .clamp(-MAX_SEMITONES, MAX_SEMITONES)
+                * 100) as f32
+                + self.transpose_cents.clamp(-MAX_OFFSET, MAX_OFFSET),
+            string_order: self.string_order,
+            tuning_order: self.tuning_order,
+            hold_time: Duration::from_secs_f32(self.hold_secs.clamp(0.0, 10.0)),
+            require_steady: self.require_steady,
+            readout: Readout::new(self.display_unit, self.precision),
+            reference_feedback: self.reference_feedback,
+            reference_channels: self.reference_channels,
+            reference_waveform: self.reference_waveform,
+            bass_reinforcement: self.bass_reinforcement,
+            reference_volume: self.reference_volume.clamp(0.0, 1.0),
+            reference_fade: Duration::from_millis(self.reference_fade_ms.clamp(1, 100) as u64),
+            reference_timeout: Duration::from_secs(self.reference_timeout_secs),
+            partials_below: self.partials_below,
+            pitch_algorithm: self.pitch_algorithm,
+            smoothing_frames: self.smoothing_frames.max(1),
+            attack_delay: Duration::from_millis(self.attack_ms as u64),
+            acquire_frames: self.acquire_frames.max(1),
+            release_frames: self.release_frames.max(1),
+            noise_gate: self.noise_gate.max(0.0),
+            min_confidence: self.min_confidence.clamp(0.0, 1.0),
+            high_pass: (self.high_pass_hz > 0.0).then_some(self.high_pass_hz),
+            hum_filter: args.hum_hz.or(Some(self.hum_hz)).filter(|&hz| hz > 0.0),
+            capture_sets_a4: self.capture_sets_a4,
+            idle_timeout: Duration::from_secs(self.idle_secs),
+            pause_when_idle: self.pause_when_idle,
+            input_device: args.device.clone().or_else(|| self.input_device.clone()),
+            sample_rate: args.sample_rate.or(self.sample_rate).filter(|&hz| hz > 0),
+            buffer_frames: args.buffer_frames.or(self.buffer_frames).filter(|&n| n > 0),
+            stdin: args.stdin.then_some(args.format),
+            input_gain_db: Gain::from_db(self.input_gain_db).db(),
+            record: args.record || self.record,
+            locale: self.locale.clone().or_else(locale_from_env),
+            piano_name: self.piano_name.clone(),
+            on_session_complete: self.on_session_complete.clone(),
+            plain: args.plain,
+            log_file: args.log_file.clone(),
+            log_level: args.log_level,
         }
     }
 }
 
+/// Set a top-level `key = value` line in TOML text: replace the existing
+/// one, or add it before the first table.
+fn set_top_level(content: &str, key: &str, value: &str) -> String {
+    let line = format!("{} = {}", key, value);
+    let mut lines: Vec<&str> = content.lines().collect();
+    let table = lines
+        .iter()
+        .position(|l| l.trim_start().starts_with('['))
+        .unwrap_or(lines.len());
+
+    let existing = lines[..table].iter().position(|l| {
+        l.split_once('=')
+            .is_some_and(|(name, _)| name.trim() == key)
+    });
+    match existing {
+        Some(i) => lines[i] = &line,
+        None => {
+            let mut at = table;
+            // Keep the blank line before the table with the table
+            while at > 0 && lines[at - 1].trim().is_empty() && at < lines.len() {
+                at -= 1;
+            }
+            lines.insert(at, &line);
+        }
+    }
+
+    let mut text = lines.join("\n");
+    text.push('\n');
+    text
+}
+
 /// Effective configuration after merging config file and CLI args.
 #[derive(Debug, Clone)]
 pub struct EffectiveConfig {
@@ -152,8 +629,189 @@ pub struct EffectiveConfig {
     pub tolerance: f32,
     /// Enable beep on lock.
     pub beep: bool,
+    /// Peak volume of the beeps.
+    pub beep_volume: f32,
     /// Use quick tune mode.
     pub quick_mode: bool,
     /// Resume previous session.
     pub resume: bool,
+    /// A4 presets.
+    pub a4_presets: Vec<f32>,
+    /// Instrument selected by default.
+    pub instrument: Instrument,
+    /// Temperament scheme override.
+    pub temperament: Option<TemperamentScheme>,
+    /// Pitch class the temperament scheme is laid from (C = 0).
+    pub temperament_root: u8,
+    /// Custom temperament file selected instead of the scheme.
+    pub temperament_file: Option<PathBuf>,
+    /// Whether targets follow the instrument's stretch curve.
+    pub stretch: bool,
+    /// Factor the stretch curve is scaled by.
+    pub stretch_intensity: f32,
+    /// Type of piano, whose stretch preset is followed.
+    pub piano_type: PianoType,
+    /// Name of the saved stretch curve selected.
+    pub stretch_curve: Option<String>,
+    /// Octave type the stretch is worked out at.
+    pub octave_type: OctaveType,
+    /// Inharmonicity coefficient per MIDI note.
+    pub inharmonicity: BTreeMap<u8, f32>,
+    /// Cents every target is transposed by.
+    pub transpose: f32,
+    /// Trichord string order.
+    pub string_order: StringOrder,
+    /// Sequence the keys are tuned in.
+    pub tuning_order: OrderStrategy,
+    /// Hold time for the last stable reading.
+    pub hold_time: Duration,
+    /// Require steady readings to confirm.
+    pub require_steady: bool,
+    /// Format of the value below the meter.
+    pub readout: Readout,
+    /// Reference tone handling during detection.
+    pub reference_feedback: ReferenceFeedback,
+    /// Where the reference tone plays.
+    pub reference_channels: ReferenceChannels,
+    /// Waveform of the reference tone.
+    pub reference_waveform: Waveform,
+    /// Whether low reference notes are reinforced with their partials.
+    pub bass_reinforcement: bool,
+    /// Volume of the reference tone (0.0 to 1.0).
+    pub reference_volume: f32,
+    /// Fade in and out of the reference tone.
+    pub reference_fade: Duration,
+    /// Time after which the reference tone stops by itself; zero plays
+    /// until stopped.
+    pub reference_timeout: Duration,
+    /// MIDI note below which pitch is measured via the partials.
+    pub partials_below: u8,
+    /// Pitch detection algorithm.
+    pub pitch_algorithm: PitchMethod,
+    /// Frames of median filtering.
+    pub smoothing_frames: usize,
+    /// Time a pitch must be heard before it is shown.
+    pub attack_delay: Duration,
+    /// Confident frames before a reading is shown.
+    pub acquire_frames: usize,
+    /// Missed frames before a reading is dropped.
+    pub release_frames: usize,
+    /// Input level (RMS) above which audio counts as activity.
+    pub noise_gate: f32,
+    /// Confidence below which a frame counts as no pitch.
+    pub min_confidence: f32,
+    /// High-pass cutoff on the input in Hz, if filtered.
+    pub high_pass: Option<f32>,
+    /// Mains frequency whose hum is notched out, if filtered.
+    pub hum_filter: Option<f32>,
+    /// Derive A4 from a captured target.
+    pub capture_sets_a4: bool,
+    /// Inactivity before going idle (zero disables).
+    pub idle_timeout: Duration,
+    /// Exclude idle time from the session duration.
+    pub pause_when_idle: bool,
+    /// Input device name.
+    pub input_device: Option<String>,
+    /// Requested capture sample rate.
+    pub sample_rate: Option<u32>,
+    /// Requested capture buffer size in frames.
+    pub buffer_frames: Option<u32>,
+    /// Format of raw audio read from stdin instead of a device, if any.
+    pub stdin: Option<PcmFormat>,
+    /// Software gain on the input in dB.
+    pub input_gain_db: f32,
+    /// Record the microphone input of each session.
+    pub record: bool,
+    /// Interface language.
+    pub locale: Option<String>,
+    /// Name of the piano.
+    pub piano_name: Option<String>,
+    /// Session hook command template.
+    pub on_session_complete: Option<String>,
+    /// Print plain status lines instead of drawing screens.
+    pub plain: bool,
+    /// Log file, if logging is enabled.
+    pub log_file: Option<PathBuf>,
+    /// Log level.
+    pub log_level: Level,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_top_level_keeps_comments() {
+        let content = "# Reference pitch\na4 = 440.0\n\n# Gain\ninput_gain_db = 0.0\n";
+        assert_eq!(
+            set_top_level(content, "input_gain_db", "6.0"),
+            "# Reference pitch\na4 = 440.0\n\n# Gain\ninput_gain_db = 6.0\n"
+        );
+
+        // Added before the first table, not inside it
+        let content = "a4 = 442.0\n\n[keys]\ninput_gain_db = 1\n";
+        let updated = set_top_level(content, "input_gain_db", "-4.0");
+        assert_eq!(
+            updated,
+            "a4 = 442.0\ninput_gain_db = -4.0\n\n[keys]\ninput_gain_db = 1\n"
+        );
+        assert!(toml::from_str::<toml::Table>(&updated).is_ok());
+
+        assert_eq!(
+            set_top_level("", "input_gain_db", "2.0"),
+            "input_gain_db = 2.0\n"
+        );
+    }
+
+    #[test]
+    fn test_input_gain_loaded() {
+        let config: Config = toml::from_str("input_gain_db = 12.0").unwrap();
+        assert_eq!(config.input_gain_db, 12.0);
+        assert_eq!(Config::default().input_gain_db, 0.0);
+    }
+
+    #[test]
+    fn test_stdin_args() {
+        let args = Args::parse_from(["onkey", "--stdin", "--format", "s16le", "--rate", "48000"]);
+        let effective = Config::default().merge_with_args(&args);
+        assert_eq!(effective.stdin, Some(PcmFormat::S16Le));
+        assert_eq!(effective.sample_rate, Some(48000));
+
+        let args = Args::parse_from(["onkey", "--stdin"]);
+        let effective = Config::default().merge_with_args(&args);
+        assert_eq!(effective.stdin, Some(PcmFormat::F32Le));
+        assert_eq!(effective.sample_rate, None);
+
+        let effective = Config::default().merge_with_args(&Args::parse_from(["onkey"]));
+        assert_eq!(effective.stdin, None);
+    }
+
+    #[test]
+    fn test_attack_delay_loaded() {
+        let config: Config = toml::from_str("attack_ms = 120").unwrap();
+        let effective = config.merge_with_args(&Args::parse_from(["onkey"]));
+        assert_eq!(effective.attack_delay, Duration::from_millis(120));
+        assert_eq!(Config::default().attack_ms, 50);
+    }
+
+    #[test]
+    fn test_inharmonicity_loaded() {
+        let config: Config = toml::from_str(
+            "octave_type = \"6:3\"
+
+[inharmonicity]
+A0 = 0.0004
+A4 = 0.0004
+X9 = 0.1
+",
+        )
+        .unwrap();
+        let effective = config.merge_with_args(&Args::parse_from(["onkey"]));
+        assert_eq!(effective.octave_type, OctaveType::SixThree);
+        assert_eq!(
+            effective.inharmonicity.into_iter().collect::<Vec<_>>(),
+            [(21, 0.0004), (69, 0.0004)]
+        );
+        assert_eq!(Config::default().octave_type, OctaveType::ByRegister);
+    }
 }
@@ -0,0 +1,174 @@
+//! Shell hook run when a tuning session completes.
+//!
+//! The session is exported as JSON to a file, with the target of every key.
+//! The path is substituted into the configured command, and the command
+//! runs in the background with its output discarded so it never disturbs
+//! the TUI.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
+
+use serde::Serialize;
+
+use crate::tuning::session::Session;
+use crate::tuning::TargetEntry;
+
+/// Command template run when a session completes.
+///
+/// Supported variables: `{json}` (path of the exported session), `{id}`
+/// (session ID) and `{piano}` (configured piano name).
+#[derive(Debug, Clone)]
+pub struct SessionHook {
+    template: String,
+    piano_name: Option<String>,
+    export_dir: PathBuf,
+}
+
+impl SessionHook {
+    /// Create a hook from a command template.
+    pub fn new(template: impl Into<String>) -> Self {
+        Self {
+            template: template.into(),
+            piano_name: None,
+            export_dir: std::env::temp_dir(),
+        }
+    }
+
+    /// Set the piano name substituted for `{piano}`.
+    pub fn with_piano_name(mut self, name: Option<String>) -> Self {
+        self.piano_name = name;
+        self
+    }
+
+    /// Set the directory the session is exported to.
+    pub fn with_export_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.export_dir = dir.into();
+        self
+    }
+
+    /// Path the session is exported to.
+    pub fn export_path(&self, session: &Session) -> PathBuf {
+        let safe_id = session.id.replace(':', "-");
+        self.export_dir
+            .join(format!("onkey-session-{}.json", safe_id))
+    }
+
+    /// The command with its variables substituted.
+    pub fn command(&self, session: &Session, export_path: &Path) -> String {
+        self.template
+            .replace("{json}", &export_path.display().to_string())
+            .replace("{id}", &session.id)
+            .replace("{piano}", self.piano_name.as_deref().unwrap_or(""))
+    }
+
+    /// Export the session and start the command in the background.
+    pub fn run(&self, session: &Session) -> anyhow::Result<HookRun> {
+        let path = self.export_path(session);
+        fs::write(&path, export_json(session)?)?;
+
+        let command = self.command(session, &path);
+        let child = shell(&command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        Ok(HookRun { command, child })
+    }
+}
+
+/// A session as exported: its saved fields and the target of every key.
+#[derive(Serialize)]
+struct Export<'a> {
+    #[serde(flatten)]
+    session: &'a Session,
+    targets: Vec<TargetEntry>,
+}
+
+/// The session as exported JSON.
+pub fn export_json(session: &Session) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&Export {
+        session,
+        targets: session.targets(),
+    })
+}
+
+/// Shell invocation for a command line.
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+/// Shell invocation for a command line.
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+/// A hook command running in the background.
+#[derive(Debug)]
+pub struct HookRun {
+    command: String,
+    child: Child,
+}
+
+impl HookRun {
+    /// The command line being run.
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// Exit status if the command has finished, without waiting.
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.child.try_wait()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuning::Temperament;
+
+    #[test]
+    fn test_command_substitution() {
+        let session = Session::new(crate::tuning::TuningMode::Concert, 440.0);
+        let hook = SessionHook::new("sync {json} --id {id} --piano '{piano}'")
+            .with_piano_name(Some("Upright".to_string()))
+            .with_export_dir("/tmp/exports");
+
+        let path = hook.export_path(&session);
+        assert!(path.starts_with("/tmp/exports"));
+        assert!(!path.to_string_lossy().contains(':'));
+        assert_eq!(
+            hook.command(&session, &path),
+            format!(
+                "sync {} --id {} --piano 'Upright'",
+                path.display(),
+                session.id
+            )
+        );
+    }
+
+    #[test]
+    fn test_export_includes_targets() {
+        let mut session = Session::new(crate::tuning::TuningMode::Concert, 442.0);
+        session.set_manual_offset(69, 2.0);
+        let json = export_json(&session).unwrap();
+
+        // Still reads back as the session
+        let restored: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.id, session.id);
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let targets: Vec<TargetEntry> = serde_json::from_value(value["targets"].clone()).unwrap();
+        assert_eq!(targets, session.targets());
+        assert_eq!(targets.len(), 88);
+        let a4 = targets.iter().find(|t| t.midi == 69).unwrap();
+        assert!((a4.frequency - 442.0 * Temperament::cents_to_ratio(2.0)).abs() < 1e-3);
+    }
+}
@@ -4,6 +4,9 @@
 //! and real-time visual feedback.
 
 pub mod audio;
+pub mod clock;
 pub mod config;
+pub mod hook;
+pub mod log;
 pub mod tuning;
 pub mod ui;
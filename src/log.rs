@@ -0,0 +1,153 @@
+//! Structured logging to a file.
+//!
+//! Each event is one line: a timestamp, the level, the event name and
+//! `key=value` fields. Nothing is ever written to stdout or stderr, so logging
+//! is safe while the TUI owns the terminal.
+
+use std::fmt::{self, Display, Write as _};
+use std::fs::{File, OpenOptions};
+use std::io::{self, LineWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use chrono::Utc;
+use clap::ValueEnum;
+
+/// Log level, from least to most verbose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, ValueEnum)]
+pub enum Level {
+    /// Failures.
+    Error,
+    /// Unexpected but recoverable conditions.
+    Warn,
+    /// State transitions and tuning results.
+    #[default]
+    Info,
+    /// Detector results.
+    Debug,
+    /// Everything.
+    Trace,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        };
+        f.pad(name)
+    }
+}
+
+/// Handle to a log file. Cloning shares the file; the default logs nothing.
+#[derive(Clone, Default)]
+pub struct Logger {
+    sink: Option<Arc<Mutex<LineWriter<File>>>>,
+    level: Level,
+}
+
+impl Logger {
+    /// A logger that discards everything.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Append events up to `level` to the file at `path`.
+    pub fn to_file(path: impl AsRef<Path>, level: Level) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            sink: Some(Arc::new(Mutex::new(LineWriter::new(file)))),
+            level,
+        })
+    }
+
+    /// Whether events at `level` are written.
+    pub fn enabled(&self, level: Level) -> bool {
+        self.sink.is_some() && level <= self.level
+    }
+
+    /// Write an event with its fields.
+    pub fn log(&self, level: Level, event: &str, fields: &[(&str, &dyn Display)]) {
+        let Some(sink) = self.sink.as_ref().filter(|_| self.enabled(level)) else {
+            return;
+        };
+
+        let line = format_line(level, event, fields);
+        if let Ok(mut writer) = sink.lock() {
+            // Logging must never disturb the session
+            let _ = writer.write_all(line.as_bytes());
+        }
+    }
+
+    /// Write an error event.
+    pub fn error(&self, event: &str, fields: &[(&str, &dyn Display)]) {
+        self.log(Level::Error, event, fields);
+    }
+
+    /// Write a warning event.
+    pub fn warn(&self, event: &str, fields: &[(&str, &dyn Display)]) {
+        self.log(Level::Warn, event, fields);
+    }
+
+    /// Write an info event.
+    pub fn info(&self, event: &str, fields: &[(&str, &dyn Display)]) {
+        self.log(Level::Info, event, fields);
+    }
+
+    /// Write a debug event.
+    pub fn debug(&self, event: &str, fields: &[(&str, &dyn Display)]) {
+        self.log(Level::Debug, event, fields);
+    }
+}
+
+/// Format one log line, quoting values that contain spaces or quotes.
+fn format_line(level: Level, event: &str, fields: &[(&str, &dyn Display)]) -> String {
+    let mut line = format!(
+        "{} {:<5} {}",
+        Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+        level,
+        event
+    );
+    for (key, value) in fields {
+        let value = value.to_string();
+        if value.is_empty() || value.contains([' ', '"', '=']) {
+            let _ = write!(line, " {}={:?}", key, value);
+        } else {
+            let _ = write!(line, " {}={}", key, value);
+        }
+    }
+    line.push('\n');
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_filter_and_fields_quote() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("onkey.log");
+        let logger = Logger::to_file(&path, Level::Info).unwrap();
+
+        logger.info("confirm", &[("note", &"F3"), ("cents", &1.5)]);
+        logger.debug("pitch", &[("freq", &174.6)]);
+        logger.error("capture", &[("error", &"device lost")]);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("INFO  confirm note=F3 cents=1.5"));
+        assert!(lines[1].ends_with("ERROR capture error=\"device lost\""));
+    }
+
+    #[test]
+    fn test_disabled_logger() {
+        let logger = Logger::disabled();
+        assert!(!logger.enabled(Level::Error));
+        logger.error("ignored", &[]);
+    }
+}
@@ -2,16 +2,52 @@
 //!
 //! A terminal-based piano tuning application with guided coaching.
 
-use std::time::Duration;
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use clap::Parser;
 
-use onkey::audio::{AudioOutput, AudioSource, MicCapture, PitchDetector, WavAudioSource};
-use onkey::config::{Args, Command, Config};
+use onkey::audio::beats::rms;
+use onkey::audio::{
+    analyze, AudioOutput, AudioSource, CaptureError, Cue, CuePlayer, Filtered, HighPassed,
+    HumFilter, MicCapture, MonoTone, PartialTracker, PcmFormat, PitchFrame, PitchSmoother,
+    PitchTracker, PlayingTone, Recorder, StdinAudioSource, WavAudioSink, WavAudioSource, Windowed,
+};
+use onkey::config::{Args, Command, Config, EffectiveConfig};
+use onkey::hook::SessionHook;
+use onkey::log::Logger;
 use onkey::tuning::notes::Note;
 use onkey::tuning::session::Session;
 use onkey::tuning::temperament::Temperament;
-use onkey::ui::{self, App};
+use onkey::tuning::{CustomTemperament, StretchProfile};
+use onkey::ui::components::Readout;
+use onkey::ui::strings::{self, Strings};
+use onkey::ui::{self, App, DeviceRequest, ReferenceRequest};
+
+/// Input poll timeout per frame (~20 fps).
+const FRAME_TIME: Duration = Duration::from_millis(50);
+
+/// Input poll timeout per frame while idle (~2 fps).
+const IDLE_FRAME_TIME: Duration = Duration::from_millis(500);
+
+/// Audio the pitch tracker keeps, enough for the longest bass window and
+/// for fitting a reference tone to cancel it.
+const PITCH_HISTORY: Duration = Duration::from_millis(1500);
+
+/// Sample rate of audio read with --stdin unless one is given.
+const STDIN_SAMPLE_RATE: u32 = 44100;
+
+/// Interval between capture stats log entries.
+const STATS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How far ahead of playback the reference tone is rendered.
+const REFERENCE_LEAD: Duration = Duration::from_millis(150);
+
+/// Exit code after Ctrl+C or a termination signal (128 + SIGINT).
+const INTERRUPTED_EXIT_CODE: i32 = 130;
 
 fn main() -> anyhow::Result<()> {
     let args = Args::parse();
@@ -19,9 +55,9 @@ fn main() -> anyhow::Result<()> {
     let effective = config.merge_with_args(&args);
 
     match args.command {
-        Some(Command::Analyze { file }) => analyze_file(&file)?,
+        Some(Command::Analyze { file }) => analyze_file(&file, &effective)?,
         Some(Command::Reference { note, duration }) => play_reference(&note, duration)?,
-        Some(Command::History) => show_history()?,
+        Some(Command::History) => show_history(&effective.readout)?,
         Some(Command::Reset) => reset_sessions()?,
         None => run_interactive(effective)?,
     }
@@ -30,63 +66,76 @@ fn main() -> anyhow::Result<()> {
 }
 
 /// Analyze a WAV file for pitch content.
-fn analyze_file(path: &str) -> anyhow::Result<()> {
+fn analyze_file(path: &str, config: &EffectiveConfig) -> anyhow::Result<()> {
     println!("Analyzing {}...", path);
 
     let file = std::fs::File::open(path)?;
-    let mut source = WavAudioSource::new(file)?;
+    let source = WavAudioSource::new(file)?;
     let sample_rate = source.sample_rate();
+    let source: Box<dyn AudioSource> = match config.high_pass {
+        Some(cutoff) => Box::new(HighPassed::new(source, cutoff)),
+        None => Box::new(source),
+    };
+    let source: Box<dyn AudioSource> = match config.hum_filter {
+        Some(mains) => Box::new(Filtered::with_filter(
+            source,
+            HumFilter::new(mains, sample_rate),
+        )),
+        None => source,
+    };
 
-    let detector = PitchDetector::new(sample_rate);
+    let detector = config.pitch_algorithm.detector(sample_rate);
     let temperament = Temperament::new();
 
-    // Read samples in chunks and detect pitch
+    // Detect pitch in consecutive chunks
     let chunk_size = (sample_rate as usize) / 4; // 250ms chunks
-    let mut buffer = vec![0.0f32; chunk_size];
+    let analysis = analyze(Windowed::new(source, chunk_size, chunk_size), &*detector);
     let mut detections = Vec::new();
 
-    loop {
-        let read = source.read_samples(&mut buffer);
-        if read == 0 {
-            break;
-        }
-
-        if let Some(result) = detector.detect(&buffer[..read]) {
-            let (midi, cents) = temperament.nearest_note(result.frequency);
-            if let Some(note) = Note::from_midi(midi) {
-                detections.push((
-                    result.frequency,
-                    note.display_name(),
-                    cents,
-                    result.confidence,
-                ));
-            }
+    for frame in &analysis.detections {
+        let Some(result) = frame.result else {
+            continue;
+        };
+        let Some((midi, cents)) = temperament.nearest_note(result.frequency) else {
+            continue;
+        };
+        if let Some(note) = Note::from_midi(midi) {
+            detections.push((
+                frame.time(sample_rate).as_secs_f32(),
+                result.frequency,
+                note.display_name(),
+                cents,
+                result.confidence,
+            ));
         }
     }
 
+    println!("Analyzed {} windows.", analysis.windows);
     if detections.is_empty() {
         println!("No pitch detected in file.");
     } else {
         println!("\nDetected pitches:");
         println!(
-            "{:<10} {:<8} {:<12} {:<10}",
-            "Freq (Hz)", "Note", "Cents", "Confidence"
+            "{:<10} {:<10} {:<8} {:<12} {:<10}",
+            "Time (s)", "Freq (Hz)", "Note", "Cents", "Confidence"
         );
-        println!("{}", "-".repeat(42));
+        println!("{}", "-".repeat(53));
 
-        for (freq, note, cents, confidence) in &detections {
+        for (time, freq, note, cents, confidence) in &detections {
             println!(
-                "{:<10.1} {:<8} {:+<12.1} {:<10.2}",
-                freq, note, cents, confidence
+                "{:<10.2} {:<10.1} {:<8} {:+<12.1} {:<10.2}",
+                time, freq, note, cents, confidence
             );
         }
 
         // Summary
         if !detections.is_empty() {
             let avg_freq: f32 =
-                detections.iter().map(|(f, _, _, _)| f).sum::<f32>() / detections.len() as f32;
-            let (midi, cents) = temperament.nearest_note(avg_freq);
-            if let Some(note) = Note::from_midi(midi) {
+                detections.iter().map(|(_, f, _, _, _)| f).sum::<f32>() / detections.len() as f32;
+            if let Some((note, cents)) = temperament
+                .nearest_note(avg_freq)
+                .and_then(|(midi, cents)| Some((Note::from_midi(midi)?, cents)))
+            {
                 println!(
                     "\nAverage: {:.1} Hz ({} {:+.1} cents)",
                     avg_freq,
@@ -115,8 +164,13 @@ fn play_reference(note_name: &str, duration: f32) -> anyhow::Result<()> {
         duration
     );
 
-    let output = AudioOutput::new()?;
-    output.play_sine(frequency, duration)?;
+    let mut output = AudioOutput::new()?;
+    let mut tone = MonoTone::new(
+        output.sample_rate(),
+        frequency,
+        Duration::from_secs_f32(duration),
+    );
+    tone.write(&mut output, usize::MAX);
 
     // Wait for playback to complete
     std::thread::sleep(Duration::from_secs_f32(duration + 0.1));
@@ -126,7 +180,7 @@ fn play_reference(note_name: &str, duration: f32) -> anyhow::Result<()> {
 }
 
 /// Show tuning session history.
-fn show_history() -> anyhow::Result<()> {
+fn show_history(readout: &Readout) -> anyhow::Result<()> {
     let sessions = Session::list_all()?;
 
     if sessions.is_empty() {
@@ -136,20 +190,31 @@ fn show_history() -> anyhow::Result<()> {
 
     println!("Tuning History:");
     println!(
-        "{:<24} {:<10} {:<12} {:<10}",
-        "Date", "Mode", "Progress", "Avg. Cents"
+        "{:<24} {:<10} {:<12} {:<10} {:<10}",
+        "Date", "Mode", "Progress", "Avg. Dev.", "Low Conf."
     );
-    println!("{}", "-".repeat(58));
+    println!("{}", "-".repeat(69));
 
     for session in sessions {
         let date = session.created_at.format("%Y-%m-%d %H:%M").to_string();
         let mode = format!("{:?}", session.mode);
         let progress = format!("{:.0}%", session.progress_percent());
-        let avg_cents = format!("{:.1}", session.average_deviation());
+        let avg_cents =
+            readout.format_average(session.average_deviation(), session.average_deviation_hz());
+        let low_confidence = session
+            .completed_notes
+            .iter()
+            .filter(|n| n.is_low_confidence())
+            .count();
+        let low_confidence = if low_confidence > 0 {
+            format!("? {}", low_confidence)
+        } else {
+            String::new()
+        };
 
         println!(
-            "{:<24} {:<10} {:<12} {:<10}",
-            date, mode, progress, avg_cents
+            "{:<24} {:<10} {:<12} {:<10} {:<10}",
+            date, mode, progress, avg_cents, low_confidence
         );
     }
 
@@ -175,20 +240,434 @@ fn reset_sessions() -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Run the interactive tuning application.
-fn run_interactive(config: onkey::config::EffectiveConfig) -> anyhow::Result<()> {
-    // Initialize audio capture
-    let mut mic = match MicCapture::new() {
-        Ok(m) => m,
+/// Audio capture with a pitch detector and history buffer for its sample rate.
+struct Input {
+    mic: MicCapture,
+    /// Pitch detection every hop of the incoming audio.
+    tracker: PitchTracker,
+    /// Median filter between the detector and the app.
+    smoother: PitchSmoother,
+    /// Partial tracker for the lowest notes.
+    partials: PartialTracker,
+    /// 3s history for beats.
+    buffer: Vec<f32>,
+    /// Stream errors already logged.
+    reported_errors: u64,
+    /// Whether the current recording has been attached to this input.
+    recording_attached: bool,
+}
+
+impl Input {
+    /// Open capture on the named input device, or the default if `None`.
+    fn open(
+        name: Option<&str>,
+        config: &EffectiveConfig,
+        logger: &Logger,
+    ) -> Result<Self, CaptureError> {
+        let mic = MicCapture::builder()
+            .with_device(name)
+            .with_sample_rate(config.sample_rate)
+            .with_buffer_frames(config.buffer_frames)
+            .open()?;
+        Ok(Self::with_capture(mic, config, logger))
+    }
+
+    /// Read raw audio in `format` piped to stdin; keys still come from the
+    /// terminal.
+    fn stdin(format: PcmFormat, config: &EffectiveConfig, logger: &Logger) -> Self {
+        let rate = config.sample_rate.unwrap_or(STDIN_SAMPLE_RATE);
+        let source = StdinAudioSource::stdin(format, rate);
+        Self::with_capture(MicCapture::from_source(source, "stdin"), config, logger)
+    }
+
+    /// Filter and analyze audio from `mic`.
+    fn with_capture(mic: MicCapture, config: &EffectiveConfig, logger: &Logger) -> Self {
+        let mic = mic
+            .with_high_pass(config.high_pass)
+            .with_hum_filter(config.hum_filter);
+        // Everything downstream runs at the rate the device agreed to
+        let sample_rate = mic.sample_rate();
+        let buffer_frames = mic
+            .buffer_frames()
+            .map_or("default".to_string(), |frames| frames.to_string());
+        logger.info(
+            "capture_started",
+            &[
+                ("device", &mic.device_name()),
+                ("sample_rate", &sample_rate),
+                ("buffer_frames", &buffer_frames),
+            ],
+        );
+
+        Self {
+            // 100ms windows every 25ms without a target
+            tracker: PitchTracker::new(config.pitch_algorithm.detector(sample_rate))
+                .with_window(sample_rate as usize / 10, sample_rate as usize / 40)
+                .with_history((PITCH_HISTORY.as_secs_f64() * sample_rate as f64) as usize)
+                .with_attack_delay(config.attack_delay, sample_rate),
+            smoother: PitchSmoother::new(config.smoothing_frames),
+            partials: PartialTracker::new(sample_rate),
+            buffer: vec![0.0; sample_rate as usize * 3],
+            reported_errors: 0,
+            recording_attached: false,
+            mic,
+        }
+    }
+}
+
+/// Microphone input of a session being written to disk.
+struct Recording {
+    session_id: String,
+    path: std::path::PathBuf,
+    sample_rate: u32,
+    recorder: Recorder<WavAudioSink<BufWriter<File>>>,
+    /// Dropped samples already logged.
+    reported_drops: u64,
+}
+
+/// Start, attach or stop the recording to follow the session in progress.
+fn update_recording(
+    recording: &mut Option<Recording>,
+    app: &mut App,
+    input: &mut Option<Input>,
+    logger: &Logger,
+) {
+    let session = app.active_session();
+    let current = recording.as_ref().map(|r| r.session_id.as_str());
+    if current.is_some() && current != session.map(|s| s.id.as_str()) {
+        finish_recording(recording.take(), input, logger);
+    }
+
+    if recording.is_none() {
+        if let (Some(session), Some(input)) = (session, input.as_mut()) {
+            *recording = start_recording(session, input.mic.sample_rate(), logger);
+            input.recording_attached = false;
+        }
+    }
+
+    if let Some(recording) = recording {
+        // Reattach after the input device changes
+        if let Some(input) = input.as_mut().filter(|i| !i.recording_attached) {
+            if input.mic.sample_rate() == recording.sample_rate {
+                input.mic.set_tap(Some(recording.recorder.tap()));
+            } else {
+                logger.warn(
+                    "recording_rate_mismatch",
+                    &[
+                        ("recording", &recording.sample_rate),
+                        ("input", &input.mic.sample_rate()),
+                    ],
+                );
+            }
+            input.recording_attached = true;
+        }
+
+        let dropped = recording.recorder.dropped();
+        if dropped > recording.reported_drops {
+            logger.warn("recording_dropped", &[("samples", &dropped)]);
+            recording.reported_drops = dropped;
+        }
+    }
+
+    if app.is_recording() != recording.is_some() {
+        app.set_recording(recording.is_some());
+    }
+}
+
+/// Open the recording file of a session, logging why if it can't be.
+fn start_recording(session: &Session, sample_rate: u32, logger: &Logger) -> Option<Recording> {
+    let path = session.recording_path()?;
+    let sink = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .map_err(hound::Error::from)
+        .and_then(|()| WavAudioSink::create(&path, sample_rate, 1, PcmFormat::F32Le));
+    match sink {
+        Ok(sink) => {
+            logger.info("recording_started", &[("path", &path.display())]);
+            Some(Recording {
+                session_id: session.id.clone(),
+                path,
+                sample_rate,
+                recorder: Recorder::start(sink),
+                reported_drops: 0,
+            })
+        }
         Err(e) => {
-            eprintln!("Error: Could not access microphone: {}", e);
-            eprintln!("Please ensure a microphone is connected and permissions are granted.");
-            return Err(e.into());
+            logger.error("recording_failed", &[("error", &e)]);
+            None
+        }
+    }
+}
+
+/// Detach a recording from the input and finish writing its file.
+fn finish_recording(recording: Option<Recording>, input: &mut Option<Input>, logger: &Logger) {
+    let Some(recording) = recording else {
+        return;
+    };
+    if let Some(input) = input {
+        input.mic.set_tap(None);
+        input.recording_attached = false;
+    }
+    match recording.recorder.stop().finalize() {
+        Ok(()) => logger.info("recording_saved", &[("path", &recording.path.display())]),
+        Err(e) => logger.error("recording_failed", &[("error", &e)]),
+    }
+}
+
+/// Carry out a device change requested on the devices screen.
+///
+/// A new input is opened before the old one is dropped, so a failure
+/// leaves the previous device running.
+fn handle_device_request(
+    request: DeviceRequest,
+    app: &mut App,
+    input: &mut Option<Input>,
+    output: &mut Option<AudioOutput>,
+    config: &EffectiveConfig,
+    logger: &Logger,
+) {
+    match request {
+        DeviceRequest::Rescan => {
+            let inputs = MicCapture::list_devices().unwrap_or_else(|e| {
+                logger.warn("device_scan_failed", &[("error", &e)]);
+                Vec::new()
+            });
+            let outputs = AudioOutput::list_devices().unwrap_or_else(|e| {
+                logger.warn("device_scan_failed", &[("error", &e)]);
+                Vec::new()
+            });
+            app.set_devices(inputs, outputs);
+        }
+        DeviceRequest::OpenInput(name) => {
+            if input.as_ref().is_some_and(|i| i.mic.device_name() == name) {
+                app.input_opened(&name);
+                return;
+            }
+            match Input::open(Some(&name), config, logger) {
+                Ok(new) => {
+                    app.set_input_format(new.mic.sample_rate(), new.mic.buffer_frames());
+                    *input = Some(new);
+                    app.input_opened(&name);
+                }
+                Err(e) => app.input_failed(&name, &e.to_string()),
+            }
+        }
+        DeviceRequest::ReopenDefaultInput => {
+            // The lost device may have come back, or another become default
+            if let Ok(inputs) = MicCapture::list_devices() {
+                let outputs = AudioOutput::list_devices().unwrap_or_default();
+                app.set_devices(inputs, outputs);
+            }
+            match Input::open(None, config, logger) {
+                Ok(new) => {
+                    let name = new.mic.device_name().to_string();
+                    app.set_input_format(new.mic.sample_rate(), new.mic.buffer_frames());
+                    *input = Some(new);
+                    app.input_opened(&name);
+                }
+                Err(e) => app.input_failed("default", &e.to_string()),
+            }
+        }
+        DeviceRequest::OpenOutput(name) => match AudioOutput::open(Some(&name)) {
+            Ok(new) => {
+                *output = Some(new);
+                app.output_opened(&name);
+            }
+            Err(e) => app.output_failed(&name, &e.to_string()),
+        },
+    }
+}
+
+/// Play the reference tone, with the octave below at `lower` or beating
+/// against a second voice `detune` cents away if given, opening the output
+/// device on first use. A tone already playing fades over to the new
+/// frequency. The tone plays until the app asks for it to stop.
+fn play_reference_tone(
+    freq: f32,
+    lower: Option<f32>,
+    detune: Option<f32>,
+    app: &App,
+    output: &mut Option<AudioOutput>,
+    tone: &mut Option<PlayingTone>,
+    logger: &Logger,
+) {
+    let lower = lower.map(|lower| (lower, MonoTone::OCTAVE_AMPLITUDE));
+    if let Some(playing) = tone {
+        playing.retarget(freq, lower, Duration::MAX);
+        playing.set_detuned_pair(detune);
+        return;
+    }
+    let Some(output) = open_output(app, output, logger) else {
+        return;
+    };
+    let mut playing = PlayingTone::new(
+        app.reference_channels(),
+        output.sample_rate(),
+        freq,
+        Duration::MAX,
+    )
+    .with_bass_reinforcement(app.bass_reinforcement())
+    .with_fade(app.reference_fade())
+    .with_volume(app.reference_volume())
+    // Last, so the octave plays like the target
+    .with_lower(lower);
+    playing.set_detuned_pair(detune);
+    *tone = Some(playing);
+}
+
+/// Play a lock or confirm cue.
+fn play_cue(
+    cue: Cue,
+    player: &CuePlayer,
+    app: &App,
+    output: &mut Option<AudioOutput>,
+    logger: &Logger,
+) {
+    if let Some(output) = open_output(app, output, logger) {
+        player.play(output, cue);
+    }
+}
+
+/// The output device, opened on first use.
+fn open_output<'a>(
+    app: &App,
+    output: &'a mut Option<AudioOutput>,
+    logger: &Logger,
+) -> Option<&'a mut AudioOutput> {
+    if output.is_none() {
+        match AudioOutput::open(app.device_select().current_output()) {
+            Ok(opened) => *output = Some(opened),
+            Err(e) => logger.error("output_failed", &[("error", &e)]),
+        }
+    }
+    output.as_mut()
+}
+
+/// Keep the reference tone rendered a little ahead of playback, in the
+/// current waveform and volume and, when split, following the latest
+/// detection.
+fn feed_reference_tone(
+    tone: &mut Option<PlayingTone>,
+    app: &App,
+    output: &mut Option<AudioOutput>,
+) {
+    let (Some(playing), Some(output)) = (tone.as_mut(), output.as_mut()) else {
+        return;
+    };
+    let lead = (REFERENCE_LEAD.as_secs_f32() * output.sample_rate() as f32) as usize;
+    playing.set_waveform(app.waveform());
+    playing.set_volume(app.reference_volume());
+    playing.set_detected(app.detected_freq());
+    playing.write(output, lead.saturating_sub(output.queued_frames()));
+    if playing.is_finished() {
+        *tone = None;
+    }
+}
+
+/// Custom temperaments from the temperaments directory, and the file the
+/// config selects. Files in the directory that fail to load are logged and
+/// skipped; the selected one must load.
+fn load_custom_temperaments(
+    config: &EffectiveConfig,
+    logger: &Logger,
+) -> anyhow::Result<(Vec<CustomTemperament>, Option<CustomTemperament>)> {
+    let mut customs = Vec::new();
+    let loaded = CustomTemperament::dir()
+        .map(|dir| CustomTemperament::load_dir(&dir))
+        .unwrap_or_default();
+    for (path, result) in loaded {
+        match result {
+            Ok(custom) => customs.push(custom),
+            Err(e) => logger.warn(
+                "temperament_failed",
+                &[("path", &path.display()), ("error", &e)],
+            ),
+        }
+    }
+
+    let selected = match &config.temperament_file {
+        Some(path) => Some(
+            CustomTemperament::load(path)
+                .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?,
+        ),
+        None => None,
+    };
+    if let Some(selected) = &selected {
+        if !customs.contains(selected) {
+            customs.push(selected.clone());
         }
+    }
+    Ok((customs, selected))
+}
+
+/// Stretch curves saved in the profiles directory, and the one the config
+/// selects by name. Files that fail to load are logged and skipped.
+fn load_stretch_profiles(
+    config: &EffectiveConfig,
+    logger: &Logger,
+) -> anyhow::Result<(Vec<StretchProfile>, Option<StretchProfile>)> {
+    let mut profiles = Vec::new();
+    let loaded = StretchProfile::dir()
+        .map(|dir| StretchProfile::load_dir(&dir))
+        .unwrap_or_default();
+    for (path, result) in loaded {
+        match result {
+            Ok(profile) => profiles.push(profile),
+            Err(e) => logger.warn(
+                "stretch_curve_failed",
+                &[("path", &path.display()), ("error", &e)],
+            ),
+        }
+    }
+
+    let selected = match &config.stretch_curve {
+        Some(name) => Some(
+            profiles
+                .iter()
+                .find(|p| &p.name == name)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("No saved stretch curve named {}", name))?,
+        ),
+        None => None,
     };
+    Ok((profiles, selected))
+}
+
+/// Save a stretch curve back to the profiles directory, e.g. after its
+/// target overrides changed.
+fn save_stretch_profile(profile: &StretchProfile, logger: &Logger) {
+    let Some(dir) = StretchProfile::dir() else {
+        return;
+    };
+    if let Err(e) = profile.save(&dir) {
+        logger.warn(
+            "stretch_curve_save_failed",
+            &[("name", &profile.name), ("error", &e)],
+        );
+    }
+}
+
+/// Run the interactive tuning application.
+fn run_interactive(config: EffectiveConfig) -> anyhow::Result<()> {
+    let logger = match &config.log_file {
+        Some(path) => Logger::to_file(path, config.log_level)?,
+        None => Logger::disabled(),
+    };
+
+    if let Some(locale) = &config.locale {
+        match Strings::for_locale(locale) {
+            Ok(table) => strings::install(table),
+            Err(e) => logger.warn("locale_failed", &[("locale", locale), ("error", &e)]),
+        }
+    }
 
-    let sample_rate = mic.sample_rate();
-    let detector = PitchDetector::new(sample_rate);
+    let (custom_temperaments, custom_temperament) = load_custom_temperaments(&config, &logger)?;
+    let (stretch_profiles, stretch_profile) = load_stretch_profiles(&config, &logger)?;
+
+    let session_hook = config.on_session_complete.as_ref().map(|template| {
+        SessionHook::new(template.as_str()).with_piano_name(config.piano_name.clone())
+    });
 
     // Create or resume app
     let mut app = if config.resume {
@@ -199,46 +678,325 @@ fn run_interactive(config: onkey::config::EffectiveConfig) -> anyhow::Result<()>
                     session.created_at.format("%Y-%m-%d %H:%M")
                 );
                 std::thread::sleep(Duration::from_millis(500));
+                // The session keeps its own instrument, temperament and stretch
+                let restored = session.custom_temperament.clone();
+                let restored_curve = session.stretch_profile.clone();
                 App::with_session(session)
+                    .with_a4_presets(config.a4_presets.clone())
+                    .with_string_order(config.string_order)
+                    .with_hold_time(config.hold_time)
+                    .with_readout(config.readout)
+                    .with_require_steady(config.require_steady)
+                    .with_pitch_lock(config.acquire_frames, config.release_frames)
+                    .with_min_confidence(config.min_confidence)
+                    .with_noise_gate(config.noise_gate)
+                    .with_custom_temperaments(custom_temperaments.clone(), restored)
+                    .with_stretch_profiles(stretch_profiles.clone(), restored_curve)
+                    .with_beep(config.beep)
+                    .with_reference_feedback(config.reference_feedback)
+                    .with_reference_channels(config.reference_channels)
+                    .with_waveform(config.reference_waveform)
+                    .with_bass_reinforcement(config.bass_reinforcement)
+                    .with_reference_fade(config.reference_fade)
+                    .with_reference_timeout(config.reference_timeout)
+                    .with_reference_volume(config.reference_volume)
+                    .with_partials_below(config.partials_below)
+                    .with_capture_sets_a4(config.capture_sets_a4)
+                    .with_idle_timeout(config.idle_timeout)
+                    .with_pause_when_idle(config.pause_when_idle)
+                    .with_gain_db(config.input_gain_db)
+                    .with_session_hook(session_hook.clone())
+                    .with_piano_name(config.piano_name.clone())
+                    .with_logger(logger.clone())
             }
             None => {
                 println!("No incomplete session found. Starting new session.");
                 std::thread::sleep(Duration::from_millis(500));
                 App::new()
+                    .with_a4_presets(config.a4_presets.clone())
+                    .with_string_order(config.string_order)
+                    .with_hold_time(config.hold_time)
+                    .with_readout(config.readout)
+                    .with_require_steady(config.require_steady)
+                    .with_pitch_lock(config.acquire_frames, config.release_frames)
+                    .with_min_confidence(config.min_confidence)
+                    .with_noise_gate(config.noise_gate)
+                    .with_instrument(config.instrument, config.temperament)
+                    .with_temperament_root(config.temperament_root)
+                    .with_custom_temperaments(
+                        custom_temperaments.clone(),
+                        custom_temperament.clone(),
+                    )
+                    .with_stretch(config.stretch)
+                    .with_stretch_intensity(config.stretch_intensity)
+                    .with_piano_type(config.piano_type)
+                    .with_order_strategy(config.tuning_order)
+                    .with_stretch_profiles(stretch_profiles.clone(), stretch_profile.clone())
+                    .with_octave_type(config.octave_type)
+                    .with_inharmonicity(config.inharmonicity.clone())
+                    .with_transpose(config.transpose)
+                    .with_beep(config.beep)
+                    .with_reference_feedback(config.reference_feedback)
+                    .with_reference_channels(config.reference_channels)
+                    .with_waveform(config.reference_waveform)
+                    .with_bass_reinforcement(config.bass_reinforcement)
+                    .with_reference_fade(config.reference_fade)
+                    .with_reference_timeout(config.reference_timeout)
+                    .with_reference_volume(config.reference_volume)
+                    .with_partials_below(config.partials_below)
+                    .with_capture_sets_a4(config.capture_sets_a4)
+                    .with_idle_timeout(config.idle_timeout)
+                    .with_pause_when_idle(config.pause_when_idle)
+                    .with_gain_db(config.input_gain_db)
+                    .with_session_hook(session_hook.clone())
+                    .with_piano_name(config.piano_name.clone())
+                    .with_logger(logger.clone())
             }
         }
     } else {
         App::new()
+            .with_a4_presets(config.a4_presets.clone())
+            .with_string_order(config.string_order)
+            .with_hold_time(config.hold_time)
+            .with_readout(config.readout)
+            .with_require_steady(config.require_steady)
+            .with_pitch_lock(config.acquire_frames, config.release_frames)
+            .with_min_confidence(config.min_confidence)
+            .with_noise_gate(config.noise_gate)
+            .with_instrument(config.instrument, config.temperament)
+            .with_temperament_root(config.temperament_root)
+            .with_custom_temperaments(custom_temperaments.clone(), custom_temperament.clone())
+            .with_stretch(config.stretch)
+            .with_stretch_intensity(config.stretch_intensity)
+            .with_piano_type(config.piano_type)
+            .with_order_strategy(config.tuning_order)
+            .with_stretch_profiles(stretch_profiles.clone(), stretch_profile.clone())
+            .with_octave_type(config.octave_type)
+            .with_inharmonicity(config.inharmonicity.clone())
+            .with_transpose(config.transpose)
+            .with_beep(config.beep)
+            .with_reference_feedback(config.reference_feedback)
+            .with_reference_channels(config.reference_channels)
+            .with_waveform(config.reference_waveform)
+            .with_bass_reinforcement(config.bass_reinforcement)
+            .with_reference_fade(config.reference_fade)
+            .with_reference_timeout(config.reference_timeout)
+            .with_reference_volume(config.reference_volume)
+            .with_partials_below(config.partials_below)
+            .with_capture_sets_a4(config.capture_sets_a4)
+            .with_idle_timeout(config.idle_timeout)
+            .with_pause_when_idle(config.pause_when_idle)
+            .with_gain_db(config.input_gain_db)
+            .with_session_hook(session_hook.clone())
+            .with_piano_name(config.piano_name.clone())
+            .with_logger(logger.clone())
     };
 
-    // Initialize terminal
-    let mut terminal = ui::init()?;
+    // Initialize audio capture; on failure start on the devices screen
+    let opened = match config.stdin {
+        Some(format) => Ok(Input::stdin(format, &config, &logger)),
+        None => Input::open(config.input_device.as_deref(), &config, &logger),
+    };
+    let mut input = match opened {
+        Ok(input) => {
+            app.set_input_device(input.mic.device_name());
+            app.set_input_format(input.mic.sample_rate(), input.mic.buffer_frames());
+            Some(input)
+        }
+        Err(e) => {
+            logger.error("capture_failed", &[("error", &e)]);
+            app.show_audio_error(&e.to_string());
+            None
+        }
+    };
+    if let Some(output) = AudioOutput::list_devices()
+        .ok()
+        .and_then(|devices| devices.into_iter().find(|d| d.is_default))
+    {
+        app.set_output_device(&output.name);
+    }
+
+    // Opened on first use of the reference tone or a cue
+    let mut output: Option<AudioOutput> = None;
+    let cues = CuePlayer::new(config.beep_volume);
+    let mut tone: Option<PlayingTone> = None;
+
+    // Initialize terminal; restored when the guard drops, even on early
+    // returns, and by the panic hook before a panic message is printed
+    ui::install_panic_hook();
+    let mut display = ui::Display::init(config.plain)?;
 
-    // Main loop
-    let mut audio_buffer = vec![0.0f32; sample_rate as usize / 10]; // 100ms buffer
+    let terminate = Arc::new(AtomicBool::new(false));
+    let stop = Arc::new(AtomicBool::new(false));
+    register_signals(&terminate, &stop)?;
+
+    let mut last_stats = Instant::now();
+    let mut recording: Option<Recording> = None;
 
     let result = loop {
-        // Read audio and detect pitch
-        let read = mic.read_samples(&mut audio_buffer);
-        if read > 0 {
-            if let Some(pitch_result) = detector.detect(&audio_buffer[..read]) {
-                app.update_pitch(pitch_result.frequency, pitch_result.confidence);
-            } else {
-                app.clear_pitch();
+        if config.record {
+            update_recording(&mut recording, &mut app, &mut input, &logger);
+        }
+
+        // Stop listening to a device that went away, saving the session
+        if let Some(Err(e)) = input.as_ref().map(|input| input.mic.check()) {
+            logger.error("capture_lost", &[("error", &e)]);
+            input = None;
+            app.input_lost(&e.to_string());
+        }
+
+        // Read audio and detect pitch (only the level while idle)
+        if let Some(input) = &mut input {
+            let sample_rate = input.mic.sample_rate();
+            let pitch_window = sample_rate as usize / 10; // 100ms for pitch and level
+
+            input.mic.set_gain_db(app.gain_db());
+            input.smoother.set_min_confidence(app.min_confidence());
+            app.update_clipping(input.mic.take_clipped());
+            let read = input.mic.read_samples(&mut input.buffer);
+            if read > 0 {
+                let recent = &input.buffer[read.saturating_sub(pitch_window)..read];
+                app.update_level(rms(recent));
+                let end = input.mic.read_end();
+                if app.is_idle() {
+                    input.smoother.clear();
+                    input.tracker.reset(end);
+                } else {
+                    app.update_samples(&input.buffer[..read], sample_rate);
+                    if let Some(target) = app.partial_target() {
+                        input.smoother.clear();
+                        input.tracker.reset(end);
+                        // Weak bass fundamentals are inferred from the partials
+                        let window = app.detection_window(
+                            &input.buffer[..read],
+                            input.partials.window_size(),
+                            sample_rate,
+                        );
+                        app.update_partials(input.partials.detect(&window, target));
+                    } else {
+                        // Longer windows for the bass, shorter for the treble
+                        let target = app.detection_target();
+                        input.tracker.set_target(target);
+                        input.smoother.set_target(target);
+
+                        // Only new samples, or a window's worth after a reset.
+                        // Frames are dated by when their audio was captured.
+                        input.tracker.push_recent_with(
+                            &input.buffer[..read],
+                            end,
+                            |history, window| app.detection_window(history, window, sample_rate),
+                        );
+                        while let Some(frame) = input.tracker.poll() {
+                            if let Some(pitch_result) = &frame.result {
+                                app.log_pitch(pitch_result);
+                            }
+                            // Spurious single frames are filtered out before the app sees them
+                            let frame = PitchFrame {
+                                result: input.smoother.push(frame.result),
+                                ..frame
+                            };
+                            app.update_pitch_frame(&frame, sample_rate);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Capture health
+        if last_stats.elapsed() >= STATS_INTERVAL {
+            if let Some(input) = &mut input {
+                let stats = input.mic.stats();
+                logger.debug(
+                    "capture_stats",
+                    &[
+                        ("callbacks", &stats.callbacks),
+                        ("samples", &stats.samples),
+                        ("errors", &stats.errors),
+                        ("clipped", &stats.clipped),
+                    ],
+                );
+                if stats.errors > input.reported_errors {
+                    let error = stats.last_error.unwrap_or_default();
+                    logger.error(
+                        "capture_error",
+                        &[("count", &stats.errors), ("error", &error)],
+                    );
+                    input.reported_errors = stats.errors;
+                }
             }
+            last_stats = Instant::now();
         }
 
+        app.tick();
+
         // Render UI
-        terminal.draw(|frame| {
-            app.render(frame);
-        })?;
+        if app.take_redraw_request() {
+            display.redraw()?;
+        }
+        display.draw(&app)?;
 
         // Handle input (non-blocking)
-        if let Some(event) = ui::poll_event(Duration::from_millis(50))? {
-            if let Some(key) = ui::is_key_press(&event) {
+        let frame_time = if app.is_idle() {
+            IDLE_FRAME_TIME
+        } else {
+            FRAME_TIME
+        };
+        let mut suspend = stop.swap(false, Ordering::Relaxed);
+        if let Some(event) = ui::poll_event(frame_time)? {
+            if ui::is_interrupt(&event) {
+                app.interrupt();
+            } else if ui::is_suspend(&event) {
+                suspend = true;
+            } else if let Some(key) = ui::is_key_press(&event) {
                 app.handle_key(key);
             }
         }
+        if suspend {
+            suspend_to_shell(&mut app, &mut display, &input, &output, &mut tone, &logger)?;
+        }
+        if terminate.load(Ordering::Relaxed) {
+            app.interrupt();
+        }
+        if app.should_force_quit() {
+            drop(display);
+            std::process::exit(INTERRUPTED_EXIT_CODE);
+        }
+
+        if let Some(request) = app.take_device_request() {
+            handle_device_request(request, &mut app, &mut input, &mut output, &config, &logger);
+        }
+        match app.take_reference_request() {
+            Some(ReferenceRequest::Play(freq)) => {
+                play_reference_tone(freq, None, None, &app, &mut output, &mut tone, &logger)
+            }
+            Some(ReferenceRequest::PlayOctave { target, lower }) => {
+                let lower = Some(lower);
+                play_reference_tone(target, lower, None, &app, &mut output, &mut tone, &logger)
+            }
+            Some(ReferenceRequest::PlayBeats { target, cents }) => {
+                let detune = Some(cents);
+                play_reference_tone(target, None, detune, &app, &mut output, &mut tone, &logger)
+            }
+            Some(ReferenceRequest::Stop) => {
+                if let Some(playing) = &mut tone {
+                    playing.stop();
+                }
+            }
+            None => {}
+        }
+        if let Some(settings) = app.take_noise_request() {
+            if let Err(e) = Config::save_noise_settings(settings) {
+                logger.warn("noise_save_failed", &[("error", &e)]);
+            }
+        }
+        if let Some(profile) = app.take_profile_request() {
+            save_stretch_profile(&profile, &logger);
+        }
+        if let Some(cue) = app.take_cue_request() {
+            play_cue(cue, &cues, &app, &mut output, &logger);
+        }
+        feed_reference_tone(&mut tone, &app, &mut output);
+        app.poll_hook();
 
         // Check for quit
         if app.should_quit() {
@@ -247,7 +1005,82 @@ fn run_interactive(config: onkey::config::EffectiveConfig) -> anyhow::Result<()>
     };
 
     // Restore terminal
-    ui::restore()?;
+    drop(display);
+
+    finish_recording(recording, &mut input, &logger);
+    if app.gain_db() != config.input_gain_db {
+        if let Err(e) = Config::save_input_gain(app.gain_db()) {
+            logger.warn("gain_save_failed", &[("error", &e)]);
+        }
+    }
+    if app.reference_volume() != config.reference_volume {
+        if let Err(e) = Config::save_reference_volume(app.reference_volume()) {
+            logger.warn("reference_volume_save_failed", &[("error", &e)]);
+        }
+    }
+    logger.info("exit", &[]);
+
+    if app.was_interrupted() {
+        // Exiting skips destructors; stop the audio streams first
+        drop((input, output, tone));
+        std::process::exit(INTERRUPTED_EXIT_CODE);
+    }
 
     result
 }
+
+/// Pause audio and hand the terminal back to the shell until the process
+/// is continued, then pick up where it left off.
+fn suspend_to_shell(
+    app: &mut App,
+    display: &mut ui::Display,
+    input: &Option<Input>,
+    output: &Option<AudioOutput>,
+    tone: &mut Option<PlayingTone>,
+    logger: &Logger,
+) -> anyhow::Result<()> {
+    app.suspend();
+    *tone = None;
+    if let Some(output) = output {
+        output.clear();
+    }
+    if let Some(input) = input {
+        if let Err(e) = input.mic.pause() {
+            logger.warn("capture_pause_failed", &[("error", &e)]);
+        }
+    }
+
+    display.suspend()?;
+
+    if let Some(input) = input {
+        if let Err(e) = input.mic.resume() {
+            logger.error("capture_resume_failed", &[("error", &e)]);
+        }
+    }
+    app.resume();
+    Ok(())
+}
+
+/// Route SIGTERM, SIGHUP and SIGINT to `terminate`, and SIGTSTP to `stop`.
+/// A second termination signal while the first is still being handled
+/// exits at once, in case saving hangs.
+#[cfg(unix)]
+fn register_signals(terminate: &Arc<AtomicBool>, stop: &Arc<AtomicBool>) -> anyhow::Result<()> {
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM, SIGTSTP};
+    use signal_hook::flag;
+
+    for signal in [SIGTERM, SIGHUP, SIGINT] {
+        // Registered first so it only fires once the flag is already set
+        flag::register_conditional_shutdown(signal, INTERRUPTED_EXIT_CODE, Arc::clone(terminate))?;
+        flag::register(signal, Arc::clone(terminate))?;
+    }
+    flag::register(SIGTSTP, Arc::clone(stop))?;
+    Ok(())
+}
+
+/// Ctrl+C arrives as a key event and there is no stop signal; there are no
+/// signals to route.
+#[cfg(not(unix))]
+fn register_signals(_terminate: &Arc<AtomicBool>, _stop: &Arc<AtomicBool>) -> anyhow::Result<()> {
+    Ok(())
+}
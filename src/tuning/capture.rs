@@ -0,0 +1,84 @@
+//! Capturing a note's target from another instrument.
+
+/// Collects pitch readings from an external instrument until they settle,
+/// then yields their median as the target.
+#[derive(Debug, Clone, Default)]
+pub struct TargetCapture {
+    /// Most recent readings in Hz, oldest first.
+    readings: Vec<f32>,
+}
+
+impl TargetCapture {
+    /// Consecutive consistent readings needed to capture.
+    pub const REQUIRED_READINGS: usize = 8;
+
+    /// Maximum spread in cents for the readings to count as stable.
+    pub const MAX_SPREAD: f32 = 3.0;
+
+    /// Start a capture.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a reading in Hz. Returns the median of the readings once enough
+    /// consistent ones have been seen.
+    pub fn update(&mut self, freq: f32) -> Option<f32> {
+        self.readings.push(freq);
+        if self.readings.len() > Self::REQUIRED_READINGS {
+            self.readings.remove(0);
+        }
+        if self.readings.len() < Self::REQUIRED_READINGS {
+            return None;
+        }
+
+        let mut sorted = self.readings.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let spread = 1200.0 * (sorted[sorted.len() - 1] / sorted[0]).log2();
+        if spread > Self::MAX_SPREAD {
+            return None;
+        }
+
+        let mid = sorted.len() / 2;
+        Some((sorted[mid - 1] + sorted[mid]) / 2.0)
+    }
+
+    /// Drop the readings; the instrument must sound again from scratch.
+    pub fn clear(&mut self) {
+        self.readings.clear();
+    }
+
+    /// Capture progress (0.0 to 1.0).
+    pub fn progress(&self) -> f64 {
+        self.readings.len() as f64 / Self::REQUIRED_READINGS as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_captures_median_of_stable_readings() {
+        let mut capture = TargetCapture::new();
+        let readings = [438.5, 438.7, 438.6, 438.4, 438.8, 438.6, 438.6, 438.5];
+        let mut captured = None;
+        for freq in readings {
+            captured = capture.update(freq);
+        }
+        assert_eq!(captured, Some(438.6));
+    }
+
+    #[test]
+    fn test_unstable_readings_and_silence() {
+        let mut capture = TargetCapture::new();
+        for i in 0..20 {
+            let freq = if i % 2 == 0 { 438.0 } else { 442.0 };
+            assert_eq!(capture.update(freq), None);
+        }
+
+        capture.clear();
+        assert_eq!(capture.progress(), 0.0);
+        capture.update(440.0);
+        assert!(capture.progress() > 0.0);
+    }
+}
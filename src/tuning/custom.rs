@@ -0,0 +1,322 @@
+//! Custom temperaments loaded from offset files.
+//!
+//! A file gives twelve offsets from equal temperament in cents, one per
+//! pitch class from the root key (C unless given), and optionally a name:
+//!
+//! ```toml
+//! name = "Kellner"
+//! root = "C"
+//! offsets = [8.2, 0.4, 2.7, 2.3, -0.7, 6.3, -1.6, 5.5, 1.2, 0.0, 4.3, -2.7]
+//! ```
+//!
+//! JSON files with the same fields are read too, and Scala scales (`.scl`)
+//! that map onto the twelve keys. Like the built-in schemes, the offsets are
+//! taken relative to A, so the A4 reference is unchanged.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::scala::{ScalaError, Scale};
+use super::scheme::{parse_root, root_name};
+
+/// Largest offset accepted from equal temperament, in cents.
+pub const MAX_OFFSET: f32 = 100.0;
+
+/// Error loading a custom temperament.
+#[derive(Debug, thiserror::Error)]
+pub enum CustomTemperamentError {
+    #[error("Failed to read temperament file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid temperament file: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("Invalid temperament file: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid Scala file: {0}")]
+    Scala(#[from] ScalaError),
+    #[error("Expected 12 offsets, one per pitch class, found {0}")]
+    OffsetCount(usize),
+    #[error("Offset {offset} cents for {pitch_class} is outside ±100 cents")]
+    OffsetRange { pitch_class: String, offset: f32 },
+    #[error("Unknown root key: {0}")]
+    Root(String),
+}
+
+/// A temperament given as offsets from equal temperament.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustomTemperament {
+    /// Name shown on screen and recorded in sessions.
+    pub name: String,
+    /// Pitch class the offsets start from (C = 0).
+    pub root: u8,
+    /// Offsets in cents from equal temperament, from the root up.
+    pub offsets: [f32; 12],
+}
+
+/// A temperament file as written.
+#[derive(Debug, Deserialize)]
+struct OffsetsFile {
+    name: Option<String>,
+    root: Option<String>,
+    offsets: Vec<f32>,
+}
+
+impl CustomTemperament {
+    /// Parse a TOML temperament file, named `default_name` unless it names
+    /// itself.
+    pub fn from_toml(content: &str, default_name: &str) -> Result<Self, CustomTemperamentError> {
+        Self::from_file(toml::from_str(content)?, default_name)
+    }
+
+    /// Parse a JSON temperament file, named `default_name` unless it names
+    /// itself.
+    pub fn from_json(content: &str, default_name: &str) -> Result<Self, CustomTemperamentError> {
+        Self::from_file(serde_json::from_str(content)?, default_name)
+    }
+
+    /// Parse a Scala scale, from C, named after its description or
+    /// `default_name` if it has none.
+    pub fn from_scl(content: &str, default_name: &str) -> Result<Self, CustomTemperamentError> {
+        let scale = Scale::parse(content)?;
+        let name = if scale.description.is_empty() {
+            default_name.to_string()
+        } else {
+            scale.description.clone()
+        };
+        Self::new(name, 0, scale.offsets()?)
+    }
+
+    /// Load a temperament file: JSON if it ends in `.json`, a Scala scale
+    /// if in `.scl`, TOML otherwise. Without a name of its own, it is named
+    /// after the file.
+    pub fn load(path: &Path) -> Result<Self, CustomTemperamentError> {
+        let content = fs::read_to_string(path)?;
+        let default_name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::from_json(&content, &default_name),
+            Some("scl") => Self::from_scl(&content, &default_name),
+            _ => Self::from_toml(&content, &default_name),
+        }
+    }
+
+    /// Load every `.toml`, `.json` and `.scl` file in `dir`, in file name
+    /// order.
+    /// A missing directory holds none.
+    pub fn load_dir(dir: &Path) -> Vec<(PathBuf, Result<Self, CustomTemperamentError>)> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                matches!(
+                    path.extension().and_then(|ext| ext.to_str()),
+                    Some("toml" | "json" | "scl")
+                )
+            })
+            .collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| {
+                let temperament = Self::load(&path);
+                (path, temperament)
+            })
+            .collect()
+    }
+
+    /// Directory custom temperaments are loaded from.
+    pub fn dir() -> Option<PathBuf> {
+        directories::ProjectDirs::from("", "", "onkey")
+            .map(|dirs| dirs.config_dir().join("temperaments"))
+    }
+
+    fn from_file(file: OffsetsFile, default_name: &str) -> Result<Self, CustomTemperamentError> {
+        let root = match &file.root {
+            Some(name) => {
+                parse_root(name).ok_or_else(|| CustomTemperamentError::Root(name.clone()))?
+            }
+            None => 0,
+        };
+        let offsets: [f32; 12] = file
+            .offsets
+            .as_slice()
+            .try_into()
+            .map_err(|_| CustomTemperamentError::OffsetCount(file.offsets.len()))?;
+        let name = file.name.unwrap_or_else(|| default_name.to_string());
+        Self::new(name, root, offsets)
+    }
+
+    /// A temperament with offsets checked to be within range.
+    fn new(name: String, root: u8, offsets: [f32; 12]) -> Result<Self, CustomTemperamentError> {
+        if let Some((i, &offset)) = offsets
+            .iter()
+            .enumerate()
+            .find(|(_, offset)| offset.is_nan() || offset.abs() > MAX_OFFSET)
+        {
+            return Err(CustomTemperamentError::OffsetRange {
+                pitch_class: root_name(root + i as u8).to_string(),
+                offset,
+            });
+        }
+        Ok(Self {
+            name,
+            root,
+            offsets,
+        })
+    }
+
+    /// Write the temperament as a TOML file.
+    pub fn to_toml(&self) -> String {
+        let offsets: Vec<String> = self.offsets.iter().map(|o| o.to_string()).collect();
+        format!(
+            "name = {:?}\nroot = {:?}\noffsets = [{}]\n",
+            self.name,
+            root_name(self.root),
+            offsets.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuning::temperament::Temperament;
+
+    const KELLNER: &str = r#"
+        name = "Kellner"
+        offsets = [8.2, 0.4, 2.7, 2.3, -0.7, 6.3, -1.6, 5.5, 1.2, 0.0, 4.3, -2.7]
+    "#;
+
+    #[test]
+    fn test_file_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let kellner = CustomTemperament::from_toml(KELLNER, "unused").unwrap();
+        assert_eq!(kellner.name, "Kellner");
+        assert_eq!(kellner.root, 0);
+
+        let path = dir.path().join("kellner.toml");
+        fs::write(&path, kellner.to_toml()).unwrap();
+        assert_eq!(CustomTemperament::load(&path).unwrap(), kellner);
+
+        // Every octave carries the offsets
+        let temperament = Temperament::from_offsets_file(&path).unwrap();
+        let equal = Temperament::new();
+        for midi in 21..=108u8 {
+            let cents = equal
+                .cents_from_target(temperament.frequency(midi), equal.frequency(midi))
+                .unwrap();
+            let expected = kellner.offsets[midi as usize % 12];
+            assert!((cents - expected).abs() < 0.01, "{}: {}", midi, cents);
+        }
+    }
+
+    #[test]
+    fn test_json_with_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("from-g.json");
+        fs::write(
+            &path,
+            r#"{"root": "G", "offsets": [5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]}"#,
+        )
+        .unwrap();
+        let custom = CustomTemperament::load(&path).unwrap();
+        assert_eq!(custom.name, "from-g");
+        assert_eq!(custom.root, 7);
+
+        // The first offset is G's, measured from A
+        let temperament = Temperament::new().with_offsets(custom.offsets, custom.root);
+        let equal = Temperament::new();
+        let cents = |midi: u8| {
+            equal
+                .cents_from_target(temperament.frequency(midi), equal.frequency(midi))
+                .unwrap()
+        };
+        assert!((cents(67) - 5.0).abs() < 0.01);
+        assert!((cents(79) - 5.0).abs() < 0.01);
+        assert!(cents(60).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_malformed_files_rejected() {
+        let error = |content: &str| {
+            CustomTemperament::from_toml(content, "bad")
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error("offsets = [1.0, 2.0]"),
+            "Expected 12 offsets, one per pitch class, found 2"
+        );
+        assert_eq!(
+            error("offsets = [0, 0, 0, 0, 120, 0, 0, 0, 0, 0, 0, 0]"),
+            "Offset 120 cents for E is outside ±100 cents"
+        );
+        assert_eq!(
+            error("root = \"H\"\noffsets = [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]"),
+            "Unknown root key: H"
+        );
+        assert!(error("name = \"No offsets\"").starts_with("Invalid temperament file"));
+    }
+
+    #[test]
+    fn test_scala_scale_checked() {
+        let custom = CustomTemperament::from_scl(
+            "Just\n12\n16/15\n9/8\n6/5\n5/4\n4/3\n45/32\n3/2\n8/5\n5/3\n9/5\n15/8\n2/1\n",
+            "unused",
+        )
+        .unwrap();
+        assert_eq!(custom.name, "Just");
+        assert!((custom.offsets[4] + 13.69).abs() < 0.01);
+
+        // Mapped onto the keys, a note more than a semitone off is refused
+        let error = CustomTemperament::from_scl(
+            "Wide\n12\n250.\n200.\n300.\n400.\n500.\n600.\n700.\n800.\n900.\n1000.\n1100.\n2/1\n",
+            "unused",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Offset 150 cents for C# is outside ±100 cents"
+        );
+        let error = CustomTemperament::from_scl("Pentatonic\n5\n9/8\n5/4\n3/2\n5/3\n2/1\n", "x")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid Scala file: A scale of 5 notes doesn't map onto the 12 keys of the octave"
+        );
+    }
+
+    #[test]
+    fn test_load_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("b.toml"), KELLNER).unwrap();
+        fs::write(dir.path().join("a.json"), r#"{"offsets": [1]}"#).unwrap();
+        fs::write(
+            dir.path().join("c.scl"),
+            "\n12\n100.\n200.\n300.\n400.\n500.\n600.\n700.\n800.\n900.\n1000.\n1100.\n2/1\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let loaded = CustomTemperament::load_dir(dir.path());
+        assert_eq!(loaded.len(), 3);
+        assert!(loaded[0].0.ends_with("a.json"));
+        assert!(matches!(
+            loaded[0].1,
+            Err(CustomTemperamentError::OffsetCount(1))
+        ));
+        assert_eq!(loaded[1].1.as_ref().unwrap().name, "Kellner");
+        // A scale without a description is named after its file
+        let scale = loaded[2].1.as_ref().unwrap();
+        assert_eq!(scale.name, "c");
+        assert_eq!(scale.offsets, [0.0; 12]);
+
+        assert!(CustomTemperament::load_dir(&dir.path().join("missing")).is_empty());
+    }
+}
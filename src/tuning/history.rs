@@ -0,0 +1,106 @@
+//! Comparison of a piano's sessions over time.
+//!
+//! Each session is compared with the one before it: the deviation found at
+//! its start is how far the piano drifted since, and the change in its
+//! measured A4 is how far the pitch as a whole wandered.
+
+use super::session::Session;
+
+/// Days assumed in a month for drift rates.
+const DAYS_PER_MONTH: f32 = 30.0;
+
+/// How a piano changed between two sessions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Drift {
+    /// Days between the end of the earlier session and the start of the later.
+    pub days: f32,
+    /// Average absolute deviation at the start of the later session in
+    /// cents, unless it repeats the earlier session's measurement.
+    pub start_deviation: Option<f32>,
+    /// Change in the measured A4 in Hz.
+    pub a4_change: Option<f32>,
+}
+
+impl Drift {
+    /// Average drift in cents per month, if the later session was measured.
+    pub fn cents_per_month(&self) -> Option<f32> {
+        if self.days <= 0.0 {
+            return None;
+        }
+        self.start_deviation
+            .map(|cents| cents / self.days * DAYS_PER_MONTH)
+    }
+}
+
+/// Compare a session with the one before it.
+pub fn compare(previous: &Session, current: &Session) -> Drift {
+    let seconds = (current.created_at - previous.updated_at)
+        .num_seconds()
+        .max(0);
+    // A session seeded from the measurement before it found nothing new
+    let seeded = current.start_deviations() == previous.start_deviations();
+    Drift {
+        days: seconds as f32 / 86_400.0,
+        start_deviation: current.start_deviation().filter(|_| !seeded),
+        a4_change: current
+            .measured_a4()
+            .zip(previous.measured_a4())
+            .map(|(current, previous)| current - previous),
+    }
+}
+
+/// Drift of each session since the one before it, for sessions listed most
+/// recent first. The oldest session has nothing to compare with.
+pub fn drift(sessions: &[Session]) -> Vec<Option<Drift>> {
+    (0..sessions.len())
+        .map(|i| {
+            sessions
+                .get(i + 1)
+                .map(|previous| compare(previous, &sessions[i]))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuning::session::TuningMode;
+    use chrono::{Duration, Utc};
+
+    /// A measurement pass `days_ago`, with A4 and C4 off by the given cents.
+    fn measured(days_ago: i64, a4_cents: f32, c4_cents: f32) -> Session {
+        let mut session = Session::new(TuningMode::Measurement, 440.0);
+        session.created_at = Utc::now() - Duration::days(days_ago);
+        session.updated_at = session.created_at;
+        session.complete_note("A4", a4_cents);
+        session.complete_note("C4", c4_cents);
+        session.updated_at = session.created_at;
+        session
+    }
+
+    #[test]
+    fn test_drift_between_measurements() {
+        let sessions = [measured(0, -6.0, -10.0), measured(60, -2.0, -4.0)];
+        let drift = drift(&sessions);
+        assert_eq!(drift.len(), 2);
+        assert!(drift[1].is_none());
+
+        let drift = drift[0].unwrap();
+        assert!((drift.days - 60.0).abs() < 0.01);
+        assert_eq!(drift.start_deviation, Some(8.0));
+        assert!((drift.cents_per_month().unwrap() - 4.0).abs() < 0.01);
+        // A4 went from -2 to -6 cents
+        let expected = 440.0 * (2.0_f32.powf(-6.0 / 1200.0) - 2.0_f32.powf(-2.0 / 1200.0));
+        assert!((drift.a4_change.unwrap() - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_unmeasured_session_has_no_drift_rate() {
+        let mut tuned = Session::new(TuningMode::Concert, 440.0);
+        tuned.created_at = Utc::now();
+        let drift = compare(&measured(10, 0.0, 0.0), &tuned);
+        assert_eq!(drift.start_deviation, None);
+        assert_eq!(drift.cents_per_month(), None);
+        assert_eq!(drift.a4_change, None);
+    }
+}
@@ -0,0 +1,133 @@
+//! Instrument profiles: keyboard range, stringing and tuning defaults.
+//!
+//! The piano is the base profile; other keyboard instruments narrow its
+//! range, change the number of strings per note and turn off stretch.
+
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use super::notes::Note;
+use super::order::TuningOrder;
+use super::scheme::TemperamentScheme;
+use super::stretch::{PianoType, StretchCurve};
+
+/// Keyboard instrument being tuned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Instrument {
+    /// Modern piano, A0 to C8.
+    #[default]
+    Piano,
+    /// Harpsichord with two 8' choirs, F1 to F6.
+    Harpsichord,
+    /// Early piano, bichord throughout, F1 to F6.
+    Fortepiano,
+}
+
+impl Instrument {
+    /// All instruments, in selection order.
+    pub const ALL: [Instrument; 3] = [
+        Instrument::Piano,
+        Instrument::Harpsichord,
+        Instrument::Fortepiano,
+    ];
+
+    /// Display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Instrument::Piano => "Piano",
+            Instrument::Harpsichord => "Harpsichord",
+            Instrument::Fortepiano => "Fortepiano",
+        }
+    }
+
+    /// The next instrument in selection order, wrapping around.
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&i| i == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Keyboard range as MIDI notes.
+    pub fn range(self) -> RangeInclusive<u8> {
+        match self {
+            Instrument::Piano => 21..=108,
+            Instrument::Harpsichord | Instrument::Fortepiano => 29..=89,
+        }
+    }
+
+    /// Number of keys.
+    pub fn note_count(self) -> usize {
+        self.range().len()
+    }
+
+    /// Number of strings (or choirs) sounding for a note.
+    pub fn strings(self, note: &Note) -> u8 {
+        match self {
+            Instrument::Piano => note.strings,
+            Instrument::Harpsichord | Instrument::Fortepiano => 2,
+        }
+    }
+
+    /// Stretch curve, the preset of `piano` for a piano; only the piano's
+    /// stiff strings need stretching.
+    pub fn stretch(self, piano: PianoType) -> StretchCurve {
+        match self {
+            Instrument::Piano => StretchCurve::preset(piano),
+            Instrument::Harpsichord | Instrument::Fortepiano => StretchCurve::flat(),
+        }
+    }
+
+    /// Temperament used unless another is selected.
+    pub fn default_temperament(self) -> TemperamentScheme {
+        match self {
+            Instrument::Piano => TemperamentScheme::Equal,
+            Instrument::Harpsichord => TemperamentScheme::WerckmeisterIii,
+            Instrument::Fortepiano => TemperamentScheme::Vallotti,
+        }
+    }
+
+    /// Whether the strings are plucked from separate choirs rather than
+    /// struck together, so unisons are isolated with stops, not mutes.
+    pub fn has_choirs(self) -> bool {
+        self == Instrument::Harpsichord
+    }
+
+    /// Whether strings are set with tuning pins needing torque warnings.
+    pub fn has_pin_torque(self) -> bool {
+        self == Instrument::Piano
+    }
+
+    /// Tuning order over the instrument's range.
+    pub fn tuning_order(self) -> TuningOrder {
+        TuningOrder::for_range(self.range())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_piano_is_the_base_profile() {
+        let piano = Instrument::Piano;
+        assert_eq!(piano.note_count(), 88);
+        assert_eq!(piano.tuning_order().len(), 88);
+        assert_eq!(piano.default_temperament(), TemperamentScheme::Equal);
+        assert_eq!(piano.strings(Note::from_midi(21).unwrap()), 1);
+        assert_eq!(piano.strings(Note::from_midi(69).unwrap()), 3);
+    }
+
+    #[test]
+    fn test_harpsichord_order_covers_its_range() {
+        let order = Instrument::Harpsichord.tuning_order();
+        assert_eq!(order.len(), 61);
+        assert_eq!(order.note_at(0).unwrap().display_name(), "F3");
+        assert_eq!(order.note_at(12).unwrap().display_name(), "F4");
+        assert_eq!(order.note_at(36).unwrap().display_name(), "F6");
+        assert_eq!(order.note_at(60).unwrap().display_name(), "F1");
+        assert!(order.is_upward_phase(36));
+        assert!(order.is_downward_phase(37));
+        assert_eq!(order.position_of(21), None);
+    }
+}
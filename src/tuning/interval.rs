@@ -0,0 +1,380 @@
+//! Interval checks for judging a finished tuning.
+//!
+//! Two notes played together beat where their coincident partials meet
+//! (the 2nd partial of the lower note against the 1st of the upper for an
+//! octave, 3:2 for a fifth, 5:4 for a major third). Comparing the measured
+//! beat rate of each check interval with the expected rate grades the result.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::notes::Note;
+use super::stretch::StretchCurve;
+use super::temperament::Temperament;
+
+/// Kind of interval between two notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntervalKind {
+    /// Octave (2:1).
+    Octave,
+    /// Perfect fifth (3:2).
+    Fifth,
+    /// Perfect fourth (4:3).
+    Fourth,
+    /// Major third (5:4).
+    MajorThird,
+}
+
+impl IntervalKind {
+    /// Size of the interval in semitones.
+    pub fn semitones(self) -> u8 {
+        match self {
+            IntervalKind::Octave => 12,
+            IntervalKind::Fifth => 7,
+            IntervalKind::Fourth => 5,
+            IntervalKind::MajorThird => 4,
+        }
+    }
+
+    /// Coincident partial numbers as (lower note, upper note).
+    pub fn partials(self) -> (u32, u32) {
+        match self {
+            IntervalKind::Octave => (2, 1),
+            IntervalKind::Fifth => (3, 2),
+            IntervalKind::Fourth => (4, 3),
+            IntervalKind::MajorThird => (5, 4),
+        }
+    }
+
+    /// Beats per second between two frequencies sounded as this interval:
+    /// the difference between their coincident partials, assuming
+    /// harmonic partials.
+    pub fn beat_rate(self, lower_freq: f32, upper_freq: f32) -> f32 {
+        let (lower_partial, upper_partial) = self.partials();
+        (lower_partial as f32 * lower_freq - upper_partial as f32 * upper_freq).abs()
+    }
+
+    /// Display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            IntervalKind::Octave => "octave",
+            IntervalKind::Fifth => "fifth",
+            IntervalKind::Fourth => "fourth",
+            IntervalKind::MajorThird => "major third",
+        }
+    }
+}
+
+/// A single interval to check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntervalCheck {
+    /// MIDI note of the lower note.
+    pub lower: u8,
+    /// Interval above the lower note.
+    pub kind: IntervalKind,
+}
+
+impl IntervalCheck {
+    /// Create a new check.
+    pub fn new(lower: u8, kind: IntervalKind) -> Self {
+        Self { lower, kind }
+    }
+
+    /// MIDI note of the upper note.
+    pub fn upper(&self) -> u8 {
+        self.lower + self.kind.semitones()
+    }
+
+    /// Display label, e.g. "A0–A1 octave".
+    pub fn label(&self) -> String {
+        format!(
+            "{}–{} {}",
+            note_name(self.lower),
+            note_name(self.upper()),
+            self.kind.name()
+        )
+    }
+}
+
+fn note_name(midi: u8) -> String {
+    Note::from_midi(midi)
+        .map(|n| n.display_name())
+        .unwrap_or_default()
+}
+
+/// The standard check sequence: octaves on A up the keyboard, then fifths
+/// and major thirds in the temperament octave (F3–F4).
+pub fn check_steps() -> Vec<IntervalCheck> {
+    let mut steps: Vec<IntervalCheck> = (0..7)
+        .map(|octave| IntervalCheck::new(21 + octave * 12, IntervalKind::Octave))
+        .collect();
+
+    // F3–C4, A3–E4, C4–G4
+    for lower in [53, 57, 60] {
+        steps.push(IntervalCheck::new(lower, IntervalKind::Fifth));
+    }
+    // F3–A3, A3–C#4, C4–E4
+    for lower in [53, 57, 60] {
+        steps.push(IntervalCheck::new(lower, IntervalKind::MajorThird));
+    }
+
+    steps
+}
+
+/// Grade of an interval check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Grade {
+    /// Beat rate close to expected.
+    Good,
+    /// Noticeably off.
+    Fair,
+    /// Far from expected.
+    Poor,
+}
+
+impl Grade {
+    /// Grade a measured beat rate against the expected one.
+    ///
+    /// No detected beating counts as 0 beats per second.
+    pub fn from_rates(measured: Option<f32>, expected: f32) -> Self {
+        let error = (measured.unwrap_or(0.0) - expected).abs();
+        let tolerance = (expected * 0.25).max(0.5);
+
+        if error <= tolerance {
+            Grade::Good
+        } else if error <= tolerance * 2.0 {
+            Grade::Fair
+        } else {
+            Grade::Poor
+        }
+    }
+
+    /// Display label.
+    pub fn label(self) -> &'static str {
+        match self {
+            Grade::Good => "Good",
+            Grade::Fair => "Fair",
+            Grade::Poor => "Poor",
+        }
+    }
+}
+
+/// Result of one interval check.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntervalResult {
+    /// Interval label (e.g., "A0–A1 octave").
+    pub label: String,
+    /// Expected beat rate in beats per second.
+    pub expected: f32,
+    /// Measured beat rate, if any beating was detected.
+    pub measured: Option<f32>,
+    /// Grade of the check.
+    pub grade: Grade,
+}
+
+/// Steps through the interval checks, listening for each pair to be struck.
+///
+/// A strike is a sudden rise in input level; the beat rate is measured once
+/// the notes have rung for [`LISTEN_TIME`](Self::LISTEN_TIME), skipping the
+/// attack.
+pub struct IntervalChecker {
+    /// Checks to run.
+    steps: Vec<IntervalCheck>,
+    /// Index of the current check.
+    current: usize,
+    /// Results so far.
+    results: Vec<IntervalResult>,
+    /// Temperament for target frequencies.
+    temperament: Temperament,
+    /// Stretch applied to expected beat rates.
+    stretch: StretchCurve,
+    /// Time of the last detected strike.
+    strike: Option<Duration>,
+    /// Previous input level.
+    last_level: f32,
+}
+
+impl IntervalChecker {
+    /// How long the notes must ring before they are analyzed.
+    pub const LISTEN_TIME: Duration = Duration::from_secs(3);
+
+    /// Length of the analyzed window, ending at the current time.
+    pub const ANALYSIS_WINDOW: Duration = Duration::from_millis(2500);
+
+    /// Level rise (ratio) that counts as a strike.
+    const ONSET_RATIO: f32 = 3.0;
+
+    /// Minimum level for a strike.
+    const MIN_LEVEL: f32 = 0.01;
+
+    /// Create a checker with the standard check sequence.
+    pub fn new(temperament: Temperament) -> Self {
+        Self::with_steps(temperament, check_steps())
+    }
+
+    /// Create a checker with custom steps.
+    pub fn with_steps(temperament: Temperament, steps: Vec<IntervalCheck>) -> Self {
+        Self {
+            steps,
+            current: 0,
+            results: Vec::new(),
+            temperament,
+            stretch: StretchCurve::new(),
+            strike: None,
+            last_level: 0.0,
+        }
+    }
+
+    /// Expect beat rates for the given stretch curve.
+    pub fn with_stretch(mut self, stretch: StretchCurve) -> Self {
+        self.stretch = stretch;
+        self
+    }
+
+    /// Get the current check, if any remain.
+    pub fn current(&self) -> Option<&IntervalCheck> {
+        self.steps.get(self.current)
+    }
+
+    /// Index of the current check.
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// Total number of checks.
+    pub fn total(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Target frequencies of the current check as (lower, upper).
+    pub fn current_frequencies(&self) -> Option<(f32, f32)> {
+        self.current().map(|check| {
+            (
+                self.stretch
+                    .apply(self.temperament.frequency(check.lower), check.lower),
+                self.stretch
+                    .apply(self.temperament.frequency(check.upper()), check.upper()),
+            )
+        })
+    }
+
+    /// Expected beat rate of a check.
+    pub fn expected(&self, check: &IntervalCheck) -> f32 {
+        self.temperament
+            .beat_rate(check.lower, check.upper(), check.kind, Some(&self.stretch))
+    }
+
+    /// Record the input level; detects strikes.
+    pub fn push_level(&mut self, now: Duration, level: f32) {
+        if level >= Self::MIN_LEVEL && level > self.last_level * Self::ONSET_RATIO {
+            self.strike = Some(now);
+        }
+        self.last_level = level;
+    }
+
+    /// Whether the notes have been struck and rung long enough to analyze.
+    pub fn is_ready(&self, now: Duration) -> bool {
+        self.strike
+            .is_some_and(|t| now.saturating_sub(t) >= Self::LISTEN_TIME)
+    }
+
+    /// Listening progress since the last strike (0.0 to 1.0).
+    pub fn listen_progress(&self, now: Duration) -> f32 {
+        match self.strike {
+            Some(t) => {
+                (now.saturating_sub(t).as_secs_f32() / Self::LISTEN_TIME.as_secs_f32()).min(1.0)
+            }
+            None => 0.0,
+        }
+    }
+
+    /// Grade the current check with a measured beat rate and move on.
+    pub fn record(&mut self, measured: Option<f32>) {
+        let Some(check) = self.current().copied() else {
+            return;
+        };
+
+        let expected = self.expected(&check);
+        self.results.push(IntervalResult {
+            label: check.label(),
+            expected,
+            measured,
+            grade: Grade::from_rates(measured, expected),
+        });
+        self.advance();
+    }
+
+    /// Skip the current check without a result.
+    pub fn skip(&mut self) {
+        self.advance();
+    }
+
+    /// Whether all checks are done.
+    pub fn is_complete(&self) -> bool {
+        self.current >= self.steps.len()
+    }
+
+    /// Get the results so far.
+    pub fn results(&self) -> &[IntervalResult] {
+        &self.results
+    }
+
+    fn advance(&mut self) {
+        self.current += 1;
+        self.strike = None;
+        self.last_level = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_steps() {
+        let steps = check_steps();
+        assert_eq!(steps.len(), 13);
+        assert_eq!(steps[0].label(), "A0–A1 octave");
+        assert_eq!(steps[6].label(), "A6–A7 octave");
+        assert_eq!(steps[7].label(), "F3–C4 fifth");
+        assert_eq!(steps[12].label(), "C4–E4 major third");
+    }
+
+    #[test]
+    fn test_beat_rate_of_targets() {
+        // An equal-tempered fourth C4–F4 beats about 1.2 times per second
+        let temperament = Temperament::new();
+        let fourth =
+            IntervalKind::Fourth.beat_rate(temperament.frequency(60), temperament.frequency(65));
+        assert!((fourth - 1.2).abs() < 0.05, "Got {}", fourth);
+
+        // A just third doesn't beat
+        assert!(IntervalKind::MajorThird.beat_rate(400.0, 500.0) < 1e-3);
+    }
+
+    #[test]
+    fn test_grade() {
+        assert_eq!(Grade::from_rates(Some(10.0), 10.4), Grade::Good);
+        assert_eq!(Grade::from_rates(None, 0.3), Grade::Good);
+        assert_eq!(Grade::from_rates(Some(6.0), 10.4), Grade::Fair);
+        assert_eq!(Grade::from_rates(Some(3.0), 0.5), Grade::Poor);
+    }
+
+    #[test]
+    fn test_strike_and_listen() {
+        let mut checker = IntervalChecker::new(Temperament::new());
+        checker.push_level(Duration::ZERO, 0.001);
+        assert!(!checker.is_ready(Duration::from_secs(5)));
+
+        checker.push_level(Duration::from_secs(1), 0.2);
+        assert!(!checker.is_ready(Duration::from_secs(2)));
+        assert!(checker.is_ready(Duration::from_secs(4)));
+
+        checker.record(None);
+        assert_eq!(checker.current_index(), 1);
+        assert_eq!(checker.results().len(), 1);
+        assert!(!checker.is_ready(Duration::from_secs(10)));
+    }
+}
@@ -1,13 +1,33 @@
 //! Tuning logic, temperament calculations, and session management.
 
+pub mod capture;
+pub mod custom;
+pub mod history;
+pub mod instrument;
+pub mod interval;
 pub mod notes;
 pub mod order;
+pub mod overrides;
+pub mod pitch_raise;
+pub mod plan;
+pub mod profile;
+pub mod scala;
+pub mod scheme;
 pub mod session;
+pub mod stats;
 pub mod stretch;
 pub mod temperament;
 
+pub use capture::TargetCapture;
+pub use custom::CustomTemperament;
+pub use instrument::Instrument;
 pub use notes::{Note, NOTES, NOTE_COUNT};
-pub use order::TuningOrder;
+pub use order::{OrderStrategy, Section, StringOrder, TrichordString, TuningOrder};
+pub use overrides::TargetOverrides;
+pub use plan::{TargetEntry, TuningPlan};
+pub use profile::{CurveSource, StretchProfile};
+pub use scheme::TemperamentScheme;
 pub use session::{CompletedNote, Session, TuningMode};
-pub use stretch::StretchCurve;
+pub use stats::SessionStats;
+pub use stretch::{OctaveType, PianoType, StretchCurve, StretchError};
 pub use temperament::Temperament;
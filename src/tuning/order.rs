@@ -4,6 +4,14 @@
 //! 1. Temperament octave (F3-F4): 12 notes that form the foundation
 //! 2. Octaves upward (F4→C8): Each note tuned as octave from below
 //! 3. Octaves downward (F3→A0): Each note tuned as octave from above
+//!
+//! Tuners differ on the sequence, so the order comes in strategies: the
+//! above, the aural order with the bass before the treble, or straight up
+//! the keyboard.
+
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
 
 use super::notes::{Note, NOTES};
 
@@ -16,45 +24,223 @@ const C8_MIDI: u8 = 108;
 /// Index in NOTES array for key reference points.
 const F3_INDEX: usize = (F3_MIDI - A0_MIDI) as usize; // 32
 const F4_INDEX: usize = (F4_MIDI - A0_MIDI) as usize; // 44
-const C8_INDEX: usize = (C8_MIDI - A0_MIDI) as usize; // 87
+
+/// Register of the keyboard, split around the temperament octave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    /// A0 to E3.
+    Bass,
+    /// F3 to F4.
+    Temperament,
+    /// F#4 to C8.
+    Treble,
+}
+
+impl Section {
+    /// All sections from low to high.
+    pub const ALL: [Section; 3] = [Section::Bass, Section::Temperament, Section::Treble];
+
+    /// Get the section a MIDI note belongs to.
+    pub fn of(midi: u8) -> Self {
+        if midi < F3_MIDI {
+            Section::Bass
+        } else if midi <= F4_MIDI {
+            Section::Temperament
+        } else {
+            Section::Treble
+        }
+    }
+
+    /// Display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            Section::Bass => "Bass",
+            Section::Temperament => "Temperament",
+            Section::Treble => "Treble",
+        }
+    }
+}
+
+/// One string of a trichord.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrichordString {
+    /// Left string.
+    Left,
+    /// Center string.
+    Center,
+    /// Right string.
+    Right,
+}
+
+impl TrichordString {
+    /// Display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            TrichordString::Left => "left",
+            TrichordString::Center => "center",
+            TrichordString::Right => "right",
+        }
+    }
+}
+
+/// Order in which the strings of a trichord are tuned.
+///
+/// The first string is muted free and tuned to pitch; the others are then
+/// tuned to unison with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StringOrder {
+    /// Center, then left, then right.
+    #[default]
+    CenterFirst,
+    /// Left, then center, then right.
+    LeftFirst,
+    /// Right, then center, then left.
+    RightFirst,
+}
+
+impl StringOrder {
+    /// The strings in tuning order.
+    pub fn strings(self) -> [TrichordString; 3] {
+        use TrichordString::*;
+        match self {
+            StringOrder::CenterFirst => [Center, Left, Right],
+            StringOrder::LeftFirst => [Left, Center, Right],
+            StringOrder::RightFirst => [Right, Center, Left],
+        }
+    }
+
+    /// The string tuned to pitch first.
+    pub fn reference(self) -> TrichordString {
+        self.strings()[0]
+    }
+
+    /// String that a later string is tuned to unison with: the center
+    /// string, or the reference when tuning the center itself.
+    pub fn partner(self, string: TrichordString) -> TrichordString {
+        if string == TrichordString::Center {
+            self.reference()
+        } else {
+            TrichordString::Center
+        }
+    }
+}
+
+/// Sequence the keys are tuned in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OrderStrategy {
+    /// Temperament octave, then octaves up through the treble, then down
+    /// through the bass.
+    #[default]
+    TrebleFirst,
+    /// Temperament octave, then octaves down through the bass, then up
+    /// through the treble.
+    AuralTemperamentFirst,
+    /// Every key from the bottom up.
+    Chromatic,
+}
+
+impl OrderStrategy {
+    /// All strategies, in selection order.
+    pub const ALL: [OrderStrategy; 3] = [
+        OrderStrategy::TrebleFirst,
+        OrderStrategy::AuralTemperamentFirst,
+        OrderStrategy::Chromatic,
+    ];
+
+    /// Display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            OrderStrategy::TrebleFirst => "Temperament, treble, bass",
+            OrderStrategy::AuralTemperamentFirst => "Temperament, bass, treble",
+            OrderStrategy::Chromatic => "Chromatic",
+        }
+    }
+
+    /// The next strategy, wrapping around.
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&s| s == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// The previous strategy, wrapping around.
+    pub fn prev(self) -> Self {
+        let i = Self::ALL.iter().position(|&s| s == self).unwrap_or(0);
+        Self::ALL[(i + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
 
 /// Tuning order generator following traditional piano tuning order.
 pub struct TuningOrder {
     /// Ordered indices into the NOTES array.
     order: Vec<usize>,
+    /// Keyboard range as MIDI notes.
+    range: RangeInclusive<u8>,
+    /// Sequence the keys are tuned in.
+    strategy: OrderStrategy,
 }
 
 impl TuningOrder {
-    /// Create a new tuning order.
+    /// Create a new tuning order for the full 88-key piano.
     pub fn new() -> Self {
+        Self::for_range(A0_MIDI..=C8_MIDI)
+    }
+
+    /// Create a tuning order for a keyboard spanning `range` (MIDI notes),
+    /// which must include the temperament octave.
+    pub fn for_range(range: RangeInclusive<u8>) -> Self {
+        let range = *range.start().max(&A0_MIDI)..=*range.end().min(&C8_MIDI);
+        let strategy = OrderStrategy::default();
         Self {
-            order: Self::generate_order(),
+            order: Self::generate_order(&range, strategy),
+            range,
+            strategy,
         }
     }
 
-    /// Generate the traditional tuning order.
-    ///
-    /// Order:
-    /// 1. Temperament octave (F3-F4): 12 notes, indices 32-44
-    /// 2. Octaves upward (F#4→C8): indices 45-87
-    /// 3. Octaves downward (E3→A0): indices 31-0
-    fn generate_order() -> Vec<usize> {
-        let mut order = Vec::with_capacity(88);
+    /// Tune the keys in the sequence of `strategy`.
+    pub fn with_strategy(mut self, strategy: OrderStrategy) -> Self {
+        self.order = Self::generate_order(&self.range, strategy);
+        self.strategy = strategy;
+        self
+    }
 
-        // 1. Temperament octave: F3 to F4 (inclusive)
-        // This is 13 notes (F3, F#3, G3, G#3, A3, A#3, B3, C4, C#4, D4, D#4, E4, F4)
-        for i in F3_INDEX..=F4_INDEX {
-            order.push(i);
-        }
+    /// Get the sequence the keys are tuned in.
+    pub fn strategy(&self) -> OrderStrategy {
+        self.strategy
+    }
 
-        // 2. Octaves upward: F#4 to C8
-        for i in (F4_INDEX + 1)..=C8_INDEX {
-            order.push(i);
+    /// Generate the tuning order of a strategy.
+    ///
+    /// Temperament first:
+    /// 1. Temperament octave (F3-F4): 13 notes, indices 32-44
+    /// 2. Octaves upward (F#4→top): indices 45 up
+    /// 3. Octaves downward (E3→bottom): indices 31 down
+    ///
+    /// The aural order swaps the last two; chromatic goes straight up.
+    fn generate_order(range: &RangeInclusive<u8>, strategy: OrderStrategy) -> Vec<usize> {
+        let index = |midi: u8| (midi - A0_MIDI) as usize;
+        let (low, high) = (index(*range.start()), index(*range.end()));
+        if strategy == OrderStrategy::Chromatic {
+            return (low..=high).collect();
         }
+        let mut order = Vec::with_capacity(high + 1 - low);
 
-        // 3. Octaves downward: E3 to A0
-        for i in (0..F3_INDEX).rev() {
-            order.push(i);
+        // 1. Temperament octave: F3 to F4 (inclusive)
+        // This is 13 notes (F3, F#3, G3, G#3, A3, A#3, B3, C4, C#4, D4, D#4, E4, F4)
+        order.extend(F3_INDEX..=F4_INDEX);
+
+        // 2. and 3. Octaves upward from F#4 to the top, and downward from
+        // E3 to the bottom
+        let upward = (F4_INDEX + 1)..=high;
+        let downward = (low..F3_INDEX).rev();
+        if strategy == OrderStrategy::AuralTemperamentFirst {
+            order.extend(downward);
+            order.extend(upward);
+        } else {
+            order.extend(upward);
+            order.extend(downward);
         }
 
         order
@@ -87,31 +273,38 @@ impl TuningOrder {
 
     /// Find the position of a note in the tuning order.
     pub fn position_of(&self, midi: u8) -> Option<usize> {
-        if !(A0_MIDI..=C8_MIDI).contains(&midi) {
+        if !self.range.contains(&midi) {
             return None;
         }
         let note_index = (midi - A0_MIDI) as usize;
         self.order.iter().position(|&i| i == note_index)
     }
 
+    /// Section of the keyboard the note at a position is in.
+    fn section_at(&self, position: usize) -> Option<Section> {
+        self.note_at(position).map(|note| Section::of(note.midi))
+    }
+
     /// Check if we're in the temperament octave phase.
     pub fn is_temperament_phase(&self, position: usize) -> bool {
-        position < 13 // F3 to F4 is 13 notes
+        self.section_at(position) == Some(Section::Temperament)
     }
 
     /// Check if we're in the upward phase.
     pub fn is_upward_phase(&self, position: usize) -> bool {
-        (13..13 + 43).contains(&position) // F#4 to C8 is 43 notes
+        self.section_at(position) == Some(Section::Treble)
     }
 
     /// Check if we're in the downward phase.
     pub fn is_downward_phase(&self, position: usize) -> bool {
-        position >= 13 + 43 // E3 to A0 is 32 notes
+        self.section_at(position) == Some(Section::Bass)
     }
 
     /// Get the phase name for a position.
     pub fn phase_name(&self, position: usize) -> &'static str {
-        if self.is_temperament_phase(position) {
+        if self.strategy == OrderStrategy::Chromatic {
+            "Chromatic"
+        } else if self.is_temperament_phase(position) {
             "Temperament Octave"
         } else if self.is_upward_phase(position) {
             "Octaves Up"
@@ -233,7 +426,7 @@ mod tests {
         // Downward phase starts at position 56
         let downward_start = 13 + 43;
 
-        for i in downward_start..(87) {
+        for i in downward_start..87 {
             let current = notes[i].midi;
             let next = notes[i + 1].midi;
             assert_eq!(
@@ -303,4 +496,98 @@ mod tests {
         assert_eq!(order.phase_name(56), "Octaves Down");
         assert_eq!(order.phase_name(87), "Octaves Down");
     }
+
+    /// The first dozen notes of an order.
+    fn first_dozen(order: &TuningOrder) -> Vec<String> {
+        order.notes()[..12]
+            .iter()
+            .map(|note| note.display_name())
+            .collect()
+    }
+
+    #[test]
+    fn test_strategies() {
+        let treble = TuningOrder::new();
+        assert_eq!(treble.strategy(), OrderStrategy::TrebleFirst);
+        assert_eq!(
+            first_dozen(&treble),
+            ["F3", "F#3", "G3", "G#3", "A3", "A#3", "B3", "C4", "C#4", "D4", "D#4", "E4"]
+        );
+
+        let aural = TuningOrder::new().with_strategy(OrderStrategy::AuralTemperamentFirst);
+        assert_eq!(
+            first_dozen(&aural),
+            ["F3", "F#3", "G3", "G#3", "A3", "A#3", "B3", "C4", "C#4", "D4", "D#4", "E4"]
+        );
+        let after_temperament: Vec<String> = aural.notes()[13..25]
+            .iter()
+            .map(|n| n.display_name())
+            .collect();
+        assert_eq!(
+            after_temperament,
+            ["E3", "D#3", "D3", "C#3", "C3", "B2", "A#2", "A2", "G#2", "G2", "F#2", "F2"]
+        );
+        assert_eq!(aural.position_of(A0_MIDI), Some(44));
+        assert_eq!(aural.note_at(45).unwrap().display_name(), "F#4");
+        assert_eq!(aural.note_at(87).unwrap().display_name(), "C8");
+        assert!(aural.is_downward_phase(13));
+        assert!(aural.is_upward_phase(45));
+        assert_eq!(aural.phase_name(13), "Octaves Down");
+
+        let chromatic = TuningOrder::new().with_strategy(OrderStrategy::Chromatic);
+        assert_eq!(
+            first_dozen(&chromatic),
+            ["A0", "A#0", "B0", "C1", "C#1", "D1", "D#1", "E1", "F1", "F#1", "G1", "G#1"]
+        );
+        assert_eq!(chromatic.position_of(69), Some(48));
+        assert_eq!(chromatic.phase_name(0), "Chromatic");
+
+        // Every strategy tunes each key exactly once
+        for strategy in OrderStrategy::ALL {
+            let order = TuningOrder::new().with_strategy(strategy);
+            let mut indices = order.indices().to_vec();
+            indices.sort_unstable();
+            assert_eq!(indices, (0..88).collect::<Vec<_>>(), "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn test_strategies_over_a_shorter_range() {
+        let order =
+            TuningOrder::for_range(29..=89).with_strategy(OrderStrategy::AuralTemperamentFirst);
+        assert_eq!(order.len(), 61);
+        assert_eq!(order.note_at(13).unwrap().display_name(), "E3");
+        assert_eq!(order.note_at(36).unwrap().display_name(), "F1");
+        assert_eq!(order.note_at(37).unwrap().display_name(), "F#4");
+        assert_eq!(order.note_at(60).unwrap().display_name(), "F6");
+
+        let chromatic = TuningOrder::for_range(29..=89).with_strategy(OrderStrategy::Chromatic);
+        assert_eq!(chromatic.note_at(0).unwrap().display_name(), "F1");
+        assert_eq!(chromatic.note_at(60).unwrap().display_name(), "F6");
+        assert_eq!(OrderStrategy::Chromatic.next(), OrderStrategy::TrebleFirst);
+        assert_eq!(OrderStrategy::TrebleFirst.prev(), OrderStrategy::Chromatic);
+    }
+
+    #[test]
+    fn test_section_boundaries() {
+        assert_eq!(Section::of(A0_MIDI), Section::Bass);
+        assert_eq!(Section::of(F3_MIDI - 1), Section::Bass);
+        assert_eq!(Section::of(F3_MIDI), Section::Temperament);
+        assert_eq!(Section::of(F4_MIDI), Section::Temperament);
+        assert_eq!(Section::of(F4_MIDI + 1), Section::Treble);
+        assert_eq!(Section::of(C8_MIDI), Section::Treble);
+    }
+
+    #[test]
+    fn test_string_orders() {
+        use TrichordString::*;
+        assert_eq!(StringOrder::default(), StringOrder::CenterFirst);
+        assert_eq!(StringOrder::LeftFirst.strings(), [Left, Center, Right]);
+        assert_eq!(StringOrder::RightFirst.reference(), Right);
+
+        // Outer strings match the center; the center matches the reference
+        assert_eq!(StringOrder::CenterFirst.partner(Right), Center);
+        assert_eq!(StringOrder::LeftFirst.partner(Center), Left);
+        assert_eq!(StringOrder::RightFirst.partner(Left), Center);
+    }
 }
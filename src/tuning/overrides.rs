@@ -0,0 +1,78 @@
+//! Per-note target overrides.
+//!
+//! A few notes can be moved off their computed target without building a
+//! custom temperament, e.g. to sweeten a unison region that never sounds
+//! clean. Overrides are cents added after the temperament and stretch,
+//! kept with the piano's profile and the session.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Cents added to the target of single notes, per MIDI note.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TargetOverrides(BTreeMap<u8, f32>);
+
+impl TargetOverrides {
+    /// Create an empty set of overrides.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Override for a note in cents (0 if none).
+    pub fn get(&self, midi: u8) -> f32 {
+        self.0.get(&midi).copied().unwrap_or(0.0)
+    }
+
+    /// Set the override for a note; zero (or NaN) removes it.
+    pub fn set(&mut self, midi: u8, cents: f32) {
+        if cents == 0.0 || cents.is_nan() {
+            self.0.remove(&midi);
+        } else {
+            self.0.insert(midi, cents);
+        }
+    }
+
+    /// Whether no note is overridden.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Number of notes overridden.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Overridden notes and their cents, from the lowest note up.
+    pub fn iter(&self) -> impl Iterator<Item = (u8, f32)> + '_ {
+        self.0.iter().map(|(&midi, &cents)| (midi, cents))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zero_removes() {
+        let mut overrides = TargetOverrides::new();
+        overrides.set(60, 1.0);
+        overrides.set(62, f32::NAN);
+        assert_eq!(overrides.len(), 1);
+        overrides.set(60, 0.0);
+        assert!(overrides.is_empty());
+    }
+
+    #[test]
+    fn test_serialized_as_map() {
+        let mut overrides = TargetOverrides::new();
+        overrides.set(78, 1.5);
+        let json = serde_json::to_string(&overrides).unwrap();
+        assert_eq!(json, r#"{"78":1.5}"#);
+        assert_eq!(
+            serde_json::from_str::<TargetOverrides>(&json).unwrap(),
+            overrides
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! Pitch raise (overpull) calculations.
+//!
+//! Bringing a piano that is well off pitch back to target changes the total
+//! string tension on the plate, and notes tuned early drift back as the rest
+//! are moved. A pitch raise compensates by deliberately overshooting each
+//! note by a fraction of the distance it has to travel, so the piano settles
+//! close to pitch for a following fine tuning.
+
+use std::collections::BTreeMap;
+
+/// Fraction of the measured deviation to overshoot by.
+pub const OVERPULL_FACTOR: f32 = 0.25;
+
+/// Largest overpull ever applied, in cents.
+pub const MAX_OVERPULL: f32 = 25.0;
+
+/// Average deviation (in cents) above which a pitch raise is recommended
+/// before fine tuning.
+pub const PITCH_RAISE_THRESHOLD: f32 = 10.0;
+
+/// Overpull in cents for a note measured at `measured_cents` from target.
+///
+/// A note 20 cents flat is overpulled 5 cents sharp; a sharp note is
+/// lowered past its target by the same proportion.
+pub fn overpull_cents(measured_cents: f32) -> f32 {
+    (-measured_cents * OVERPULL_FACTOR).clamp(-MAX_OVERPULL, MAX_OVERPULL)
+}
+
+/// Overpull target offsets (MIDI note → cents) for a set of measurements.
+pub fn overpull_targets(measurements: &BTreeMap<u8, f32>) -> BTreeMap<u8, f32> {
+    measurements
+        .iter()
+        .map(|(&midi, &cents)| (midi, overpull_cents(cents)))
+        .collect()
+}
+
+/// Whether the measurements call for a pitch raise before fine tuning.
+pub fn recommends_pitch_raise(measurements: &BTreeMap<u8, f32>) -> bool {
+    if measurements.is_empty() {
+        return false;
+    }
+    let sum: f32 = measurements.values().map(|c| c.abs()).sum();
+    sum / measurements.len() as f32 > PITCH_RAISE_THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overpull_flat_note() {
+        assert!((overpull_cents(-20.0) - 5.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_overpull_sharp_note() {
+        assert!((overpull_cents(12.0) + 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_overpull_in_tune_note() {
+        assert_eq!(overpull_cents(0.0), 0.0);
+    }
+
+    #[test]
+    fn test_overpull_is_capped() {
+        assert_eq!(overpull_cents(-400.0), MAX_OVERPULL);
+        assert_eq!(overpull_cents(400.0), -MAX_OVERPULL);
+    }
+
+    #[test]
+    fn test_overpull_targets() {
+        let measurements: BTreeMap<u8, f32> = [(53, -20.0), (69, -8.0)].into_iter().collect();
+        let targets = overpull_targets(&measurements);
+
+        assert_eq!(targets.len(), 2);
+        assert!((targets[&53] - 5.0).abs() < 1e-6);
+        assert!((targets[&69] - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_recommends_pitch_raise() {
+        let flat: BTreeMap<u8, f32> = [(53, -25.0), (69, -18.0)].into_iter().collect();
+        assert!(recommends_pitch_raise(&flat));
+
+        let close: BTreeMap<u8, f32> = [(53, -3.0), (69, 2.0)].into_iter().collect();
+        assert!(!recommends_pitch_raise(&close));
+
+        assert!(!recommends_pitch_raise(&BTreeMap::new()));
+    }
+}
@@ -0,0 +1,133 @@
+//! The full table of targets a session tunes to.
+//!
+//! Every target is built the same way: the temperament (with its A4 and any
+//! transposition), then the stretch curve, then per-note overrides. The
+//! tuning screen and the exports both read their targets from here, so they
+//! can't disagree.
+
+use serde::{Deserialize, Serialize};
+
+use super::notes::NOTES;
+use super::overrides::TargetOverrides;
+use super::stretch::StretchCurve;
+use super::temperament::Temperament;
+
+/// Target of one key.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TargetEntry {
+    /// MIDI note number.
+    pub midi: u8,
+    /// Target frequency in Hz.
+    pub frequency: f32,
+    /// Offset from equal temperament at A4 = 440 Hz, in cents.
+    pub cents: f32,
+}
+
+/// Targets of the 88 keys.
+pub struct TuningPlan;
+
+impl TuningPlan {
+    /// Targets of all 88 keys, from A0 up.
+    pub fn compute(
+        temperament: &Temperament,
+        stretch: &StretchCurve,
+        overrides: &TargetOverrides,
+    ) -> Vec<TargetEntry> {
+        NOTES
+            .iter()
+            .map(|note| Self::target(temperament, stretch, overrides, note.midi))
+            .collect()
+    }
+
+    /// Target of a single key.
+    pub fn target(
+        temperament: &Temperament,
+        stretch: &StretchCurve,
+        overrides: &TargetOverrides,
+        midi: u8,
+    ) -> TargetEntry {
+        let adjustment = stretch.offset_cents_f64(midi) + overrides.get(midi) as f64;
+        let frequency =
+            temperament.frequency_f64(midi) * Temperament::cents_to_ratio_f64(adjustment);
+        let equal = Temperament::new().frequency_f64(midi);
+        TargetEntry {
+            midi,
+            frequency: frequency as f32,
+            cents: (1200.0 * (frequency / equal).log2()) as f32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuning::temperament::REFERENCE_FREQUENCIES;
+    use crate::tuning::TemperamentScheme;
+
+    #[test]
+    fn test_defaults_match_reference_frequencies() {
+        let plan = TuningPlan::compute(
+            &Temperament::new(),
+            &StretchCurve::flat(),
+            &TargetOverrides::new(),
+        );
+        assert_eq!(plan.len(), 88);
+        for (entry, &(midi, freq)) in plan.iter().zip(REFERENCE_FREQUENCIES.iter()) {
+            assert_eq!(entry.midi, midi);
+            assert!((entry.frequency - freq).abs() / freq < 1e-4, "{}", midi);
+            assert!(entry.cents.abs() < 0.01, "{}: {}", midi, entry.cents);
+        }
+    }
+
+    #[test]
+    fn test_stretch_at_442() {
+        let curve = StretchCurve::new();
+        let plan = TuningPlan::compute(
+            &Temperament::with_a4(442.0),
+            &curve,
+            &TargetOverrides::new(),
+        );
+        let a4_offset = 1200.0 * (442.0f32 / 440.0).log2();
+        for entry in &plan {
+            let stretch = entry.cents - a4_offset;
+            assert!(
+                (stretch - curve.offset_cents(entry.midi)).abs() < 0.01,
+                "{}: {}",
+                entry.midi,
+                stretch
+            );
+        }
+    }
+
+    #[test]
+    fn test_a0_target_precise() {
+        let curve = StretchCurve::new();
+        let mut overrides = TargetOverrides::new();
+        overrides.set(21, 0.7);
+        let entry = TuningPlan::target(&Temperament::with_a4(441.3), &curve, &overrides, 21);
+
+        // Four octaves below A4, then stretched and moved, worked out exactly
+        let a4 = 441.3f32 as f64;
+        let adjustment = curve.offset_cents_f64(21) + 0.7f32 as f64;
+        let expected = a4 / 16.0 * 2.0_f64.powf(adjustment / 1200.0);
+        let cents = 1200.0 * (a4 / 440.0).log2() + adjustment;
+        let error = 1200.0 * (entry.frequency as f64 / expected).log2();
+        assert!(error.abs() < 0.02, "{} cents", error);
+        assert!((entry.cents as f64 - cents).abs() < 0.02, "{}", entry.cents);
+    }
+
+    #[test]
+    fn test_overrides_on_top_of_temperament_and_stretch() {
+        let vallotti = Temperament::new().with_scheme(TemperamentScheme::Vallotti, 0);
+        let curve = StretchCurve::new();
+        let mut overrides = TargetOverrides::new();
+        overrides.set(84, 1.5);
+
+        let plain = TuningPlan::target(&vallotti, &curve, &TargetOverrides::new(), 84);
+        let moved = TuningPlan::target(&vallotti, &curve, &overrides, 84);
+        assert!((moved.cents - plain.cents - 1.5).abs() < 0.01);
+        let expected =
+            vallotti.frequency(84) * Temperament::cents_to_ratio(curve.offset_cents(84) + 1.5);
+        assert!((moved.frequency - expected).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,196 @@
+//! Stretch curves saved with a piano's profile.
+//!
+//! A curve built for one piano, from a preset, measurements or by hand, is
+//! saved once and selected on setup from then on. Targets of single notes
+//! moved for that piano, in cents per MIDI note, are kept with it:
+//!
+//! ```json
+//! {
+//!   "name": "Studio Yamaha",
+//!   "created_at": "2026-10-16T09:30:00Z",
+//!   "source": "measured",
+//!   "curve": { "offsets": [-18.2, -17.6, ...], "intensity": 1.0 },
+//!   "target_overrides": { "78": 1.5, "84": -1.0 }
+//! }
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::overrides::TargetOverrides;
+use super::stretch::StretchCurve;
+
+/// Error reading or writing a saved stretch curve.
+#[derive(Debug, thiserror::Error)]
+pub enum ProfileError {
+    #[error("Failed to read stretch curve: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid stretch curve file: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Where a saved curve came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CurveSource {
+    /// A piano type's preset.
+    Preset,
+    /// Fitted to measurements of the piano.
+    Measured,
+    /// Entered by hand.
+    Manual,
+}
+
+/// A named stretch curve, saved with the piano's profile.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StretchProfile {
+    /// Name shown on setup.
+    pub name: String,
+    /// When the curve was made.
+    pub created_at: DateTime<Utc>,
+    /// Where the curve came from.
+    pub source: CurveSource,
+    /// Offsets of the 88 keys.
+    pub curve: StretchCurve,
+    /// Cents added to single notes' targets on this piano.
+    #[serde(default, skip_serializing_if = "TargetOverrides::is_empty")]
+    pub target_overrides: TargetOverrides,
+}
+
+impl StretchProfile {
+    /// Name a curve made now.
+    pub fn new(name: impl Into<String>, source: CurveSource, curve: StretchCurve) -> Self {
+        Self {
+            name: name.into(),
+            created_at: Utc::now(),
+            source,
+            curve,
+            target_overrides: TargetOverrides::new(),
+        }
+    }
+
+    /// Move the targets of single notes on this piano.
+    pub fn with_target_overrides(mut self, overrides: TargetOverrides) -> Self {
+        self.target_overrides = overrides;
+        self
+    }
+
+    /// Load a saved curve.
+    pub fn load(path: &Path) -> Result<Self, ProfileError> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Save the curve in `dir`, named after it, and return the path.
+    pub fn save(&self, dir: &Path) -> Result<PathBuf, ProfileError> {
+        fs::create_dir_all(dir)?;
+        let path = dir.join(format!("{}.json", self.file_stem()));
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(path)
+    }
+
+    /// Load every `.json` file in `dir`, in file name order. A missing
+    /// directory holds none.
+    pub fn load_dir(dir: &Path) -> Vec<(PathBuf, Result<Self, ProfileError>)> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| {
+                let profile = Self::load(&path);
+                (path, profile)
+            })
+            .collect()
+    }
+
+    /// Directory saved curves are kept in.
+    pub fn dir() -> Option<PathBuf> {
+        directories::ProjectDirs::from("", "", "onkey").map(|dirs| dirs.data_dir().join("profiles"))
+    }
+
+    /// The name made safe for use as a file name.
+    fn file_stem(&self) -> String {
+        self.name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '-'
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuning::stretch::PianoType;
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let curve = StretchCurve::from_points(&[(21, -30.0), (69, 0.0), (108, 35.0)]).unwrap();
+        let mut overrides = TargetOverrides::new();
+        overrides.set(78, 1.5);
+        let profile = StretchProfile::new("Studio Yamaha", CurveSource::Measured, curve)
+            .with_target_overrides(overrides.clone());
+
+        let path = profile.save(dir.path()).unwrap();
+        assert_eq!(path, dir.path().join("Studio-Yamaha.json"));
+        let loaded = StretchProfile::load(&path).unwrap();
+        assert_eq!(loaded.name, "Studio Yamaha");
+        assert_eq!(loaded.created_at, profile.created_at);
+        assert_eq!(loaded.source, CurveSource::Measured);
+        assert_eq!(loaded.target_overrides, overrides);
+        assert!((21..=108).all(|midi| (loaded.curve.offset_cents_f64(midi)
+            - profile.curve.offset_cents_f64(midi))
+        .abs()
+            < 1e-9));
+    }
+
+    #[test]
+    fn test_load_dir_reports_bad_files() {
+        let dir = tempfile::tempdir().unwrap();
+        StretchProfile::new(
+            "Spinet",
+            CurveSource::Preset,
+            StretchCurve::preset(PianoType::Spinet),
+        )
+        .save(dir.path())
+        .unwrap();
+        fs::write(dir.path().join("corrupt.json"), "{ not json").unwrap();
+        fs::write(
+            dir.path().join("short.json"),
+            r#"{"name": "Short", "created_at": "2026-01-01T00:00:00Z",
+                "source": "manual", "curve": {"offsets": [-10.0, 0.0, 10.0]}}"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("notes.txt"), "skipped").unwrap();
+
+        let loaded = StretchProfile::load_dir(dir.path());
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded[0].0.file_name().unwrap(), "Spinet.json");
+        // Saved without overrides, and loaded with none
+        let spinet = loaded[0].1.as_ref().unwrap();
+        assert!(spinet.target_overrides.is_empty());
+        let json = fs::read_to_string(&loaded[0].0).unwrap();
+        assert!(!json.contains("target_overrides"));
+        assert!(matches!(loaded[1].1, Err(ProfileError::Json(_))));
+        let error = loaded[2].1.as_ref().unwrap_err().to_string();
+        assert!(error.contains("Expected 88 offsets"), "{}", error);
+
+        assert!(StretchProfile::load_dir(&dir.path().join("missing")).is_empty());
+    }
+}
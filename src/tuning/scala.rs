@@ -0,0 +1,236 @@
+//! Scala scale files (`.scl`).
+//!
+//! A scale file has a description line, the number of notes, then a pitch
+//! per note above the 1/1: cents if it has a decimal point, a ratio such as
+//! `3/2` or `2` otherwise. The last pitch is the interval the scale repeats
+//! at, usually `2/1`. Lines starting with `!` are comments.
+//!
+//! Scales of twelve notes to the octave map onto the keys directly; scales
+//! of 24, 36, ... notes are mapped by taking every second, third, ... note.
+
+/// Error reading a Scala scale.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum ScalaError {
+    #[error("Missing note count")]
+    MissingCount,
+    #[error("Invalid note count: {0}")]
+    InvalidCount(String),
+    #[error("Invalid pitch on line {line}: {text}")]
+    InvalidPitch { line: usize, text: String },
+    #[error("Expected {expected} pitches, found {found}")]
+    TooFewPitches { expected: usize, found: usize },
+    #[error("Scale repeats at {0:.1} cents, not at the octave")]
+    NotOctave(f32),
+    #[error("A scale of {0} notes doesn't map onto the 12 keys of the octave")]
+    Unmappable(usize),
+}
+
+/// Cents within which the repeat interval counts as an octave.
+const OCTAVE_TOLERANCE: f32 = 0.01;
+
+/// A scale read from a Scala file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scale {
+    /// Description line, possibly empty.
+    pub description: String,
+    /// Pitches above the 1/1 in cents; the last is the repeat interval.
+    pub pitches: Vec<f32>,
+}
+
+impl Scale {
+    /// Parse the contents of a Scala file.
+    pub fn parse(content: &str) -> Result<Self, ScalaError> {
+        let mut lines = content
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.starts_with('!'));
+
+        let description = lines
+            .next()
+            .map(|(_, line)| line.trim().to_string())
+            .unwrap_or_default();
+        let count = lines
+            .next()
+            .map(|(_, line)| first_token(line))
+            .ok_or(ScalaError::MissingCount)?;
+        let count: usize = count
+            .parse()
+            .map_err(|_| ScalaError::InvalidCount(count.to_string()))?;
+
+        let pitches = lines
+            .filter(|(_, line)| !line.trim().is_empty())
+            .take(count)
+            .map(|(i, line)| {
+                parse_pitch(first_token(line)).ok_or_else(|| ScalaError::InvalidPitch {
+                    line: i + 1,
+                    text: line.trim().to_string(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if pitches.len() < count {
+            return Err(ScalaError::TooFewPitches {
+                expected: count,
+                found: pitches.len(),
+            });
+        }
+
+        Ok(Self {
+            description,
+            pitches,
+        })
+    }
+
+    /// Offsets from equal temperament in cents of the twelve keys from the
+    /// 1/1 up, for a scale that repeats at the octave with a multiple of
+    /// twelve notes.
+    pub fn offsets(&self) -> Result<[f32; 12], ScalaError> {
+        let size = self.pitches.len();
+        let repeat = self.pitches.last().copied().unwrap_or(0.0);
+        if (repeat - 1200.0).abs() > OCTAVE_TOLERANCE {
+            return Err(ScalaError::NotOctave(repeat));
+        }
+        if size == 0 || !size.is_multiple_of(12) {
+            return Err(ScalaError::Unmappable(size));
+        }
+
+        let step = size / 12;
+        Ok(std::array::from_fn(|key| {
+            let cents = if key == 0 {
+                0.0
+            } else {
+                self.pitches[key * step - 1]
+            };
+            cents - 100.0 * key as f32
+        }))
+    }
+}
+
+/// The value on a pitch or count line; anything after it is a comment.
+fn first_token(line: &str) -> &str {
+    line.split_whitespace().next().unwrap_or("")
+}
+
+/// A pitch in cents, from cents (with a decimal point) or a ratio.
+fn parse_pitch(text: &str) -> Option<f32> {
+    if text.contains('.') {
+        return text.parse().ok();
+    }
+    let (numerator, denominator) = text.split_once('/').unwrap_or((text, "1"));
+    let numerator: f64 = numerator.parse::<u64>().ok()? as f64;
+    let denominator: f64 = denominator.parse::<u64>().ok()? as f64;
+    if numerator == 0.0 || denominator == 0.0 {
+        return None;
+    }
+    Some((1200.0 * (numerator / denominator).log2()) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuning::scheme::{offset_from, TemperamentScheme};
+
+    const WERCKMEISTER: &str = "\
+! werck3.scl
+!
+Andreas Werckmeister's temperament III (the most famous one, 1681)
+ 12
+!
+ 256/243
+ 192.18000
+ 32/27
+ 390.22500
+ 4/3
+ 1024/729
+ 696.09000
+ 128/81
+ 888.27000
+ 16/9
+ 1092.18000
+ 2/1
+";
+
+    #[test]
+    fn test_mixed_ratios_and_cents() {
+        let scale = Scale::parse(WERCKMEISTER).unwrap();
+        assert_eq!(
+            scale.description,
+            "Andreas Werckmeister's temperament III (the most famous one, 1681)"
+        );
+        assert_eq!(scale.pitches.len(), 12);
+        assert!((scale.pitches[3] - 390.225).abs() < 1e-3);
+        // The trailing 2/1 is the octave the scale repeats at
+        assert!((scale.pitches[11] - 1200.0).abs() < 1e-3);
+
+        // The same temperament as the built-in one
+        let offsets = scale.offsets().unwrap();
+        for midi in 60..72 {
+            let builtin = TemperamentScheme::WerckmeisterIii.offset_cents(midi);
+            let scala = offset_from(&offsets, midi, 0);
+            assert!((builtin - scala).abs() < 0.1, "{}: {}", midi, scala);
+        }
+    }
+
+    #[test]
+    fn test_pythagorean_ratios() {
+        let scale = Scale::parse(
+            "Pythagorean\n12\n2187/2048\n9/8\n32/27\n81/64\n4/3\n729/512\n3/2\n128/81\n27/16\n16/9\n243/128\n2\n",
+        )
+        .unwrap();
+        let offsets = scale.offsets().unwrap();
+        // Fifths are pure, thirds a comma wide
+        assert!((offsets[7] - 1.955).abs() < 1e-3);
+        assert!((offsets[4] - 7.82).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_larger_scales_mapped() {
+        // Quarter tones: every second note lands on a key
+        let mut content = String::from("24-tone equal\n 24 ! notes\n");
+        for i in 1..24 {
+            content.push_str(&format!("{}.0 quarter {}\n", i * 50, i));
+        }
+        content.push_str("2/1\n");
+        let scale = Scale::parse(&content).unwrap();
+        assert_eq!(scale.offsets().unwrap(), [0.0; 12]);
+    }
+
+    #[test]
+    fn test_unusable_scales() {
+        let pentatonic = Scale::parse("Pentatonic\n5\n9/8\n5/4\n3/2\n5/3\n2/1\n").unwrap();
+        assert_eq!(pentatonic.offsets(), Err(ScalaError::Unmappable(5)));
+
+        let mut tritave = String::from("Bohlen-Pierce\n12\n");
+        for i in 1..12 {
+            tritave.push_str(&format!("{}.\n", i * 150));
+        }
+        tritave.push_str("3/1\n");
+        let error = Scale::parse(&tritave).unwrap().offsets().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Scale repeats at 1902.0 cents, not at the octave"
+        );
+
+        assert_eq!(
+            Scale::parse("Short\n3\n3/2\n2/1\n"),
+            Err(ScalaError::TooFewPitches {
+                expected: 3,
+                found: 2
+            })
+        );
+        assert_eq!(
+            Scale::parse("Bad\n2\n3/0\n2/1\n"),
+            Err(ScalaError::InvalidPitch {
+                line: 3,
+                text: "3/0".to_string()
+            })
+        );
+        assert_eq!(
+            Scale::parse("Bad\ntwelve\n"),
+            Err(ScalaError::InvalidCount("twelve".to_string()))
+        );
+        assert_eq!(
+            Scale::parse("! only a comment\n"),
+            Err(ScalaError::MissingCount)
+        );
+    }
+}
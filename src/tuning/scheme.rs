@@ -0,0 +1,302 @@
+//! Temperament schemes: how the twelve notes of the octave are tempered.
+//!
+//! Historical schemes are given as offsets from equal temperament per pitch
+//! class, laid from C. A scheme can be moved to another root key; either way
+//! A stays at zero so the A4 reference is unchanged.
+
+use serde::{Deserialize, Serialize};
+
+/// Temperament scheme laid over equal temperament.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TemperamentScheme {
+    /// Equal temperament.
+    #[default]
+    Equal,
+    /// Werckmeister III (1691), a well temperament.
+    WerckmeisterIii,
+    /// Kirnberger III (1779), a well temperament with a pure C-E.
+    KirnbergerIii,
+    /// Vallotti (c. 1750), a well temperament.
+    Vallotti,
+    /// Young II (1799), Vallotti a fifth higher.
+    YoungIi,
+    /// Quarter-comma meantone, with the wolf between G# and Eb when laid
+    /// from C; another root key moves the wolf with it.
+    Meantone,
+}
+
+impl TemperamentScheme {
+    /// All schemes, in selection order.
+    pub const ALL: [TemperamentScheme; 6] = [
+        TemperamentScheme::Equal,
+        TemperamentScheme::WerckmeisterIii,
+        TemperamentScheme::Vallotti,
+        TemperamentScheme::Meantone,
+        TemperamentScheme::KirnbergerIii,
+        TemperamentScheme::YoungIi,
+    ];
+
+    /// Display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            TemperamentScheme::Equal => "Equal",
+            TemperamentScheme::WerckmeisterIii => "Werckmeister III",
+            TemperamentScheme::KirnbergerIii => "Kirnberger III",
+            TemperamentScheme::Vallotti => "Vallotti",
+            TemperamentScheme::YoungIi => "Young II",
+            TemperamentScheme::Meantone => "1/4-comma meantone",
+        }
+    }
+
+    /// The next scheme in selection order, wrapping around.
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&s| s == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Offsets from equal temperament in cents, from C to B.
+    pub fn offsets(self) -> [f32; 12] {
+        match self {
+            TemperamentScheme::Equal => [0.0; 12],
+            TemperamentScheme::WerckmeisterIii => {
+                [11.7, 1.9, 3.9, 5.8, 1.9, 9.7, 0.0, 7.8, 3.9, 0.0, 7.8, 3.9]
+            }
+            TemperamentScheme::KirnbergerIii => [
+                10.3, 0.5, 3.5, 4.4, -3.4, 8.3, 0.5, 6.9, 2.5, 0.0, 6.4, -1.4,
+            ],
+            TemperamentScheme::Vallotti => [
+                5.9, 0.0, 2.0, 3.9, -2.0, 7.8, -2.0, 3.9, 2.0, 0.0, 5.9, -3.9,
+            ],
+            TemperamentScheme::YoungIi => [
+                5.9, -3.9, 2.0, 0.0, -2.0, 3.9, -5.9, 3.9, -2.0, 0.0, 2.0, -3.9,
+            ],
+            TemperamentScheme::Meantone => {
+                // Fifths a quarter comma narrow, from Eb round to G#
+                let narrowing = 700.0 - (PURE_FIFTH - SYNTONIC_COMMA / 4.0);
+                std::array::from_fn(|pitch_class| {
+                    let fifths = (pitch_class * 7 % 12) as i32;
+                    let fifths = if fifths > 8 { fifths - 12 } else { fifths };
+                    (A_FIFTHS - fifths) as f32 * narrowing
+                })
+            }
+        }
+    }
+
+    /// Pitch classes (C = 0) of the lower and upper notes of the wolf fifth
+    /// with the scheme laid from `root`, for a scheme that has one.
+    pub fn wolf(self, root: u8) -> Option<(u8, u8)> {
+        match self {
+            TemperamentScheme::Meantone => Some(((root + 8) % 12, (root + 3) % 12)),
+            _ => None,
+        }
+    }
+
+    /// Name of the wolf fifth with the scheme laid from `root`, such as
+    /// "G#-Eb": the end of the chain of sharps, then of flats.
+    pub fn wolf_name(self, root: u8) -> Option<String> {
+        self.wolf(root).map(|(lower, upper)| {
+            format!(
+                "{}-{}",
+                SHARP_NAMES[lower as usize], FLAT_NAMES[upper as usize]
+            )
+        })
+    }
+
+    /// Offset of a MIDI note from equal temperament in cents, with the
+    /// scheme laid from C.
+    pub fn offset_cents(self, midi: u8) -> f32 {
+        self.offset_cents_from(midi, 0)
+    }
+
+    /// Offset of a MIDI note from equal temperament in cents, with the
+    /// scheme laid from the root pitch class `root` (C = 0) instead of C.
+    pub fn offset_cents_from(self, midi: u8, root: u8) -> f32 {
+        offset_from(&self.offsets(), midi, root)
+    }
+}
+
+/// Offset of a MIDI note in cents, from `offsets` per pitch class starting
+/// at the root pitch class `root` (C = 0), moved so that A is at zero.
+pub fn offset_from(offsets: &[f32; 12], midi: u8, root: u8) -> f32 {
+    let from_root = |pitch_class: usize| offsets[(pitch_class + 12 - root as usize % 12) % 12];
+    from_root(midi as usize % 12) - from_root(A)
+}
+
+/// Pitch class of A, which every scheme keeps at zero.
+const A: usize = 9;
+
+/// Fifths from C up to A.
+const A_FIFTHS: i32 = 3;
+
+/// A pure 3:2 fifth in cents.
+const PURE_FIFTH: f32 = 701.955;
+
+/// The syntonic comma (81:80) in cents.
+const SYNTONIC_COMMA: f32 = 21.506;
+
+/// Pitch class names from C, spelled with sharps.
+const SHARP_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Pitch class names from C, spelled with flats.
+const FLAT_NAMES: [&str; 12] = [
+    "C", "Db", "D", "Eb", "E", "F", "Gb", "G", "Ab", "A", "Bb", "B",
+];
+
+/// Pitch class names from C, as shown for a root key.
+const ROOT_NAMES: [&str; 12] = [
+    "C", "C#", "D", "Eb", "E", "F", "F#", "G", "Ab", "A", "Bb", "B",
+];
+
+/// Pitch class (C = 0) of a root key name such as "G", "F#" or "Bb".
+pub fn parse_root(name: &str) -> Option<u8> {
+    let name = name.trim();
+    let mut chars = name.chars();
+    let letter = chars.next()?.to_ascii_uppercase();
+    let natural: i32 = match letter {
+        'C' => 0,
+        'D' => 2,
+        'E' => 4,
+        'F' => 5,
+        'G' => 7,
+        'A' => 9,
+        'B' => 11,
+        _ => return None,
+    };
+    let accidental = match chars.as_str() {
+        "" => 0,
+        "#" | "♯" => 1,
+        "b" | "♭" => -1,
+        _ => return None,
+    };
+    Some((natural + accidental).rem_euclid(12) as u8)
+}
+
+/// Name of a root pitch class (C = 0).
+pub fn root_name(root: u8) -> &'static str {
+    ROOT_NAMES[root as usize % 12]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_a_is_the_reference() {
+        for scheme in TemperamentScheme::ALL {
+            assert_eq!(scheme.offset_cents(69), 0.0, "{}", scheme.name());
+            assert_eq!(scheme.offset_cents(57), 0.0, "{}", scheme.name());
+        }
+        assert!(TemperamentScheme::ALL
+            .iter()
+            .all(|s| s.offset_cents(60) == s.offset_cents(72)));
+    }
+
+    #[test]
+    fn test_meantone_thirds_are_pure() {
+        // A pure major third is 386.3 cents; equal temperament's is 400
+        let scheme = TemperamentScheme::Meantone;
+        let third = |lower: u8| 400.0 + scheme.offset_cents(lower + 4) - scheme.offset_cents(lower);
+        for lower in [60, 62, 65, 67] {
+            assert!((third(lower) - 386.3).abs() < 0.2, "{}", third(lower));
+        }
+    }
+
+    /// Size in cents of the interval from `lower` up to `upper`.
+    fn interval(scheme: TemperamentScheme, root: u8, lower: u8, upper: u8) -> f32 {
+        100.0 * (upper - lower) as f32 + scheme.offset_cents_from(upper, root)
+            - scheme.offset_cents_from(lower, root)
+    }
+
+    #[test]
+    fn test_published_interval_sizes() {
+        use TemperamentScheme::*;
+        // Major thirds and fifths as tabulated for each scheme
+        let cases = [
+            (WerckmeisterIii, 60, 64, 390.2),
+            (WerckmeisterIii, 60, 67, 696.1),
+            (WerckmeisterIii, 66, 70, 407.8),
+            (KirnbergerIii, 60, 64, 386.3),
+            (KirnbergerIii, 64, 68, 405.9),
+            (KirnbergerIii, 62, 69, 696.6),
+            (Vallotti, 60, 64, 392.2),
+            (Vallotti, 65, 69, 392.2),
+            (Vallotti, 66, 70, 407.8),
+            (YoungIi, 60, 64, 392.2),
+            (YoungIi, 65, 69, 396.1),
+            (YoungIi, 64, 71, 698.0),
+        ];
+        for (scheme, lower, upper, cents) in cases {
+            let size = interval(scheme, 0, lower, upper);
+            assert!(
+                (size - cents).abs() < 0.3,
+                "{} {}-{}: {}",
+                scheme.name(),
+                lower,
+                upper,
+                size
+            );
+        }
+    }
+
+    #[test]
+    fn test_root_moves_scheme() {
+        let scheme = TemperamentScheme::KirnbergerIii;
+        let g = parse_root("G").unwrap();
+        // The pure third moves from C-E to G-B
+        assert!((interval(scheme, g, 67, 71) - 386.3).abs() < 0.3);
+        assert!((interval(scheme, 0, 67, 71) - 386.3).abs() > 5.0);
+        // Intervals keep their sizes, a fifth further on
+        for lower in 60..72 {
+            let from_c = interval(scheme, 0, lower, lower + 4);
+            let from_g = interval(scheme, g, lower + 7, lower + 11);
+            assert!((from_c - from_g).abs() < 1e-3);
+        }
+        // A is still the reference
+        assert_eq!(scheme.offset_cents_from(69, g), 0.0);
+        // Young II is Vallotti from G
+        for midi in 60..72 {
+            let young = TemperamentScheme::YoungIi.offset_cents(midi);
+            let vallotti = TemperamentScheme::Vallotti.offset_cents_from(midi, g);
+            assert!((young - vallotti).abs() < 0.15, "{}", midi);
+        }
+    }
+
+    #[test]
+    fn test_meantone_fifths_and_wolf() {
+        let scheme = TemperamentScheme::Meantone;
+        for root in 0..12 {
+            let (lower, upper) = scheme.wolf(root).unwrap();
+            for from in 0..12u8 {
+                // The fifth up from each pitch class, an octave down if need be
+                let fifth = interval(scheme, root, 60 + from, 67 + from);
+                let narrowed = PURE_FIFTH - fifth;
+                if from == lower {
+                    assert_eq!((67 + from) % 12, upper);
+                    assert!((fifth - PURE_FIFTH - 35.7).abs() < 0.1, "Wolf {}", fifth);
+                } else {
+                    assert!((narrowed - 5.38).abs() < 0.01, "{} {}", root, fifth);
+                }
+            }
+        }
+        assert_eq!(scheme.wolf(0), Some((8, 3)));
+        assert_eq!(scheme.wolf_name(0).unwrap(), "G#-Eb");
+        assert_eq!(scheme.wolf_name(5).unwrap(), "C#-Ab");
+        assert_eq!(scheme.wolf_name(2).unwrap(), "A#-F");
+        assert!(TemperamentScheme::Vallotti.wolf(0).is_none());
+    }
+
+    #[test]
+    fn test_parse_root() {
+        assert_eq!(parse_root("C"), Some(0));
+        assert_eq!(parse_root("f#"), Some(6));
+        assert_eq!(parse_root("Bb"), Some(10));
+        assert_eq!(parse_root("Cb"), Some(11));
+        assert_eq!(parse_root("H"), None);
+        assert_eq!(parse_root("C##"), None);
+        assert_eq!(root_name(7), "G");
+        assert_eq!(root_name(parse_root("A#").unwrap()), "Bb");
+    }
+}
@@ -1,11 +1,24 @@
 //! Session state and persistence.
 
 use chrono::{DateTime, Utc};
-use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::PathBuf;
 
+use super::custom::CustomTemperament;
+use super::instrument::Instrument;
+use super::interval::IntervalResult;
+use super::notes::Note;
+use super::order::{OrderStrategy, TuningOrder};
+use super::overrides::TargetOverrides;
+use super::plan::{TargetEntry, TuningPlan};
+use super::profile::StretchProfile;
+use super::scheme::TemperamentScheme;
+use super::stats::SessionStats;
+use super::stretch::{OctaveType, PianoType, StretchCurve};
+use super::temperament::Temperament;
+
 /// Tuning mode.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -15,6 +28,20 @@ pub enum TuningMode {
     /// Concert pitch tuning (A4 = 440Hz or custom).
     #[default]
     Concert,
+    /// Measurement pass: record each note's deviation without tuning.
+    Measurement,
+    /// Pitch raise: overpull each note based on prior measurements.
+    #[serde(rename = "pitch_raise")]
+    PitchRaise,
+}
+
+/// Reading for one string of a multi-string note, taken as its step was confirmed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StringReading {
+    /// Which string was tuned (e.g., "left").
+    pub string: String,
+    /// Cents deviation when the step was confirmed.
+    pub cents: f32,
 }
 
 /// A completed note in a tuning session.
@@ -26,17 +53,82 @@ pub struct CompletedNote {
     pub final_cents: f32,
     /// Timestamp when completed.
     pub timestamp: DateTime<Utc>,
+    /// Whether the string beat although steady and in tune (false beats).
+    #[serde(default)]
+    pub false_beats: bool,
+    /// Whether the note was skipped rather than tuned.
+    #[serde(default)]
+    pub skipped: bool,
+    /// Per-string readings for multi-string notes, in tuning order.
+    #[serde(default)]
+    pub strings: Vec<StringReading>,
+    /// Manual adjustment of the target in cents.
+    #[serde(default)]
+    pub target_offset: f32,
+    /// Detection confidence when confirmed (None if nothing was detected).
+    #[serde(default)]
+    pub confidence: Option<f32>,
+    /// Spread of the readings in cents when confirmed, if known.
+    #[serde(default)]
+    pub spread: Option<f32>,
 }
 
 impl CompletedNote {
+    /// Confidence below which a reading is flagged as doubtful.
+    pub const LOW_CONFIDENCE: f32 = 0.8;
+
     /// Create a new completed note.
     pub fn new(note: impl Into<String>, final_cents: f32) -> Self {
         Self {
             note: note.into(),
             final_cents,
             timestamp: Utc::now(),
+            false_beats: false,
+            skipped: false,
+            strings: Vec::new(),
+            target_offset: 0.0,
+            confidence: None,
+            spread: None,
         }
     }
+
+    /// Create a record for a skipped note.
+    pub fn skipped(note: impl Into<String>) -> Self {
+        Self {
+            skipped: true,
+            ..Self::new(note, 0.0)
+        }
+    }
+
+    /// Flag the note as having false beats.
+    pub fn with_false_beats(mut self, false_beats: bool) -> Self {
+        self.false_beats = false_beats;
+        self
+    }
+
+    /// Set the per-string readings.
+    pub fn with_strings(mut self, strings: Vec<StringReading>) -> Self {
+        self.strings = strings;
+        self
+    }
+
+    /// Set the manual adjustment of the target.
+    pub fn with_target_offset(mut self, cents: f32) -> Self {
+        self.target_offset = cents;
+        self
+    }
+
+    /// Set the detection confidence and reading spread at confirmation.
+    pub fn with_detection(mut self, confidence: Option<f32>, spread: Option<f32>) -> Self {
+        self.confidence = confidence;
+        self.spread = spread;
+        self
+    }
+
+    /// Whether the reading this note was confirmed on is doubtful.
+    pub fn is_low_confidence(&self) -> bool {
+        self.confidence.is_some_and(|c| c < Self::LOW_CONFIDENCE)
+    }
 }
 
 /// A tuning session.
@@ -48,18 +140,78 @@ pub struct Session {
     pub mode: TuningMode,
     /// A4 reference frequency.
     pub a4_reference: f32,
+    /// Instrument being tuned.
+    #[serde(default)]
+    pub instrument: Instrument,
+    /// Temperament scheme the targets follow.
+    #[serde(default)]
+    pub temperament: TemperamentScheme,
+    /// Pitch class the temperament scheme is laid from (C = 0).
+    #[serde(default)]
+    pub temperament_root: u8,
+    /// Custom temperament the targets follow instead of the scheme.
+    #[serde(default)]
+    pub custom_temperament: Option<CustomTemperament>,
+    /// Whether targets follow the instrument's stretch curve.
+    #[serde(default)]
+    pub stretch: bool,
+    /// Factor the stretch curve is scaled by (1.0 for the default curve).
+    #[serde(default = "default_stretch_intensity")]
+    pub stretch_intensity: f32,
+    /// Type of piano, whose preset stretch curve the targets follow.
+    #[serde(default)]
+    pub piano_type: PianoType,
+    /// Saved stretch curve followed instead of the piano type's preset.
+    #[serde(default)]
+    pub stretch_profile: Option<StretchProfile>,
+    /// Partials the octaves are tuned beatless at, with inharmonicity data.
+    #[serde(default)]
+    pub octave_type: OctaveType,
+    /// Inharmonicity coefficient measured per MIDI note, which the stretch
+    /// curve is worked out from instead of the preset.
+    #[serde(default)]
+    pub inharmonicity: BTreeMap<u8, f32>,
+    /// Cents every target is moved by, e.g. -100 to tune a semitone flat.
+    #[serde(default)]
+    pub transpose_cents: f32,
+    /// Sequence the keys are tuned in.
+    #[serde(default)]
+    pub order_strategy: OrderStrategy,
+    /// Name of the piano tuned, if configured.
+    #[serde(default)]
+    pub piano: Option<String>,
     /// Piano's offset from concert pitch in cents (for quick tune).
     pub piano_offset_cents: f32,
     /// Current note index in tuning order.
     pub current_note_index: usize,
     /// Completed notes.
     pub completed_notes: Vec<CompletedNote>,
+    /// Deviations in cents per MIDI note from a prior measurement pass.
+    #[serde(default)]
+    pub measured_cents: BTreeMap<u8, f32>,
+    /// Manual target adjustments in cents per MIDI note, on top of the
+    /// stretch profile's overrides.
+    #[serde(default)]
+    pub target_offsets: TargetOverrides,
+    /// Targets in Hz captured from another instrument, per MIDI note.
+    #[serde(default)]
+    pub captured_targets: BTreeMap<u8, f32>,
+    /// Interval check results recorded after tuning.
+    #[serde(default)]
+    pub interval_checks: Vec<IntervalResult>,
+    /// Seconds spent idle that don't count toward the session duration.
+    #[serde(default)]
+    pub paused_secs: u64,
     /// Session creation time.
     pub created_at: DateTime<Utc>,
     /// Last update time.
     pub updated_at: DateTime<Utc>,
 }
 
+fn default_stretch_intensity() -> f32 {
+    1.0
+}
+
 impl Session {
     /// Create a new session.
     pub fn new(mode: TuningMode, a4_reference: f32) -> Self {
@@ -68,9 +220,27 @@ impl Session {
             id: now.to_rfc3339(),
             mode,
             a4_reference,
+            instrument: Instrument::default(),
+            temperament: TemperamentScheme::default(),
+            temperament_root: 0,
+            custom_temperament: None,
+            stretch: false,
+            stretch_intensity: 1.0,
+            piano_type: PianoType::default(),
+            stretch_profile: None,
+            octave_type: OctaveType::default(),
+            inharmonicity: BTreeMap::new(),
+            order_strategy: OrderStrategy::default(),
+            transpose_cents: 0.0,
+            piano: None,
             piano_offset_cents: 0.0,
             current_note_index: 0,
             completed_notes: Vec::new(),
+            measured_cents: BTreeMap::new(),
+            target_offsets: TargetOverrides::new(),
+            captured_targets: BTreeMap::new(),
+            interval_checks: Vec::new(),
+            paused_secs: 0,
             created_at: now,
             updated_at: now,
         }
@@ -88,19 +258,292 @@ impl Session {
         Self::new(TuningMode::Concert, a4_reference)
     }
 
+    /// Set the instrument and temperament scheme.
+    pub fn with_instrument(
+        mut self,
+        instrument: Instrument,
+        temperament: TemperamentScheme,
+    ) -> Self {
+        self.instrument = instrument;
+        self.temperament = temperament;
+        self
+    }
+
+    /// Lay the temperament scheme from the root pitch class `root` (C = 0)
+    /// rather than C.
+    pub fn with_temperament_root(mut self, root: u8) -> Self {
+        self.temperament_root = root % 12;
+        self
+    }
+
+    /// Follow a custom temperament instead of the scheme.
+    pub fn with_custom_temperament(mut self, custom: Option<CustomTemperament>) -> Self {
+        self.custom_temperament = custom;
+        self
+    }
+
+    /// Name of the temperament the targets follow.
+    pub fn temperament_name(&self) -> &str {
+        match &self.custom_temperament {
+            Some(custom) => &custom.name,
+            None => self.temperament.name(),
+        }
+    }
+
+    /// Temperament the targets follow: the A4 reference, transposed, laid
+    /// out in the scheme or the custom temperament.
+    pub fn tuning_temperament(&self) -> Temperament {
+        let transposed = Temperament::with_a4(self.a4_reference).transposed(self.transpose_cents);
+        match &self.custom_temperament {
+            Some(custom) => transposed.with_custom(custom),
+            None => transposed.with_scheme(self.temperament, self.temperament_root),
+        }
+    }
+
+    /// Stretch curve the targets follow, flat unless the session stretches.
+    pub fn stretch_curve(&self) -> StretchCurve {
+        if self.stretch {
+            self.instrument_stretch()
+        } else {
+            StretchCurve::flat()
+        }
+    }
+
+    /// Stretch curve of the instrument, stretched or not: the saved curve
+    /// if one is selected, else the curve worked out from the piano's
+    /// inharmonicity, else the piano type's preset, at the session's
+    /// intensity.
+    pub fn instrument_stretch(&self) -> StretchCurve {
+        let curve = match &self.stretch_profile {
+            Some(profile) => profile.curve.clone(),
+            None => self
+                .inharmonic_stretch()
+                .unwrap_or_else(|| self.instrument.stretch(self.piano_type)),
+        };
+        curve.with_intensity(self.stretch_intensity)
+    }
+
+    /// Stretch curve worked out from the inharmonicity at the octave type,
+    /// for a piano measured at two notes or more.
+    fn inharmonic_stretch(&self) -> Option<StretchCurve> {
+        if self.instrument != Instrument::Piano {
+            return None;
+        }
+        let points: Vec<(u8, f32)> = self.inharmonicity.iter().map(|(&m, &b)| (m, b)).collect();
+        StretchCurve::from_inharmonicity(&points, self.octave_type).ok()
+    }
+
+    /// Octave type the stretch of a note follows, if the targets are
+    /// worked out from the inharmonicity.
+    pub fn octave_type_at(&self, midi: u8) -> Option<OctaveType> {
+        let inharmonic =
+            self.stretch && self.stretch_profile.is_none() && self.inharmonic_stretch().is_some();
+        inharmonic.then(|| self.octave_type.at(midi))
+    }
+
+    /// Final target of every key, with the overrides and manual
+    /// adjustments.
+    pub fn targets(&self) -> Vec<TargetEntry> {
+        TuningPlan::compute(
+            &self.tuning_temperament(),
+            &self.stretch_curve(),
+            &self.target_adjustments(),
+        )
+    }
+
+    /// Tune to the instrument's stretch curve rather than the bare
+    /// temperament.
+    pub fn with_stretch(mut self, stretch: bool) -> Self {
+        self.stretch = stretch;
+        self
+    }
+
+    /// Scale the stretch curve by `intensity` (1.0 for the default curve).
+    pub fn with_stretch_intensity(mut self, intensity: f32) -> Self {
+        self.stretch_intensity = intensity;
+        self
+    }
+
+    /// Follow the stretch preset of the piano type.
+    pub fn with_piano_type(mut self, piano_type: PianoType) -> Self {
+        self.piano_type = piano_type;
+        self
+    }
+
+    /// Follow a saved stretch curve instead of the piano type's preset.
+    pub fn with_stretch_profile(mut self, profile: Option<StretchProfile>) -> Self {
+        self.stretch_profile = profile;
+        self
+    }
+
+    /// Tune the octaves beatless at the partials of `octave_type`.
+    pub fn with_octave_type(mut self, octave_type: OctaveType) -> Self {
+        self.octave_type = octave_type;
+        self
+    }
+
+    /// Work the stretch curve out from the inharmonicity measured per MIDI
+    /// note rather than the piano type's preset.
+    pub fn with_inharmonicity(mut self, inharmonicity: BTreeMap<u8, f32>) -> Self {
+        self.inharmonicity = inharmonicity;
+        self
+    }
+
+    /// Tune the keys in the sequence of `strategy`.
+    pub fn with_order_strategy(mut self, strategy: OrderStrategy) -> Self {
+        self.order_strategy = strategy;
+        self
+    }
+
+    /// Order the instrument's keys are tuned in.
+    pub fn tuning_order(&self) -> TuningOrder {
+        self.instrument
+            .tuning_order()
+            .with_strategy(self.order_strategy)
+    }
+
+    /// Move every target by `cents` from the reference, for a piano kept
+    /// off concert pitch.
+    pub fn with_transpose(mut self, cents: f32) -> Self {
+        self.transpose_cents = cents;
+        self
+    }
+
+    /// Start with the targets of single notes moved by `overrides`, as
+    /// manual adjustments.
+    pub fn with_target_overrides(mut self, overrides: TargetOverrides) -> Self {
+        self.target_offsets = overrides;
+        self
+    }
+
+    /// Set the name of the piano tuned.
+    pub fn with_piano(mut self, piano: Option<String>) -> Self {
+        self.piano = piano;
+        self
+    }
+
+    /// Create a session seeded with the results of a measurement pass.
+    pub fn seeded_from(mode: TuningMode, a4_reference: f32, measurement: &Session) -> Self {
+        let mut session = Self::new(mode, a4_reference)
+            .with_instrument(measurement.instrument, measurement.temperament)
+            .with_temperament_root(measurement.temperament_root)
+            .with_custom_temperament(measurement.custom_temperament.clone())
+            .with_stretch(measurement.stretch)
+            .with_stretch_intensity(measurement.stretch_intensity)
+            .with_piano_type(measurement.piano_type)
+            .with_stretch_profile(measurement.stretch_profile.clone())
+            .with_octave_type(measurement.octave_type)
+            .with_inharmonicity(measurement.inharmonicity.clone())
+            .with_order_strategy(measurement.order_strategy)
+            .with_transpose(measurement.transpose_cents)
+            .with_target_overrides(measurement.target_offsets.clone())
+            .with_piano(measurement.piano.clone());
+        session.measured_cents = measurement.measurements();
+        session
+    }
+
+    /// Get the deviations recorded by this session as MIDI note → cents.
+    pub fn measurements(&self) -> BTreeMap<u8, f32> {
+        self.completed_notes
+            .iter()
+            .filter(|cn| !cn.skipped)
+            .filter_map(|cn| {
+                super::notes::Note::from_name(&cn.note).map(|n| (n.midi, cn.final_cents))
+            })
+            .collect()
+    }
+
+    /// Deviations found before tuning as MIDI note → cents: the results of
+    /// a measurement pass, or the measurement a session was seeded from.
+    pub fn start_deviations(&self) -> BTreeMap<u8, f32> {
+        if self.mode == TuningMode::Measurement {
+            self.measurements()
+        } else {
+            self.measured_cents.clone()
+        }
+    }
+
+    /// Average absolute deviation found before tuning, if measured.
+    pub fn start_deviation(&self) -> Option<f32> {
+        let deviations = self.start_deviations();
+        if deviations.is_empty() {
+            return None;
+        }
+        Some(deviations.values().map(|c| c.abs()).sum::<f32>() / deviations.len() as f32)
+    }
+
+    /// Pitch of the piano's A4 before tuning: measured directly, or
+    /// calibrated to in quick tune.
+    pub fn measured_a4(&self) -> Option<f32> {
+        match self.start_deviations().get(&69) {
+            Some(&cents) => Some(self.a4_reference * Temperament::cents_to_ratio(cents)),
+            None if self.mode == TuningMode::Quick => Some(self.a4_reference),
+            None => None,
+        }
+    }
+
+    /// Target adjustment for a note in cents (0 if none): the stretch
+    /// profile's override plus the manual adjustment.
+    pub fn target_offset(&self, midi: u8) -> f32 {
+        self.override_offset(midi) + self.manual_offset(midi)
+    }
+
+    /// Override kept with the stretch profile for a note in cents (0 if
+    /// none).
+    pub fn override_offset(&self, midi: u8) -> f32 {
+        self.stretch_profile
+            .as_ref()
+            .map_or(0.0, |profile| profile.target_overrides.get(midi))
+    }
+
+    /// Manual target adjustment for a note in this session in cents (0 if
+    /// none).
+    pub fn manual_offset(&self, midi: u8) -> f32 {
+        self.target_offsets.get(midi)
+    }
+
+    /// Set the manual target adjustment for a note; zero removes it.
+    pub fn set_manual_offset(&mut self, midi: u8, cents: f32) {
+        self.target_offsets.set(midi, cents);
+        self.updated_at = Utc::now();
+    }
+
+    /// Target adjustments of every note, overrides and manual ones added.
+    pub fn target_adjustments(&self) -> TargetOverrides {
+        let mut adjustments = self
+            .stretch_profile
+            .as_ref()
+            .map(|profile| profile.target_overrides.clone())
+            .unwrap_or_default();
+        for (midi, cents) in self.target_offsets.iter() {
+            adjustments.set(midi, adjustments.get(midi) + cents);
+        }
+        adjustments
+    }
+
     /// Check if the session is complete.
     pub fn is_complete(&self) -> bool {
-        self.current_note_index >= 88
+        self.current_note_index >= self.instrument.note_count()
     }
 
     /// Mark a note as completed.
     pub fn complete_note(&mut self, note_name: impl Into<String>, final_cents: f32) {
-        self.completed_notes
-            .push(CompletedNote::new(note_name, final_cents));
+        self.record_note(CompletedNote::new(note_name, final_cents));
+    }
+
+    /// Record a completed note and advance to the next one.
+    pub fn record_note(&mut self, note: CompletedNote) {
+        self.completed_notes.push(note);
         self.current_note_index += 1;
         self.updated_at = Utc::now();
     }
 
+    /// Time spent tuning in seconds, excluding paused time.
+    pub fn duration_secs(&self) -> u64 {
+        let elapsed = (self.updated_at - self.created_at).num_seconds().max(0) as u64;
+        elapsed.saturating_sub(self.paused_secs)
+    }
+
     /// Skip to the next note without recording completion.
     pub fn skip_note(&mut self) {
         self.current_note_index += 1;
@@ -108,17 +551,43 @@ impl Session {
     }
 
     /// Get the sessions directory path.
+    #[cfg(not(test))]
+    fn sessions_dir() -> Option<PathBuf> {
+        directories::ProjectDirs::from("", "", "onkey").map(|dirs| dirs.data_dir().join("sessions"))
+    }
+
+    /// Keep sessions saved by unit tests out of the user's data directory.
+    #[cfg(test)]
     fn sessions_dir() -> Option<PathBuf> {
-        ProjectDirs::from("", "", "onkey").map(|dirs| dirs.data_dir().join("sessions"))
+        Some(std::env::temp_dir().join("onkey-test-sessions"))
+    }
+
+    /// Get the recordings directory path.
+    #[cfg(not(test))]
+    fn recordings_dir() -> Option<PathBuf> {
+        directories::ProjectDirs::from("", "", "onkey")
+            .map(|dirs| dirs.data_dir().join("recordings"))
+    }
+
+    /// Keep recordings made by unit tests out of the user's data directory.
+    #[cfg(test)]
+    fn recordings_dir() -> Option<PathBuf> {
+        Some(std::env::temp_dir().join("onkey-test-recordings"))
+    }
+
+    /// The ID sanitized for use as a filename.
+    fn file_stem(&self) -> String {
+        self.id.replace(':', "-")
     }
 
     /// Get the path for this session's file.
     fn session_path(&self) -> Option<PathBuf> {
-        Self::sessions_dir().map(|dir| {
-            // Sanitize the ID for use as filename
-            let safe_id = self.id.replace(':', "-");
-            dir.join(format!("{}.json", safe_id))
-        })
+        Self::sessions_dir().map(|dir| dir.join(format!("{}.json", self.file_stem())))
+    }
+
+    /// Get the path the session's microphone input is recorded to.
+    pub fn recording_path(&self) -> Option<PathBuf> {
+        Self::recordings_dir().map(|dir| dir.join(format!("{}.wav", self.file_stem())))
     }
 
     /// Save session to disk.
@@ -172,7 +641,7 @@ impl Session {
         }
 
         // Sort by updated_at descending
-        sessions.sort_by(|a, b| b.1.updated_at.cmp(&a.1.updated_at));
+        sessions.sort_by_key(|(_, s)| std::cmp::Reverse(s.updated_at));
 
         Ok(sessions.into_iter().next().map(|(_, s)| s))
     }
@@ -202,11 +671,29 @@ impl Session {
         }
 
         // Sort by created_at descending
-        sessions.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+        sessions.sort_by_key(|s| std::cmp::Reverse(s.created_at));
+
+        Ok(sessions)
+    }
 
+    /// List the sessions of one piano, most recent first.
+    pub fn list(piano: &str) -> anyhow::Result<Vec<Session>> {
+        let mut sessions = Self::list_all()?;
+        sessions.retain(|s| s.piano.as_deref() == Some(piano));
         Ok(sessions)
     }
 
+    /// Names of the pianos with saved sessions, most recently tuned first.
+    pub fn pianos() -> anyhow::Result<Vec<String>> {
+        let mut pianos: Vec<String> = Vec::new();
+        for piano in Self::list_all()?.into_iter().filter_map(|s| s.piano) {
+            if !pianos.contains(&piano) {
+                pianos.push(piano);
+            }
+        }
+        Ok(pianos)
+    }
+
     /// Delete this session file.
     pub fn delete(&self) -> anyhow::Result<()> {
         if let Some(path) = self.session_path() {
@@ -231,30 +718,46 @@ impl Session {
         Ok(())
     }
 
-    /// Get average deviation in cents for completed notes.
+    /// Get average deviation in cents for completed notes (excluding skipped).
     pub fn average_deviation(&self) -> f32 {
-        if self.completed_notes.is_empty() {
-            return 0.0;
-        }
+        self.stats().avg_deviation
+    }
 
-        let sum: f32 = self
+    /// Get average absolute deviation in Hz for completed notes (excluding
+    /// skipped), relative to their equal-tempered targets.
+    pub fn average_deviation_hz(&self) -> f32 {
+        let temperament = Temperament::with_a4(self.a4_reference);
+        let deviations: Vec<f32> = self
             .completed_notes
             .iter()
-            .map(|n| n.final_cents.abs())
-            .sum();
+            .filter(|n| !n.skipped)
+            .filter_map(|n| {
+                let target = temperament.frequency_for_note(Note::from_name(&n.note)?);
+                Some((target * (Temperament::cents_to_ratio(n.final_cents) - 1.0)).abs())
+            })
+            .collect();
+        if deviations.is_empty() {
+            0.0
+        } else {
+            deviations.iter().sum::<f32>() / deviations.len() as f32
+        }
+    }
 
-        sum / self.completed_notes.len() as f32
+    /// Get summary statistics for the completed notes.
+    pub fn stats(&self) -> SessionStats {
+        SessionStats::from_notes(&self.completed_notes)
     }
 
     /// Get progress as a percentage.
     pub fn progress_percent(&self) -> f32 {
-        (self.current_note_index as f32 / 88.0) * 100.0
+        (self.current_note_index as f32 / self.instrument.note_count() as f32) * 100.0
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tuning::CurveSource;
     use tempfile::TempDir;
 
     fn create_test_session() -> Session {
@@ -285,6 +788,141 @@ mod tests {
         assert_eq!(session.a4_reference, 442.0);
     }
 
+    #[test]
+    fn test_measurement_mode_serialization() {
+        let json = serde_json::to_string(&TuningMode::Measurement).expect("serialize");
+        assert_eq!(json, "\"measurement\"");
+
+        let json = serde_json::to_string(&TuningMode::PitchRaise).expect("serialize");
+        assert_eq!(json, "\"pitch_raise\"");
+    }
+
+    #[test]
+    fn test_seeded_from_measurement() {
+        let mut measurement = Session::new(TuningMode::Measurement, 440.0).with_transpose(-50.0);
+        measurement.complete_note("F3", -18.0);
+        measurement.skip_note();
+        measurement.complete_note("G3", -12.5);
+
+        let measured = measurement.measurements();
+        assert_eq!(measured.len(), 2);
+        assert_eq!(measured[&53], -18.0);
+        assert_eq!(measured[&55], -12.5);
+
+        measurement.set_manual_offset(78, 1.5);
+        measurement.piano_type = PianoType::Spinet;
+
+        let raise = Session::seeded_from(TuningMode::PitchRaise, 440.0, &measurement);
+        assert_eq!(raise.mode, TuningMode::PitchRaise);
+        assert_eq!(raise.measured_cents, measured);
+        assert!(raise.completed_notes.is_empty());
+
+        let json = serde_json::to_string(&raise).expect("Should serialize");
+        let restored: Session = serde_json::from_str(&json).expect("Should deserialize");
+        assert_eq!(restored.measured_cents, measured);
+        assert_eq!(restored.transpose_cents, -50.0);
+        assert_eq!(restored.target_offset(78), 1.5);
+        assert_eq!(restored.piano_type, PianoType::Spinet);
+    }
+
+    #[test]
+    fn test_overrides_add_to_manual_offsets() {
+        let mut overrides = TargetOverrides::new();
+        overrides.set(78, 1.0);
+        overrides.set(84, -2.0);
+        let profile = StretchProfile::new("Studio", CurveSource::Manual, StretchCurve::new())
+            .with_target_overrides(overrides);
+        let mut session =
+            Session::new(TuningMode::Concert, 440.0).with_stretch_profile(Some(profile));
+        session.set_manual_offset(78, 0.5);
+        session.set_manual_offset(60, -1.0);
+
+        assert_eq!(session.override_offset(78), 1.0);
+        assert_eq!(session.manual_offset(78), 0.5);
+        assert_eq!(session.target_offset(78), 1.5);
+        let adjustments = session.target_adjustments();
+        assert_eq!(
+            adjustments.iter().collect::<Vec<_>>(),
+            [(60, -1.0), (78, 1.5), (84, -2.0)]
+        );
+        // Only the manual ones are the session's own
+        assert_eq!(session.target_offsets.len(), 2);
+    }
+
+    #[test]
+    fn test_stretch_follows_piano_type() {
+        let session = Session::new(TuningMode::Concert, 440.0)
+            .with_stretch(true)
+            .with_piano_type(PianoType::ConcertGrand);
+        let concert = StretchCurve::preset(PianoType::ConcertGrand);
+        assert_eq!(
+            session.stretch_curve().offset_cents(21),
+            concert.offset_cents(21)
+        );
+
+        // Sessions saved before piano types stretch as an upright
+        let json = serde_json::to_string(&session)
+            .unwrap()
+            .replace(r#","piano_type":"concert-grand""#, "");
+        let restored: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.piano_type, PianoType::Upright);
+    }
+
+    #[test]
+    fn test_stretch_from_inharmonicity() {
+        let inharmonicity = BTreeMap::from([(21, 4e-4), (48, 1e-4), (69, 4e-4), (108, 1e-2)]);
+        let preset = Session::new(TuningMode::Concert, 440.0).with_stretch(true);
+        let session = preset
+            .clone()
+            .with_octave_type(OctaveType::FourTwo)
+            .with_inharmonicity(inharmonicity.clone());
+        let points: Vec<(u8, f32)> = inharmonicity.into_iter().collect();
+        let curve = StretchCurve::from_inharmonicity(&points, OctaveType::FourTwo).unwrap();
+        assert_eq!(session.stretch_curve(), curve);
+        assert_eq!(session.octave_type_at(21), Some(OctaveType::FourTwo));
+
+        // Without data the preset holds and no octave type is shown
+        assert_eq!(preset.stretch_curve(), StretchCurve::new());
+        assert_eq!(preset.octave_type_at(21), None);
+        let one_note = preset.with_inharmonicity(BTreeMap::from([(21, 4e-4)]));
+        assert_eq!(one_note.stretch_curve(), StretchCurve::new());
+
+        // By register, the octave type follows the note
+        let mixed = session.clone().with_octave_type(OctaveType::ByRegister);
+        assert_eq!(mixed.octave_type_at(30), Some(OctaveType::SixThree));
+        assert_eq!(mixed.octave_type_at(69), Some(OctaveType::Pure));
+
+        // Kept through a save, and off with stretch
+        let restored: Session =
+            serde_json::from_str(&serde_json::to_string(&session).unwrap()).unwrap();
+        assert_eq!(restored.octave_type, OctaveType::FourTwo);
+        assert_eq!(restored.inharmonicity, session.inharmonicity);
+        assert_eq!(session.with_stretch(false).octave_type_at(21), None);
+    }
+
+    #[test]
+    fn test_custom_temperament_kept() {
+        let custom = CustomTemperament {
+            name: "Kellner".to_string(),
+            root: 0,
+            offsets: [
+                8.2, 0.4, 2.7, 2.3, -0.7, 6.3, -1.6, 5.5, 1.2, 0.0, 4.3, -2.7,
+            ],
+        };
+        let measurement = Session::new(TuningMode::Measurement, 440.0)
+            .with_custom_temperament(Some(custom.clone()));
+        assert_eq!(measurement.temperament_name(), "Kellner");
+
+        let seeded = Session::seeded_from(TuningMode::Concert, 440.0, &measurement);
+        let json = serde_json::to_string(&seeded).expect("Should serialize");
+        let restored: Session = serde_json::from_str(&json).expect("Should deserialize");
+        assert_eq!(restored.custom_temperament, Some(custom));
+        assert_eq!(
+            Session::new(TuningMode::Concert, 440.0).temperament_name(),
+            "Equal"
+        );
+    }
+
     #[test]
     fn test_complete_note() {
         let mut session = create_test_session();
@@ -296,6 +934,27 @@ mod tests {
         assert_eq!(session.completed_notes[0].final_cents, 1.5);
     }
 
+    #[test]
+    fn test_record_note_with_false_beats() {
+        let mut session = create_test_session();
+        session.record_note(CompletedNote::new("A4", 0.5).with_false_beats(true));
+
+        assert_eq!(session.current_note_index, 1);
+        assert!(session.completed_notes[0].false_beats);
+
+        let json = serde_json::to_string(&session).expect("Should serialize");
+        let restored: Session = serde_json::from_str(&json).expect("Should deserialize");
+        assert!(restored.completed_notes[0].false_beats);
+    }
+
+    #[test]
+    fn test_false_beats_defaults_when_missing() {
+        let json = r#"{"note":"A4","final_cents":1.0,"timestamp":"2024-01-01T00:00:00Z"}"#;
+        let note: CompletedNote = serde_json::from_str(json).expect("Should deserialize");
+        assert!(!note.false_beats);
+        assert_eq!(note.confidence, None);
+    }
+
     #[test]
     fn test_skip_note() {
         let mut session = create_test_session();
@@ -328,6 +987,22 @@ mod tests {
 
         // Average of |2.0|, |-4.0|, |3.0| = (2 + 4 + 3) / 3 = 3.0
         assert!((session.average_deviation() - 3.0).abs() < 0.01);
+
+        // Skipped notes don't count
+        session.record_note(CompletedNote::skipped("G#3"));
+        assert!((session.average_deviation() - 3.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_average_deviation_hz() {
+        let mut session = create_test_session();
+        assert_eq!(session.average_deviation_hz(), 0.0);
+
+        // 5 cents is about 0.08 Hz on A0 and 1.27 Hz on A4
+        session.complete_note("A0", 5.0);
+        session.complete_note("A4", -5.0);
+        session.record_note(CompletedNote::skipped("A5"));
+        assert!((session.average_deviation_hz() - 0.674).abs() < 0.001);
     }
 
     #[test]
@@ -0,0 +1,299 @@
+//! Summary statistics for a tuning session.
+
+use super::notes::Note;
+use super::order::Section;
+use super::session::CompletedNote;
+
+/// Deviation (in cents) at which a note scores zero.
+const ZERO_SCORE_CENTS: f32 = 25.0;
+
+/// Weight of a note in the quality score, by register.
+///
+/// Errors are weighted by how audible they are:
+///
+/// | Range    | MIDI    | Weight |
+/// |----------|---------|--------|
+/// | A0–B1    | 21–35   | 0.25   |
+/// | C2–E3    | 36–52   | 0.6    |
+/// | F3–F4    | 53–65   | 1.0    |
+/// | F#4–C6   | 66–84   | 0.9    |
+/// | C#6–B6   | 85–95   | 0.5    |
+/// | C7–C8    | 96–108  | 0.25   |
+pub fn note_weight(midi: u8) -> f32 {
+    match midi {
+        21..=35 => 0.25,
+        36..=52 => 0.6,
+        53..=65 => 1.0,
+        66..=84 => 0.9,
+        85..=95 => 0.5,
+        _ => 0.25,
+    }
+}
+
+/// Score of a single note (0.0 to 1.0), falling linearly to zero at 25 cents.
+pub fn note_score(cents: f32) -> f32 {
+    (1.0 - cents.abs() / ZERO_SCORE_CENTS).max(0.0)
+}
+
+/// Final deviation of each tuned note as `(midi, cents)`, lowest key first.
+///
+/// Skipped notes and unknown note names are omitted.
+pub fn tuned_deviations(notes: &[CompletedNote]) -> Vec<(u8, f32)> {
+    let mut deviations: Vec<(u8, f32)> = notes
+        .iter()
+        .filter(|n| !n.skipped)
+        .filter_map(|n| Note::from_name(&n.note).map(|note| (note.midi, n.final_cents)))
+        .collect();
+    deviations.sort_by_key(|&(midi, _)| midi);
+    deviations
+}
+
+/// Statistics for one section of the keyboard.
+#[derive(Debug, Clone)]
+pub struct SectionStats {
+    /// The section.
+    pub section: Section,
+    /// Notes tuned in this section.
+    pub notes: usize,
+    /// Average absolute deviation in cents.
+    pub avg_deviation: f32,
+    /// Largest absolute deviation in cents.
+    pub worst_deviation: f32,
+}
+
+/// Statistics over the completed notes of a session.
+#[derive(Debug, Clone, Default)]
+pub struct SessionStats {
+    /// Notes tuned (excluding skipped).
+    pub notes_tuned: usize,
+    /// Notes skipped.
+    pub notes_skipped: usize,
+    /// Average absolute deviation in cents over tuned notes.
+    pub avg_deviation: f32,
+    /// Weighted quality score (0 to 100).
+    pub quality_score: f32,
+    /// Notes within tolerance (±5 cents).
+    pub notes_in_tune: usize,
+    /// Notes with warning (±5-15 cents).
+    pub notes_warning: usize,
+    /// Notes out of tune (>±15 cents).
+    pub notes_out_of_tune: usize,
+    /// Notes flagged with false beats.
+    pub false_beat_notes: Vec<String>,
+    /// Notes confirmed on a low-confidence reading.
+    pub low_confidence_notes: Vec<String>,
+    /// Per-section breakdown (bass, temperament, treble).
+    pub sections: Vec<SectionStats>,
+}
+
+impl SessionStats {
+    /// Compute statistics from completed notes.
+    pub fn from_notes(notes: &[CompletedNote]) -> Self {
+        let tuned: Vec<&CompletedNote> = notes.iter().filter(|n| !n.skipped).collect();
+
+        let deviations: Vec<f32> = tuned.iter().map(|n| n.final_cents.abs()).collect();
+        let avg_deviation = if deviations.is_empty() {
+            0.0
+        } else {
+            deviations.iter().sum::<f32>() / deviations.len() as f32
+        };
+
+        Self {
+            notes_tuned: tuned.len(),
+            notes_skipped: notes.len() - tuned.len(),
+            avg_deviation,
+            quality_score: quality_score(&tuned),
+            notes_in_tune: deviations.iter().filter(|&&c| c <= 5.0).count(),
+            notes_warning: deviations.iter().filter(|&&c| c > 5.0 && c <= 15.0).count(),
+            notes_out_of_tune: deviations.iter().filter(|&&c| c > 15.0).count(),
+            false_beat_notes: tuned
+                .iter()
+                .filter(|n| n.false_beats)
+                .map(|n| n.note.clone())
+                .collect(),
+            low_confidence_notes: tuned
+                .iter()
+                .filter(|n| n.is_low_confidence())
+                .map(|n| n.note.clone())
+                .collect(),
+            sections: section_stats(&tuned),
+        }
+    }
+
+    /// Get the statistics for one section.
+    pub fn section(&self, section: Section) -> Option<&SectionStats> {
+        self.sections.iter().find(|s| s.section == section)
+    }
+
+    /// Verdict for the weighted quality score.
+    pub fn verdict(&self) -> Verdict {
+        Verdict::from_score(self.quality_score)
+    }
+}
+
+/// Average and worst deviation per section.
+fn section_stats(notes: &[&CompletedNote]) -> Vec<SectionStats> {
+    Section::ALL
+        .iter()
+        .map(|&section| {
+            let deviations: Vec<f32> = notes
+                .iter()
+                .filter(|n| {
+                    Note::from_name(&n.note).is_some_and(|note| Section::of(note.midi) == section)
+                })
+                .map(|n| n.final_cents.abs())
+                .collect();
+
+            let avg_deviation = if deviations.is_empty() {
+                0.0
+            } else {
+                deviations.iter().sum::<f32>() / deviations.len() as f32
+            };
+
+            SectionStats {
+                section,
+                notes: deviations.len(),
+                avg_deviation,
+                worst_deviation: deviations.iter().copied().fold(0.0, f32::max),
+            }
+        })
+        .collect()
+}
+
+/// Weighted average of the note scores, scaled to 0-100.
+fn quality_score(notes: &[&CompletedNote]) -> f32 {
+    let (weighted, total) = notes
+        .iter()
+        .filter_map(|n| Note::from_name(&n.note).map(|note| (note.midi, n.final_cents)))
+        .fold((0.0, 0.0), |(weighted, total), (midi, cents)| {
+            let weight = note_weight(midi);
+            (weighted + weight * note_score(cents), total + weight)
+        });
+
+    if total > 0.0 {
+        100.0 * weighted / total
+    } else {
+        0.0
+    }
+}
+
+/// Overall verdict on a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Score 90 and above.
+    Excellent,
+    /// Score 75 and above.
+    Good,
+    /// Score 50 and above.
+    Acceptable,
+    /// Below 50.
+    NeedsImprovement,
+}
+
+impl Verdict {
+    /// Verdict for a quality score.
+    pub fn from_score(score: f32) -> Self {
+        if score >= 90.0 {
+            Verdict::Excellent
+        } else if score >= 75.0 {
+            Verdict::Good
+        } else if score >= 50.0 {
+            Verdict::Acceptable
+        } else {
+            Verdict::NeedsImprovement
+        }
+    }
+
+    /// Display message.
+    pub fn message(self) -> &'static str {
+        match self {
+            Verdict::Excellent => "Excellent tuning!",
+            Verdict::Good => "Good tuning!",
+            Verdict::Acceptable => "Acceptable tuning",
+            Verdict::NeedsImprovement => "Tuning needs improvement",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_note_weights() {
+        assert_eq!(note_weight(21), 0.25);
+        assert_eq!(note_weight(60), 1.0);
+        assert_eq!(note_weight(69), 0.9);
+        assert_eq!(note_weight(108), 0.25);
+    }
+
+    #[test]
+    fn test_quality_score_by_hand() {
+        // A0 off by 10 cents, C4 and A4 in tune
+        let notes = vec![
+            CompletedNote::new("A0", 10.0),
+            CompletedNote::new("C4", 0.0),
+            CompletedNote::new("A4", 0.0),
+        ];
+        let stats = SessionStats::from_notes(&notes);
+
+        // (0.25 * 0.6 + 1.0 * 1.0 + 0.9 * 1.0) / (0.25 + 1.0 + 0.9)
+        let expected = 100.0 * (0.25 * 0.6 + 1.0 + 0.9) / 2.15;
+        assert!((stats.quality_score - expected).abs() < 1e-3);
+        assert!((stats.avg_deviation - 10.0 / 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_skipped_notes_excluded() {
+        let notes = vec![CompletedNote::new("C4", 2.0), CompletedNote::skipped("C#4")];
+        let stats = SessionStats::from_notes(&notes);
+
+        assert_eq!(stats.notes_tuned, 1);
+        assert_eq!(stats.notes_skipped, 1);
+        assert_eq!(stats.avg_deviation, 2.0);
+        assert!((stats.quality_score - 92.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_section_stats() {
+        let notes = vec![
+            CompletedNote::new("A0", -18.0),
+            CompletedNote::new("E3", 6.0),
+            CompletedNote::new("A3", 2.0),
+            CompletedNote::new("C8", 4.0),
+            CompletedNote::skipped("C7"),
+        ];
+        let stats = SessionStats::from_notes(&notes);
+
+        let bass = stats.section(Section::Bass).unwrap();
+        assert_eq!(bass.notes, 2);
+        assert!((bass.avg_deviation - 12.0).abs() < 1e-5);
+        assert_eq!(bass.worst_deviation, 18.0);
+
+        let temperament = stats.section(Section::Temperament).unwrap();
+        assert_eq!(temperament.notes, 1);
+        assert_eq!(temperament.worst_deviation, 2.0);
+
+        let treble = stats.section(Section::Treble).unwrap();
+        assert_eq!(treble.notes, 1);
+        assert_eq!(treble.avg_deviation, 4.0);
+    }
+
+    #[test]
+    fn test_tuned_deviations_by_key() {
+        let notes = vec![
+            CompletedNote::new("A4", 1.0),
+            CompletedNote::skipped("C4"),
+            CompletedNote::new("A0", -3.0),
+        ];
+        assert_eq!(tuned_deviations(&notes), vec![(21, -3.0), (69, 1.0)]);
+    }
+
+    #[test]
+    fn test_verdict_thresholds() {
+        assert_eq!(Verdict::from_score(95.0), Verdict::Excellent);
+        assert_eq!(Verdict::from_score(80.0), Verdict::Good);
+        assert_eq!(Verdict::from_score(60.0), Verdict::Acceptable);
+        assert_eq!(Verdict::from_score(20.0), Verdict::NeedsImprovement);
+    }
+}
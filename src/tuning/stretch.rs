@@ -4,83 +4,511 @@
 //! than perfect integer multiples of the fundamental. Professional piano tuning
 //! compensates with "stretch tuning" where bass notes are tuned slightly flat
 //! and treble notes slightly sharp.
+//!
+//! The shorter the strings, the stiffer they are for their pitch, so the
+//! curve comes in presets by piano type: a spinet is stretched most, a
+//! concert grand least. Each preset is a smooth fit through averaged
+//! Railsback measurements at every A, so the stretch sets in gradually from
+//! the middle of the keyboard rather than all at once near the ends.
+//!
+//! With the inharmonicity measured, the curve can instead be worked out
+//! octave by octave, each octave made beatless at the partials of its
+//! octave type: the lower note's 2nd partial against the upper note's
+//! fundamental for a 2:1 octave, the 4th against the 2nd for 4:2, the 6th
+//! against the 3rd for 6:3. The higher the partials, the wider the octave.
+
+use serde::{Deserialize, Serialize};
+
+/// Strongest stretch intensity, as a factor of the default curve.
+pub const MAX_INTENSITY: f32 = 1.5;
+
+/// Error building a stretch curve from points.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum StretchError {
+    #[error("Expected at least 2 points, found {0}")]
+    TooFewPoints(usize),
+    #[error("MIDI note {0} is not a piano key")]
+    OutOfRange(u8),
+    #[error("MIDI note {0} is given more than once")]
+    Duplicate(u8),
+    #[error("MIDI note {0} is out of order; points go from the lowest note up")]
+    Unsorted(u8),
+    #[error("Offset for MIDI note {0} is not a number")]
+    InvalidOffset(u8),
+    #[error("Expected 88 offsets, one per key, found {0}")]
+    OffsetCount(usize),
+    #[error("Inharmonicity for MIDI note {0} is not a positive number")]
+    InvalidInharmonicity(u8),
+}
+
+/// Size of piano, which sets how far it is stretched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PianoType {
+    Spinet,
+    Console,
+    #[default]
+    Upright,
+    BabyGrand,
+    SemiConcert,
+    ConcertGrand,
+    /// The curve of earlier versions, for pianos already tuned to it.
+    Legacy,
+}
+
+impl PianoType {
+    /// All piano types, from the shortest strings to the longest, then
+    /// the legacy curve.
+    pub const ALL: [PianoType; 7] = [
+        PianoType::Spinet,
+        PianoType::Console,
+        PianoType::Upright,
+        PianoType::BabyGrand,
+        PianoType::SemiConcert,
+        PianoType::ConcertGrand,
+        PianoType::Legacy,
+    ];
+
+    /// Display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            PianoType::Spinet => "Spinet",
+            PianoType::Console => "Console",
+            PianoType::Upright => "Upright",
+            PianoType::BabyGrand => "Baby Grand",
+            PianoType::SemiConcert => "Semi-Concert",
+            PianoType::ConcertGrand => "Concert Grand",
+            PianoType::Legacy => "Legacy",
+        }
+    }
+
+    /// The next longer piano type, wrapping around.
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&p| p == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// The next shorter piano type, wrapping around.
+    pub fn prev(self) -> Self {
+        let i = Self::ALL.iter().position(|&p| p == self).unwrap_or(0);
+        Self::ALL[(i + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    /// Stretch in cents at each A and at C8, averaged from Railsback
+    /// measurements of the type; the legacy curve has none.
+    fn anchors(self) -> Option<&'static [(u8, f64); 9]> {
+        let anchors = match self {
+            PianoType::Spinet => &[
+                (21, -34.0),
+                (33, -18.0),
+                (45, -7.0),
+                (57, -1.5),
+                (69, 0.0),
+                (81, 6.0),
+                (93, 17.0),
+                (105, 34.0),
+                (108, 42.0),
+            ],
+            PianoType::Console => &[
+                (21, -24.0),
+                (33, -12.0),
+                (45, -4.5),
+                (57, -1.0),
+                (69, 0.0),
+                (81, 5.0),
+                (93, 14.0),
+                (105, 27.0),
+                (108, 32.0),
+            ],
+            PianoType::Upright => &[
+                (21, -16.0),
+                (33, -7.5),
+                (45, -2.5),
+                (57, -0.5),
+                (69, 0.0),
+                (81, 4.5),
+                (93, 11.0),
+                (105, 21.0),
+                (108, 24.0),
+            ],
+            PianoType::BabyGrand => &[
+                (21, -14.0),
+                (33, -6.0),
+                (45, -2.0),
+                (57, -0.4),
+                (69, 0.0),
+                (81, 3.5),
+                (93, 9.5),
+                (105, 18.0),
+                (108, 21.0),
+            ],
+            PianoType::SemiConcert => &[
+                (21, -11.0),
+                (33, -4.5),
+                (45, -1.5),
+                (57, -0.3),
+                (69, 0.0),
+                (81, 3.0),
+                (93, 8.0),
+                (105, 15.0),
+                (108, 18.0),
+            ],
+            PianoType::ConcertGrand => &[
+                (21, -8.0),
+                (33, -3.0),
+                (45, -1.0),
+                (57, -0.2),
+                (69, 0.0),
+                (81, 2.5),
+                (93, 7.0),
+                (105, 13.0),
+                (108, 16.0),
+            ],
+            PianoType::Legacy => return None,
+        };
+        Some(anchors)
+    }
+}
+
+/// Partials an octave is tuned beatless at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OctaveType {
+    /// The lower note's 2nd partial against the upper note's fundamental.
+    #[serde(rename = "2:1")]
+    Pure,
+    /// The lower note's 4th partial against the upper note's 2nd.
+    #[serde(rename = "4:2")]
+    FourTwo,
+    /// The lower note's 6th partial against the upper note's 3rd.
+    #[serde(rename = "6:3")]
+    SixThree,
+    /// 6:3 below C2, 4:2 from there to C3 and 2:1 above.
+    #[default]
+    #[serde(rename = "by-register")]
+    ByRegister,
+}
+
+impl OctaveType {
+    /// Display name.
+    pub fn name(self) -> &'static str {
+        match self {
+            OctaveType::Pure => "2:1",
+            OctaveType::FourTwo => "4:2",
+            OctaveType::SixThree => "6:3",
+            OctaveType::ByRegister => "By register",
+        }
+    }
+
+    /// Octave type of the octave from `midi` up.
+    pub fn at(self, midi: u8) -> Self {
+        match self {
+            OctaveType::ByRegister if midi < 36 => OctaveType::SixThree,
+            OctaveType::ByRegister if midi < 48 => OctaveType::FourTwo,
+            OctaveType::ByRegister => OctaveType::Pure,
+            octave => octave,
+        }
+    }
+
+    /// Partial of the upper note heard against the lower note's partial
+    /// twice as high, for the octave from `midi` up.
+    fn upper_partial(self, midi: u8) -> f64 {
+        match self.at(midi) {
+            OctaveType::SixThree => 3.0,
+            OctaveType::FourTwo => 2.0,
+            _ => 1.0,
+        }
+    }
+}
 
 /// Stretch tuning curve based on the Railsback curve.
 ///
 /// The Railsback curve is an empirical curve showing how piano tuners
 /// deviate from equal temperament to achieve the most pleasing sound.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "CurveFile", into = "CurveFile")]
 pub struct StretchCurve {
     /// Stretch values in cents for each of the 88 keys.
     /// Index 0 = A0 (MIDI 21), Index 87 = C8 (MIDI 108)
-    offsets: [f32; 88],
+    offsets: [f64; 88],
+    /// Factor every offset is scaled by.
+    intensity: f64,
 }
 
 impl StretchCurve {
-    /// Create a new stretch curve with default Railsback-inspired values.
+    /// Create the stretch curve of the default piano type (an upright).
     pub fn new() -> Self {
+        Self::preset(PianoType::default())
+    }
+
+    /// Create the stretch curve of a piano type.
+    pub fn preset(piano: PianoType) -> Self {
+        let offsets = match piano.anchors() {
+            Some(anchors) => {
+                let (xs, ys): (Vec<f64>, Vec<f64>) = anchors
+                    .iter()
+                    .map(|&(midi, cents)| (midi as f64, cents))
+                    .unzip();
+                Self::monotone_cubic(&xs, &ys)
+            }
+            None => Self::legacy(),
+        };
+        Self {
+            offsets,
+            intensity: 1.0,
+        }
+    }
+
+    /// Create a flat curve with no stretch, for instruments with little
+    /// inharmonicity.
+    pub fn flat() -> Self {
         Self {
-            offsets: Self::generate_railsback_curve(),
+            offsets: [0.0; 88],
+            intensity: 1.0,
+        }
+    }
+
+    /// Create a curve through measured points (MIDI note, cents), from the
+    /// lowest note up. Between the points the curve is a monotone cubic
+    /// (Fritsch–Carlson), so it never overshoots them; beyond the outer
+    /// points it holds their offsets.
+    pub fn from_points(points: &[(u8, f32)]) -> Result<Self, StretchError> {
+        Self::check_points(points)?;
+        if let Some(&(midi, _)) = points.iter().find(|(_, cents)| !cents.is_finite()) {
+            return Err(StretchError::InvalidOffset(midi));
+        }
+
+        let xs: Vec<f64> = points.iter().map(|&(midi, _)| midi as f64).collect();
+        let ys: Vec<f64> = points.iter().map(|&(_, cents)| cents as f64).collect();
+        Ok(Self {
+            offsets: Self::monotone_cubic(&xs, &ys),
+            intensity: 1.0,
+        })
+    }
+
+    /// Create a curve from the inharmonicity coefficient `b` measured at
+    /// some notes (MIDI note, b), from the lowest note up. The octave A3–A4
+    /// is split evenly about A4 and every other note tuned beatless an
+    /// octave from one already tuned, at the partials `octaves` gives the
+    /// octave. Between the points b is interpolated on a log scale; beyond
+    /// the outer points it holds.
+    pub fn from_inharmonicity(
+        points: &[(u8, f32)],
+        octaves: OctaveType,
+    ) -> Result<Self, StretchError> {
+        Self::check_points(points)?;
+        if let Some(&(midi, _)) = points.iter().find(|&&(_, b)| !(b > 0.0 && b.is_finite())) {
+            return Err(StretchError::InvalidInharmonicity(midi));
         }
+
+        // b of every key, log-linear between the points
+        let mut b = [0.0_f64; 88];
+        for (i, key) in b.iter_mut().enumerate() {
+            let x = (i + 21) as f64;
+            let k = points
+                .windows(2)
+                .position(|w| x <= w[1].0 as f64)
+                .unwrap_or(points.len() - 2);
+            let (low_midi, low) = (points[k].0 as f64, (points[k].1 as f64).ln());
+            let (high_midi, high) = (points[k + 1].0 as f64, (points[k + 1].1 as f64).ln());
+            let t = ((x - low_midi) / (high_midi - low_midi)).clamp(0.0, 1.0);
+            *key = (low + t * (high - low)).exp();
+        }
+
+        // Width in cents beyond 2:1 of the beatless octave from `low` up
+        let octave = |low: u8| {
+            let n = octaves.upper_partial(low);
+            let (b_low, b_high) = (b[(low - 21) as usize], b[(low - 9) as usize]);
+            600.0 * ((1.0 + 4.0 * n * n * b_low) / (1.0 + n * n * b_high)).log2()
+        };
+
+        let mut offsets = [0.0_f64; 88];
+        let width = octave(57);
+        for midi in 63..=74u8 {
+            offsets[(midi - 21) as usize] = width * (midi as f64 - 69.0) / 12.0;
+        }
+        for midi in (21..63u8).rev() {
+            offsets[(midi - 21) as usize] = offsets[(midi - 9) as usize] - octave(midi);
+        }
+        for midi in 75..=108u8 {
+            offsets[(midi - 21) as usize] = offsets[(midi - 33) as usize] + octave(midi - 12);
+        }
+        Ok(Self {
+            offsets,
+            intensity: 1.0,
+        })
+    }
+
+    /// Check points are at least two piano keys, from the lowest up.
+    fn check_points(points: &[(u8, f32)]) -> Result<(), StretchError> {
+        if points.len() < 2 {
+            return Err(StretchError::TooFewPoints(points.len()));
+        }
+        if let Some(&(midi, _)) = points.iter().find(|(midi, _)| !(21..=108).contains(midi)) {
+            return Err(StretchError::OutOfRange(midi));
+        }
+        for pair in points.windows(2) {
+            let (prev, midi) = (pair[0].0, pair[1].0);
+            if midi == prev {
+                return Err(StretchError::Duplicate(midi));
+            }
+            if midi < prev {
+                return Err(StretchError::Unsorted(midi));
+            }
+        }
+        Ok(())
+    }
+
+    /// Offsets of the 88 keys on a monotone cubic through the points,
+    /// holding the outer points' offsets beyond them.
+    fn monotone_cubic(xs: &[f64], ys: &[f64]) -> [f64; 88] {
+        let tangents = Self::monotone_tangents(xs, ys);
+        let mut offsets = [0.0_f64; 88];
+        for (i, offset) in offsets.iter_mut().enumerate() {
+            let x = (i + 21) as f64;
+            *offset = if x <= xs[0] {
+                ys[0]
+            } else if x >= xs[xs.len() - 1] {
+                ys[ys.len() - 1]
+            } else {
+                let k = xs.windows(2).position(|w| x <= w[1]).unwrap_or(0);
+                let h = xs[k + 1] - xs[k];
+                let t = (x - xs[k]) / h;
+                let t2 = t * t;
+                let t3 = t2 * t;
+                (2.0 * t3 - 3.0 * t2 + 1.0) * ys[k]
+                    + (t3 - 2.0 * t2 + t) * h * tangents[k]
+                    + (-2.0 * t3 + 3.0 * t2) * ys[k + 1]
+                    + (t3 - t2) * h * tangents[k + 1]
+            };
+        }
+        offsets
+    }
+
+    /// Tangents at each point, limited so the cubic between two points
+    /// stays between them (Fritsch–Carlson).
+    fn monotone_tangents(xs: &[f64], ys: &[f64]) -> Vec<f64> {
+        let n = xs.len();
+        let slopes: Vec<f64> = (0..n - 1)
+            .map(|k| (ys[k + 1] - ys[k]) / (xs[k + 1] - xs[k]))
+            .collect();
+
+        let mut tangents = vec![0.0; n];
+        tangents[0] = slopes[0];
+        tangents[n - 1] = slopes[n - 2];
+        for k in 1..n - 1 {
+            // Flat at a peak or dip, so the curve doesn't overshoot it
+            if slopes[k - 1] * slopes[k] > 0.0 {
+                tangents[k] = (slopes[k - 1] + slopes[k]) / 2.0;
+            }
+        }
+
+        for k in 0..n - 1 {
+            if slopes[k] == 0.0 {
+                tangents[k] = 0.0;
+                tangents[k + 1] = 0.0;
+                continue;
+            }
+            let a = tangents[k] / slopes[k];
+            let b = tangents[k + 1] / slopes[k];
+            let s = a * a + b * b;
+            if s > 9.0 {
+                let t = 3.0 / s.sqrt();
+                tangents[k] = t * a * slopes[k];
+                tangents[k + 1] = t * b * slopes[k];
+            }
+        }
+        tangents
+    }
+
+    /// Scale every offset by `intensity`, from 0.0 (equal temperament) to
+    /// [`MAX_INTENSITY`]: more for a spinet's short strings, less for a
+    /// concert grand.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity.clamp(0.0, MAX_INTENSITY) as f64;
+        self
+    }
+
+    /// Factor every offset is scaled by (1.0 for the default curve).
+    pub fn intensity(&self) -> f32 {
+        self.intensity as f32
     }
 
     /// Get the stretch offset in cents for a given MIDI note.
     /// Positive values = tune sharp, negative = tune flat.
     pub fn offset_cents(&self, midi_note: u8) -> f32 {
+        self.offset_cents_f64(midi_note) as f32
+    }
+
+    /// Stretch offset in cents for a MIDI note at full precision.
+    pub fn offset_cents_f64(&self, midi_note: u8) -> f64 {
         if !(21..=108).contains(&midi_note) {
             return 0.0;
         }
-        self.offsets[(midi_note - 21) as usize]
+        self.offsets[(midi_note - 21) as usize] * self.intensity
     }
 
     /// Get the stretch offset for a note by index (0-87).
     pub fn offset_cents_by_index(&self, index: usize) -> f32 {
-        self.offsets.get(index).copied().unwrap_or(0.0)
+        self.offsets
+            .get(index)
+            .map_or(0.0, |&cents| (cents * self.intensity) as f32)
     }
 
-    /// Generate a simplified Railsback-style stretch curve.
-    ///
-    /// This is a simplified model based on typical Railsback curves:
-    /// - Bass notes (A0-C3): progressively flat, up to -20 cents at A0
-    /// - Middle octaves (C3-F5): close to 0, the "temperament zone"
-    /// - Treble notes (F5-C8): progressively sharp, up to +20 cents at C8
-    fn generate_railsback_curve() -> [f32; 88] {
-        let mut offsets = [0.0_f32; 88];
-
+    /// The curve of earlier versions: flat through the middle, rising as
+    /// the square of the distance from C4 to about 20 cents at either end.
+    fn legacy() -> [f64; 88] {
+        let mut offsets = [0.0_f64; 88];
         for (i, offset) in offsets.iter_mut().enumerate() {
-            let midi = (i + 21) as u8;
-            *offset = Self::calculate_stretch(midi);
+            let x = (i as f64 + 21.0 - 60.0) / 44.0;
+            *offset = 20.0 * x * x * x.signum();
         }
-
         offsets
     }
 
-    /// Calculate stretch for a single note.
-    ///
-    /// Uses a smooth cubic curve across the entire range:
-    /// - A0 (21): approximately -20 cents
-    /// - C4 (60): approximately 0 cents
-    /// - C8 (108): approximately +20 cents
-    fn calculate_stretch(midi: u8) -> f32 {
-        // Center of the piano (around middle C)
-        let center: f32 = 60.0;
-        let range: f32 = 44.0; // Half the piano range
+    /// Apply stretch to a base frequency.
+    pub fn apply(&self, base_frequency: f32, midi_note: u8) -> f32 {
+        let cents_offset = self.offset_cents_f64(midi_note);
+        (base_frequency as f64 * 2.0_f64.powf(cents_offset / 1200.0)) as f32
+    }
+}
+
+/// A stretch curve as saved: the offsets of the 88 keys, from A0 up.
+#[derive(Serialize, Deserialize)]
+struct CurveFile {
+    offsets: Vec<f64>,
+    #[serde(default = "default_intensity")]
+    intensity: f64,
+}
 
-        // Normalized position: -1 at low end, 0 at center, +1 at high end
-        let x = (midi as f32 - center) / range;
+fn default_intensity() -> f64 {
+    1.0
+}
 
-        // Cubic function: starts flat at center, steepens toward extremes
-        // This gives approximately:
-        // - x = -0.89 (A0): stretch ≈ -20
-        // - x = 0 (C4): stretch ≈ 0
-        // - x = 1.09 (C8): stretch ≈ +20
-        20.0 * x * x * x.signum()
+impl From<StretchCurve> for CurveFile {
+    fn from(curve: StretchCurve) -> Self {
+        Self {
+            offsets: curve.offsets.to_vec(),
+            intensity: curve.intensity,
+        }
     }
+}
 
-    /// Apply stretch to a base frequency.
-    pub fn apply(&self, base_frequency: f32, midi_note: u8) -> f32 {
-        let cents_offset = self.offset_cents(midi_note);
-        base_frequency * 2.0_f32.powf(cents_offset / 1200.0)
+impl TryFrom<CurveFile> for StretchCurve {
+    type Error = StretchError;
+
+    fn try_from(file: CurveFile) -> Result<Self, StretchError> {
+        let offsets: [f64; 88] = file
+            .offsets
+            .as_slice()
+            .try_into()
+            .map_err(|_| StretchError::OffsetCount(file.offsets.len()))?;
+        if let Some(i) = offsets.iter().position(|offset| !offset.is_finite()) {
+            return Err(StretchError::InvalidOffset(i as u8 + 21));
+        }
+        Ok(Self {
+            offsets,
+            intensity: file.intensity.clamp(0.0, MAX_INTENSITY as f64),
+        })
     }
 }
 
@@ -203,16 +631,303 @@ mod tests {
         // Verify approximate magnitudes match Railsback expectations
         let a0 = curve.offset_cents(21);
         assert!(
-            a0 >= -25.0 && a0 <= -10.0,
+            (-25.0..=-10.0).contains(&a0),
             "A0 stretch {} out of expected range",
             a0
         );
 
         let c8 = curve.offset_cents(108);
         assert!(
-            c8 >= 10.0 && c8 <= 25.0,
+            (10.0..=25.0).contains(&c8),
             "C8 stretch {} out of expected range",
             c8
         );
     }
+
+    #[test]
+    fn test_presets_monotonic_and_ordered() {
+        for piano in PianoType::ALL {
+            let curve = StretchCurve::preset(piano);
+            if piano != PianoType::Legacy {
+                assert_eq!(curve.offset_cents(69), 0.0, "{:?}", piano);
+            }
+            for midi in 22..=108 {
+                assert!(
+                    curve.offset_cents(midi) >= curve.offset_cents(midi - 1),
+                    "{:?} at MIDI {}",
+                    piano,
+                    midi
+                );
+            }
+        }
+
+        // Shorter strings are stretched further at both ends
+        for pair in PianoType::ALL[..6].windows(2) {
+            let (shorter, longer) = (StretchCurve::preset(pair[0]), StretchCurve::preset(pair[1]));
+            assert!(
+                shorter.offset_cents(21) < longer.offset_cents(21),
+                "{:?}",
+                pair
+            );
+            assert!(
+                shorter.offset_cents(108) > longer.offset_cents(108),
+                "{:?}",
+                pair
+            );
+        }
+        let spinet = StretchCurve::preset(PianoType::Spinet);
+        let concert = StretchCurve::preset(PianoType::ConcertGrand);
+        assert!(spinet.offset_cents(21) < concert.offset_cents(21));
+
+        // The default curve is the upright's
+        let upright = StretchCurve::preset(PianoType::Upright);
+        assert!((21..=108)
+            .all(|midi| StretchCurve::new().offset_cents(midi) == upright.offset_cents(midi)));
+    }
+
+    #[test]
+    fn test_presets_fit_railsback() {
+        // Through the averaged measurements at every A
+        for piano in &PianoType::ALL[..6] {
+            let curve = StretchCurve::preset(*piano);
+            for &(midi, cents) in piano.anchors().unwrap() {
+                assert!(
+                    (curve.offset_cents_f64(midi) - cents).abs() < 1e-9,
+                    "{:?} at MIDI {}",
+                    piano,
+                    midi
+                );
+            }
+        }
+
+        // The stretch sets in gradually either side of the middle
+        let curve = StretchCurve::new();
+        let c2 = curve.offset_cents(36);
+        assert!((c2 + 8.0).abs() <= 3.0, "C2 stretch {}", c2);
+        let c6 = curve.offset_cents(84);
+        assert!((c6 - 4.0).abs() <= 3.0, "C6 stretch {}", c6);
+        let c3 = curve.offset_cents(48);
+        assert!((-3.0..-0.5).contains(&c3), "C3 stretch {}", c3);
+        let c5 = curve.offset_cents(72);
+        assert!((0.5..3.0).contains(&c5), "C5 stretch {}", c5);
+
+        // No corners at the anchors: the steps either side of each are
+        // within a cent of each other
+        for midi in 22..108 {
+            let below = curve.offset_cents(midi) - curve.offset_cents(midi - 1);
+            let above = curve.offset_cents(midi + 1) - curve.offset_cents(midi);
+            assert!((above - below).abs() < 1.0, "MIDI {}", midi);
+        }
+    }
+
+    #[test]
+    fn test_legacy_preset_unchanged() {
+        let legacy = StretchCurve::preset(PianoType::Legacy);
+        for (midi, cents) in [(21, -15.71), (60, 0.0), (69, 0.84), (108, 23.8)] {
+            assert!(
+                (legacy.offset_cents(midi) - cents).abs() < 0.01,
+                "MIDI {}: {}",
+                midi,
+                legacy.offset_cents(midi)
+            );
+        }
+        let curve = serde_json::to_string(&PianoType::Legacy).unwrap();
+        assert_eq!(curve, r#""legacy""#);
+    }
+
+    #[test]
+    fn test_piano_type_cycles() {
+        assert_eq!(PianoType::Spinet.prev(), PianoType::Legacy);
+        assert_eq!(PianoType::ConcertGrand.next(), PianoType::Legacy);
+        assert_eq!(PianoType::Legacy.next(), PianoType::Spinet);
+        assert_eq!(PianoType::Upright.next(), PianoType::BabyGrand);
+        assert_eq!(PianoType::BabyGrand.name(), "Baby Grand");
+    }
+
+    #[test]
+    fn test_from_points_through_anchors() {
+        let points = [
+            (21, -30.0),
+            (33, -12.0),
+            (45, -4.0),
+            (69, 0.0),
+            (81, 3.0),
+            (93, 12.0),
+            (105, 28.0),
+            (108, 36.0),
+        ];
+        let curve = StretchCurve::from_points(&points).unwrap();
+        for &(midi, cents) in &points {
+            assert_eq!(curve.offset_cents(midi), cents, "MIDI {}", midi);
+        }
+
+        // Rising between the anchors, and never past them
+        for pair in points.windows(2) {
+            let ((low, low_cents), (high, high_cents)) = (pair[0], pair[1]);
+            for midi in low + 1..=high {
+                let cents = curve.offset_cents(midi);
+                assert!(cents >= curve.offset_cents(midi - 1), "MIDI {}", midi);
+                assert!((low_cents..=high_cents).contains(&cents), "MIDI {}", midi);
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_points_without_overshoot() {
+        // A steep step between flat stretches stays flat on either side
+        let curve =
+            StretchCurve::from_points(&[(40, 0.0), (60, 0.0), (62, 20.0), (80, 20.0)]).unwrap();
+        assert!((21..=60).all(|midi| curve.offset_cents(midi) == 0.0));
+        assert!((62..=108).all(|midi| curve.offset_cents(midi) == 20.0));
+        assert!((0.0..=20.0).contains(&curve.offset_cents(61)));
+    }
+
+    #[test]
+    fn test_from_points_rejected() {
+        assert_eq!(
+            StretchCurve::from_points(&[(21, -20.0)]).unwrap_err(),
+            StretchError::TooFewPoints(1)
+        );
+        assert_eq!(
+            StretchCurve::from_points(&[(21, -20.0), (45, -2.0), (33, -8.0)]).unwrap_err(),
+            StretchError::Unsorted(33)
+        );
+        assert_eq!(
+            StretchCurve::from_points(&[(21, -20.0), (45, -2.0), (45, -1.0)]).unwrap_err(),
+            StretchError::Duplicate(45)
+        );
+        assert_eq!(
+            StretchCurve::from_points(&[(12, -20.0), (45, -2.0)]).unwrap_err(),
+            StretchError::OutOfRange(12)
+        );
+        assert_eq!(
+            StretchCurve::from_points(&[(21, f32::NAN), (45, -2.0)]).unwrap_err(),
+            StretchError::InvalidOffset(21)
+        );
+    }
+
+    /// Inharmonicity of a typical upright: least in the tenor, rising into
+    /// the bass and steeply into the treble.
+    const UPRIGHT_B: [(u8, f32); 4] = [(21, 4e-4), (48, 1e-4), (69, 4e-4), (108, 1e-2)];
+
+    #[test]
+    fn test_octave_types_widen_bass() {
+        let curve = |octaves| StretchCurve::from_inharmonicity(&UPRIGHT_B, octaves).unwrap();
+        let pure = curve(OctaveType::Pure);
+        let four_two = curve(OctaveType::FourTwo);
+        let six_three = curve(OctaveType::SixThree);
+
+        for midi in [21, 33, 45] {
+            let (a, b, c) = (
+                pure.offset_cents(midi),
+                four_two.offset_cents(midi),
+                six_three.offset_cents(midi),
+            );
+            assert!(c < b && b < a && a < 0.0, "{}: {} {} {}", midi, a, b, c);
+        }
+        for curve in [&pure, &four_two, &six_three] {
+            assert_eq!(curve.offset_cents(69), 0.0);
+            assert!(curve.offset_cents(108) > 0.0);
+            let octaves: Vec<f32> = (21..=108)
+                .step_by(12)
+                .map(|m| curve.offset_cents(m))
+                .collect();
+            assert!(octaves.windows(2).all(|w| w[0] < w[1]), "{:?}", octaves);
+        }
+
+        // Each octave up from A0 is as wide as its partials make it
+        let n = 3.0_f64;
+        let (b_a0, b_a1) = (
+            4e-4_f64,
+            (4e-4_f64.ln() * 15.0 / 27.0 + 1e-4_f64.ln() * 12.0 / 27.0).exp(),
+        );
+        let width = 600.0 * ((1.0 + 4.0 * n * n * b_a0) / (1.0 + n * n * b_a1)).log2();
+        let measured = six_three.offset_cents_f64(33) - six_three.offset_cents_f64(21);
+        assert!((measured - width).abs() < 1e-6, "{} {}", measured, width);
+    }
+
+    #[test]
+    fn test_octave_type_by_register() {
+        assert_eq!(OctaveType::ByRegister.at(21), OctaveType::SixThree);
+        assert_eq!(OctaveType::ByRegister.at(40), OctaveType::FourTwo);
+        assert_eq!(OctaveType::ByRegister.at(60), OctaveType::Pure);
+        assert_eq!(OctaveType::FourTwo.at(21), OctaveType::FourTwo);
+
+        // The bass follows 6:3, the middle 2:1
+        let mixed = StretchCurve::from_inharmonicity(&UPRIGHT_B, OctaveType::ByRegister).unwrap();
+        let six_three = StretchCurve::from_inharmonicity(&UPRIGHT_B, OctaveType::SixThree).unwrap();
+        let pure = StretchCurve::from_inharmonicity(&UPRIGHT_B, OctaveType::Pure).unwrap();
+        let octave = |curve: &StretchCurve, low: u8| {
+            curve.offset_cents_f64(low + 12) - curve.offset_cents_f64(low)
+        };
+        assert!((octave(&mixed, 21) - octave(&six_three, 21)).abs() < 1e-9);
+        assert!((octave(&mixed, 72) - octave(&pure, 72)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_from_inharmonicity_rejected() {
+        let from =
+            |points: &[(u8, f32)]| StretchCurve::from_inharmonicity(points, OctaveType::Pure);
+        assert_eq!(from(&[(21, 4e-4)]), Err(StretchError::TooFewPoints(1)));
+        assert_eq!(
+            from(&[(21, 4e-4), (69, 0.0)]),
+            Err(StretchError::InvalidInharmonicity(69))
+        );
+        assert_eq!(
+            from(&[(21, f32::NAN), (69, 4e-4)]),
+            Err(StretchError::InvalidInharmonicity(21))
+        );
+        assert_eq!(
+            from(&[(69, 4e-4), (21, 4e-4)]),
+            Err(StretchError::Unsorted(21))
+        );
+    }
+
+    #[test]
+    fn test_serialized_with_every_offset() {
+        let curve = StretchCurve::preset(PianoType::Console).with_intensity(0.8);
+        let json = serde_json::to_string(&curve).unwrap();
+        let restored: StretchCurve = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.intensity(), 0.8);
+        assert!((21..=108).all(|midi| (restored.offset_cents_f64(midi)
+            - curve.offset_cents_f64(midi))
+        .abs()
+            < 1e-9));
+
+        let short = r#"{"offsets": [-20.0, 0.0, 20.0]}"#;
+        let error = serde_json::from_str::<StretchCurve>(short).unwrap_err();
+        assert!(
+            error.to_string().contains("Expected 88 offsets"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_intensity_scales_offsets() {
+        let curve = StretchCurve::new();
+        let spinet = StretchCurve::new().with_intensity(1.5);
+        let grand = StretchCurve::new().with_intensity(0.5);
+        for midi in 21..=108 {
+            let offset = curve.offset_cents_f64(midi);
+            assert!((spinet.offset_cents_f64(midi) - 1.5 * offset).abs() < 1e-9);
+            assert!((grand.offset_cents_f64(midi) - 0.5 * offset).abs() < 1e-9);
+        }
+        assert_eq!(
+            spinet.apply(27.5, 21),
+            27.5 * 2.0_f32.powf(spinet.offset_cents(21) / 1200.0)
+        );
+
+        // No stretch at all reduces to equal temperament
+        let none = StretchCurve::new().with_intensity(0.0);
+        assert!((21..=108).all(|midi| none.offset_cents(midi) == 0.0));
+        assert_eq!(none.apply(4186.0, 108), 4186.0);
+
+        // Held within range
+        assert_eq!(
+            StretchCurve::new().with_intensity(3.0).intensity(),
+            MAX_INTENSITY
+        );
+        assert_eq!(StretchCurve::new().with_intensity(-1.0).intensity(), 0.0);
+    }
 }
@@ -1,35 +1,123 @@
-//! Equal temperament calculations.
+//! Temperament calculations: equal temperament, optionally tempered by a
+//! historical scheme or custom offsets.
+//!
+//! Frequencies are worked out in f64 and handed out as f32, so rounding
+//! doesn't build up as stretch and overrides multiply more ratios on top.
 
+use std::path::Path;
+
+use super::custom::{CustomTemperament, CustomTemperamentError};
+use super::interval::IntervalKind;
 use super::notes::Note;
+use super::scheme::{offset_from, TemperamentScheme};
+use super::stretch::StretchCurve;
+
+/// Common A4 references: baroque, and the usual modern concert pitches.
+pub const DEFAULT_A4_PRESETS: [f32; 6] = [415.0, 435.0, 440.0, 441.0, 442.0, 443.0];
 
-/// Equal temperament calculator.
+/// Accepted A4 reference range in Hz.
+pub const A4_RANGE: std::ops::RangeInclusive<f32> = 380.0..=480.0;
+
+/// Temperament calculator.
 #[derive(Debug, Clone, Copy)]
 pub struct Temperament {
     /// Reference frequency for A4.
-    a4_freq: f32,
+    a4_freq: f64,
+    /// Offsets from equal temperament in cents per pitch class, from the
+    /// root up.
+    offsets: [f32; 12],
+    /// Pitch class the offsets start from (C = 0).
+    root: u8,
 }
 
 impl Temperament {
-    /// Create a new temperament with A4 = 440 Hz.
+    /// Create a new equal temperament with A4 = 440 Hz.
     pub fn new() -> Self {
-        Self { a4_freq: 440.0 }
+        Self::with_a4(440.0)
     }
 
-    /// Create a temperament with a custom A4 reference.
+    /// Create an equal temperament with a custom A4 reference.
     pub fn with_a4(a4_freq: f32) -> Self {
-        Self { a4_freq }
+        Self {
+            a4_freq: a4_freq as f64,
+            offsets: [0.0; 12],
+            root: 0,
+        }
+    }
+
+    /// Temper by `scheme`, laid from the root pitch class `root` (C = 0).
+    pub fn with_scheme(self, scheme: TemperamentScheme, root: u8) -> Self {
+        self.with_offsets(scheme.offsets(), root)
+    }
+
+    /// Temper by offsets from equal temperament in cents per pitch class,
+    /// from the root pitch class `root` (C = 0) up. A is kept at the
+    /// reference.
+    pub fn with_offsets(mut self, offsets: [f32; 12], root: u8) -> Self {
+        self.offsets = offsets;
+        self.root = root % 12;
+        self
+    }
+
+    /// Temper by a custom temperament.
+    pub fn with_custom(self, custom: &CustomTemperament) -> Self {
+        self.with_offsets(custom.offsets, custom.root)
+    }
+
+    /// Equal temperament at A4 = 440 Hz tempered by the offsets in a
+    /// custom temperament file.
+    pub fn from_offsets_file(path: &Path) -> Result<Self, CustomTemperamentError> {
+        Ok(Self::new().with_custom(&CustomTemperament::load(path)?))
+    }
+
+    /// The same temperament with every note moved by `cents`, as when
+    /// tuning a piano a semitone or a few cents below concert pitch.
+    pub fn transposed(mut self, cents: f32) -> Self {
+        self.a4_freq *= Self::cents_to_ratio_f64(cents as f64);
+        self
+    }
+
+    /// Split an A4 heard far from 440 Hz into whole semitones of
+    /// transposition, in cents, and the A4 left within half a semitone of
+    /// 440 Hz.
+    pub fn split_transposition(a4: f32) -> (f32, f32) {
+        let semitones = (12.0 * (a4 as f64 / 440.0).log2()).round();
+        let transpose = semitones * 100.0;
+        (
+            (a4 as f64 / Self::cents_to_ratio_f64(transpose)) as f32,
+            transpose as f32,
+        )
+    }
+
+    /// Pitch class the offsets start from (C = 0).
+    pub fn root(&self) -> u8 {
+        self.root
     }
 
     /// Get the A4 reference frequency.
     pub fn a4(&self) -> f32 {
-        self.a4_freq
+        self.a4_freq as f32
     }
 
     /// Calculate the frequency for a given MIDI note number.
-    /// Uses the formula: f = A4 * 2^((n - 69) / 12)
+    /// Uses the formula: f = A4 * 2^((n - 69) / 12), moved by the offset
+    /// for the note's pitch class.
     pub fn frequency(&self, midi_note: u8) -> f32 {
+        self.frequency_f64(midi_note) as f32
+    }
+
+    /// Frequency of a MIDI note at full precision, for targets built up
+    /// from further ratios.
+    pub fn frequency_f64(&self, midi_note: u8) -> f64 {
         // A4 is MIDI note 69
-        self.a4_freq * 2.0_f32.powf((midi_note as f32 - 69.0) / 12.0)
+        let equal = self.a4_freq * 2.0_f64.powf((midi_note as f64 - 69.0) / 12.0);
+        let offset = offset_from(&self.offsets, midi_note, self.root);
+        equal * Self::cents_to_ratio_f64(offset as f64)
+    }
+
+    /// A4 implied by hearing `frequency` as the given MIDI note.
+    pub fn implied_a4(frequency: f32, midi_note: u8) -> f32 {
+        (frequency as f64 * 2.0_f64.powf((69.0 - midi_note as f64) / 12.0)) as f32
     }
 
     /// Calculate the frequency for a Note.
@@ -39,12 +127,18 @@ impl Temperament {
 
     /// Convert a frequency to cents deviation from a target frequency.
     /// Positive = sharp, negative = flat.
-    pub fn cents_from_target(&self, frequency: f32, target: f32) -> f32 {
-        1200.0 * (frequency / target).log2()
+    ///
+    /// Returns `None` unless both frequencies are positive and finite, so
+    /// silence or a bad reading never turns into infinite or NaN cents.
+    pub fn cents_from_target(&self, frequency: f32, target: f32) -> Option<f32> {
+        if !(is_audible(frequency) && is_audible(target)) {
+            return None;
+        }
+        Some((1200.0 * (frequency as f64 / target as f64).log2()) as f32)
     }
 
-    /// Convert frequency deviation to cents.
-    pub fn frequency_to_cents(&self, frequency: f32, midi_note: u8) -> f32 {
+    /// Convert frequency deviation to cents, if the frequency is valid.
+    pub fn frequency_to_cents(&self, frequency: f32, midi_note: u8) -> Option<f32> {
         let target = self.frequency(midi_note);
         self.cents_from_target(frequency, target)
     }
@@ -57,24 +151,58 @@ impl Temperament {
 
     /// Convert cents deviation to frequency ratio.
     pub fn cents_to_ratio(cents: f32) -> f32 {
-        2.0_f32.powf(cents / 1200.0)
+        Self::cents_to_ratio_f64(cents as f64) as f32
+    }
+
+    /// Convert cents to frequency ratio at full precision.
+    pub fn cents_to_ratio_f64(cents: f64) -> f64 {
+        2.0_f64.powf(cents / 1200.0)
+    }
+
+    /// Beats per second between two notes sounded together.
+    ///
+    /// The beat rate is the difference between the coincident partials of
+    /// the interval (e.g. 3×lower against 2×upper for a fifth), assuming
+    /// harmonic partials. With a stretch curve, both notes are taken at
+    /// their stretched frequencies.
+    pub fn beat_rate(
+        &self,
+        lower_midi: u8,
+        upper_midi: u8,
+        kind: IntervalKind,
+        stretch: Option<&StretchCurve>,
+    ) -> f32 {
+        let frequency = |midi: u8| match stretch {
+            Some(curve) => curve.apply(self.frequency(midi), midi),
+            None => self.frequency(midi),
+        };
+        kind.beat_rate(frequency(lower_midi), frequency(upper_midi))
     }
 
     /// Find the nearest MIDI note for a given frequency.
-    /// Returns (midi_note, cents_deviation).
-    pub fn nearest_note(&self, frequency: f32) -> (u8, f32) {
+    /// Returns (midi_note, cents_deviation), with the note held within
+    /// MIDI 0-127, or `None` if the frequency isn't positive and finite.
+    pub fn nearest_note(&self, frequency: f32) -> Option<(u8, f32)> {
+        if !is_audible(frequency) {
+            return None;
+        }
         // Calculate fractional MIDI note
-        let midi_float = 69.0 + 12.0 * (frequency / self.a4_freq).log2();
-        let midi_note = midi_float.round() as u8;
+        let midi_float = 69.0 + 12.0 * (frequency as f64 / self.a4_freq).log2();
+        let midi_note = midi_float.round().clamp(0.0, 127.0) as u8;
 
         // Calculate cents deviation
         let target_freq = self.frequency(midi_note);
-        let cents = self.cents_from_target(frequency, target_freq);
+        let cents = self.cents_from_target(frequency, target_freq)?;
 
-        (midi_note, cents)
+        Some((midi_note, cents))
     }
 }
 
+/// Whether a frequency can be measured in cents: positive and finite.
+fn is_audible(frequency: f32) -> bool {
+    frequency > 0.0 && frequency.is_finite()
+}
+
 impl Default for Temperament {
     fn default() -> Self {
         Self::new()
@@ -201,22 +329,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_implied_a4() {
+        assert!((Temperament::implied_a4(438.6, 69) - 438.6).abs() < 0.001);
+        let c4 = Temperament::with_a4(442.0).frequency(60);
+        assert!((Temperament::implied_a4(c4, 60) - 442.0).abs() < 0.01);
+    }
+
     #[test]
     fn test_all_88_notes_at_440() {
         let temp = Temperament::new();
 
+        // Within the rounding of the table to a thousandth of a Hz
         for &(midi, expected) in &REFERENCE_FREQUENCIES {
             let calculated = temp.frequency(midi);
             let error = (calculated - expected).abs();
-            let relative_error = error / expected;
 
             assert!(
-                relative_error < 0.001,
-                "MIDI {} should be {:.3}Hz, got {:.3}Hz (error: {:.4}%)",
+                error < 0.001,
+                "MIDI {} should be {:.3}Hz, got {:.4}Hz (error: {:.5}Hz)",
                 midi,
                 expected,
                 calculated,
-                relative_error * 100.0
+                error
             );
         }
     }
@@ -272,7 +407,7 @@ mod tests {
         let temp = Temperament::new();
 
         // Exact pitch should be 0 cents
-        let cents = temp.cents_from_target(440.0, 440.0);
+        let cents = temp.cents_from_target(440.0, 440.0).unwrap();
         assert!(
             (cents).abs() < 0.001,
             "Same frequency should be 0 cents, got {}",
@@ -288,7 +423,7 @@ mod tests {
         let a4 = 440.0;
         let asharp4 = temp.frequency(70); // A#4
 
-        let cents = temp.cents_from_target(asharp4, a4);
+        let cents = temp.cents_from_target(asharp4, a4).unwrap();
         assert!(
             (cents - 100.0).abs() < 0.1,
             "Semitone should be 100 cents, got {}",
@@ -301,7 +436,7 @@ mod tests {
         let temp = Temperament::new();
 
         // One octave = 1200 cents
-        let cents = temp.cents_from_target(880.0, 440.0);
+        let cents = temp.cents_from_target(880.0, 440.0).unwrap();
         assert!(
             (cents - 1200.0).abs() < 0.1,
             "Octave should be 1200 cents, got {}",
@@ -316,7 +451,7 @@ mod tests {
 
         for cents in [-50.0, -25.0, -10.0, 0.0, 10.0, 25.0, 50.0] {
             let freq = temp.cents_to_frequency(target, cents);
-            let recovered_cents = temp.cents_from_target(freq, target);
+            let recovered_cents = temp.cents_from_target(freq, target).unwrap();
 
             assert!(
                 (recovered_cents - cents).abs() < 0.01,
@@ -332,37 +467,132 @@ mod tests {
         let temp = Temperament::new();
 
         // Exact A4
-        let (midi, cents) = temp.nearest_note(440.0);
+        let (midi, cents) = temp.nearest_note(440.0).unwrap();
         assert_eq!(midi, 69);
         assert!(cents.abs() < 0.1);
 
         // A4 + 25 cents
         let freq = temp.cents_to_frequency(440.0, 25.0);
-        let (midi, cents) = temp.nearest_note(freq);
+        let (midi, cents) = temp.nearest_note(freq).unwrap();
         assert_eq!(midi, 69);
         assert!((cents - 25.0).abs() < 0.1);
 
         // Between A4 and A#4 (should round to nearest)
         let freq = temp.cents_to_frequency(440.0, 49.0);
-        let (midi, _) = temp.nearest_note(freq);
+        let (midi, _) = temp.nearest_note(freq).unwrap();
         assert_eq!(midi, 69); // Still A4
 
         let freq = temp.cents_to_frequency(440.0, 51.0);
-        let (midi, _) = temp.nearest_note(freq);
+        let (midi, _) = temp.nearest_note(freq).unwrap();
         assert_eq!(midi, 70); // A#4
     }
 
+    #[test]
+    fn test_unusable_frequencies() {
+        let temp = Temperament::new();
+
+        for freq in [0.0, -440.0, f32::NAN, f32::INFINITY] {
+            assert_eq!(temp.nearest_note(freq), None, "{}", freq);
+            assert_eq!(temp.cents_from_target(freq, 440.0), None, "{}", freq);
+            assert_eq!(temp.cents_from_target(440.0, freq), None, "{}", freq);
+            assert_eq!(temp.frequency_to_cents(freq, 69), None, "{}", freq);
+        }
+
+        // Far outside the piano: held at the ends of the MIDI range
+        let (midi, cents) = temp.nearest_note(5.0).unwrap();
+        assert_eq!(midi, 0);
+        assert!(cents.is_finite() && cents < -800.0, "{}", cents);
+        let (midi, cents) = temp.nearest_note(30_000.0).unwrap();
+        assert_eq!(midi, 127);
+        assert!(cents.is_finite() && cents > 1000.0, "{}", cents);
+    }
+
     #[test]
     fn test_frequency_to_cents() {
         let temp = Temperament::new();
 
         // A4 at exactly 440Hz
-        let cents = temp.frequency_to_cents(440.0, 69);
+        let cents = temp.frequency_to_cents(440.0, 69).unwrap();
         assert!(cents.abs() < 0.01);
 
         // A4 at 442Hz (slightly sharp)
-        let cents = temp.frequency_to_cents(442.0, 69);
+        let cents = temp.frequency_to_cents(442.0, 69).unwrap();
         assert!(cents > 0.0);
         assert!((cents - 7.85).abs() < 0.1); // ~7.85 cents sharp
     }
+
+    #[test]
+    fn test_beat_rate_equal_temperament() {
+        let temp = Temperament::new();
+
+        // C4–E4 major third beats about 10.4 times per second
+        let third = temp.beat_rate(60, 64, IntervalKind::MajorThird, None);
+        assert!((third - 10.4).abs() < 0.1, "Got {}", third);
+
+        // Fifths in the temperament octave beat slowly
+        let fifth = temp.beat_rate(60, 67, IntervalKind::Fifth, None);
+        assert!((0.6..=0.9).contains(&fifth), "Got {}", fifth);
+
+        // Pure equal-tempered octaves don't beat
+        let octave = temp.beat_rate(57, 69, IntervalKind::Octave, None);
+        assert!(octave.abs() < 1e-3, "Got {}", octave);
+    }
+
+    #[test]
+    fn test_beat_rate_with_stretch() {
+        let temp = Temperament::new();
+        let curve = StretchCurve::new();
+
+        // Stretched treble octaves are wide and beat
+        let octave = temp.beat_rate(93, 105, IntervalKind::Octave, Some(&curve));
+        assert!(octave > 1.0, "Got {}", octave);
+    }
+
+    #[test]
+    fn test_scheme_applied_to_frequency() {
+        let vallotti = Temperament::with_a4(440.0).with_scheme(TemperamentScheme::Vallotti, 0);
+        // A4 stays at the reference
+        assert!((vallotti.frequency(69) - 440.0).abs() < 1e-3);
+
+        // C4-E4 is a 392-cent third rather than 400
+        let third = vallotti
+            .cents_from_target(vallotti.frequency(64), vallotti.frequency(60))
+            .unwrap();
+        assert!((third - 392.2).abs() < 0.3, "Got {}", third);
+
+        // Readings are measured against the tempered target
+        let e4 = vallotti.frequency(64);
+        assert!(vallotti.frequency_to_cents(e4, 64).unwrap().abs() < 1e-3);
+        let (midi, cents) = vallotti.nearest_note(e4).unwrap();
+        assert_eq!(midi, 64);
+        assert!(cents.abs() < 1e-3);
+
+        // Laid from G, the narrow third is G-B
+        let from_g = vallotti.with_scheme(TemperamentScheme::Vallotti, 7);
+        assert_eq!(from_g.root(), 7);
+        let third = from_g
+            .cents_from_target(from_g.frequency(71), from_g.frequency(67))
+            .unwrap();
+        assert!((third - 392.2).abs() < 0.3, "Got {}", third);
+        assert!((from_g.frequency(69) - 440.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_transposed() {
+        // A semitone flat, every note sounds as the one below
+        let flat = Temperament::new().transposed(-100.0);
+        for (midi, freq) in REFERENCE_FREQUENCIES.iter() {
+            assert!((flat.frequency(midi + 1) - freq).abs() / freq < 1e-4);
+        }
+        // Offsets move with it
+        let vallotti = Temperament::new().with_scheme(TemperamentScheme::Vallotti, 0);
+        let ratio = vallotti.transposed(-30.0).frequency(60) / vallotti.frequency(60);
+        assert!((ratio - Temperament::cents_to_ratio(-30.0)).abs() < 1e-6);
+
+        // A piano a semitone and a bit flat
+        let (a4, transpose) = Temperament::split_transposition(412.0);
+        assert_eq!(transpose, -100.0);
+        assert!((a4 - 436.5).abs() < 0.1, "{}", a4);
+        assert_eq!(Temperament::split_transposition(437.0), (437.0, 0.0));
+    }
 }
@@ -1,29 +1,125 @@
 //! Main application state machine.
 
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::time::Duration;
 
 use crossterm::event::KeyCode;
 use ratatui::Frame;
 
-use crate::tuning::order::TuningOrder;
-use crate::tuning::session::{Session, TuningMode};
-use crate::tuning::temperament::Temperament;
+use crate::audio::beats::{BeatDetector, FalseBeatDetector};
+use crate::audio::pitch::PitchResult;
+use crate::audio::{
+    AudioDevice, Cue, Gain, NoiseFloor, NoiseSettings, Notch, PartialResult, PitchFrame, PitchLock,
+    ReferenceChannels, ReferenceFeedback, ReferenceTone, ToneCanceller, Waveform,
+};
+use crate::clock::{Clock, SystemClock};
+use crate::hook::{HookRun, SessionHook};
+use crate::log::{Level, Logger};
+use crate::tuning::capture::TargetCapture;
+use crate::tuning::interval::IntervalChecker;
+use crate::tuning::order::{StringOrder, TuningOrder};
+use crate::tuning::pitch_raise;
+use crate::tuning::session::{CompletedNote, Session, TuningMode};
+use crate::tuning::temperament::{Temperament, A4_RANGE, DEFAULT_A4_PRESETS};
+use crate::tuning::{
+    CustomTemperament, Instrument, OctaveType, OrderStrategy, PianoType, StretchCurve,
+    StretchProfile, TargetOverrides, TemperamentScheme, TuningPlan,
+};
 
+use super::components::{CurveChart, CurveOverlay, DeviceLostOverlay, IdleOverlay, Readout};
 use super::screens::{
-    mode_select::SelectedMode, CalibrationScreen, CompleteScreen, ModeSelectScreen, TuningScreen,
+    device_select::DeviceKind, mode_select::SelectedMode, CalibrationScreen, CompleteScreen,
+    DeviceSelectScreen, HistoryScreen, IntervalCheckScreen, MeasureScreen, MeasureSummaryScreen,
+    ModeSelectScreen, NoiseStatus, ReferenceStatus, SetupScreen, TuningScreen,
 };
+use super::strings;
+
+/// Progress of a room noise measurement.
+#[derive(Debug, Clone, Copy)]
+enum NoiseCheck {
+    /// Collecting input since the given time.
+    Measuring(Duration),
+    /// Measured, waiting for the suggested settings to be accepted.
+    Measured(NoiseFloor),
+}
 
 /// Application screen state.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AppState {
     /// Mode selection screen.
     ModeSelect,
+    /// A4, temperament and stretch for the new session.
+    Setup,
     /// Calibration (for quick tune).
     Calibration,
     /// Main tuning screen.
     Tuning,
     /// Session complete.
     Complete,
+    /// Measurement pass over the whole piano.
+    Measure,
+    /// Measurement results and choice of what to do next.
+    MeasureSummary,
+    /// Interval checks after tuning.
+    IntervalCheck,
+    /// Audio device selection.
+    DeviceSelect,
+    /// Past sessions of a piano.
+    History,
+}
+
+/// Change to the reference tone, carried out by the main loop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReferenceRequest {
+    /// Play the tone at this frequency, fading over from one already
+    /// playing.
+    Play(f32),
+    /// Play the tone at `target` with the octave below at `lower`, for
+    /// checking the octave by ear.
+    PlayOctave { target: f32, lower: f32 },
+    /// Play the tone at `target` beating against a second voice `cents`
+    /// away, to hear what a beat rate sounds like.
+    PlayBeats { target: f32, cents: f32 },
+    /// Fade out the tone playing.
+    Stop,
+}
+
+/// What the reference tone plays with the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReferenceKind {
+    /// The target alone.
+    Target,
+    /// The octave below as well.
+    Octave,
+    /// A second voice detuned from the target, beating against it.
+    Beats,
+}
+
+/// The reference tone the app has asked for.
+#[derive(Debug, Clone, Copy)]
+struct Reference {
+    /// Frequency of the target.
+    freq: f32,
+    kind: ReferenceKind,
+    /// Frequency of the octave below sounding with it, if any.
+    lower: Option<f32>,
+    /// When it stops by itself, if it does.
+    ends: Option<Duration>,
+}
+
+/// Audio device change requested by the user, carried out by the main loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceRequest {
+    /// Enumerate the devices again.
+    Rescan,
+    /// Reopen capture on the named input device.
+    OpenInput(String),
+    /// Enumerate the devices again and reopen capture on the default
+    /// input, after the input was lost.
+    ReopenDefaultInput,
+    /// Switch to the named output device.
+    OpenOutput(String),
 }
 
 /// Main application.
@@ -34,36 +130,601 @@ pub struct App {
     session: Option<Session>,
     /// Should quit flag.
     should_quit: bool,
+    /// When the last interrupt arrived.
+    last_interrupt: Option<Duration>,
+    /// Exit immediately, skipping the remaining shutdown.
+    force_quit: bool,
+    /// When the process was suspended to the shell, while it is.
+    suspended_at: Option<Duration>,
+    /// The terminal must be redrawn in full.
+    redraw_requested: bool,
     /// Mode select screen.
     mode_select: ModeSelectScreen,
+    /// Setup screen for the new session.
+    setup: SetupScreen,
     /// Calibration screen.
     calibration: CalibrationScreen,
     /// Tuning screen (created when tuning starts).
     tuning: Option<TuningScreen>,
     /// Complete screen (created when session ends).
     complete: Option<CompleteScreen>,
+    /// Measure screen (created for each note of a measurement pass).
+    measure: Option<MeasureScreen>,
+    /// Measurement summary screen (created when a measurement pass ends).
+    measure_summary: Option<MeasureSummaryScreen>,
+    /// Tuning history screen (created when opened from mode select).
+    history: Option<HistoryScreen>,
+    /// Finished measurement session, used to seed the next session.
+    measurement: Option<Session>,
+    /// Interval check screen (created when checks start).
+    interval_check: Option<IntervalCheckScreen>,
+    /// Audio device selection screen.
+    device_select: DeviceSelectScreen,
+    /// State to return to when leaving device selection.
+    device_return: AppState,
+    /// Device change waiting for the main loop.
+    device_request: Option<DeviceRequest>,
     /// Tuning order.
     tuning_order: TuningOrder,
     /// Temperament calculator.
     temperament: Temperament,
     /// Current note index in tuning order.
     current_note_idx: usize,
+    /// Time source.
+    clock: Box<dyn Clock>,
+    /// False-beat detector for the current note.
+    false_beats: FalseBeatDetector,
+    /// Hysteresis between showing a reading and listening.
+    pitch_lock: PitchLock,
+    /// Confidence below which a frame counts as no pitch.
+    min_confidence: f32,
+    /// Input level (RMS) above which audio counts as activity.
+    noise_gate: f32,
+    /// App clock time at which the first sample of the input was captured.
+    capture_epoch: Option<Duration>,
+    /// Capture time of the latest detection.
+    last_capture: Duration,
+    /// Room noise measurement on the calibration screen.
+    noise_check: Option<NoiseCheck>,
+    /// Accepted room noise settings waiting for the main loop to save.
+    noise_request: Option<NoiseSettings>,
+    /// Stretch curve with changed target overrides waiting for the main
+    /// loop to save.
+    profile_request: Option<StretchProfile>,
+    /// A4 presets offered on calibration and mode select.
+    a4_presets: Vec<f32>,
+    /// Order in which trichord strings are tuned.
+    string_order: StringOrder,
+    /// How long the last stable reading is held after a note decays.
+    hold_time: Duration,
+    /// Format of the value below the meter.
+    readout: Readout,
+    /// How detection handles the reference tone.
+    reference_feedback: ReferenceFeedback,
+    /// Where the reference tone is played.
+    reference_channels: ReferenceChannels,
+    /// Waveform of the reference tone.
+    waveform: Waveform,
+    /// Whether low reference notes are reinforced with their partials.
+    bass_reinforcement: bool,
+    /// Reference tone playing.
+    reference: Option<Reference>,
+    /// Time after which the reference tone stops by itself; zero to play
+    /// until stopped.
+    reference_timeout: Duration,
+    /// Reference tone change waiting for the main loop.
+    reference_request: Option<ReferenceRequest>,
+    /// Length of the reference tone's fade in and out.
+    reference_fade: Duration,
+    /// Volume of the reference tone, from 0 to 1.
+    reference_volume: f32,
+    /// Detuning of the beat demonstration's second voice in cents.
+    beat_cents: f32,
+    /// Whether to play audible cues on lock and confirm.
+    beep: bool,
+    /// Whether the microphone input is being recorded.
+    recording: bool,
+    /// When the input last clipped.
+    clipped_at: Option<Duration>,
+    /// Why the input device was lost, until capture is reopened.
+    input_lost: Option<String>,
+    /// Whether the stretch curve overlay is open.
+    curve_view: bool,
+    /// Software gain on the input in dB.
+    gain_db: f32,
+    /// Cue waiting for the main loop to play.
+    cue_request: Option<Cue>,
+    /// Whether confirming a sounding step requires steady readings.
+    require_steady: bool,
+    /// MIDI note below which pitch is measured via the partials.
+    partials_below: u8,
+    /// Target being captured from another instrument.
+    capture: Option<TargetCapture>,
+    /// Whether a captured target also sets A4 for the rest of the piano.
+    capture_sets_a4: bool,
+    /// Inactivity before going idle (zero disables idling).
+    idle_timeout: Duration,
+    /// Whether idle time is excluded from the session duration.
+    pause_when_idle: bool,
+    /// Time of the last key press or audible input.
+    last_activity: Duration,
+    /// Command run when a session completes.
+    session_hook: Option<SessionHook>,
+    /// Name of the piano, recorded in new sessions.
+    piano_name: Option<String>,
+    /// Cents new sessions are transposed by, from setup or calibration.
+    transpose: f32,
+    /// Octave type new sessions work the stretch out at.
+    octave_type: OctaveType,
+    /// Inharmonicity per MIDI note for new sessions.
+    inharmonicity: BTreeMap<u8, f32>,
+    /// Session hook command still running.
+    hook_run: Option<HookRun>,
+    /// Debug log.
+    logger: Logger,
 }
 
 impl App {
+    /// Default inactivity before going idle.
+    pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+    /// Default input level (RMS) above which audio counts as activity.
+    pub const NOISE_FLOOR: f32 = 0.01;
+
+    /// Clarity below which a detection is treated as no reading.
+    pub const MIN_CLARITY: f32 = 0.5;
+
+    /// Input analyzed for beats between strings tuned to unison.
+    pub const BEAT_WINDOW: Duration = Duration::from_secs(2);
+
+    /// Default time after which the reference tone stops by itself.
+    pub const DEFAULT_REFERENCE_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Manual target adjustment per key press, in cents.
+    pub const TARGET_STEP: f32 = 0.5;
+
+    /// Target override change per key press, in cents.
+    pub const OVERRIDE_STEP: f32 = 1.0;
+
+    /// A second interrupt within this time forces an immediate exit.
+    pub const FORCE_QUIT_WINDOW: Duration = Duration::from_secs(1);
+
+    /// Notes below C1 are measured via their partials by default.
+    pub const DEFAULT_PARTIALS_BELOW: u8 = 24;
+
+    /// Most a detection's capture time may trail the clock before the
+    /// sample count is anchored afresh.
+    pub const MAX_CAPTURE_LAG: Duration = Duration::from_secs(1);
+
+    /// Input gain change per key press, in dB.
+    pub const GAIN_STEP_DB: f32 = 2.0;
+
+    /// Reference volume change per key press.
+    pub const VOLUME_STEP: f32 = 0.1;
+
+    /// Detuning the beat demonstration starts at, in cents.
+    pub const DEFAULT_BEAT_CENTS: f32 = 5.0;
+
+    /// Most the beat demonstration is detuned by, in cents.
+    pub const MAX_BEAT_CENTS: f32 = 50.0;
+
+    /// How long the clipping warning stays up after the input last clipped.
+    pub const CLIP_HOLD: Duration = Duration::from_secs(1);
+
     /// Create a new application.
     pub fn new() -> Self {
         Self {
             state: AppState::ModeSelect,
             session: None,
             should_quit: false,
+            last_interrupt: None,
+            force_quit: false,
+            suspended_at: None,
+            redraw_requested: false,
             mode_select: ModeSelectScreen::new(),
+            setup: SetupScreen::new(),
             calibration: CalibrationScreen::new(),
             tuning: None,
             complete: None,
+            measure: None,
+            measure_summary: None,
+            history: None,
+            measurement: None,
+            interval_check: None,
+            device_select: DeviceSelectScreen::new(),
+            device_return: AppState::ModeSelect,
+            device_request: None,
             tuning_order: TuningOrder::new(),
             temperament: Temperament::new(),
             current_note_idx: 0,
+            clock: Box::new(SystemClock::new()),
+            false_beats: FalseBeatDetector::new(),
+            // Readings pass straight through unless configured
+            pitch_lock: PitchLock::new(1, 1).with_min_confidence(0.0),
+            min_confidence: PitchLock::DEFAULT_MIN_CONFIDENCE,
+            noise_gate: Self::NOISE_FLOOR,
+            noise_check: None,
+            noise_request: None,
+            profile_request: None,
+            capture_epoch: None,
+            last_capture: Duration::ZERO,
+            recording: false,
+            clipped_at: None,
+            input_lost: None,
+            curve_view: false,
+            gain_db: 0.0,
+            a4_presets: DEFAULT_A4_PRESETS.to_vec(),
+            string_order: StringOrder::default(),
+            hold_time: TuningScreen::DEFAULT_HOLD_TIME,
+            readout: Readout::default(),
+            reference_feedback: ReferenceFeedback::default(),
+            reference_channels: ReferenceChannels::default(),
+            waveform: Waveform::default(),
+            bass_reinforcement: true,
+            reference: None,
+            reference_timeout: Self::DEFAULT_REFERENCE_TIMEOUT,
+            reference_request: None,
+            reference_fade: ReferenceTone::DEFAULT_FADE,
+            reference_volume: 1.0,
+            beat_cents: Self::DEFAULT_BEAT_CENTS,
+            beep: false,
+            cue_request: None,
+            require_steady: false,
+            partials_below: Self::DEFAULT_PARTIALS_BELOW,
+            capture: None,
+            capture_sets_a4: false,
+            idle_timeout: Self::DEFAULT_IDLE_TIMEOUT,
+            pause_when_idle: false,
+            last_activity: Duration::ZERO,
+            session_hook: None,
+            piano_name: None,
+            transpose: 0.0,
+            octave_type: OctaveType::default(),
+            inharmonicity: BTreeMap::new(),
+            hook_run: None,
+            logger: Logger::disabled(),
+        }
+    }
+
+    /// Use the given A4 presets on the calibration and setup screens.
+    pub fn with_a4_presets(mut self, presets: Vec<f32>) -> Self {
+        self.setup = std::mem::take(&mut self.setup).with_a4_presets(presets.clone());
+        self.a4_presets = presets;
+        self.calibration = self.new_calibration();
+        self
+    }
+
+    /// Tune trichord strings in the given order.
+    pub fn with_string_order(mut self, order: StringOrder) -> Self {
+        self.string_order = order;
+        if self.tuning.is_some() {
+            self.setup_current_note();
+        }
+        self
+    }
+
+    /// Hold the last stable reading for the given time after a note decays.
+    pub fn with_hold_time(mut self, hold_time: Duration) -> Self {
+        self.hold_time = hold_time;
+        if self.tuning.is_some() {
+            self.setup_current_note();
+        }
+        self
+    }
+
+    /// Show a reading after `acquire` confident frames and drop it after
+    /// `release` missed ones.
+    pub fn with_pitch_lock(mut self, acquire: usize, release: usize) -> Self {
+        self.pitch_lock = PitchLock::new(acquire, release).with_min_confidence(self.min_confidence);
+        self
+    }
+
+    /// Set the confidence below which a frame counts as no pitch.
+    pub fn with_min_confidence(mut self, confidence: f32) -> Self {
+        self.min_confidence = confidence;
+        self.pitch_lock.set_min_confidence(confidence);
+        self
+    }
+
+    /// Set the input level (RMS) above which audio counts as activity.
+    pub fn with_noise_gate(mut self, level: f32) -> Self {
+        self.noise_gate = level;
+        self
+    }
+
+    /// Preselect an instrument on mode select, with a temperament instead of
+    /// its default on setup.
+    pub fn with_instrument(
+        mut self,
+        instrument: Instrument,
+        temperament: Option<TemperamentScheme>,
+    ) -> Self {
+        self.mode_select = std::mem::take(&mut self.mode_select).with_instrument(instrument);
+        self.setup = std::mem::take(&mut self.setup).with_instrument(instrument, temperament);
+        self
+    }
+
+    /// Offer custom temperaments on setup, preselecting `selected` if
+    /// given.
+    pub fn with_custom_temperaments(
+        mut self,
+        customs: Vec<CustomTemperament>,
+        selected: Option<CustomTemperament>,
+    ) -> Self {
+        self.setup = std::mem::take(&mut self.setup)
+            .with_custom_temperaments(customs)
+            .with_custom_temperament(selected);
+        self
+    }
+
+    /// Offer saved stretch curves after the piano types on setup, with one
+    /// preselected.
+    pub fn with_stretch_profiles(
+        mut self,
+        profiles: Vec<StretchProfile>,
+        selected: Option<StretchProfile>,
+    ) -> Self {
+        self.setup = std::mem::take(&mut self.setup)
+            .with_stretch_profiles(profiles)
+            .with_stretch_profile(selected);
+        self
+    }
+
+    /// Show the value below the meter in the given unit and precision.
+    pub fn with_readout(mut self, readout: Readout) -> Self {
+        self.readout = readout;
+        if self.tuning.is_some() {
+            self.setup_current_note();
+        }
+        self
+    }
+
+    /// Handle the reference tone leaking into the microphone this way.
+    pub fn with_reference_feedback(mut self, feedback: ReferenceFeedback) -> Self {
+        self.reference_feedback = feedback;
+        self
+    }
+
+    /// Play the reference tone in both ears, or split against the detected pitch.
+    pub fn with_reference_channels(mut self, channels: ReferenceChannels) -> Self {
+        self.reference_channels = channels;
+        self
+    }
+
+    /// Play the reference tone with this waveform.
+    pub fn with_waveform(mut self, waveform: Waveform) -> Self {
+        self.waveform = waveform;
+        if let Some(tuning) = &mut self.tuning {
+            tuning.set_waveform(waveform);
+        }
+        self
+    }
+
+    /// Stop the reference tone by itself `timeout` after it starts; zero
+    /// plays it until stopped.
+    pub fn with_reference_timeout(mut self, timeout: Duration) -> Self {
+        self.reference_timeout = timeout;
+        self
+    }
+
+    /// Fade the reference tone in and out over `fade`.
+    pub fn with_reference_fade(mut self, fade: Duration) -> Self {
+        self.reference_fade = fade;
+        self
+    }
+
+    /// Play the reference tone at `volume`, from 0 to 1.
+    pub fn with_reference_volume(mut self, volume: f32) -> Self {
+        self.reference_volume = volume.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Reinforce low reference notes with their partials, or play them
+    /// as they are.
+    pub fn with_bass_reinforcement(mut self, enabled: bool) -> Self {
+        self.bass_reinforcement = enabled;
+        self
+    }
+
+    /// Only confirm a sounding step once the readings are steady.
+    pub fn with_require_steady(mut self, require: bool) -> Self {
+        self.require_steady = require;
+        self
+    }
+
+    /// Play audible cues when a note locks in tune and on confirm.
+    pub fn with_beep(mut self, beep: bool) -> Self {
+        self.beep = beep;
+        self
+    }
+
+    /// Measure notes below the given MIDI note via their partials.
+    pub fn with_partials_below(mut self, midi: u8) -> Self {
+        self.partials_below = midi;
+        self
+    }
+
+    /// Derive A4 from a target captured from another instrument.
+    pub fn with_capture_sets_a4(mut self, sets_a4: bool) -> Self {
+        self.capture_sets_a4 = sets_a4;
+        self
+    }
+
+    /// Amplify the input by `db` decibels.
+    pub fn with_gain_db(mut self, db: f32) -> Self {
+        self.set_gain_db(db);
+        self
+    }
+
+    /// Go idle after the given inactivity (zero disables idling).
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Exclude idle time from the session duration.
+    pub fn with_pause_when_idle(mut self, pause: bool) -> Self {
+        self.pause_when_idle = pause;
+        self
+    }
+
+    /// Run the given hook when a session completes.
+    pub fn with_session_hook(mut self, hook: Option<SessionHook>) -> Self {
+        self.session_hook = hook;
+        self
+    }
+
+    /// Record new sessions as tuning the named piano.
+    pub fn with_piano_name(mut self, name: Option<String>) -> Self {
+        self.piano_name = name;
+        self
+    }
+
+    /// Lay the temperament scheme of new sessions from the root pitch
+    /// class `root` (C = 0).
+    pub fn with_temperament_root(mut self, root: u8) -> Self {
+        self.setup = std::mem::take(&mut self.setup).with_root(root);
+        self
+    }
+
+    /// Preselect a transposition of every target by `cents` on setup.
+    pub fn with_transpose(mut self, cents: f32) -> Self {
+        self.setup = std::mem::take(&mut self.setup).with_transpose(cents);
+        self
+    }
+
+    /// Tune the keys of new sessions in the sequence of `strategy`.
+    pub fn with_order_strategy(mut self, strategy: OrderStrategy) -> Self {
+        self.setup = std::mem::take(&mut self.setup).with_order_strategy(strategy);
+        self
+    }
+
+    /// Tune the octaves of new sessions beatless at the partials of
+    /// `octave_type`, when the stretch is worked out from the inharmonicity.
+    pub fn with_octave_type(mut self, octave_type: OctaveType) -> Self {
+        self.octave_type = octave_type;
+        self
+    }
+
+    /// Work the stretch of new sessions out from the inharmonicity
+    /// measured per MIDI note.
+    pub fn with_inharmonicity(mut self, inharmonicity: BTreeMap<u8, f32>) -> Self {
+        self.inharmonicity = inharmonicity;
+        self
+    }
+
+    /// Tune new sessions to the instrument's stretch curve.
+    pub fn with_stretch(mut self, stretch: bool) -> Self {
+        self.setup = std::mem::take(&mut self.setup).with_stretch(stretch);
+        self
+    }
+
+    /// Preselect the stretch intensity on setup (1.0 for the default
+    /// curve).
+    pub fn with_stretch_intensity(mut self, intensity: f32) -> Self {
+        self.setup = std::mem::take(&mut self.setup).with_stretch_intensity(intensity);
+        self
+    }
+
+    /// Preselect the piano type whose stretch preset is followed on setup.
+    pub fn with_piano_type(mut self, piano_type: PianoType) -> Self {
+        self.setup = std::mem::take(&mut self.setup).with_piano_type(piano_type);
+        self
+    }
+
+    /// Write events to the given logger.
+    pub fn with_logger(mut self, logger: Logger) -> Self {
+        self.logger = logger;
+        self.log(Level::Info, "start", &[]);
+        self
+    }
+
+    /// Use the given clock as the time source.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self.last_activity = self.clock.now();
+        self
+    }
+
+    /// Whether the app is idle: no key input or audible input for the
+    /// idle timeout.
+    pub fn is_idle(&self) -> bool {
+        !self.idle_timeout.is_zero()
+            && self.clock.now().saturating_sub(self.last_activity) >= self.idle_timeout
+    }
+
+    /// Record activity, waking from idle.
+    fn mark_activity(&mut self) {
+        let now = self.clock.now();
+        if self.is_idle() && self.pause_when_idle {
+            if let Some(session) = &mut self.session {
+                let idle_since = self.last_activity + self.idle_timeout;
+                session.paused_secs += now.saturating_sub(idle_since).as_secs();
+            }
+        }
+        self.last_activity = now;
+    }
+
+    /// Change state, logging the transition.
+    fn set_state(&mut self, state: AppState) {
+        if state != self.state {
+            let from = format!("{:?}", self.state);
+            let to = format!("{:?}", state);
+            self.state = state;
+            self.log(Level::Info, "state_change", &[("from", &from), ("to", &to)]);
+        }
+    }
+
+    /// Log an event with the current note and step for context.
+    fn log(&self, level: Level, event: &str, fields: &[(&str, &dyn std::fmt::Display)]) {
+        if !self.logger.enabled(level) {
+            return;
+        }
+
+        let state = format!("{:?}", self.state);
+        let note = self
+            .tuning_order
+            .note_at(self.current_note_idx)
+            .filter(|_| matches!(self.state, AppState::Tuning | AppState::Measure))
+            .map(|n| n.display_name());
+        let step = self
+            .tuning
+            .as_ref()
+            .filter(|_| self.state == AppState::Tuning)
+            .and_then(|t| t.tuning_step())
+            .map(|s| s.title());
+
+        let mut all: Vec<(&str, &dyn std::fmt::Display)> = vec![("state", &state)];
+        if let Some(note) = &note {
+            all.push(("note", note));
+        }
+        if let Some(step) = &step {
+            all.push(("step", step));
+        }
+        all.extend_from_slice(fields);
+        self.logger.log(level, event, &all);
+    }
+
+    /// Log a pitch detector result.
+    pub fn log_pitch(&self, result: &PitchResult) {
+        self.log(
+            Level::Debug,
+            "pitch",
+            &[
+                ("freq", &format!("{:.2}", result.frequency)),
+                ("confidence", &format!("{:.2}", result.confidence)),
+                ("tau", &format!("{:.2}", result.tau)),
+                ("rms", &format!("{:.3}", result.rms)),
+                ("clarity", &format!("{:.2}", result.clarity)),
+                ("ambiguous", &result.ambiguous),
+            ],
+        );
+    }
+
+    /// Save the current session, logging failures.
+    fn save_session(&self) {
+        if let Some(Err(e)) = self.session.as_ref().map(|s| s.save()) {
+            self.log(Level::Error, "session_save_failed", &[("error", &e)]);
         }
     }
 
@@ -72,12 +733,102 @@ impl App {
         let mut app = Self::new();
         app.current_note_idx = session.current_note_index;
         app.temperament = Temperament::with_a4(session.a4_reference);
-        app.session = Some(session);
-        app.state = AppState::Tuning;
-        app.setup_current_note();
+        app.transpose = session.transpose_cents;
+        app.mode_select = ModeSelectScreen::new().with_instrument(session.instrument);
+        app.setup = SetupScreen::new()
+            .with_a4(session.a4_reference)
+            .with_instrument(session.instrument, Some(session.temperament))
+            .with_root(session.temperament_root)
+            .with_custom_temperament(session.custom_temperament.clone())
+            .with_stretch(session.stretch)
+            .with_stretch_intensity(session.stretch_intensity)
+            .with_piano_type(session.piano_type)
+            .with_stretch_profile(session.stretch_profile.clone())
+            .with_order_strategy(session.order_strategy)
+            .with_transpose(session.transpose_cents);
+        if session.mode == TuningMode::Measurement {
+            app.set_session(session);
+            app.set_state(AppState::Measure);
+            app.setup_current_measure();
+        } else {
+            app.set_session(session);
+            app.set_state(AppState::Tuning);
+            app.setup_current_note();
+        }
         app
     }
 
+    /// Show the past sessions, starting with the configured piano.
+    fn open_history(&mut self) {
+        let pianos = Session::pianos().unwrap_or_else(|e| {
+            self.log(Level::Error, "history_failed", &[("error", &e)]);
+            Vec::new()
+        });
+        self.history = Some(HistoryScreen::new(pianos).with_piano(self.piano_name.as_deref()));
+        self.load_history();
+        self.set_state(AppState::History);
+    }
+
+    /// List the sessions of the piano shown in the history.
+    fn load_history(&mut self) {
+        let Some(piano) = self
+            .history
+            .as_ref()
+            .and_then(|h| h.piano())
+            .map(str::to_string)
+        else {
+            return;
+        };
+        let sessions = Session::list(&piano).unwrap_or_else(|e| {
+            self.log(Level::Error, "history_failed", &[("error", &e)]);
+            Vec::new()
+        });
+        if let Some(history) = &mut self.history {
+            history.set_sessions(sessions);
+        }
+    }
+
+    /// Get the history screen.
+    pub fn history(&self) -> Option<&HistoryScreen> {
+        self.history.as_ref()
+    }
+
+    /// Make `session` the current session, tuning its instrument's range.
+    fn set_session(&mut self, session: Session) {
+        self.tuning_order = session.tuning_order();
+        self.session = Some(session);
+    }
+
+    /// A new session for the instrument, temperament and stretch chosen on
+    /// setup.
+    fn new_session(&self, mode: TuningMode) -> Session {
+        Session::new(mode, self.temperament.a4())
+            .with_instrument(self.setup.instrument(), self.setup.temperament())
+            .with_temperament_root(self.setup.root())
+            .with_custom_temperament(self.setup.custom_temperament().cloned())
+            .with_stretch(self.setup.stretch())
+            .with_stretch_intensity(self.setup.stretch_intensity())
+            .with_piano_type(self.setup.piano_type())
+            .with_stretch_profile(self.setup.stretch_profile().cloned())
+            .with_octave_type(self.octave_type)
+            .with_inharmonicity(self.inharmonicity.clone())
+            .with_order_strategy(self.setup.order_strategy())
+            .with_transpose(self.transpose)
+            .with_piano(self.piano_name.clone())
+    }
+
+    /// A fresh calibration screen with the configured presets and gain.
+    fn new_calibration(&self) -> CalibrationScreen {
+        let mut calibration = CalibrationScreen::new().with_presets(self.a4_presets.clone());
+        calibration.set_gain_db(self.gain_db);
+        calibration
+    }
+
+    /// A fresh mode select screen keeping the chosen instrument.
+    fn new_mode_select(&self) -> ModeSelectScreen {
+        ModeSelectScreen::new().with_instrument(self.mode_select.instrument())
+    }
+
     /// Get current state.
     pub fn state(&self) -> AppState {
         self.state
@@ -93,6 +844,73 @@ impl App {
         self.should_quit = true;
     }
 
+    /// Handle Ctrl+C or a termination signal: save the session and quit as
+    /// 'q' does. A repeated interrupt within [`Self::FORCE_QUIT_WINDOW`]
+    /// requests an immediate exit instead.
+    pub fn interrupt(&mut self) {
+        let now = self.clock.now();
+        if self
+            .last_interrupt
+            .is_some_and(|at| now.saturating_sub(at) < Self::FORCE_QUIT_WINDOW)
+        {
+            self.log(Level::Warn, "force_quit", &[]);
+            self.force_quit = true;
+            return;
+        }
+
+        self.log(Level::Info, "interrupted", &[]);
+        self.last_interrupt = Some(now);
+        self.save_session();
+        self.quit();
+    }
+
+    /// Check if the app was interrupted rather than quit normally.
+    pub fn was_interrupted(&self) -> bool {
+        self.last_interrupt.is_some()
+    }
+
+    /// Check if a repeated interrupt asked to exit immediately.
+    pub fn should_force_quit(&self) -> bool {
+        self.force_quit
+    }
+
+    /// Prepare to be suspended to the shell: stop the reference tone and
+    /// start excluding time from the session.
+    pub fn suspend(&mut self) {
+        self.mark_activity();
+        self.reference = None;
+        self.reference_request = None;
+        self.suspended_at = Some(self.clock.now());
+        self.log(Level::Info, "suspended", &[]);
+    }
+
+    /// Continue after a suspend. The suspended time doesn't count toward
+    /// the session duration or the idle timeout.
+    pub fn resume(&mut self) {
+        let Some(since) = self.suspended_at.take() else {
+            return;
+        };
+        let now = self.clock.now();
+        let suspended = now.saturating_sub(since);
+        if let Some(session) = &mut self.session {
+            session.paused_secs += suspended.as_secs();
+        }
+        self.last_activity = now;
+        self.redraw_requested = true;
+        self.clear_pitch();
+        self.log(Level::Info, "resumed", &[("secs", &suspended.as_secs())]);
+    }
+
+    /// Whether audio is paused while suspended.
+    pub fn is_suspended(&self) -> bool {
+        self.suspended_at.is_some()
+    }
+
+    /// Take a pending request to redraw the whole terminal.
+    pub fn take_redraw_request(&mut self) -> bool {
+        std::mem::take(&mut self.redraw_requested)
+    }
+
     /// Get current session.
     pub fn session(&self) -> Option<&Session> {
         self.session.as_ref()
@@ -103,28 +921,71 @@ impl App {
         self.session.as_mut()
     }
 
+    /// Get the finished measurement session, if any.
+    pub fn measurement(&self) -> Option<&Session> {
+        self.measurement.as_ref()
+    }
+
     /// Get target frequency for current note.
     pub fn current_target_freq(&self) -> Option<f32> {
         self.tuning.as_ref().map(|t| t.target_freq())
     }
 
+    /// Get the pitch currently detected on the tuning screen.
+    pub fn detected_freq(&self) -> Option<f32> {
+        self.tuning.as_ref().and_then(|t| t.detected_freq())
+    }
+
     /// Handle key press event.
     pub fn handle_key(&mut self, key: KeyCode) {
+        // A key press only wakes the app when idle
+        let was_idle = self.is_idle();
+        self.mark_activity();
+        if self.input_lost.is_some() {
+            self.handle_input_lost_key(key);
+            return;
+        }
+        if was_idle {
+            return;
+        }
+        if self.curve_view {
+            self.handle_curve_view_key(key);
+            return;
+        }
+
         match self.state {
             AppState::ModeSelect => self.handle_mode_select_key(key),
+            AppState::Setup => self.handle_setup_key(key),
             AppState::Calibration => self.handle_calibration_key(key),
             AppState::Tuning => self.handle_tuning_key(key),
             AppState::Complete => self.handle_complete_key(key),
+            AppState::Measure => self.handle_measure_key(key),
+            AppState::MeasureSummary => self.handle_measure_summary_key(key),
+            AppState::IntervalCheck => self.handle_interval_check_key(key),
+            AppState::DeviceSelect => self.handle_device_select_key(key),
+            AppState::History => self.handle_history_key(key),
         }
     }
 
     fn handle_mode_select_key(&mut self, key: KeyCode) {
         match key {
-            KeyCode::Up | KeyCode::Down | KeyCode::Tab => {
+            KeyCode::Up => {
+                self.mode_select.prev();
+            }
+            KeyCode::Down | KeyCode::Tab => {
                 self.mode_select.next();
             }
             KeyCode::Enter => {
-                self.start_session();
+                self.open_setup();
+            }
+            KeyCode::Char('i') | KeyCode::Char('I') => {
+                self.mode_select.next_instrument();
+            }
+            KeyCode::Char('h') | KeyCode::Char('H') => {
+                self.open_history();
+            }
+            KeyCode::Char('d') | KeyCode::Char('D') => {
+                self.open_device_select();
             }
             KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
                 self.quit();
@@ -133,13 +994,76 @@ impl App {
         }
     }
 
+    fn handle_setup_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Up => {
+                self.setup.prev_field();
+            }
+            KeyCode::Down | KeyCode::Tab => {
+                self.setup.next_field();
+            }
+            KeyCode::Left => {
+                self.setup.adjust(false);
+            }
+            KeyCode::Right => {
+                self.setup.adjust(true);
+            }
+            KeyCode::Backspace => {
+                self.setup.a4_mut().backspace();
+            }
+            KeyCode::Char(c) if c.is_ascii_digit() || c == '.' => {
+                self.setup.a4_mut().push_char(c);
+            }
+            KeyCode::Char('p') | KeyCode::Char('P') => {
+                self.setup.a4_mut().next_preset();
+            }
+            KeyCode::Char('t') | KeyCode::Char('T') => {
+                self.setup.next_temperament();
+            }
+            KeyCode::Char('k') | KeyCode::Char('K') => {
+                self.setup.next_root();
+            }
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                self.setup.toggle_stretch();
+            }
+            KeyCode::Char('v') | KeyCode::Char('V') => {
+                self.curve_view = true;
+            }
+            KeyCode::Enter => {
+                self.setup.a4_mut().commit();
+                self.start_session();
+            }
+            KeyCode::Char('b') | KeyCode::Char('B') | KeyCode::Esc => {
+                self.set_state(AppState::ModeSelect);
+            }
+            KeyCode::Char('q') | KeyCode::Char('Q') => {
+                self.quit();
+            }
+            _ => {}
+        }
+    }
+
     fn handle_calibration_key(&mut self, key: KeyCode) {
+        if self.noise_check.is_some() {
+            self.handle_noise_check_key(key);
+            return;
+        }
         match key {
+            KeyCode::Char(c) if c.is_ascii_digit() => {
+                // Preset A4 instead of listening
+                if let Some(a4) = c.to_digit(10).and_then(|d| self.calibration.preset(d)) {
+                    self.temperament = Temperament::with_a4(a4);
+                    self.start_tuning();
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') => self.start_noise_check(),
             KeyCode::Char('s') | KeyCode::Char('S') => {
                 // Skip calibration, use 440 Hz
                 self.temperament = Temperament::new();
                 self.start_tuning();
             }
+            KeyCode::F(3) => self.set_gain_db(self.gain_db - Self::GAIN_STEP_DB),
+            KeyCode::F(4) => self.set_gain_db(self.gain_db + Self::GAIN_STEP_DB),
             KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
                 self.quit();
             }
@@ -147,6 +1071,20 @@ impl App {
         }
     }
 
+    fn handle_noise_check_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+                if let Some(NoiseCheck::Measured(floor)) = self.noise_check {
+                    self.set_noise_settings(floor.suggest());
+                    self.set_noise_check(None);
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => self.set_noise_check(None),
+            KeyCode::Char('q') | KeyCode::Char('Q') => self.quit(),
+            _ => {}
+        }
+    }
+
     fn handle_tuning_key(&mut self, key: KeyCode) {
         match key {
             KeyCode::Char(' ') => {
@@ -161,278 +1099,4391 @@ impl App {
                 // Toggle piano progress display
                 self.toggle_piano_progress();
             }
+            KeyCode::Char('d') | KeyCode::Char('D') => {
+                self.open_device_select();
+            }
+            KeyCode::Char('r') | KeyCode::Char('R') => {
+                self.toggle_reference();
+            }
+            KeyCode::Char('o') | KeyCode::Char('O') => {
+                self.toggle_octave_reference();
+            }
+            KeyCode::Char('x') | KeyCode::Char('X') => {
+                self.toggle_beat_demo();
+            }
+            KeyCode::Up => self.adjust_beat_cents(1.0),
+            KeyCode::Down => self.adjust_beat_cents(-1.0),
+            KeyCode::Char('w') | KeyCode::Char('W') => {
+                self.cycle_waveform();
+            }
+            KeyCode::Char('[') if self.playing_reference() => {
+                self.set_reference_volume(self.reference_volume - Self::VOLUME_STEP);
+            }
+            KeyCode::Char(']') if self.playing_reference() => {
+                self.set_reference_volume(self.reference_volume + Self::VOLUME_STEP);
+            }
+            KeyCode::Char('<') | KeyCode::Char(',') => {
+                self.adjust_target(Some(-Self::TARGET_STEP));
+            }
+            KeyCode::Char('>') | KeyCode::Char('.') => {
+                self.adjust_target(Some(Self::TARGET_STEP));
+            }
+            KeyCode::Char('-') => self.adjust_override(-Self::OVERRIDE_STEP),
+            KeyCode::Char('+') => self.adjust_override(Self::OVERRIDE_STEP),
+            KeyCode::Char('=') => {
+                self.adjust_target(None);
+            }
+            KeyCode::Char('m') | KeyCode::Char('M') => {
+                self.toggle_capture();
+            }
+            KeyCode::Char('t') | KeyCode::Char('T') => {
+                self.toggle_stretch();
+            }
+            KeyCode::Char('v') | KeyCode::Char('V') => {
+                self.curve_view = true;
+            }
+            KeyCode::F(3) => self.set_gain_db(self.gain_db - Self::GAIN_STEP_DB),
+            KeyCode::F(4) => self.set_gain_db(self.gain_db + Self::GAIN_STEP_DB),
             KeyCode::Char('s') | KeyCode::Char('S') => {
                 // Skip current note
                 self.skip_note();
             }
             KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
                 // Save session before quitting
-                if let Some(session) = &self.session {
-                    let _ = session.save();
-                }
+                self.save_session();
                 self.quit();
             }
             _ => {}
         }
     }
 
-    /// Toggle piano progress display.
-    fn toggle_piano_progress(&mut self) {
-        if let Some(tuning) = &mut self.tuning {
-            tuning.toggle_piano_progress();
+    /// Close the stretch curve overlay; other keys wait until it is closed.
+    fn handle_curve_view_key(&mut self, key: KeyCode) {
+        if matches!(
+            key,
+            KeyCode::Char('v') | KeyCode::Char('V') | KeyCode::Esc | KeyCode::Enter
+        ) {
+            self.curve_view = false;
         }
     }
 
-    fn handle_complete_key(&mut self, key: KeyCode) {
-        match key {
-            KeyCode::Enter => {
-                // Start new session
-                self.reset();
+    /// Whether the stretch curve overlay is open.
+    pub fn curve_view(&self) -> bool {
+        self.curve_view
+    }
+
+    /// Chart of the stretch curve for the overlay, with its name and whether
+    /// the targets follow it: the session's while tuning, with the current
+    /// note marked, else the one set up.
+    fn curve_chart(&self) -> (CurveChart, &str, bool) {
+        match (&self.state, &self.session) {
+            (AppState::Tuning, Some(session)) => {
+                let name = match (&session.stretch_profile, session.instrument) {
+                    (Some(profile), _) => profile.name.as_str(),
+                    (None, Instrument::Piano) => session.piano_type.name(),
+                    (None, instrument) => instrument.name(),
+                };
+                let current = self
+                    .tuning_order
+                    .note_at(self.current_note_idx)
+                    .map(|note| note.midi);
+                let chart = CurveChart::new(&session.instrument_stretch()).with_current(current);
+                (chart, name, session.stretch)
             }
-            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
-                self.quit();
+            _ => {
+                let name = match self.setup.instrument() {
+                    Instrument::Piano => self.setup.curve_label(),
+                    instrument => instrument.name(),
+                };
+                let session = self.new_session(TuningMode::Concert);
+                (
+                    CurveChart::new(&session.instrument_stretch()),
+                    name,
+                    self.setup.stretch(),
+                )
             }
-            _ => {}
         }
     }
 
-    /// Start a new tuning session based on selected mode.
-    fn start_session(&mut self) {
-        let mode = match self.mode_select.selected() {
-            SelectedMode::QuickTune => TuningMode::Quick,
-            SelectedMode::ConcertPitch => TuningMode::Concert,
-        };
-
-        match mode {
-            TuningMode::Quick => {
-                self.state = AppState::Calibration;
-                self.calibration.reset();
-            }
-            TuningMode::Concert => {
-                self.temperament = Temperament::new();
-                self.start_tuning();
-            }
+    /// Play the reference tone, or stop it if it is playing.
+    fn toggle_reference(&mut self) {
+        if self.playing_reference() {
+            self.log(Level::Info, "reference_stopped", &[]);
+            self.reference = None;
+            self.reference_request = Some(ReferenceRequest::Stop);
+            self.sync_reference();
+        } else {
+            self.play_reference();
         }
     }
 
-    /// Start tuning after calibration.
-    fn start_tuning(&mut self) {
-        let mode = match self.mode_select.selected() {
-            SelectedMode::QuickTune => TuningMode::Quick,
-            SelectedMode::ConcertPitch => TuningMode::Concert,
-        };
+    /// Play the target with the octave below, or stop it if it is playing.
+    /// A plain reference tone playing gains the octave. Headphones in split
+    /// mode play the target alone, so there is no octave to add.
+    fn toggle_octave_reference(&mut self) {
+        if self.reference_channels != ReferenceChannels::Split {
+            self.toggle_reference_kind(ReferenceKind::Octave);
+        }
+    }
 
-        self.session = Some(Session::new(mode, self.temperament.a4()));
-        self.current_note_idx = 0;
-        self.state = AppState::Tuning;
-        self.setup_current_note();
+    /// Play the target beating against a second voice detuned by the beat
+    /// demonstration's cents, or stop it if it is playing.
+    fn toggle_beat_demo(&mut self) {
+        self.toggle_reference_kind(ReferenceKind::Beats);
     }
 
-    /// Set up the tuning screen for the current note.
-    fn setup_current_note(&mut self) {
-        if self.current_note_idx >= 88 {
-            self.finish_session();
+    /// Play the reference tone as `kind`, or stop it if that is what is
+    /// playing. Another kind playing switches over.
+    fn toggle_reference_kind(&mut self, kind: ReferenceKind) {
+        if self.playing_kind() == Some(kind) {
+            self.toggle_reference();
+        } else {
+            self.request_reference(kind);
+        }
+    }
+
+    /// Detune the beat demonstration by `delta` cents more, while it plays.
+    fn adjust_beat_cents(&mut self, delta: f32) {
+        if self.playing_kind() != Some(ReferenceKind::Beats) {
             return;
         }
+        self.beat_cents = (self.beat_cents + delta).clamp(0.0, Self::MAX_BEAT_CENTS);
+        self.play_reference();
+    }
 
-        if let Some(note) = self.tuning_order.note_at(self.current_note_idx) {
-            let target_freq = self.temperament.frequency(note.midi);
+    /// Request the reference tone for the current target, as whatever is
+    /// playing.
+    fn play_reference(&mut self) {
+        self.request_reference(self.playing_kind().unwrap_or(ReferenceKind::Target));
+    }
 
-            // Collect completed chromatic indices from session (midi - 21)
-            let completed_notes: HashSet<usize> = if let Some(session) = &self.session {
-                session
-                    .completed_notes
-                    .iter()
-                    .filter_map(|cn| {
-                        // Look up note by name to get its midi, then convert to chromatic index
-                        crate::tuning::notes::Note::from_name(&cn.note)
-                            .map(|n| (n.midi - 21) as usize)
-                    })
-                    .collect()
-            } else {
-                HashSet::new()
-            };
+    /// What the reference tone playing plays, if it is playing.
+    fn playing_kind(&self) -> Option<ReferenceKind> {
+        self.reference
+            .filter(|_| self.playing_reference())
+            .map(|r| r.kind)
+    }
 
-            let mut tuning = TuningScreen::new(
-                note.display_name(),
-                self.current_note_idx,
-                88,
-                target_freq,
-                note.strings,
-                note.midi,
-            );
-            tuning.set_completed_notes(completed_notes);
-            self.tuning = Some(tuning);
-        }
+    /// Request the reference tone for the current target, as `kind`.
+    fn request_reference(&mut self, kind: ReferenceKind) {
+        let Some(note) = self.tuning_order.note_at(self.current_note_idx) else {
+            return;
+        };
+        let Some(freq) = self.current_target_freq() else {
+            return;
+        };
+        let lower = (kind == ReferenceKind::Octave).then(|| self.octave_below(note.midi, freq));
+        let request = match kind {
+            ReferenceKind::Target => ReferenceRequest::Play(freq),
+            ReferenceKind::Octave => ReferenceRequest::PlayOctave {
+                target: freq,
+                lower: lower.unwrap_or(freq / 2.0),
+            },
+            ReferenceKind::Beats => ReferenceRequest::PlayBeats {
+                target: freq,
+                cents: self.beat_cents,
+            },
+        };
+        self.log(
+            Level::Info,
+            "reference_played",
+            &[("request", &format!("{:?}", request))],
+        );
+        // Moving to another note keeps the countdown going
+        let ends = match self.reference {
+            Some(reference) if self.playing_reference() => reference.ends,
+            _ => (!self.reference_timeout.is_zero())
+                .then(|| self.clock.now() + self.reference_timeout),
+        };
+        self.reference = Some(Reference {
+            freq,
+            kind,
+            lower,
+            ends,
+        });
+        self.reference_request = Some(request);
+        self.sync_reference();
     }
 
-    /// Update with detected pitch.
-    pub fn update_pitch(&mut self, freq: f32, confidence: f32) {
-        match self.state {
-            AppState::Calibration => {
-                if confidence > 0.8 {
-                    self.calibration.update(freq);
-                    if self.calibration.is_complete() {
-                        if let Some(a4) = self.calibration.result() {
-                            self.temperament = Temperament::with_a4(a4);
-                        }
-                        self.start_tuning();
-                    }
-                }
-            }
-            AppState::Tuning => {
-                if let Some(tuning) = &mut self.tuning {
-                    if confidence > 0.6 {
-                        let target = tuning.target_freq();
-                        let cents = self.temperament.cents_from_target(freq, target);
-                        tuning.update(freq, cents);
-                    } else {
-                        tuning.clear();
-                    }
-                }
-            }
-            _ => {}
+    /// Frequency of the octave below a note whose target is `target`:
+    /// half of it, moved by the difference in stretch between the two.
+    fn octave_below(&self, midi: u8, target: f32) -> f32 {
+        let stretch = self.stretch_cents(midi.saturating_sub(12)) - self.stretch_cents(midi);
+        target / 2.0 * Temperament::cents_to_ratio(stretch)
+    }
+
+    /// Switch the reference tone to the next waveform, including one
+    /// already playing.
+    fn cycle_waveform(&mut self) {
+        self.waveform = self.waveform.next();
+        self.log(
+            Level::Info,
+            "waveform_changed",
+            &[("waveform", &format!("{:?}", self.waveform))],
+        );
+        if let Some(tuning) = &mut self.tuning {
+            tuning.set_waveform(self.waveform);
         }
     }
 
-    /// Clear pitch detection (silence).
-    pub fn clear_pitch(&mut self) {
-        match self.state {
-            AppState::Calibration => {
-                self.calibration.clear();
-            }
-            AppState::Tuning => {
-                if let Some(tuning) = &mut self.tuning {
-                    tuning.clear();
-                }
+    /// Waveform of the reference tone, for the main loop to play.
+    pub fn waveform(&self) -> Waveform {
+        self.waveform
+    }
+
+    /// Whether low reference notes are reinforced with their partials.
+    pub fn bass_reinforcement(&self) -> bool {
+        self.bass_reinforcement
+    }
+
+    /// Take the reference tone change waiting to be made, if any.
+    pub fn take_reference_request(&mut self) -> Option<ReferenceRequest> {
+        self.reference_request.take()
+    }
+
+    /// Length of the reference tone's fade in and out.
+    pub fn reference_fade(&self) -> Duration {
+        self.reference_fade
+    }
+
+    /// Set the reference volume, from 0 to 1, in whole steps.
+    fn set_reference_volume(&mut self, volume: f32) {
+        let steps = (volume / Self::VOLUME_STEP).round();
+        self.reference_volume = (steps * Self::VOLUME_STEP).clamp(0.0, 1.0);
+        self.log(
+            Level::Info,
+            "reference_volume_changed",
+            &[("volume", &format!("{:.0}%", self.reference_volume * 100.0))],
+        );
+        self.sync_reference();
+    }
+
+    /// Volume of the reference tone, from 0 to 1, for the main loop to
+    /// apply.
+    pub fn reference_volume(&self) -> f32 {
+        self.reference_volume
+    }
+
+    /// Take accepted room noise settings for the main loop to save.
+    pub fn take_noise_request(&mut self) -> Option<NoiseSettings> {
+        self.noise_request.take()
+    }
+
+    /// Take a stretch curve whose target overrides changed, for the main
+    /// loop to save.
+    pub fn take_profile_request(&mut self) -> Option<StretchProfile> {
+        self.profile_request.take()
+    }
+
+    /// Confidence below which a frame counts as no pitch, for the main
+    /// loop's smoother.
+    pub fn min_confidence(&self) -> f32 {
+        self.min_confidence
+    }
+
+    /// Input level (RMS) above which audio counts as activity.
+    pub fn noise_gate(&self) -> f32 {
+        self.noise_gate
+    }
+
+    /// Start listening to the room for [`NoiseFloor::MEASURE_TIME`].
+    fn start_noise_check(&mut self) {
+        self.calibration.clear();
+        self.set_noise_check(Some(NoiseCheck::Measuring(self.clock.now())));
+    }
+
+    /// Set the room noise measurement and show it.
+    fn set_noise_check(&mut self, check: Option<NoiseCheck>) {
+        self.noise_check = check;
+        let status = check.map(|check| match check {
+            NoiseCheck::Measuring(started) => {
+                let elapsed = self.clock.now().saturating_sub(started);
+                NoiseStatus::Measuring(
+                    (elapsed.as_secs_f64() / NoiseFloor::MEASURE_TIME.as_secs_f64()).min(1.0),
+                )
             }
-            _ => {}
+            NoiseCheck::Measured(floor) => NoiseStatus::Measured(floor, floor.suggest()),
+        });
+        self.calibration.set_noise(status);
+    }
+
+    /// Measure the room noise once enough input has been collected.
+    fn update_noise_check(&mut self, samples: &[f32], sample_rate: u32) {
+        let Some(NoiseCheck::Measuring(started)) = self.noise_check else {
+            return;
+        };
+        let needed = (NoiseFloor::MEASURE_TIME.as_secs_f32() * sample_rate as f32) as usize;
+        let elapsed = self.clock.now().saturating_sub(started);
+        if elapsed < NoiseFloor::MEASURE_TIME || samples.len() < needed {
+            // Update the progress
+            self.set_noise_check(self.noise_check);
+            return;
         }
+
+        let floor = NoiseFloor::measure(&samples[samples.len() - needed..], sample_rate);
+        self.log(
+            Level::Info,
+            "noise_measured",
+            &[
+                ("db", &format!("{:.1}", floor.db())),
+                ("flatness", &format!("{:.2}", floor.flatness)),
+            ],
+        );
+        self.set_noise_check(Some(NoiseCheck::Measured(floor)));
     }
 
-    /// Confirm current note is tuned.
-    fn confirm_note(&mut self) {
-        if let Some(tuning) = &mut self.tuning {
-            // For multi-string notes (bichord/trichord), advance through steps
-            if tuning.is_multi_string() && tuning.next_step() {
-                return;
-            }
+    /// Use the settings suggested for the room, and ask the main loop to
+    /// save them.
+    fn set_noise_settings(&mut self, settings: NoiseSettings) {
+        self.log(
+            Level::Info,
+            "noise_settings",
+            &[
+                ("gate", &format!("{:.4}", settings.noise_gate)),
+                ("min_confidence", &format!("{:.2}", settings.min_confidence)),
+            ],
+        );
+        self.noise_gate = settings.noise_gate;
+        self.min_confidence = settings.min_confidence;
+        self.pitch_lock.set_min_confidence(settings.min_confidence);
+        self.noise_request = Some(settings);
+    }
 
-            // Record completion
-            if let Some(session) = &mut self.session {
-                if let Some(note) = self.tuning_order.note_at(self.current_note_idx) {
-                    session.complete_note(note.display_name(), tuning.cents());
-                }
-            }
+    /// Take a pending cue for the main loop to play.
+    pub fn take_cue_request(&mut self) -> Option<Cue> {
+        self.cue_request.take()
+    }
 
-            self.advance_to_next_note();
+    /// Ask for a cue, unless cues are off or the reference tone is
+    /// sounding on the same output.
+    fn request_cue(&mut self, cue: Cue) {
+        if self.beep && !self.playing_reference() {
+            self.cue_request = Some(cue);
         }
     }
 
-    /// Go back to previous step or previous note.
-    fn go_back(&mut self) {
-        // Try to go to previous step first
+    /// Whether the reference tone is playing.
+    pub fn playing_reference(&self) -> bool {
+        self.reference
+            .is_some_and(|r| r.ends.is_none_or(|ends| self.clock.now() < ends))
+    }
+
+    /// Where the reference tone is played.
+    pub fn reference_channels(&self) -> ReferenceChannels {
+        self.reference_channels
+    }
+
+    /// Whether the reference tone may reach the microphone. A split tone is
+    /// played on headphones and needs detection to keep running.
+    fn reference_audible(&self) -> bool {
+        self.reference_channels == ReferenceChannels::Mono && self.playing_reference()
+    }
+
+    /// Whether detection is paused for the reference tone.
+    fn detection_paused(&self) -> bool {
+        self.reference_feedback == ReferenceFeedback::Pause && self.reference_audible()
+    }
+
+    /// Stop a reference tone that has timed out, and update the meter
+    /// notice and the countdown.
+    fn sync_reference(&mut self) {
+        if self.reference.is_some() && !self.playing_reference() {
+            self.log(Level::Info, "reference_timed_out", &[]);
+            self.reference = None;
+            self.reference_request = Some(ReferenceRequest::Stop);
+        }
+        let notice = self
+            .detection_paused()
+            .then(|| strings::get("tuning.reference_paused").to_string());
+        let now = self.clock.now();
+        let status = self.reference.map(|reference| ReferenceStatus {
+            volume: self.reference_volume,
+            octave: reference.kind == ReferenceKind::Octave,
+            beats: (reference.kind == ReferenceKind::Beats).then(|| {
+                let detuned = self
+                    .temperament
+                    .cents_to_frequency(reference.freq, self.beat_cents);
+                (self.beat_cents, detuned - reference.freq)
+            }),
+            remaining: reference.ends.map(|ends| ends.saturating_sub(now)),
+        });
         if let Some(tuning) = &mut self.tuning {
-            if tuning.prev_step() {
-                return;
-            }
+            tuning.set_reference_notice(notice);
+            tuning.set_reference(status);
         }
+    }
 
-        // Otherwise go to previous note
-        if self.current_note_idx > 0 {
-            self.current_note_idx -= 1;
-            self.setup_current_note();
+    /// Catch up with the time passed since the last input or detection,
+    /// once a frame: a reference tone times out even with nothing else
+    /// going on.
+    pub fn tick(&mut self) {
+        self.sync_reference();
+    }
 
-            // For multi-string notes, go to last step
-            if let Some(tuning) = &mut self.tuning {
-                while tuning.next_step() {}
+    /// The last `window` samples of `history` to run pitch detection on.
+    ///
+    /// While the reference tone plays in filter mode, its frequency and
+    /// harmonics are notched out first; in cancel mode, they are
+    /// subtracted.
+    pub fn detection_window<'a>(
+        &self,
+        history: &'a [f32],
+        window: usize,
+        sample_rate: u32,
+    ) -> Cow<'a, [f32]> {
+        let recent = || Cow::Borrowed(&history[history.len().saturating_sub(window)..]);
+        let Some(reference) = self.reference.filter(|_| self.reference_audible()) else {
+            return recent();
+        };
+        let tone = ReferenceTone::new(sample_rate)
+            .with_waveform(self.waveform)
+            .with_bass_reinforcement(self.bass_reinforcement);
+        let detuned = (reference.kind == ReferenceKind::Beats).then(|| {
+            self.temperament
+                .cents_to_frequency(reference.freq, self.beat_cents)
+        });
+        let frequencies = std::iter::once(reference.freq)
+            .chain(reference.lower)
+            .chain(detuned)
+            .flat_map(|freq| {
+                tone.harmonic_numbers(freq)
+                    .into_iter()
+                    .map(move |k| freq * k as f32)
+            });
+        match self.reference_feedback {
+            ReferenceFeedback::Pause => recent(),
+            ReferenceFeedback::Filter => {
+                let notches: Vec<Notch> = frequencies
+                    .map(|freq| Notch::new(freq, sample_rate, Notch::DEFAULT_Q))
+                    .collect();
+                Cow::Owned(Notch::apply_window_chain(&notches, history, window))
             }
-
-            // Update session
-            if let Some(session) = &mut self.session {
-                session.current_note_index = self.current_note_idx;
+            ReferenceFeedback::Cancel => {
+                let canceller = ToneCanceller::new(frequencies.collect(), sample_rate);
+                Cow::Owned(canceller.apply_window(history, window))
             }
         }
     }
 
-    /// Skip current note.
-    fn skip_note(&mut self) {
-        // Record as skipped (0 cents)
-        if let Some(session) = &mut self.session {
-            if let Some(note) = self.tuning_order.note_at(self.current_note_idx) {
-                session.complete_note(note.display_name(), 0.0);
+    /// Frequency the pitch detector searches around, while a note is being
+    /// tuned or measured. Capturing a target from another instrument
+    /// searches the whole range.
+    pub fn detection_target(&self) -> Option<f32> {
+        match self.state {
+            AppState::Tuning if self.capture.is_none() => self.current_target_freq(),
+            AppState::Measure => {
+                let note = self.tuning_order.note_at(self.current_note_idx)?;
+                Some(self.scheme_freq(note.midi))
             }
+            _ => None,
         }
+    }
 
-        self.advance_to_next_note();
+    /// Target fundamental when the current note is measured via its
+    /// partials rather than the pitch detector.
+    pub fn partial_target(&self) -> Option<f32> {
+        let note = self.tuning_order.note_at(self.current_note_idx)?;
+        if note.midi >= self.partials_below {
+            return None;
+        }
+        match self.state {
+            AppState::Tuning => self.current_target_freq(),
+            AppState::Measure => Some(self.scheme_freq(note.midi)),
+            _ => None,
+        }
     }
 
-    /// Advance to the next note.
-    fn advance_to_next_note(&mut self) {
-        self.current_note_idx += 1;
+    /// Update with a fundamental inferred from the partials.
+    pub fn update_partials(&mut self, result: Option<PartialResult>) {
+        let Some(result) = result else {
+            self.clear_pitch();
+            return;
+        };
 
-        if self.current_note_idx >= 88 {
-            self.finish_session();
-        } else {
-            self.setup_current_note();
+        self.log(
+            Level::Debug,
+            "partial",
+            &[
+                ("freq", &format!("{:.2}", result.frequency)),
+                ("confidence", &format!("{:.2}", result.confidence)),
+                ("partial", &result.partial),
+            ],
+        );
+        if let Some(tuning) = &mut self.tuning {
+            tuning.set_partial(Some(result.partial));
+        }
+        self.update_pitch(result.frequency, result.confidence);
+    }
 
-            // Update session progress
-            if let Some(session) = &mut self.session {
-                session.current_note_index = self.current_note_idx;
-                let _ = session.save();
-            }
+    /// Toggle piano progress display.
+    fn toggle_piano_progress(&mut self) {
+        if let Some(tuning) = &mut self.tuning {
+            tuning.toggle_piano_progress();
         }
     }
 
-    /// Finish the tuning session.
-    fn finish_session(&mut self) {
-        if let Some(session) = self.session.take() {
-            let completed_notes = session.completed_notes.clone();
-            self.complete = Some(CompleteScreen::new(completed_notes));
-        } else {
-            self.complete = Some(CompleteScreen::new(Vec::new()));
+    fn handle_complete_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Enter => {
+                // Start new session
+                self.reset();
+            }
+            KeyCode::Char('i') | KeyCode::Char('I') => {
+                self.start_interval_check();
+            }
+            KeyCode::Char('c') | KeyCode::Char('C') => {
+                if let Some(complete) = &mut self.complete {
+                    complete.toggle_chart();
+                }
+            }
+            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
+                self.quit();
+            }
+            _ => {}
         }
-        self.state = AppState::Complete;
     }
 
-    /// Reset to start a new session.
-    fn reset(&mut self) {
-        self.state = AppState::ModeSelect;
-        self.session = None;
-        self.tuning = None;
-        self.complete = None;
-        self.current_note_idx = 0;
-        self.mode_select = ModeSelectScreen::new();
-        self.calibration = CalibrationScreen::new();
+    fn handle_interval_check_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                if let Some(check) = &mut self.interval_check {
+                    check.checker_mut().skip();
+                }
+                if self.interval_checks_done() {
+                    self.finish_interval_check();
+                }
+            }
+            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
+                self.finish_interval_check();
+            }
+            _ => {}
+        }
     }
 
-    /// Render the current screen.
-    pub fn render(&self, frame: &mut Frame) {
-        let area = frame.area();
+    fn handle_measure_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                // Skip without recording a measurement
+                if let Some(session) = &mut self.session {
+                    session.skip_note();
+                }
+                self.advance_measurement();
+            }
+            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
+                self.save_session();
+                self.quit();
+            }
+            _ => {}
+        }
+    }
 
-        match self.state {
-            AppState::ModeSelect => {
-                frame.render_widget(&self.mode_select, area);
+    fn handle_measure_summary_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('r') | KeyCode::Char('R') => {
+                self.start_from_measurement(TuningMode::PitchRaise);
             }
-            AppState::Calibration => {
-                frame.render_widget(&self.calibration, area);
+            KeyCode::Char('f') | KeyCode::Char('F') => {
+                self.start_from_measurement(TuningMode::Concert);
             }
-            AppState::Tuning => {
-                if let Some(tuning) = &self.tuning {
-                    frame.render_widget(tuning, area);
-                }
+            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
+                self.quit();
             }
-            AppState::Complete => {
-                if let Some(complete) = &self.complete {
-                    frame.render_widget(complete, area);
+            _ => {}
+        }
+    }
+
+    fn handle_history_key(&mut self, key: KeyCode) {
+        let Some(history) = &mut self.history else {
+            return;
+        };
+
+        // A past session's summary can only be looked at
+        if let Some(summary) = history.summary_mut() {
+            match key {
+                KeyCode::Char('c') | KeyCode::Char('C') => summary.toggle_chart(),
+                KeyCode::Char('b')
+                | KeyCode::Char('B')
+                | KeyCode::Char('q')
+                | KeyCode::Char('Q')
+                | KeyCode::Esc => {
+                    history.close_summary();
                 }
+                _ => {}
             }
+            return;
         }
-    }
-}
 
-impl Default for App {
-    fn default() -> Self {
-        Self::new()
+        match key {
+            KeyCode::Up => history.prev(),
+            KeyCode::Down | KeyCode::Tab => history.next(),
+            KeyCode::Left => {
+                history.prev_piano();
+                self.load_history();
+            }
+            KeyCode::Right => {
+                history.next_piano();
+                self.load_history();
+            }
+            KeyCode::Enter => history.open_summary(),
+            KeyCode::Char('b')
+            | KeyCode::Char('B')
+            | KeyCode::Char('q')
+            | KeyCode::Char('Q')
+            | KeyCode::Esc => {
+                self.history = None;
+                self.set_state(AppState::ModeSelect);
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_device_select_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Up => {
+                self.device_select.prev();
+            }
+            KeyCode::Down | KeyCode::Tab => {
+                self.device_select.next();
+            }
+            KeyCode::Enter => {
+                self.device_request = match self.device_select.selected() {
+                    Some((DeviceKind::Input, device)) => {
+                        Some(DeviceRequest::OpenInput(device.name.clone()))
+                    }
+                    Some((DeviceKind::Output, device)) => {
+                        Some(DeviceRequest::OpenOutput(device.name.clone()))
+                    }
+                    None => None,
+                };
+            }
+            KeyCode::Char('r') | KeyCode::Char('R') => {
+                self.device_request = Some(DeviceRequest::Rescan);
+            }
+            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
+                self.close_device_select();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_input_lost_key(&mut self, key: KeyCode) {
+        match key {
+            KeyCode::Char('r') | KeyCode::Char('R') | KeyCode::Enter => {
+                self.device_request = Some(DeviceRequest::ReopenDefaultInput);
+            }
+            KeyCode::Char('q') | KeyCode::Char('Q') | KeyCode::Esc => {
+                self.save_session();
+                self.quit();
+            }
+            _ => {}
+        }
+    }
+
+    /// Show device selection, returning to the current screen afterwards.
+    fn open_device_select(&mut self) {
+        if self.state != AppState::DeviceSelect {
+            self.device_return = self.state;
+        }
+        self.device_select.set_toast(None);
+        self.device_request = Some(DeviceRequest::Rescan);
+        self.set_state(AppState::DeviceSelect);
+    }
+
+    /// Leave device selection for the screen it was opened from.
+    fn close_device_select(&mut self) {
+        self.device_select.set_toast(None);
+        self.set_state(self.device_return);
+    }
+
+    /// Show device selection with an audio error (capture failed or lost).
+    pub fn show_audio_error(&mut self, error: &str) {
+        self.log(Level::Error, "audio_error", &[("error", &error)]);
+        self.open_device_select();
+        self.device_select.set_toast(Some(strings::format(
+            "devices.audio_error",
+            &[("error", &error)],
+        )));
+    }
+
+    /// Take the pending device change, if any.
+    pub fn take_device_request(&mut self) -> Option<DeviceRequest> {
+        self.device_request.take()
+    }
+
+    /// Replace the listed devices (after enumeration).
+    pub fn set_devices(&mut self, inputs: Vec<AudioDevice>, outputs: Vec<AudioDevice>) {
+        self.device_select.set_devices(inputs, outputs);
+    }
+
+    /// Get the device selection screen.
+    pub fn device_select(&self) -> &DeviceSelectScreen {
+        &self.device_select
+    }
+
+    /// Get the mode selection screen.
+    pub fn mode_select(&self) -> &ModeSelectScreen {
+        &self.mode_select
+    }
+
+    /// Get the setup screen.
+    pub fn setup(&self) -> &SetupScreen {
+        &self.setup
+    }
+
+    /// Get the tuning screen, while tuning.
+    pub fn tuning(&self) -> Option<&TuningScreen> {
+        self.tuning.as_ref()
+    }
+
+    /// Get the measure screen, while measuring.
+    pub fn measure(&self) -> Option<&MeasureScreen> {
+        self.measure.as_ref()
+    }
+
+    /// Get the measurement summary screen.
+    pub fn measure_summary(&self) -> Option<&MeasureSummaryScreen> {
+        self.measure_summary.as_ref()
+    }
+
+    /// Get the interval check screen.
+    pub fn interval_check(&self) -> Option<&IntervalCheckScreen> {
+        self.interval_check.as_ref()
+    }
+
+    /// Get the session complete screen.
+    pub fn complete(&self) -> Option<&CompleteScreen> {
+        self.complete.as_ref()
+    }
+
+    /// Session in progress, whose input can be recorded, if any.
+    pub fn active_session(&self) -> Option<&Session> {
+        match self.state {
+            AppState::ModeSelect | AppState::Setup | AppState::Complete | AppState::History => None,
+            _ => self.session.as_ref(),
+        }
+    }
+
+    /// Set whether the microphone input is being recorded.
+    pub fn set_recording(&mut self, recording: bool) {
+        self.recording = recording;
+        if let Some(tuning) = &mut self.tuning {
+            tuning.set_recording(recording);
+        }
+    }
+
+    /// Whether the microphone input is being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Set the input gain in dB, within the limits of [`Gain`].
+    pub fn set_gain_db(&mut self, db: f32) {
+        let db = Gain::from_db(db).db();
+        if db != self.gain_db {
+            self.log(Level::Info, "gain_changed", &[("db", &db)]);
+        }
+        self.gain_db = db;
+        self.calibration.set_gain_db(db);
+        if let Some(tuning) = &mut self.tuning {
+            tuning.set_gain_db(db);
+        }
+    }
+
+    /// Input gain in dB, for the main loop to apply.
+    pub fn gain_db(&self) -> f32 {
+        self.gain_db
+    }
+
+    /// Update with the number of input samples clipped since the last
+    /// update. The warning is held for [`Self::CLIP_HOLD`] so it doesn't
+    /// flicker between frames.
+    pub fn update_clipping(&mut self, clipped: usize) {
+        if clipped > 0 {
+            self.clipped_at = Some(self.clock.now());
+        }
+        let clipping = self.is_clipping();
+        self.calibration.set_clipping(clipping);
+        if let Some(tuning) = &mut self.tuning {
+            tuning.set_clipping(clipping);
+        }
+    }
+
+    /// Whether the input clipped recently.
+    pub fn is_clipping(&self) -> bool {
+        self.clipped_at
+            .is_some_and(|at| self.clock.now().saturating_sub(at) < Self::CLIP_HOLD)
+    }
+
+    /// Record the input device in use.
+    pub fn set_input_device(&mut self, name: &str) {
+        self.device_select.set_current_input(Some(name.to_string()));
+    }
+
+    /// Record the sample rate and buffer frames negotiated with the input.
+    pub fn set_input_format(&mut self, sample_rate: u32, buffer_frames: Option<u32>) {
+        self.device_select
+            .set_input_format(sample_rate, buffer_frames);
+    }
+
+    /// Record the output device in use.
+    pub fn set_output_device(&mut self, name: &str) {
+        self.device_select
+            .set_current_output(Some(name.to_string()));
+    }
+
+    /// Capture was reopened on the requested input device.
+    pub fn input_opened(&mut self, name: &str) {
+        self.log(Level::Info, "input_device_changed", &[("device", &name)]);
+        self.input_lost = None;
+        self.set_input_device(name);
+        if self.state == AppState::DeviceSelect {
+            self.close_device_select();
+        }
+    }
+
+    /// The input device went away, e.g. unplugged mid-session. The session
+    /// is saved before the user is asked to retry or quit.
+    pub fn input_lost(&mut self, error: &str) {
+        self.log(Level::Error, "input_lost", &[("error", &error)]);
+        self.save_session();
+        self.reference = None;
+        self.reference_request = None;
+        self.clear_pitch();
+        self.input_lost = Some(error.to_string());
+    }
+
+    /// Why the input device was lost, while waiting to retry or quit.
+    pub fn input_lost_error(&self) -> Option<&str> {
+        self.input_lost.as_deref()
+    }
+
+    /// Switching to an output device succeeded.
+    pub fn output_opened(&mut self, name: &str) {
+        self.log(Level::Info, "output_device_changed", &[("device", &name)]);
+        self.set_output_device(name);
+        if self.state == AppState::DeviceSelect {
+            self.close_device_select();
+        }
+    }
+
+    /// Reopening capture failed; the previous input stays in use, or the
+    /// lost input overlay stays up with the new error.
+    pub fn input_failed(&mut self, name: &str, error: &str) {
+        if self.input_lost.is_some() {
+            // Still without input; explain on the overlay instead
+            self.log(
+                Level::Warn,
+                "input_reopen_failed",
+                &[("device", &name), ("error", &error)],
+            );
+            self.input_lost = Some(error.to_string());
+            return;
+        }
+        let current = self.device_select.current_input().map(str::to_string);
+        self.device_failed(name, error, current);
+    }
+
+    /// Switching outputs failed; the previous output stays in use.
+    pub fn output_failed(&mut self, name: &str, error: &str) {
+        let current = self.device_select.current_output().map(str::to_string);
+        self.device_failed(name, error, current);
+    }
+
+    /// Log a failed device switch and explain it on the selection screen.
+    fn device_failed(&mut self, name: &str, error: &str, current: Option<String>) {
+        self.log(
+            Level::Warn,
+            "device_change_failed",
+            &[("device", &name), ("error", &error)],
+        );
+        let toast = match current {
+            Some(current) => strings::format(
+                "devices.open_failed_current",
+                &[("device", &name), ("error", &error), ("current", &current)],
+            ),
+            None => strings::format(
+                "devices.open_failed",
+                &[("device", &name), ("error", &error)],
+            ),
+        };
+        self.device_select.set_toast(Some(toast));
+    }
+
+    /// Set up a session in the mode and for the instrument selected.
+    fn open_setup(&mut self) {
+        self.setup.set_mode(self.mode_select.selected());
+        self.setup.set_instrument(self.mode_select.instrument());
+        self.set_state(AppState::Setup);
+    }
+
+    /// Start a new tuning session based on selected mode.
+    fn start_session(&mut self) {
+        match self.setup.mode() {
+            SelectedMode::QuickTune => {
+                self.transpose = 0.0;
+                self.set_state(AppState::Calibration);
+                self.calibration.reset();
+            }
+            SelectedMode::ConcertPitch => {
+                self.temperament = Temperament::with_a4(self.setup.a4().value());
+                self.transpose = self.setup.transpose_cents();
+                self.start_tuning();
+            }
+            SelectedMode::Measure => {
+                self.temperament = Temperament::with_a4(self.setup.a4().value());
+                self.transpose = self.setup.transpose_cents();
+                self.start_measurement();
+            }
+        }
+    }
+
+    /// Start tuning after calibration.
+    fn start_tuning(&mut self) {
+        let mode = match self.setup.mode() {
+            SelectedMode::QuickTune => TuningMode::Quick,
+            SelectedMode::ConcertPitch | SelectedMode::Measure => TuningMode::Concert,
+        };
+
+        let session = self.new_session(mode);
+        self.set_session(session);
+        self.current_note_idx = 0;
+        self.set_state(AppState::Tuning);
+        self.setup_current_note();
+    }
+
+    /// Start a measurement pass over the whole piano.
+    fn start_measurement(&mut self) {
+        let session = self.new_session(TuningMode::Measurement);
+        self.set_session(session);
+        self.current_note_idx = 0;
+        self.set_state(AppState::Measure);
+        self.setup_current_measure();
+    }
+
+    /// Start a pitch raise or fine tuning seeded with the last measurement.
+    fn start_from_measurement(&mut self, mode: TuningMode) {
+        let Some(measurement) = &self.measurement else {
+            return;
+        };
+
+        let session = Session::seeded_from(mode, self.temperament.a4(), measurement);
+        self.set_session(session);
+        self.current_note_idx = 0;
+        self.set_state(AppState::Tuning);
+        self.setup_current_note();
+    }
+
+    /// Compute the target frequency for a note in the current session.
+    ///
+    /// Pitch raise sessions overpull each note by a fraction of its
+    /// measured deviation.
+    fn target_freq(&self, midi: u8) -> f32 {
+        let adjustments = self.session.as_ref().map(Session::target_adjustments);
+        self.planned_freq(midi, adjustments.as_ref())
+    }
+
+    /// Instrument of the current session.
+    fn instrument(&self) -> Instrument {
+        self.session
+            .as_ref()
+            .map_or(Instrument::default(), |session| session.instrument)
+    }
+
+    /// Cents the current session's targets are transposed by.
+    fn transpose_cents(&self) -> f32 {
+        self.session
+            .as_ref()
+            .map_or(0.0, |session| session.transpose_cents)
+    }
+
+    /// Frequency of a note in the session's temperament scheme, or its
+    /// custom temperament, transposed with the session.
+    fn scheme_freq(&self, midi: u8) -> f32 {
+        match &self.session {
+            Some(session) => session.tuning_temperament().frequency(midi),
+            None => self.temperament.frequency(midi),
+        }
+    }
+
+    /// Stretch of a note in cents, if the session tunes to the
+    /// instrument's stretch curve.
+    fn stretch_cents(&self, midi: u8) -> f32 {
+        self.session
+            .as_ref()
+            .map_or(0.0, |session| session.stretch_curve().offset_cents(midi))
+    }
+
+    /// Stretch curve of the current session's instrument, for the interval
+    /// checks.
+    fn stretch_curve(&self) -> StretchCurve {
+        self.session.as_ref().map_or_else(
+            || self.instrument().stretch(PianoType::default()),
+            Session::instrument_stretch,
+        )
+    }
+
+    /// Target frequency for a note before any manual adjustment: stretched
+    /// if the session asks for it, so the meter and the reference tone
+    /// agree.
+    fn computed_target_freq(&self, midi: u8) -> f32 {
+        self.planned_freq(midi, None)
+    }
+
+    /// Target from the session's tuning plan with the given manual
+    /// adjustments, overpulled in a pitch raise.
+    fn planned_freq(&self, midi: u8, overrides: Option<&TargetOverrides>) -> f32 {
+        let Some(session) = &self.session else {
+            return self.temperament.frequency(midi);
+        };
+        let target = TuningPlan::target(
+            &session.tuning_temperament(),
+            &session.stretch_curve(),
+            overrides.unwrap_or(&TargetOverrides::new()),
+            midi,
+        )
+        .frequency;
+        match session.measured_cents.get(&midi) {
+            Some(&measured) if session.mode == TuningMode::PitchRaise => {
+                target * Temperament::cents_to_ratio(pitch_raise::overpull_cents(measured))
+            }
+            _ => target,
+        }
+    }
+
+    /// Start capturing the current note's target from another instrument,
+    /// or cancel a capture in progress.
+    fn toggle_capture(&mut self) {
+        let Some(tuning) = &mut self.tuning else {
+            return;
+        };
+        if self.capture.take().is_some() {
+            tuning.set_capture(None);
+            self.log(Level::Info, "capture_cancelled", &[]);
+        } else {
+            let capture = TargetCapture::new();
+            tuning.set_capture(Some(capture.progress()));
+            self.capture = Some(capture);
+            self.log(Level::Info, "capture_started", &[]);
+        }
+    }
+
+    /// Use a pitch captured from another instrument as the current note's
+    /// target, optionally deriving A4 from it.
+    fn apply_captured_target(&mut self, freq: f32) {
+        self.capture = None;
+        let Some(note) = self.tuning_order.note_at(self.current_note_idx) else {
+            return;
+        };
+
+        if self.capture_sets_a4 {
+            let a4 = Temperament::implied_a4(freq, note.midi)
+                / Temperament::cents_to_ratio(self.transpose_cents());
+            if A4_RANGE.contains(&a4) {
+                self.temperament = Temperament::with_a4(a4);
+                if let Some(session) = &mut self.session {
+                    session.a4_reference = a4;
+                }
+            }
+        }
+
+        let Some(offset) = self
+            .temperament
+            .cents_from_target(freq, self.computed_target_freq(note.midi))
+        else {
+            return;
+        };
+        if let Some(session) = &mut self.session {
+            session.captured_targets.insert(note.midi, freq);
+            let manual = offset - session.override_offset(note.midi);
+            session.set_manual_offset(note.midi, manual);
+        }
+        let target = self.target_freq(note.midi);
+        if let Some(tuning) = &mut self.tuning {
+            tuning.set_capture(None);
+            tuning.set_target(target, offset);
+            tuning.set_target_captured(true);
+        }
+        self.log(
+            Level::Info,
+            "target_captured",
+            &[
+                ("freq", &format!("{:.2}", freq)),
+                ("offset", &format!("{:+.1}", offset)),
+                ("a4", &format!("{:.2}", self.temperament.a4())),
+            ],
+        );
+        self.save_session();
+    }
+
+    /// Nudge the current note's target by `delta` cents for this session,
+    /// or reset it to the computed target with `None`, dropping any
+    /// override kept with the stretch curve too.
+    fn adjust_target(&mut self, delta: Option<f32>) {
+        let Some(note) = self.tuning_order.note_at(self.current_note_idx) else {
+            return;
+        };
+        let Some(session) = &mut self.session else {
+            return;
+        };
+
+        match delta {
+            Some(d) => session.set_manual_offset(note.midi, session.manual_offset(note.midi) + d),
+            None => {
+                session.captured_targets.remove(&note.midi);
+                session.set_manual_offset(note.midi, 0.0);
+                self.set_profile_override(note.midi, 0.0);
+            }
+        }
+        self.update_target(delta.is_none());
+    }
+
+    /// Move the current note's target override by `delta` cents. With a
+    /// saved stretch curve, the override is kept with it for later
+    /// sessions on the piano; without one it is a nudge for this session.
+    fn adjust_override(&mut self, delta: f32) {
+        let Some(note) = self.tuning_order.note_at(self.current_note_idx) else {
+            return;
+        };
+        let Some(session) = &self.session else {
+            return;
+        };
+        if session.stretch_profile.is_none() {
+            self.adjust_target(Some(delta));
+            return;
+        }
+        let cents = session.override_offset(note.midi) + delta;
+        self.set_profile_override(note.midi, cents);
+        self.update_target(false);
+    }
+
+    /// Set the override for `midi` on the session's stretch curve and ask
+    /// for the curve to be saved, if it has one and the override changes.
+    fn set_profile_override(&mut self, midi: u8, cents: f32) {
+        let Some(profile) = self
+            .session
+            .as_mut()
+            .and_then(|session| session.stretch_profile.as_mut())
+        else {
+            return;
+        };
+        if profile.target_overrides.get(midi) == cents {
+            return;
+        }
+        profile.target_overrides.set(midi, cents);
+        let profile = profile.clone();
+        let profiles = self
+            .setup
+            .stretch_profiles()
+            .iter()
+            .map(|p| if p.name == profile.name { &profile } else { p })
+            .cloned()
+            .collect();
+        let selected = self
+            .setup
+            .stretch_profile()
+            .map(|p| if p.name == profile.name { &profile } else { p })
+            .cloned();
+        self.setup = std::mem::take(&mut self.setup)
+            .with_stretch_profiles(profiles)
+            .with_stretch_profile(selected);
+        self.profile_request = Some(profile);
+    }
+
+    /// Show the current note's adjusted target, moving a reference tone
+    /// playing along with it, and save the session. `uncaptured` clears
+    /// the captured mark.
+    fn update_target(&mut self, uncaptured: bool) {
+        let Some(note) = self.tuning_order.note_at(self.current_note_idx) else {
+            return;
+        };
+        let Some(offset) = self
+            .session
+            .as_ref()
+            .map(|session| session.target_offset(note.midi))
+        else {
+            return;
+        };
+        let target = self.target_freq(note.midi);
+        if let Some(tuning) = &mut self.tuning {
+            tuning.set_target(target, offset);
+            if uncaptured {
+                tuning.set_target_captured(false);
+            }
+        }
+        // A reference tone playing follows the target
+        if self.playing_reference() {
+            self.play_reference();
+        }
+        self.log(
+            Level::Info,
+            "target_adjusted",
+            &[
+                ("offset", &format!("{:+.1}", offset)),
+                ("target", &format!("{:.2}", target)),
+            ],
+        );
+        self.save_session();
+    }
+
+    /// Switch the session's targets onto the stretch curve or off it,
+    /// mid-session, and start the current note again against the new target.
+    fn toggle_stretch(&mut self) {
+        let Some(session) = &mut self.session else {
+            return;
+        };
+        session.stretch = !session.stretch;
+        let stretch = session.stretch;
+        self.log(Level::Info, "stretch_toggled", &[("stretch", &stretch)]);
+        self.setup_current_note();
+        self.save_session();
+    }
+
+    /// Set up the measure screen for the current note.
+    fn setup_current_measure(&mut self) {
+        if self.current_note_idx >= self.tuning_order.len() {
+            self.finish_measurement();
+            return;
+        }
+
+        if let Some(note) = self.tuning_order.note_at(self.current_note_idx) {
+            let mut measure = MeasureScreen::new(
+                note.display_name(),
+                self.current_note_idx,
+                self.tuning_order.len(),
+                note.midi,
+            );
+
+            if let Some(session) = &self.session {
+                measure.set_measured_notes(
+                    session
+                        .measurements()
+                        .keys()
+                        .map(|&midi| (midi - 21) as usize)
+                        .collect(),
+                );
+                if let Some(last) = session.completed_notes.last() {
+                    measure.set_last_capture(&last.note, last.final_cents);
+                }
+            }
+
+            self.measure = Some(measure);
+        }
+    }
+
+    /// Record a captured measurement for the current note and move on.
+    fn record_measurement(&mut self, cents: f32) {
+        self.log(
+            Level::Info,
+            "note_measured",
+            &[("cents", &format!("{:+.1}", cents))],
+        );
+        if let Some(session) = &mut self.session {
+            if let Some(note) = self.tuning_order.note_at(self.current_note_idx) {
+                session.complete_note(note.display_name(), cents);
+            }
+        }
+        self.advance_measurement();
+    }
+
+    /// Advance the measurement pass to the next note.
+    fn advance_measurement(&mut self) {
+        self.current_note_idx += 1;
+
+        if let Some(session) = &mut self.session {
+            session.current_note_index = self.current_note_idx;
+        }
+        self.save_session();
+
+        self.setup_current_measure();
+    }
+
+    /// Finish the measurement pass and show the summary.
+    fn finish_measurement(&mut self) {
+        let measurement = self.session.take();
+        let measurements = measurement
+            .as_ref()
+            .map(|s| s.measurements())
+            .unwrap_or_default();
+
+        self.measure = None;
+        self.measure_summary = Some(MeasureSummaryScreen::new(measurements));
+        self.measurement = measurement;
+        self.set_state(AppState::MeasureSummary);
+    }
+
+    /// Set up the tuning screen for the current note.
+    fn setup_current_note(&mut self) {
+        if self.current_note_idx >= self.tuning_order.len() {
+            self.finish_session();
+            return;
+        }
+
+        if let Some(note) = self.tuning_order.note_at(self.current_note_idx) {
+            let target_freq = self.target_freq(note.midi);
+
+            // Collect completed chromatic indices from session (midi - 21)
+            let completed_notes: HashSet<usize> = if let Some(session) = &self.session {
+                session
+                    .completed_notes
+                    .iter()
+                    .filter_map(|cn| {
+                        // Look up note by name to get its midi, then convert to chromatic index
+                        crate::tuning::notes::Note::from_name(&cn.note)
+                            .map(|n| (n.midi - 21) as usize)
+                    })
+                    .collect()
+            } else {
+                HashSet::new()
+            };
+
+            let mut tuning = TuningScreen::new(
+                note.display_name(),
+                self.current_note_idx,
+                self.tuning_order.len(),
+                target_freq,
+                self.instrument().strings(note),
+                note.midi,
+            )
+            .with_instrument(self.instrument())
+            .with_string_order(self.string_order)
+            .with_hold_time(self.hold_time)
+            .with_readout(self.readout);
+            tuning.set_completed_notes(completed_notes);
+            tuning.set_recording(self.recording);
+            tuning.set_clipping(self.is_clipping());
+            tuning.set_gain_db(self.gain_db);
+            tuning.set_waveform(self.waveform);
+            tuning.set_stretch(self.stretch_cents(note.midi));
+            tuning.set_octave_type(
+                self.session
+                    .as_ref()
+                    .and_then(|session| session.octave_type_at(note.midi)),
+            );
+            tuning.set_transposition(self.temperament.a4(), self.transpose_cents());
+            if let Some(session) = &self.session {
+                tuning.set_target(target_freq, session.target_offset(note.midi));
+                tuning.set_target_captured(session.captured_targets.contains_key(&note.midi));
+                tuning.set_measured_cents(session.measured_cents.get(&note.midi).copied());
+
+                // Final cents of tuned notes, by tuning-order position
+                let mut quality = vec![None; self.tuning_order.len()];
+                for cn in session.completed_notes.iter().filter(|cn| !cn.skipped) {
+                    let position = crate::tuning::notes::Note::from_name(&cn.note)
+                        .and_then(|n| self.tuning_order.position_of(n.midi));
+                    if let Some(slot) = position.and_then(|p| quality.get_mut(p)) {
+                        *slot = Some(cn.final_cents);
+                    }
+                }
+                tuning.set_note_quality(quality);
+            }
+            self.tuning = Some(tuning);
+            self.capture = None;
+            self.false_beats.reset();
+            self.pitch_lock.reset();
+            self.log(
+                Level::Info,
+                "note_started",
+                &[("target", &format!("{:.2}", target_freq))],
+            );
+
+            // A reference tone playing moves on to the new note
+            if self.playing_reference() {
+                self.play_reference();
+            }
+        }
+    }
+
+    /// Update with a detection dated by the input it came from: the end of
+    /// its window, as a sample index counted from the start of capture.
+    ///
+    /// Readings are timed by when the audio was captured rather than when
+    /// it was read, so late or bunched reads don't skew the stability and
+    /// beat measurements.
+    pub fn update_pitch_frame(&mut self, frame: &PitchFrame, sample_rate: u32) {
+        let now = self.capture_time(frame.end, sample_rate);
+        match &frame.result {
+            Some(result) => self.update_pitch_result_at(now, result),
+            None => self.clear_pitch_at(now),
+        }
+    }
+
+    /// Time on the app clock at which sample `index` of the input was
+    /// captured. The newest sample of the first frame is taken as captured
+    /// on arrival, and later ones by their distance from it; the count
+    /// starts over if capture restarts or drifts too far from the clock.
+    fn capture_time(&mut self, index: u64, sample_rate: u32) -> Duration {
+        let now = self.clock.now();
+        let offset = Duration::from_secs_f64(index as f64 / sample_rate.max(1) as f64);
+        let time = match self.capture_epoch.map(|epoch| epoch + offset) {
+            Some(time)
+                if time <= now
+                    && now - time <= Self::MAX_CAPTURE_LAG
+                    && time >= self.last_capture =>
+            {
+                time
+            }
+            _ => {
+                self.capture_epoch = now.checked_sub(offset);
+                now
+            }
+        };
+        self.last_capture = time;
+        time
+    }
+
+    /// Update with a detector result, ignoring readings of low clarity or
+    /// with more than one note sounding.
+    pub fn update_pitch_result(&mut self, result: &PitchResult) {
+        self.update_pitch_result_at(self.clock.now(), result);
+    }
+
+    fn update_pitch_result_at(&mut self, now: Duration, result: &PitchResult) {
+        // Loud but noisy input isn't a reading, nor are two notes at once
+        if result.clarity < Self::MIN_CLARITY || result.ambiguous {
+            self.clear_pitch_at(now);
+        } else {
+            self.update_pitch_at(now, result.frequency, result.confidence);
+        }
+
+        let tuning = self
+            .tuning
+            .as_mut()
+            .filter(|_| self.state == AppState::Tuning);
+        if let Some(tuning) = tuning {
+            tuning.set_ambiguous(result.ambiguous);
+        }
+    }
+
+    /// Update with detected pitch.
+    pub fn update_pitch(&mut self, freq: f32, confidence: f32) {
+        self.update_pitch_at(self.clock.now(), freq, confidence);
+    }
+
+    /// Update with a pitch detected in input captured at `now`.
+    fn update_pitch_at(&mut self, now: Duration, freq: f32, confidence: f32) {
+        self.sync_reference();
+        if self.is_idle() || self.detection_paused() {
+            return;
+        }
+        if !self.pitch_lock.update(Some(confidence)) {
+            // Not confident for long enough to show
+            self.drop_pitch(now);
+            return;
+        }
+        if !self.pitch_lock.accepts(confidence) {
+            // A dropout within a held reading
+            return;
+        }
+
+        match self.state {
+            AppState::Calibration if confidence > 0.8 && self.noise_check.is_none() => {
+                self.calibration.update(freq);
+                if self.calibration.is_complete() {
+                    if let Some(a4) = self.calibration.result() {
+                        // A piano a semitone or more away is transposed
+                        // rather than given an A4 that far off
+                        let (a4, transpose) = Temperament::split_transposition(a4);
+                        self.temperament = Temperament::with_a4(a4);
+                        self.transpose = transpose;
+                    }
+                    self.start_tuning();
+                }
+            }
+            AppState::Tuning if self.capture.is_some() => {
+                let Some(capture) = &mut self.capture else {
+                    return;
+                };
+                let captured = if confidence > 0.6 {
+                    capture.update(freq)
+                } else {
+                    capture.clear();
+                    None
+                };
+                let progress = capture.progress();
+                match captured {
+                    Some(freq) => self.apply_captured_target(freq),
+                    None => {
+                        if let Some(tuning) = &mut self.tuning {
+                            tuning.set_capture(Some(progress));
+                        }
+                    }
+                }
+            }
+            AppState::Tuning => {
+                let mut locked = false;
+                if let Some(tuning) = &mut self.tuning {
+                    let cents = self
+                        .temperament
+                        .cents_from_target(freq, tuning.target_freq())
+                        .filter(|_| confidence > 0.6);
+                    if let Some(cents) = cents {
+                        tuning.update(now, freq, cents);
+                        tuning.set_confidence(confidence);
+                    } else {
+                        tuning.clear(now);
+                    }
+                    self.false_beats.push_cents(now, cents);
+                    locked = tuning.check_lock(now);
+                }
+                if locked {
+                    self.request_cue(Cue::Lock);
+                }
+                self.sync_false_beats();
+            }
+            AppState::Measure => {
+                let Some(note) = self.tuning_order.note_at(self.current_note_idx) else {
+                    return;
+                };
+                let target = self.scheme_freq(note.midi);
+                let cents = self
+                    .temperament
+                    .cents_from_target(freq, target)
+                    .filter(|_| confidence > 0.6);
+                let captured = match (&mut self.measure, cents) {
+                    (Some(measure), Some(cents)) => measure.update(cents),
+                    (Some(measure), None) => {
+                        measure.clear();
+                        None
+                    }
+                    (None, _) => None,
+                };
+                if let Some(cents) = captured {
+                    self.record_measurement(cents);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Clear pitch detection (silence).
+    pub fn clear_pitch(&mut self) {
+        self.clear_pitch_at(self.clock.now());
+    }
+
+    /// Clear pitch detection for input captured at `now`.
+    fn clear_pitch_at(&mut self, now: Duration) {
+        self.sync_reference();
+        if self.is_idle() || self.detection_paused() {
+            return;
+        }
+        // A brief dropout keeps the reading
+        if self.pitch_lock.update(None) {
+            return;
+        }
+        self.drop_pitch(now);
+    }
+
+    /// Stop showing a reading.
+    fn drop_pitch(&mut self, now: Duration) {
+        match self.state {
+            AppState::Calibration => {
+                self.calibration.clear();
+            }
+            AppState::Tuning => {
+                if let Some(tuning) = &mut self.tuning {
+                    tuning.clear(now);
+                    tuning.check_lock(now);
+                    if let Some(capture) = &mut self.capture {
+                        capture.clear();
+                        tuning.set_capture(Some(capture.progress()));
+                    }
+                }
+                self.false_beats.push_cents(now, None);
+            }
+            AppState::Measure => {
+                if let Some(measure) = &mut self.measure {
+                    measure.clear();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Update with the current input level (RMS).
+    pub fn update_level(&mut self, rms: f32) {
+        if rms >= self.noise_gate {
+            self.mark_activity();
+        }
+        if self.is_idle() {
+            return;
+        }
+
+        self.sync_reference();
+        if let Some(tuning) = self
+            .tuning
+            .as_mut()
+            .filter(|_| self.state == AppState::Tuning)
+        {
+            tuning.set_level(rms);
+        }
+        if self.state == AppState::Tuning
+            && self.listens_for_false_beats()
+            && !self.detection_paused()
+        {
+            self.false_beats.push_level(self.clock.now(), rms);
+            self.sync_false_beats();
+        }
+
+        if self.state == AppState::IntervalCheck {
+            let now = self.clock.now();
+            if let Some(check) = &mut self.interval_check {
+                check.checker_mut().push_level(now, rms);
+                let progress = check.checker().listen_progress(now);
+                check.set_listen_progress(progress);
+            }
+        }
+    }
+
+    /// Update with the most recent input samples (for beat analysis).
+    ///
+    /// On unison steps the last [`BEAT_WINDOW`](Self::BEAT_WINDOW) of samples
+    /// is analyzed for beats between the strings. During interval checks,
+    /// once the struck notes have rung long enough the last
+    /// [`IntervalChecker::ANALYSIS_WINDOW`] of samples is analyzed.
+    pub fn update_samples(&mut self, samples: &[f32], sample_rate: u32) {
+        if self.is_idle() {
+            return;
+        }
+        match self.state {
+            AppState::Calibration => self.update_noise_check(samples, sample_rate),
+            AppState::Tuning => self.update_unison_beats(samples, sample_rate),
+            AppState::IntervalCheck => self.update_interval_check(samples, sample_rate),
+            _ => {}
+        }
+    }
+
+    /// Measure the beating between strings sounding together.
+    fn update_unison_beats(&mut self, samples: &[f32], sample_rate: u32) {
+        let paused = self.detection_paused();
+        let Some(tuning) = &mut self.tuning else {
+            return;
+        };
+        if paused || !tuning.tuning_step().is_some_and(|step| step.is_unison()) {
+            return;
+        }
+
+        let window = (Self::BEAT_WINDOW.as_secs_f32() * sample_rate as f32) as usize;
+        let recent = &samples[samples.len().saturating_sub(window)..];
+        let rate = BeatDetector::new(sample_rate).detect(recent, tuning.target_freq());
+        tuning.set_measured_beats(rate);
+    }
+
+    /// Analyze the interval being checked once it has rung long enough.
+    fn update_interval_check(&mut self, samples: &[f32], sample_rate: u32) {
+        let now = self.clock.now();
+        let Some(check) = &mut self.interval_check else {
+            return;
+        };
+        let checker = check.checker_mut();
+        if !checker.is_ready(now) {
+            return;
+        }
+        let (Some(current), Some((lower, upper))) =
+            (checker.current().copied(), checker.current_frequencies())
+        else {
+            return;
+        };
+
+        let window = (IntervalChecker::ANALYSIS_WINDOW.as_secs_f32() * sample_rate as f32) as usize;
+        let recent = &samples[samples.len().saturating_sub(window)..];
+        let measured = BeatDetector::new(sample_rate).detect_interval(
+            recent,
+            lower,
+            upper,
+            current.kind.partials(),
+        );
+        checker.record(measured);
+        check.set_listen_progress(0.0);
+
+        if self.interval_checks_done() {
+            self.finish_interval_check();
+        }
+    }
+
+    /// Whether a single string or a unison is sounding (not a muting step).
+    fn listens_for_false_beats(&self) -> bool {
+        self.tuning
+            .as_ref()
+            .and_then(|t| t.tuning_step())
+            .map(|step| !step.is_muting())
+            .unwrap_or(true)
+    }
+
+    /// Copy the false-beat flag onto the tuning screen once raised.
+    fn sync_false_beats(&mut self) {
+        if self.false_beats.is_flagged() {
+            if let Some(tuning) = &mut self.tuning {
+                tuning.set_false_beats(true);
+            }
+        }
+    }
+
+    /// Whether the current step may be confirmed: muting steps always, and
+    /// sounding steps once steady if that is required.
+    fn can_confirm(&self) -> bool {
+        let Some(tuning) = &self.tuning else {
+            return false;
+        };
+        let muting = tuning.tuning_step().is_some_and(|s| s.is_muting());
+        !self.require_steady || muting || tuning.is_steady()
+    }
+
+    /// Confirm current note is tuned.
+    fn confirm_note(&mut self) {
+        if !self.can_confirm() {
+            let stability = self
+                .tuning
+                .as_ref()
+                .and_then(|t| t.stability())
+                .map_or("none", |s| s.label());
+            self.log(Level::Info, "confirm_blocked", &[("stability", &stability)]);
+            return;
+        }
+        self.request_cue(Cue::Confirm);
+
+        if let Some(tuning) = &self.tuning {
+            let cents = format!("{:+.1}", tuning.cents());
+            if tuning.tuning_step().and_then(|s| s.next()).is_some() {
+                self.log(Level::Info, "step_confirmed", &[("cents", &cents)]);
+            } else {
+                let false_beats = tuning.has_false_beats();
+                self.log(
+                    Level::Info,
+                    "note_confirmed",
+                    &[("cents", &cents), ("false_beats", &false_beats)],
+                );
+            }
+        }
+
+        if let Some(tuning) = &mut self.tuning {
+            // For multi-string notes (bichord/trichord), advance through steps
+            if tuning.is_multi_string() && tuning.next_step() {
+                self.false_beats.reset();
+                return;
+            }
+
+            // Record completion
+            tuning.record_step();
+            if let Some(session) = &mut self.session {
+                if let Some(note) = self.tuning_order.note_at(self.current_note_idx) {
+                    session.record_note(
+                        CompletedNote::new(note.display_name(), tuning.cents())
+                            .with_false_beats(tuning.has_false_beats())
+                            .with_strings(tuning.string_readings())
+                            .with_target_offset(tuning.target_offset())
+                            .with_detection(tuning.confidence(), tuning.spread()),
+                    );
+                }
+            }
+
+            self.advance_to_next_note();
+        }
+    }
+
+    /// Go back to previous step or previous note.
+    fn go_back(&mut self) {
+        self.log(Level::Info, "back", &[]);
+
+        // Try to go to previous step first
+        if let Some(tuning) = &mut self.tuning {
+            if tuning.prev_step() {
+                return;
+            }
+        }
+
+        // Otherwise go to previous note
+        if self.current_note_idx > 0 {
+            self.current_note_idx -= 1;
+            self.setup_current_note();
+
+            // For multi-string notes, go to last step
+            if let Some(tuning) = &mut self.tuning {
+                while tuning.next_step() {}
+            }
+
+            // Update session
+            if let Some(session) = &mut self.session {
+                session.current_note_index = self.current_note_idx;
+            }
+        }
+    }
+
+    /// Skip current note.
+    fn skip_note(&mut self) {
+        self.log(Level::Info, "note_skipped", &[]);
+
+        // Record as skipped
+        if let Some(session) = &mut self.session {
+            if let Some(note) = self.tuning_order.note_at(self.current_note_idx) {
+                session.record_note(CompletedNote::skipped(note.display_name()));
+            }
+        }
+
+        self.advance_to_next_note();
+    }
+
+    /// Advance to the next note.
+    fn advance_to_next_note(&mut self) {
+        self.current_note_idx += 1;
+
+        if self.current_note_idx >= self.tuning_order.len() {
+            self.finish_session();
+        } else {
+            self.setup_current_note();
+
+            // Update session progress
+            if let Some(session) = &mut self.session {
+                session.current_note_index = self.current_note_idx;
+            }
+            self.save_session();
+        }
+    }
+
+    /// Finish the tuning session.
+    fn finish_session(&mut self) {
+        self.complete = Some(self.complete_screen());
+        self.set_state(AppState::Complete);
+        self.run_session_hook();
+    }
+
+    /// Start the session hook in the background, if configured.
+    fn run_session_hook(&mut self) {
+        let (Some(hook), Some(session)) = (&self.session_hook, &self.session) else {
+            return;
+        };
+
+        match hook.run(session) {
+            Ok(run) => {
+                self.log(Level::Info, "hook_started", &[("command", &run.command())]);
+                self.hook_run = Some(run);
+            }
+            Err(e) => self.hook_failed(&e.to_string()),
+        }
+    }
+
+    /// Report the outcome of the session hook once it has finished.
+    pub fn poll_hook(&mut self) {
+        let Some(run) = &mut self.hook_run else {
+            return;
+        };
+
+        match run.try_wait() {
+            Ok(None) => {}
+            Ok(Some(status)) if status.success() => {
+                self.hook_run = None;
+                self.log(Level::Info, "hook_finished", &[]);
+                if let Some(complete) = &mut self.complete {
+                    complete.set_toast(strings::get("complete.hook_finished"), false);
+                }
+            }
+            Ok(Some(status)) => {
+                self.hook_run = None;
+                self.hook_failed(&status.to_string());
+            }
+            Err(e) => {
+                self.hook_run = None;
+                self.hook_failed(&e.to_string());
+            }
+        }
+    }
+
+    /// Log and show a failed session hook.
+    fn hook_failed(&mut self, error: &str) {
+        self.log(Level::Error, "hook_failed", &[("error", &error)]);
+        if let Some(complete) = &mut self.complete {
+            complete.set_toast(
+                strings::format("complete.hook_failed", &[("error", &error)]),
+                true,
+            );
+        }
+    }
+
+    /// Build the complete screen from the current session.
+    fn complete_screen(&self) -> CompleteScreen {
+        match &self.session {
+            Some(session) => CompleteScreen::new(session.completed_notes.clone())
+                .with_interval_checks(session.interval_checks.clone())
+                .with_duration(session.duration_secs())
+                .with_temperament(session.temperament_name())
+                .with_stretch(session.stretch.then_some(session.stretch_intensity)),
+            None => CompleteScreen::new(Vec::new()),
+        }
+    }
+
+    /// Start interval checks on the finished tuning.
+    fn start_interval_check(&mut self) {
+        self.interval_check = Some(IntervalCheckScreen::new(
+            IntervalChecker::new(self.temperament.transposed(self.transpose_cents()))
+                .with_stretch(self.stretch_curve()),
+        ));
+        self.set_state(AppState::IntervalCheck);
+    }
+
+    /// Whether every interval check has been run or skipped.
+    fn interval_checks_done(&self) -> bool {
+        self.interval_check
+            .as_ref()
+            .is_none_or(|check| check.checker().is_complete())
+    }
+
+    /// Store interval check results and return to the complete screen.
+    fn finish_interval_check(&mut self) {
+        if let Some(check) = self.interval_check.take() {
+            let results = check.checker().results().to_vec();
+            if let Some(session) = &mut self.session {
+                session.interval_checks.extend(results);
+            }
+            self.save_session();
+        }
+        self.finish_session();
+    }
+
+    /// Reset to start a new session.
+    fn reset(&mut self) {
+        self.set_state(AppState::ModeSelect);
+        self.session = None;
+        self.tuning = None;
+        self.complete = None;
+        self.measure = None;
+        self.measure_summary = None;
+        self.measurement = None;
+        self.interval_check = None;
+        self.current_note_idx = 0;
+        self.mode_select = self.new_mode_select();
+        self.calibration = self.new_calibration();
+        self.noise_check = None;
+    }
+
+    /// Render the current screen.
+    pub fn render(&self, frame: &mut Frame) {
+        let area = frame.area();
+
+        match self.state {
+            AppState::ModeSelect => {
+                frame.render_widget(&self.mode_select, area);
+            }
+            AppState::Setup => {
+                frame.render_widget(&self.setup, area);
+            }
+            AppState::Calibration => {
+                frame.render_widget(&self.calibration, area);
+            }
+            AppState::Tuning => {
+                if let Some(tuning) = &self.tuning {
+                    frame.render_widget(tuning, area);
+                }
+            }
+            AppState::Complete => {
+                if let Some(complete) = &self.complete {
+                    frame.render_widget(complete, area);
+                }
+            }
+            AppState::Measure => {
+                if let Some(measure) = &self.measure {
+                    frame.render_widget(measure, area);
+                }
+            }
+            AppState::MeasureSummary => {
+                if let Some(summary) = &self.measure_summary {
+                    frame.render_widget(summary, area);
+                }
+            }
+            AppState::IntervalCheck => {
+                if let Some(check) = &self.interval_check {
+                    frame.render_widget(check, area);
+                }
+            }
+            AppState::DeviceSelect => {
+                frame.render_widget(&self.device_select, area);
+            }
+            AppState::History => {
+                if let Some(history) = &self.history {
+                    frame.render_widget(history, area);
+                }
+            }
+        }
+
+        if let Some(error) = &self.input_lost {
+            let overlay = DeviceLostOverlay {
+                error,
+                saved: self.session.is_some(),
+            };
+            frame.render_widget(overlay, area);
+        } else if self.is_idle() {
+            frame.render_widget(IdleOverlay, area);
+        } else if self.curve_view && matches!(self.state, AppState::Setup | AppState::Tuning) {
+            let (chart, name, stretch) = self.curve_chart();
+            let overlay = CurveOverlay {
+                chart: &chart,
+                name,
+                stretch,
+            };
+            frame.render_widget(overlay, area);
+        }
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::PartialTracker;
+    use crate::clock::ManualClock;
+    use crate::tuning::interval::{Grade, IntervalKind};
+    use crate::tuning::{CurveSource, TargetCapture};
+
+    const SAMPLE_RATE: u32 = 44100;
+
+    /// Deviations scripted for the first five notes of the tuning order.
+    const DEVIATIONS: [f32; 5] = [-20.0, -16.0, -24.0, -12.0, -18.0];
+
+    /// Run a measurement pass: five scripted notes, the rest skipped.
+    fn measured_app() -> App {
+        let mut app = App::new();
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.state(), AppState::Setup);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.state(), AppState::Measure);
+
+        let temperament = Temperament::new();
+        let order = TuningOrder::new();
+        for (i, &cents) in DEVIATIONS.iter().enumerate() {
+            let note = order.note_at(i).unwrap();
+            let freq = temperament.frequency(note.midi) * Temperament::cents_to_ratio(cents);
+            for _ in 0..6 {
+                app.update_pitch(freq, 0.9);
+            }
+        }
+        for _ in DEVIATIONS.len()..88 {
+            app.handle_key(KeyCode::Char('s'));
+        }
+
+        app
+    }
+
+    #[test]
+    fn test_measurement_records_deviations() {
+        let app = measured_app();
+        assert_eq!(app.state(), AppState::MeasureSummary);
+
+        let measurement = app.measurement().unwrap();
+        assert_eq!(measurement.mode, TuningMode::Measurement);
+
+        let measured = measurement.measurements();
+        assert_eq!(measured.len(), DEVIATIONS.len());
+
+        let order = TuningOrder::new();
+        for (i, &cents) in DEVIATIONS.iter().enumerate() {
+            let midi = order.note_at(i).unwrap().midi;
+            assert!(
+                (measured[&midi] - cents).abs() < 0.1,
+                "Note {} measured {} expected {}",
+                midi,
+                measured[&midi],
+                cents
+            );
+        }
+    }
+
+    #[test]
+    fn test_measurement_hands_off_to_pitch_raise() {
+        let mut app = measured_app();
+        app.handle_key(KeyCode::Char('r'));
+
+        assert_eq!(app.state(), AppState::Tuning);
+        let session = app.session().unwrap();
+        assert_eq!(session.mode, TuningMode::PitchRaise);
+        assert_eq!(session.measured_cents.len(), DEVIATIONS.len());
+
+        // First note is overpulled by a quarter of its measured deviation
+        let midi = TuningOrder::new().note_at(0).unwrap().midi;
+        let expected = Temperament::new().frequency(midi)
+            * Temperament::cents_to_ratio(pitch_raise::overpull_cents(DEVIATIONS[0]));
+        let target = app.current_target_freq().unwrap();
+        assert!(
+            (target - expected).abs() < 0.01,
+            "Target {} expected {}",
+            target,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_measurement_hands_off_to_fine_tuning() {
+        let mut app = measured_app();
+        app.handle_key(KeyCode::Char('f'));
+
+        assert_eq!(app.state(), AppState::Tuning);
+        let session = app.session().unwrap();
+        assert_eq!(session.mode, TuningMode::Concert);
+        assert_eq!(session.measured_cents.len(), DEVIATIONS.len());
+
+        let midi = TuningOrder::new().note_at(0).unwrap().midi;
+        let expected = Temperament::new().frequency(midi);
+        assert!((app.current_target_freq().unwrap() - expected).abs() < 0.01);
+    }
+
+    /// Two piano-like tones (six harmonic partials each) played together.
+    fn interval_mix(lower: f32, upper: f32, duration_secs: f32) -> Vec<f32> {
+        let num_samples = (SAMPLE_RATE as f32 * duration_secs) as usize;
+        (0..num_samples)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                (1..=6)
+                    .map(|k| {
+                        let phase = 2.0 * std::f32::consts::PI * k as f32 * t;
+                        ((phase * lower).sin() + (phase * upper).sin()) / k as f32
+                    })
+                    .sum::<f32>()
+                    * 0.1
+            })
+            .collect()
+    }
+
+    /// Strike an interval, let it ring, and hand the recording to the app.
+    fn play_interval(app: &mut App, clock: &ManualClock, samples: &[f32]) {
+        app.update_level(0.0);
+        clock.advance(Duration::from_millis(100));
+        app.update_level(0.2);
+        clock.advance(IntervalChecker::LISTEN_TIME);
+        app.update_level(0.1);
+        app.update_samples(samples, SAMPLE_RATE);
+    }
+
+    #[test]
+    fn test_interval_check_grades_and_records() {
+        let clock = ManualClock::new();
+        let mut session = Session::new(TuningMode::Concert, 440.0);
+        session.current_note_index = 88;
+        let mut app = App::with_session(session).with_clock(clock.clone());
+        assert_eq!(app.state(), AppState::Complete);
+
+        app.handle_key(KeyCode::Char('i'));
+        assert_eq!(app.state(), AppState::IntervalCheck);
+
+        // Skip the octaves and fifths, then the first third
+        for _ in 0..11 {
+            app.handle_key(KeyCode::Char('s'));
+        }
+
+        let temperament = Temperament::new();
+        let curve = StretchCurve::new();
+        let stretched = |midi: u8| curve.apply(temperament.frequency(midi), midi);
+
+        // A3–C#4 played as a pure 5:4 third: no beats where ~9/s are expected
+        let lower = stretched(57);
+        play_interval(&mut app, &clock, &interval_mix(lower, lower * 1.25, 3.0));
+        assert_eq!(app.state(), AppState::IntervalCheck);
+
+        // C4–E4 beating at the expected rate
+        let expected = temperament.beat_rate(60, 64, IntervalKind::MajorThird, Some(&curve));
+        let lower = stretched(60);
+        let upper = (5.0 * lower - expected) / 4.0;
+        play_interval(&mut app, &clock, &interval_mix(lower, upper, 3.0));
+
+        assert_eq!(app.state(), AppState::Complete);
+        let checks = &app.session().unwrap().interval_checks;
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[0].label, "A3–C#4 major third");
+        assert_eq!(checks[0].grade, Grade::Poor);
+        assert_eq!(checks[1].label, "C4–E4 major third");
+        assert_eq!(checks[1].grade, Grade::Good, "{:?}", checks[1]);
+    }
+
+    #[test]
+    fn test_calibration_preset_sets_a4() {
+        let mut app = App::new();
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.state(), AppState::Calibration);
+
+        app.handle_key(KeyCode::Char('1'));
+        assert_eq!(app.state(), AppState::Tuning);
+        let session = app.session().unwrap();
+        assert_eq!(session.mode, TuningMode::Quick);
+        assert_eq!(session.a4_reference, 415.0);
+    }
+
+    #[test]
+    fn test_calibration_preset_from_config() {
+        let mut app = App::new().with_a4_presets(vec![430.0, 432.0]);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Char('2'));
+        assert_eq!(app.session().unwrap().a4_reference, 432.0);
+
+        // Keys without a preset keep listening
+        let mut app = App::new().with_a4_presets(vec![430.0]);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Char('5'));
+        assert_eq!(app.state(), AppState::Calibration);
+    }
+
+    #[test]
+    fn test_transposed_session() {
+        let mut app = App::new().with_transpose(-100.0);
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.session().unwrap().transpose_cents, -100.0);
+        assert_eq!(app.session().unwrap().a4_reference, 440.0);
+
+        // A4 is tuned to concert G#4
+        let g_sharp = Temperament::new().frequency(68);
+        app.current_note_idx = app.tuning_order.position_of(69).unwrap();
+        app.setup_current_note();
+        assert!((app.current_target_freq().unwrap() - g_sharp).abs() < 1e-3);
+        let tuning = app.tuning.as_ref().unwrap();
+        assert_eq!(tuning.target_freq(), app.current_target_freq().unwrap());
+
+        // Readings are measured against the transposed target
+        app.update_pitch(g_sharp, 0.95);
+        assert!(app.tuning.as_ref().unwrap().cents().abs() < 0.1);
+    }
+
+    #[test]
+    fn test_calibration_transposes_a_semitone_flat_piano() {
+        let mut app = App::new();
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        for _ in 0..20 {
+            app.update_pitch(412.0, 0.95);
+        }
+        assert_eq!(app.state(), AppState::Tuning);
+
+        let session = app.session().unwrap();
+        assert_eq!(session.transpose_cents, -100.0);
+        assert!((session.a4_reference - 436.5).abs() < 0.1);
+        app.current_note_idx = app.tuning_order.position_of(69).unwrap();
+        app.setup_current_note();
+        assert!((app.current_target_freq().unwrap() - 412.0).abs() < 1e-2);
+
+        // A piano less than half a semitone off keeps its A4
+        let mut app = App::new();
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        for _ in 0..20 {
+            app.update_pitch(432.0, 0.95);
+        }
+        let session = app.session().unwrap();
+        assert_eq!(session.transpose_cents, 0.0);
+        assert!((session.a4_reference - 432.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_room_noise_suggestion_accepted() {
+        let clock = ManualClock::new();
+        let mut app = App::new().with_clock(clock.clone()).with_pitch_lock(1, 1);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Char('n'));
+
+        // Fan hum at -40 dB; pitches heard meanwhile aren't calibration
+        let hum: Vec<f32> = (0..SAMPLE_RATE as usize * 3)
+            .map(|i| {
+                let phase = 2.0 * std::f32::consts::PI * 120.0 * i as f32 / SAMPLE_RATE as f32;
+                0.01 * std::f32::consts::SQRT_2 * phase.sin()
+            })
+            .collect();
+        clock.advance(Duration::from_secs(1));
+        app.update_samples(&hum, SAMPLE_RATE);
+        app.update_pitch(440.0, 0.95);
+        assert!(app.calibration.progress() == 0.0);
+        assert_eq!(app.take_noise_request(), None);
+
+        clock.advance(Duration::from_secs(1));
+        app.update_samples(&hum, SAMPLE_RATE);
+        app.handle_key(KeyCode::Char('y'));
+        let settings = app.take_noise_request().expect("Settings should be saved");
+        assert!((settings.noise_gate - 0.04).abs() < 1e-3);
+        assert!(settings.min_confidence > 0.75);
+        assert_eq!(app.noise_gate(), settings.noise_gate);
+        assert_eq!(app.min_confidence(), settings.min_confidence);
+
+        // The hum no longer counts as activity, nor as a pitch
+        assert!(!app.pitch_lock.accepts(0.7));
+        clock.advance(App::DEFAULT_IDLE_TIMEOUT);
+        app.update_level(0.01);
+        assert!(app.is_idle());
+
+        // Discarding keeps the settings; calibration goes on
+        let mut app = App::new().with_clock(clock.clone());
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Char('n'));
+        clock.advance(NoiseFloor::MEASURE_TIME);
+        app.update_samples(&hum, SAMPLE_RATE);
+        app.handle_key(KeyCode::Esc);
+        assert_eq!(app.state(), AppState::Calibration);
+        assert_eq!(app.noise_gate(), App::NOISE_FLOOR);
+        assert_eq!(app.take_noise_request(), None);
+        app.update_pitch(440.0, 0.95);
+        assert!(app.calibration.progress() > 0.0);
+    }
+
+    #[test]
+    fn test_setup_a4_preset_and_steps() {
+        let mut app = App::new();
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.state(), AppState::Setup);
+        app.handle_key(KeyCode::Char('p'));
+        assert_eq!(app.setup().a4().value(), 441.0);
+        for _ in 0..3 {
+            app.handle_key(KeyCode::Up);
+        }
+        app.handle_key(KeyCode::Right);
+        app.handle_key(KeyCode::Right);
+        app.handle_key(KeyCode::Enter);
+
+        let session = app.session().unwrap();
+        assert_eq!(session.mode, TuningMode::Concert);
+        assert_eq!(session.a4_reference, 442.0);
+        let a4 = Temperament::with_a4(442.0).frequency(69);
+        assert!((app.temperament.frequency(69) - a4).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_harpsichord_session() {
+        let mut app = App::new();
+        app.handle_key(KeyCode::Char('i'));
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+
+        let session = app.session().unwrap();
+        assert_eq!(session.instrument, Instrument::Harpsichord);
+        assert_eq!(session.temperament, TemperamentScheme::WerckmeisterIii);
+        assert_eq!(app.tuning_order.len(), 61);
+
+        // F3 is tuned 9.7 cents above equal temperament
+        let f3 = Temperament::new().frequency(53) * Temperament::cents_to_ratio(9.7);
+        assert!((app.current_target_freq().unwrap() - f3).abs() < 1e-2);
+
+        // Every note is a bichord, down to F1
+        for _ in 0..61 {
+            let tuning = app.tuning.as_ref().unwrap();
+            assert!(tuning.is_bichord(), "{}", tuning.note_name());
+            app.handle_key(KeyCode::Char('s'));
+        }
+        assert_eq!(app.state(), AppState::Complete);
+        assert!(app.session().unwrap().is_complete());
+
+        // Interval checks expect no stretch
+        app.handle_key(KeyCode::Char('i'));
+        let checker = app.interval_check.as_ref().unwrap().checker();
+        let (lower, upper) = checker.current_frequencies().unwrap();
+        let check = checker.current().unwrap();
+        assert_eq!(lower, Temperament::new().frequency(check.lower));
+        assert_eq!(upper, Temperament::new().frequency(check.upper()));
+    }
+
+    #[test]
+    fn test_custom_temperament_session() {
+        let mut offsets = [0.0; 12];
+        offsets[4] = -10.0;
+        let custom = CustomTemperament {
+            name: "Flat E".to_string(),
+            root: 0,
+            offsets,
+        };
+        let mut app = App::new().with_custom_temperaments(vec![custom.clone()], Some(custom));
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.session().unwrap().temperament_name(), "Flat E");
+
+        let e4 = Temperament::new().frequency(64) * Temperament::cents_to_ratio(-10.0);
+        app.current_note_idx = app.tuning_order.position_of(64).unwrap();
+        app.setup_current_note();
+        assert!((app.current_target_freq().unwrap() - e4).abs() < 1e-2);
+
+        // Kept for the next session
+        assert_eq!(app.setup().custom_temperament().unwrap().name, "Flat E");
+    }
+
+    #[test]
+    fn test_temperament_root() {
+        let mut app = App::new().with_temperament_root(7);
+        app.handle_key(KeyCode::Char('i'));
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        let session = app.session().unwrap();
+        assert_eq!(session.temperament_root, 7);
+
+        // Werckmeister III laid from G: B3 takes E's offset, two cents flat
+        // rather than four sharp
+        let b3 = Temperament::new().frequency(59) * Temperament::cents_to_ratio(1.9 - 3.9);
+        app.current_note_idx = app.tuning_order.position_of(59).unwrap();
+        app.setup_current_note();
+        assert!((app.current_target_freq().unwrap() - b3).abs() < 1e-2);
+        // And A stays at the reference
+        app.current_note_idx = app.tuning_order.position_of(69).unwrap();
+        app.setup_current_note();
+        assert!((app.current_target_freq().unwrap() - 440.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_history_of_one_piano() {
+        use chrono::{Duration as Days, Utc};
+
+        let piano = format!("History test piano {}", std::process::id());
+        // Measured 90 and 30 days ago, then tuned from the last measurement
+        let session = |mode: TuningMode, days_ago: i64, a4_cents: f32, c4_cents: f32| {
+            let mut session = Session::new(mode, 440.0).with_piano(Some(piano.clone()));
+            session.id = format!("{}-{}", piano, days_ago);
+            session.created_at = Utc::now() - Days::days(days_ago);
+            if mode == TuningMode::Measurement {
+                session.complete_note("A4", a4_cents);
+                session.complete_note("C4", c4_cents);
+            } else {
+                session.measured_cents = [(69, a4_cents), (60, c4_cents)].into();
+                session.complete_note("A4", 1.0);
+            }
+            session.updated_at = session.created_at;
+            session
+        };
+        let fixtures = [
+            session(TuningMode::Measurement, 90, -1.0, -3.0),
+            session(TuningMode::Measurement, 30, -4.0, -8.0),
+            session(TuningMode::PitchRaise, 29, -4.0, -8.0),
+        ];
+        for fixture in &fixtures {
+            fixture.save().unwrap();
+        }
+
+        let mut app = App::new().with_piano_name(Some(piano.clone()));
+        app.handle_key(KeyCode::Char('h'));
+        assert_eq!(app.state(), AppState::History);
+
+        let history = app.history().unwrap();
+        assert_eq!(history.piano(), Some(piano.as_str()));
+        let modes: Vec<TuningMode> = history.sessions().iter().map(|s| s.mode).collect();
+        assert_eq!(
+            modes,
+            [
+                TuningMode::PitchRaise,
+                TuningMode::Measurement,
+                TuningMode::Measurement
+            ]
+        );
+
+        // 6 cents off a month after the first measurement
+        let drift = history.drift();
+        let measured = drift[1].unwrap();
+        assert!((measured.days - 60.0).abs() < 0.01);
+        assert_eq!(measured.start_deviation, Some(6.0));
+        assert!((measured.cents_per_month().unwrap() - 3.0).abs() < 0.01);
+        assert!(measured.a4_change.unwrap() < -0.7);
+        assert!(drift[2].is_none());
+        // The pitch raise only repeats the measurement it was seeded from
+        assert_eq!(drift[0].unwrap().cents_per_month(), None);
+
+        // Summary of the pitch raise, read-only
+        app.handle_key(KeyCode::Enter);
+        let summary = app.history().unwrap().summary().unwrap();
+        assert!(summary.is_read_only());
+        assert_eq!(summary.note_count(), 1);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Char('i'));
+        assert_eq!(app.state(), AppState::History);
+        assert!(app.session().is_none());
+        app.handle_key(KeyCode::Esc);
+        assert!(app.history().unwrap().summary().is_none());
+
+        // The next session's summary, then back to mode select
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.history().unwrap().summary().unwrap().note_count(), 2);
+        app.handle_key(KeyCode::Esc);
+        app.handle_key(KeyCode::Esc);
+        assert_eq!(app.state(), AppState::ModeSelect);
+
+        for fixture in &fixtures {
+            fixture.delete().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_setup_custom_a4() {
+        let mut app = App::new();
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        for c in "432.5".chars() {
+            app.handle_key(KeyCode::Char(c));
+        }
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.session().unwrap().a4_reference, 432.5);
+
+        // Out-of-range entries are ignored
+        let mut app = App::new();
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        for c in "4400".chars() {
+            app.handle_key(KeyCode::Char(c));
+        }
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.session().unwrap().a4_reference, 440.0);
+    }
+
+    #[test]
+    fn test_setup_selections_reach_targets() {
+        let mut app = App::new();
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        for c in "415".chars() {
+            app.handle_key(KeyCode::Char(c));
+        }
+        app.handle_key(KeyCode::Right);
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Right);
+        app.handle_key(KeyCode::Enter);
+
+        let session = app.session().unwrap().clone();
+        assert_eq!(session.a4_reference, 415.0);
+        assert_eq!(session.temperament, TemperamentScheme::WerckmeisterIii);
+        assert!(session.stretch);
+        assert_eq!(app.temperament.a4(), 415.0);
+
+        // F3 sits 9.7 cents above equal temperament from 415 Hz, stretched
+        let cents = 9.7
+            + Instrument::Piano
+                .stretch(PianoType::Upright)
+                .offset_cents(53);
+        let f3 = Temperament::with_a4(415.0).frequency(53) * Temperament::cents_to_ratio(cents);
+        app.current_note_idx = app.tuning_order.position_of(53).unwrap();
+        app.setup_current_note();
+        assert!((app.current_target_freq().unwrap() - f3).abs() < 1e-2);
+
+        // A resumed session restores the setup and the same targets
+        let mut resumed = App::with_session(session);
+        assert_eq!(resumed.setup().a4().value(), 415.0);
+        assert_eq!(
+            resumed.setup().temperament(),
+            TemperamentScheme::WerckmeisterIii
+        );
+        assert!(resumed.setup().stretch());
+        resumed.current_note_idx = resumed.tuning_order.position_of(53).unwrap();
+        resumed.setup_current_note();
+        assert!((resumed.current_target_freq().unwrap() - f3).abs() < 1e-2);
+
+        // Back out of setup to mode select
+        let mut app = App::new();
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Esc);
+        assert_eq!(app.state(), AppState::ModeSelect);
+        assert!(app.session().is_none());
+    }
+
+    #[test]
+    fn test_bichord_records_both_strings() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        assert_eq!(app.state(), AppState::Tuning);
+        let target = app.current_target_freq().unwrap();
+
+        // F3 is a bichord: left string to pitch, then right to unison
+        app.update_pitch(target * Temperament::cents_to_ratio(3.0), 0.9);
+        app.handle_key(KeyCode::Char(' '));
+        assert!(app.session().unwrap().completed_notes.is_empty());
+
+        app.update_pitch(target * Temperament::cents_to_ratio(-1.0), 0.9);
+        app.handle_key(KeyCode::Char(' '));
+
+        let note = &app.session().unwrap().completed_notes[0];
+        assert_eq!(note.note, "F3");
+        let strings: Vec<&str> = note.strings.iter().map(|r| r.string.as_str()).collect();
+        assert_eq!(strings, ["left", "right"]);
+        assert!((note.strings[0].cents - 3.0).abs() < 0.1);
+        assert!((note.strings[1].cents + 1.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_left_first_trichord_records_in_order() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_string_order(StringOrder::LeftFirst);
+
+        // Skip to A3, the first trichord
+        for _ in 0..4 {
+            app.handle_key(KeyCode::Char('s'));
+        }
+        let target = app.current_target_freq().unwrap();
+
+        let mut steps = Vec::new();
+        for cents in [0.0, 4.0, 1.0, -1.0] {
+            let step = app.tuning.as_ref().unwrap().tuning_step().unwrap();
+            steps.push(step.title());
+            app.update_pitch(target * Temperament::cents_to_ratio(cents), 0.9);
+            app.handle_key(KeyCode::Char(' '));
+        }
+        assert_eq!(
+            steps,
+            [
+                "Mute center and right strings",
+                "Tune left string",
+                "Tune center string",
+                "Tune right string",
+            ]
+        );
+
+        let note = app.session().unwrap().completed_notes.last().unwrap();
+        assert_eq!(note.note, "A3");
+        let strings: Vec<&str> = note.strings.iter().map(|r| r.string.as_str()).collect();
+        assert_eq!(strings, ["left", "center", "right"]);
+        assert!((note.strings[1].cents - 1.0).abs() < 0.1);
+    }
+
+    fn tuning_text(app: &App) -> String {
+        let area = ratatui::layout::Rect::new(0, 0, 100, 32);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        ratatui::widgets::Widget::render(app.tuning.as_ref().unwrap(), area, &mut buf);
+        (0..area.height)
+            .map(|y| {
+                (0..area.width)
+                    .map(|x| buf[(x, y)].symbol().to_string())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_stable_reading_held_after_decay() {
+        let clock = ManualClock::new();
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(clock.clone())
+            .with_hold_time(Duration::from_secs(2));
+        let target = app.current_target_freq().unwrap();
+        let freq = target * Temperament::cents_to_ratio(3.0);
+
+        // One second of steady readings, then the note dies away
+        for _ in 0..10 {
+            app.update_pitch(freq, 0.9);
+            clock.advance(Duration::from_millis(100));
+        }
+        app.update_pitch(freq, 0.2);
+        let tuning = app.tuning.as_ref().unwrap();
+        assert!(tuning.is_held());
+        assert!((tuning.cents() - 3.0).abs() < 0.1);
+        assert!(tuning_text(&app).contains("[+3.0 cents] held"));
+
+        clock.advance(Duration::from_millis(1900));
+        app.clear_pitch();
+        assert!(app.tuning.as_ref().unwrap().is_held());
+
+        clock.advance(Duration::from_millis(200));
+        app.clear_pitch();
+        assert!(!app.tuning.as_ref().unwrap().is_held());
+        assert!(tuning_text(&app).contains("Listening..."));
+
+        // A new confident reading replaces a held one immediately
+        for _ in 0..10 {
+            app.update_pitch(freq, 0.9);
+            clock.advance(Duration::from_millis(100));
+        }
+        app.clear_pitch();
+        app.update_pitch(target, 0.9);
+        let tuning = app.tuning.as_ref().unwrap();
+        assert!(!tuning.is_held());
+        assert!(tuning.cents().abs() < 0.1);
+        assert!(!tuning_text(&app).contains("held"));
+    }
+
+    #[test]
+    fn test_idle_and_wake_on_audio() {
+        let clock = ManualClock::new();
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(clock.clone())
+            .with_idle_timeout(Duration::from_secs(60));
+        let target = app.current_target_freq().unwrap();
+        let sharp = target * Temperament::cents_to_ratio(20.0);
+
+        // Quiet input doesn't count as activity
+        clock.advance(Duration::from_secs(59));
+        app.update_level(0.001);
+        assert!(!app.is_idle());
+        clock.advance(Duration::from_secs(1));
+        assert!(app.is_idle());
+
+        // Detection is paused while idle
+        app.update_pitch(sharp, 0.9);
+        assert_eq!(app.tuning.as_ref().unwrap().cents(), 0.0);
+
+        // Playing a note wakes it, and pitch updates resume
+        app.update_level(0.2);
+        assert!(!app.is_idle());
+        app.update_pitch(sharp, 0.9);
+        assert!((app.tuning.as_ref().unwrap().cents() - 20.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_wake_key_is_consumed() {
+        let clock = ManualClock::new();
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(clock.clone())
+            .with_idle_timeout(Duration::from_secs(60))
+            .with_pause_when_idle(true);
+
+        clock.advance(Duration::from_secs(90));
+        assert!(app.is_idle());
+        app.handle_key(KeyCode::Char('s'));
+        assert!(!app.is_idle());
+        assert_eq!(app.current_note_idx, 0);
+        assert_eq!(app.session().unwrap().paused_secs, 30);
+
+        app.handle_key(KeyCode::Char('s'));
+        assert_eq!(app.current_note_idx, 1);
+    }
+
+    #[test]
+    fn test_idle_disabled() {
+        let clock = ManualClock::new();
+        let app = App::new()
+            .with_clock(clock.clone())
+            .with_idle_timeout(Duration::ZERO);
+        clock.advance(Duration::from_secs(3600));
+        assert!(!app.is_idle());
+    }
+
+    fn mock_devices() -> (Vec<AudioDevice>, Vec<AudioDevice>) {
+        (
+            vec![
+                AudioDevice::new("Built-in Microphone", true),
+                AudioDevice::new("USB Interface", false),
+            ],
+            vec![AudioDevice::new("Built-in Speakers", true)],
+        )
+    }
+
+    #[test]
+    fn test_device_switch_keeps_session() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        app.set_input_device("Built-in Microphone");
+        app.handle_key(KeyCode::Char('s'));
+
+        // Opening the screen asks for an enumeration
+        app.handle_key(KeyCode::Char('d'));
+        assert_eq!(app.state(), AppState::DeviceSelect);
+        assert_eq!(app.take_device_request(), Some(DeviceRequest::Rescan));
+        let (inputs, outputs) = mock_devices();
+        app.set_devices(inputs, outputs);
+
+        // The cursor starts on the current input
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(
+            app.take_device_request(),
+            Some(DeviceRequest::OpenInput("USB Interface".to_string()))
+        );
+
+        app.input_opened("USB Interface");
+        assert_eq!(app.state(), AppState::Tuning);
+        assert_eq!(app.device_select().current_input(), Some("USB Interface"));
+        assert_eq!(app.current_note_idx, 1);
+        assert_eq!(app.session().unwrap().completed_notes.len(), 1);
+    }
+
+    #[test]
+    fn test_device_switch_failure_rolls_back() {
+        let mut app = App::new();
+        app.set_input_device("Built-in Microphone");
+        app.handle_key(KeyCode::Char('d'));
+        let (inputs, outputs) = mock_devices();
+        app.set_devices(inputs, outputs);
+
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Char('r'));
+        assert_eq!(app.take_device_request(), Some(DeviceRequest::Rescan));
+        app.handle_key(KeyCode::Enter);
+        app.take_device_request();
+        app.input_failed("USB Interface", "device busy");
+
+        assert_eq!(app.state(), AppState::DeviceSelect);
+        assert_eq!(
+            app.device_select().current_input(),
+            Some("Built-in Microphone")
+        );
+        assert!(app
+            .device_select()
+            .toast()
+            .is_some_and(|t| t.contains("still using Built-in Microphone")));
+
+        // Choosing an output
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(
+            app.take_device_request(),
+            Some(DeviceRequest::OpenOutput("Built-in Speakers".to_string()))
+        );
+
+        app.handle_key(KeyCode::Esc);
+        assert_eq!(app.state(), AppState::ModeSelect);
+    }
+
+    #[test]
+    fn test_audio_error_opens_devices() {
+        let mut app = App::new();
+        app.show_audio_error("No input device available");
+        assert_eq!(app.state(), AppState::DeviceSelect);
+        assert_eq!(app.take_device_request(), Some(DeviceRequest::Rescan));
+        assert!(app
+            .device_select()
+            .toast()
+            .is_some_and(|t| t.contains("No input device available")));
+    }
+
+    #[test]
+    fn test_confirm_requires_steady() {
+        let clock = ManualClock::new();
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(clock.clone())
+            .with_require_steady(true);
+        let target = app.current_target_freq().unwrap();
+        let step = app.tuning.as_ref().unwrap().tuning_step();
+
+        // Wavering ±4 cents around the target: confirm is refused
+        for i in 0..20 {
+            let cents = if i % 2 == 0 { 4.0 } else { -4.0 };
+            app.update_pitch(target * Temperament::cents_to_ratio(cents), 0.9);
+            clock.advance(Duration::from_millis(50));
+        }
+        assert_eq!(
+            app.tuning.as_ref().unwrap().stability(),
+            Some(crate::ui::components::Stability::Unstable)
+        );
+        app.handle_key(KeyCode::Char(' '));
+        assert_eq!(app.tuning.as_ref().unwrap().tuning_step(), step);
+
+        // The same mean held steady: confirm goes through
+        for _ in 0..20 {
+            app.update_pitch(target, 0.9);
+            clock.advance(Duration::from_millis(50));
+        }
+        assert!(app.tuning.as_ref().unwrap().is_steady());
+        app.handle_key(KeyCode::Char(' '));
+        assert_ne!(app.tuning.as_ref().unwrap().tuning_step(), step);
+    }
+
+    #[test]
+    fn test_confirm_without_steady_gate() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        let step = app.tuning.as_ref().unwrap().tuning_step();
+        app.handle_key(KeyCode::Char(' '));
+        assert_ne!(app.tuning.as_ref().unwrap().tuning_step(), step);
+    }
+
+    #[test]
+    fn test_reference_pauses_detection() {
+        let clock = ManualClock::new();
+        let mut app =
+            App::with_session(Session::new(TuningMode::Concert, 440.0)).with_clock(clock.clone());
+        let target = app.current_target_freq().unwrap();
+
+        app.handle_key(KeyCode::Char('r'));
+        assert_eq!(
+            app.take_reference_request(),
+            Some(ReferenceRequest::Play(target))
+        );
+        assert!(app.playing_reference());
+
+        // The mic hears the reference itself: nothing reaches the meter
+        for _ in 0..10 {
+            app.update_pitch(target, 0.95);
+            clock.advance(Duration::from_millis(50));
+        }
+        let tuning = app.tuning.as_ref().unwrap();
+        assert!(tuning.stability().is_none());
+        let notice = strings::get("tuning.reference_paused");
+        assert_eq!(tuning.reference_notice(), Some(notice));
+        let text = tuning_text(&app);
+        assert!(text.contains(notice), "{}", text);
+        assert!(!text.contains("+0.0 cents"), "{}", text);
+
+        // Detection resumes once the tone ends
+        clock.advance(App::DEFAULT_REFERENCE_TIMEOUT);
+        app.update_pitch(target, 0.95);
+        assert!(!app.playing_reference());
+        assert!(app.tuning.as_ref().unwrap().reference_notice().is_none());
+        assert!(tuning_text(&app).contains("+0.0 cents"));
+    }
+
+    #[test]
+    fn test_reference_toggles_and_follows_note() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(ManualClock::new());
+        let first = app.current_target_freq().unwrap();
+
+        app.handle_key(KeyCode::Char('r'));
+        assert_eq!(
+            app.take_reference_request(),
+            Some(ReferenceRequest::Play(first))
+        );
+
+        // Moving on while it plays takes the tone to the next note
+        app.handle_key(KeyCode::Char('s'));
+        let second = app.current_target_freq().unwrap();
+        assert_ne!(second, first);
+        assert_eq!(
+            app.take_reference_request(),
+            Some(ReferenceRequest::Play(second))
+        );
+
+        // R again stops it, and nothing follows the next note
+        app.handle_key(KeyCode::Char('r'));
+        assert_eq!(app.take_reference_request(), Some(ReferenceRequest::Stop));
+        assert!(!app.playing_reference());
+        app.handle_key(KeyCode::Char('s'));
+        assert_eq!(app.take_reference_request(), None);
+    }
+
+    #[test]
+    fn test_stretch_toggled_mid_session() {
+        let session = Session::new(TuningMode::Concert, 440.0).with_stretch(true);
+        let mut app = App::with_session(session).with_clock(ManualClock::new());
+        let equal = Temperament::new().frequency(21);
+        let a0 = StretchCurve::new().offset_cents(21);
+        app.current_note_idx = app.tuning_order.position_of(21).unwrap();
+        app.setup_current_note();
+        let stretched = app.tuning.as_ref().unwrap().target_freq();
+        let cents = 1200.0 * (stretched / equal).log2();
+        assert!((cents - a0).abs() < 0.01, "{}", cents);
+
+        // Off, the note starts again against the bare temperament
+        app.handle_key(KeyCode::Char('t'));
+        assert!(!app.session().unwrap().stretch);
+        assert_eq!(app.tuning.as_ref().unwrap().target_freq(), equal);
+        assert!(!tuning_text(&app).contains("stretch"));
+
+        app.handle_key(KeyCode::Char('T'));
+        assert!(app.session().unwrap().stretch);
+        assert_eq!(app.current_target_freq(), Some(stretched));
+        assert!(tuning_text(&app).contains("stretch -16.0¢"));
+    }
+
+    fn screen_text(app: &App, width: u16, height: u16) -> String {
+        let backend = ratatui::backend::TestBackend::new(width, height);
+        let mut terminal = ratatui::Terminal::new(backend).unwrap();
+        terminal.draw(|frame| app.render(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_curve_view_from_setup() {
+        let mut app = App::new();
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.state(), AppState::Setup);
+
+        app.handle_key(KeyCode::Char('v'));
+        assert!(app.curve_view());
+        let text = screen_text(&app, 80, 24);
+        assert!(text.contains("Stretch curve: Upright (off)"), "{}", text);
+        assert!(text.contains("+25"), "{}", text);
+
+        // Other keys wait until the overlay is closed
+        app.handle_key(KeyCode::Char('s'));
+        assert!(!app.setup().stretch());
+        app.handle_key(KeyCode::Esc);
+        assert!(!app.curve_view());
+        assert_eq!(app.state(), AppState::Setup);
+        assert!(!screen_text(&app, 80, 24).contains("Stretch curve"));
+    }
+
+    #[test]
+    fn test_curve_view_while_tuning() {
+        let session = Session::new(TuningMode::Concert, 440.0).with_stretch(true);
+        let mut app = App::with_session(session).with_clock(ManualClock::new());
+        app.current_note_idx = app.tuning_order.position_of(36).unwrap();
+        app.setup_current_note();
+
+        app.handle_key(KeyCode::Char('V'));
+        let text = screen_text(&app, 80, 24);
+        assert!(text.contains("Stretch curve: Upright"), "{}", text);
+        assert!(!text.contains("(off)"), "{}", text);
+        assert!(text.contains("C2"), "{}", text);
+
+        // Narrow, the offsets are listed with the current note's
+        let text = screen_text(&app, 40, 10);
+        assert!(text.contains("C2 -5.9¢"), "{}", text);
+
+        app.handle_key(KeyCode::Char(' '));
+        assert_eq!(
+            app.tuning_order.note_at(app.current_note_idx).unwrap().midi,
+            36
+        );
+        app.handle_key(KeyCode::Char('v'));
+        assert!(!app.curve_view());
+    }
+
+    #[test]
+    fn test_octave_type_shown_while_tuning() {
+        let inharmonicity = BTreeMap::from([(21, 4e-4), (48, 1e-4), (69, 4e-4), (108, 1e-2)]);
+        let mut app = App::new()
+            .with_stretch(true)
+            .with_inharmonicity(inharmonicity.clone());
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        let session = app.session().unwrap();
+        assert_eq!(session.inharmonicity, inharmonicity);
+        assert_eq!(session.octave_type, OctaveType::ByRegister);
+
+        for (midi, octave) in [(24, "6:3"), (40, "4:2"), (72, "2:1")] {
+            app.current_note_idx = app.tuning_order.position_of(midi).unwrap();
+            app.setup_current_note();
+            let tag = format!("¢ ({} octave)", octave);
+            assert!(tuning_text(&app).contains(&tag), "{}", tuning_text(&app));
+        }
+
+        // Without inharmonicity the preset's stretch is shown alone
+        let session = Session::new(TuningMode::Concert, 440.0).with_stretch(true);
+        let mut app = App::with_session(session).with_clock(ManualClock::new());
+        app.current_note_idx = app.tuning_order.position_of(24).unwrap();
+        app.setup_current_note();
+        assert!(
+            !tuning_text(&app).contains("octave)"),
+            "{}",
+            tuning_text(&app)
+        );
+    }
+
+    #[test]
+    fn test_order_strategy_followed_and_resumed() {
+        let mut app = App::new().with_order_strategy(OrderStrategy::AuralTemperamentFirst);
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        let session = app.session().unwrap();
+        assert_eq!(session.order_strategy, OrderStrategy::AuralTemperamentFirst);
+        assert_eq!(
+            app.tuning_order.strategy(),
+            OrderStrategy::AuralTemperamentFirst
+        );
+
+        // After the temperament octave the bass comes next
+        app.current_note_idx = 13;
+        app.setup_current_note();
+        assert!(tuning_text(&app).contains("E3"), "{}", tuning_text(&app));
+
+        let mut session = app.session().unwrap().clone();
+        session.current_note_index = 13;
+        let resumed = App::with_session(session);
+        assert_eq!(
+            resumed.setup().order_strategy(),
+            OrderStrategy::AuralTemperamentFirst
+        );
+        let note = resumed
+            .tuning_order
+            .note_at(resumed.current_note_idx)
+            .unwrap();
+        assert_eq!(note.display_name(), "E3");
+
+        // Chromatic from setup starts at A0
+        let mut app = App::new().with_order_strategy(OrderStrategy::Chromatic);
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.tuning_order.note_at(0).unwrap().display_name(), "A0");
+    }
+
+    #[test]
+    fn test_stretched_reference() {
+        let session = Session::new(TuningMode::Concert, 440.0).with_stretch(true);
+        let mut app = App::with_session(session).with_clock(ManualClock::new());
+        let temperament = Temperament::new();
+        let curve = StretchCurve::new();
+
+        for (midi, text) in [(21, "stretch -16.0¢"), (108, "stretch +24.0¢")] {
+            app.current_note_idx = app.tuning_order.position_of(midi).unwrap();
+            app.setup_current_note();
+            assert!(tuning_text(&app).contains(text), "{}", tuning_text(&app));
+
+            app.handle_key(KeyCode::Char('r'));
+            let Some(ReferenceRequest::Play(freq)) = app.take_reference_request() else {
+                panic!("No reference played");
+            };
+            let cents = 1200.0 * (freq / temperament.frequency(midi)).log2();
+            assert!((cents - curve.offset_cents(midi)).abs() < 0.01, "{}", cents);
+            // The meter reads against the same target
+            assert_eq!(app.current_target_freq(), Some(freq));
+            app.handle_key(KeyCode::Char('r'));
+            app.take_reference_request();
+        }
+
+        // Without stretch the reference is the bare temperament
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        app.current_note_idx = app.tuning_order.position_of(21).unwrap();
+        app.setup_current_note();
+        assert_eq!(app.current_target_freq(), Some(temperament.frequency(21)));
+        assert!(!tuning_text(&app).contains("stretch"));
+    }
+
+    #[test]
+    fn test_stretch_intensity_from_setup() {
+        let mut app = App::new().with_stretch(true).with_stretch_intensity(1.5);
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.session().unwrap().stretch_intensity, 1.5);
+
+        app.current_note_idx = app.tuning_order.position_of(108).unwrap();
+        app.setup_current_note();
+        let stretch = 1.5 * StretchCurve::new().offset_cents(108);
+        let expected = Temperament::new().frequency(108) * Temperament::cents_to_ratio(stretch);
+        assert!((app.current_target_freq().unwrap() - expected).abs() < 1e-2);
+
+        // Resumed with the same intensity
+        let app = App::with_session(app.session().unwrap().clone());
+        assert_eq!(app.setup().stretch_intensity(), 1.5);
+    }
+
+    #[test]
+    fn test_saved_curve_restored_with_session() {
+        let curve = StretchCurve::from_points(&[(21, -30.0), (69, 0.0), (108, 35.0)]).unwrap();
+        let profile = StretchProfile::new("Studio", CurveSource::Measured, curve.clone());
+        let mut app = App::new()
+            .with_stretch(true)
+            .with_stretch_profiles(Vec::new(), Some(profile.clone()));
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+
+        // Saved and resumed, the session follows the same curve
+        let json = serde_json::to_string(app.session().unwrap()).unwrap();
+        let mut app = App::with_session(serde_json::from_str(&json).unwrap());
+        let restored = app.session().unwrap().stretch_profile.clone().unwrap();
+        assert_eq!(restored.name, "Studio");
+        assert_eq!(app.setup().curve_label(), "Studio");
+        assert_eq!(app.setup().stretch_profile(), Some(&restored));
+
+        app.current_note_idx = app.tuning_order.position_of(108).unwrap();
+        app.setup_current_note();
+        let expected = Temperament::new().frequency(108) * Temperament::cents_to_ratio(35.0);
+        assert!((app.current_target_freq().unwrap() - expected).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_octave_reference() {
+        let session = Session::new(TuningMode::Concert, 440.0).with_stretch(true);
+        let mut app = App::with_session(session).with_clock(ManualClock::new());
+        let temperament = Temperament::new();
+        let curve = StretchCurve::new();
+        let stretched = |midi: u8| curve.apply(temperament.frequency(midi), midi);
+
+        // C8 with a stretched C7 under it
+        app.current_note_idx = app.tuning_order.position_of(108).unwrap();
+        app.setup_current_note();
+        app.handle_key(KeyCode::Char('o'));
+        let Some(ReferenceRequest::PlayOctave { target, lower }) = app.take_reference_request()
+        else {
+            panic!("No octave played");
+        };
+        assert!((target - stretched(108)).abs() < 0.01);
+        assert!((lower - stretched(96)).abs() < 0.01, "{}", lower);
+        // C7 is stretched less than C8, so the octave is a little wide
+        assert!(lower < target / 2.0);
+        assert!(tuning_text(&app).contains("♪ Sine + octave below 100%"));
+
+        // The octave follows the next note, and R or O stops it
+        app.handle_key(KeyCode::Char('s'));
+        assert!(matches!(
+            app.take_reference_request(),
+            Some(ReferenceRequest::PlayOctave { .. })
+        ));
+        app.handle_key(KeyCode::Char('o'));
+        assert_eq!(app.take_reference_request(), Some(ReferenceRequest::Stop));
+
+        // A plain reference gains the octave
+        app.handle_key(KeyCode::Char('r'));
+        assert!(matches!(
+            app.take_reference_request(),
+            Some(ReferenceRequest::Play(_))
+        ));
+        app.handle_key(KeyCode::Char('o'));
+        assert!(matches!(
+            app.take_reference_request(),
+            Some(ReferenceRequest::PlayOctave { .. })
+        ));
+        app.handle_key(KeyCode::Char('r'));
+        assert_eq!(app.take_reference_request(), Some(ReferenceRequest::Stop));
+
+        // Headphones in split mode only play the target
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_reference_channels(ReferenceChannels::Split);
+        app.handle_key(KeyCode::Char('o'));
+        assert_eq!(app.take_reference_request(), None);
+    }
+
+    #[test]
+    fn test_beat_demo() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(ManualClock::new());
+        app.current_note_idx = app.tuning_order.position_of(69).unwrap();
+        app.setup_current_note();
+
+        // Arrows do nothing until the demonstration plays
+        app.handle_key(KeyCode::Up);
+        assert_eq!(app.take_reference_request(), None);
+
+        app.handle_key(KeyCode::Char('x'));
+        assert_eq!(
+            app.take_reference_request(),
+            Some(ReferenceRequest::PlayBeats {
+                target: 440.0,
+                cents: App::DEFAULT_BEAT_CENTS,
+            })
+        );
+        assert!(tuning_text(&app).contains("♪ Sine +5¢ → 1.3 beats/s [↑/↓] 100%"));
+
+        // A cent at a time, from unison up
+        app.handle_key(KeyCode::Up);
+        assert_eq!(
+            app.take_reference_request(),
+            Some(ReferenceRequest::PlayBeats {
+                target: 440.0,
+                cents: 6.0,
+            })
+        );
+        for _ in 0..10 {
+            app.handle_key(KeyCode::Down);
+        }
+        assert_eq!(
+            app.take_reference_request(),
+            Some(ReferenceRequest::PlayBeats {
+                target: 440.0,
+                cents: 0.0,
+            })
+        );
+        for _ in 0..16 {
+            app.handle_key(KeyCode::Up);
+        }
+        // 4 beats per second at A4
+        assert!(tuning_text(&app).contains("+16¢ → 4.1 beats/s"));
+
+        // The octave takes over, and X stops whatever plays
+        app.handle_key(KeyCode::Char('o'));
+        assert!(matches!(
+            app.take_reference_request(),
+            Some(ReferenceRequest::PlayOctave { .. })
+        ));
+        app.handle_key(KeyCode::Char('x'));
+        assert!(matches!(
+            app.take_reference_request(),
+            Some(ReferenceRequest::PlayBeats { cents, .. }) if cents == 16.0
+        ));
+        app.handle_key(KeyCode::Char('x'));
+        assert_eq!(app.take_reference_request(), Some(ReferenceRequest::Stop));
+        app.handle_key(KeyCode::Down);
+        assert_eq!(app.take_reference_request(), None);
+    }
+
+    #[test]
+    fn test_reference_times_out() {
+        let clock = ManualClock::new();
+        let mut app =
+            App::with_session(Session::new(TuningMode::Concert, 440.0)).with_clock(clock.clone());
+        app.handle_key(KeyCode::Char('r'));
+        app.take_reference_request();
+        assert!(tuning_text(&app).contains("Stop reference (10s)"));
+
+        // Moving on to the next note doesn't restart the countdown
+        clock.advance(Duration::from_millis(6500));
+        app.handle_key(KeyCode::Char('s'));
+        app.take_reference_request();
+        app.tick();
+        assert!(tuning_text(&app).contains("Stop reference (4s)"));
+        assert_eq!(app.take_reference_request(), None);
+
+        // The main loop hears about it without any input
+        clock.advance(Duration::from_secs(4));
+        app.tick();
+        assert!(!app.playing_reference());
+        assert_eq!(app.take_reference_request(), Some(ReferenceRequest::Stop));
+        app.tick();
+        assert_eq!(app.take_reference_request(), None);
+        assert!(tuning_text(&app).contains("Reference"));
+
+        // No timeout plays on until stopped
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(clock.clone())
+            .with_reference_timeout(Duration::ZERO);
+        app.handle_key(KeyCode::Char('r'));
+        app.take_reference_request();
+        clock.advance(Duration::from_secs(600));
+        app.tick();
+        assert!(app.playing_reference());
+        assert_eq!(app.take_reference_request(), None);
+        assert!(tuning_text(&app).contains("Stop reference"));
+        assert!(!tuning_text(&app).contains("Stop reference ("));
+    }
+
+    #[test]
+    fn test_reference_volume_keys() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(ManualClock::new())
+            .with_reference_volume(0.5);
+
+        // Only while the reference plays
+        app.handle_key(KeyCode::Char(']'));
+        assert_eq!(app.reference_volume(), 0.5);
+        assert!(!tuning_text(&app).contains("50%"));
+
+        app.handle_key(KeyCode::Char('r'));
+        assert!(tuning_text(&app).contains("♪ Sine 50% [[/]]"));
+        app.handle_key(KeyCode::Char(']'));
+        assert!((app.reference_volume() - 0.6).abs() < 1e-6);
+        for _ in 0..12 {
+            app.handle_key(KeyCode::Char('['));
+        }
+        assert_eq!(app.reference_volume(), 0.0);
+        assert!(tuning_text(&app).contains("♪ Sine 0%"));
+        for _ in 0..12 {
+            app.handle_key(KeyCode::Char(']'));
+        }
+        assert_eq!(app.reference_volume(), 1.0);
+    }
+
+    #[test]
+    fn test_split_reference_keeps_detecting() {
+        let clock = ManualClock::new();
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(clock.clone())
+            .with_reference_channels(ReferenceChannels::Split);
+        let target = app.current_target_freq().unwrap();
+        app.handle_key(KeyCode::Char('r'));
+        assert!(app.playing_reference());
+
+        // Headphones: the meter and the right channel keep following the piano
+        let piano = target * Temperament::cents_to_ratio(3.0);
+        app.update_pitch(piano, 0.9);
+        assert!(app.tuning.as_ref().unwrap().reference_notice().is_none());
+        assert_eq!(app.detected_freq(), Some(piano));
+    }
+
+    #[test]
+    fn test_reference_filtered_from_detection() {
+        let clock = ManualClock::new();
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(clock.clone())
+            .with_reference_feedback(ReferenceFeedback::Filter);
+        let target = app.current_target_freq().unwrap();
+        app.handle_key(KeyCode::Char('r'));
+
+        // Reference from the speakers plus a piano string 100 cents sharp
+        let piano = target * Temperament::cents_to_ratio(100.0);
+        let history: Vec<f32> = (0..SAMPLE_RATE)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                let phase = 2.0 * std::f32::consts::PI * t;
+                let strings: f32 = (1..=4)
+                    .map(|k| (phase * piano * k as f32).sin() / k as f32)
+                    .sum();
+                0.5 * (phase * target).sin() + 0.3 * strings
+            })
+            .collect();
+
+        let window = app.detection_window(&history, SAMPLE_RATE as usize / 10, SAMPLE_RATE);
+        let result = crate::audio::PitchDetector::new(SAMPLE_RATE)
+            .detect(&window)
+            .expect("Piano pitch should be detected");
+        app.update_pitch(result.frequency, result.confidence);
+
+        let tuning = app.tuning.as_ref().unwrap();
+        assert!(tuning.reference_notice().is_none());
+        assert!(
+            (tuning.cents() - 100.0).abs() < 2.0,
+            "cents {}",
+            tuning.cents()
+        );
+    }
+
+    #[test]
+    fn test_reference_cancelled_from_detection() {
+        let clock = ManualClock::new();
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(clock.clone())
+            .with_reference_feedback(ReferenceFeedback::Cancel);
+        let target = app.current_target_freq().unwrap();
+        app.handle_key(KeyCode::Char('r'));
+
+        // Speaker bleed, delayed by the room, and a string 20 cents flat:
+        // close enough that a notch would take the string out too
+        let string = target * Temperament::cents_to_ratio(-20.0);
+        let bleed =
+            crate::audio::ReferenceTone::new(SAMPLE_RATE).render(target, SAMPLE_RATE as usize);
+        let history: Vec<f32> = (0..SAMPLE_RATE as usize)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                let phase = 2.0 * std::f32::consts::PI * t;
+                let partials: f32 = (1..=4)
+                    .map(|k| (phase * string * k as f32).sin() / k as f32)
+                    .sum();
+                let delayed = i.checked_sub(37).map_or(0.0, |i| bleed[i]);
+                0.6 * delayed + 0.2 * partials
+            })
+            .collect();
+
+        let window = app.detection_window(&history, SAMPLE_RATE as usize / 10, SAMPLE_RATE);
+        let result = crate::audio::PitchDetector::new(SAMPLE_RATE)
+            .detect(&window)
+            .expect("String pitch should be detected");
+        app.update_pitch(result.frequency, result.confidence);
+
+        let tuning = app.tuning.as_ref().unwrap();
+        assert!(tuning.reference_notice().is_none());
+        assert!(
+            (tuning.cents() + 20.0).abs() < 1.0,
+            "cents {}",
+            tuning.cents()
+        );
+    }
+
+    #[test]
+    fn test_waveform_cycles_and_is_filtered() {
+        let clock = ManualClock::new();
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(clock.clone())
+            .with_reference_feedback(ReferenceFeedback::Filter)
+            .with_waveform(Waveform::Sawtooth);
+        let target = app.current_target_freq().unwrap();
+        assert!(tuning_text(&app).contains("[W] Sawtooth"));
+
+        // A sawtooth reference from the speakers, its harmonics and all,
+        // plus a piano string 100 cents flat
+        app.handle_key(KeyCode::Char('r'));
+        let piano = target * Temperament::cents_to_ratio(-100.0);
+        let reference = crate::audio::ReferenceTone::new(SAMPLE_RATE)
+            .with_waveform(Waveform::Sawtooth)
+            .generate(target, 1.0);
+        let history: Vec<f32> = reference
+            .iter()
+            .enumerate()
+            .map(|(i, r)| {
+                let phase = 2.0 * std::f32::consts::PI * piano * i as f32 / SAMPLE_RATE as f32;
+                0.5 * r + 0.3 * phase.sin()
+            })
+            .collect();
+        let window = app.detection_window(&history, SAMPLE_RATE as usize / 10, SAMPLE_RATE);
+        let result = crate::audio::PitchDetector::new(SAMPLE_RATE)
+            .detect(&window)
+            .expect("Piano pitch should be detected");
+        app.update_pitch(result.frequency, result.confidence);
+        let cents = app.tuning.as_ref().unwrap().cents();
+        assert!((cents + 100.0).abs() < 2.0, "cents {}", cents);
+
+        // W moves on to the next waveform, wrapping around
+        app.handle_key(KeyCode::Char('w'));
+        assert_eq!(app.waveform(), Waveform::PianoLike);
+        assert!(tuning_text(&app).contains("[W] Piano"));
+        app.handle_key(KeyCode::Char('W'));
+        assert_eq!(app.waveform(), Waveform::Sine);
+    }
+
+    #[test]
+    fn test_manual_target_override() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        let computed = app.current_target_freq().unwrap();
+
+        for _ in 0..4 {
+            app.handle_key(KeyCode::Char('>'));
+        }
+        let target = app.current_target_freq().unwrap();
+        let expected = computed * Temperament::cents_to_ratio(2.0);
+        assert!((target - expected).abs() < 1e-3, "target {}", target);
+        assert!(tuning_text(&app).contains("target +2.0¢ (manual)"));
+
+        // The reference tone plays the adjusted target
+        app.handle_key(KeyCode::Char('r'));
+        assert_eq!(
+            app.take_reference_request(),
+            Some(ReferenceRequest::Play(target))
+        );
+
+        // The override survives save and resume
+        let json = serde_json::to_string(app.session().unwrap()).unwrap();
+        let restored: Session = serde_json::from_str(&json).unwrap();
+        let mut app = App::with_session(restored);
+        assert!((app.current_target_freq().unwrap() - expected).abs() < 1e-3);
+        assert!(tuning_text(&app).contains("target +2.0¢ (manual)"));
+
+        // One key resets to the computed target
+        app.handle_key(KeyCode::Char('='));
+        assert!((app.current_target_freq().unwrap() - computed).abs() < 1e-3);
+        assert!(app.session().unwrap().target_offsets.is_empty());
+        assert!(!tuning_text(&app).contains("(manual)"));
+    }
+
+    #[test]
+    fn test_manual_target_recorded_on_note() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        app.handle_key(KeyCode::Char('<'));
+        let target = app.current_target_freq().unwrap();
+
+        // Tune both strings of F3 to the adjusted target
+        app.update_pitch(target, 0.9);
+        app.handle_key(KeyCode::Char(' '));
+        app.update_pitch(target, 0.9);
+        app.handle_key(KeyCode::Char(' '));
+
+        let note = &app.session().unwrap().completed_notes[0];
+        assert_eq!(note.target_offset, -0.5);
+        assert!(note.final_cents.abs() < 0.1);
+    }
+
+    #[test]
+    fn test_target_overrides_after_temperament_and_stretch() {
+        let mut overrides = TargetOverrides::new();
+        overrides.set(84, 1.5);
+        let profile = StretchProfile::new("Studio", CurveSource::Manual, StretchCurve::new())
+            .with_target_overrides(overrides);
+        let mut app = App::new()
+            .with_instrument(Instrument::Piano, Some(TemperamentScheme::Vallotti))
+            .with_stretch(true)
+            .with_stretch_profiles(vec![profile.clone()], Some(profile));
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        assert_eq!(app.session().unwrap().target_offset(84), 1.5);
+
+        // Temperament, then stretch, then the override
+        let vallotti = Temperament::new().with_scheme(TemperamentScheme::Vallotti, 0);
+        let stretch = StretchCurve::new().offset_cents(84);
+        let expected = vallotti.frequency(84) * Temperament::cents_to_ratio(stretch + 1.5);
+        app.current_note_idx = app.tuning_order.position_of(84).unwrap();
+        app.setup_current_note();
+        assert!((app.current_target_freq().unwrap() - expected).abs() < 1e-3);
+        assert!(tuning_text(&app).contains("target +1.5¢ (manual)"));
+
+        // Adjusted further while tuning and saved with the session
+        app.handle_key(KeyCode::Char('>'));
+        let json = serde_json::to_string(app.session().unwrap()).unwrap();
+        let restored: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.target_offset(84), 2.0);
+        assert_eq!(restored.target_offsets.len(), 1);
+
+        // Notes without an override keep the computed target
+        app.current_note_idx = app.tuning_order.position_of(83).unwrap();
+        app.setup_current_note();
+        let expected = vallotti.frequency(83)
+            * Temperament::cents_to_ratio(StretchCurve::new().offset_cents(83));
+        assert!((app.current_target_freq().unwrap() - expected).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_override_keys_save_to_profile() {
+        let profile = StretchProfile::new("Studio", CurveSource::Manual, StretchCurve::new());
+        let mut app = App::new()
+            .with_stretch(true)
+            .with_stretch_profiles(vec![profile.clone()], Some(profile));
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        app.current_note_idx = app.tuning_order.position_of(78).unwrap();
+        app.setup_current_note();
+        let computed = app.current_target_freq().unwrap();
+
+        // A cent a press, unlike the half-cent nudges
+        app.handle_key(KeyCode::Char('+'));
+        app.handle_key(KeyCode::Char('+'));
+        app.handle_key(KeyCode::Char('-'));
+        app.handle_key(KeyCode::Char('>'));
+        let expected = computed * Temperament::cents_to_ratio(1.5);
+        assert!((app.current_target_freq().unwrap() - expected).abs() < 1e-3);
+        assert_eq!(app.session().unwrap().target_offset(78), 1.5);
+
+        // The override goes back to the curve's file and the next session
+        let saved = app.take_profile_request().expect("Should save the curve");
+        assert_eq!(saved.target_overrides.get(78), 1.0);
+        assert_eq!(app.take_profile_request(), None);
+        assert_eq!(
+            app.setup().stretch_profile().unwrap().target_overrides,
+            saved.target_overrides
+        );
+        assert_eq!(app.setup().stretch_profiles()[0], saved);
+        assert_eq!(app.new_session(TuningMode::Concert).target_offset(78), 1.0);
+
+        // Without a saved curve only the session keeps it
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        app.handle_key(KeyCode::Char('+'));
+        assert_eq!(app.session().unwrap().target_offsets.len(), 1);
+        assert_eq!(app.take_profile_request(), None);
+    }
+
+    #[test]
+    fn test_session_adjustments_stay_off_profile() {
+        let profile = StretchProfile::new("Studio", CurveSource::Manual, StretchCurve::new());
+        let mut app = App::new()
+            .with_stretch(true)
+            .with_stretch_profiles(vec![profile.clone()], Some(profile));
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        app.current_note_idx = app.tuning_order.position_of(69).unwrap();
+        app.setup_current_note();
+        let computed = app.current_target_freq().unwrap();
+        app.handle_key(KeyCode::Char('+'));
+        app.take_profile_request().expect("Should save the curve");
+
+        // A captured target is kept by the session, over the override
+        app.handle_key(KeyCode::Char('m'));
+        for _ in 0..TargetCapture::REQUIRED_READINGS {
+            app.update_pitch(438.6, 0.9);
+        }
+        assert!((app.current_target_freq().unwrap() - 438.6).abs() < 0.01);
+        app.handle_key(KeyCode::Char('<'));
+        assert_eq!(app.take_profile_request(), None);
+
+        // Moving the override doesn't save the capture or the nudge
+        app.handle_key(KeyCode::Char('+'));
+        let saved = app.take_profile_request().expect("Should save the curve");
+        assert_eq!(saved.target_overrides.get(69), 2.0);
+        let expected = 438.6 * Temperament::cents_to_ratio(0.5);
+        assert!((app.current_target_freq().unwrap() - expected).abs() < 0.01);
+
+        // Resetting drops the override from the curve as well
+        app.handle_key(KeyCode::Char('='));
+        assert!((app.current_target_freq().unwrap() - computed).abs() < 1e-3);
+        let saved = app.take_profile_request().expect("Should save the curve");
+        assert!(saved.target_overrides.is_empty());
+        assert!(app.setup().stretch_profiles()[0]
+            .target_overrides
+            .is_empty());
+        assert_eq!(app.new_session(TuningMode::Concert).target_offset(69), 0.0);
+
+        // Nothing left to drop the next time
+        app.handle_key(KeyCode::Char('='));
+        assert_eq!(app.take_profile_request(), None);
+    }
+
+    #[test]
+    fn test_detection_confidence_recorded_on_note() {
+        let clock = ManualClock::new();
+        let mut app =
+            App::with_session(Session::new(TuningMode::Concert, 440.0)).with_clock(clock.clone());
+        let target = app.current_target_freq().unwrap();
+
+        // Left string of F3 confirmed with nothing detected
+        app.handle_key(KeyCode::Char(' '));
+        // Right string on a doubtful but accepted reading
+        for _ in 0..6 {
+            app.update_pitch(target, 0.65);
+            clock.advance(Duration::from_millis(50));
+        }
+        app.handle_key(KeyCode::Char(' '));
+
+        let note = &app.session().unwrap().completed_notes[0];
+        assert_eq!(note.confidence, Some(0.65));
+        assert!(note.spread.unwrap() < 0.1);
+        assert!(note.is_low_confidence());
+
+        let screen = CompleteScreen::new(app.session().unwrap().completed_notes.clone());
+        assert_eq!(screen.stats().low_confidence_notes, ["F3"]);
+        let area = ratatui::layout::Rect::new(0, 0, 80, 24);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        ratatui::widgets::Widget::render(&screen, area, &mut buf);
+        let text: String = buf.content().iter().map(|c| c.symbol()).collect();
+        assert!(text.contains("? Low confidence: F3"), "{}", text);
+    }
+
+    #[test]
+    fn test_force_confirmed_note_has_no_confidence() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        app.handle_key(KeyCode::Char(' '));
+        app.handle_key(KeyCode::Char(' '));
+
+        let note = &app.session().unwrap().completed_notes[0];
+        assert_eq!(note.confidence, None);
+        assert_eq!(note.spread, None);
+        assert!(!note.is_low_confidence());
+    }
+
+    /// App tuning A4, with a target capture started.
+    fn capturing_a4(capture_sets_a4: bool) -> App {
+        let mut session = Session::new(TuningMode::Concert, 440.0);
+        session.current_note_index = TuningOrder::new().position_of(69).unwrap();
+        let mut app = App::with_session(session).with_capture_sets_a4(capture_sets_a4);
+        app.handle_key(KeyCode::Char('m'));
+        assert!(app.tuning.as_ref().unwrap().is_capturing());
+        assert!(tuning_text(&app).contains("Capturing target"));
+
+        // The organ plays A4 at 438.6 Hz
+        for _ in 0..TargetCapture::REQUIRED_READINGS {
+            app.update_pitch(438.6, 0.9);
+        }
+        assert!(!app.tuning.as_ref().unwrap().is_capturing());
+        app
+    }
+
+    #[test]
+    fn test_capture_target_from_other_instrument() {
+        let mut app = capturing_a4(false);
+        assert!((app.current_target_freq().unwrap() - 438.6).abs() < 0.01);
+        assert!(tuning_text(&app).contains("(captured)"));
+
+        let session = app.session().unwrap();
+        assert_eq!(session.captured_targets.get(&69), Some(&438.6));
+        assert_eq!(session.a4_reference, 440.0);
+
+        // The piano matching the organ reads zero
+        app.update_pitch(438.6, 0.9);
+        assert!(app.tuning.as_ref().unwrap().cents().abs() < 0.01);
+
+        // Other notes keep the computed targets
+        assert!((app.target_freq(60) - Temperament::new().frequency(60)).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_capture_target_sets_implied_a4() {
+        let mut app = capturing_a4(true);
+        assert!((app.session().unwrap().a4_reference - 438.6).abs() < 0.01);
+
+        app.update_pitch(438.6, 0.9);
+        assert!(app.tuning.as_ref().unwrap().cents().abs() < 0.01);
+
+        // Other notes scale from the implied A4
+        let expected = Temperament::with_a4(438.6).frequency(60);
+        assert!((app.target_freq(60) - expected).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_low_clarity_is_no_reading() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        let f3 = Temperament::new().frequency(53);
+        let result = |clarity: f32| PitchResult {
+            frequency: f3,
+            confidence: 0.9,
+            tau: SAMPLE_RATE as f32 / f3,
+            rms: 0.2,
+            clarity,
+            ambiguous: false,
+        };
+
+        app.update_level(0.2);
+        app.update_pitch_result(&result(0.9));
+        assert!(app.detected_freq().is_some());
+        assert!(tuning_text(&app).contains("-14 dB"));
+
+        // Loud but noisy
+        app.update_pitch_result(&result(0.3));
+        assert!(app.detected_freq().is_none());
+    }
+
+    #[test]
+    fn test_detections_timed_by_capture() {
+        let clock = ManualClock::new();
+        clock.advance(Duration::from_secs(10));
+        let mut app =
+            App::with_session(Session::new(TuningMode::Concert, 440.0)).with_clock(clock.clone());
+        let target = app.current_target_freq().unwrap();
+        let hop = 1024u64;
+        let hop_time = hop as f64 / SAMPLE_RATE as f64;
+
+        // Reads come unevenly, some late and some bunched together; the
+        // samples don't
+        let waits = [0, 30, 20, 60, 25, 25, 0, 70, 25, 0, 40, 25];
+        let mut times = Vec::new();
+        for (i, &wait) in waits.iter().enumerate() {
+            clock.advance(Duration::from_millis(wait));
+            times.push(app.capture_time(8192 + i as u64 * hop, SAMPLE_RATE));
+        }
+        for pair in times.windows(2) {
+            assert!(pair[1] > pair[0]);
+            assert!(((pair[1] - pair[0]).as_secs_f64() - hop_time).abs() < 1e-6);
+        }
+        assert!(*times.last().unwrap() <= clock.now());
+
+        // Capture restarting counts afresh from the clock
+        clock.advance(Duration::from_millis(20));
+        let restarted = app.capture_time(512, SAMPLE_RATE);
+        assert_eq!(restarted, clock.now());
+        clock.advance(Duration::from_millis(50));
+        let next = app.capture_time(512 + hop, SAMPLE_RATE);
+        assert!(((next - restarted).as_secs_f64() - hop_time).abs() < 1e-6);
+
+        // Frames reach the meter
+        let result = PitchResult {
+            frequency: target,
+            confidence: 0.95,
+            tau: SAMPLE_RATE as f32 / target,
+            rms: 0.2,
+            clarity: 0.95,
+            ambiguous: false,
+        };
+        clock.advance(Duration::from_millis(100));
+        let frame = PitchFrame {
+            end: 512 + 3 * hop,
+            result: Some(result),
+        };
+        app.update_pitch_frame(&frame, SAMPLE_RATE);
+        assert!(app.tuning.as_ref().unwrap().detected_freq().is_some());
+    }
+
+    #[test]
+    fn test_attack_delay_holds_back_meter() {
+        use crate::audio::{PitchMethod, PitchTracker, TestAudioSource};
+
+        let noise = TestAudioSource::noise(0.05, 12345, SAMPLE_RATE);
+        let mut samples = noise.samples().to_vec();
+        samples.extend_from_slice(TestAudioSource::sine(440.0, 0.5, SAMPLE_RATE).samples());
+        let onset = noise.samples().len() as u64;
+        let delay = Duration::from_millis(100);
+        let delay_samples = SAMPLE_RATE as u64 / 10;
+
+        // End of the first frame to show a reading
+        let first_reading = |delay: Duration| {
+            let mut app =
+                App::with_session(Session::new(TuningMode::Concert, 440.0)).with_pitch_lock(1, 1);
+            app.current_note_idx = app.tuning_order.position_of(69).unwrap();
+            app.setup_current_note();
+            let mut tracker = PitchTracker::new(PitchMethod::Yin.detector(SAMPLE_RATE))
+                .with_window(2048, 256)
+                .with_attack_delay(delay, SAMPLE_RATE);
+            tracker.set_target(app.detection_target());
+            tracker.push(&samples);
+            std::iter::from_fn(|| tracker.poll()).find_map(|frame| {
+                app.update_pitch_frame(&frame, SAMPLE_RATE);
+                app.detected_freq().map(|_| frame.end)
+            })
+        };
+
+        assert!(first_reading(Duration::ZERO).unwrap() < onset + delay_samples);
+        let first = first_reading(delay).expect("Should show the sustained pitch");
+        assert!(first >= onset + delay_samples, "Shown at {}", first);
+    }
+
+    #[test]
+    fn test_pitch_lock_stops_flicker() {
+        let mut app =
+            App::with_session(Session::new(TuningMode::Concert, 440.0)).with_pitch_lock(2, 3);
+        let f3 = Temperament::new().frequency(53);
+
+        app.update_pitch(f3, 0.9);
+        assert!(app.detected_freq().is_none());
+        app.update_pitch(f3, 0.9);
+        assert!(app.detected_freq().is_some());
+
+        // Dropouts shorter than the release keep the reading
+        app.clear_pitch();
+        app.update_pitch(f3, 0.3);
+        assert!(app.detected_freq().is_some());
+        app.clear_pitch();
+        assert!(app.detected_freq().is_none());
+    }
+
+    #[test]
+    fn test_multiple_notes_are_no_reading() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        let f3 = Temperament::new().frequency(53);
+        let result = |ambiguous: bool| PitchResult {
+            frequency: f3,
+            confidence: 0.95,
+            tau: SAMPLE_RATE as f32 / f3,
+            rms: 0.2,
+            clarity: 0.95,
+            ambiguous,
+        };
+
+        app.update_pitch_result(&result(true));
+        assert!(app.detected_freq().is_none());
+        assert!(tuning_text(&app).contains("Multiple notes detected"));
+
+        app.update_pitch_result(&result(false));
+        assert!(app.detected_freq().is_some());
+        assert!(!tuning_text(&app).contains("Multiple notes detected"));
+
+        app.update_pitch_result(&result(true));
+        app.clear_pitch();
+        assert!(!tuning_text(&app).contains("Multiple notes detected"));
+    }
+
+    #[test]
+    fn test_detection_target() {
+        assert_eq!(App::new().detection_target(), None);
+
+        let app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        let f3 = Temperament::new().frequency(53);
+        assert!((app.detection_target().unwrap() - f3).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_recording_indicator() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        assert!(app.active_session().is_some());
+        assert!(!tuning_text(&app).contains("● REC"));
+
+        app.set_recording(true);
+        assert!(app.is_recording());
+        assert!(tuning_text(&app).contains("● REC"));
+
+        // Kept on the next note
+        app.handle_key(KeyCode::Char('s'));
+        assert!(tuning_text(&app).contains("● REC"));
+
+        app.set_recording(false);
+        assert!(!tuning_text(&app).contains("● REC"));
+    }
+
+    #[test]
+    fn test_clipping_banner() {
+        let clock = ManualClock::new();
+        let mut app =
+            App::with_session(Session::new(TuningMode::Concert, 440.0)).with_clock(clock.clone());
+        app.update_clipping(0);
+        assert!(!app.is_clipping());
+        assert!(!tuning_text(&app).contains("INPUT CLIPPING"));
+
+        app.update_clipping(37);
+        assert!(tuning_text(&app).contains("INPUT CLIPPING — reduce gain"));
+
+        // Held briefly once the input is clean again, and on the next note
+        clock.advance(App::CLIP_HOLD / 2);
+        app.update_clipping(0);
+        app.handle_key(KeyCode::Char('s'));
+        assert!(tuning_text(&app).contains("INPUT CLIPPING"));
+
+        clock.advance(App::CLIP_HOLD);
+        app.update_clipping(0);
+        assert!(!app.is_clipping());
+        assert!(!tuning_text(&app).contains("INPUT CLIPPING"));
+
+        // Also shown while calibrating
+        app.update_clipping(1);
+        let area = ratatui::layout::Rect::new(0, 0, 80, 24);
+        let mut buf = ratatui::buffer::Buffer::empty(area);
+        ratatui::widgets::Widget::render(&app.calibration, area, &mut buf);
+        let top: String = (0..area.width).map(|x| buf[(x, 0)].symbol()).collect();
+        assert!(top.contains("INPUT CLIPPING"));
+    }
+
+    #[test]
+    fn test_input_gain_clears_noise_gate() {
+        let clock = ManualClock::new();
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(clock.clone())
+            .with_idle_timeout(Duration::from_secs(60));
+        for _ in 0..10 {
+            app.handle_key(KeyCode::F(4));
+        }
+        assert_eq!(app.gain_db(), 20.0);
+        clock.advance(Duration::from_secs(60));
+        assert!(app.is_idle());
+
+        // A -40 dB sine at the note being tuned
+        let target = app.current_target_freq().unwrap();
+        let quiet: Vec<f32> = (0..SAMPLE_RATE as usize / 10)
+            .map(|i| {
+                let phase = 2.0 * std::f32::consts::PI * target * i as f32 / SAMPLE_RATE as f32;
+                0.01 * phase.sin()
+            })
+            .collect();
+        let detector = crate::audio::PitchDetector::new(SAMPLE_RATE);
+        // As the main loop does: detection only once the level wakes the app
+        let listen = |app: &mut App, samples: &[f32]| {
+            app.update_level(crate::audio::beats::rms(samples));
+            if !app.is_idle() {
+                if let Some(result) = detector.detect(samples) {
+                    app.update_pitch_result(&result);
+                }
+            }
+        };
+
+        listen(&mut app, &quiet);
+        assert!(app.is_idle());
+        assert!(app.tuning.as_ref().unwrap().detected_freq().is_none());
+
+        let mut gained = quiet.clone();
+        Gain::from_db(app.gain_db()).process_in_place(&mut gained);
+        listen(&mut app, &gained);
+        assert!(!app.is_idle());
+        let detected = app.tuning.as_ref().unwrap().detected_freq().unwrap();
+        assert!((detected - target).abs() < 1.0, "Detected {}", detected);
+    }
+
+    #[test]
+    fn test_unison_beats_measured() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        let f3 = Temperament::new().frequency(53);
+        let samples: Vec<f32> = (0..SAMPLE_RATE * 3)
+            .map(|i| {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                let phase = 2.0 * std::f32::consts::PI * t;
+                0.2 * ((phase * f3).sin() + (phase * (f3 + 1.5)).sin())
+            })
+            .collect();
+
+        // A single string is being tuned
+        app.update_samples(&samples, SAMPLE_RATE);
+        assert!(app.tuning().unwrap().measured_beats().is_none());
+
+        app.handle_key(KeyCode::Char(' '));
+        app.update_samples(&samples, SAMPLE_RATE);
+        let rate = app
+            .tuning()
+            .unwrap()
+            .measured_beats()
+            .expect("Should hear beats");
+        assert!((rate - 1.5).abs() < 0.2, "rate {}", rate);
+        assert!(tuning_text(&app).contains("Beats: 1.5/s"));
+    }
+
+    #[test]
+    fn test_bass_note_measured_via_partials() {
+        let mut session = Session::new(TuningMode::Concert, 440.0);
+        session.current_note_index = TuningOrder::new().position_of(21).unwrap();
+        let mut app = App::with_session(session);
+        let target = app.partial_target().expect("A0 is measured via partials");
+        assert!((target - 27.5).abs() < 0.01);
+
+        // A0 4 cents sharp with the fundamental 20 dB below the 2nd partial
+        let f0 = 27.5 * Temperament::cents_to_ratio(4.0) as f64;
+        let partials = [(1, 0.1), (2, 1.0), (3, 0.5), (4, 0.3)];
+        let history: Vec<f32> = (0..SAMPLE_RATE)
+            .map(|i| {
+                let t = i as f64 / SAMPLE_RATE as f64;
+                let sum: f64 = partials
+                    .iter()
+                    .map(|&(n, a)| a * (2.0 * std::f64::consts::PI * f0 * n as f64 * t).sin())
+                    .sum();
+                0.5 * sum as f32
+            })
+            .collect();
+
+        let tracker = PartialTracker::new(SAMPLE_RATE);
+        let window = app.detection_window(&history, tracker.window_size(), SAMPLE_RATE);
+        app.update_partials(tracker.detect(&window, target));
+
+        let tuning = app.tuning.as_ref().unwrap();
+        assert!(
+            (tuning.cents() - 4.0).abs() < 1.0,
+            "cents {}",
+            tuning.cents()
+        );
+        assert!(tuning_text(&app).contains("measuring via 2nd partial"));
+
+        // Notes above the threshold use the pitch detector
+        let app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        assert!(app.partial_target().is_none());
+    }
+
+    /// Finish a session on its last note with a hook, waiting for the command.
+    #[cfg(unix)]
+    fn finish_with_hook(template: &str, dir: &std::path::Path) -> App {
+        let mut session = Session::new(TuningMode::Concert, 440.0);
+        session.current_note_index = 87;
+        let hook = SessionHook::new(template)
+            .with_piano_name(Some("Upright".to_string()))
+            .with_export_dir(dir);
+        let mut app = App::with_session(session).with_session_hook(Some(hook));
+        app.handle_key(KeyCode::Char('s'));
+        assert_eq!(app.state(), AppState::Complete);
+
+        for _ in 0..500 {
+            app.poll_hook();
+            if app.hook_run.is_none() {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        app
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_session_hook_runs_with_export() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = finish_with_hook("touch {json}.{piano}.done", dir.path());
+
+        let session = app.session().unwrap();
+        let export = SessionHook::new("")
+            .with_export_dir(dir.path())
+            .export_path(session);
+        let exported: Session =
+            serde_json::from_str(&std::fs::read_to_string(&export).unwrap()).unwrap();
+        assert_eq!(exported.id, session.id);
+
+        // The exported targets are the ones tuned to
+        let value: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&export).unwrap()).unwrap();
+        let f3 = &value["targets"][(53 - 21) as usize];
+        assert_eq!(f3["midi"], 53);
+        assert!((f3["frequency"].as_f64().unwrap() as f32 - app.target_freq(53)).abs() < 1e-3);
+
+        let sentinel = format!("{}.Upright.done", export.display());
+        assert!(std::path::Path::new(&sentinel).exists());
+        assert_eq!(
+            app.complete.as_ref().unwrap().toast(),
+            Some("Session hook finished")
+        );
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_failing_session_hook_shows_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = finish_with_hook("exit 3", dir.path());
+
+        let toast = app.complete.as_ref().unwrap().toast().unwrap();
+        assert!(toast.starts_with("Session hook failed"), "{}", toast);
+        assert!(toast.contains('3'), "{}", toast);
+    }
+
+    #[test]
+    fn test_log_file_records_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("onkey.log");
+        let logger = Logger::to_file(&path, Level::Debug).unwrap();
+
+        let mut app = App::new().with_logger(logger);
+        app.handle_key(KeyCode::Down);
+        app.handle_key(KeyCode::Enter);
+        app.handle_key(KeyCode::Enter);
+        let target = app.current_target_freq().unwrap();
+
+        app.log_pitch(&PitchResult {
+            frequency: target,
+            confidence: 0.95,
+            tau: 252.5,
+            rms: 0.2,
+            clarity: 0.95,
+            ambiguous: false,
+        });
+        app.update_pitch(target * Temperament::cents_to_ratio(3.0), 0.9);
+        app.handle_key(KeyCode::Char(' '));
+        app.update_pitch(target * Temperament::cents_to_ratio(-1.0), 0.9);
+        app.handle_key(KeyCode::Char(' '));
+        app.handle_key(KeyCode::Char('s'));
+
+        let log = std::fs::read_to_string(&path).unwrap();
+        let line = |event: &str| {
+            log.lines()
+                .find(|l| l.contains(&format!(" {} ", event)) || l.ends_with(event))
+                .unwrap_or_else(|| panic!("no {} event in:\n{}", event, log))
+                .to_string()
+        };
+
+        assert!(line("state_change").contains("from=ModeSelect to=Setup"));
+        assert!(log.contains("from=Setup to=Tuning"), "{}", log);
+        assert!(line("note_started").contains("note=F3"));
+        let pitch = line("pitch");
+        assert!(pitch.contains("confidence=0.95") && pitch.contains("tau=252.50"));
+        assert!(line("step_confirmed").contains("note=F3 step=\"Tune left string\" cents=+3.0"));
+        assert!(line("note_confirmed").contains("cents=-1.0 false_beats=false"));
+        assert!(line("note_skipped").contains("note=F#3"));
+    }
+
+    #[test]
+    fn test_ctrl_c_saves_and_quits() {
+        use crossterm::event::{Event, KeyEvent, KeyModifiers};
+
+        let ctrl_c = Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        let plain_c = Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE));
+        assert!(crate::ui::is_interrupt(&ctrl_c));
+        assert!(!crate::ui::is_interrupt(&plain_c));
+
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        app.handle_key(KeyCode::Char('s'));
+        let id = app.session().unwrap().id.clone();
+        app.interrupt();
+
+        assert!(app.should_quit());
+        assert!(app.was_interrupted());
+        assert!(!app.should_force_quit());
+        let saved = Session::list_all()
+            .unwrap()
+            .into_iter()
+            .find(|s| s.id == id)
+            .expect("session saved on interrupt");
+        assert_eq!(saved.current_note_index, 1);
+        saved.delete().unwrap();
+    }
+
+    #[test]
+    fn test_input_lost_saves_and_offers_retry() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        app.handle_key(KeyCode::Char('s'));
+        let id = app.session().unwrap().id.clone();
+
+        app.input_lost("Input device lost: device unplugged");
+        assert_eq!(app.state(), AppState::Tuning);
+        assert_eq!(
+            app.input_lost_error(),
+            Some("Input device lost: device unplugged")
+        );
+        let saved = Session::list_all()
+            .unwrap()
+            .into_iter()
+            .find(|s| s.id == id)
+            .expect("session saved when the input is lost");
+        assert_eq!(saved.current_note_index, 1);
+
+        // Tuning keys do nothing until the input is back
+        app.handle_key(KeyCode::Char('s'));
+        assert_eq!(app.session().unwrap().current_note_index, 1);
+        app.handle_key(KeyCode::Char('r'));
+        assert_eq!(
+            app.take_device_request(),
+            Some(DeviceRequest::ReopenDefaultInput)
+        );
+
+        // A failed retry keeps the overlay with the new reason
+        app.input_failed("default", "No input device available");
+        assert_eq!(app.state(), AppState::Tuning);
+        assert_eq!(app.input_lost_error(), Some("No input device available"));
+
+        app.input_opened("USB Interface");
+        assert!(app.input_lost_error().is_none());
+        app.handle_key(KeyCode::Char('s'));
+        assert_eq!(app.session().unwrap().current_note_index, 2);
+        saved.delete().unwrap();
+    }
+
+    #[test]
+    fn test_input_lost_quit_saves() {
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0));
+        let id = app.session().unwrap().id.clone();
+        app.input_lost("Input device lost: no audio for 2 s");
+
+        app.handle_key(KeyCode::Char('q'));
+        assert!(app.should_quit());
+        assert!(!app.was_interrupted());
+        let saved = Session::list_all()
+            .unwrap()
+            .into_iter()
+            .find(|s| s.id == id)
+            .expect("session saved on quit");
+        saved.delete().unwrap();
+    }
+
+    #[test]
+    fn test_repeated_interrupt_forces_quit() {
+        let clock = ManualClock::new();
+        let mut app = App::new().with_clock(clock.clone());
+
+        app.interrupt();
+        clock.advance(Duration::from_millis(1500));
+        app.interrupt();
+        assert!(app.should_quit());
+        assert!(!app.should_force_quit());
+
+        clock.advance(Duration::from_millis(900));
+        app.interrupt();
+        assert!(app.should_force_quit());
+    }
+
+    #[test]
+    fn test_suspend_excludes_time_and_requests_redraw() {
+        use crossterm::event::{Event, KeyEvent, KeyModifiers};
+
+        let ctrl_z = Event::Key(KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL));
+        assert_eq!(crate::ui::is_suspend(&ctrl_z), cfg!(unix));
+
+        let clock = ManualClock::new();
+        let mut app =
+            App::with_session(Session::new(TuningMode::Concert, 440.0)).with_clock(clock.clone());
+        app.handle_key(KeyCode::Char('r'));
+        assert!(app.playing_reference());
+
+        app.suspend();
+        assert!(app.is_suspended());
+        assert!(!app.playing_reference());
+        assert_eq!(app.take_reference_request(), None);
+
+        // Stopped in the shell well past the idle timeout
+        clock.advance(Duration::from_secs(600));
+        app.resume();
+        assert!(!app.is_suspended());
+        assert!(!app.is_idle());
+        assert_eq!(app.session().unwrap().paused_secs, 600);
+        assert!(app.take_redraw_request());
+        assert!(!app.take_redraw_request());
+
+        // Continuing without a suspend changes nothing
+        app.resume();
+        assert_eq!(app.session().unwrap().paused_secs, 600);
+        assert!(!app.take_redraw_request());
+    }
+
+    /// Feed `secs` of readings at `cents` off target, playing any cues.
+    fn play_readings(
+        app: &mut App,
+        clock: &ManualClock,
+        sink: &mut crate::audio::TestAudioSink,
+        cents: f32,
+        secs: u64,
+    ) {
+        let freq = app.current_target_freq().unwrap() * Temperament::cents_to_ratio(cents);
+        for _ in 0..secs * 10 {
+            app.update_pitch(freq, 0.9);
+            if let Some(cue) = app.take_cue_request() {
+                crate::audio::CuePlayer::default().play(sink, cue);
+            }
+            clock.advance(Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    fn test_beep_once_per_lock() {
+        let blip = crate::audio::CuePlayer::default()
+            .render(Cue::Lock, 44100)
+            .len();
+        let clock = ManualClock::new();
+        let mut sink = crate::audio::TestAudioSink::new(44100);
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(clock.clone())
+            .with_beep(true);
+
+        play_readings(&mut app, &clock, &mut sink, 1.0, 2);
+        assert!(sink.samples().is_empty());
+        play_readings(&mut app, &clock, &mut sink, 1.0, 5);
+        assert_eq!(sink.samples().len(), blip);
+
+        // Out of tune never locks; coming back in locks again
+        play_readings(&mut app, &clock, &mut sink, 15.0, 5);
+        assert_eq!(sink.samples().len(), blip);
+        play_readings(&mut app, &clock, &mut sink, -1.0, 5);
+        assert_eq!(sink.samples().len(), 2 * blip);
+
+        // Confirming plays its own cue
+        app.handle_key(KeyCode::Char(' '));
+        assert_eq!(app.take_cue_request(), Some(Cue::Confirm));
+    }
+
+    #[test]
+    fn test_beep_disabled_or_under_reference() {
+        let clock = ManualClock::new();
+        let mut sink = crate::audio::TestAudioSink::new(44100);
+        let mut app =
+            App::with_session(Session::new(TuningMode::Concert, 440.0)).with_clock(clock.clone());
+        play_readings(&mut app, &clock, &mut sink, 0.0, 5);
+        app.handle_key(KeyCode::Char(' '));
+        assert_eq!(app.take_cue_request(), None);
+        assert!(sink.samples().is_empty());
+
+        // No cue on top of the reference tone
+        let mut app = App::with_session(Session::new(TuningMode::Concert, 440.0))
+            .with_clock(clock.clone())
+            .with_beep(true)
+            .with_reference_feedback(ReferenceFeedback::Filter);
+        app.handle_key(KeyCode::Char('r'));
+        app.take_reference_request();
+        app.handle_key(KeyCode::Char(' '));
+        assert_eq!(app.take_cue_request(), None);
     }
 }
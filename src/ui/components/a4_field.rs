@@ -0,0 +1,158 @@
+//! A4 reference field: cycle through presets or type a custom value.
+
+use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+
+use crate::tuning::temperament::{A4_RANGE, DEFAULT_A4_PRESETS};
+use crate::ui::theme::{BoxChars, Theme};
+
+/// Editable A4 reference value.
+#[derive(Debug, Clone)]
+pub struct A4Field {
+    /// Available presets.
+    presets: Vec<f32>,
+    /// Current value in Hz.
+    value: f32,
+    /// Custom value being typed, if any.
+    input: Option<String>,
+}
+
+impl A4Field {
+    /// Create a field with the given presets, starting at 440 Hz.
+    pub fn new(mut presets: Vec<f32>) -> Self {
+        presets.retain(|p| A4_RANGE.contains(p));
+        presets.sort_by(f32::total_cmp);
+        presets.dedup();
+        Self {
+            presets,
+            value: 440.0,
+            input: None,
+        }
+    }
+
+    /// Get the presets.
+    pub fn presets(&self) -> &[f32] {
+        &self.presets
+    }
+
+    /// Get the current value, including a valid custom entry in progress.
+    pub fn value(&self) -> f32 {
+        self.typed_value().unwrap_or(self.value)
+    }
+
+    /// Parse the custom entry, if it is a valid A4.
+    fn typed_value(&self) -> Option<f32> {
+        self.input
+            .as_deref()
+            .and_then(|s| s.parse::<f32>().ok())
+            .filter(|v| A4_RANGE.contains(v))
+    }
+
+    /// Select the next preset above the current value.
+    pub fn next_preset(&mut self) {
+        let current = self.value();
+        self.input = None;
+        self.value = self
+            .presets
+            .iter()
+            .copied()
+            .find(|&p| p > current)
+            .or_else(|| self.presets.first().copied())
+            .unwrap_or(current);
+    }
+
+    /// Select the next preset below the current value.
+    pub fn prev_preset(&mut self) {
+        let current = self.value();
+        self.input = None;
+        self.value = self
+            .presets
+            .iter()
+            .rev()
+            .copied()
+            .find(|&p| p < current)
+            .or_else(|| self.presets.last().copied())
+            .unwrap_or(current);
+    }
+
+    /// Set the value, dropping any custom entry in progress. Values outside
+    /// the A4 range are ignored.
+    pub fn set_value(&mut self, hz: f32) {
+        self.input = None;
+        if A4_RANGE.contains(&hz) {
+            self.value = hz;
+        }
+    }
+
+    /// Move the value by `delta` Hz, kept within the A4 range.
+    pub fn step(&mut self, delta: f32) {
+        let value = (self.value() + delta).clamp(*A4_RANGE.start(), *A4_RANGE.end());
+        self.set_value(value);
+    }
+
+    /// Type a character of a custom value. Returns false if not accepted.
+    pub fn push_char(&mut self, c: char) -> bool {
+        if !(c.is_ascii_digit() || c == '.') {
+            return false;
+        }
+        let input = self.input.get_or_insert_with(String::new);
+        if input.len() >= 6 || (c == '.' && input.contains('.')) {
+            return false;
+        }
+        input.push(c);
+        true
+    }
+
+    /// Delete the last typed character.
+    pub fn backspace(&mut self) {
+        if let Some(input) = &mut self.input {
+            input.pop();
+            if input.is_empty() {
+                self.input = None;
+            }
+        }
+    }
+
+    /// Whether a custom value is being typed.
+    pub fn is_editing(&self) -> bool {
+        self.input.is_some()
+    }
+
+    /// Apply a custom entry in progress (invalid entries are dropped).
+    pub fn commit(&mut self) {
+        self.value = self.value();
+        self.input = None;
+    }
+}
+
+impl Default for A4Field {
+    fn default() -> Self {
+        Self::new(DEFAULT_A4_PRESETS.to_vec())
+    }
+}
+
+impl Widget for &A4Field {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 1 || area.width < 20 {
+            return;
+        }
+
+        let text = match &self.input {
+            Some(input) => format!("A4 = {}_ Hz", input),
+            None => format!(
+                "A4 = {} {:.1} Hz {}",
+                BoxChars::LEFT_ARROW,
+                self.value,
+                BoxChars::RIGHT_ARROW
+            ),
+        };
+        let style = if self.input.is_some() && self.typed_value().is_none() {
+            Theme::warning()
+        } else {
+            Theme::accent()
+        };
+
+        let width = text.chars().count().min(area.width as usize) as u16;
+        let x = area.x + (area.width - width) / 2;
+        buf.set_stringn(x, area.y, &text, area.width as usize, style);
+    }
+}
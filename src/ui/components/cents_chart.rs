@@ -0,0 +1,247 @@
+//! Chart of final cents deviation across the keyboard.
+//!
+//! Each column covers one or more keys and shows a bar from the zero line
+//! to the largest deviation among them, colored by severity. The tolerance
+//! band around zero is shaded.
+
+use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+
+use crate::ui::theme::Theme;
+
+/// Characters for chart rendering.
+pub mod chars {
+    pub const BAR: char = '█';
+    pub const ZERO: char = '─';
+    pub const ON_ZERO: char = '━';
+    pub const BAND: char = '░';
+}
+
+/// Lowest MIDI note on the keyboard.
+const FIRST_MIDI: u8 = 21;
+
+/// Number of keys on the keyboard.
+const KEYS: u16 = 88;
+
+/// Cents deviation plotted against key position.
+pub struct CentsChart {
+    /// Final cents per MIDI note.
+    points: Vec<(u8, f32)>,
+    /// Half-width of the shaded band in cents.
+    tolerance: f32,
+}
+
+impl CentsChart {
+    /// Width of the axis labels to the left of the plot.
+    pub const LABEL_WIDTH: u16 = 4;
+
+    /// Create a chart of `(midi, cents)` points.
+    pub fn new(points: Vec<(u8, f32)>) -> Self {
+        Self {
+            points,
+            tolerance: 5.0,
+        }
+    }
+
+    /// Set the half-width of the shaded tolerance band.
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Cents at the top (and, negated, the bottom) of the plot: the largest
+    /// deviation rounded up to 5 cents, at least twice the tolerance.
+    pub fn range(&self) -> f32 {
+        let max = self
+            .points
+            .iter()
+            .map(|(_, cents)| cents.abs())
+            .fold(0.0, f32::max);
+        ((max / 5.0).ceil() * 5.0).max(self.tolerance * 2.0)
+    }
+
+    /// Row (from the top) for a deviation in a plot of the given height.
+    pub fn row_for(&self, cents: f32, height: u16) -> u16 {
+        let range = self.range();
+        let fraction = (range - cents.clamp(-range, range)) / (2.0 * range);
+        (fraction * (height - 1) as f32).round() as u16
+    }
+
+    /// Column (from the left of the plot) for a key in a plot of the given width.
+    pub fn column_for(midi: u8, width: u16) -> u16 {
+        let key = midi.saturating_sub(FIRST_MIDI) as u16;
+        (key.min(KEYS - 1) as u32 * width as u32 / KEYS as u32) as u16
+    }
+
+    /// Largest deviation per plot column.
+    fn columns(&self, width: u16) -> Vec<Option<f32>> {
+        let mut columns: Vec<Option<f32>> = vec![None; width as usize];
+        for &(midi, cents) in &self.points {
+            let slot = &mut columns[Self::column_for(midi, width) as usize];
+            if slot.is_none_or(|c| cents.abs() > c.abs()) {
+                *slot = Some(cents);
+            }
+        }
+        columns
+    }
+}
+
+impl Widget for &CentsChart {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 3 || area.width <= CentsChart::LABEL_WIDTH + 10 {
+            return;
+        }
+
+        let plot = Rect {
+            x: area.x + CentsChart::LABEL_WIDTH,
+            width: area.width - CentsChart::LABEL_WIDTH,
+            ..area
+        };
+        let range = self.range();
+        let zero_row = self.row_for(0.0, plot.height);
+        let band_top = self.row_for(self.tolerance, plot.height);
+        let band_bottom = self.row_for(-self.tolerance, plot.height);
+
+        // Axis labels
+        let labels = [
+            (0, format!("{:+.0}", range)),
+            (zero_row, "0".to_string()),
+            (plot.height - 1, format!("{:+.0}", -range)),
+        ];
+        for (row, label) in labels {
+            let width = label.chars().count() as u16;
+            let x = area.x + (CentsChart::LABEL_WIDTH - 1).saturating_sub(width);
+            buf.set_stringn(
+                x,
+                area.y + row,
+                &label,
+                CentsChart::LABEL_WIDTH as usize,
+                Theme::muted(),
+            );
+        }
+
+        // Tolerance band and zero line
+        for row in band_top..=band_bottom {
+            let symbol = if row == zero_row {
+                chars::ZERO
+            } else {
+                chars::BAND
+            };
+            for x in plot.x..plot.x + plot.width {
+                buf[(x, plot.y + row)]
+                    .set_char(symbol)
+                    .set_style(Theme::muted());
+            }
+        }
+
+        // Bars from the zero line to each deviation
+        for (i, cents) in self.columns(plot.width).into_iter().enumerate() {
+            let Some(cents) = cents else {
+                continue;
+            };
+            let x = plot.x + i as u16;
+            let style = Theme::style_for_cents(cents);
+            let row = self.row_for(cents, plot.height);
+
+            if row == zero_row {
+                buf[(x, plot.y + row)]
+                    .set_char(chars::ON_ZERO)
+                    .set_style(style);
+                continue;
+            }
+            let rows = if row < zero_row {
+                row..zero_row
+            } else {
+                zero_row + 1..row + 1
+            };
+            for r in rows {
+                buf[(x, plot.y + r)].set_char(chars::BAR).set_style(style);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sharp at both ends of the keyboard, flat in the middle.
+    fn v_shape() -> CentsChart {
+        let points = (0..88u8)
+            .map(|key| {
+                let distance = (key as f32 - 43.5).abs() / 43.5;
+                (FIRST_MIDI + key, distance * 20.0 - 10.0)
+            })
+            .collect();
+        CentsChart::new(points)
+    }
+
+    fn render(chart: &CentsChart, width: u16, height: u16) -> Buffer {
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        chart.render(area, &mut buf);
+        buf
+    }
+
+    #[test]
+    fn test_range_and_rows() {
+        let chart = v_shape();
+        assert_eq!(chart.range(), 10.0);
+        assert_eq!(chart.row_for(10.0, 11), 0);
+        assert_eq!(chart.row_for(0.0, 11), 5);
+        assert_eq!(chart.row_for(-10.0, 11), 10);
+        assert_eq!(chart.row_for(-40.0, 11), 10);
+
+        // Small deviations still get a scale of twice the tolerance
+        assert_eq!(CentsChart::new(vec![(60, 1.0)]).range(), 10.0);
+    }
+
+    #[test]
+    fn test_v_shape_extremes() {
+        let chart = v_shape();
+        let width = CentsChart::LABEL_WIDTH + 88;
+        let buf = render(&chart, width, 11);
+
+        // Both ends reach the top row
+        let left = CentsChart::LABEL_WIDTH;
+        let right = width - 1;
+        assert_eq!(buf[(left, 0)].symbol(), "█");
+        assert_eq!(buf[(right, 0)].symbol(), "█");
+        assert_eq!(buf[(left, 0)].fg, Theme::WARNING);
+
+        // The middle reaches the bottom row, not the top
+        let middle = left + 44;
+        assert_eq!(buf[(middle, 10)].symbol(), "█");
+        assert_eq!(buf[(middle, 0)].symbol(), " ");
+
+        // Zero line and labels
+        assert_eq!(buf[(middle, 5)].symbol(), "─");
+        assert_eq!(buf[(left + 2, 5)].symbol(), "─");
+        assert_eq!(buf[(2, 5)].symbol(), "0");
+        let top_label: String = (0..3).map(|x| buf[(x, 0)].symbol()).collect();
+        assert_eq!(top_label, "+10");
+    }
+
+    #[test]
+    fn test_skipped_keys_leave_band_empty() {
+        let chart = CentsChart::new(vec![(21, 3.0)]);
+        let buf = render(&chart, CentsChart::LABEL_WIDTH + 88, 11);
+
+        // The band is shaded where no key was tuned
+        let x = CentsChart::LABEL_WIDTH + 40;
+        assert_eq!(buf[(x, 4)].symbol(), "░");
+        assert_eq!(buf[(x, 0)].symbol(), " ");
+
+        // The tuned key has an in-tune bar above the zero line
+        let x = CentsChart::LABEL_WIDTH;
+        assert_eq!(buf[(x, 4)].symbol(), "█");
+        assert_eq!(buf[(x, 4)].fg, Theme::IN_TUNE);
+    }
+
+    #[test]
+    fn test_columns_keep_worst_key() {
+        let chart = CentsChart::new(vec![(21, 2.0), (22, -12.0)]);
+        let columns = chart.columns(44);
+        assert_eq!(columns[0], Some(-12.0));
+        assert_eq!(columns[1], None);
+    }
+}
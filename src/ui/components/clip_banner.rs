@@ -0,0 +1,19 @@
+//! Warning shown while the input is clipping.
+
+use ratatui::{buffer::Buffer, layout::Rect, style::Modifier, widgets::Widget};
+
+use crate::ui::strings;
+use crate::ui::theme::Theme;
+
+/// Red banner centered on the top line of the area, over a screen's border.
+pub struct ClipBanner;
+
+impl Widget for ClipBanner {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let message = format!(" {} ", strings::get("common.clipping"));
+        let width = (message.chars().count() as u16).min(area.width);
+        let x = area.x + (area.width - width) / 2;
+        let style = Theme::out_of_tune().add_modifier(Modifier::BOLD | Modifier::REVERSED);
+        buf.set_stringn(x, area.y, message, width as usize, style);
+    }
+}
@@ -0,0 +1,310 @@
+//! Chart of a stretch curve across the keyboard.
+//!
+//! The curve is drawn in Braille dots, two columns and four rows of dots per
+//! cell, against the zero line, with the current note marked. Too narrow for
+//! a plot, it shrinks to the offsets at the ends of the keyboard.
+
+use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+
+use crate::tuning::{Note, StretchCurve};
+use crate::ui::theme::{BoxChars, Theme};
+
+/// Lowest MIDI note on the keyboard.
+const FIRST_MIDI: u8 = 21;
+
+/// Number of keys on the keyboard.
+const KEYS: usize = 88;
+
+/// First Braille pattern; the dots are bits on top of it.
+const BRAILLE: u32 = 0x2800;
+
+/// Bit of each dot in a Braille cell, by dot row and then dot column.
+const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Stretch in cents plotted against key position.
+pub struct CurveChart {
+    /// Offset of each key in cents, from A0 up.
+    offsets: Vec<f32>,
+    /// Note to mark, if any.
+    current: Option<u8>,
+}
+
+impl CurveChart {
+    /// Width of the axis labels to the left of the plot.
+    pub const LABEL_WIDTH: u16 = 4;
+
+    /// Narrowest area that gets a plot rather than a summary line.
+    pub const MIN_WIDTH: u16 = 40;
+
+    /// Create a chart of the curve's 88 offsets.
+    pub fn new(curve: &StretchCurve) -> Self {
+        Self {
+            offsets: (0..KEYS).map(|i| curve.offset_cents_by_index(i)).collect(),
+            current: None,
+        }
+    }
+
+    /// Mark a note on the chart.
+    pub fn with_current(mut self, midi: Option<u8>) -> Self {
+        self.current = midi.filter(|m| (FIRST_MIDI..FIRST_MIDI + KEYS as u8).contains(m));
+        self
+    }
+
+    /// Cents at the top (and, negated, the bottom) of the plot: the largest
+    /// offset rounded up to 5 cents, at least 5.
+    pub fn range(&self) -> f32 {
+        let max = self.offsets.iter().map(|c| c.abs()).fold(0.0, f32::max);
+        ((max / 5.0).ceil() * 5.0).max(5.0)
+    }
+
+    /// Offset of a key in cents.
+    fn offset(&self, midi: u8) -> f32 {
+        self.offsets[(midi - FIRST_MIDI) as usize]
+    }
+
+    /// Offset at a fractional key position, between the keys either side.
+    fn offset_at(&self, position: f32) -> f32 {
+        let low = (position.floor() as usize).min(KEYS - 1);
+        let high = (low + 1).min(KEYS - 1);
+        let t = position - low as f32;
+        self.offsets[low] + t * (self.offsets[high] - self.offsets[low])
+    }
+
+    /// Dot row (from the top) for an offset in a plot `dots` rows high.
+    fn dot_row(&self, cents: f32, dots: u16) -> u16 {
+        let range = self.range();
+        let fraction = (range - cents.clamp(-range, range)) / (2.0 * range);
+        (fraction * (dots - 1) as f32).round() as u16
+    }
+
+    /// Cell column (from the left of the plot) of a key in a plot `width`
+    /// cells wide.
+    fn column_for(midi: u8, width: u16) -> u16 {
+        let dots = width as u32 * 2;
+        let key = (midi - FIRST_MIDI) as u32;
+        (key * (dots - 1) / (KEYS as u32 - 1) / 2) as u16
+    }
+
+    /// The offsets at both ends and of the marked note, on one line.
+    fn summary(&self) -> String {
+        let mut notes = vec![FIRST_MIDI, 69, FIRST_MIDI + KEYS as u8 - 1];
+        if let Some(current) = self.current {
+            notes.push(current);
+            notes.sort_unstable();
+            notes.dedup();
+        }
+        notes
+            .into_iter()
+            .filter_map(|midi| {
+                Note::from_midi(midi)
+                    .map(|note| format!("{} {:+.1}¢", note.display_name(), self.offset(midi)))
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
+
+    fn render_summary(&self, area: Rect, buf: &mut Buffer) {
+        let text = self.summary();
+        let width = (text.chars().count() as u16).min(area.width);
+        let x = area.x + (area.width - width) / 2;
+        let y = area.y + area.height.saturating_sub(1) / 2;
+        buf.set_stringn(x, y, &text, area.width as usize, Theme::accent());
+    }
+}
+
+impl Widget for &CurveChart {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height == 0 || area.width == 0 {
+            return;
+        }
+        if area.width < CurveChart::MIN_WIDTH || area.height < 4 {
+            self.render_summary(area, buf);
+            return;
+        }
+
+        // Plot above a row of key names
+        let plot = Rect {
+            x: area.x + CurveChart::LABEL_WIDTH,
+            width: area.width - CurveChart::LABEL_WIDTH,
+            height: area.height - 1,
+            ..area
+        };
+        let dot_rows = plot.height * 4;
+        let dot_columns = plot.width * 2;
+        let zero_row = self.dot_row(0.0, dot_rows) / 4;
+
+        // Axis labels
+        let range = self.range();
+        let labels = [
+            (0, format!("{:+.0}", range)),
+            (zero_row, "0".to_string()),
+            (plot.height - 1, format!("{:+.0}", -range)),
+        ];
+        for (row, label) in labels {
+            let width = label.chars().count() as u16;
+            let x = area.x + (CurveChart::LABEL_WIDTH - 1).saturating_sub(width);
+            buf.set_stringn(
+                x,
+                plot.y + row,
+                &label,
+                CurveChart::LABEL_WIDTH as usize,
+                Theme::muted(),
+            );
+        }
+
+        // Zero line, and the marked note's column
+        for x in plot.x..plot.x + plot.width {
+            buf[(x, plot.y + zero_row)]
+                .set_char('─')
+                .set_style(Theme::muted());
+        }
+        let marked = self
+            .current
+            .map(|midi| plot.x + CurveChart::column_for(midi, plot.width));
+        if let Some(x) = marked {
+            for y in plot.y..plot.y + plot.height {
+                buf[(x, y)]
+                    .set_char(BoxChars::THIN_VERTICAL)
+                    .set_style(Theme::selected());
+            }
+        }
+
+        // The curve, each dot column joined to the one before
+        let mut cells = vec![0u32; (plot.width * plot.height) as usize];
+        let mut previous: Option<u16> = None;
+        for column in 0..dot_columns {
+            let position = column as f32 * (KEYS - 1) as f32 / (dot_columns - 1) as f32;
+            let row = self.dot_row(self.offset_at(position), dot_rows);
+            let (from, to) = match previous {
+                Some(p) if p < row => (p + 1, row),
+                Some(p) if p > row => (row, p - 1),
+                _ => (row, row),
+            };
+            for r in from..=to {
+                let cell = (r / 4) * plot.width + column / 2;
+                cells[cell as usize] |= DOTS[(r % 4) as usize][(column % 2) as usize];
+            }
+            previous = Some(row);
+        }
+        for (i, &bits) in cells.iter().enumerate() {
+            if bits == 0 {
+                continue;
+            }
+            let x = plot.x + i as u16 % plot.width;
+            let y = plot.y + i as u16 / plot.width;
+            let style = if Some(x) == marked {
+                Theme::selected()
+            } else {
+                Theme::accent()
+            };
+            if let Some(symbol) = char::from_u32(BRAILLE + bits) {
+                buf[(x, y)].set_char(symbol).set_style(style);
+            }
+        }
+
+        // Key names: both ends, and the marked note under its column
+        let y = plot.y + plot.height;
+        buf.set_string(plot.x, y, "A0", Theme::muted());
+        buf.set_string(plot.x + plot.width - 2, y, "C8", Theme::muted());
+        if let (Some(x), Some(note)) = (marked, self.current.and_then(Note::from_midi)) {
+            let name = note.display_name();
+            let width = name.chars().count() as u16;
+            let x = x
+                .saturating_sub(width / 2)
+                .clamp(plot.x, plot.x + plot.width - width);
+            buf.set_string(x, y, &name, Theme::selected());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuning::PianoType;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    /// Draw the chart on a test terminal and return its text.
+    fn draw(chart: &CurveChart, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| frame.render_widget(chart, frame.area()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_plot_snapshot() {
+        let chart =
+            CurveChart::new(&StretchCurve::preset(PianoType::Upright)).with_current(Some(69));
+        assert_eq!(chart.range(), 25.0);
+        assert_eq!(draw(&chart, 44, 6), SNAPSHOT_44X6);
+    }
+
+    #[test]
+    fn test_plot_fills_wide_terminal() {
+        let chart =
+            CurveChart::new(&StretchCurve::preset(PianoType::Spinet)).with_current(Some(36));
+        let text = draw(&chart, 96, 12);
+        let rows: Vec<&str> = text.lines().collect();
+
+        // The bass starts near the bottom and the treble ends at the top
+        assert_eq!(chart.range(), 45.0);
+        assert!(rows[0].starts_with("+45"), "{}", text);
+        assert!(rows[10].starts_with("-45"), "{}", text);
+        assert!(
+            rows[0]
+                .trim_end()
+                .ends_with(|c: char| ('⠀'..='⣿').contains(&c)),
+            "{}",
+            text
+        );
+        let first = rows[9]
+            .chars()
+            .nth(CurveChart::LABEL_WIDTH as usize)
+            .unwrap();
+        assert!(('⠁'..='⣿').contains(&first), "{}", text);
+
+        // The zero line, the marked note and the key names
+        assert!(rows[5].starts_with("  0 ─"), "{}", text);
+        assert!(rows[11].contains("C2"), "{}", text);
+        assert!(rows[11].starts_with("    A0"), "{}", text);
+        assert!(rows[11].ends_with("C8"), "{}", text);
+        let marked = CurveChart::LABEL_WIDTH + CurveChart::column_for(36, 92);
+        assert!(rows
+            .iter()
+            .take(11)
+            .all(|row| row.chars().nth(marked as usize) != Some(' ')));
+    }
+
+    #[test]
+    fn test_narrow_terminal_summarizes() {
+        let chart =
+            CurveChart::new(&StretchCurve::preset(PianoType::Upright)).with_current(Some(36));
+        let text = draw(&chart, 38, 3);
+        assert_eq!(
+            text,
+            concat!(
+                "                                      \n",
+                "A0 -16.0¢  C2 -5.9¢  A4 +0.0¢  C8 +24.\n",
+                "                                      ",
+            )
+        );
+    }
+
+    const SNAPSHOT_44X6: &str = concat!(
+        "+25                      ┊             ⢀⣀⠤⠔⠊\n",
+        "                         ┊      ⣀⣀⡠⠤⠔⠒⠊⠁    \n",
+        "  0 ───────⢀⣀⣀⣀⠤⠤⠤⠤⠤⠤⠤⠤⠤⠤⠤⠒⠒⠒⠊⠉⠉────────────\n",
+        "    ⢀⣀⠤⠔⠒⠊⠉⠁             ┊                  \n",
+        "-25 ⠁                    ┊                  \n",
+        "    A0                  A4                C8",
+    );
+}
@@ -0,0 +1,149 @@
+//! Overlay showing the stretch curve about to be tuned to.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::ui::strings;
+use crate::ui::theme::{Shortcuts, Theme};
+
+use super::curve::CurveChart;
+
+/// Dims the screen and shows the curve in a centered popup.
+pub struct CurveOverlay<'a> {
+    /// Chart of the curve.
+    pub chart: &'a CurveChart,
+    /// Name of the curve.
+    pub name: &'a str,
+    /// Whether the targets follow the curve.
+    pub stretch: bool,
+}
+
+impl CurveOverlay<'_> {
+    /// Widest the popup grows.
+    const MAX_WIDTH: u16 = 100;
+
+    /// Tallest the popup grows.
+    const MAX_HEIGHT: u16 = 18;
+}
+
+impl Widget for CurveOverlay<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        buf.set_style(area, Style::default().add_modifier(Modifier::DIM));
+
+        let width = area
+            .width
+            .saturating_sub(4)
+            .min(Self::MAX_WIDTH)
+            .max(area.width.min(20));
+        let height = area
+            .height
+            .saturating_sub(2)
+            .min(Self::MAX_HEIGHT)
+            .max(area.height.min(5));
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        let title = strings::format(
+            if self.stretch {
+                "curve.title"
+            } else {
+                "curve.title_off"
+            },
+            &[("curve", &self.name)],
+        );
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Theme::border())
+            .title(format!(" {} ", title))
+            .title_style(Theme::title());
+        let inner = block.inner(popup);
+        Clear.render(popup, buf);
+        block.render(popup, buf);
+        if inner.height < 2 {
+            return;
+        }
+
+        // The chart above a line of help
+        let chart = Rect {
+            height: inner.height - 1,
+            ..inner
+        };
+        self.chart.render(chart, buf);
+        let help = Shortcuts::help(&[(Shortcuts::VIEW_CURVE, strings::get("help.close"))]);
+        Paragraph::new(help)
+            .style(Theme::muted())
+            .alignment(Alignment::Center)
+            .render(
+                Rect {
+                    y: inner.y + inner.height - 1,
+                    height: 1,
+                    ..inner
+                },
+                buf,
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuning::{PianoType, StretchCurve};
+    use ratatui::{backend::TestBackend, Terminal};
+
+    fn draw(overlay: CurveOverlay, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| frame.render_widget(overlay, frame.area()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_overlay_sizes() {
+        let chart = CurveChart::new(&StretchCurve::preset(PianoType::BabyGrand));
+        let overlay = || CurveOverlay {
+            chart: &chart,
+            name: "Baby Grand",
+            stretch: true,
+        };
+
+        let text = draw(overlay(), 80, 24);
+        assert!(text.contains(" Stretch curve: Baby Grand "), "{}", text);
+        assert!(text.contains("[V] Close"), "{}", text);
+        assert!(text.contains("A0"), "{}", text);
+        assert!(text.contains("C8"), "{}", text);
+        assert!(text.contains("+25"), "{}", text);
+
+        // Too narrow to plot, the ends are listed instead
+        let text = draw(overlay(), 36, 8);
+        assert!(text.contains("A0 -14.0¢"), "{}", text);
+        assert!(text.contains("C8 +21.0¢"), "{}", text);
+        assert!(!text.contains("+25"), "{}", text);
+
+        let text = draw(
+            CurveOverlay {
+                stretch: false,
+                ..overlay()
+            },
+            80,
+            24,
+        );
+        assert!(text.contains("Stretch curve: Baby Grand (off)"), "{}", text);
+    }
+}
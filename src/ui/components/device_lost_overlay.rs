@@ -0,0 +1,66 @@
+//! Overlay shown when the input device goes away.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::ui::strings;
+use crate::ui::theme::{Shortcuts, Theme};
+
+/// Explains that the input was lost and offers to retry or quit.
+pub struct DeviceLostOverlay<'a> {
+    /// Why the input was lost.
+    pub error: &'a str,
+    /// Whether a session was saved when it happened.
+    pub saved: bool,
+}
+
+impl Widget for DeviceLostOverlay<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        buf.set_style(area, Style::default().add_modifier(Modifier::DIM));
+
+        let help = Shortcuts::help(&[
+            (Shortcuts::RETRY, strings::get("help.retry_input")),
+            (Shortcuts::QUIT, strings::get("help.save_quit")),
+        ]);
+        let mut lines = vec![
+            Line::styled(
+                strings::get("device_lost.title"),
+                Theme::out_of_tune().add_modifier(Modifier::BOLD),
+            ),
+            Line::styled(self.error, Theme::muted()),
+            Line::raw(""),
+        ];
+        if self.saved {
+            lines.push(Line::styled(
+                strings::get("device_lost.saved"),
+                Theme::in_tune(),
+            ));
+        }
+        lines.push(Line::styled(help, Theme::accent()));
+
+        let content = lines.iter().map(Line::width).max().unwrap_or(0) as u16;
+        let width = (content + 4).min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        Clear.render(popup, buf);
+        Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Theme::out_of_tune()),
+            )
+            .render(popup, buf);
+    }
+}
@@ -0,0 +1,41 @@
+//! Overlay shown while the app is idle.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+
+use crate::ui::strings;
+use crate::ui::theme::Theme;
+
+/// Dims the screen and shows a centered wake-up hint.
+pub struct IdleOverlay;
+
+impl Widget for IdleOverlay {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        buf.set_style(area, Style::default().add_modifier(Modifier::DIM));
+
+        let message = strings::get("idle.message");
+        let width = (message.chars().count() as u16 + 4).min(area.width);
+        let height = 3.min(area.height);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        Clear.render(popup, buf);
+        Paragraph::new(message)
+            .style(Theme::accent())
+            .alignment(Alignment::Center)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Theme::muted()),
+            )
+            .render(popup, buf);
+    }
+}
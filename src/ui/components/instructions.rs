@@ -7,116 +7,223 @@ use ratatui::{
     widgets::{Block, Borders, Widget},
 };
 
+use crate::tuning::order::{StringOrder, TrichordString};
+use crate::tuning::Instrument;
+use crate::ui::strings;
 use crate::ui::theme::Theme;
 
 /// Step in the tuning process for multi-string notes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TuningStep {
     // Bichord (2 strings) - 2 steps
-    /// Mute the right string.
-    MuteBichord,
-    /// Tune left string to pitch, then right to match.
-    TuneBichord,
+    /// Mute the right string and tune the left string to pitch.
+    TunePrimary,
+    /// Remove the mute and tune the right string to unison.
+    TuneUnison,
 
     // Trichord (3 strings) - 4 steps
-    /// Mute outer strings.
-    MuteOuter,
-    /// Tune center string.
-    TuneCenter,
-    /// Tune left string to unison.
-    TuneLeft,
-    /// Tune right string to unison.
-    TuneRight,
+    /// Mute all strings but the first in the order.
+    MuteOthers(StringOrder),
+    /// Tune the n-th string of the order: the first to pitch, the others
+    /// to unison.
+    TuneString(StringOrder, usize),
 }
 
 impl TuningStep {
-    /// Create first step for given string count.
+    /// Tolerance in cents for steps tuned against the target pitch.
+    pub const PITCH_TOLERANCE: f32 = 5.0;
+
+    /// Tolerance in cents for unison steps, where beats are audible.
+    pub const UNISON_TOLERANCE: f32 = 2.0;
+
+    /// Create first step for given string count (center string first).
     pub fn first_for_strings(strings: u8) -> Option<Self> {
+        Self::first_for(strings, StringOrder::default())
+    }
+
+    /// Create first step for given string count and trichord string order.
+    pub fn first_for(strings: u8, order: StringOrder) -> Option<Self> {
         match strings {
-            2 => Some(Self::MuteBichord),
-            3 => Some(Self::MuteOuter),
+            2 => Some(Self::TunePrimary),
+            3 => Some(Self::MuteOthers(order)),
             _ => None, // Monochord has no steps
         }
     }
 
     /// Check if this is a muting step (no tuning hints).
     pub fn is_muting(&self) -> bool {
-        matches!(self, Self::MuteBichord | Self::MuteOuter)
+        matches!(self, Self::MuteOthers(_))
+    }
+
+    /// Check if this step tunes a string to unison with one already tuned.
+    pub fn is_unison(&self) -> bool {
+        matches!(self, Self::TuneUnison | Self::TuneString(_, 1..))
+    }
+
+    /// Tolerance in cents for completing this step.
+    pub fn tolerance(&self) -> f32 {
+        if self.is_unison() {
+            Self::UNISON_TOLERANCE
+        } else {
+            Self::PITCH_TOLERANCE
+        }
+    }
+
+    /// The string tuned in this step, if any.
+    fn string(&self) -> Option<TrichordString> {
+        match self {
+            Self::TunePrimary => Some(TrichordString::Left),
+            Self::TuneUnison => Some(TrichordString::Right),
+            Self::MuteOthers(_) => None,
+            Self::TuneString(order, i) => order.strings().get(*i).copied(),
+        }
+    }
+
+    /// Name of the string tuned in this step, if any.
+    pub fn string_label(&self) -> Option<&'static str> {
+        self.string().map(TrichordString::name)
     }
 
     /// Get total steps for this string type.
     pub fn total_steps(&self) -> u8 {
         match self {
-            Self::MuteBichord | Self::TuneBichord => 2,
-            Self::MuteOuter | Self::TuneCenter | Self::TuneLeft | Self::TuneRight => 4,
+            Self::TunePrimary | Self::TuneUnison => 2,
+            Self::MuteOthers(_) | Self::TuneString(..) => 4,
         }
     }
 
     /// Get step number (1-based).
     pub fn number(&self) -> u8 {
         match self {
-            Self::MuteBichord => 1,
-            Self::TuneBichord => 2,
-            Self::MuteOuter => 1,
-            Self::TuneCenter => 2,
-            Self::TuneLeft => 3,
-            Self::TuneRight => 4,
+            Self::TunePrimary => 1,
+            Self::TuneUnison => 2,
+            Self::MuteOthers(_) => 1,
+            Self::TuneString(_, i) => *i as u8 + 2,
         }
     }
 
     /// Get the step title.
-    pub fn title(&self) -> &'static str {
+    pub fn title(&self) -> String {
         match self {
-            Self::MuteBichord => "Mute right string",
-            Self::TuneBichord => "Tune left string",
-            Self::MuteOuter => "Mute outer strings",
-            Self::TuneCenter => "Tune center string",
-            Self::TuneLeft => "Tune left string",
-            Self::TuneRight => "Tune right string",
+            Self::TunePrimary => strings::get("step.tune_primary.title").to_string(),
+            Self::TuneUnison => strings::get("step.tune_unison.title").to_string(),
+            Self::MuteOthers(StringOrder::CenterFirst) => {
+                strings::get("step.mute_outer.title").to_string()
+            }
+            Self::MuteOthers(order) => {
+                let [_, a, b] = order.strings();
+                strings::format(
+                    "step.mute.title",
+                    &[("first", &string_name(a)), ("second", &string_name(b))],
+                )
+            }
+            Self::TuneString(..) => strings::format(
+                "step.tune_string.title",
+                &[(
+                    "string",
+                    &self.string().map(string_name).unwrap_or_default(),
+                )],
+            ),
         }
     }
 
     /// Get instruction text.
-    pub fn instruction(&self) -> &'static str {
+    pub fn instruction(&self) -> String {
+        match self {
+            Self::TunePrimary => strings::get("step.tune_primary.instruction").to_string(),
+            Self::TuneUnison => strings::get("step.tune_unison.instruction").to_string(),
+            Self::MuteOthers(StringOrder::CenterFirst) => {
+                strings::get("step.mute_outer.instruction").to_string()
+            }
+            Self::MuteOthers(order) => {
+                let [reference, a, b] = order.strings();
+                strings::format(
+                    "step.mute.instruction",
+                    &[
+                        ("first", &string_name(a)),
+                        ("second", &string_name(b)),
+                        ("reference", &string_name(reference)),
+                    ],
+                )
+            }
+            Self::TuneString(order, i) => {
+                let string = order.strings()[*i];
+                if *i == 0 {
+                    strings::format(
+                        "step.tune_pitch.instruction",
+                        &[("string", &string_name(string))],
+                    )
+                } else {
+                    strings::format(
+                        "step.tune_match.instruction",
+                        &[
+                            ("string", &string_name(string)),
+                            ("partner", &string_name(order.partner(string))),
+                        ],
+                    )
+                }
+            }
+        }
+    }
+
+    /// Title and instruction for a bichord step on an instrument whose
+    /// strings belong to separate choirs, isolated with stops not mutes.
+    fn choir_text(&self) -> Option<(&'static str, &'static str)> {
         match self {
-            Self::MuteBichord => "Use a felt wedge or rubber mute to mute the right string. Only the left string should sound.",
-            Self::TuneBichord => "Tune the left string to pitch. Then remove the mute and tune the right string to match.",
-            Self::MuteOuter => "Use felt strip or rubber mutes to mute the outer strings. Only the center string should sound.",
-            Self::TuneCenter => "Tune the center string to the target pitch using the meter.",
-            Self::TuneLeft => "Unmute the left string. Tune it to match the center string until you hear no beats.",
-            Self::TuneRight => "Unmute the right string. Tune it to match the center string until you hear no beats.",
+            Self::TunePrimary => Some((
+                strings::get("step.choir_primary.title"),
+                strings::get("step.choir_primary.instruction"),
+            )),
+            Self::TuneUnison => Some((
+                strings::get("step.choir_unison.title"),
+                strings::get("step.choir_unison.instruction"),
+            )),
+            _ => None,
         }
     }
 
     /// Get the next step.
     pub fn next(&self) -> Option<Self> {
-        match self {
-            Self::MuteBichord => Some(Self::TuneBichord),
-            Self::TuneBichord => None,
-            Self::MuteOuter => Some(Self::TuneCenter),
-            Self::TuneCenter => Some(Self::TuneLeft),
-            Self::TuneLeft => Some(Self::TuneRight),
-            Self::TuneRight => None,
+        match *self {
+            Self::TunePrimary => Some(Self::TuneUnison),
+            Self::TuneUnison => None,
+            Self::MuteOthers(order) => Some(Self::TuneString(order, 0)),
+            Self::TuneString(order, i) if i + 1 < order.strings().len() => {
+                Some(Self::TuneString(order, i + 1))
+            }
+            Self::TuneString(..) => None,
         }
     }
 
     /// Get the previous step.
     pub fn prev(&self) -> Option<Self> {
-        match self {
-            Self::MuteBichord => None,
-            Self::TuneBichord => Some(Self::MuteBichord),
-            Self::MuteOuter => None,
-            Self::TuneCenter => Some(Self::MuteOuter),
-            Self::TuneLeft => Some(Self::TuneCenter),
-            Self::TuneRight => Some(Self::TuneLeft),
+        match *self {
+            Self::TunePrimary => None,
+            Self::TuneUnison => Some(Self::TunePrimary),
+            Self::MuteOthers(_) => None,
+            Self::TuneString(order, 0) => Some(Self::MuteOthers(order)),
+            Self::TuneString(order, i) => Some(Self::TuneString(order, i - 1)),
         }
     }
 }
 
+/// Localized name of a trichord string.
+fn string_name(string: TrichordString) -> &'static str {
+    strings::get(match string {
+        TrichordString::Left => "string.left",
+        TrichordString::Center => "string.center",
+        TrichordString::Right => "string.right",
+    })
+}
+
 /// Instructions panel for coaching the user.
 pub struct Instructions {
     step: Option<TuningStep>,
+    instrument: Instrument,
     direction_hint: Option<String>,
+    beat_rate: Option<f32>,
+    /// Whether the beat rate was measured rather than estimated from cents.
+    beats_measured: bool,
 }
 
 impl Instructions {
@@ -124,7 +231,10 @@ impl Instructions {
     pub fn for_step(step: TuningStep, _string_count: u8) -> Self {
         Self {
             step: Some(step),
+            instrument: Instrument::default(),
             direction_hint: None,
+            beat_rate: None,
+            beats_measured: false,
         }
     }
 
@@ -132,22 +242,47 @@ impl Instructions {
     pub fn simple() -> Self {
         Self {
             step: None,
+            instrument: Instrument::default(),
             direction_hint: None,
+            beat_rate: None,
+            beats_measured: false,
         }
     }
 
+    /// Word the instructions for the given instrument. Set before the
+    /// direction hint.
+    pub fn with_instrument(mut self, instrument: Instrument) -> Self {
+        self.instrument = instrument;
+        self
+    }
+
     /// Set a direction hint based on cents deviation.
     pub fn with_direction_hint(mut self, cents: f32) -> Self {
         if cents.abs() > 5.0 {
-            let hint = if cents < 0.0 {
-                "Turn tuning pin CLOCKWISE (tighten) slightly"
+            // Only the piano's pins need the torque spelled out
+            let (raise, lower) = if self.instrument.has_pin_torque() {
+                ("instructions.tighten", "instructions.loosen")
             } else {
-                "Turn tuning pin COUNTER-CLOCKWISE (loosen) slightly"
+                ("instructions.raise", "instructions.lower")
             };
+            let hint = strings::get(if cents < 0.0 { raise } else { lower });
             self.direction_hint = Some(hint.to_string());
         }
         self
     }
+
+    /// Show the beat rate against the reference string (unison steps).
+    pub fn with_beat_rate(mut self, beats_per_sec: f32) -> Self {
+        self.beat_rate = Some(beats_per_sec);
+        self
+    }
+
+    /// Show the beat rate measured from the sound (unison steps).
+    pub fn with_measured_beat_rate(mut self, beats_per_sec: f32) -> Self {
+        self.beat_rate = Some(beats_per_sec);
+        self.beats_measured = true;
+        self
+    }
 }
 
 impl Widget for Instructions {
@@ -165,38 +300,45 @@ impl Widget for Instructions {
         }
 
         let mut y = inner.y;
+        let width = inner.width.saturating_sub(2) as usize;
 
         if let Some(step) = &self.step {
             // Multi-string note with steps (bichord or trichord)
+            let (title, instruction) = match step.choir_text() {
+                Some((title, instruction)) if self.instrument.has_choirs() => {
+                    (title.to_string(), instruction.to_string())
+                }
+                _ => (step.title(), step.instruction()),
+            };
+
             // Step indicator
-            let step_text = format!(
-                "Step {} of {}: {}",
-                step.number(),
-                step.total_steps(),
-                step.title()
+            let step_text = strings::format(
+                "instructions.step",
+                &[
+                    ("number", &step.number()),
+                    ("total", &step.total_steps()),
+                    ("title", &title),
+                ],
             );
             let step_style = Theme::accent();
-            buf.set_string(inner.x + 1, y, &step_text, step_style);
+            buf.set_stringn(inner.x + 1, y, &step_text, width, step_style);
             y += 2;
 
             // Instruction text
             if y < inner.y + inner.height {
-                let instruction = step.instruction();
-                let available_width = inner.width.saturating_sub(2) as usize;
-
                 // Word wrap
-                for line in textwrap(instruction, available_width) {
+                for line in textwrap(&instruction, width) {
                     if y >= inner.y + inner.height {
                         break;
                     }
-                    buf.set_string(inner.x + 1, y, &line, Style::default());
+                    buf.set_stringn(inner.x + 1, y, &line, width, Style::default());
                     y += 1;
                 }
             }
         } else {
             // Monochord note - simple instruction
-            let text = "Tune this string to the target pitch using the meter.";
-            buf.set_string(inner.x + 1, y, text, Style::default());
+            let text = strings::get("instructions.simple");
+            buf.set_stringn(inner.x + 1, y, text, width, Style::default());
             y += 2;
         }
 
@@ -204,17 +346,32 @@ impl Widget for Instructions {
         if let Some(hint) = &self.direction_hint {
             if y < inner.y + inner.height {
                 y += 1;
-                buf.set_string(inner.x + 1, y, hint, Theme::warning());
+                buf.set_stringn(inner.x + 1, y, hint, width, Theme::warning());
+            }
+        }
+
+        // Beating against the reference string
+        if let Some(rate) = self.beat_rate {
+            if y + 1 < inner.y + inner.height {
+                y += 1;
+                let key = if self.beats_measured {
+                    "instructions.beats"
+                } else {
+                    "instructions.beating"
+                };
+                let text = strings::format(key, &[("rate", &format!("{:.1}", rate))]);
+                buf.set_stringn(inner.x + 1, y, &text, width, Theme::accent());
             }
         }
 
         // Press SPACE prompt
         if y + 1 < inner.y + inner.height {
-            let prompt = "Press SPACE to continue";
-            buf.set_string(
+            let prompt = strings::get("instructions.continue");
+            buf.set_stringn(
                 inner.x + 1,
                 inner.y + inner.height - 1,
                 prompt,
+                width,
                 Theme::muted(),
             );
         }
@@ -229,7 +386,7 @@ fn textwrap(text: &str, max_width: usize) -> Vec<String> {
     for word in text.split_whitespace() {
         if current_line.is_empty() {
             current_line = word.to_string();
-        } else if current_line.len() + 1 + word.len() <= max_width {
+        } else if current_line.chars().count() + 1 + word.chars().count() <= max_width {
             current_line.push(' ');
             current_line.push_str(word);
         } else {
@@ -1,7 +1,11 @@
 //! Cents deviation meter component.
 
+use std::time::Duration;
+
 use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
 
+use super::Readout;
+use crate::ui::strings;
 use crate::ui::theme::{BoxChars, Theme};
 
 /// Cents deviation meter for visualizing pitch accuracy.
@@ -13,6 +17,16 @@ pub struct Meter {
     detecting: bool,
     /// Tolerance threshold in cents.
     tolerance: f32,
+    /// Held (flat, sharp) excursions to mark, if any.
+    peaks: Option<(f32, f32)>,
+    /// Whether the value is a held reading after the note decayed.
+    held: bool,
+    /// Message shown instead of "Listening..." when not detecting.
+    message: Option<String>,
+    /// Format of the value below the meter.
+    readout: Readout,
+    /// Target in Hz, for readouts in Hz.
+    target: f32,
 }
 
 impl Meter {
@@ -22,6 +36,11 @@ impl Meter {
             cents,
             detecting: true,
             tolerance: 5.0,
+            peaks: None,
+            held: false,
+            message: None,
+            readout: Readout::default(),
+            target: 0.0,
         }
     }
 
@@ -31,6 +50,11 @@ impl Meter {
             cents: 0.0,
             detecting: false,
             tolerance: 5.0,
+            peaks: None,
+            held: false,
+            message: None,
+            readout: Readout::default(),
+            target: 0.0,
         }
     }
 
@@ -45,14 +69,110 @@ impl Meter {
         self.detecting = detecting;
         self
     }
+
+    /// Show the value as held from a note that has decayed (dimmed).
+    pub fn held(mut self, held: bool) -> Self {
+        self.held = held;
+        self
+    }
+
+    /// Show a message in place of "Listening..." (e.g. detection paused).
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    /// Format the value below the meter with `readout`, relative to a
+    /// `target` in Hz. The meter itself stays in cents.
+    pub fn readout(mut self, readout: Readout, target: f32) -> Self {
+        self.readout = readout;
+        self.target = target;
+        self
+    }
+
+    /// Mark the held peak excursions.
+    pub fn peak_hold(mut self, hold: &PeakHold) -> Self {
+        self.peaks = hold.flat().zip(hold.sharp());
+        self
+    }
+}
+
+/// Peak-hold state for the meter.
+///
+/// Tracks the furthest flat and sharp excursions, holds them for
+/// [`HOLD`](Self::HOLD) and then lets them decay back toward the current value.
+#[derive(Debug, Clone, Default)]
+pub struct PeakHold {
+    /// Furthest flat excursion and when it was reached.
+    flat: Option<(f32, Duration)>,
+    /// Furthest sharp excursion and when it was reached.
+    sharp: Option<(f32, Duration)>,
+    /// Time of the last update.
+    last_update: Option<Duration>,
+}
+
+impl PeakHold {
+    /// How long a peak is held before it starts to decay.
+    pub const HOLD: Duration = Duration::from_secs(2);
+
+    /// Time constant of the decay toward the current value.
+    pub const DECAY: Duration = Duration::from_secs(1);
+
+    /// Create an empty peak hold.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the current deviation.
+    pub fn update(&mut self, now: Duration, cents: f32) {
+        let elapsed = self
+            .last_update
+            .map(|t| now.saturating_sub(t))
+            .unwrap_or_default();
+        let decay = (-elapsed.as_secs_f32() / Self::DECAY.as_secs_f32()).exp();
+        self.last_update = Some(now);
+
+        self.sharp = Some(match self.sharp {
+            Some((peak, _)) if cents >= peak => (cents, now),
+            Some((peak, at)) if now.saturating_sub(at) > Self::HOLD => {
+                (cents + (peak - cents) * decay, at)
+            }
+            Some(held) => held,
+            None => (cents, now),
+        });
+
+        self.flat = Some(match self.flat {
+            Some((peak, _)) if cents <= peak => (cents, now),
+            Some((peak, at)) if now.saturating_sub(at) > Self::HOLD => {
+                (cents + (peak - cents) * decay, at)
+            }
+            Some(held) => held,
+            None => (cents, now),
+        });
+    }
+
+    /// Held sharp excursion in cents.
+    pub fn sharp(&self) -> Option<f32> {
+        self.sharp.map(|(cents, _)| cents)
+    }
+
+    /// Held flat excursion in cents.
+    pub fn flat(&self) -> Option<f32> {
+        self.flat.map(|(cents, _)| cents)
+    }
+
+    /// Forget the held peaks.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
 }
 
 impl Meter {
     /// Convert cents to screen position using logarithmic scale.
-    /// Values within ±tolerance return 0 (center).
+    /// Values within ±tolerance (or NaN) return 0 (center).
     /// Values outside use log scale: more resolution near center, compressed at edges.
     pub fn log_position(cents: f32, max_cents: f32, half_width: f32, tolerance: f32) -> f32 {
-        if cents.abs() <= tolerance {
+        if cents.is_nan() || cents.abs() <= tolerance {
             return 0.0;
         }
 
@@ -95,14 +215,15 @@ impl Widget for Meter {
             }
             let x_offset = Self::log_position(cents as f32, max_cents, half_width, self.tolerance);
             let x = (center_x as f32 + x_offset) as u16;
-            if x >= area.x && x + label.len() as u16 <= area.x + area.width {
+            let width = label.chars().count() as u16;
+            if x >= area.x && x + width <= area.x + area.width {
                 let style = if cents == 0 {
                     Theme::accent()
                 } else {
                     Theme::muted()
                 };
                 buf.set_string(
-                    x.saturating_sub(label.len() as u16 / 2),
+                    x.saturating_sub(width / 2).max(area.x),
                     label_y,
                     &label,
                     style,
@@ -139,12 +260,38 @@ impl Widget for Meter {
             }
         }
 
+        // Peak-hold markers (outside the in-tune zone only)
+        if let (true, Some((flat, sharp))) = (self.detecting, self.peaks) {
+            for peak in [flat, sharp] {
+                if peak.abs() <= self.tolerance {
+                    continue;
+                }
+                let clamped = peak.clamp(-max_cents, max_cents);
+                let x_offset = Self::log_position(clamped, max_cents, half_width, self.tolerance);
+                let x = (center_x as f32 + x_offset) as u16;
+                if x >= area.x && x < area.x + area.width {
+                    for row in 0..meter_height {
+                        buf.set_string(
+                            x,
+                            meter_y_start + row,
+                            BoxChars::PEAK_MARKER.to_string(),
+                            Theme::style_for_cents(peak),
+                        );
+                    }
+                }
+            }
+        }
+
         // Fixed in-tune zone width (in characters)
         let in_tune_zone_width: u16 = 7;
 
         // Draw the indicator if detecting
         if self.detecting {
-            let style = Theme::style_for_cents(self.cents);
+            let style = if self.held {
+                Theme::muted()
+            } else {
+                Theme::style_for_cents(self.cents)
+            };
 
             if self.cents.abs() <= self.tolerance {
                 // Within tolerance: draw fixed, wide green zone at center (no movement)
@@ -174,31 +321,44 @@ impl Widget for Meter {
                 }
             }
 
-            // Draw cents value below meter
-            let cents_text = format!("{:+.1} cents", self.cents);
-            let cents_x = center_x.saturating_sub(cents_text.len() as u16 / 2);
+            // Draw the value below meter
+            let reading = self.readout.format(self.cents, self.target);
+            let cents_text = if self.held {
+                strings::format("meter.held", &[("reading", &reading)])
+            } else {
+                reading
+            };
+            let cents_x = center_x.saturating_sub(cents_text.chars().count() as u16 / 2);
             let cents_y = meter_y_start + meter_height;
             buf.set_string(cents_x, cents_y, &cents_text, style);
 
             // Draw direction hint if significantly off
-            if self.cents.abs() > self.tolerance {
+            if self.cents.abs() > self.tolerance && !self.held {
                 let hint = if self.cents < 0.0 {
-                    format!("{} Tighten", BoxChars::RIGHT_ARROW)
+                    format!(
+                        "{} {}",
+                        BoxChars::RIGHT_ARROW,
+                        strings::get("meter.tighten")
+                    )
                 } else {
-                    format!("Loosen {}", BoxChars::LEFT_ARROW)
+                    format!("{} {}", strings::get("meter.loosen"), BoxChars::LEFT_ARROW)
                 };
                 let hint_y = cents_y + 1;
                 if hint_y < area.y + area.height {
-                    let hint_x = center_x.saturating_sub(hint.len() as u16 / 2);
+                    let hint_x = center_x.saturating_sub(hint.chars().count() as u16 / 2);
                     buf.set_string(hint_x, hint_y, &hint, style);
                 }
             }
         } else {
-            // Show "Listening..." message
-            let msg = "Listening...";
-            let msg_x = center_x.saturating_sub(msg.len() as u16 / 2);
+            // Show "Listening..." or the given message
+            let msg = self
+                .message
+                .as_deref()
+                .unwrap_or(strings::get("common.listening"));
+            let msg_width = (msg.chars().count() as u16).min(area.width);
+            let msg_x = center_x.saturating_sub(msg_width / 2).max(area.x);
             let msg_y = meter_y_start + meter_height / 2;
-            buf.set_string(msg_x, msg_y, msg, Theme::muted());
+            buf.set_stringn(msg_x, msg_y, msg, area.width as usize, Theme::muted());
         }
     }
 }
@@ -245,3 +405,99 @@ impl Widget for CompactMeter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 60,
+        height: 8,
+    };
+
+    fn marker_x(cents: f32) -> u16 {
+        let half_width = (AREA.width / 2 - 1) as f32;
+        (30.0 + Meter::log_position(cents, 500.0, half_width, 5.0)) as u16
+    }
+
+    fn render(cents: f32, hold: &PeakHold) -> Buffer {
+        let mut buf = Buffer::empty(AREA);
+        Meter::new(cents).peak_hold(hold).render(AREA, &mut buf);
+        buf
+    }
+
+    fn markers(buf: &Buffer) -> Vec<u16> {
+        (0..AREA.width)
+            .filter(|&x| buf[(x, 2)].symbol() == BoxChars::PEAK_MARKER.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_peaks_marked() {
+        let mut hold = PeakHold::new();
+        let cents = [0.0, 30.0, 80.0, 20.0, -40.0, -10.0, 0.0];
+        for (i, &c) in cents.iter().enumerate() {
+            hold.update(Duration::from_millis(100 * i as u64), c);
+        }
+        assert_eq!(hold.sharp(), Some(80.0));
+        assert_eq!(hold.flat(), Some(-40.0));
+
+        let buf = render(0.0, &hold);
+        assert_eq!(markers(&buf), vec![marker_x(-40.0), marker_x(80.0)]);
+        assert_eq!(buf[(marker_x(80.0), 2)].fg, Theme::OUT_OF_TUNE);
+    }
+
+    #[test]
+    fn test_peaks_decay_to_center() {
+        let mut hold = PeakHold::new();
+        hold.update(Duration::ZERO, 0.0);
+        hold.update(Duration::from_millis(100), 80.0);
+
+        // Held while within the hold time
+        let mut t = 100;
+        while t < 2000 {
+            t += 100;
+            hold.update(Duration::from_millis(t), 0.0);
+        }
+        assert_eq!(hold.sharp(), Some(80.0));
+        assert_eq!(markers(&render(0.0, &hold)), vec![marker_x(80.0)]);
+
+        // Partway back after one time constant
+        while t < 3100 {
+            t += 100;
+            hold.update(Duration::from_millis(t), 0.0);
+        }
+        let sharp = hold.sharp().unwrap();
+        assert!(sharp > 5.0 && sharp < 80.0 * 0.5, "sharp = {sharp}");
+        assert_eq!(markers(&render(0.0, &hold)), vec![marker_x(sharp)]);
+
+        // Back inside the in-tune zone: no marker
+        while t < 8000 {
+            t += 100;
+            hold.update(Duration::from_millis(t), 0.0);
+        }
+        assert!(hold.sharp().unwrap() < 5.0);
+        assert!(markers(&render(0.0, &hold)).is_empty());
+    }
+
+    #[test]
+    fn test_log_position_bounded() {
+        assert_eq!(Meter::log_position(f32::NAN, 50.0, 20.0, 2.0), 0.0);
+        assert_eq!(Meter::log_position(f32::INFINITY, 50.0, 20.0, 2.0), 20.0);
+        assert_eq!(
+            Meter::log_position(f32::NEG_INFINITY, 50.0, 20.0, 2.0),
+            -20.0
+        );
+    }
+
+    #[test]
+    fn test_reset_clears_peaks() {
+        let mut hold = PeakHold::new();
+        hold.update(Duration::ZERO, -60.0);
+        hold.reset();
+        assert_eq!(hold.flat(), None);
+        assert!(markers(&render(0.0, &hold)).is_empty());
+    }
+}
@@ -1,11 +1,29 @@
 //! Reusable UI components.
 
+pub mod a4_field;
+pub mod cents_chart;
+pub mod clip_banner;
+pub mod curve;
+pub mod curve_overlay;
+pub mod device_lost_overlay;
+pub mod idle_overlay;
 pub mod instructions;
 pub mod meter;
 pub mod piano;
 pub mod progress;
+pub mod readout;
+pub mod stability;
 
+pub use a4_field::A4Field;
+pub use cents_chart::CentsChart;
+pub use clip_banner::ClipBanner;
+pub use curve::CurveChart;
+pub use curve_overlay::CurveOverlay;
+pub use device_lost_overlay::DeviceLostOverlay;
+pub use idle_overlay::IdleOverlay;
 pub use instructions::Instructions;
-pub use meter::Meter;
+pub use meter::{Meter, PeakHold};
 pub use piano::Piano;
 pub use progress::Progress;
+pub use readout::{DisplayUnit, Readout};
+pub use stability::{Stability, StabilityIndicator, StabilityTracker};
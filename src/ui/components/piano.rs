@@ -13,7 +13,7 @@
 //!         C D   F G A
 //! ```
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use ratatui::{
     buffer::Buffer,
@@ -22,6 +22,8 @@ use ratatui::{
     widgets::Widget,
 };
 
+use crate::ui::theme::Theme;
+
 /// Characters for piano rendering.
 pub mod chars {
     pub const EDGE: char = '║';
@@ -67,6 +69,10 @@ pub struct Piano {
     num_keys: usize,
     /// Set of highlighted key indices (relative to start).
     highlighted: HashSet<usize>,
+    /// Final cents per key index, colored by severity.
+    results: HashMap<usize, f32>,
+    /// Skipped key indices (shown muted).
+    skipped: HashSet<usize>,
     /// Currently active key (shown with accent color).
     current: Option<usize>,
     /// Color for highlighted keys.
@@ -84,6 +90,8 @@ impl Piano {
             start_midi,
             num_keys,
             highlighted: HashSet::new(),
+            results: HashMap::new(),
+            skipped: HashSet::new(),
             current: None,
             on_color: Color::Green,
             current_color: Color::Cyan,
@@ -107,6 +115,18 @@ impl Piano {
         self
     }
 
+    /// Set tuning results (final cents per key index), colored by severity.
+    pub fn with_results(mut self, results: HashMap<usize, f32>) -> Self {
+        self.results = results;
+        self
+    }
+
+    /// Set skipped keys, shown in the muted color.
+    pub fn with_skipped(mut self, keys: HashSet<usize>) -> Self {
+        self.skipped = keys;
+        self
+    }
+
     /// Set the currently active key.
     pub fn current(mut self, key: Option<usize>) -> Self {
         self.current = key;
@@ -265,15 +285,22 @@ impl Piano {
         result
     }
 
-    /// Check if a key is "on" (highlighted or current).
+    /// Check if a key is "on" (highlighted, has a result, or current).
     fn is_on(&self, index: usize) -> bool {
-        self.highlighted.contains(&index) || self.current == Some(index)
+        self.highlighted.contains(&index)
+            || self.results.contains_key(&index)
+            || self.skipped.contains(&index)
+            || self.current == Some(index)
     }
 
     /// Get style for a key.
     fn key_style(&self, index: usize) -> Style {
         if self.current == Some(index) {
             Style::default().fg(self.current_color)
+        } else if let Some(&cents) = self.results.get(&index) {
+            Theme::style_for_cents(cents)
+        } else if self.skipped.contains(&index) {
+            Theme::muted()
         } else if self.highlighted.contains(&index) {
             Style::default().fg(self.on_color)
         } else {
@@ -535,4 +562,26 @@ mod tests {
         assert_eq!(cells[3], Cell::White(1)); // C
         assert_eq!(cells[4], Cell::Edge);
     }
+
+    #[test]
+    fn test_results_colored_by_severity() {
+        // A0 (index 0) out of tune, A#0 in tune, B0 (index 2) skipped
+        let results: HashMap<usize, f32> = [(0, -20.0), (1, 2.0)].into_iter().collect();
+        let skipped: HashSet<usize> = [2].into_iter().collect();
+        let piano = Piano::new(21, 3)
+            .with_results(results)
+            .with_skipped(skipped);
+
+        let area = Rect::new(0, 0, 5, 4);
+        let mut buf = Buffer::empty(area);
+        piano.render(area, &mut buf);
+
+        // Cells: edge, A0, A#0, B0, edge
+        assert_eq!(buf[(1, 0)].symbol(), chars::WHITE_ON.to_string());
+        assert_eq!(buf[(1, 0)].fg, Theme::OUT_OF_TUNE);
+        assert_eq!(buf[(2, 0)].symbol(), chars::BLACK_ON.to_string());
+        assert_eq!(buf[(2, 0)].fg, Theme::IN_TUNE);
+        assert_eq!(buf[(3, 2)].symbol(), chars::WHITE_ON.to_string());
+        assert_eq!(buf[(3, 2)].fg, Theme::MUTED);
+    }
 }
@@ -1,5 +1,7 @@
 //! Progress indicator component.
 
+use std::ops::Range;
+
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -14,6 +16,8 @@ pub struct Progress {
     total: usize,
     note_name: String,
     phase_name: String,
+    /// Final cents per position when drawing per-note cells.
+    note_quality: Option<Vec<Option<f32>>>,
 }
 
 impl Progress {
@@ -29,9 +33,18 @@ impl Progress {
             total,
             note_name: note_name.into(),
             phase_name: phase_name.into(),
+            note_quality: None,
         }
     }
 
+    /// Draw the bar as per-note cells tinted by each note's final cents.
+    ///
+    /// `quality` is indexed by position; `None` marks notes not (yet) tuned.
+    pub fn with_note_quality(mut self, quality: Vec<Option<f32>>) -> Self {
+        self.note_quality = Some(quality);
+        self
+    }
+
     /// Get progress as a ratio (0.0 to 1.0).
     pub fn ratio(&self) -> f64 {
         if self.total == 0 {
@@ -42,6 +55,47 @@ impl Progress {
     }
 }
 
+/// Positions covered by a bar cell.
+///
+/// With at least one cell per note each cell covers a single note;
+/// otherwise consecutive notes are grouped into each cell.
+pub fn cell_notes(cell: usize, cells: usize, notes: usize) -> Range<usize> {
+    if cells == 0 || notes == 0 {
+        return 0..0;
+    }
+    let start = cell * notes / cells;
+    let end = ((cell + 1) * notes / cells).max(start + 1).min(notes);
+    start..end
+}
+
+/// Draw per-note quality cells on a single line.
+fn render_quality_cells(quality: &[Option<f32>], current: usize, area: Rect, buf: &mut Buffer) {
+    let cells = area.width as usize;
+    let total = quality.len();
+
+    for cell in 0..cells {
+        let notes = cell_notes(cell, cells, total);
+        let x = area.x + cell as u16;
+
+        if notes.contains(&current) {
+            buf.set_string(x, area.y, "┃", Theme::accent());
+            continue;
+        }
+
+        // Worst tuned note in the cell
+        let worst = quality[notes]
+            .iter()
+            .flatten()
+            .copied()
+            .max_by(|a, b| a.abs().total_cmp(&b.abs()));
+
+        match worst {
+            Some(cents) => buf.set_string(x, area.y, "█", Theme::style_for_cents(cents)),
+            None => buf.set_string(x, area.y, "░", Theme::muted()),
+        };
+    }
+}
+
 impl Widget for Progress {
     fn render(self, area: Rect, buf: &mut Buffer) {
         if area.height < 1 || area.width < 20 {
@@ -69,6 +123,11 @@ impl Widget for Progress {
                 height: 1,
             };
 
+            if let Some(quality) = &self.note_quality {
+                render_quality_cells(quality, self.current, bar_area, buf);
+                return;
+            }
+
             let percent = (self.ratio() * 100.0) as u16;
             let gauge = Gauge::default()
                 .ratio(self.ratio())
@@ -104,3 +163,65 @@ impl Widget for CompactProgress {
         buf.set_string(area.x, area.y, &text, Theme::muted());
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::style::Color;
+
+    fn render_cells(quality: Vec<Option<f32>>, current: usize, width: u16) -> Buffer {
+        let total = quality.len();
+        let area = Rect::new(0, 0, width, 2);
+        let mut buf = Buffer::empty(area);
+        Progress::new(current, total, "A4", "Trichord")
+            .with_note_quality(quality)
+            .render(area, &mut buf);
+        buf
+    }
+
+    #[test]
+    fn test_cell_grouping() {
+        // One cell per note when wide enough
+        assert_eq!(cell_notes(0, 88, 88), 0..1);
+        assert_eq!(cell_notes(87, 88, 88), 87..88);
+        assert_eq!(cell_notes(3, 100, 20), 0..1);
+
+        // 88 notes on 40 cells: two or three notes per cell
+        assert_eq!(cell_notes(0, 40, 88), 0..2);
+        assert_eq!(cell_notes(1, 40, 88), 2..4);
+        assert_eq!(cell_notes(39, 40, 88), 85..88);
+        let covered: usize = (0..40).map(|c| cell_notes(c, 40, 88).len()).sum();
+        assert_eq!(covered, 88);
+    }
+
+    #[test]
+    fn test_cells_colored_per_note() {
+        let mut quality = vec![None; 20];
+        quality[0] = Some(1.0);
+        quality[1] = Some(-8.0);
+        quality[2] = Some(22.0);
+        let buf = render_cells(quality, 3, 20);
+
+        assert_eq!(buf[(0, 1)].fg, Color::Green);
+        assert_eq!(buf[(1, 1)].fg, Color::Yellow);
+        assert_eq!(buf[(2, 1)].fg, Color::Red);
+        assert_eq!(buf[(3, 1)].symbol(), "┃");
+        assert_eq!(buf[(4, 1)].symbol(), "░");
+        assert_eq!(buf[(4, 1)].fg, Theme::MUTED);
+    }
+
+    #[test]
+    fn test_grouped_cells_use_worst_note() {
+        // 40 notes on 20 cells: two notes per cell
+        let mut quality = vec![None; 40];
+        quality[0] = Some(1.0);
+        quality[1] = Some(18.0);
+        quality[2] = Some(-2.0);
+        quality[3] = Some(3.0);
+        let buf = render_cells(quality, 10, 20);
+
+        assert_eq!(buf[(0, 1)].fg, Color::Red);
+        assert_eq!(buf[(1, 1)].fg, Color::Green);
+        assert_eq!(buf[(5, 1)].symbol(), "┃");
+    }
+}
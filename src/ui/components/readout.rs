@@ -0,0 +1,140 @@
+//! Numeric readout of a deviation from the target.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ui::strings;
+
+/// Unit the deviation is shown in. The meter itself is always in cents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisplayUnit {
+    /// Cents everywhere.
+    #[default]
+    Cents,
+    /// Signed frequency difference in Hz.
+    Hz,
+    /// Hz in the bass, cents above.
+    Auto,
+}
+
+/// Formats a deviation in the configured unit and precision.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Readout {
+    unit: DisplayUnit,
+    precision: u8,
+}
+
+impl Readout {
+    /// Targets below this frequency are shown in Hz in auto mode.
+    pub const AUTO_HZ_BELOW: f32 = 100.0;
+
+    /// Most decimal places allowed.
+    pub const MAX_PRECISION: u8 = 2;
+
+    /// Default decimal places.
+    pub const DEFAULT_PRECISION: u8 = 1;
+
+    /// Create a readout with `precision` decimal places (at most 2).
+    pub fn new(unit: DisplayUnit, precision: u8) -> Self {
+        Self {
+            unit,
+            precision: precision.min(Self::MAX_PRECISION),
+        }
+    }
+
+    /// Whether a deviation from `target` Hz is shown in Hz.
+    pub fn shows_hz(&self, target: f32) -> bool {
+        match self.unit {
+            DisplayUnit::Cents => false,
+            DisplayUnit::Hz => true,
+            DisplayUnit::Auto => target < Self::AUTO_HZ_BELOW,
+        }
+    }
+
+    /// Deviation of `cents` from `target` Hz with its unit, e.g.
+    /// "+5.0 cents" or "+0.080 Hz".
+    pub fn format(&self, cents: f32, target: f32) -> String {
+        if self.shows_hz(target) {
+            let hz = target * (2.0_f32.powf(cents / 1200.0) - 1.0);
+            let places = self.precision as usize + Self::extra_hz_places(target);
+            strings::format("common.hz", &[("hz", &format!("{:+.*}", places, hz))])
+        } else {
+            let cents = format!("{:+.*}", self.precision as usize, cents);
+            strings::format("common.cents", &[("cents", &cents)])
+        }
+    }
+
+    /// Average absolute deviation over notes of different targets: in Hz
+    /// only when everything is shown in Hz, as auto mode mixes units.
+    pub fn format_average(&self, cents: f32, hz: f32) -> String {
+        if self.unit == DisplayUnit::Hz {
+            format!("{:.*} Hz", self.precision as usize + 1, hz)
+        } else {
+            format!("{:.*}", self.precision as usize, cents)
+        }
+    }
+
+    /// A cent is a smaller step in Hz the lower the note, so low targets
+    /// get more decimal places: two below 100 Hz, one below 1 kHz.
+    fn extra_hz_places(target: f32) -> usize {
+        if target < 100.0 {
+            2
+        } else if target < 1000.0 {
+            1
+        } else {
+            0
+        }
+    }
+}
+
+impl Default for Readout {
+    fn default() -> Self {
+        Self::new(DisplayUnit::default(), Self::DEFAULT_PRECISION)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A0: f32 = 27.5;
+    const A4: f32 = 440.0;
+
+    #[test]
+    fn test_five_cents_in_each_mode() {
+        let cents = Readout::new(DisplayUnit::Cents, 1);
+        assert_eq!(cents.format(5.0, A0), "+5.0 cents");
+        assert_eq!(cents.format(5.0, A4), "+5.0 cents");
+
+        let hz = Readout::new(DisplayUnit::Hz, 1);
+        assert_eq!(hz.format(5.0, A0), "+0.080 Hz");
+        assert_eq!(hz.format(5.0, A4), "+1.27 Hz");
+        assert_eq!(hz.format(-5.0, A4), "-1.27 Hz");
+
+        let auto = Readout::new(DisplayUnit::Auto, 1);
+        assert_eq!(auto.format(5.0, A0), "+0.080 Hz");
+        assert_eq!(auto.format(5.0, A4), "+5.0 cents");
+    }
+
+    #[test]
+    fn test_precision() {
+        assert_eq!(
+            Readout::new(DisplayUnit::Cents, 0).format(5.0, A4),
+            "+5 cents"
+        );
+        assert_eq!(
+            Readout::new(DisplayUnit::Cents, 2).format(5.0, A4),
+            "+5.00 cents"
+        );
+        assert_eq!(Readout::new(DisplayUnit::Hz, 0).format(5.0, A0), "+0.08 Hz");
+        assert_eq!(
+            Readout::new(DisplayUnit::Hz, 2).format(5.0, A4),
+            "+1.273 Hz"
+        );
+        // Clamped to two places
+        assert_eq!(
+            Readout::new(DisplayUnit::Cents, 5),
+            Readout::new(DisplayUnit::Cents, 2)
+        );
+    }
+}
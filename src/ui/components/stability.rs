@@ -0,0 +1,260 @@
+//! Stability of the recent readings: steady, wavering or unstable.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use ratatui::{buffer::Buffer, layout::Rect, style::Style, widgets::Widget};
+
+use crate::ui::strings;
+use crate::ui::theme::Theme;
+
+/// How settled the recent readings are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stability {
+    /// Readings agree to within a cent or so.
+    Steady,
+    /// Readings drift by a few cents.
+    Wavering,
+    /// Readings jump around.
+    Unstable,
+}
+
+impl Stability {
+    /// Largest spread (standard deviation in cents) that counts as steady.
+    pub const STEADY_SPREAD: f32 = 1.0;
+
+    /// Largest spread that counts as wavering.
+    pub const WAVERING_SPREAD: f32 = 3.0;
+
+    /// Classify a spread in cents.
+    pub fn from_spread(spread: f32) -> Self {
+        if spread <= Self::STEADY_SPREAD {
+            Stability::Steady
+        } else if spread <= Self::WAVERING_SPREAD {
+            Stability::Wavering
+        } else {
+            Stability::Unstable
+        }
+    }
+
+    /// Label used in logs.
+    pub fn label(self) -> &'static str {
+        match self {
+            Stability::Steady => "steady",
+            Stability::Wavering => "wavering",
+            Stability::Unstable => "unstable",
+        }
+    }
+
+    /// Localized label for display.
+    pub fn display_label(self) -> &'static str {
+        strings::get(match self {
+            Stability::Steady => "stability.steady",
+            Stability::Wavering => "stability.wavering",
+            Stability::Unstable => "stability.unstable",
+        })
+    }
+
+    /// Icon shown before the label.
+    pub fn icon(self) -> char {
+        match self {
+            Stability::Steady => '●',
+            Stability::Wavering => '◐',
+            Stability::Unstable => '○',
+        }
+    }
+
+    /// Display style.
+    pub fn style(self) -> Style {
+        match self {
+            Stability::Steady => Theme::in_tune(),
+            Stability::Wavering => Theme::warning(),
+            Stability::Unstable => Theme::out_of_tune(),
+        }
+    }
+}
+
+/// Rolling spread of the cents readings over the last [`Self::WINDOW`].
+#[derive(Debug, Clone, Default)]
+pub struct StabilityTracker {
+    /// Recent readings with their timestamps, oldest first.
+    readings: VecDeque<(Duration, f32)>,
+}
+
+impl StabilityTracker {
+    /// Span of readings considered.
+    pub const WINDOW: Duration = Duration::from_secs(1);
+
+    /// Readings needed before stability is reported.
+    pub const MIN_READINGS: usize = 5;
+
+    /// Create an empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a reading, dropping those older than the window.
+    pub fn push(&mut self, now: Duration, cents: f32) {
+        self.readings.push_back((now, cents));
+        while self
+            .readings
+            .front()
+            .is_some_and(|&(t, _)| now.saturating_sub(t) > Self::WINDOW)
+        {
+            self.readings.pop_front();
+        }
+    }
+
+    /// Forget all readings.
+    pub fn reset(&mut self) {
+        self.readings.clear();
+    }
+
+    /// Standard deviation of the readings in cents, once there are enough.
+    pub fn spread(&self) -> Option<f32> {
+        if self.readings.len() < Self::MIN_READINGS {
+            return None;
+        }
+        Some(std_dev(self.readings.iter().map(|&(_, c)| c)))
+    }
+
+    /// Whether the last `min_frames` readings agree to within
+    /// `tolerance_cents` (standard deviation). False until there are that
+    /// many, so a string still gliding after a pin turn isn't stable.
+    pub fn is_stable(&self, tolerance_cents: f32, min_frames: usize) -> bool {
+        let n = self.readings.len();
+        if n < min_frames.max(1) {
+            return false;
+        }
+        let recent = self.readings.iter().skip(n - min_frames.max(1));
+        std_dev(recent.map(|&(_, c)| c)) <= tolerance_cents
+    }
+
+    /// Stability of the readings, once there are enough.
+    pub fn stability(&self) -> Option<Stability> {
+        self.spread().map(Stability::from_spread)
+    }
+}
+
+/// Standard deviation of a non-empty sequence.
+fn std_dev(values: impl Iterator<Item = f32> + Clone) -> f32 {
+    let n = values.clone().count() as f32;
+    let mean = values.clone().sum::<f32>() / n;
+    let variance = values.map(|c| (c - mean).powi(2)).sum::<f32>() / n;
+    variance.sqrt()
+}
+
+/// Small stability readout: icon and label, plus the spread when wide.
+pub struct StabilityIndicator {
+    /// Spread in cents.
+    spread: f32,
+}
+
+impl StabilityIndicator {
+    /// Width from which the numeric spread is shown.
+    pub const WIDE: u16 = 20;
+
+    /// Create an indicator for a spread in cents.
+    pub fn new(spread: f32) -> Self {
+        Self { spread }
+    }
+
+    /// Text for the given available width.
+    pub fn text(&self, width: u16) -> String {
+        let stability = Stability::from_spread(self.spread);
+        if width >= Self::WIDE {
+            format!(
+                "{} {} ±{:.1}¢",
+                stability.icon(),
+                stability.display_label(),
+                self.spread
+            )
+        } else {
+            format!("{} {}", stability.icon(), stability.display_label())
+        }
+    }
+}
+
+impl Widget for StabilityIndicator {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if area.height < 1 {
+            return;
+        }
+        let text = self.text(area.width);
+        let style = Stability::from_spread(self.spread).style();
+        buf.set_stringn(area.x, area.y, &text, area.width as usize, style);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed readings every 50 ms, returning the tracker.
+    fn track(readings: &[f32]) -> StabilityTracker {
+        let mut tracker = StabilityTracker::new();
+        for (i, &cents) in readings.iter().enumerate() {
+            tracker.push(Duration::from_millis(50 * i as u64), cents);
+        }
+        tracker
+    }
+
+    #[test]
+    fn test_same_mean_different_stability() {
+        let steady: Vec<f32> = (0..20)
+            .map(|i| 1.0 + if i % 2 == 0 { 0.2 } else { -0.2 })
+            .collect();
+        let noisy: Vec<f32> = (0..20)
+            .map(|i| 1.0 + if i % 2 == 0 { 4.0 } else { -4.0 })
+            .collect();
+
+        let steady = track(&steady);
+        let noisy = track(&noisy);
+        assert_eq!(steady.stability(), Some(Stability::Steady));
+        assert_eq!(noisy.stability(), Some(Stability::Unstable));
+        assert!((noisy.spread().unwrap() - 4.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_window_drops_old_readings() {
+        // A jump, then a second of steady readings
+        let mut readings = vec![20.0, -20.0, 20.0];
+        readings.extend(std::iter::repeat_n(0.0, 25));
+        assert_eq!(track(&readings).stability(), Some(Stability::Steady));
+    }
+
+    #[test]
+    fn test_glide_is_not_stable() {
+        // A string gliding from -20 to 0 cents after a pin turn, then flat
+        let mut readings: Vec<f32> = (0..=20).map(|i| -20.0 + i as f32).collect();
+        readings.extend([0.0; 5]);
+
+        let mut tracker = StabilityTracker::new();
+        for (i, &cents) in readings.iter().enumerate() {
+            tracker.push(Duration::from_millis(50 * i as u64), cents);
+            let stable = tracker.is_stable(Stability::STEADY_SPREAD, 5);
+            if i <= 20 {
+                assert!(!stable, "Stable during the glide at {} cents", cents);
+            }
+        }
+        assert!(tracker.is_stable(Stability::STEADY_SPREAD, 5));
+        assert!(!tracker.is_stable(Stability::STEADY_SPREAD, 10));
+    }
+
+    #[test]
+    fn test_needs_enough_readings() {
+        assert_eq!(track(&[0.0, 0.0, 0.0]).stability(), None);
+
+        let mut tracker = track(&[0.0; 10]);
+        tracker.reset();
+        assert_eq!(tracker.spread(), None);
+    }
+
+    #[test]
+    fn test_indicator_text() {
+        assert_eq!(Stability::from_spread(2.0), Stability::Wavering);
+        let indicator = StabilityIndicator::new(2.0);
+        assert_eq!(indicator.text(30), "◐ wavering ±2.0¢");
+        assert_eq!(indicator.text(12), "◐ wavering");
+    }
+}
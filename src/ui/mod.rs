@@ -1,9 +1,10 @@
 //! Terminal UI screens and components.
 
 use std::io::{self, Stdout};
+use std::ops::{Deref, DerefMut};
 
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEventKind},
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -11,21 +12,61 @@ use ratatui::{backend::CrosstermBackend, Terminal};
 
 pub mod app;
 pub mod components;
+pub mod plain;
 pub mod screens;
+pub mod strings;
 pub mod theme;
 
-pub use app::App;
+pub use app::{App, DeviceRequest, ReferenceRequest};
+pub use plain::PlainRenderer;
 
 /// Type alias for our terminal.
 pub type Tui = Terminal<CrosstermBackend<Stdout>>;
 
+/// The terminal in TUI mode, restored to normal mode when dropped.
+///
+/// Dropping the guard on every exit path, including early `?` returns and
+/// unwinding, keeps the user's shell usable.
+pub struct TerminalGuard {
+    terminal: Tui,
+}
+
+impl Deref for TerminalGuard {
+    type Target = Tui;
+
+    fn deref(&self) -> &Tui {
+        &self.terminal
+    }
+}
+
+impl DerefMut for TerminalGuard {
+    fn deref_mut(&mut self) -> &mut Tui {
+        &mut self.terminal
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = restore();
+    }
+}
+
 /// Initialize the terminal for TUI mode.
-pub fn init() -> io::Result<Tui> {
+pub fn init() -> io::Result<TerminalGuard> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
+    if let Err(e) = execute!(stdout, EnterAlternateScreen) {
+        let _ = disable_raw_mode();
+        return Err(e);
+    }
     let backend = CrosstermBackend::new(stdout);
-    Terminal::new(backend)
+    match Terminal::new(backend) {
+        Ok(terminal) => Ok(TerminalGuard { terminal }),
+        Err(e) => {
+            let _ = restore();
+            Err(e)
+        }
+    }
 }
 
 /// Restore the terminal to normal mode.
@@ -35,6 +76,110 @@ pub fn restore() -> io::Result<()> {
     Ok(())
 }
 
+/// Hand the terminal back to the shell and stop the process, as Ctrl+Z
+/// does outside raw mode. Returns once the process is continued, with the
+/// terminal in TUI mode again; the caller must redraw it in full.
+#[cfg(unix)]
+pub fn suspend() -> io::Result<()> {
+    execute!(io::stdout(), LeaveAlternateScreen)?;
+    suspend_raw()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    Ok(())
+}
+
+/// Processes can't be suspended to the shell here.
+#[cfg(not(unix))]
+pub fn suspend() -> io::Result<()> {
+    Ok(())
+}
+
+/// Leave raw mode and stop the process, entering raw mode again once it is
+/// continued.
+#[cfg(unix)]
+fn suspend_raw() -> io::Result<()> {
+    disable_raw_mode()?;
+    signal_hook::low_level::emulate_default_handler(signal_hook::consts::SIGTSTP)?;
+    enable_raw_mode()
+}
+
+/// Processes can't be suspended to the shell here.
+#[cfg(not(unix))]
+fn suspend_raw() -> io::Result<()> {
+    Ok(())
+}
+
+/// Where the app is shown: the full-screen TUI, or plain status lines for
+/// screen readers and minimal terminals.
+pub enum Display {
+    /// Screens drawn on the alternate screen.
+    Tui(TerminalGuard),
+    /// One line per meaningful change, in the normal scrollback.
+    Plain(PlainRenderer<Stdout>),
+}
+
+impl Display {
+    /// Set up the terminal. Plain mode still uses raw mode, so single key
+    /// presses are read, but stays on the normal screen.
+    pub fn init(plain: bool) -> io::Result<Self> {
+        if plain {
+            enable_raw_mode()?;
+            Ok(Self::Plain(PlainRenderer::new(io::stdout())))
+        } else {
+            init().map(Self::Tui)
+        }
+    }
+
+    /// Show the app's current state.
+    pub fn draw(&mut self, app: &App) -> io::Result<()> {
+        match self {
+            Self::Tui(terminal) => {
+                terminal.draw(|frame| app.render(frame))?;
+                Ok(())
+            }
+            Self::Plain(renderer) => renderer.render(app),
+        }
+    }
+
+    /// Show everything again on the next draw, e.g. after a suspend.
+    pub fn redraw(&mut self) -> io::Result<()> {
+        match self {
+            Self::Tui(terminal) => terminal.clear(),
+            Self::Plain(renderer) => {
+                renderer.reprint();
+                Ok(())
+            }
+        }
+    }
+
+    /// Hand the terminal back to the shell and stop the process until it is
+    /// continued.
+    pub fn suspend(&mut self) -> io::Result<()> {
+        match self {
+            Self::Tui(_) => suspend(),
+            Self::Plain(_) => suspend_raw(),
+        }
+    }
+}
+
+impl Drop for Display {
+    fn drop(&mut self) {
+        // The TUI's guard restores the terminal itself
+        if let Self::Plain(_) = self {
+            let _ = disable_raw_mode();
+        }
+    }
+}
+
+/// Restore the terminal before a panic message is printed, so the message
+/// is readable and not wiped by leaving the alternate screen afterwards.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore();
+        default_hook(info);
+    }));
+}
+
 /// Event handler result.
 pub enum EventResult {
     /// Continue running.
@@ -61,3 +206,71 @@ pub fn is_key_press(event: &Event) -> Option<KeyCode> {
     }
     None
 }
+
+/// Check if a key event is Ctrl+C, which raw mode delivers as a key
+/// rather than a signal.
+pub fn is_interrupt(event: &Event) -> bool {
+    matches!(
+        event,
+        Event::Key(key)
+            if key.kind == KeyEventKind::Press
+                && key.code == KeyCode::Char('c')
+                && key.modifiers.contains(KeyModifiers::CONTROL)
+    )
+}
+
+/// Check if a key event is Ctrl+Z, which raw mode delivers as a key
+/// rather than a stop signal. Never true where suspending isn't supported.
+pub fn is_suspend(event: &Event) -> bool {
+    cfg!(unix)
+        && matches!(
+            event,
+            Event::Key(key)
+                if key.kind == KeyEventKind::Press
+                    && key.code == KeyCode::Char('z')
+                    && key.modifiers.contains(KeyModifiers::CONTROL)
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// Run in a child process by `test_panic_restores_terminal`, as the
+    /// panic hook is process-wide.
+    #[test]
+    #[ignore]
+    fn panicking_loop() {
+        install_panic_hook();
+        // Fails without a terminal; the hook restores either way
+        let _terminal = init();
+        let result = std::panic::catch_unwind(|| panic!("deliberate panic in loop"));
+        assert!(result.is_err());
+        println!(
+            "raw mode enabled: {}",
+            crossterm::terminal::is_raw_mode_enabled().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_panic_restores_terminal() {
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args([
+                "ui::tests::panicking_loop",
+                "--exact",
+                "--ignored",
+                "--nocapture",
+            ])
+            .output()
+            .unwrap();
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        assert!(output.status.success(), "{}\n{}", stdout, stderr);
+        assert!(stdout.contains("raw mode enabled: false"), "{}", stdout);
+        // Left the alternate screen before the message was printed
+        assert!(stdout.contains("\x1b[?1049l"), "{:?}", stdout);
+        assert!(stderr.contains("deliberate panic in loop"), "{}", stderr);
+    }
+}
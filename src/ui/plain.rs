@@ -0,0 +1,350 @@
+//! Plain-text status lines for screen readers and minimal terminals.
+//!
+//! Instead of drawing the screens, the app's state is described in a single
+//! line, printed again only when it meaningfully changes: another screen,
+//! note or step, or the reading crossing the tolerance. A reading moving
+//! within the same zone isn't reprinted.
+
+use std::io::{self, Write};
+
+use crate::tuning::Instrument;
+
+use super::app::{App, AppState};
+use super::screens::{mode_select::SelectedMode, TuningScreen};
+use super::strings;
+
+/// Where the reading lies relative to the tolerance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Zone {
+    Flat,
+    InTune,
+    Sharp,
+}
+
+impl Zone {
+    fn of(cents: f32, tolerance: f32) -> Self {
+        if cents < -tolerance {
+            Zone::Flat
+        } else if cents > tolerance {
+            Zone::Sharp
+        } else {
+            Zone::InTune
+        }
+    }
+
+    fn label(self) -> &'static str {
+        strings::get(match self {
+            Zone::Flat => "plain.flat",
+            Zone::InTune => "plain.in_tune",
+            Zone::Sharp => "plain.sharp",
+        })
+    }
+}
+
+/// A status line and the part of it that decides whether it is printed
+/// again.
+struct Status {
+    line: String,
+    key: String,
+}
+
+impl Status {
+    /// A status that is printed again whenever its text changes.
+    fn new(line: String) -> Self {
+        Self {
+            key: line.clone(),
+            line,
+        }
+    }
+
+    /// Describe the app's current state.
+    fn of(app: &App) -> Self {
+        if let Some(error) = app.input_lost_error() {
+            return Self::new(strings::format("plain.device_lost", &[("error", &error)]));
+        }
+        if app.is_idle() {
+            return Self::new(strings::get("plain.idle").to_string());
+        }
+
+        match app.state() {
+            AppState::ModeSelect => {
+                let select = app.mode_select();
+                Self::new(strings::format(
+                    "plain.mode_select",
+                    &[
+                        ("mode", &select.selected().title()),
+                        ("instrument", &select.instrument().name()),
+                    ],
+                ))
+            }
+            AppState::Setup => {
+                let setup = app.setup();
+                let a4 = if setup.mode() == SelectedMode::QuickTune {
+                    strings::get("setup.a4_calibrated").to_string()
+                } else {
+                    strings::format(
+                        "common.hz",
+                        &[("hz", &format!("{:.1}", setup.a4().value()))],
+                    )
+                };
+                let stretch = if !setup.stretch() {
+                    setup.stretch_label().to_string()
+                } else if setup.instrument() == Instrument::Piano {
+                    format!(
+                        "{} {} {}",
+                        setup.stretch_label(),
+                        setup.curve_label(),
+                        setup.intensity_label()
+                    )
+                } else {
+                    format!("{} {}", setup.stretch_label(), setup.intensity_label())
+                };
+                Self::new(strings::format(
+                    "plain.setup",
+                    &[
+                        ("a4", &a4),
+                        ("temperament", &setup.temperament_label()),
+                        ("stretch", &stretch),
+                    ],
+                ))
+            }
+            AppState::Calibration => Self::new(strings::get("plain.calibration").to_string()),
+            AppState::Tuning => match app.tuning() {
+                Some(tuning) => Self::tuning(tuning),
+                None => Self::new(String::new()),
+            },
+            AppState::Measure => Self::new(strings::format(
+                "plain.measure",
+                &[("note", &app.measure().map_or("", |m| m.note_name()))],
+            )),
+            AppState::MeasureSummary => {
+                let (count, mean) = app.measure_summary().map_or((0, 0.0), |summary| {
+                    (summary.measurements().len(), summary.mean_deviation())
+                });
+                Self::new(strings::format(
+                    "plain.measure_summary",
+                    &[("count", &count), ("mean", &format!("{:+.1}", mean))],
+                ))
+            }
+            AppState::IntervalCheck => {
+                let checker = app.interval_check().map(|check| check.checker());
+                match checker.and_then(|c| c.current().map(|check| (c, check))) {
+                    Some((checker, check)) => Self::new(strings::format(
+                        "plain.interval_check",
+                        &[
+                            ("number", &(checker.current_index() + 1)),
+                            ("total", &checker.total()),
+                            ("interval", &check.label()),
+                        ],
+                    )),
+                    None => Self::new(strings::get("plain.interval_check_done").to_string()),
+                }
+            }
+            AppState::Complete => {
+                let (notes, avg) = app.complete().map_or((0, 0.0), |complete| {
+                    (complete.note_count(), complete.avg_deviation())
+                });
+                Self::new(strings::format(
+                    "plain.complete",
+                    &[("notes", &notes), ("avg", &format!("{:.1}", avg))],
+                ))
+            }
+            AppState::History => match app.history() {
+                Some(history) if history.summary().is_some() => {
+                    let (notes, avg) = history.summary().map_or((0, 0.0), |summary| {
+                        (summary.note_count(), summary.avg_deviation())
+                    });
+                    Self::new(strings::format(
+                        "plain.history_summary",
+                        &[("notes", &notes), ("avg", &format!("{:.1}", avg))],
+                    ))
+                }
+                Some(history) => {
+                    let session = history.selected().map_or(String::new(), |session| {
+                        strings::format(
+                            "plain.history_session",
+                            &[
+                                ("date", &session.created_at.format("%Y-%m-%d %H:%M")),
+                                ("mode", &format!("{:?}", session.mode)),
+                                ("score", &format!("{:.0}", session.stats().quality_score)),
+                            ],
+                        )
+                    });
+                    Self::new(strings::format(
+                        "plain.history",
+                        &[
+                            ("piano", &history.piano().unwrap_or_default()),
+                            ("count", &history.sessions().len()),
+                            ("session", &session),
+                        ],
+                    ))
+                }
+                None => Self::new(String::new()),
+            },
+            AppState::DeviceSelect => {
+                let device = app
+                    .device_select()
+                    .selected()
+                    .map_or(String::new(), |(_, device)| device.name.clone());
+                Self::new(strings::format("plain.devices", &[("device", &device)]))
+            }
+        }
+    }
+
+    /// Describe the note being tuned. The reading only counts as a change
+    /// when it crosses the tolerance.
+    fn tuning(tuning: &TuningScreen) -> Self {
+        let note = match tuning.tuning_step() {
+            Some(step) => strings::format(
+                "plain.step",
+                &[
+                    ("note", &tuning.note_name()),
+                    ("number", &step.number()),
+                    ("total", &step.total_steps()),
+                    ("title", &step.title()),
+                ],
+            ),
+            None => tuning.note_name().to_string(),
+        };
+        let target = format!("{:.1}", tuning.target_freq());
+
+        let (reading, zone) = if let Some(notice) = tuning.reference_notice() {
+            (notice.to_string(), None)
+        } else if tuning.is_ambiguous() {
+            (strings::get("tuning.multiple_notes").to_string(), None)
+        } else if tuning.detected_freq().is_some() {
+            let zone = Zone::of(tuning.cents(), tuning.tolerance());
+            let value = tuning
+                .readout()
+                .format(tuning.cents(), tuning.target_freq());
+            let reading = strings::format(
+                "plain.reading",
+                &[("value", &value), ("zone", &zone.label())],
+            );
+            (reading, Some(zone))
+        } else {
+            (strings::get("plain.no_reading").to_string(), None)
+        };
+
+        let line = strings::format(
+            "plain.tuning",
+            &[("note", &note), ("target", &target), ("reading", &reading)],
+        );
+        let key = match zone {
+            Some(zone) => format!("{} {} {:?}", note, target, zone),
+            None => line.clone(),
+        };
+        Self { line, key }
+    }
+}
+
+/// Prints the app's state as plain lines, skipping unchanged ones.
+pub struct PlainRenderer<W: Write> {
+    out: W,
+    last: Option<String>,
+}
+
+impl<W: Write> PlainRenderer<W> {
+    /// Create a renderer writing to `out`.
+    pub fn new(out: W) -> Self {
+        Self { out, last: None }
+    }
+
+    /// Print the current status if it changed since the last one.
+    pub fn render(&mut self, app: &App) -> io::Result<()> {
+        if app.is_suspended() {
+            return Ok(());
+        }
+        let status = Status::of(app);
+        if self.last.as_ref() == Some(&status.key) {
+            return Ok(());
+        }
+        // Raw mode doesn't return the carriage on a newline
+        write!(self.out, "{}\r\n", status.line)?;
+        self.out.flush()?;
+        self.last = Some(status.key);
+        Ok(())
+    }
+
+    /// Print the next status even if it is unchanged.
+    pub fn reprint(&mut self) {
+        self.last = None;
+    }
+
+    /// Get the output written to.
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::tuning::session::{Session, TuningMode};
+    use crate::tuning::temperament::Temperament;
+    use crossterm::event::KeyCode;
+
+    fn lines(renderer: &PlainRenderer<Vec<u8>>) -> Vec<String> {
+        String::from_utf8_lossy(renderer.get_ref())
+            .split_terminator("\r\n")
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_simulated_session() {
+        let clock = ManualClock::new();
+        let mut app =
+            App::with_session(Session::new(TuningMode::Concert, 440.0)).with_clock(clock.clone());
+        let mut renderer = PlainRenderer::new(Vec::new());
+        let f3 = Temperament::new().frequency(53);
+        let at = |cents: f32| f3 * Temperament::cents_to_ratio(cents);
+
+        let mut frame = |app: &mut App, reading: Option<f32>| {
+            match reading {
+                Some(freq) => app.update_pitch(freq, 0.9),
+                None => app.clear_pitch(),
+            }
+            renderer.render(app).unwrap();
+        };
+
+        frame(&mut app, None);
+        frame(&mut app, None);
+        frame(&mut app, Some(at(12.0)));
+        frame(&mut app, Some(at(9.0)));
+        frame(&mut app, Some(at(7.2)));
+        frame(&mut app, Some(at(1.0)));
+        frame(&mut app, Some(at(0.5)));
+        app.handle_key(KeyCode::Char(' '));
+        frame(&mut app, Some(at(0.5)));
+        app.handle_key(KeyCode::Char('s'));
+        clock.advance(TuningScreen::DEFAULT_HOLD_TIME * 2);
+        frame(&mut app, None);
+
+        assert_eq!(
+            lines(&renderer),
+            [
+                "Tuning F3, step 1 of 2: Tune left string — 174.6 Hz target — waiting for a reading — press space to confirm",
+                "Tuning F3, step 1 of 2: Tune left string — 174.6 Hz target — reading +12.0 cents, sharp — press space to confirm",
+                "Tuning F3, step 1 of 2: Tune left string — 174.6 Hz target — reading +1.0 cents, in tune — press space to confirm",
+                "Tuning F3, step 2 of 2: Tune right string to unison — 174.6 Hz target — reading +0.5 cents, in tune — press space to confirm",
+                "Tuning F#3, step 1 of 2: Tune left string — 185.0 Hz target — waiting for a reading — press space to confirm",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reprint_after_resume() {
+        let app = App::new();
+        let mut renderer = PlainRenderer::new(Vec::new());
+        renderer.render(&app).unwrap();
+        renderer.render(&app).unwrap();
+        assert_eq!(lines(&renderer).len(), 1);
+        assert!(lines(&renderer)[0].starts_with("Quick Tune — Piano"));
+
+        renderer.reprint();
+        renderer.render(&app).unwrap();
+        assert_eq!(lines(&renderer).len(), 2);
+    }
+}
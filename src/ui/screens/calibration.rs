@@ -3,11 +3,25 @@
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Layout, Rect},
+    style::Style,
     widgets::{Block, Borders, Gauge, Paragraph, Widget},
 };
 
+use crate::audio::{NoiseFloor, NoiseSettings};
+use crate::tuning::temperament::DEFAULT_A4_PRESETS;
+use crate::ui::components::ClipBanner;
+use crate::ui::strings;
 use crate::ui::theme::{Shortcuts, Theme};
 
+/// Room noise measurement shown in place of the detected pitch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoiseStatus {
+    /// Listening to the room, with the fraction done.
+    Measuring(f64),
+    /// Measured, with the settings offered.
+    Measured(NoiseFloor, NoiseSettings),
+}
+
 /// Calibration screen for initial A4 detection.
 pub struct CalibrationScreen {
     /// Collected frequency samples.
@@ -18,6 +32,14 @@ pub struct CalibrationScreen {
     current_freq: Option<f32>,
     /// Whether we're actively listening.
     listening: bool,
+    /// A4 presets selectable with the number keys.
+    presets: Vec<f32>,
+    /// Whether the input is clipping.
+    clipping: bool,
+    /// Software gain on the input in dB.
+    gain_db: f32,
+    /// Room noise measurement in progress or awaiting a decision.
+    noise: Option<NoiseStatus>,
 }
 
 impl CalibrationScreen {
@@ -28,7 +50,25 @@ impl CalibrationScreen {
             target_samples: 10,
             current_freq: None,
             listening: true,
+            presets: DEFAULT_A4_PRESETS.to_vec(),
+            clipping: false,
+            gain_db: 0.0,
+            noise: None,
+        }
+    }
+
+    /// Use the given A4 presets (the first nine get number keys).
+    pub fn with_presets(mut self, presets: Vec<f32>) -> Self {
+        self.presets = presets;
+        self
+    }
+
+    /// Get the preset for a number key (1-based), if any.
+    pub fn preset(&self, key: u32) -> Option<f32> {
+        if !(1..=9).contains(&key) {
+            return None;
         }
+        self.presets.get(key as usize - 1).copied()
     }
 
     /// Update with a detected frequency.
@@ -75,6 +115,21 @@ impl CalibrationScreen {
         self.listening = listening;
     }
 
+    /// Set whether the input is clipping.
+    pub fn set_clipping(&mut self, clipping: bool) {
+        self.clipping = clipping;
+    }
+
+    /// Set the software gain on the input in dB, shown when not zero.
+    pub fn set_gain_db(&mut self, db: f32) {
+        self.gain_db = db;
+    }
+
+    /// Show a room noise measurement, or `None` to go back to listening.
+    pub fn set_noise(&mut self, noise: Option<NoiseStatus>) {
+        self.noise = noise;
+    }
+
     /// Reset calibration.
     pub fn reset(&mut self) {
         self.samples.clear();
@@ -95,14 +150,17 @@ impl Widget for &CalibrationScreen {
         let block = Block::default()
             .borders(Borders::ALL)
             .border_style(Theme::border())
-            .title(" Calibration ")
+            .title(format!(" {} ", strings::get("calibration.title")))
             .title_style(Theme::title());
 
         let inner = block.inner(area);
         block.render(area, buf);
+        if self.clipping {
+            ClipBanner.render(area, buf);
+        }
 
         if inner.height < 10 || inner.width < 30 {
-            let msg = "Terminal too small";
+            let msg = strings::get("common.too_small");
             buf.set_string(inner.x, inner.y, msg, Theme::warning());
             return;
         }
@@ -114,49 +172,74 @@ impl Widget for &CalibrationScreen {
             Constraint::Length(3), // Current pitch display
             Constraint::Length(1), // Spacer
             Constraint::Length(3), // Progress bar
-            Constraint::Min(2),    // Spacer
+            Constraint::Min(1),    // Spacer
+            Constraint::Length(1), // Presets
             Constraint::Length(2), // Help text
         ])
         .split(inner);
 
         // Instructions
-        let instruction = Paragraph::new("Play A4 (the A above middle C) and hold the key")
+        let instruction = Paragraph::new(strings::get("calibration.instruction"))
             .style(Theme::title())
             .alignment(Alignment::Center);
         instruction.render(chunks[0], buf);
 
-        // Current pitch display
+        // Input gain, below the instructions
+        if self.gain_db != 0.0 {
+            let gain = strings::format("common.gain", &[("db", &format!("{:+.0}", self.gain_db))]);
+            centered(&gain, chunks[1], chunks[1].y, Theme::muted(), buf);
+        }
+
+        // Current pitch display, or the room noise measurement
         let pitch_area = chunks[2];
-        if let Some(freq) = self.current_freq {
+        if let Some(noise) = self.noise {
+            render_noise(noise, pitch_area, buf);
+        } else if let Some(freq) = self.current_freq {
             let deviation = freq - 440.0;
             let style = Theme::style_for_cents(deviation * 4.0); // Approximate cents
 
             let freq_text = format!("{:.1} Hz", freq);
-            let deviation_text = format!("({:+.1} Hz from 440)", deviation);
+            let deviation_text = strings::format(
+                "calibration.deviation",
+                &[("deviation", &format!("{:+.1}", deviation))],
+            );
 
-            let freq_x = pitch_area.x + pitch_area.width / 2 - freq_text.len() as u16 / 2;
-            buf.set_string(freq_x, pitch_area.y, &freq_text, style);
-
-            let dev_x = pitch_area.x + pitch_area.width / 2 - deviation_text.len() as u16 / 2;
-            buf.set_string(dev_x, pitch_area.y + 1, &deviation_text, Theme::muted());
+            centered(&freq_text, pitch_area, pitch_area.y, style, buf);
+            centered(
+                &deviation_text,
+                pitch_area,
+                pitch_area.y + 1,
+                Theme::muted(),
+                buf,
+            );
         } else {
             let listening_text = if self.listening {
-                "Listening..."
+                strings::get("common.listening")
             } else {
-                "No pitch detected"
+                strings::get("calibration.no_pitch")
             };
-            let x = pitch_area.x + pitch_area.width / 2 - listening_text.len() as u16 / 2;
-            buf.set_string(x, pitch_area.y, listening_text, Theme::muted());
+            centered(
+                listening_text,
+                pitch_area,
+                pitch_area.y,
+                Theme::muted(),
+                buf,
+            );
         }
 
         // Progress bar
         let progress_area = chunks[4];
         let percent = (self.progress() * 100.0) as u16;
-        let label = format!("Samples: {}/{}", self.samples.len(), self.target_samples);
+        let label = strings::format(
+            "calibration.samples",
+            &[
+                ("count", &self.samples.len()),
+                ("total", &self.target_samples),
+            ],
+        );
 
         // Progress label
-        let label_x = progress_area.x + progress_area.width / 2 - label.len() as u16 / 2;
-        buf.set_string(label_x, progress_area.y, &label, Theme::muted());
+        centered(&label, progress_area, progress_area.y, Theme::muted(), buf);
 
         // Progress bar
         if progress_area.height >= 2 {
@@ -173,15 +256,78 @@ impl Widget for &CalibrationScreen {
             gauge.render(bar_area, buf);
         }
 
+        // Presets
+        let presets: Vec<String> = self
+            .presets
+            .iter()
+            .take(9)
+            .enumerate()
+            .map(|(i, a4)| format!("[{}] {}", i + 1, a4))
+            .collect();
+        if !presets.is_empty() {
+            Paragraph::new(strings::format(
+                "calibration.presets",
+                &[("presets", &presets.join("  "))],
+            ))
+            .style(Theme::accent())
+            .alignment(Alignment::Center)
+            .render(chunks[6], buf);
+        }
+
         // Help text
-        let help_text = format!(
-            "{} Skip calibration (use 440 Hz)  {} Quit",
-            Shortcuts::SKIP,
-            Shortcuts::QUIT
-        );
+        let help_text = Shortcuts::help(&[
+            (Shortcuts::SKIP, strings::get("help.skip_calibration")),
+            (Shortcuts::GAIN, strings::get("help.gain")),
+            (Shortcuts::NOISE, strings::get("help.room_noise")),
+            (Shortcuts::QUIT, strings::get("help.quit")),
+        ]);
         let help = Paragraph::new(help_text)
             .style(Theme::muted())
             .alignment(Alignment::Center);
-        help.render(chunks[6], buf);
+        help.render(chunks[7], buf);
     }
 }
+
+/// Draw the progress or outcome of a room noise measurement.
+fn render_noise(noise: NoiseStatus, area: Rect, buf: &mut Buffer) {
+    match noise {
+        NoiseStatus::Measuring(progress) => {
+            let text = strings::format(
+                "calibration.noise_measuring",
+                &[("percent", &((progress * 100.0) as u32))],
+            );
+            centered(&text, area, area.y, Theme::warning(), buf);
+        }
+        NoiseStatus::Measured(floor, suggested) => {
+            let measured = strings::format(
+                "calibration.noise_floor",
+                &[
+                    ("db", &format!("{:.0}", floor.db())),
+                    ("flatness", &format!("{:.2}", floor.flatness)),
+                ],
+            );
+            let gate_db = 20.0 * suggested.noise_gate.log10();
+            let suggestion = strings::format(
+                "calibration.noise_suggestion",
+                &[
+                    ("gate", &format!("{:.0}", gate_db)),
+                    ("confidence", &format!("{:.2}", suggested.min_confidence)),
+                ],
+            );
+            centered(&measured, area, area.y, Theme::muted(), buf);
+            centered(&suggestion, area, area.y + 1, Theme::accent(), buf);
+            let help = Shortcuts::help(&[
+                (Shortcuts::YES, strings::get("help.accept")),
+                (Shortcuts::NO, strings::get("help.discard")),
+            ]);
+            centered(&help, area, area.y + 2, Theme::muted(), buf);
+        }
+    }
+}
+
+/// Draw a line of text centered in an area, truncated to its width.
+fn centered(text: &str, area: Rect, y: u16, style: Style, buf: &mut Buffer) {
+    let width = text.chars().count().min(area.width as usize) as u16;
+    let x = area.x + (area.width - width) / 2;
+    buf.set_stringn(x, y, text, area.width as usize, style);
+}
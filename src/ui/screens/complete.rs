@@ -1,62 +1,65 @@
 //! Session complete summary screen.
 
+use std::collections::{HashMap, HashSet};
+
 use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Layout, Rect},
     widgets::{Block, Borders, Paragraph, Widget},
 };
 
+use crate::tuning::interval::IntervalResult;
+use crate::tuning::notes::Note;
+use crate::tuning::order::Section;
 use crate::tuning::session::CompletedNote;
+use crate::tuning::stats::{tuned_deviations, SessionStats, Verdict};
+use crate::tuning::TemperamentScheme;
+use crate::ui::components::{CentsChart, Piano};
+use crate::ui::strings;
 use crate::ui::theme::{Shortcuts, Theme};
 
+use super::interval_check::{format_result, grade_style};
+
+/// Inner height needed to show the keyboard overview above the stats.
+const MIN_HEIGHT_WITH_PIANO: u16 = 27;
+
 /// Session complete screen with summary.
 pub struct CompleteScreen {
     /// Completed notes from the session.
     completed_notes: Vec<CompletedNote>,
-    /// Average absolute deviation in cents.
-    avg_deviation: f32,
-    /// Notes within tolerance (±5 cents).
-    notes_in_tune: usize,
-    /// Notes with warning (±5-15 cents).
-    notes_warning: usize,
-    /// Notes out of tune (>±15 cents).
-    notes_out_of_tune: usize,
+    /// Statistics over the completed notes.
+    stats: SessionStats,
+    /// Interval check results.
+    interval_checks: Vec<IntervalResult>,
     /// Total tuning duration.
     duration_secs: u64,
+    /// Name of the temperament tuned to, if not equal temperament.
+    temperament: Option<String>,
+    /// Stretch intensity tuned to, if stretched.
+    stretch: Option<f32>,
+    /// Show the cents chart in place of the breakdown.
+    show_chart: bool,
+    /// Message shown above the breakdown, and whether it reports an error.
+    toast: Option<(String, bool)>,
+    /// Whether this is a past session, which can only be looked at.
+    read_only: bool,
 }
 
 impl CompleteScreen {
     /// Create a new complete screen.
     pub fn new(completed_notes: Vec<CompletedNote>) -> Self {
-        let avg_deviation = if completed_notes.is_empty() {
-            0.0
-        } else {
-            let sum: f32 = completed_notes.iter().map(|n| n.final_cents.abs()).sum();
-            sum / completed_notes.len() as f32
-        };
-
-        let notes_in_tune = completed_notes
-            .iter()
-            .filter(|n| n.final_cents.abs() <= 5.0)
-            .count();
-
-        let notes_warning = completed_notes
-            .iter()
-            .filter(|n| n.final_cents.abs() > 5.0 && n.final_cents.abs() <= 15.0)
-            .count();
-
-        let notes_out_of_tune = completed_notes
-            .iter()
-            .filter(|n| n.final_cents.abs() > 15.0)
-            .count();
+        let stats = SessionStats::from_notes(&completed_notes);
 
         Self {
             completed_notes,
-            avg_deviation,
-            notes_in_tune,
-            notes_warning,
-            notes_out_of_tune,
+            stats,
+            interval_checks: Vec::new(),
             duration_secs: 0,
+            temperament: None,
+            stretch: None,
+            show_chart: false,
+            toast: None,
+            read_only: false,
         }
     }
 
@@ -66,6 +69,41 @@ impl CompleteScreen {
         self
     }
 
+    /// Set the name of the temperament tuned to; equal temperament isn't
+    /// shown.
+    pub fn with_temperament(mut self, name: &str) -> Self {
+        self.temperament = (name != TemperamentScheme::Equal.name()).then(|| name.to_string());
+        self
+    }
+
+    /// Set the stretch intensity tuned to, if the targets were stretched.
+    pub fn with_stretch(mut self, intensity: Option<f32>) -> Self {
+        self.stretch = intensity;
+        self
+    }
+
+    /// Set the interval check results.
+    pub fn with_interval_checks(mut self, results: Vec<IntervalResult>) -> Self {
+        self.interval_checks = results;
+        self
+    }
+
+    /// Show a past session, offering only the chart and the way back.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Whether this is a past session.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Get the interval check results.
+    pub fn interval_checks(&self) -> &[IntervalResult] {
+        &self.interval_checks
+    }
+
     /// Get the number of completed notes.
     pub fn note_count(&self) -> usize {
         self.completed_notes.len()
@@ -73,120 +111,601 @@ impl CompleteScreen {
 
     /// Get average deviation.
     pub fn avg_deviation(&self) -> f32 {
-        self.avg_deviation
+        self.stats.avg_deviation
     }
-}
 
-impl Widget for &CompleteScreen {
-    fn render(self, area: Rect, buf: &mut Buffer) {
-        // Main container
-        let block = Block::default()
-            .borders(Borders::ALL)
-            .border_style(Theme::border())
-            .title(" Tuning Complete! ")
-            .title_style(Theme::title());
+    /// Get the session statistics.
+    pub fn stats(&self) -> &SessionStats {
+        &self.stats
+    }
 
-        let inner = block.inner(area);
-        block.render(area, buf);
+    /// Notes tuned to a moved target, with the cents they were moved by.
+    pub fn adjusted_targets(&self) -> Vec<String> {
+        self.completed_notes
+            .iter()
+            .filter(|cn| !cn.skipped && cn.target_offset != 0.0)
+            .map(|cn| format!("{} {:+.1}¢", cn.note, cn.target_offset))
+            .collect()
+    }
 
-        if inner.height < 12 || inner.width < 40 {
-            let msg = "Terminal too small";
-            buf.set_string(inner.x, inner.y, msg, Theme::warning());
-            return;
+    /// Build the keyboard overview: tuned keys by severity, skipped keys muted.
+    fn results_piano(&self) -> Piano {
+        let mut results = HashMap::new();
+        let mut skipped = HashSet::new();
+        for cn in &self.completed_notes {
+            let Some(note) = Note::from_name(&cn.note) else {
+                continue;
+            };
+            let index = (note.midi - 21) as usize;
+            if cn.skipped {
+                skipped.insert(index);
+            } else {
+                results.insert(index, cn.final_cents);
+            }
         }
+        Piano::full().with_results(results).with_skipped(skipped)
+    }
 
-        // Layout
-        let chunks = Layout::vertical([
-            Constraint::Length(3), // Title/congrats
-            Constraint::Length(1), // Spacer
-            Constraint::Length(6), // Summary stats
-            Constraint::Length(1), // Spacer
-            Constraint::Min(4),    // Quality breakdown
-            Constraint::Length(2), // Help text
-        ])
-        .split(inner);
+    /// Build the chart of final cents across the keyboard.
+    fn results_chart(&self) -> CentsChart {
+        CentsChart::new(tuned_deviations(&self.completed_notes))
+    }
 
-        // Congratulations message
-        let quality = if self.avg_deviation <= 3.0 {
-            ("Excellent tuning!", Theme::in_tune())
-        } else if self.avg_deviation <= 8.0 {
-            ("Good tuning!", Theme::in_tune())
-        } else if self.avg_deviation <= 15.0 {
-            ("Acceptable tuning", Theme::warning())
-        } else {
-            ("Tuning needs improvement", Theme::out_of_tune())
-        };
+    /// Toggle between the breakdown and the cents chart.
+    pub fn toggle_chart(&mut self) {
+        self.show_chart = !self.show_chart;
+    }
 
-        let congrats = Paragraph::new(quality.0)
-            .style(quality.1)
-            .alignment(Alignment::Center);
-        congrats.render(chunks[0], buf);
+    /// Whether the cents chart is shown.
+    pub fn is_showing_chart(&self) -> bool {
+        self.show_chart
+    }
 
-        // Summary stats
-        let stats_area = chunks[2];
-        let stats = [
-            format!("Notes tuned: {}", self.completed_notes.len()),
-            format!("Average deviation: {:.1} cents", self.avg_deviation),
-            format!(
-                "Duration: {}:{:02}",
-                self.duration_secs / 60,
-                self.duration_secs % 60
-            ),
-        ];
+    /// Show a message, e.g. the outcome of the session hook.
+    pub fn set_toast(&mut self, message: impl Into<String>, is_error: bool) {
+        self.toast = Some((message.into(), is_error));
+    }
 
-        for (i, stat) in stats.iter().enumerate() {
-            let y = stats_area.y + i as u16;
-            if y < stats_area.y + stats_area.height {
-                let x = stats_area.x + stats_area.width / 2 - stat.len() as u16 / 2;
-                buf.set_string(x, y, stat, Theme::muted());
-            }
-        }
+    /// Get the message being shown.
+    pub fn toast(&self) -> Option<&str> {
+        self.toast.as_ref().map(|(message, _)| message.as_str())
+    }
 
-        // Quality breakdown
-        let breakdown_area = chunks[4];
+    /// Get the names of notes flagged with false beats.
+    pub fn false_beat_notes(&self) -> &[String] {
+        &self.stats.false_beat_notes
+    }
+
+    /// Render the quality breakdown, with the interval checks alongside if any.
+    fn render_breakdown(&self, area: Rect, buf: &mut Buffer) {
+        let (breakdown_area, checks_area) = if self.interval_checks.is_empty() {
+            (area, None)
+        } else {
+            let halves =
+                Layout::horizontal([Constraint::Percentage(40), Constraint::Min(20)]).split(area);
+            (halves[0], Some(halves[1]))
+        };
         let breakdown_block = Block::default()
             .borders(Borders::ALL)
             .border_style(Theme::muted())
-            .title(" Breakdown ")
+            .title(format!(" {} ", strings::get("complete.breakdown")))
             .title_style(Theme::muted());
 
         let breakdown_inner = breakdown_block.inner(breakdown_area);
         breakdown_block.render(breakdown_area, buf);
 
+        // Per-section columns below the counts
+        if breakdown_inner.height >= 7 {
+            let sections_area = Rect {
+                x: breakdown_inner.x + 2,
+                y: breakdown_inner.y + 4,
+                width: breakdown_inner.width.saturating_sub(4),
+                height: 3,
+            };
+            let columns = Layout::horizontal([Constraint::Ratio(1, 3); 3]).split(sections_area);
+
+            for (section, column) in self.stats.sections.iter().zip(columns.iter()) {
+                let style = if section.notes == 0 {
+                    Theme::muted()
+                } else {
+                    Theme::style_for_cents(section.avg_deviation)
+                };
+                let width = column.width as usize;
+                let lines = [
+                    section_name(section.section).to_string(),
+                    strings::format(
+                        "complete.section_avg",
+                        &[("cents", &format!("{:.1}", section.avg_deviation))],
+                    ),
+                    strings::format(
+                        "complete.section_worst",
+                        &[("cents", &format!("{:.1}", section.worst_deviation))],
+                    ),
+                ];
+                for (i, line) in lines.iter().enumerate() {
+                    buf.set_stringn(column.x, column.y + i as u16, line, width, style);
+                }
+            }
+        }
+
         if breakdown_inner.height >= 3 {
-            let in_tune_text = format!("● In tune (±5¢): {}", self.notes_in_tune);
-            let warning_text = format!("● Warning (±5-15¢): {}", self.notes_warning);
-            let out_text = format!("● Out of tune (>±15¢): {}", self.notes_out_of_tune);
+            let in_tune_text =
+                strings::format("complete.in_tune", &[("count", &self.stats.notes_in_tune)]);
+            let warning_text =
+                strings::format("complete.warning", &[("count", &self.stats.notes_warning)]);
+            let out_text = strings::format(
+                "complete.out_of_tune",
+                &[("count", &self.stats.notes_out_of_tune)],
+            );
+            let width = breakdown_inner.width.saturating_sub(2) as usize;
 
-            buf.set_string(
+            buf.set_stringn(
                 breakdown_inner.x + 2,
                 breakdown_inner.y,
                 &in_tune_text,
+                width,
                 Theme::in_tune(),
             );
             if breakdown_inner.height >= 2 {
-                buf.set_string(
+                buf.set_stringn(
                     breakdown_inner.x + 2,
                     breakdown_inner.y + 1,
                     &warning_text,
+                    width,
                     Theme::warning(),
                 );
             }
             if breakdown_inner.height >= 3 {
-                buf.set_string(
+                buf.set_stringn(
                     breakdown_inner.x + 2,
                     breakdown_inner.y + 2,
                     &out_text,
+                    width,
                     Theme::out_of_tune(),
                 );
             }
         }
 
+        if let Some(checks_area) = checks_area {
+            let checks_block = Block::default()
+                .borders(Borders::ALL)
+                .border_style(Theme::muted())
+                .title(format!(" {} ", strings::get("complete.quality_check")))
+                .title_style(Theme::muted());
+
+            let checks_inner = checks_block.inner(checks_area);
+            checks_block.render(checks_area, buf);
+
+            for (i, result) in self
+                .interval_checks
+                .iter()
+                .take(checks_inner.height as usize)
+                .enumerate()
+            {
+                buf.set_stringn(
+                    checks_inner.x + 1,
+                    checks_inner.y + i as u16,
+                    format_result(result),
+                    checks_inner.width.saturating_sub(1) as usize,
+                    grade_style(result.grade),
+                );
+            }
+        }
+    }
+}
+
+impl Widget for &CompleteScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // Main container
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Theme::border())
+            .title(format!(" {} ", strings::get("complete.title")))
+            .title_style(Theme::title());
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        if inner.height < 12 || inner.width < 40 {
+            let msg = strings::get("common.too_small");
+            buf.set_string(inner.x, inner.y, msg, Theme::warning());
+            return;
+        }
+
+        // Layout, with the keyboard overview when there is room for it
+        let piano_height = if inner.height >= MIN_HEIGHT_WITH_PIANO {
+            4
+        } else {
+            0
+        };
+        let chunks = Layout::vertical([
+            Constraint::Length(3),            // Title/congrats
+            Constraint::Length(1),            // Spacer
+            Constraint::Length(piano_height), // Keyboard overview
+            Constraint::Length(6),            // Summary stats
+            Constraint::Length(1),            // Spacer
+            Constraint::Min(4),               // Quality breakdown
+            Constraint::Length(2),            // Help text
+        ])
+        .split(inner);
+
+        if piano_height > 0 {
+            let piano = self.results_piano();
+            let width = (piano.width() as u16).min(chunks[2].width);
+            let piano_area = Rect {
+                x: chunks[2].x + (chunks[2].width - width) / 2,
+                width,
+                ..chunks[2]
+            };
+            piano.render(piano_area, buf);
+        }
+
+        // Congratulations message and weighted score
+        let verdict = self.stats.verdict();
+        let style = match verdict {
+            Verdict::Excellent | Verdict::Good => Theme::in_tune(),
+            Verdict::Acceptable => Theme::warning(),
+            Verdict::NeedsImprovement => Theme::out_of_tune(),
+        };
+
+        let score = strings::format(
+            "complete.score",
+            &[("score", &format!("{:.0}", self.stats.quality_score))],
+        );
+        let congrats = Paragraph::new(format!("{}\n{}", verdict_message(verdict), score))
+            .style(style)
+            .alignment(Alignment::Center);
+        congrats.render(chunks[0], buf);
+
+        // Summary stats
+        let stats_area = chunks[3];
+        let mut stats = vec![
+            (
+                strings::format(
+                    "complete.notes_tuned",
+                    &[("count", &self.stats.notes_tuned)],
+                ),
+                Theme::muted(),
+            ),
+            (
+                strings::format(
+                    "complete.average",
+                    &[("cents", &format!("{:.1}", self.stats.avg_deviation))],
+                ),
+                Theme::muted(),
+            ),
+            (
+                strings::format(
+                    "complete.duration",
+                    &[(
+                        "duration",
+                        &format!("{}:{:02}", self.duration_secs / 60, self.duration_secs % 60),
+                    )],
+                ),
+                Theme::muted(),
+            ),
+        ];
+        if let Some(temperament) = &self.temperament {
+            stats.push((
+                strings::format("complete.temperament", &[("name", temperament)]),
+                Theme::muted(),
+            ));
+        }
+        if let Some(intensity) = self.stretch {
+            stats.push((
+                strings::format(
+                    "complete.stretch",
+                    &[("percent", &format!("{:.0}", intensity * 100.0))],
+                ),
+                Theme::muted(),
+            ));
+        }
+        let adjusted = self.adjusted_targets();
+        if !adjusted.is_empty() {
+            stats.push((
+                strings::format("complete.adjusted", &[("notes", &adjusted.join(", "))]),
+                Theme::muted(),
+            ));
+        }
+        if self.stats.notes_skipped > 0 {
+            stats.push((
+                strings::format("complete.skipped", &[("count", &self.stats.notes_skipped)]),
+                Theme::muted(),
+            ));
+        }
+        if !self.stats.false_beat_notes.is_empty() {
+            stats.push((
+                strings::format(
+                    "complete.false_beats",
+                    &[("notes", &self.stats.false_beat_notes.join(", "))],
+                ),
+                Theme::warning(),
+            ));
+        }
+
+        if !self.stats.low_confidence_notes.is_empty() {
+            stats.push((
+                strings::format(
+                    "complete.low_confidence",
+                    &[("notes", &self.stats.low_confidence_notes.join(", "))],
+                ),
+                Theme::muted(),
+            ));
+        }
+
+        for (i, (stat, style)) in stats.iter().enumerate() {
+            let y = stats_area.y + i as u16;
+            if y < stats_area.y + stats_area.height {
+                let width = stat.chars().count().min(stats_area.width as usize) as u16;
+                let x = stats_area.x + stats_area.width / 2 - width / 2;
+                buf.set_stringn(x, y, stat, stats_area.width as usize, *style);
+            }
+        }
+
+        if let Some((message, is_error)) = &self.toast {
+            let style = if *is_error {
+                Theme::warning()
+            } else {
+                Theme::muted()
+            };
+            Paragraph::new(message.as_str())
+                .style(style)
+                .alignment(Alignment::Center)
+                .render(chunks[4], buf);
+        }
+
+        if self.show_chart {
+            let chart_block = Block::default()
+                .borders(Borders::ALL)
+                .border_style(Theme::muted())
+                .title(format!(" {} ", strings::get("complete.chart")))
+                .title_style(Theme::muted());
+            let chart_inner = chart_block.inner(chunks[5]);
+            chart_block.render(chunks[5], buf);
+            self.results_chart().render(chart_inner, buf);
+        } else {
+            self.render_breakdown(chunks[5], buf);
+        }
+
         // Help text
-        let help_text = format!("{} New session  {} Quit", Shortcuts::ENTER, Shortcuts::QUIT);
+        let chart = (
+            Shortcuts::CHART,
+            if self.show_chart {
+                strings::get("help.breakdown")
+            } else {
+                strings::get("help.chart")
+            },
+        );
+        let help_text = if self.read_only {
+            Shortcuts::help(&[chart, (Shortcuts::ESC, strings::get("help.back"))])
+        } else {
+            Shortcuts::help(&[
+                (Shortcuts::ENTER, strings::get("help.new_session")),
+                chart,
+                (
+                    Shortcuts::INTERVAL_CHECK,
+                    strings::get("help.interval_check"),
+                ),
+                (Shortcuts::QUIT, strings::get("help.quit")),
+            ])
+        };
         let help = Paragraph::new(help_text)
             .style(Theme::muted())
             .alignment(Alignment::Center);
-        help.render(chunks[5], buf);
+        help.render(chunks[6], buf);
+    }
+}
+
+/// Localized verdict message.
+fn verdict_message(verdict: Verdict) -> &'static str {
+    strings::get(match verdict {
+        Verdict::Excellent => "verdict.excellent",
+        Verdict::Good => "verdict.good",
+        Verdict::Acceptable => "verdict.acceptable",
+        Verdict::NeedsImprovement => "verdict.needs_improvement",
+    })
+}
+
+/// Localized section name.
+fn section_name(section: Section) -> &'static str {
+    strings::get(match section {
+        Section::Bass => "section.bass",
+        Section::Temperament => "section.temperament",
+        Section::Treble => "section.treble",
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_buffer(screen: &CompleteScreen) -> Buffer {
+        render_buffer_sized(screen, 80, 24)
+    }
+
+    fn render_buffer_sized(screen: &CompleteScreen, width: u16, height: u16) -> Buffer {
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        screen.render(area, &mut buf);
+        buf
+    }
+
+    fn buffer_text(buf: &Buffer) -> String {
+        (0..buf.area.height)
+            .map(|y| {
+                (0..buf.area.width)
+                    .map(|x| buf[(x, y)].symbol().to_string())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn render_text(screen: &CompleteScreen) -> String {
+        buffer_text(&render_buffer(screen))
+    }
+
+    fn session_with_error_on(note: &str) -> CompleteScreen {
+        let notes = ["A0", "C4", "A4"]
+            .iter()
+            .map(|&n| CompletedNote::new(n, if n == note { 20.0 } else { 0.0 }))
+            .collect();
+        CompleteScreen::new(notes)
+    }
+
+    #[test]
+    fn test_verdict_uses_weighted_score() {
+        let bass_error = session_with_error_on("A0");
+        let mid_error = session_with_error_on("A4");
+
+        // Same raw average either way
+        assert_eq!(bass_error.avg_deviation(), mid_error.avg_deviation());
+
+        let text = render_text(&bass_error);
+        assert!(text.contains("Excellent tuning!"), "{}", text);
+        assert!(text.contains("Quality score: 91/100"), "{}", text);
+
+        let text = render_text(&mid_error);
+        assert!(text.contains("Acceptable tuning"), "{}", text);
+        assert!(text.contains("Quality score: 67/100"), "{}", text);
+    }
+
+    #[test]
+    fn test_temperament_shown_unless_equal() {
+        let text = render_text(&session_with_error_on("A4").with_temperament("Kellner"));
+        assert!(text.contains("Temperament: Kellner"), "{}", text);
+        let text = render_text(&session_with_error_on("A4").with_temperament("Equal"));
+        assert!(!text.contains("Temperament:"), "{}", text);
+    }
+
+    #[test]
+    fn test_stretch_intensity_noted() {
+        let text = render_text(&session_with_error_on("A4").with_stretch(Some(1.2)));
+        assert!(
+            text.contains("Stretch: 120% of the default curve"),
+            "{}",
+            text
+        );
+        let text = render_text(&session_with_error_on("A4").with_stretch(None));
+        assert!(!text.contains("Stretch:"), "{}", text);
+    }
+
+    #[test]
+    fn test_adjusted_targets_listed() {
+        let notes = vec![
+            CompletedNote::new("F#5", 0.5).with_target_offset(1.5),
+            CompletedNote::new("G5", 0.0),
+            CompletedNote::new("C6", -1.0).with_target_offset(-1.0),
+        ];
+        let text = render_text(&CompleteScreen::new(notes));
+        assert!(
+            text.contains("Adjusted targets: F#5 +1.5¢, C6 -1.0¢"),
+            "{}",
+            text
+        );
+        assert!(!render_text(&session_with_error_on("A4")).contains("Adjusted"));
+    }
+
+    #[test]
+    fn test_section_breakdown_shows_bad_bass() {
+        let mut notes: Vec<CompletedNote> = ["A0", "C1", "E2"]
+            .iter()
+            .map(|&n| CompletedNote::new(n, -22.0))
+            .collect();
+        notes.extend(
+            ["F3", "A3", "C4", "A5", "C7"]
+                .iter()
+                .map(|&n| CompletedNote::new(n, 1.5)),
+        );
+        let screen = CompleteScreen::new(notes);
+
+        let buf = render_buffer(&screen);
+        let text = buffer_text(&buf);
+
+        // Locate the bass and treble columns by their headers
+        let lines: Vec<&str> = text.lines().collect();
+        let header_row = lines
+            .iter()
+            .position(|l| l.contains("Bass") && l.contains("Treble"))
+            .expect("Section headers should render");
+        let bass_col = lines[header_row].chars().position(|c| c == 'B').unwrap();
+        let avg_row: String = lines[header_row + 1].chars().skip(bass_col).collect();
+        assert!(avg_row.starts_with("avg 22.0¢"), "{}", avg_row);
+        assert!(text.contains("avg 1.5¢"), "{}", text);
+
+        let cell = &buf[(bass_col as u16, header_row as u16 + 1)];
+        assert_eq!(cell.fg, Theme::OUT_OF_TUNE);
+        let treble_col = lines[header_row].find("Treble").unwrap();
+        let treble_col = lines[header_row][..treble_col].chars().count() as u16;
+        assert_eq!(buf[(treble_col, header_row as u16 + 1)].fg, Theme::IN_TUNE);
+    }
+
+    #[test]
+    fn test_keyboard_colored_by_result() {
+        let notes = vec![
+            CompletedNote::new("A0", -20.0),
+            CompletedNote::new("A#0", 1.0),
+            CompletedNote::skipped("B0"),
+        ];
+        let screen = CompleteScreen::new(notes);
+
+        // Keyboard is centered below the congrats line
+        let buf = render_buffer_sized(&screen, 120, 32);
+        let width = Piano::full().width() as u16;
+        let (x, y) = (1 + (118 - width) / 2, 1 + 4);
+        assert_eq!(buf[(x, y)].symbol(), "║");
+        assert_eq!(buf[(x + 1, y)].fg, Theme::OUT_OF_TUNE);
+        assert_eq!(buf[(x + 2, y)].fg, Theme::IN_TUNE);
+        assert_eq!(buf[(x + 3, y)].fg, Theme::MUTED);
+        assert_eq!(buf[(x + 3, y)].symbol(), "▓");
+
+        // Hidden when the terminal is short
+        let text = render_text(&screen);
+        assert!(!text.contains('╚'), "{}", text);
+    }
+
+    #[test]
+    fn test_chart_toggle() {
+        // Bass sharp, treble flat
+        let notes = vec![
+            CompletedNote::new("A0", 12.0),
+            CompletedNote::new("A4", 0.0),
+            CompletedNote::new("C8", -12.0),
+            CompletedNote::skipped("C4"),
+        ];
+        let mut screen = CompleteScreen::new(notes);
+        assert!(!render_text(&screen).contains("Cents by key"));
+
+        screen.toggle_chart();
+        assert!(screen.is_showing_chart());
+        let buf = render_buffer(&screen);
+        let text = buffer_text(&buf);
+        assert!(text.contains("Cents by key"), "{}", text);
+        assert!(!text.contains("In tune (±5¢)"), "{}", text);
+        assert!(text.contains("+15"), "{}", text);
+
+        // The A0 bar rises from the zero line and the C8 bar falls from it
+        let lines: Vec<&str> = text.lines().collect();
+        let zero_row = lines
+            .iter()
+            .position(|l| l.contains(" 0 ─") || l.contains(" 0━"))
+            .expect("Zero line should render");
+        let bar_cols = |row: usize| -> Vec<usize> {
+            lines[row]
+                .chars()
+                .enumerate()
+                .filter(|&(_, c)| c == '█')
+                .map(|(i, _)| i)
+                .collect()
+        };
+        let above = bar_cols(zero_row - 1);
+        let below = bar_cols(zero_row + 1);
+        assert_eq!(above.len(), 1, "{}", text);
+        assert_eq!(below.len(), 1, "{}", text);
+        assert!(above[0] < below[0]);
+        assert_eq!(
+            buf[(above[0] as u16, zero_row as u16 - 1)].fg,
+            Theme::WARNING
+        );
     }
 }
@@ -0,0 +1,377 @@
+//! Audio device selection screen.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::Modifier,
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use crate::audio::AudioDevice;
+use crate::ui::strings;
+use crate::ui::theme::{Shortcuts, Theme};
+
+/// Direction of an audio device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    /// Microphone or audio interface input.
+    Input,
+    /// Speakers or headphones.
+    Output,
+}
+
+/// Device selection screen state.
+///
+/// The cursor moves over the input devices, then the output devices.
+#[derive(Debug, Clone, Default)]
+pub struct DeviceSelectScreen {
+    /// Available input devices.
+    inputs: Vec<AudioDevice>,
+    /// Available output devices.
+    outputs: Vec<AudioDevice>,
+    /// Name of the input device in use.
+    current_input: Option<String>,
+    /// Name of the output device in use.
+    current_output: Option<String>,
+    /// Sample rate and buffer frames negotiated with the input in use.
+    input_format: Option<(u32, Option<u32>)>,
+    /// Cursor position over inputs then outputs.
+    cursor: usize,
+    /// Message shown after a failed switch.
+    toast: Option<String>,
+}
+
+impl DeviceSelectScreen {
+    /// Create an empty device selection screen.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the device lists, keeping the cursor on the same device.
+    pub fn set_devices(&mut self, inputs: Vec<AudioDevice>, outputs: Vec<AudioDevice>) {
+        let selected = self.selected().map(|(kind, d)| (kind, d.name.clone()));
+
+        self.inputs = inputs;
+        self.outputs = outputs;
+
+        self.cursor = selected
+            .and_then(|(kind, name)| self.position(kind, &name))
+            .or_else(|| {
+                let current = self.current_input.clone()?;
+                self.position(DeviceKind::Input, &current)
+            })
+            .unwrap_or(0);
+    }
+
+    /// Cursor position of a device.
+    fn position(&self, kind: DeviceKind, name: &str) -> Option<usize> {
+        match kind {
+            DeviceKind::Input => self.inputs.iter().position(|d| d.name == name),
+            DeviceKind::Output => self
+                .outputs
+                .iter()
+                .position(|d| d.name == name)
+                .map(|i| self.inputs.len() + i),
+        }
+    }
+
+    /// Get the input devices.
+    pub fn inputs(&self) -> &[AudioDevice] {
+        &self.inputs
+    }
+
+    /// Get the output devices.
+    pub fn outputs(&self) -> &[AudioDevice] {
+        &self.outputs
+    }
+
+    /// Mark the input device in use.
+    pub fn set_current_input(&mut self, name: Option<String>) {
+        self.current_input = name;
+    }
+
+    /// Set the sample rate and buffer frames negotiated with the input in
+    /// use; `None` frames if the host picks.
+    pub fn set_input_format(&mut self, sample_rate: u32, buffer_frames: Option<u32>) {
+        self.input_format = Some((sample_rate, buffer_frames));
+    }
+
+    /// Mark the output device in use.
+    pub fn set_current_output(&mut self, name: Option<String>) {
+        self.current_output = name;
+    }
+
+    /// Get the name of the input device in use.
+    pub fn current_input(&self) -> Option<&str> {
+        self.current_input.as_deref()
+    }
+
+    /// Get the name of the output device in use.
+    pub fn current_output(&self) -> Option<&str> {
+        self.current_output.as_deref()
+    }
+
+    /// Get the device under the cursor.
+    pub fn selected(&self) -> Option<(DeviceKind, &AudioDevice)> {
+        match self.inputs.get(self.cursor) {
+            Some(device) => Some((DeviceKind::Input, device)),
+            None => self
+                .outputs
+                .get(self.cursor - self.inputs.len())
+                .map(|device| (DeviceKind::Output, device)),
+        }
+    }
+
+    /// Move the cursor to the next device.
+    pub fn next(&mut self) {
+        let total = self.inputs.len() + self.outputs.len();
+        if total > 0 {
+            self.cursor = (self.cursor + 1) % total;
+        }
+    }
+
+    /// Move the cursor to the previous device.
+    pub fn prev(&mut self) {
+        let total = self.inputs.len() + self.outputs.len();
+        if total > 0 {
+            self.cursor = (self.cursor + total - 1) % total;
+        }
+    }
+
+    /// Show a message, e.g. after a failed switch.
+    pub fn set_toast(&mut self, message: Option<String>) {
+        self.toast = message;
+    }
+
+    /// Get the message being shown.
+    pub fn toast(&self) -> Option<&str> {
+        self.toast.as_deref()
+    }
+
+    /// Describe the format negotiated with the input in use.
+    fn input_format_text(&self) -> Option<String> {
+        self.input_format.map(|(rate, frames)| match frames {
+            Some(frames) => strings::format(
+                "devices.input_format",
+                &[("rate", &rate), ("frames", &frames)],
+            ),
+            None => strings::format("devices.input_rate", &[("rate", &rate)]),
+        })
+    }
+
+    /// Render the input or output list under a heading, returning the rows
+    /// used.
+    fn render_list(&self, kind: DeviceKind, area: Rect, buf: &mut Buffer) -> u16 {
+        if area.height < 2 {
+            return 0;
+        }
+
+        let (heading, devices, current, first_index, current_detail) = match kind {
+            DeviceKind::Input => (
+                strings::get("devices.input"),
+                &self.inputs,
+                self.current_input(),
+                0,
+                self.input_format_text(),
+            ),
+            DeviceKind::Output => (
+                strings::get("devices.output"),
+                &self.outputs,
+                self.current_output(),
+                self.inputs.len(),
+                None,
+            ),
+        };
+
+        let width = area.width as usize;
+        buf.set_stringn(area.x, area.y, heading, width, Theme::title());
+        if devices.is_empty() {
+            buf.set_stringn(
+                area.x + 2,
+                area.y + 1,
+                strings::get("devices.none"),
+                width,
+                Theme::muted(),
+            );
+            return 2;
+        }
+
+        let visible = devices.len().min(area.height as usize - 1);
+        for (i, device) in devices.iter().take(visible).enumerate() {
+            let is_selected = first_index + i == self.cursor;
+            let is_current = current == Some(device.name.as_str());
+
+            let prefix = if is_selected { "▶ " } else { "  " };
+            let mark = if is_current { "● " } else { "  " };
+            let mut line = format!("{}{}{}", prefix, mark, device.name);
+            if device.is_default {
+                line.push(' ');
+                line.push_str(strings::get("devices.default"));
+            }
+            if let Some(detail) = current_detail.as_ref().filter(|_| is_current) {
+                line.push_str(" — ");
+                line.push_str(detail);
+            }
+
+            let style = if is_selected {
+                Theme::selected().add_modifier(Modifier::BOLD)
+            } else if is_current {
+                Theme::in_tune()
+            } else {
+                Theme::muted()
+            };
+            buf.set_stringn(area.x, area.y + 1 + i as u16, &line, width, style);
+        }
+        1 + visible as u16
+    }
+}
+
+impl Widget for &DeviceSelectScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Theme::border())
+            .title(format!(" {} ", strings::get("devices.title")))
+            .title_style(Theme::title());
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        if inner.height < 10 || inner.width < 40 {
+            let msg = strings::get("common.too_small");
+            buf.set_string(inner.x, inner.y, msg, Theme::warning());
+            return;
+        }
+
+        let chunks = Layout::vertical([
+            Constraint::Min(4),    // Device lists
+            Constraint::Length(1), // Toast
+            Constraint::Length(2), // Help text
+        ])
+        .split(inner);
+
+        let lists = Rect {
+            x: chunks[0].x + 2,
+            width: chunks[0].width.saturating_sub(4),
+            ..chunks[0]
+        };
+        let rows = self.render_list(DeviceKind::Input, lists, buf);
+        let outputs = Rect {
+            y: lists.y + rows + 1,
+            height: lists.height.saturating_sub(rows + 1),
+            ..lists
+        };
+        self.render_list(DeviceKind::Output, outputs, buf);
+
+        if let Some(toast) = &self.toast {
+            Paragraph::new(toast.as_str())
+                .style(Theme::warning())
+                .alignment(Alignment::Center)
+                .render(chunks[1], buf);
+        }
+
+        let help_text = Shortcuts::help(&[
+            (Shortcuts::ARROWS, strings::get("help.navigate")),
+            (Shortcuts::ENTER, strings::get("help.use_device")),
+            (Shortcuts::RESCAN, strings::get("help.rescan")),
+            (Shortcuts::ESC, strings::get("help.back")),
+        ]);
+        Paragraph::new(help_text)
+            .style(Theme::muted())
+            .alignment(Alignment::Center)
+            .render(chunks[2], buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_inputs() -> Vec<AudioDevice> {
+        vec![
+            AudioDevice::new("Built-in Microphone", true),
+            AudioDevice::new("USB Interface", false),
+        ]
+    }
+
+    fn mock_outputs() -> Vec<AudioDevice> {
+        vec![AudioDevice::new("Built-in Speakers", true)]
+    }
+
+    fn screen() -> DeviceSelectScreen {
+        let mut screen = DeviceSelectScreen::new();
+        screen.set_current_input(Some("Built-in Microphone".to_string()));
+        screen.set_devices(mock_inputs(), mock_outputs());
+        screen
+    }
+
+    #[test]
+    fn test_cursor_moves_over_inputs_then_outputs() {
+        let mut screen = screen();
+        assert_eq!(
+            screen.selected().map(|(k, d)| (k, d.name.as_str())),
+            Some((DeviceKind::Input, "Built-in Microphone"))
+        );
+
+        screen.next();
+        screen.next();
+        assert_eq!(
+            screen.selected().map(|(k, d)| (k, d.name.as_str())),
+            Some((DeviceKind::Output, "Built-in Speakers"))
+        );
+
+        // Wraps around both ways
+        screen.next();
+        assert_eq!(screen.selected().unwrap().1.name, "Built-in Microphone");
+        screen.prev();
+        assert_eq!(screen.selected().unwrap().1.name, "Built-in Speakers");
+    }
+
+    #[test]
+    fn test_rescan_keeps_cursor_on_device() {
+        let mut screen = screen();
+        screen.next();
+        assert_eq!(screen.selected().unwrap().1.name, "USB Interface");
+
+        // A headset appears ahead of the selected device
+        let mut inputs = mock_inputs();
+        inputs.insert(0, AudioDevice::new("Bluetooth Headset", false));
+        screen.set_devices(inputs, mock_outputs());
+        assert_eq!(screen.selected().unwrap().1.name, "USB Interface");
+
+        // The selected device disappears: back to the current input
+        screen.set_devices(vec![AudioDevice::new("Built-in Microphone", true)], vec![]);
+        assert_eq!(screen.selected().unwrap().1.name, "Built-in Microphone");
+    }
+
+    #[test]
+    fn test_empty_lists() {
+        let mut screen = DeviceSelectScreen::new();
+        screen.next();
+        screen.prev();
+        assert!(screen.selected().is_none());
+    }
+
+    #[test]
+    fn test_render_marks_current_and_toast() {
+        let mut screen = screen();
+        screen.set_toast(Some("Could not open USB Interface".to_string()));
+        screen.set_input_format(48000, Some(256));
+
+        let area = Rect::new(0, 0, 80, 16);
+        let mut buf = Buffer::empty(area);
+        (&screen).render(area, &mut buf);
+
+        let text: Vec<String> = (0..area.height)
+            .map(|y| (0..area.width).map(|x| buf[(x, y)].symbol()).collect())
+            .collect();
+        assert!(text
+            .iter()
+            .any(|l| l.contains("▶ ● Built-in Microphone (default) — 48000 Hz, 256 frames")));
+        assert!(text.iter().any(|l| l.contains("USB Interface")));
+        assert!(text.iter().any(|l| l.contains("Built-in Speakers")));
+        assert!(text
+            .iter()
+            .any(|l| l.contains("Could not open USB Interface")));
+    }
+}
@@ -0,0 +1,301 @@
+//! Tuning history of one piano, with how it drifts between tunings.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::Modifier,
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use crate::tuning::history::{self, Drift};
+use crate::tuning::session::Session;
+use crate::ui::strings;
+use crate::ui::theme::{BoxChars, Shortcuts, Theme};
+
+use super::CompleteScreen;
+
+/// Bar heights for the drift and A4 charts, lowest first.
+const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// History screen state.
+#[derive(Default)]
+pub struct HistoryScreen {
+    /// Pianos with saved sessions.
+    pianos: Vec<String>,
+    /// Index of the piano shown.
+    piano: usize,
+    /// Sessions of the piano shown, most recent first.
+    sessions: Vec<Session>,
+    /// Drift of each session since the one before it.
+    drift: Vec<Option<Drift>>,
+    /// Selected session.
+    cursor: usize,
+    /// Read-only summary of the selected session, if open.
+    summary: Option<CompleteScreen>,
+}
+
+impl HistoryScreen {
+    /// Create a history screen choosing between the given pianos.
+    pub fn new(pianos: Vec<String>) -> Self {
+        Self {
+            pianos,
+            ..Self::default()
+        }
+    }
+
+    /// Show the named piano first, if it has sessions.
+    pub fn with_piano(mut self, name: Option<&str>) -> Self {
+        if let Some(i) = self.pianos.iter().position(|p| Some(p.as_str()) == name) {
+            self.piano = i;
+        }
+        self
+    }
+
+    /// Name of the piano shown.
+    pub fn piano(&self) -> Option<&str> {
+        self.pianos.get(self.piano).map(String::as_str)
+    }
+
+    /// Show the next piano.
+    pub fn next_piano(&mut self) {
+        if !self.pianos.is_empty() {
+            self.piano = (self.piano + 1) % self.pianos.len();
+        }
+    }
+
+    /// Show the previous piano.
+    pub fn prev_piano(&mut self) {
+        if !self.pianos.is_empty() {
+            self.piano = (self.piano + self.pianos.len() - 1) % self.pianos.len();
+        }
+    }
+
+    /// Replace the sessions listed, most recent first.
+    pub fn set_sessions(&mut self, sessions: Vec<Session>) {
+        self.drift = history::drift(&sessions);
+        self.sessions = sessions;
+        self.cursor = 0;
+        self.summary = None;
+    }
+
+    /// Get the sessions listed.
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    /// Drift of each listed session since the one before it.
+    pub fn drift(&self) -> &[Option<Drift>] {
+        &self.drift
+    }
+
+    /// Get the selected session.
+    pub fn selected(&self) -> Option<&Session> {
+        self.sessions.get(self.cursor)
+    }
+
+    /// Select the next session.
+    pub fn next(&mut self) {
+        if self.cursor + 1 < self.sessions.len() {
+            self.cursor += 1;
+        }
+    }
+
+    /// Select the previous session.
+    pub fn prev(&mut self) {
+        self.cursor = self.cursor.saturating_sub(1);
+    }
+
+    /// Open the summary of the selected session.
+    pub fn open_summary(&mut self) {
+        self.summary = self.selected().map(|session| {
+            CompleteScreen::new(session.completed_notes.clone())
+                .with_interval_checks(session.interval_checks.clone())
+                .with_duration(session.duration_secs())
+                .read_only()
+        });
+    }
+
+    /// Close the summary; false if none was open.
+    pub fn close_summary(&mut self) -> bool {
+        self.summary.take().is_some()
+    }
+
+    /// Get the open summary.
+    pub fn summary(&self) -> Option<&CompleteScreen> {
+        self.summary.as_ref()
+    }
+
+    /// Get the open summary mutably.
+    pub fn summary_mut(&mut self) -> Option<&mut CompleteScreen> {
+        self.summary.as_mut()
+    }
+
+    /// Render the session list.
+    fn render_list(&self, area: Rect, buf: &mut Buffer) {
+        let width = area.width as usize;
+        let header = format!(
+            "  {:<17} {:<12} {:>7} {:>9} {:>11}",
+            strings::get("history.date"),
+            strings::get("history.mode"),
+            strings::get("history.score"),
+            strings::get("history.start"),
+            strings::get("history.drift"),
+        );
+        buf.set_stringn(area.x, area.y, &header, width, Theme::title());
+
+        // Keep the cursor in view
+        let rows = (area.height as usize).saturating_sub(1);
+        let first = (self.cursor + 1).saturating_sub(rows);
+        for (row, i) in (first..self.sessions.len()).take(rows).enumerate() {
+            let session = &self.sessions[i];
+            let is_selected = i == self.cursor;
+            let start = session
+                .start_deviation()
+                .map_or("—".to_string(), |c| format!("{:.1}", c));
+            let drift = self.drift[i]
+                .and_then(|d| d.cents_per_month())
+                .map_or("—".to_string(), |c| {
+                    strings::format("history.per_month", &[("cents", &format!("{:.1}", c))])
+                });
+            let line = format!(
+                "{}{:<17} {:<12} {:>7.0} {:>9} {:>11}",
+                if is_selected { "▶ " } else { "  " },
+                session.created_at.format("%Y-%m-%d %H:%M"),
+                format!("{:?}", session.mode),
+                session.stats().quality_score,
+                start,
+                drift,
+            );
+            let style = if is_selected {
+                Theme::selected().add_modifier(Modifier::BOLD)
+            } else {
+                Theme::muted()
+            };
+            buf.set_stringn(area.x, area.y + 1 + row as u16, &line, width, style);
+        }
+    }
+
+    /// Render the start deviation and measured A4 over time, oldest first.
+    fn render_charts(&self, area: Rect, buf: &mut Buffer) {
+        let oldest_first = self.sessions.iter().rev();
+        let start: Vec<Option<f32>> = oldest_first.clone().map(|s| s.start_deviation()).collect();
+        let a4: Vec<Option<f32>> = oldest_first.map(|s| s.measured_a4()).collect();
+
+        let charts = [
+            (strings::get("history.start_chart"), sparkline(&start)),
+            (strings::get("history.a4_chart"), sparkline(&a4)),
+        ];
+        for (row, (label, line)) in charts.iter().enumerate() {
+            let y = area.y + row as u16;
+            if y >= area.y + area.height {
+                break;
+            }
+            buf.set_stringn(area.x, y, label, 12, Theme::muted());
+            buf.set_stringn(
+                area.x + 12,
+                y,
+                line,
+                area.width.saturating_sub(12) as usize,
+                Theme::accent(),
+            );
+        }
+    }
+}
+
+/// Bars scaled between the lowest and highest value; gaps where there is
+/// no value.
+fn sparkline(values: &[Option<f32>]) -> String {
+    let known = values.iter().flatten();
+    let min = known.clone().copied().fold(f32::INFINITY, f32::min);
+    let max = known.copied().fold(f32::NEG_INFINITY, f32::max);
+    values
+        .iter()
+        .map(|value| match value {
+            Some(v) if max > min => {
+                let level = (v - min) / (max - min) * (BARS.len() - 1) as f32;
+                BARS[level.round() as usize]
+            }
+            Some(_) => BARS[BARS.len() / 2],
+            None => '·',
+        })
+        .collect()
+}
+
+impl Widget for &HistoryScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if let Some(summary) = &self.summary {
+            summary.render(area, buf);
+            return;
+        }
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Theme::border())
+            .title(format!(" {} ", strings::get("history.title")))
+            .title_style(Theme::title());
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        if inner.height < 10 || inner.width < 40 {
+            let msg = strings::get("common.too_small");
+            buf.set_string(inner.x, inner.y, msg, Theme::warning());
+            return;
+        }
+
+        let chunks = Layout::vertical([
+            Constraint::Length(2), // Piano
+            Constraint::Min(3),    // Sessions
+            Constraint::Length(3), // Charts
+            Constraint::Length(2), // Help text
+        ])
+        .split(inner);
+
+        let piano = match self.piano() {
+            Some(name) => format!(
+                "{} {} {}",
+                BoxChars::LEFT_ARROW,
+                name,
+                BoxChars::RIGHT_ARROW
+            ),
+            None => strings::get("history.none").to_string(),
+        };
+        Paragraph::new(piano)
+            .style(Theme::accent())
+            .alignment(Alignment::Center)
+            .render(chunks[0], buf);
+
+        let content = |area: Rect| Rect {
+            x: area.x + 2,
+            width: area.width.saturating_sub(4),
+            ..area
+        };
+        if !self.sessions.is_empty() {
+            self.render_list(content(chunks[1]), buf);
+            self.render_charts(content(chunks[2]), buf);
+        }
+
+        let help_text = Shortcuts::help(&[
+            (Shortcuts::LEFT_RIGHT, strings::get("help.piano")),
+            (Shortcuts::ARROWS, strings::get("help.navigate")),
+            (Shortcuts::ENTER, strings::get("help.summary")),
+            (Shortcuts::ESC, strings::get("help.back")),
+        ]);
+        Paragraph::new(help_text)
+            .style(Theme::muted())
+            .alignment(Alignment::Center)
+            .render(chunks[3], buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sparkline() {
+        assert_eq!(sparkline(&[Some(1.0), None, Some(8.0), Some(4.5)]), "▁·█▅");
+        assert_eq!(sparkline(&[Some(2.0), Some(2.0)]), "▅▅");
+        assert_eq!(sparkline(&[]), "");
+    }
+}
@@ -0,0 +1,195 @@
+//! Interval check screen: grade octaves, fifths and thirds by beat rate.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::Style,
+    widgets::{Block, Borders, Gauge, Paragraph, Widget},
+};
+
+use crate::tuning::interval::{
+    Grade, IntervalCheck, IntervalChecker, IntervalKind, IntervalResult,
+};
+use crate::tuning::notes::Note;
+use crate::ui::strings;
+use crate::ui::theme::{Shortcuts, Theme};
+
+/// Interval check screen state.
+pub struct IntervalCheckScreen {
+    /// Check sequence and results.
+    checker: IntervalChecker,
+    /// Listening progress for the current check (0.0 to 1.0).
+    listen_progress: f32,
+}
+
+impl IntervalCheckScreen {
+    /// Create a new interval check screen.
+    pub fn new(checker: IntervalChecker) -> Self {
+        Self {
+            checker,
+            listen_progress: 0.0,
+        }
+    }
+
+    /// Get the checker.
+    pub fn checker(&self) -> &IntervalChecker {
+        &self.checker
+    }
+
+    /// Get the checker mutably.
+    pub fn checker_mut(&mut self) -> &mut IntervalChecker {
+        &mut self.checker
+    }
+
+    /// Set listening progress for display.
+    pub fn set_listen_progress(&mut self, progress: f32) {
+        self.listen_progress = progress;
+    }
+}
+
+/// Style for a check grade.
+pub fn grade_style(grade: Grade) -> Style {
+    match grade {
+        Grade::Good => Theme::in_tune(),
+        Grade::Fair => Theme::warning(),
+        Grade::Poor => Theme::out_of_tune(),
+    }
+}
+
+/// Localized grade label.
+fn grade_label(grade: Grade) -> &'static str {
+    strings::get(match grade {
+        Grade::Good => "grade.good",
+        Grade::Fair => "grade.fair",
+        Grade::Poor => "grade.poor",
+    })
+}
+
+/// Localized check label, e.g. "A0–A1 octave".
+fn check_label(check: &IntervalCheck) -> String {
+    let name = |midi| {
+        Note::from_midi(midi)
+            .map(|n| n.display_name())
+            .unwrap_or_default()
+    };
+    let kind = strings::get(match check.kind {
+        IntervalKind::Octave => "interval.octave",
+        IntervalKind::Fifth => "interval.fifth",
+        IntervalKind::Fourth => "interval.fourth",
+        IntervalKind::MajorThird => "interval.major_third",
+    });
+    format!("{}–{} {}", name(check.lower), name(check.upper()), kind)
+}
+
+/// One-line summary of a check result.
+pub fn format_result(result: &IntervalResult) -> String {
+    format!(
+        "{:<20} {:>5.1} / {:>4.1} bps  {}",
+        result.label,
+        result.measured.unwrap_or(0.0),
+        result.expected,
+        grade_label(result.grade)
+    )
+}
+
+impl Widget for &IntervalCheckScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Theme::border())
+            .title(format!(" {} ", strings::get("interval.title")))
+            .title_style(Theme::title());
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        if inner.height < 12 || inner.width < 40 {
+            let msg = strings::get("common.too_small");
+            buf.set_string(inner.x, inner.y, msg, Theme::warning());
+            return;
+        }
+
+        let chunks = Layout::vertical([
+            Constraint::Length(1), // Step counter
+            Constraint::Length(1), // Spacer
+            Constraint::Length(2), // Instructions
+            Constraint::Length(1), // Expected rate
+            Constraint::Length(1), // Listening progress
+            Constraint::Length(1), // Spacer
+            Constraint::Min(1),    // Results
+            Constraint::Length(2), // Help text
+        ])
+        .split(inner);
+
+        let checker = &self.checker;
+        let counter = strings::format(
+            "interval.counter",
+            &[
+                (
+                    "number",
+                    &(checker.current_index() + 1).min(checker.total()),
+                ),
+                ("total", &checker.total()),
+            ],
+        );
+        Paragraph::new(counter)
+            .style(Theme::muted())
+            .alignment(Alignment::Center)
+            .render(chunks[0], buf);
+
+        if let Some(check) = checker.current() {
+            let instruction =
+                strings::format("interval.instruction", &[("interval", &check_label(check))]);
+            Paragraph::new(instruction)
+                .style(Theme::title())
+                .alignment(Alignment::Center)
+                .render(chunks[2], buf);
+
+            let expected = strings::format(
+                "interval.expected",
+                &[("rate", &format!("{:.1}", checker.expected(check)))],
+            );
+            Paragraph::new(expected)
+                .style(Theme::muted())
+                .alignment(Alignment::Center)
+                .render(chunks[3], buf);
+
+            let bar_area = Rect {
+                x: chunks[4].x + 2,
+                width: chunks[4].width.saturating_sub(4),
+                ..chunks[4]
+            };
+            Gauge::default()
+                .ratio(self.listen_progress.clamp(0.0, 1.0) as f64)
+                .gauge_style(Theme::accent())
+                .label("")
+                .render(bar_area, buf);
+        }
+
+        // Most recent results that fit
+        let results_area = chunks[6];
+        let results = checker.results();
+        let visible = results.len().min(results_area.height as usize);
+        for (i, result) in results[results.len() - visible..].iter().enumerate() {
+            let line = format_result(result);
+            let width = line.chars().count().min(results_area.width as usize) as u16;
+            let x = results_area.x + (results_area.width - width) / 2;
+            buf.set_stringn(
+                x,
+                results_area.y + i as u16,
+                &line,
+                results_area.width as usize,
+                grade_style(result.grade),
+            );
+        }
+
+        let help_text = Shortcuts::help(&[
+            (Shortcuts::SKIP, strings::get("help.skip")),
+            (Shortcuts::QUIT, strings::get("help.done")),
+        ]);
+        Paragraph::new(help_text)
+            .style(Theme::muted())
+            .alignment(Alignment::Center)
+            .render(chunks[7], buf);
+    }
+}
@@ -0,0 +1,251 @@
+//! Measurement pass screen: record each note's deviation without tuning.
+
+use std::collections::HashSet;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Layout, Rect},
+    widgets::{Block, Borders, Gauge, Paragraph, Widget},
+};
+
+use crate::ui::components::{Piano, Progress};
+use crate::ui::strings;
+use crate::ui::theme::{Shortcuts, Theme};
+
+/// Number of consecutive consistent readings needed to capture a note.
+const REQUIRED_READINGS: usize = 6;
+
+/// Maximum spread (in cents) of the readings for them to count as stable.
+const MAX_SPREAD: f32 = 3.0;
+
+/// Measurement screen state for the current note.
+pub struct MeasureScreen {
+    /// Current note name.
+    note_name: String,
+    /// Current note index in tuning order.
+    note_index: usize,
+    /// Total notes to measure.
+    total_notes: usize,
+    /// Chromatic note index (0=A0, 87=C8) for piano display.
+    chromatic_index: usize,
+    /// Recent cents readings for the current note.
+    readings: Vec<f32>,
+    /// Most recent reading, if detecting.
+    current_cents: Option<f32>,
+    /// Chromatic indices already measured.
+    measured_notes: HashSet<usize>,
+    /// Previously captured note and its deviation.
+    last_capture: Option<(String, f32)>,
+}
+
+impl MeasureScreen {
+    /// Create a measurement screen for a note.
+    pub fn new(
+        note_name: impl Into<String>,
+        note_index: usize,
+        total_notes: usize,
+        midi: u8,
+    ) -> Self {
+        Self {
+            note_name: note_name.into(),
+            note_index,
+            total_notes,
+            chromatic_index: (midi - 21) as usize,
+            readings: Vec::new(),
+            current_cents: None,
+            measured_notes: HashSet::new(),
+            last_capture: None,
+        }
+    }
+
+    /// Set the notes already measured, for progress display.
+    pub fn set_measured_notes(&mut self, measured: HashSet<usize>) {
+        self.measured_notes = measured;
+    }
+
+    /// Set the previously captured note for display.
+    pub fn set_last_capture(&mut self, note_name: impl Into<String>, cents: f32) {
+        self.last_capture = Some((note_name.into(), cents));
+    }
+
+    /// Add a cents reading. Returns the captured deviation (median of the
+    /// stable readings) once enough consistent readings have been seen.
+    pub fn update(&mut self, cents: f32) -> Option<f32> {
+        self.current_cents = Some(cents);
+        self.readings.push(cents);
+        if self.readings.len() > REQUIRED_READINGS {
+            self.readings.remove(0);
+        }
+
+        if self.readings.len() < REQUIRED_READINGS {
+            return None;
+        }
+
+        let min = self.readings.iter().cloned().fold(f32::INFINITY, f32::min);
+        let max = self
+            .readings
+            .iter()
+            .cloned()
+            .fold(f32::NEG_INFINITY, f32::max);
+        if max - min > MAX_SPREAD {
+            return None;
+        }
+
+        let mut sorted = self.readings.clone();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let mid = sorted.len() / 2;
+        Some((sorted[mid - 1] + sorted[mid]) / 2.0)
+    }
+
+    /// Clear current detection; the note must sound again from scratch.
+    pub fn clear(&mut self) {
+        self.current_cents = None;
+        self.readings.clear();
+    }
+
+    /// Get capture progress (0.0 to 1.0).
+    pub fn capture_progress(&self) -> f64 {
+        self.readings.len() as f64 / REQUIRED_READINGS as f64
+    }
+
+    /// Get note name.
+    pub fn note_name(&self) -> &str {
+        &self.note_name
+    }
+}
+
+impl Widget for &MeasureScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Theme::border())
+            .title(format!(
+                " {} ",
+                strings::format("measure.title", &[("note", &self.note_name)])
+            ))
+            .title_style(Theme::title());
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        if inner.height < 14 || inner.width < 40 {
+            let msg = strings::get("common.too_small");
+            buf.set_string(inner.x, inner.y, msg, Theme::warning());
+            return;
+        }
+
+        let chunks = Layout::vertical([
+            Constraint::Length(2), // Progress bar
+            Constraint::Length(1), // Spacer
+            Constraint::Length(4), // Piano visualization
+            Constraint::Length(1), // Spacer
+            Constraint::Length(2), // Instructions
+            Constraint::Length(1), // Spacer
+            Constraint::Length(2), // Current reading
+            Constraint::Length(1), // Capture progress
+            Constraint::Min(1),    // Last capture
+            Constraint::Length(2), // Help text
+        ])
+        .split(inner);
+
+        Progress::new(
+            self.note_index,
+            self.total_notes,
+            &self.note_name,
+            strings::get("measure.phase"),
+        )
+        .render(chunks[0], buf);
+
+        Piano::full()
+            .highlighted(self.measured_notes.clone())
+            .current(Some(self.chromatic_index))
+            .render(chunks[2], buf);
+
+        let instruction = strings::format("measure.instruction", &[("note", &self.note_name)]);
+        Paragraph::new(instruction)
+            .style(Theme::title())
+            .alignment(Alignment::Center)
+            .render(chunks[4], buf);
+
+        let (reading, style) = match self.current_cents {
+            Some(cents) => (
+                strings::format("common.cents", &[("cents", &format!("{:+.1}", cents))]),
+                Theme::style_for_cents(cents),
+            ),
+            None => (strings::get("common.listening").to_string(), Theme::muted()),
+        };
+        Paragraph::new(reading)
+            .style(style)
+            .alignment(Alignment::Center)
+            .render(chunks[6], buf);
+
+        let bar_area = Rect {
+            x: chunks[7].x + 2,
+            width: chunks[7].width.saturating_sub(4),
+            ..chunks[7]
+        };
+        Gauge::default()
+            .ratio(self.capture_progress().min(1.0))
+            .gauge_style(Theme::accent())
+            .label("")
+            .render(bar_area, buf);
+
+        if let Some((name, cents)) = &self.last_capture {
+            let text = strings::format(
+                "measure.last",
+                &[("note", name), ("cents", &format!("{:+.1}", cents))],
+            );
+            Paragraph::new(text)
+                .style(Theme::muted())
+                .alignment(Alignment::Center)
+                .render(chunks[8], buf);
+        }
+
+        let help_text = Shortcuts::help(&[
+            (Shortcuts::SKIP, strings::get("help.skip")),
+            (Shortcuts::QUIT, strings::get("help.quit")),
+        ]);
+        Paragraph::new(help_text)
+            .style(Theme::muted())
+            .alignment(Alignment::Center)
+            .render(chunks[9], buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_captures_median_of_stable_readings() {
+        let mut screen = MeasureScreen::new("A4", 0, 88, 69);
+        let readings = [-12.0, -11.0, -12.5, -11.5, -12.0, -13.0];
+
+        let mut captured = None;
+        for &r in &readings {
+            captured = screen.update(r);
+        }
+
+        let cents = captured.expect("Stable readings should capture");
+        assert!((cents - (-12.0)).abs() < 1e-6, "Got {}", cents);
+    }
+
+    #[test]
+    fn test_unstable_readings_do_not_capture() {
+        let mut screen = MeasureScreen::new("A4", 0, 88, 69);
+        for &r in &[-30.0, -20.0, -10.0, 0.0, 10.0, 20.0] {
+            assert!(screen.update(r).is_none());
+        }
+    }
+
+    #[test]
+    fn test_silence_restarts_capture() {
+        let mut screen = MeasureScreen::new("A4", 0, 88, 69);
+        for _ in 0..REQUIRED_READINGS - 1 {
+            assert!(screen.update(-5.0).is_none());
+        }
+        screen.clear();
+        assert_eq!(screen.capture_progress(), 0.0);
+        assert!(screen.update(-5.0).is_none());
+    }
+}
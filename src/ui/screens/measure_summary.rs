@@ -0,0 +1,136 @@
+//! Summary of a measurement pass with the choice of what to do next.
+
+use std::collections::BTreeMap;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Layout, Rect},
+    widgets::{Block, Borders, Paragraph, Widget},
+};
+
+use crate::tuning::notes::Note;
+use crate::tuning::pitch_raise;
+use crate::ui::strings;
+use crate::ui::theme::{Shortcuts, Theme};
+
+/// Measurement summary screen.
+pub struct MeasureSummaryScreen {
+    /// Measured deviation per MIDI note.
+    measurements: BTreeMap<u8, f32>,
+}
+
+impl MeasureSummaryScreen {
+    /// Create a summary for the given measurements.
+    pub fn new(measurements: BTreeMap<u8, f32>) -> Self {
+        Self { measurements }
+    }
+
+    /// Get the measurements.
+    pub fn measurements(&self) -> &BTreeMap<u8, f32> {
+        &self.measurements
+    }
+
+    /// Average signed deviation in cents.
+    pub fn mean_deviation(&self) -> f32 {
+        if self.measurements.is_empty() {
+            return 0.0;
+        }
+        self.measurements.values().sum::<f32>() / self.measurements.len() as f32
+    }
+
+    /// Note with the largest absolute deviation.
+    pub fn worst(&self) -> Option<(u8, f32)> {
+        self.measurements
+            .iter()
+            .map(|(&midi, &cents)| (midi, cents))
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+    }
+
+    /// Whether a pitch raise is recommended.
+    pub fn recommends_pitch_raise(&self) -> bool {
+        pitch_raise::recommends_pitch_raise(&self.measurements)
+    }
+}
+
+impl Widget for &MeasureSummaryScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Theme::border())
+            .title(format!(" {} ", strings::get("measure_summary.title")))
+            .title_style(Theme::title());
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        if inner.height < 10 || inner.width < 40 {
+            let msg = strings::get("common.too_small");
+            buf.set_string(inner.x, inner.y, msg, Theme::warning());
+            return;
+        }
+
+        let chunks = Layout::vertical([
+            Constraint::Length(2), // Recommendation
+            Constraint::Length(1), // Spacer
+            Constraint::Length(4), // Stats
+            Constraint::Min(1),    // Spacer
+            Constraint::Length(2), // Help text
+        ])
+        .split(inner);
+
+        let (verdict, style) = if self.measurements.is_empty() {
+            (strings::get("measure_summary.none"), Theme::muted())
+        } else if self.recommends_pitch_raise() {
+            (
+                strings::get("measure_summary.pitch_raise"),
+                Theme::warning(),
+            )
+        } else {
+            (
+                strings::get("measure_summary.fine_tuning"),
+                Theme::in_tune(),
+            )
+        };
+        Paragraph::new(verdict)
+            .style(style)
+            .alignment(Alignment::Center)
+            .render(chunks[0], buf);
+
+        let mut stats = vec![
+            strings::format(
+                "measure_summary.notes",
+                &[("count", &self.measurements.len())],
+            ),
+            strings::format(
+                "measure_summary.average",
+                &[("cents", &format!("{:+.1}", self.mean_deviation()))],
+            ),
+        ];
+        if let Some((midi, cents)) = self.worst() {
+            let name = Note::from_midi(midi)
+                .map(|n| n.display_name())
+                .unwrap_or_default();
+            stats.push(strings::format(
+                "measure_summary.furthest",
+                &[("note", &name), ("cents", &format!("{:+.1}", cents))],
+            ));
+        }
+
+        for (i, stat) in stats.iter().enumerate() {
+            let y = chunks[2].y + i as u16;
+            let width = stat.chars().count().min(chunks[2].width as usize) as u16;
+            let x = chunks[2].x + (chunks[2].width - width) / 2;
+            buf.set_stringn(x, y, stat, chunks[2].width as usize, Theme::muted());
+        }
+
+        let help_text = Shortcuts::help(&[
+            (Shortcuts::PITCH_RAISE, strings::get("help.pitch_raise")),
+            (Shortcuts::FINE_TUNE, strings::get("help.fine_tuning")),
+            (Shortcuts::QUIT, strings::get("help.quit")),
+        ]);
+        Paragraph::new(help_text)
+            .style(Theme::muted())
+            .alignment(Alignment::Center)
+            .render(chunks[4], buf);
+    }
+}
@@ -2,10 +2,22 @@
 
 pub mod calibration;
 pub mod complete;
+pub mod device_select;
+pub mod history;
+pub mod interval_check;
+pub mod measure;
+pub mod measure_summary;
 pub mod mode_select;
+pub mod setup;
 pub mod tuning;
 
-pub use calibration::CalibrationScreen;
+pub use calibration::{CalibrationScreen, NoiseStatus};
 pub use complete::CompleteScreen;
+pub use device_select::DeviceSelectScreen;
+pub use history::HistoryScreen;
+pub use interval_check::IntervalCheckScreen;
+pub use measure::MeasureScreen;
+pub use measure_summary::MeasureSummaryScreen;
 pub use mode_select::ModeSelectScreen;
-pub use tuning::TuningScreen;
+pub use setup::SetupScreen;
+pub use tuning::{ReferenceStatus, TuningScreen};
@@ -4,9 +4,11 @@ use ratatui::{
     buffer::Buffer,
     layout::{Alignment, Constraint, Layout, Rect},
     style::{Modifier, Style},
-    widgets::{Block, Borders, Paragraph, Widget},
+    widgets::{Block, Borders, Paragraph, Widget, Wrap},
 };
 
+use crate::tuning::Instrument;
+use crate::ui::strings;
 use crate::ui::theme::{Shortcuts, Theme};
 
 /// Selected tuning mode.
@@ -15,29 +17,34 @@ pub enum SelectedMode {
     #[default]
     QuickTune,
     ConcertPitch,
+    Measure,
 }
 
 impl SelectedMode {
     /// Get the mode title.
     pub fn title(&self) -> &'static str {
-        match self {
-            Self::QuickTune => "Quick Tune",
-            Self::ConcertPitch => "Concert Pitch (A4 = 440 Hz)",
-        }
+        strings::get(match self {
+            Self::QuickTune => "mode.quick.title",
+            Self::ConcertPitch => "mode.concert.title",
+            Self::Measure => "mode.measure.title",
+        })
     }
 
     /// Get the mode description.
     pub fn description(&self) -> &'static str {
-        match self {
-            Self::QuickTune => "Calibrate to the piano's current pitch center, then tune all strings relative to that. Best for regular maintenance.",
-            Self::ConcertPitch => "Tune all strings to standard concert pitch (A4 = 440 Hz). Use for pianos that are already close to pitch.",
-        }
+        strings::get(match self {
+            Self::QuickTune => "mode.quick.description",
+            Self::ConcertPitch => "mode.concert.description",
+            Self::Measure => "mode.measure.description",
+        })
     }
 }
 
 /// Mode selection screen.
 pub struct ModeSelectScreen {
     selected: SelectedMode,
+    /// Instrument to tune.
+    instrument: Instrument,
 }
 
 impl ModeSelectScreen {
@@ -45,9 +52,26 @@ impl ModeSelectScreen {
     pub fn new() -> Self {
         Self {
             selected: SelectedMode::default(),
+            instrument: Instrument::default(),
         }
     }
 
+    /// Select an instrument.
+    pub fn with_instrument(mut self, instrument: Instrument) -> Self {
+        self.instrument = instrument;
+        self
+    }
+
+    /// Get the selected instrument.
+    pub fn instrument(&self) -> Instrument {
+        self.instrument
+    }
+
+    /// Select the next instrument.
+    pub fn next_instrument(&mut self) {
+        self.instrument = self.instrument.next();
+    }
+
     /// Get the currently selected mode.
     pub fn selected(&self) -> SelectedMode {
         self.selected
@@ -57,13 +81,18 @@ impl ModeSelectScreen {
     pub fn next(&mut self) {
         self.selected = match self.selected {
             SelectedMode::QuickTune => SelectedMode::ConcertPitch,
-            SelectedMode::ConcertPitch => SelectedMode::QuickTune,
+            SelectedMode::ConcertPitch => SelectedMode::Measure,
+            SelectedMode::Measure => SelectedMode::QuickTune,
         };
     }
 
-    /// Select the previous mode (same as next for 2 options).
+    /// Select the previous mode.
     pub fn prev(&mut self) {
-        self.next();
+        self.selected = match self.selected {
+            SelectedMode::QuickTune => SelectedMode::Measure,
+            SelectedMode::ConcertPitch => SelectedMode::QuickTune,
+            SelectedMode::Measure => SelectedMode::ConcertPitch,
+        };
     }
 }
 
@@ -79,14 +108,14 @@ impl Widget for &ModeSelectScreen {
         let block = Block::default()
             .borders(Borders::ALL)
             .border_style(Theme::border())
-            .title(" onkey - Piano Tuner ")
+            .title(format!(" {} ", strings::get("mode_select.title")))
             .title_style(Theme::title());
 
         let inner = block.inner(area);
         block.render(area, buf);
 
         if inner.height < 10 || inner.width < 40 {
-            let msg = "Terminal too small";
+            let msg = strings::get("common.too_small");
             buf.set_string(inner.x, inner.y, msg, Theme::warning());
             return;
         }
@@ -96,18 +125,23 @@ impl Widget for &ModeSelectScreen {
             Constraint::Length(3), // Title
             Constraint::Length(1), // Spacer
             Constraint::Min(8),    // Mode options
+            Constraint::Length(1), // Instrument
             Constraint::Length(3), // Help text
         ])
         .split(inner);
 
         // Title
-        let title = Paragraph::new("Select Tuning Mode")
+        let title = Paragraph::new(strings::get("mode_select.heading"))
             .style(Theme::title())
             .alignment(Alignment::Center);
         title.render(chunks[0], buf);
 
         // Mode options
-        let modes = [SelectedMode::QuickTune, SelectedMode::ConcertPitch];
+        let modes = [
+            SelectedMode::QuickTune,
+            SelectedMode::ConcertPitch,
+            SelectedMode::Measure,
+        ];
         let option_height = 4;
         let options_area = chunks[2];
 
@@ -129,17 +163,30 @@ impl Widget for &ModeSelectScreen {
             render_mode_option(*mode, is_selected, option_area, buf);
         }
 
-        // Help text at bottom
-        let help_text = format!(
-            "{} Navigate  {} Select  {} Quit",
-            Shortcuts::ARROWS,
-            Shortcuts::ENTER,
-            Shortcuts::QUIT
+        // Instrument
+        let instrument = strings::format(
+            "mode_select.instrument",
+            &[("instrument", &self.instrument.name())],
         );
+        Paragraph::new(instrument)
+            .style(Theme::muted())
+            .alignment(Alignment::Center)
+            .render(chunks[3], buf);
+
+        // Help text at bottom
+        let help_text = Shortcuts::help(&[
+            (Shortcuts::ARROWS, strings::get("help.navigate")),
+            (Shortcuts::INSTRUMENT, strings::get("help.instrument")),
+            (Shortcuts::ENTER, strings::get("help.select")),
+            (Shortcuts::HISTORY, strings::get("help.history")),
+            (Shortcuts::DEVICES, strings::get("help.devices")),
+            (Shortcuts::QUIT, strings::get("help.quit")),
+        ]);
         let help = Paragraph::new(help_text)
             .style(Theme::muted())
-            .alignment(Alignment::Center);
-        help.render(chunks[3], buf);
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true });
+        help.render(chunks[4], buf);
     }
 }
 
@@ -165,10 +212,11 @@ fn render_mode_option(mode: SelectedMode, is_selected: bool, area: Rect, buf: &m
 
     // Title line
     let title_line = format!("{}{}", prefix, mode.title());
-    buf.set_string(
+    buf.set_stringn(
         inner.x,
         inner.y,
         &title_line,
+        inner.width as usize,
         title_style.add_modifier(Modifier::BOLD),
     );
 
@@ -176,11 +224,56 @@ fn render_mode_option(mode: SelectedMode, is_selected: bool, area: Rect, buf: &m
     if inner.height >= 2 && inner.width > 4 {
         let desc = mode.description();
         let max_width = inner.width.saturating_sub(2) as usize;
-        let truncated = if desc.len() > max_width {
-            format!("{}...", &desc[..max_width.saturating_sub(3)])
+        let truncated = if desc.chars().count() > max_width {
+            let kept: String = desc.chars().take(max_width.saturating_sub(3)).collect();
+            format!("{}...", kept)
         } else {
             desc.to_string()
         };
-        buf.set_string(inner.x + 2, inner.y + 1, &truncated, Theme::muted());
+        buf.set_stringn(
+            inner.x + 2,
+            inner.y + 1,
+            &truncated,
+            max_width,
+            Theme::muted(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translated_description_truncated() {
+        strings::with_strings(strings::tests::german(), || {
+            let mut screen = ModeSelectScreen::new();
+            screen.next();
+            let area = Rect::new(0, 0, 44, 24);
+            let mut buf = Buffer::empty(area);
+            (&screen).render(area, &mut buf);
+
+            let rows: Vec<String> = (0..area.height)
+                .map(|y| {
+                    (0..area.width)
+                        .map(|x| buf[(x, y)].symbol().to_string())
+                        .collect()
+                })
+                .collect();
+            let text = rows.join("\n");
+            assert!(text.contains("▶ Kammerton"), "{}", text);
+            assert!(text.contains("Alle Saiten"), "{}", text);
+            assert!(text.contains("Quick Tune"), "{}", text);
+            let description = rows.iter().find(|r| r.contains("Alle Saiten")).unwrap();
+            assert!(description.contains("...│"), "{}", text);
+        });
+    }
+
+    #[test]
+    fn test_instrument_cycles() {
+        let mut screen = ModeSelectScreen::new();
+        assert_eq!(screen.instrument(), Instrument::Piano);
+        screen.next_instrument();
+        assert_eq!(screen.instrument(), Instrument::Harpsichord);
     }
 }
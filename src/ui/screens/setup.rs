@@ -0,0 +1,1021 @@
+//! Session setup screen: A4 reference, temperament and stretch.
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Widget, Wrap},
+};
+
+use crate::tuning::scheme::root_name;
+use crate::tuning::stretch::MAX_INTENSITY;
+use crate::tuning::{
+    CustomTemperament, Instrument, OrderStrategy, PianoType, StretchProfile, TemperamentScheme,
+};
+use crate::ui::components::A4Field;
+use crate::ui::strings;
+use crate::ui::theme::{BoxChars, Shortcuts, Theme};
+
+use super::mode_select::SelectedMode;
+
+/// Hz the A4 reference moves by per step.
+pub const A4_STEP: f32 = 0.5;
+
+/// Largest transposition in whole semitones, either way.
+pub const MAX_SEMITONES: i32 = 12;
+
+/// Cents the fine offset moves by per step.
+pub const OFFSET_STEP: f32 = 5.0;
+
+/// Largest fine offset in cents, either way; beyond it, transpose.
+pub const MAX_OFFSET: f32 = 50.0;
+
+/// Stretch intensity moves by a tenth of the default curve per step.
+pub const INTENSITY_STEP: f32 = 0.1;
+
+/// Setting chosen on the setup screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupField {
+    A4,
+    Transpose,
+    Offset,
+    Temperament,
+    Key,
+    Stretch,
+    Curve,
+    Intensity,
+    Order,
+}
+
+impl SetupField {
+    /// All settings, top to bottom.
+    const ALL: [SetupField; 9] = [
+        Self::A4,
+        Self::Transpose,
+        Self::Offset,
+        Self::Temperament,
+        Self::Key,
+        Self::Stretch,
+        Self::Curve,
+        Self::Intensity,
+        Self::Order,
+    ];
+
+    fn label(&self) -> &'static str {
+        strings::get(match self {
+            Self::A4 => "setup.a4",
+            Self::Transpose => "setup.transpose",
+            Self::Offset => "setup.offset",
+            Self::Temperament => "setup.temperament",
+            Self::Key => "setup.key",
+            Self::Stretch => "setup.stretch",
+            Self::Curve => "setup.curve",
+            Self::Intensity => "setup.intensity",
+            Self::Order => "setup.order",
+        })
+    }
+}
+
+/// Setup screen, shown between mode select and calibration or tuning.
+pub struct SetupScreen {
+    /// Mode the session is set up for.
+    mode: SelectedMode,
+    /// Setting being changed.
+    focus: SetupField,
+    /// A4 reference for concert pitch and measurement.
+    a4: A4Field,
+    /// Instrument to tune.
+    instrument: Instrument,
+    /// Temperament scheme the targets follow.
+    temperament: TemperamentScheme,
+    /// Pitch class the scheme is laid from (C = 0).
+    root: u8,
+    /// Custom temperaments offered after the schemes.
+    customs: Vec<CustomTemperament>,
+    /// Custom temperament selected instead of the scheme.
+    custom: Option<CustomTemperament>,
+    /// Whether the targets follow the instrument's stretch curve.
+    stretch: bool,
+    /// Type of piano, whose stretch preset is followed.
+    piano_type: PianoType,
+    /// Saved stretch curves offered after the piano types.
+    profiles: Vec<StretchProfile>,
+    /// Saved stretch curve selected instead of the piano type's preset.
+    profile: Option<StretchProfile>,
+    /// Factor the stretch curve is scaled by.
+    intensity: f32,
+    /// Sequence the keys are tuned in.
+    order: OrderStrategy,
+    /// Whole semitones every target is moved by.
+    semitones: i32,
+    /// Cents every target is moved by on top of the semitones.
+    offset: f32,
+}
+
+impl SetupScreen {
+    /// Create a new setup screen.
+    pub fn new() -> Self {
+        Self {
+            mode: SelectedMode::default(),
+            focus: SetupField::Temperament,
+            a4: A4Field::default(),
+            instrument: Instrument::default(),
+            temperament: Instrument::default().default_temperament(),
+            root: 0,
+            customs: Vec::new(),
+            custom: None,
+            stretch: false,
+            piano_type: PianoType::default(),
+            profiles: Vec::new(),
+            profile: None,
+            intensity: 1.0,
+            order: OrderStrategy::default(),
+            semitones: 0,
+            offset: 0.0,
+        }
+    }
+
+    /// Use the given A4 presets.
+    pub fn with_a4_presets(mut self, presets: Vec<f32>) -> Self {
+        let value = self.a4.value();
+        self.a4 = A4Field::new(presets);
+        self.a4.set_value(value);
+        self
+    }
+
+    /// Start from the given A4 reference.
+    pub fn with_a4(mut self, hz: f32) -> Self {
+        self.a4.set_value(hz);
+        self
+    }
+
+    /// Set up for the given mode.
+    pub fn with_mode(mut self, mode: SelectedMode) -> Self {
+        self.set_mode(mode);
+        self
+    }
+
+    /// Select an instrument, with its default temperament unless one is
+    /// given.
+    pub fn with_instrument(
+        mut self,
+        instrument: Instrument,
+        temperament: Option<TemperamentScheme>,
+    ) -> Self {
+        self.instrument = instrument;
+        self.temperament = temperament.unwrap_or(instrument.default_temperament());
+        self
+    }
+
+    /// Lay the temperament scheme from the root pitch class `root` (C = 0).
+    pub fn with_root(mut self, root: u8) -> Self {
+        self.root = root % 12;
+        self
+    }
+
+    /// Offer custom temperaments after the schemes.
+    pub fn with_custom_temperaments(mut self, customs: Vec<CustomTemperament>) -> Self {
+        self.customs = customs;
+        self
+    }
+
+    /// Select a custom temperament instead of the scheme.
+    pub fn with_custom_temperament(mut self, custom: Option<CustomTemperament>) -> Self {
+        self.custom = custom;
+        self
+    }
+
+    /// Tune to the instrument's stretch curve.
+    pub fn with_stretch(mut self, stretch: bool) -> Self {
+        self.stretch = stretch;
+        self
+    }
+
+    /// Follow the stretch preset of the piano type.
+    pub fn with_piano_type(mut self, piano_type: PianoType) -> Self {
+        self.piano_type = piano_type;
+        self
+    }
+
+    /// Offer saved stretch curves after the piano types.
+    pub fn with_stretch_profiles(mut self, profiles: Vec<StretchProfile>) -> Self {
+        self.profiles = profiles;
+        self
+    }
+
+    /// Select a saved stretch curve instead of the piano type's preset.
+    pub fn with_stretch_profile(mut self, profile: Option<StretchProfile>) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Scale the stretch curve by `intensity`, 1.0 being the default curve.
+    pub fn with_stretch_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = intensity.clamp(0.0, MAX_INTENSITY);
+        self
+    }
+
+    /// Tune the keys in the sequence of `strategy`.
+    pub fn with_order_strategy(mut self, strategy: OrderStrategy) -> Self {
+        self.order = strategy;
+        self
+    }
+
+    /// Move every target by `cents`: whole semitones, and the rest as a
+    /// fine offset.
+    pub fn with_transpose(mut self, cents: f32) -> Self {
+        let semitones = (cents / 100.0).round();
+        self.semitones = (semitones as i32).clamp(-MAX_SEMITONES, MAX_SEMITONES);
+        self.offset = (cents - semitones * 100.0).clamp(-MAX_OFFSET, MAX_OFFSET);
+        self
+    }
+
+    /// Set up for `mode`. Quick tune finds A4 by calibration, so neither
+    /// the reference nor a transposition is offered.
+    pub fn set_mode(&mut self, mode: SelectedMode) {
+        self.mode = mode;
+        if !self.offers(self.focus) {
+            self.focus = SetupField::Temperament;
+        }
+    }
+
+    /// Set up for `instrument`, bringing back its default temperament if it
+    /// changed. A stretch curve is only chosen for a piano.
+    pub fn set_instrument(&mut self, instrument: Instrument) {
+        if instrument != self.instrument {
+            self.instrument = instrument;
+            self.temperament = instrument.default_temperament();
+            self.custom = None;
+            self.profile = None;
+            if !self.offers(self.focus) {
+                self.focus = SetupField::Stretch;
+            }
+        }
+    }
+
+    /// Get the mode being set up.
+    pub fn mode(&self) -> SelectedMode {
+        self.mode
+    }
+
+    /// Get the setting being changed.
+    pub fn focus(&self) -> SetupField {
+        self.focus
+    }
+
+    /// Get the A4 reference field.
+    pub fn a4(&self) -> &A4Field {
+        &self.a4
+    }
+
+    /// Get the A4 reference field mutably.
+    pub fn a4_mut(&mut self) -> &mut A4Field {
+        &mut self.a4
+    }
+
+    /// Get the instrument.
+    pub fn instrument(&self) -> Instrument {
+        self.instrument
+    }
+
+    /// Get the selected temperament scheme.
+    pub fn temperament(&self) -> TemperamentScheme {
+        self.temperament
+    }
+
+    /// Get the pitch class the scheme is laid from (C = 0).
+    pub fn root(&self) -> u8 {
+        self.root
+    }
+
+    /// Get the custom temperaments offered.
+    pub fn custom_temperaments(&self) -> &[CustomTemperament] {
+        &self.customs
+    }
+
+    /// Get the custom temperament selected instead of the scheme.
+    pub fn custom_temperament(&self) -> Option<&CustomTemperament> {
+        self.custom.as_ref()
+    }
+
+    /// Whether the targets follow the instrument's stretch curve.
+    pub fn stretch(&self) -> bool {
+        self.stretch
+    }
+
+    /// Type of piano, whose stretch preset is followed.
+    pub fn piano_type(&self) -> PianoType {
+        self.piano_type
+    }
+
+    /// Get the saved stretch curves offered.
+    pub fn stretch_profiles(&self) -> &[StretchProfile] {
+        &self.profiles
+    }
+
+    /// Get the saved stretch curve selected instead of the piano type's
+    /// preset.
+    pub fn stretch_profile(&self) -> Option<&StretchProfile> {
+        self.profile.as_ref()
+    }
+
+    /// Factor the stretch curve is scaled by.
+    pub fn stretch_intensity(&self) -> f32 {
+        self.intensity
+    }
+
+    /// Sequence the keys are tuned in.
+    pub fn order_strategy(&self) -> OrderStrategy {
+        self.order
+    }
+
+    /// Cents every target is moved by.
+    pub fn transpose_cents(&self) -> f32 {
+        self.semitones as f32 * 100.0 + self.offset
+    }
+
+    /// Whether the setting is offered for the mode; the intensity only
+    /// with stretch on, and the curve only for a stretched piano.
+    fn offers(&self, field: SetupField) -> bool {
+        match field {
+            SetupField::A4 | SetupField::Transpose | SetupField::Offset => {
+                self.mode != SelectedMode::QuickTune
+            }
+            SetupField::Curve => self.stretch && self.instrument == Instrument::Piano,
+            SetupField::Intensity => self.stretch,
+            _ => true,
+        }
+    }
+
+    /// The settings offered for the mode, top to bottom.
+    fn fields(&self) -> impl Iterator<Item = SetupField> + '_ {
+        SetupField::ALL.into_iter().filter(|f| self.offers(*f))
+    }
+
+    /// Change the next setting down, wrapping around.
+    pub fn next_field(&mut self) {
+        let fields: Vec<_> = self.fields().collect();
+        let i = fields.iter().position(|f| *f == self.focus).unwrap_or(0);
+        self.focus = fields[(i + 1) % fields.len()];
+    }
+
+    /// Change the next setting up, wrapping around.
+    pub fn prev_field(&mut self) {
+        let fields: Vec<_> = self.fields().collect();
+        let i = fields.iter().position(|f| *f == self.focus).unwrap_or(0);
+        self.focus = fields[(i + fields.len() - 1) % fields.len()];
+    }
+
+    /// Move the setting being changed up (`forward`) or down.
+    pub fn adjust(&mut self, forward: bool) {
+        match self.focus {
+            SetupField::A4 => self.a4.step(if forward { A4_STEP } else { -A4_STEP }),
+            SetupField::Transpose => {
+                let step = if forward { 1 } else { -1 };
+                self.semitones = (self.semitones + step).clamp(-MAX_SEMITONES, MAX_SEMITONES);
+            }
+            SetupField::Offset => {
+                let step = if forward { OFFSET_STEP } else { -OFFSET_STEP };
+                self.offset = (self.offset + step).clamp(-MAX_OFFSET, MAX_OFFSET);
+            }
+            SetupField::Temperament if forward => self.next_temperament(),
+            SetupField::Temperament => self.prev_temperament(),
+            SetupField::Key if forward => self.next_root(),
+            SetupField::Key => self.prev_root(),
+            SetupField::Stretch => self.toggle_stretch(),
+            SetupField::Curve if forward => self.cycle_curve(1),
+            SetupField::Curve => self.cycle_curve(-1),
+            SetupField::Intensity => {
+                let step = if forward {
+                    INTENSITY_STEP
+                } else {
+                    -INTENSITY_STEP
+                };
+                // Kept to whole steps, so repeated steps don't drift
+                let intensity = ((self.intensity + step) / INTENSITY_STEP).round() * INTENSITY_STEP;
+                self.intensity = intensity.clamp(0.0, MAX_INTENSITY);
+            }
+            SetupField::Order if forward => self.order = self.order.next(),
+            SetupField::Order => self.order = self.order.prev(),
+        }
+    }
+
+    /// Select the next temperament: the next scheme, then each custom
+    /// temperament, then back to the first scheme.
+    pub fn next_temperament(&mut self) {
+        self.cycle_temperament(1);
+    }
+
+    /// Select the previous temperament.
+    pub fn prev_temperament(&mut self) {
+        self.cycle_temperament(-1);
+    }
+
+    fn cycle_temperament(&mut self, step: isize) {
+        let schemes = &TemperamentScheme::ALL;
+        let current = match &self.custom {
+            Some(custom) => self
+                .customs
+                .iter()
+                .position(|c| c == custom)
+                .map_or(schemes.len() - 1, |i| schemes.len() + i),
+            None => schemes
+                .iter()
+                .position(|s| *s == self.temperament)
+                .unwrap_or(0),
+        };
+        let count = (schemes.len() + self.customs.len()) as isize;
+        let next = (current as isize + step).rem_euclid(count) as usize;
+        if next < schemes.len() {
+            self.temperament = schemes[next];
+            self.custom = None;
+        } else {
+            self.custom = Some(self.customs[next - schemes.len()].clone());
+        }
+    }
+
+    /// Select the next stretch curve: the next piano type's preset, then
+    /// each saved curve, then back to the spinet.
+    fn cycle_curve(&mut self, step: isize) {
+        let types = &PianoType::ALL;
+        let current = match &self.profile {
+            Some(profile) => self
+                .profiles
+                .iter()
+                .position(|p| p == profile)
+                .map_or(types.len() - 1, |i| types.len() + i),
+            None => types
+                .iter()
+                .position(|t| *t == self.piano_type)
+                .unwrap_or(0),
+        };
+        let count = (types.len() + self.profiles.len()) as isize;
+        let next = (current as isize + step).rem_euclid(count) as usize;
+        if next < types.len() {
+            self.piano_type = types[next];
+            self.profile = None;
+        } else {
+            self.profile = Some(self.profiles[next - types.len()].clone());
+        }
+    }
+
+    /// Lay the scheme from the next root key, a fifth up.
+    pub fn next_root(&mut self) {
+        self.root = (self.root + 7) % 12;
+    }
+
+    /// Lay the scheme from the previous root key, a fifth down.
+    pub fn prev_root(&mut self) {
+        self.root = (self.root + 5) % 12;
+    }
+
+    /// Switch tuning to the stretch curve on or off.
+    pub fn toggle_stretch(&mut self) {
+        self.stretch = !self.stretch;
+        if !self.offers(self.focus) {
+            self.focus = SetupField::Stretch;
+        }
+    }
+
+    /// The temperament as shown: with its wolf, or the key it is laid from
+    /// if not C.
+    pub fn temperament_label(&self) -> String {
+        if let Some(custom) = &self.custom {
+            return if custom.root == 0 {
+                custom.name.clone()
+            } else {
+                strings::format(
+                    "setup.from_root",
+                    &[
+                        ("temperament", &custom.name),
+                        ("root", &root_name(custom.root)),
+                    ],
+                )
+            };
+        }
+        let name = self.temperament.name();
+        if let Some(wolf) = self.temperament.wolf_name(self.root) {
+            strings::format("setup.wolf", &[("temperament", &name), ("wolf", &wolf)])
+        } else if self.root == 0 || self.temperament == TemperamentScheme::Equal {
+            name.to_string()
+        } else {
+            strings::format(
+                "setup.from_root",
+                &[("temperament", &name), ("root", &root_name(self.root))],
+            )
+        }
+    }
+
+    /// Whether stretch is on, as shown.
+    pub fn stretch_label(&self) -> &'static str {
+        strings::get(if self.stretch {
+            "setup.on"
+        } else {
+            "setup.off"
+        })
+    }
+
+    /// The stretch curve as shown: the saved curve's name, or the piano
+    /// type.
+    pub fn curve_label(&self) -> &str {
+        match &self.profile {
+            Some(profile) => &profile.name,
+            None => self.piano_type.name(),
+        }
+    }
+
+    /// The stretch intensity as shown, in percent of the default curve.
+    pub fn intensity_label(&self) -> String {
+        strings::format(
+            "setup.percent",
+            &[("percent", &format!("{:.0}", self.intensity * 100.0))],
+        )
+    }
+
+    /// The value of a setting other than A4, as shown.
+    fn value_label(&self, field: SetupField) -> String {
+        match field {
+            SetupField::A4 => format!("{:.1} Hz", self.a4.value()),
+            SetupField::Transpose => strings::format(
+                if self.semitones.abs() == 1 {
+                    "setup.semitone"
+                } else {
+                    "setup.semitones"
+                },
+                &[("count", &format!("{:+}", self.semitones))],
+            ),
+            SetupField::Offset => strings::format(
+                "common.cents",
+                &[("cents", &format!("{:+.0}", self.offset))],
+            ),
+            SetupField::Temperament => self.temperament_label(),
+            SetupField::Key => root_name(self.root).to_string(),
+            SetupField::Stretch => self.stretch_label().to_string(),
+            SetupField::Curve => self.curve_label().to_string(),
+            SetupField::Intensity => self.intensity_label(),
+            SetupField::Order => self.order.name().to_string(),
+        }
+    }
+}
+
+impl Default for SetupScreen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Widget for &SetupScreen {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Theme::border())
+            .title(format!(" {} ", strings::get("setup.title")))
+            .title_style(Theme::title());
+
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        if inner.height < 16 || inner.width < 40 {
+            let msg = strings::get("common.too_small");
+            buf.set_string(inner.x, inner.y, msg, Theme::warning());
+            return;
+        }
+
+        // Layout: heading, settings, help text
+        let chunks = Layout::vertical([
+            Constraint::Length(3), // Heading
+            Constraint::Length(1), // Spacer
+            Constraint::Min(9),    // Settings
+            Constraint::Length(3), // Help text
+        ])
+        .split(inner);
+
+        let heading = strings::format(
+            "setup.heading",
+            &[
+                ("mode", &self.mode.title()),
+                ("instrument", &self.instrument.name()),
+            ],
+        );
+        Paragraph::new(heading)
+            .style(Theme::title())
+            .alignment(Alignment::Center)
+            .render(chunks[0], buf);
+
+        // One row per setting, two lines apart if there is room
+        let settings = chunks[2];
+        let rows = SetupField::ALL.len() as u16;
+        let spacing = if settings.height >= rows * 2 - 1 {
+            2
+        } else {
+            1
+        };
+        for (i, field) in SetupField::ALL.iter().enumerate() {
+            let y = settings.y + i as u16 * spacing;
+            let row = Rect {
+                x: settings.x + 2,
+                y,
+                width: settings.width.saturating_sub(4),
+                height: 1,
+            };
+            self.render_field(*field, row, buf);
+        }
+
+        let help_text = Shortcuts::help(&[
+            (Shortcuts::ARROWS, strings::get("help.navigate")),
+            (Shortcuts::LEFT_RIGHT, strings::get("help.change")),
+            (Shortcuts::PRESET, strings::get("help.a4_preset")),
+            (Shortcuts::TEMPERAMENT, strings::get("help.temperament")),
+            (Shortcuts::ROOT, strings::get("help.root")),
+            (Shortcuts::STRETCH, strings::get("help.stretch")),
+            (Shortcuts::VIEW_CURVE, strings::get("help.curve")),
+            (Shortcuts::ENTER, strings::get("help.start")),
+            (Shortcuts::ESC, strings::get("help.back")),
+        ]);
+        Paragraph::new(help_text)
+            .style(Theme::muted())
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .render(chunks[3], buf);
+    }
+}
+
+impl SetupScreen {
+    /// Render a setting's label on the left and its value on the right.
+    fn render_field(&self, field: SetupField, area: Rect, buf: &mut Buffer) {
+        let offered = self.offers(field);
+        let focused = offered && field == self.focus;
+        let label_style = if focused {
+            Theme::selected().add_modifier(Modifier::BOLD)
+        } else if offered {
+            Style::default()
+        } else {
+            Theme::muted()
+        };
+        let prefix = if focused { "▶ " } else { "  " };
+        let label = format!("{}{}", prefix, field.label());
+        buf.set_stringn(area.x, area.y, &label, area.width as usize, label_style);
+
+        let label_width = 18.min(area.width);
+        let value_area = Rect {
+            x: area.x + label_width,
+            width: area.width - label_width,
+            ..area
+        };
+        if !offered {
+            let value = match field {
+                SetupField::Curve | SetupField::Intensity => self.value_label(field),
+                _ => strings::get("setup.a4_calibrated").to_string(),
+            };
+            Paragraph::new(value)
+                .style(Theme::muted())
+                .alignment(Alignment::Center)
+                .render(value_area, buf);
+        } else if field == SetupField::A4 {
+            self.a4.render(value_area, buf);
+        } else {
+            let value = format!(
+                "{} {} {}",
+                BoxChars::LEFT_ARROW,
+                self.value_label(field),
+                BoxChars::RIGHT_ARROW
+            );
+            Paragraph::new(value)
+                .style(if focused {
+                    Theme::accent()
+                } else {
+                    Style::default()
+                })
+                .alignment(Alignment::Center)
+                .render(value_area, buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tuning::{CurveSource, StretchCurve};
+    use ratatui::{backend::TestBackend, Terminal};
+
+    /// Draw the screen on a test terminal and return its text.
+    fn draw(screen: &SetupScreen, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| frame.render_widget(screen, frame.area()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    fn custom(name: &str, root: u8) -> CustomTemperament {
+        CustomTemperament {
+            name: name.to_string(),
+            root,
+            offsets: [0.0; 12],
+        }
+    }
+
+    #[test]
+    fn test_renders_settings() {
+        let mut screen = SetupScreen::new()
+            .with_mode(SelectedMode::ConcertPitch)
+            .with_instrument(Instrument::Harpsichord, None)
+            .with_a4(415.0)
+            .with_transpose(-130.0);
+        screen.toggle_stretch();
+        let text = draw(&screen, 70, 20);
+        assert!(text.contains("◀ -1 semitone ▶"), "{}", text);
+        assert!(text.contains("◀ -30 cents ▶"), "{}", text);
+        assert!(text.contains("Concert Pitch · Harpsichord"), "{}", text);
+        assert!(text.contains("A4 = ◀ 415.0 Hz ▶"), "{}", text);
+        assert!(text.contains("▶ Temperament"), "{}", text);
+        assert!(text.contains("◀ Werckmeister III ▶"), "{}", text);
+        assert!(text.contains("◀ C ▶"), "{}", text);
+        assert!(text.contains("◀ On ▶"), "{}", text);
+
+        // Calibration sets A4 for a quick tune
+        let text = draw(&screen.with_mode(SelectedMode::QuickTune), 70, 20);
+        assert!(text.contains("Set by calibration"), "{}", text);
+        assert!(!text.contains("415.0"), "{}", text);
+
+        let text = draw(&SetupScreen::new(), 30, 8);
+        assert!(text.contains("Terminal too small"), "{}", text);
+    }
+
+    #[test]
+    fn test_transpose_split() {
+        let screen = SetupScreen::new().with_transpose(-160.0);
+        assert_eq!(screen.value_label(SetupField::Transpose), "-2 semitones");
+        assert_eq!(screen.value_label(SetupField::Offset), "+40 cents");
+        assert_eq!(screen.transpose_cents(), -160.0);
+
+        // Beyond an octave is held at the octave
+        let mut screen = SetupScreen::new()
+            .with_mode(SelectedMode::ConcertPitch)
+            .with_transpose(1300.0);
+        assert_eq!(screen.transpose_cents(), 1200.0);
+        screen.prev_field();
+        screen.prev_field();
+        screen.adjust(true);
+        assert_eq!(screen.transpose_cents(), 1200.0);
+    }
+
+    #[test]
+    fn test_navigate_and_adjust() {
+        let mut screen = SetupScreen::new().with_mode(SelectedMode::Measure);
+        assert_eq!(screen.focus(), SetupField::Temperament);
+        screen.prev_field();
+        assert_eq!(screen.focus(), SetupField::Offset);
+        screen.adjust(false);
+        screen.prev_field();
+        assert_eq!(screen.focus(), SetupField::Transpose);
+        screen.adjust(false);
+        assert_eq!(screen.transpose_cents(), -105.0);
+        screen.prev_field();
+        assert_eq!(screen.focus(), SetupField::A4);
+        screen.adjust(true);
+        screen.adjust(true);
+        screen.adjust(false);
+        assert_eq!(screen.a4().value(), 440.5);
+        screen.prev_field();
+        assert_eq!(screen.focus(), SetupField::Order);
+        screen.prev_field();
+        assert_eq!(screen.focus(), SetupField::Stretch);
+        screen.adjust(false);
+        assert!(screen.stretch());
+
+        screen.prev_field();
+        screen.adjust(true);
+        assert_eq!(screen.root(), 7);
+        screen.adjust(false);
+        screen.adjust(false);
+        assert_eq!(screen.root(), 5);
+
+        // Quick tune skips A4 and the transposition
+        screen.set_mode(SelectedMode::QuickTune);
+        screen.next_field();
+        screen.next_field();
+        assert_eq!(screen.focus(), SetupField::Curve);
+        screen.next_field();
+        assert_eq!(screen.focus(), SetupField::Intensity);
+        screen.next_field();
+        assert_eq!(screen.focus(), SetupField::Order);
+        screen.next_field();
+        assert_eq!(screen.focus(), SetupField::Temperament);
+        screen.prev_field();
+        screen.prev_field();
+        assert_eq!(screen.focus(), SetupField::Intensity);
+        screen.prev_field();
+        screen.prev_field();
+        assert_eq!(screen.focus(), SetupField::Stretch);
+    }
+
+    #[test]
+    fn test_stretch_intensity() {
+        let mut screen = SetupScreen::new().with_stretch_intensity(1.4);
+        // Only offered with stretch on
+        screen.prev_field();
+        screen.prev_field();
+        assert_eq!(screen.focus(), SetupField::Stretch);
+        screen.toggle_stretch();
+        screen.next_field();
+        screen.next_field();
+        assert_eq!(screen.focus(), SetupField::Intensity);
+        for _ in 0..3 {
+            screen.adjust(true);
+        }
+        assert_eq!(screen.stretch_intensity(), MAX_INTENSITY);
+        for _ in 0..15 {
+            screen.adjust(false);
+        }
+        assert_eq!(screen.stretch_intensity(), 0.0);
+        for _ in 0..7 {
+            screen.adjust(true);
+        }
+        assert_eq!(screen.intensity_label(), "70%");
+        assert!(draw(&screen, 70, 20).contains("◀ 70% ▶"));
+
+        // Switching stretch off leaves the intensity row
+        screen.toggle_stretch();
+        assert_eq!(screen.focus(), SetupField::Stretch);
+        assert_eq!(screen.stretch_intensity(), 0.7);
+    }
+
+    #[test]
+    fn test_order_strategy() {
+        let mut screen = SetupScreen::new();
+        assert_eq!(screen.order_strategy(), OrderStrategy::TrebleFirst);
+        for _ in 0..3 {
+            screen.next_field();
+        }
+        assert_eq!(screen.focus(), SetupField::Order);
+        screen.adjust(true);
+        assert_eq!(
+            screen.order_strategy(),
+            OrderStrategy::AuralTemperamentFirst
+        );
+        assert!(draw(&screen, 70, 20).contains("◀ Temperament, bass, treble ▶"));
+        screen.adjust(false);
+        screen.adjust(false);
+        assert_eq!(screen.order_strategy(), OrderStrategy::Chromatic);
+
+        let screen = SetupScreen::new().with_order_strategy(OrderStrategy::Chromatic);
+        assert!(draw(&screen, 70, 20).contains("Chromatic"));
+    }
+
+    #[test]
+    fn test_piano_type() {
+        let mut screen = SetupScreen::new()
+            .with_stretch(true)
+            .with_piano_type(PianoType::Legacy);
+        for _ in 0..3 {
+            screen.next_field();
+        }
+        assert_eq!(screen.focus(), SetupField::Curve);
+        screen.adjust(true);
+        assert_eq!(screen.piano_type(), PianoType::Spinet);
+        screen.adjust(true);
+        screen.adjust(false);
+        screen.adjust(false);
+        assert_eq!(screen.piano_type(), PianoType::Legacy);
+        assert!(draw(&screen, 70, 20).contains("◀ Legacy ▶"));
+        screen.adjust(false);
+        assert_eq!(screen.piano_type(), PianoType::ConcertGrand);
+
+        // Only pianos come in types
+        screen.set_instrument(Instrument::Harpsichord);
+        assert_eq!(screen.focus(), SetupField::Stretch);
+        screen.next_field();
+        assert_eq!(screen.focus(), SetupField::Intensity);
+    }
+
+    #[test]
+    fn test_saved_curves_follow_piano_types() {
+        let saved = |name: &str| {
+            StretchProfile::new(
+                name,
+                CurveSource::Manual,
+                StretchCurve::preset(PianoType::Spinet),
+            )
+        };
+        let mut screen = SetupScreen::new()
+            .with_stretch(true)
+            .with_piano_type(PianoType::Legacy)
+            .with_stretch_profiles(vec![saved("Studio"), saved("Hall")]);
+        for _ in 0..3 {
+            screen.next_field();
+        }
+        screen.adjust(true);
+        assert_eq!(screen.curve_label(), "Studio");
+        screen.adjust(true);
+        assert_eq!(
+            screen.stretch_profile(),
+            Some(&screen.stretch_profiles()[1])
+        );
+        assert!(draw(&screen, 70, 20).contains("◀ Hall ▶"));
+        screen.adjust(true);
+        assert!(screen.stretch_profile().is_none());
+        assert_eq!(screen.curve_label(), "Spinet");
+
+        // Backwards from the first piano type reaches the last saved curve
+        screen.adjust(false);
+        assert_eq!(screen.curve_label(), "Hall");
+
+        // A new instrument drops it
+        screen.set_instrument(Instrument::Harpsichord);
+        assert!(screen.stretch_profile().is_none());
+    }
+
+    #[test]
+    fn test_a4_steps_stay_in_range() {
+        let mut screen = SetupScreen::new().with_a4(480.0);
+        screen.a4_mut().step(A4_STEP);
+        assert_eq!(screen.a4().value(), 480.0);
+        // A typed value is stepped from
+        screen.a4_mut().push_char('4');
+        screen.a4_mut().push_char('3');
+        screen.a4_mut().push_char('2');
+        screen.a4_mut().step(-A4_STEP);
+        assert_eq!(screen.a4().value(), 431.5);
+        assert!(!screen.a4().is_editing());
+    }
+
+    #[test]
+    fn test_instrument_selects_its_temperament() {
+        let mut screen = SetupScreen::new();
+        assert_eq!(screen.temperament(), TemperamentScheme::Equal);
+        screen.set_instrument(Instrument::Harpsichord);
+        assert_eq!(screen.temperament(), TemperamentScheme::WerckmeisterIii);
+        screen.next_temperament();
+        assert_eq!(screen.temperament(), TemperamentScheme::Vallotti);
+
+        // The same instrument keeps the choice
+        screen.set_instrument(Instrument::Harpsichord);
+        assert_eq!(screen.temperament(), TemperamentScheme::Vallotti);
+
+        let screen = SetupScreen::new()
+            .with_instrument(Instrument::Fortepiano, Some(TemperamentScheme::Meantone));
+        assert_eq!(screen.temperament(), TemperamentScheme::Meantone);
+    }
+
+    #[test]
+    fn test_root_and_wolf_shown() {
+        let mut screen = SetupScreen::new();
+        assert_eq!(screen.temperament_label(), "Equal");
+        screen.next_root();
+        assert_eq!(screen.root(), 7);
+        // Equal temperament is the same from any key
+        assert_eq!(screen.temperament_label(), "Equal");
+
+        screen.set_instrument(Instrument::Harpsichord);
+        assert_eq!(screen.temperament_label(), "Werckmeister III from G");
+
+        screen.next_temperament();
+        screen.next_temperament();
+        assert_eq!(screen.temperament(), TemperamentScheme::Meantone);
+        assert_eq!(screen.temperament_label(), "1/4-comma meantone, wolf D#-Bb");
+        let screen = screen.with_root(0);
+        assert_eq!(screen.temperament_label(), "1/4-comma meantone, wolf G#-Eb");
+    }
+
+    #[test]
+    fn test_custom_temperaments_follow_schemes() {
+        let mut screen = SetupScreen::new()
+            .with_custom_temperaments(vec![custom("Kellner", 0), custom("Mine", 7)]);
+        for _ in 1..TemperamentScheme::ALL.len() {
+            screen.next_temperament();
+            assert!(screen.custom_temperament().is_none());
+        }
+        assert_eq!(screen.temperament(), TemperamentScheme::YoungIi);
+
+        screen.next_temperament();
+        assert_eq!(screen.temperament_label(), "Kellner");
+        screen.next_temperament();
+        assert_eq!(screen.temperament_label(), "Mine from G");
+        screen.next_temperament();
+        assert!(screen.custom_temperament().is_none());
+        assert_eq!(screen.temperament(), TemperamentScheme::Equal);
+
+        // Backwards from the first scheme reaches the last custom
+        screen.prev_temperament();
+        assert_eq!(screen.temperament_label(), "Mine from G");
+        screen.prev_temperament();
+        screen.prev_temperament();
+        assert_eq!(screen.temperament(), TemperamentScheme::YoungIi);
+        assert!(screen.custom_temperament().is_none());
+
+        // A new instrument brings back its own scheme
+        let mut screen = screen.with_custom_temperament(Some(custom("Kellner", 0)));
+        screen.set_instrument(Instrument::Harpsichord);
+        assert!(screen.custom_temperament().is_none());
+        assert_eq!(screen.temperament(), TemperamentScheme::WerckmeisterIii);
+    }
+}
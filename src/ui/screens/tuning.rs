@@ -1,6 +1,7 @@
 //! Main tuning screen.
 
 use std::collections::HashSet;
+use std::time::Duration;
 
 use ratatui::{
     buffer::Buffer,
@@ -8,9 +9,31 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph, Widget},
 };
 
+use crate::audio::Waveform;
+use crate::tuning::order::StringOrder;
+use crate::tuning::session::StringReading;
+use crate::tuning::{Instrument, OctaveType};
 use crate::ui::components::instructions::TuningStep;
-use crate::ui::components::{Instructions, Meter, Piano, Progress};
-use crate::ui::theme::{Shortcuts, Theme};
+use crate::ui::components::{
+    ClipBanner, Instructions, Meter, PeakHold, Piano, Progress, Readout, Stability,
+    StabilityIndicator, StabilityTracker,
+};
+use crate::ui::strings;
+use crate::ui::theme::{BoxChars, Shortcuts, Theme};
+
+/// The reference tone as shown while it plays.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReferenceStatus {
+    /// Volume from 0 to 1.
+    pub volume: f32,
+    /// Whether the octave below sounds with the target.
+    pub octave: bool,
+    /// Cents a second voice beating against the target is detuned by,
+    /// and the beat rate in Hz, if there is one.
+    pub beats: Option<(f32, f32)>,
+    /// Time left before it stops by itself, if it does.
+    pub remaining: Option<Duration>,
+}
 
 /// Main tuning screen state.
 pub struct TuningScreen {
@@ -28,19 +51,91 @@ pub struct TuningScreen {
     detected_freq: Option<f32>,
     /// Cents deviation from target.
     cents_deviation: f32,
+    /// Detection confidence of the last reading (kept while it is held).
+    confidence: Option<f32>,
+    /// Input level (RMS), if audio is being captured.
+    level: Option<f32>,
+    /// Peak excursions for the meter.
+    peak_hold: PeakHold,
+    /// Rolling spread of the recent readings.
+    stability: StabilityTracker,
+    /// Start of the current run of stable readings.
+    stable_since: Option<Duration>,
+    /// Reading the current stable run is centered on.
+    stable_cents: f32,
+    /// How long to hold the last stable reading after detection drops.
+    hold_time: Duration,
+    /// Time until which a held reading is shown.
+    held_until: Option<Duration>,
+    /// Format of the value below the meter.
+    readout: Readout,
+    /// Whether the reading has locked in tune since it last drifted.
+    locked: bool,
+    /// Instrument being tuned, for the wording of the instructions.
+    instrument: Instrument,
     /// Number of strings for this note.
     string_count: u8,
     /// Current tuning step (for multi-string notes).
     tuning_step: Option<TuningStep>,
+    /// Cents recorded as each tuning step was confirmed.
+    step_readings: Vec<(TuningStep, f32)>,
     /// Phase name for display.
     phase_name: String,
     /// Whether to show piano progress view.
     show_piano_progress: bool,
     /// Set of completed chromatic indices.
     completed_notes: HashSet<usize>,
+    /// Whether false beats were detected on this note.
+    false_beats: bool,
+    /// Beat rate measured between the strings sounding, on unison steps.
+    measured_beats: Option<f32>,
+    /// Whether the latest frame had more than one note sounding.
+    ambiguous: bool,
+    /// Deviation measured before this session, if any.
+    measured_cents: Option<f32>,
+    /// Final cents per tuning-order position (None if not tuned).
+    note_quality: Vec<Option<f32>>,
+    /// Notice shown in place of the meter while detection is paused.
+    reference_notice: Option<String>,
+    /// Partial the pitch is being inferred from, for bass notes.
+    partial: Option<u32>,
+    /// Manual adjustment of the target in cents.
+    target_offset: f32,
+    /// Stretch of the target in cents.
+    stretch_cents: f32,
+    /// Octave type the stretch was worked out at, if from inharmonicity.
+    octave_type: Option<OctaveType>,
+    /// A4 reference and the transposition from it in cents, if transposed.
+    transposition: Option<(f32, f32)>,
+    /// Whether the target was captured from another instrument.
+    target_captured: bool,
+    /// Progress of a target capture in progress.
+    capture_progress: Option<f64>,
+    /// Whether the microphone input is being recorded.
+    recording: bool,
+    /// Whether the input is clipping.
+    clipping: bool,
+    /// Software gain on the input in dB.
+    gain_db: f32,
+    /// Waveform of the reference tone.
+    waveform: Waveform,
+    /// The reference tone, while it plays.
+    reference: Option<ReferenceStatus>,
 }
 
 impl TuningScreen {
+    /// Default time a stable reading is held after the note decays.
+    pub const DEFAULT_HOLD_TIME: Duration = Duration::from_millis(2500);
+
+    /// Bottom of the input level meter in dBFS.
+    pub const LEVEL_FLOOR_DB: f32 = -60.0;
+
+    /// How long readings must stay together to count as stable.
+    const STABLE_TIME: Duration = Duration::from_millis(500);
+
+    /// Spread in cents within which readings count as stable.
+    const STABLE_SPREAD: f32 = 2.0;
+
     /// Create a new tuning screen.
     pub fn new(
         note_name: impl Into<String>,
@@ -54,11 +149,11 @@ impl TuningScreen {
         let tuning_step = TuningStep::first_for_strings(string_count);
 
         let phase_name = if string_count == 3 {
-            "Trichord".to_string()
+            strings::get("tuning.phase.trichord").to_string()
         } else if string_count == 2 {
-            "Bichord".to_string()
+            strings::get("tuning.phase.bichord").to_string()
         } else {
-            "Single".to_string()
+            strings::get("tuning.phase.single").to_string()
         };
 
         // Chromatic index: 0=A0 (MIDI 21), 87=C8 (MIDI 108)
@@ -72,14 +167,69 @@ impl TuningScreen {
             target_freq,
             detected_freq: None,
             cents_deviation: 0.0,
+            confidence: None,
+            level: None,
+            peak_hold: PeakHold::new(),
+            stability: StabilityTracker::new(),
+            stable_since: None,
+            stable_cents: 0.0,
+            hold_time: Self::DEFAULT_HOLD_TIME,
+            held_until: None,
+            readout: Readout::default(),
+            locked: false,
+            instrument: Instrument::default(),
             string_count,
             tuning_step,
+            step_readings: Vec::new(),
             phase_name,
             show_piano_progress: false,
             completed_notes: HashSet::new(),
+            false_beats: false,
+            recording: false,
+            clipping: false,
+            gain_db: 0.0,
+            waveform: Waveform::default(),
+            reference: None,
+            measured_beats: None,
+            ambiguous: false,
+            measured_cents: None,
+            note_quality: Vec::new(),
+            reference_notice: None,
+            partial: None,
+            target_offset: 0.0,
+            stretch_cents: 0.0,
+            octave_type: None,
+            transposition: None,
+            target_captured: false,
+            capture_progress: None,
         }
     }
 
+    /// Set how long the last stable reading is held after the note decays.
+    pub fn with_hold_time(mut self, hold_time: Duration) -> Self {
+        self.hold_time = hold_time;
+        self
+    }
+
+    /// Show the value below the meter in the given unit and precision.
+    pub fn with_readout(mut self, readout: Readout) -> Self {
+        self.readout = readout;
+        self
+    }
+
+    /// Word the instructions for the given instrument.
+    pub fn with_instrument(mut self, instrument: Instrument) -> Self {
+        self.instrument = instrument;
+        self
+    }
+
+    /// Tune trichord strings in the given order.
+    pub fn with_string_order(mut self, order: StringOrder) -> Self {
+        self.tuning_step = TuningStep::first_for(self.string_count, order);
+        self.step_readings.clear();
+        self
+    }
+
     /// Toggle piano progress display.
     pub fn toggle_piano_progress(&mut self) {
         self.show_piano_progress = !self.show_piano_progress;
@@ -90,21 +240,206 @@ impl TuningScreen {
         self.completed_notes = completed;
     }
 
+    /// Flag that false beats were detected on this note.
+    pub fn set_false_beats(&mut self, false_beats: bool) {
+        self.false_beats = false_beats;
+    }
+
+    /// Check if false beats were detected on this note.
+    pub fn has_false_beats(&self) -> bool {
+        self.false_beats
+    }
+
+    /// Set whether the microphone input is being recorded.
+    pub fn set_recording(&mut self, recording: bool) {
+        self.recording = recording;
+    }
+
+    /// Whether the microphone input is being recorded.
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Set whether the input is clipping.
+    pub fn set_clipping(&mut self, clipping: bool) {
+        self.clipping = clipping;
+    }
+
+    /// Set the software gain on the input in dB, shown with the level when
+    /// not zero.
+    pub fn set_gain_db(&mut self, db: f32) {
+        self.gain_db = db;
+    }
+
+    /// Set the waveform of the reference tone, shown in the help bar.
+    pub fn set_waveform(&mut self, waveform: Waveform) {
+        self.waveform = waveform;
+    }
+
+    /// Set the reference tone playing, shown above the meter with its
+    /// volume and in the help bar with its countdown; `None` when it isn't
+    /// playing.
+    pub fn set_reference(&mut self, reference: Option<ReferenceStatus>) {
+        self.reference = reference;
+    }
+
+    /// Set the final cents of tuned notes, by tuning-order position.
+    pub fn set_note_quality(&mut self, quality: Vec<Option<f32>>) {
+        self.note_quality = quality;
+    }
+
+    /// Set the deviation measured for this note before the session.
+    pub fn set_measured_cents(&mut self, measured_cents: Option<f32>) {
+        self.measured_cents = measured_cents;
+    }
+
+    /// Show a notice in place of the meter (e.g. while a reference tone
+    /// pauses detection).
+    pub fn set_reference_notice(&mut self, notice: Option<String>) {
+        self.reference_notice = notice;
+    }
+
+    /// Get the notice shown in place of the meter.
+    pub fn reference_notice(&self) -> Option<&str> {
+        self.reference_notice.as_deref()
+    }
+
+    /// Flag that more than one note is sounding, so there is no reading.
+    pub fn set_ambiguous(&mut self, ambiguous: bool) {
+        self.ambiguous = ambiguous;
+    }
+
+    /// Whether more than one note is sounding.
+    pub fn is_ambiguous(&self) -> bool {
+        self.ambiguous
+    }
+
+    /// Show that the pitch is inferred from the given partial.
+    pub fn set_partial(&mut self, partial: Option<u32>) {
+        self.partial = partial;
+    }
+
+    /// Get the partial the pitch is inferred from.
+    pub fn partial(&self) -> Option<u32> {
+        self.partial
+    }
+
     /// Get note index.
     pub fn note_index(&self) -> usize {
         self.note_index
     }
 
     /// Update with detected pitch.
-    pub fn update(&mut self, freq: f32, cents: f32) {
+    pub fn update(&mut self, now: Duration, freq: f32, cents: f32) {
+        if self.stable_since.is_none() || (cents - self.stable_cents).abs() > Self::STABLE_SPREAD {
+            self.stable_since = Some(now);
+            self.stable_cents = cents;
+        }
+        self.held_until = None;
+        self.ambiguous = false;
         self.detected_freq = Some(freq);
         self.cents_deviation = cents;
+        self.peak_hold.update(now, cents);
+        self.stability.push(now, cents);
     }
 
     /// Clear detected pitch (silence/no detection).
-    pub fn clear(&mut self) {
+    ///
+    /// After a run of stable readings the last value is held for the hold
+    /// time before the meter reverts to listening.
+    pub fn clear(&mut self, now: Duration) {
+        if self.detected_freq.is_some() && self.is_stable(now) {
+            self.held_until = Some(now + self.hold_time);
+        }
         self.detected_freq = None;
+        self.stable_since = None;
+        self.ambiguous = false;
+
+        if self.held_until.is_some_and(|until| now < until) {
+            return;
+        }
+        self.held_until = None;
         self.cents_deviation = 0.0;
+        self.confidence = None;
+        self.peak_hold.reset();
+        self.stability.reset();
+    }
+
+    /// Check whether the reading has just locked: sounding, within
+    /// tolerance and stable for the hold time. True once per lock; the
+    /// reading must drift or drop out before it can lock again.
+    pub fn check_lock(&mut self, now: Duration) -> bool {
+        let sounding =
+            self.detected_freq.is_some() && self.tuning_step.is_none_or(|step| !step.is_muting());
+        let in_lock = sounding
+            && self.cents_deviation.abs() <= self.tolerance()
+            && self
+                .stable_since
+                .is_some_and(|since| now.saturating_sub(since) >= self.hold_time);
+        let locked = in_lock && !self.locked;
+        self.locked = in_lock;
+        locked
+    }
+
+    /// Whether readings have been stable long enough to hold.
+    fn is_stable(&self, now: Duration) -> bool {
+        self.stable_since
+            .is_some_and(|since| now.saturating_sub(since) >= Self::STABLE_TIME)
+    }
+
+    /// Stability of the recent readings (kept while a reading is held).
+    pub fn stability(&self) -> Option<Stability> {
+        self.stability.stability()
+    }
+
+    /// Spread of the recent readings in cents.
+    pub fn spread(&self) -> Option<f32> {
+        self.stability.spread()
+    }
+
+    /// Set the detection confidence of the latest reading.
+    pub fn set_confidence(&mut self, confidence: f32) {
+        self.confidence = Some(confidence);
+    }
+
+    /// Detection confidence of the reading shown, if any.
+    pub fn confidence(&self) -> Option<f32> {
+        self.confidence
+    }
+
+    /// Set the input level (RMS).
+    pub fn set_level(&mut self, rms: f32) {
+        self.level = Some(rms);
+    }
+
+    /// Input level in dBFS, if known.
+    pub fn level_db(&self) -> Option<f32> {
+        self.level
+            .map(|rms| (20.0 * rms.max(1e-6).log10()).max(Self::LEVEL_FLOOR_DB))
+    }
+
+    /// Whether the recent readings are steady.
+    pub fn is_steady(&self) -> bool {
+        self.stability() == Some(Stability::Steady)
+    }
+
+    /// Whether the string is still settling: sounding, but the recent
+    /// readings don't yet agree.
+    pub fn is_settling(&self) -> bool {
+        self.detected_freq.is_some()
+            && !self
+                .stability
+                .is_stable(Stability::STEADY_SPREAD, StabilityTracker::MIN_READINGS)
+    }
+
+    /// Whether the meter shows a held reading.
+    pub fn is_held(&self) -> bool {
+        self.held_until.is_some()
+    }
+
+    /// Get the meter's peak-hold state.
+    pub fn peak_hold(&self) -> &PeakHold {
+        &self.peak_hold
     }
 
     /// Get current cents deviation.
@@ -133,10 +468,17 @@ impl TuningScreen {
     }
 
     /// Advance to next tuning step (for multi-string notes).
+    ///
+    /// Records the current reading for the step being left.
     pub fn next_step(&mut self) -> bool {
-        if let Some(step) = &self.tuning_step {
+        if let Some(step) = self.tuning_step {
             if let Some(next) = step.next() {
+                self.record_step();
                 self.tuning_step = Some(next);
+                self.measured_beats = None;
+                self.stability.reset();
+                self.stable_since = None;
+                self.locked = false;
                 return true;
             }
         }
@@ -145,30 +487,78 @@ impl TuningScreen {
 
     /// Go back to previous tuning step.
     pub fn prev_step(&mut self) -> bool {
-        if let Some(step) = &self.tuning_step {
+        if let Some(step) = self.tuning_step {
             if let Some(prev) = step.prev() {
+                self.step_readings.retain(|&(s, _)| s != prev);
                 self.tuning_step = Some(prev);
+                self.measured_beats = None;
+                self.stability.reset();
+                self.stable_since = None;
+                self.locked = false;
                 return true;
             }
         }
         false
     }
 
-    /// Check if note tuning is complete.
-    pub fn is_complete(&self) -> bool {
-        match self.string_count {
-            3 => {
-                self.tuning_step == Some(TuningStep::TuneRight)
-                    && self.cents_deviation.abs() <= 5.0
-                    && self.detected_freq.is_some()
-            }
-            2 => {
-                self.tuning_step == Some(TuningStep::TuneBichord)
-                    && self.cents_deviation.abs() <= 5.0
-                    && self.detected_freq.is_some()
-            }
-            _ => self.cents_deviation.abs() <= 5.0 && self.detected_freq.is_some(),
+    /// Record the current reading for the current step (ignored for muting
+    /// steps and without a detected pitch).
+    pub fn record_step(&mut self) {
+        let Some(step) = self.tuning_step else {
+            return;
+        };
+        if step.is_muting() || self.detected_freq.is_none() {
+            return;
         }
+        self.step_readings.retain(|&(s, _)| s != step);
+        self.step_readings.push((step, self.cents_deviation));
+    }
+
+    /// Per-string readings recorded so far, in tuning order.
+    pub fn string_readings(&self) -> Vec<StringReading> {
+        self.step_readings
+            .iter()
+            .filter_map(|&(step, cents)| {
+                step.string_label().map(|string| StringReading {
+                    string: string.to_string(),
+                    cents,
+                })
+            })
+            .collect()
+    }
+
+    /// Tolerance in cents for the current step.
+    pub fn tolerance(&self) -> f32 {
+        self.tuning_step
+            .map(|step| step.tolerance())
+            .unwrap_or(TuningStep::PITCH_TOLERANCE)
+    }
+
+    /// Set the beat rate measured between the strings sounding, or `None`
+    /// if they don't beat.
+    pub fn set_measured_beats(&mut self, rate: Option<f32>) {
+        self.measured_beats = rate;
+    }
+
+    /// Beat rate measured between the strings sounding, on unison steps.
+    pub fn measured_beats(&self) -> Option<f32> {
+        self.tuning_step.filter(|step| step.is_unison())?;
+        self.measured_beats
+    }
+
+    /// Beat rate between the sounding string and the target, for unison steps.
+    pub fn unison_beat_rate(&self) -> Option<f32> {
+        self.tuning_step.filter(|step| step.is_unison())?;
+        self.detected_freq?;
+        let ratio = 2.0_f32.powf(self.cents_deviation / 1200.0);
+        Some(self.target_freq * (ratio - 1.0).abs())
+    }
+
+    /// Check if note tuning is complete: on the last step and within its
+    /// tolerance.
+    pub fn is_complete(&self) -> bool {
+        let last_step = self.tuning_step.is_none_or(|step| step.next().is_none());
+        last_step && self.cents_deviation.abs() <= self.tolerance() && self.detected_freq.is_some()
     }
 
     /// Get note name.
@@ -176,26 +566,118 @@ impl TuningScreen {
         &self.note_name
     }
 
+    /// Format of the value below the meter.
+    pub fn readout(&self) -> Readout {
+        self.readout
+    }
+
     /// Get target frequency.
     pub fn target_freq(&self) -> f32 {
         self.target_freq
     }
+
+    /// Get the detected frequency, if detecting.
+    pub fn detected_freq(&self) -> Option<f32> {
+        self.detected_freq
+    }
+
+    /// Replace the target with one manually offset by `offset` cents.
+    pub fn set_target(&mut self, target_freq: f32, offset: f32) {
+        self.target_freq = target_freq;
+        self.target_offset = offset;
+    }
+
+    /// Get the manual adjustment of the target in cents.
+    pub fn target_offset(&self) -> f32 {
+        self.target_offset
+    }
+
+    /// Show the stretch of the target in cents; zero hides it.
+    pub fn set_stretch(&mut self, cents: f32) {
+        self.stretch_cents = cents;
+    }
+
+    /// Show the octave type the stretch was worked out at beside it.
+    pub fn set_octave_type(&mut self, octave_type: Option<OctaveType>) {
+        self.octave_type = octave_type;
+    }
+
+    /// Show the A4 reference and a transposition of `cents` from it in
+    /// the header; zero hides it.
+    pub fn set_transposition(&mut self, a4: f32, cents: f32) {
+        self.transposition = (cents != 0.0).then_some((a4, cents));
+    }
+
+    /// Mark the target as captured from another instrument.
+    pub fn set_target_captured(&mut self, captured: bool) {
+        self.target_captured = captured;
+    }
+
+    /// Show the progress of a target capture, or `None` when not capturing.
+    pub fn set_capture(&mut self, progress: Option<f64>) {
+        self.capture_progress = progress;
+    }
+
+    /// Whether a target capture is in progress.
+    pub fn is_capturing(&self) -> bool {
+        self.capture_progress.is_some()
+    }
+
+    /// Whether a notice replaces the meter.
+    fn has_notice(&self) -> bool {
+        self.reference_notice.is_some() || self.is_capturing()
+    }
 }
 
 impl Widget for &TuningScreen {
     fn render(self, area: Rect, buf: &mut Buffer) {
         // Main container
+        let title = match self.measured_cents {
+            Some(cents) => strings::format(
+                "tuning.title_measured",
+                &[
+                    ("note", &self.note_name),
+                    ("cents", &format!("{:+.1}", cents)),
+                ],
+            ),
+            None => strings::format("tuning.title", &[("note", &self.note_name)]),
+        };
+        let title = match self.transposition {
+            Some((a4, cents)) => {
+                let a4 = format!("{:.1}", a4);
+                let sign = if cents < 0.0 { '−' } else { '+' };
+                let transposed = strings::format(
+                    "tuning.transposed",
+                    &[
+                        ("a4", &a4.trim_end_matches(".0")),
+                        ("offset", &format!("{} {:.0}", sign, cents.abs())),
+                    ],
+                );
+                format!("{} · {}", title, transposed)
+            }
+            None => title,
+        };
         let block = Block::default()
             .borders(Borders::ALL)
             .border_style(Theme::border())
-            .title(format!(" Tuning: {} ", self.note_name))
+            .title(format!(" {} ", title))
             .title_style(Theme::title());
 
         let inner = block.inner(area);
         block.render(area, buf);
 
+        // Recording indicator, right-aligned on the top border
+        if self.recording {
+            let flag = format!(" {} ", strings::get("tuning.recording"));
+            let x = (area.x + area.width).saturating_sub(flag.chars().count() as u16 + 1);
+            buf.set_string(x, area.y, flag, Theme::out_of_tune());
+        }
+        if self.clipping {
+            ClipBanner.render(area, buf);
+        }
+
         if inner.height < 15 || inner.width < 40 {
-            let msg = "Terminal too small";
+            let msg = strings::get("common.too_small");
             buf.set_string(inner.x, inner.y, msg, Theme::warning());
             return;
         }
@@ -217,14 +699,24 @@ impl Widget for &TuningScreen {
         .split(inner);
 
         // Progress indicator
-        let progress = Progress::new(
+        let mut progress = Progress::new(
             self.note_index,
             self.total_notes,
             &self.note_name,
             &self.phase_name,
         );
+        if !self.note_quality.is_empty() {
+            progress = progress.with_note_quality(self.note_quality.clone());
+        }
         progress.render(chunks[0], buf);
 
+        // False-beat indicator, right-aligned on the header line
+        if self.false_beats {
+            let flag = strings::get("tuning.false_beats");
+            let x = (chunks[0].x + chunks[0].width).saturating_sub(flag.chars().count() as u16);
+            buf.set_string(x, chunks[0].y, flag, Theme::warning());
+        }
+
         // Piano visualization (full 88-key piano, A0=MIDI 21)
         let piano = if self.show_piano_progress {
             Piano::full()
@@ -235,46 +727,499 @@ impl Widget for &TuningScreen {
         };
         piano.render(chunks[2], buf);
 
+        // Stretch of the target, right-aligned below the piano
+        if self.stretch_cents != 0.0 {
+            let offset = format!("{:+.1}", self.stretch_cents);
+            let tag = match self.octave_type {
+                Some(octave) => strings::format(
+                    "tuning.stretch_octave",
+                    &[("offset", &offset), ("octave", &octave.name())],
+                ),
+                None => strings::format("tuning.stretch", &[("offset", &offset)]),
+            };
+            let x = (chunks[3].x + chunks[3].width).saturating_sub(tag.chars().count() as u16 + 1);
+            buf.set_string(x, chunks[3].y, &tag, Theme::muted());
+        }
+
         // Instructions panel
         let instructions_area = chunks[4];
         if let Some(step) = self.tuning_step {
             // Multi-string note (bichord or trichord)
-            let instructions = if is_muting_step {
+            let mut instructions = if is_muting_step {
                 // Don't show direction hints during muting
-                Instructions::for_step(step, self.string_count)
+                Instructions::for_step(step, self.string_count).with_instrument(self.instrument)
             } else {
                 Instructions::for_step(step, self.string_count)
+                    .with_instrument(self.instrument)
                     .with_direction_hint(self.cents_deviation)
             };
+            if let Some(rate) = self.measured_beats() {
+                instructions = instructions.with_measured_beat_rate(rate);
+            } else if let Some(rate) = self.unison_beat_rate() {
+                instructions = instructions.with_beat_rate(rate);
+            }
             instructions.render(instructions_area, buf);
         } else {
             // Monochord note - simple instruction
-            let instructions = Instructions::simple().with_direction_hint(self.cents_deviation);
+            let instructions = Instructions::simple()
+                .with_instrument(self.instrument)
+                .with_direction_hint(self.cents_deviation);
             instructions.render(instructions_area, buf);
         }
 
-        // Cents meter (hidden during muting step)
-        if !is_muting_step {
-            let meter = if self.detected_freq.is_some() {
+        // Input level, left-aligned above the meter
+        if let Some(db) = self.level_db() {
+            let bar = level_bar(db, 8);
+            let mut tag = strings::format(
+                "tuning.level",
+                &[("bar", &bar), ("db", &format!("{:.0}", db))],
+            );
+            if self.gain_db != 0.0 {
+                tag.push_str(", ");
+                tag.push_str(&strings::format(
+                    "common.gain",
+                    &[("db", &format!("{:+.0}", self.gain_db))],
+                ));
+            }
+            buf.set_string(chunks[5].x + 1, chunks[5].y, &tag, Theme::muted());
+        }
+
+        // Reference volume, centred above the meter while it plays
+        if let Some(ReferenceStatus {
+            volume,
+            octave,
+            beats,
+            ..
+        }) = self.reference
+        {
+            let waveform = strings::get(self.waveform.label_key());
+            let waveform = match beats {
+                Some((cents, rate)) => strings::format(
+                    "tuning.reference_beats",
+                    &[
+                        ("waveform", &waveform),
+                        ("cents", &format!("{:+.0}", cents)),
+                        ("rate", &format!("{:.1}", rate)),
+                        ("keys", &Shortcuts::ARROWS),
+                    ],
+                ),
+                None if octave => {
+                    strings::format("tuning.reference_octave", &[("waveform", &waveform)])
+                }
+                None => waveform.to_string(),
+            };
+            let tag = strings::format(
+                "tuning.reference_volume",
+                &[
+                    ("waveform", &waveform),
+                    ("percent", &format!("{:.0}", volume * 100.0)),
+                    ("keys", &Shortcuts::VOLUME),
+                ],
+            );
+            let width = tag.chars().count() as u16;
+            buf.set_stringn(
+                chunks[5].x + chunks[5].width.saturating_sub(width) / 2,
+                chunks[5].y,
+                &tag,
+                chunks[5].width as usize,
+                Theme::accent(),
+            );
+        }
+
+        // Manual target, right-aligned above the meter
+        if self.target_offset != 0.0 || self.target_captured {
+            let key = if self.target_captured {
+                "tuning.target_captured"
+            } else {
+                "tuning.target_manual"
+            };
+            let tag = strings::format(key, &[("offset", &format!("{:+.1}", self.target_offset))]);
+            let x = (chunks[5].x + chunks[5].width).saturating_sub(tag.chars().count() as u16 + 1);
+            buf.set_string(x, chunks[5].y, &tag, Theme::warning());
+        }
+
+        // Settling, centered above the meter
+        if self.is_settling() && !is_muting_step && !self.has_notice() {
+            let tag = strings::get("tuning.settling");
+            let x = chunks[5].x + (chunks[5].width.saturating_sub(tag.chars().count() as u16)) / 2;
+            buf.set_string(x, chunks[5].y, tag, Theme::warning());
+        }
+
+        // Cents meter (hidden during muting step unless showing a notice)
+        if !is_muting_step || self.has_notice() {
+            let meter = if let Some(notice) = &self.reference_notice {
+                Meter::listening().message(notice.as_str())
+            } else if let Some(progress) = self.capture_progress {
+                Meter::listening().message(strings::format(
+                    "tuning.capturing",
+                    &[("percent", &format!("{:.0}", progress * 100.0))],
+                ))
+            } else if self.ambiguous {
+                Meter::listening().message(strings::get("tuning.multiple_notes"))
+            } else if self.detected_freq.is_some() || self.is_held() {
                 Meter::new(self.cents_deviation)
+                    .readout(self.readout, self.target_freq)
+                    .tolerance(self.tolerance())
+                    .held(self.is_held())
+                    .peak_hold(&self.peak_hold)
             } else {
                 Meter::listening()
             };
             meter.render(chunks[6], buf);
+
+            // Stability, right-aligned between the scale and the meter
+            if let Some(spread) = self.stability.spread().filter(|_| !self.has_notice()) {
+                let width = if chunks[6].width >= 80 {
+                    StabilityIndicator::WIDE
+                } else {
+                    12
+                };
+                let indicator_area = Rect {
+                    x: (chunks[6].x + chunks[6].width).saturating_sub(width + 1),
+                    y: chunks[6].y + 1,
+                    width,
+                    height: 1,
+                };
+                StabilityIndicator::new(spread).render(indicator_area, buf);
+            }
+
+            // Partial in use, left-aligned on the same line
+            if let Some(n) = self.partial.filter(|_| !self.has_notice()) {
+                let tag = strings::format("tuning.partial", &[("n", &n), ("ordinal", &ordinal(n))]);
+                buf.set_stringn(
+                    chunks[6].x + 1,
+                    chunks[6].y + 1,
+                    &tag,
+                    chunks[6].width.saturating_sub(2) as usize,
+                    Theme::accent(),
+                );
+            }
         }
 
-        // Help text
-        let help_text = format!(
-            "{} Confirm  {} Back  {} Progress  {} Skip  {} Quit",
-            Shortcuts::SPACE,
-            Shortcuts::BACK,
-            Shortcuts::PIANO,
-            Shortcuts::SKIP,
-            Shortcuts::QUIT
-        );
+        // Help text, counting down to the reference stopping by itself
+        let reference = match self.reference {
+            Some(ReferenceStatus {
+                remaining: Some(remaining),
+                ..
+            }) => strings::format(
+                "help.stop_reference_in",
+                &[("seconds", &remaining.as_secs_f32().ceil())],
+            ),
+            Some(_) => strings::get("help.stop_reference").to_string(),
+            None => strings::get("help.reference").to_string(),
+        };
+        let help_text = Shortcuts::help(&[
+            (Shortcuts::SPACE, strings::get("help.confirm")),
+            (Shortcuts::BACK, strings::get("help.back")),
+            (Shortcuts::PIANO, strings::get("help.progress")),
+            (Shortcuts::REFERENCE, &reference),
+            (Shortcuts::WAVEFORM, strings::get(self.waveform.label_key())),
+            (Shortcuts::DEVICES, strings::get("help.devices")),
+            (Shortcuts::SKIP, strings::get("help.skip")),
+            (Shortcuts::QUIT, strings::get("help.quit")),
+        ]);
         let help = Paragraph::new(help_text)
             .style(Theme::muted())
             .alignment(Alignment::Center);
         help.render(chunks[7], buf);
     }
 }
+
+/// Level meter `width` cells wide, from the level floor to 0 dBFS.
+fn level_bar(db: f32, width: usize) -> String {
+    let fill = (1.0 - db / TuningScreen::LEVEL_FLOOR_DB).clamp(0.0, 1.0) * width as f32;
+    let eighths = (fill * 8.0).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    if let Some(&block) = (eighths % 8)
+        .checked_sub(1)
+        .and_then(|i| BoxChars::BLOCKS.get(i))
+    {
+        bar.push(block);
+    }
+    format!("{:<width$}", bar, width = width)
+}
+
+/// English ordinal for a partial number: 2nd, 3rd, 4th.
+fn ordinal(n: u32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{}{}", n, suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_text(screen: &TuningScreen) -> String {
+        let area = Rect::new(0, 0, 100, 32);
+        let mut buf = Buffer::empty(area);
+        screen.render(area, &mut buf);
+        (0..area.height)
+            .map(|y| {
+                (0..area.width)
+                    .map(|x| buf[(x, y)].symbol().to_string())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_bichord_two_step_flow() {
+        // F3: bichord
+        let mut screen = TuningScreen::new("F3", 0, 88, 174.61, 2, 53);
+        assert_eq!(screen.tuning_step(), Some(TuningStep::TunePrimary));
+        let text = render_text(&screen);
+        assert!(text.contains("Step 1 of 2: Tune left string"), "{}", text);
+        assert!(text.contains("Mute the right string"), "{}", text);
+
+        // In tune, but the unison is still to come
+        screen.update(Duration::ZERO, 174.7, 1.0);
+        assert!(!screen.is_complete());
+        assert!(screen.unison_beat_rate().is_none());
+
+        assert!(screen.next_step());
+        assert_eq!(screen.tuning_step(), Some(TuningStep::TuneUnison));
+        let text = render_text(&screen);
+        assert!(
+            text.contains("Step 2 of 2: Tune right string to unison"),
+            "{}",
+            text
+        );
+
+        // Unison tolerance is tighter than the pitch tolerance
+        screen.update(Duration::from_millis(100), 175.1, 3.0);
+        assert!(!screen.is_complete());
+        assert!(render_text(&screen).contains("Beating: ~0.3/s"));
+
+        // Beats heard between the strings replace the estimate
+        screen.set_measured_beats(Some(2.3));
+        assert!(render_text(&screen).contains("Beats: 2.3/s"));
+        screen.set_measured_beats(None);
+
+        screen.update(Duration::from_millis(200), 174.8, 1.5);
+        assert!(screen.is_complete());
+        assert!(!screen.next_step());
+
+        screen.record_step();
+        let readings = screen.string_readings();
+        assert_eq!(readings.len(), 2);
+        assert_eq!(readings[0].string, "left");
+        assert_eq!(readings[0].cents, 1.0);
+        assert_eq!(readings[1].string, "right");
+        assert_eq!(readings[1].cents, 1.5);
+    }
+
+    #[test]
+    fn test_going_back_drops_step_reading() {
+        let mut screen = TuningScreen::new("F3", 0, 88, 174.61, 2, 53);
+        screen.update(Duration::ZERO, 174.7, 4.0);
+        screen.next_step();
+        assert!(screen.prev_step());
+        assert!(screen.string_readings().is_empty());
+
+        screen.update(Duration::ZERO, 174.7, 0.5);
+        screen.next_step();
+        assert_eq!(screen.string_readings()[0].cents, 0.5);
+    }
+
+    #[test]
+    fn test_left_first_trichord_sequence() {
+        // A3: trichord
+        let mut screen =
+            TuningScreen::new("A3", 4, 88, 220.0, 3, 57).with_string_order(StringOrder::LeftFirst);
+
+        let mut titles = vec![screen.tuning_step().unwrap().title()];
+        while screen.next_step() {
+            titles.push(screen.tuning_step().unwrap().title());
+        }
+        assert_eq!(
+            titles,
+            [
+                "Mute center and right strings",
+                "Tune left string",
+                "Tune center string",
+                "Tune right string",
+            ]
+        );
+
+        let text = render_text(&screen);
+        assert!(text.contains("Step 4 of 4: Tune right string"), "{}", text);
+        assert!(
+            text.contains("Tune it to match the center string"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn test_unstable_reading_not_held() {
+        let mut screen = TuningScreen::new("A0", 87, 88, 27.5, 1, 21);
+        for (i, cents) in [0.0, 8.0, -6.0, 10.0].into_iter().enumerate() {
+            screen.update(Duration::from_millis(200 * i as u64), 27.5, cents);
+        }
+        screen.clear(Duration::from_millis(800));
+        assert!(!screen.is_held());
+        assert_eq!(screen.cents(), 0.0);
+        assert!(render_text(&screen).contains("Listening..."));
+    }
+
+    #[test]
+    fn test_stability_indicator() {
+        let feed = |spread: f32| {
+            let mut screen = TuningScreen::new("A0", 87, 88, 27.5, 1, 21);
+            for i in 0..20 {
+                let cents = 1.0 + if i % 2 == 0 { spread } else { -spread };
+                screen.update(Duration::from_millis(50 * i as u64), 27.5, cents);
+            }
+            screen
+        };
+
+        let steady = feed(0.3);
+        assert!(steady.is_steady());
+        assert!(render_text(&steady).contains("● steady ±0.3¢"));
+
+        let noisy = feed(4.0);
+        assert!(!noisy.is_steady());
+        assert_eq!(noisy.stability(), Some(Stability::Unstable));
+        assert!(render_text(&noisy).contains("○ unstable ±4.0¢"));
+
+        // No indicator until there are enough readings
+        let screen = TuningScreen::new("A0", 87, 88, 27.5, 1, 21);
+        assert!(!render_text(&screen).contains("steady"));
+    }
+
+    #[test]
+    fn test_settling_after_pin_turn() {
+        let mut screen = TuningScreen::new("A4", 40, 88, 440.0, 1, 69);
+        assert!(!screen.is_settling());
+
+        // Gliding up from -20 cents, then flat
+        let mut readings: Vec<f32> = (0..=10).map(|i| -20.0 + 2.0 * i as f32).collect();
+        readings.extend([0.0; 5]);
+        for (i, &cents) in readings.iter().enumerate() {
+            screen.update(Duration::from_millis(50 * i as u64), 440.0, cents);
+            if i == 10 {
+                assert!(screen.is_settling());
+                assert!(render_text(&screen).contains("settling…"));
+            }
+        }
+        assert!(!screen.is_settling());
+        assert!(!render_text(&screen).contains("settling…"));
+    }
+
+    #[test]
+    fn test_partial_tag() {
+        let mut screen = TuningScreen::new("A0", 87, 88, 27.5, 1, 21);
+        assert!(!render_text(&screen).contains("measuring via"));
+
+        screen.set_partial(Some(2));
+        assert!(render_text(&screen).contains("measuring via 2nd partial"));
+        screen.set_partial(Some(3));
+        assert!(render_text(&screen).contains("measuring via 3rd partial"));
+        assert_eq!(ordinal(11), "11th");
+    }
+
+    #[test]
+    fn test_manual_target_tag() {
+        let mut screen = TuningScreen::new("A0", 87, 88, 27.5, 1, 21);
+        assert!(!render_text(&screen).contains("(manual)"));
+
+        screen.set_target(27.52, 1.5);
+        assert_eq!(screen.target_freq(), 27.52);
+        assert!(render_text(&screen).contains("target +1.5¢ (manual)"));
+
+        screen.set_target_captured(true);
+        screen.set_capture(Some(0.5));
+        let text = render_text(&screen);
+        assert!(text.contains("target +1.5¢ (captured)"));
+        assert!(text.contains("Capturing target"));
+    }
+
+    #[test]
+    fn test_transposition_in_header() {
+        let mut screen = TuningScreen::new("A4", 40, 88, 427.5, 1, 69);
+        screen.set_transposition(440.0, 0.0);
+        assert!(!render_text(&screen).contains("A4 ="));
+
+        screen.set_transposition(440.0, -50.0);
+        assert!(render_text(&screen).contains("Tuning: A4 · A4 = 440Hz − 50¢"));
+        screen.set_transposition(436.5, 100.0);
+        assert!(render_text(&screen).contains("A4 = 436.5Hz + 100¢"));
+    }
+
+    #[test]
+    fn test_partial_translation() {
+        strings::with_strings(strings::tests::german(), || {
+            let mut screen = TuningScreen::new("F3", 0, 88, 174.61, 2, 53);
+            screen.set_false_beats(true);
+            let text = render_text(&screen);
+
+            // Translated where the locale provides a string
+            assert!(
+                text.contains("Step 1 of 2: Linke Saite stimmen"),
+                "{}",
+                text
+            );
+            assert!(text.contains("Dämpfen Sie die rechte Saite"), "{}", text);
+            assert!(text.contains("Falsche Schwebungen?"), "{}", text);
+            assert!(text.contains("Höre zu..."), "{}", text);
+            // English elsewhere
+            assert!(text.contains("Press SPACE to continue"), "{}", text);
+            assert!(text.contains("[Q] Quit"), "{}", text);
+        });
+    }
+
+    #[test]
+    fn test_longer_translation_wraps_inside_panel() {
+        strings::with_strings(strings::tests::german(), || {
+            let screen = TuningScreen::new("F3", 0, 88, 174.61, 2, 53);
+            let area = Rect::new(0, 0, 44, 32);
+            let mut buf = Buffer::empty(area);
+            screen.render(area, &mut buf);
+
+            let rows: Vec<String> = (0..area.height)
+                .map(|y| {
+                    (0..area.width)
+                        .map(|x| buf[(x, y)].symbol().to_string())
+                        .collect()
+                })
+                .collect();
+            // Borders of the screen and the instructions panel stay intact
+            for row in &rows[1..rows.len() - 1] {
+                assert!(row.ends_with('│'), "{}", rows.join("\n"));
+            }
+            let panel: Vec<&String> = rows.iter().filter(|r| r.contains("Dämpfen")).collect();
+            assert_eq!(panel.len(), 1, "{}", rows.join("\n"));
+            assert!(panel[0].ends_with(" ││"), "{}", rows.join("\n"));
+            // The instruction wraps onto later lines instead of overflowing
+            assert!(
+                rows.iter().any(|r| r.contains("Zieltonhöhe.")),
+                "{}",
+                rows.join("\n")
+            );
+        });
+    }
+
+    #[test]
+    fn test_input_level() {
+        let mut screen = TuningScreen::new("F3", 0, 88, 174.61, 2, 53);
+        assert!(!render_text(&screen).contains("input"));
+
+        screen.set_level(0.1);
+        assert_eq!(screen.level_db(), Some(-20.0));
+        assert!(render_text(&screen).contains("input █████▍   -20 dB"));
+
+        screen.set_gain_db(20.0);
+        assert!(render_text(&screen).contains("input █████▍   -20 dB, gain +20 dB"));
+
+        screen.set_level(0.0);
+        assert_eq!(screen.level_db(), Some(TuningScreen::LEVEL_FLOOR_DB));
+        assert_eq!(level_bar(0.0, 4), "████");
+    }
+}
@@ -0,0 +1,609 @@
+//! Localized UI strings.
+//!
+//! Every user-facing string is looked up by a stable key such as
+//! `tuning.false_beats`. English is built in; a locale file overrides any
+//! subset of the keys and the rest fall back to English. Locale files live in
+//! `~/.config/onkey/locales/<lang>.toml`, with keys written as dotted names or
+//! nested tables:
+//!
+//! ```toml
+//! [meter]
+//! tighten = "Anziehen"
+//! loosen = "Lösen"
+//! ```
+//!
+//! Strings with arguments use `{name}` placeholders, filled in by [`format`].
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use directories::ProjectDirs;
+
+/// Built-in English strings.
+const ENGLISH: &[(&str, &str)] = &[
+    // Shared
+    ("common.too_small", "Terminal too small"),
+    ("common.listening", "Listening..."),
+    ("common.cents", "{cents} cents"),
+    ("common.hz", "{hz} Hz"),
+    ("common.clipping", "INPUT CLIPPING — reduce gain"),
+    ("common.gain", "gain {db} dB"),
+    // Help bar actions
+    ("help.navigate", "Navigate"),
+    ("help.select", "Select"),
+    ("help.a4_preset", "A4 preset (or type Hz)"),
+    ("help.change", "Change"),
+    ("help.stretch", "Stretch"),
+    ("help.start", "Start"),
+    ("help.instrument", "Instrument"),
+    ("help.temperament", "Temperament"),
+    ("help.root", "Key"),
+    ("help.history", "History"),
+    ("help.piano", "Piano"),
+    ("help.summary", "Summary"),
+    ("help.devices", "Devices"),
+    ("help.quit", "Quit"),
+    ("help.skip", "Skip"),
+    ("help.skip_calibration", "Skip calibration (use 440 Hz)"),
+    ("help.confirm", "Confirm"),
+    ("help.back", "Back"),
+    ("help.progress", "Progress"),
+    ("help.reference", "Reference"),
+    ("help.stop_reference", "Stop reference"),
+    ("help.stop_reference_in", "Stop reference ({seconds}s)"),
+    ("help.done", "Done"),
+    ("help.new_session", "New session"),
+    ("help.chart", "Chart"),
+    ("help.breakdown", "Breakdown"),
+    ("help.interval_check", "Interval check"),
+    ("help.pitch_raise", "Pitch raise"),
+    ("help.fine_tuning", "Fine tuning"),
+    ("help.use_device", "Use device"),
+    ("help.rescan", "Rescan"),
+    ("help.gain", "Gain"),
+    ("help.room_noise", "Room noise"),
+    ("help.accept", "Accept"),
+    ("help.discard", "Discard"),
+    ("help.retry_input", "Retry with the default input"),
+    ("help.save_quit", "Save and quit"),
+    ("help.curve", "Curve"),
+    ("help.close", "Close"),
+    // Reference waveforms
+    ("waveform.sine", "Sine"),
+    ("waveform.triangle", "Triangle"),
+    ("waveform.sawtooth", "Sawtooth"),
+    ("waveform.piano_like", "Piano"),
+    // Mode select
+    ("mode_select.title", "onkey - Piano Tuner"),
+    ("mode_select.heading", "Select Tuning Mode"),
+    ("mode_select.instrument", "Instrument: {instrument}"),
+    ("mode.quick.title", "Quick Tune"),
+    (
+        "mode.quick.description",
+        "Calibrate to the piano's current pitch center, then tune all strings relative to that. Best for regular maintenance.",
+    ),
+    ("mode.concert.title", "Concert Pitch"),
+    (
+        "mode.concert.description",
+        "Tune all strings to the A4 reference chosen next (440 Hz by default). Use for pianos that are already close to pitch.",
+    ),
+    ("mode.measure.title", "Measure"),
+    (
+        "mode.measure.description",
+        "Strike every note once to record how far the piano is from pitch, then choose a pitch raise or fine tuning.",
+    ),
+    // Setup
+    ("setup.title", "Session Setup"),
+    ("setup.heading", "{mode} · {instrument}"),
+    ("setup.a4", "A4 reference"),
+    ("setup.a4_calibrated", "Set by calibration"),
+    ("setup.transpose", "Transpose"),
+    ("setup.offset", "Fine offset"),
+    ("setup.semitone", "{count} semitone"),
+    ("setup.semitones", "{count} semitones"),
+    ("setup.temperament", "Temperament"),
+    ("setup.key", "Key"),
+    ("setup.stretch", "Stretch"),
+    ("setup.curve", "Curve"),
+    ("setup.intensity", "Intensity"),
+    ("setup.order", "Order"),
+    ("setup.percent", "{percent}%"),
+    ("setup.on", "On"),
+    ("setup.off", "Off"),
+    ("setup.from_root", "{temperament} from {root}"),
+    ("setup.wolf", "{temperament}, wolf {wolf}"),
+    // Calibration
+    ("calibration.title", "Calibration"),
+    (
+        "calibration.instruction",
+        "Play A4 (the A above middle C) and hold the key",
+    ),
+    ("calibration.deviation", "({deviation} Hz from 440)"),
+    ("calibration.no_pitch", "No pitch detected"),
+    ("calibration.samples", "Samples: {count}/{total}"),
+    ("calibration.presets", "Or pick A4: {presets}"),
+    (
+        "calibration.noise_measuring",
+        "Measuring room noise, stay quiet… {percent}%",
+    ),
+    ("calibration.noise_floor", "Room noise {db} dB, flatness {flatness}"),
+    (
+        "calibration.noise_suggestion",
+        "Suggested gate {gate} dB, confidence {confidence}",
+    ),
+    // Tuning
+    ("tuning.title", "Tuning: {note}"),
+    ("tuning.title_measured", "Tuning: {note} (measured {cents}c)"),
+    ("tuning.transposed", "A4 = {a4}Hz {offset}¢"),
+    ("tuning.phase.trichord", "Trichord"),
+    ("tuning.phase.bichord", "Bichord"),
+    ("tuning.phase.single", "Single"),
+    ("tuning.false_beats", "false beats?"),
+    ("tuning.target_manual", "target {offset}¢ (manual)"),
+    ("tuning.stretch", "stretch {offset}¢"),
+    ("tuning.stretch_octave", "stretch {offset}¢ ({octave} octave)"),
+    ("tuning.target_captured", "target {offset}¢ (captured)"),
+    (
+        "tuning.capturing",
+        "Capturing target — play the other instrument ({percent}%)",
+    ),
+    ("tuning.partial", "measuring via {ordinal} partial"),
+    ("tuning.level", "input {bar} {db} dB"),
+    ("tuning.multiple_notes", "Multiple notes detected"),
+    ("tuning.settling", "settling…"),
+    ("tuning.recording", "● REC"),
+    (
+        "tuning.reference_paused",
+        "Reference playing — detection paused",
+    ),
+    (
+        "tuning.reference_volume",
+        "♪ {waveform} {percent}% {keys}",
+    ),
+    ("tuning.reference_octave", "{waveform} + octave below"),
+    (
+        "tuning.reference_beats",
+        "{waveform} {cents}¢ → {rate} beats/s {keys}",
+    ),
+    // Coaching instructions
+    ("instructions.step", "Step {number} of {total}: {title}"),
+    (
+        "instructions.simple",
+        "Tune this string to the target pitch using the meter.",
+    ),
+    ("instructions.raise", "Raise the pitch slightly"),
+    ("instructions.lower", "Lower the pitch slightly"),
+    (
+        "instructions.tighten",
+        "Turn tuning pin CLOCKWISE (tighten) slightly",
+    ),
+    (
+        "instructions.loosen",
+        "Turn tuning pin COUNTER-CLOCKWISE (loosen) slightly",
+    ),
+    ("instructions.beating", "Beating: ~{rate}/s"),
+    ("instructions.beats", "Beats: {rate}/s"),
+    ("instructions.continue", "Press SPACE to continue"),
+    ("step.tune_primary.title", "Tune left string"),
+    (
+        "step.tune_primary.instruction",
+        "Mute the right string with a felt wedge or rubber mute. Tune the left string to the target pitch using the meter.",
+    ),
+    ("step.tune_unison.title", "Tune right string to unison"),
+    (
+        "step.tune_unison.instruction",
+        "Remove the mute. Tune the right string to match the left string until you hear no beats.",
+    ),
+    ("step.choir_primary.title", "Tune front 8' choir"),
+    (
+        "step.choir_primary.instruction",
+        "Take the back 8' stop off so only the front choir plucks. Tune it to the target pitch using the meter.",
+    ),
+    ("step.choir_unison.title", "Tune back 8' choir to unison"),
+    (
+        "step.choir_unison.instruction",
+        "Engage both 8' stops. Tune the back choir to match the front until you hear no beats.",
+    ),
+    ("step.mute_outer.title", "Mute outer strings"),
+    (
+        "step.mute_outer.instruction",
+        "Use felt strip or rubber mutes to mute the outer strings. Only the center string should sound.",
+    ),
+    ("step.mute.title", "Mute {first} and {second} strings"),
+    (
+        "step.mute.instruction",
+        "Use felt strip or rubber mutes to mute the {first} and {second} strings. Only the {reference} string should sound.",
+    ),
+    ("step.tune_string.title", "Tune {string} string"),
+    (
+        "step.tune_pitch.instruction",
+        "Tune the {string} string to the target pitch using the meter.",
+    ),
+    (
+        "step.tune_match.instruction",
+        "Unmute the {string} string. Tune it to match the {partner} string until you hear no beats.",
+    ),
+    ("string.left", "left"),
+    ("string.center", "center"),
+    ("string.right", "right"),
+    // Meter and stability
+    ("meter.held", "[{reading}] held"),
+    ("meter.tighten", "Tighten"),
+    ("meter.loosen", "Loosen"),
+    ("stability.steady", "steady"),
+    ("stability.wavering", "wavering"),
+    ("stability.unstable", "unstable"),
+    ("idle.message", "Idle — press any key or play a note"),
+    ("device_lost.title", "Input device lost"),
+    ("device_lost.saved", "Session saved"),
+    ("curve.title", "Stretch curve: {curve}"),
+    ("curve.title_off", "Stretch curve: {curve} (off)"),
+    // Measure
+    ("measure.title", "Measure: {note}"),
+    ("measure.phase", "Measure"),
+    (
+        "measure.instruction",
+        "Strike {note} once and let it ring. No tuning needed.",
+    ),
+    ("measure.last", "{note}: {cents} cents"),
+    ("measure_summary.title", "Measurement Complete"),
+    ("measure_summary.none", "No notes were measured"),
+    (
+        "measure_summary.pitch_raise",
+        "Pitch raise recommended before fine tuning",
+    ),
+    (
+        "measure_summary.fine_tuning",
+        "Close to pitch: fine tuning is enough",
+    ),
+    ("measure_summary.notes", "Notes measured: {count}"),
+    ("measure_summary.average", "Average deviation: {cents} cents"),
+    ("measure_summary.furthest", "Furthest off: {note} ({cents} cents)"),
+    // Complete
+    ("complete.title", "Tuning Complete!"),
+    ("complete.score", "Quality score: {score}/100"),
+    ("complete.notes_tuned", "Notes tuned: {count}"),
+    ("complete.average", "Average deviation: {cents} cents"),
+    ("complete.duration", "Duration: {duration}"),
+    ("complete.temperament", "Temperament: {name}"),
+    ("complete.stretch", "Stretch: {percent}% of the default curve"),
+    ("complete.skipped", "Skipped: {count}"),
+    ("complete.adjusted", "Adjusted targets: {notes}"),
+    ("complete.false_beats", "False beats: {notes}"),
+    ("complete.low_confidence", "? Low confidence: {notes}"),
+    ("complete.breakdown", "Breakdown"),
+    ("complete.in_tune", "● In tune (±5¢): {count}"),
+    ("complete.warning", "● Warning (±5-15¢): {count}"),
+    ("complete.out_of_tune", "● Out of tune (>±15¢): {count}"),
+    ("complete.section_avg", "avg {cents}¢"),
+    ("complete.section_worst", "worst {cents}¢"),
+    ("complete.quality_check", "Quality check"),
+    ("complete.chart", "Cents by key"),
+    ("complete.hook_finished", "Session hook finished"),
+    ("complete.hook_failed", "Session hook failed: {error}"),
+    ("verdict.excellent", "Excellent tuning!"),
+    ("verdict.good", "Good tuning!"),
+    ("verdict.acceptable", "Acceptable tuning"),
+    ("verdict.needs_improvement", "Tuning needs improvement"),
+    ("section.bass", "Bass"),
+    ("section.temperament", "Temperament"),
+    ("section.treble", "Treble"),
+    // Interval check
+    ("interval.title", "Interval Check"),
+    ("interval.counter", "Check {number} of {total}"),
+    (
+        "interval.instruction",
+        "Play the {interval} together and let them ring",
+    ),
+    ("interval.expected", "Expected: {rate} beats/s"),
+    ("interval.octave", "octave"),
+    ("interval.fifth", "fifth"),
+    ("interval.fourth", "fourth"),
+    ("interval.major_third", "major third"),
+    ("grade.good", "Good"),
+    ("grade.fair", "Fair"),
+    ("grade.poor", "Poor"),
+    // History
+    ("history.title", "Tuning History"),
+    (
+        "history.none",
+        "No sessions with a piano name yet (set piano_name in the config)",
+    ),
+    ("history.date", "Date"),
+    ("history.mode", "Mode"),
+    ("history.score", "Score"),
+    ("history.start", "Start"),
+    ("history.drift", "Drift"),
+    ("history.per_month", "{cents}/mo"),
+    ("history.start_chart", "Start dev."),
+    ("history.a4_chart", "A4"),
+    // Devices
+    ("devices.title", "Audio Devices"),
+    ("devices.input", "Input"),
+    ("devices.output", "Output"),
+    ("devices.none", "No devices found"),
+    ("devices.default", "(default)"),
+    ("devices.input_format", "{rate} Hz, {frames} frames"),
+    ("devices.input_rate", "{rate} Hz"),
+    ("devices.open_failed", "Could not open {device}: {error}"),
+    (
+        "devices.open_failed_current",
+        "Could not open {device}: {error} (still using {current})",
+    ),
+    ("devices.audio_error", "Audio error: {error}"),
+    // Plain status lines
+    (
+        "plain.mode_select",
+        "{mode} — {instrument} — up and down change the mode, enter sets up the session",
+    ),
+    (
+        "plain.setup",
+        "Setup — A4 {a4} — {temperament} — stretch {stretch} — up and down choose a setting, left and right change it, enter starts",
+    ),
+    (
+        "plain.calibration",
+        "Calibrating — play A4 and let it ring, or press 1 to 9 for a preset",
+    ),
+    (
+        "plain.tuning",
+        "Tuning {note} — {target} Hz target — {reading} — press space to confirm",
+    ),
+    ("plain.step", "{note}, step {number} of {total}: {title}"),
+    ("plain.reading", "reading {value}, {zone}"),
+    ("plain.no_reading", "waiting for a reading"),
+    ("plain.flat", "flat"),
+    ("plain.in_tune", "in tune"),
+    ("plain.sharp", "sharp"),
+    (
+        "plain.measure",
+        "Measuring {note} — play the note and let it ring, S skips",
+    ),
+    (
+        "plain.measure_summary",
+        "Measured {count} notes, mean {mean} cents — R for a pitch raise, F for fine tuning",
+    ),
+    (
+        "plain.interval_check",
+        "Interval check {number} of {total}: {interval} — play both notes together, S skips",
+    ),
+    ("plain.interval_check_done", "Interval checks done"),
+    (
+        "plain.complete",
+        "Session complete — {notes} notes, average {avg} cents — enter starts a new session, Q quits",
+    ),
+    (
+        "plain.devices",
+        "Audio devices — {device} — up and down choose, enter uses it, escape goes back",
+    ),
+    (
+        "plain.history",
+        "History of {piano} — {count} sessions — {session} — left and right change the piano, enter opens the summary",
+    ),
+    (
+        "plain.history_summary",
+        "Past session — {notes} notes, average {avg} cents — escape goes back",
+    ),
+    ("plain.history_session", "{date}, {mode}, score {score}"),
+    ("plain.idle", "Idle — press any key to resume"),
+    (
+        "plain.device_lost",
+        "Input device lost: {error} — press R to retry with the default input, Q to save and quit",
+    ),
+];
+
+/// English built-in, for the thread-local default.
+static BUILT_IN: Strings = Strings {
+    overrides: BTreeMap::new(),
+};
+
+thread_local! {
+    /// Table used by [`get`] and [`format`].
+    static CURRENT: Cell<&'static Strings> = Cell::new(&BUILT_IN);
+}
+
+/// A string table: translated overrides on top of the English built-in.
+#[derive(Debug, Clone, Default)]
+pub struct Strings {
+    overrides: BTreeMap<String, String>,
+}
+
+impl Strings {
+    /// The English built-in.
+    pub fn english() -> Self {
+        Self::default()
+    }
+
+    /// Parse a locale file. Nested tables become dotted keys; values other
+    /// than strings are ignored.
+    pub fn from_toml(content: &str) -> Result<Self, toml::de::Error> {
+        let table: toml::Table = toml::from_str(content)?;
+        let mut overrides = BTreeMap::new();
+        flatten("", &table, &mut overrides);
+        Ok(Self { overrides })
+    }
+
+    /// Load a locale file.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        Ok(Self::from_toml(&fs::read_to_string(path)?)?)
+    }
+
+    /// Strings for a locale such as `de` or `de_DE.UTF-8`. English if the
+    /// locale has no file.
+    pub fn for_locale(locale: &str) -> anyhow::Result<Self> {
+        let language = language(locale);
+        match Self::locales_dir().map(|dir| dir.join(format!("{}.toml", language))) {
+            Some(path) if language != "en" && path.exists() => Self::load(&path),
+            _ => Ok(Self::english()),
+        }
+    }
+
+    /// Directory holding the locale files.
+    pub fn locales_dir() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "onkey").map(|dirs| dirs.config_dir().join("locales"))
+    }
+
+    /// String for a key, falling back to English, then to the key itself.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.overrides
+            .get(key)
+            .map(String::as_str)
+            .or_else(|| english(key))
+            .unwrap_or(key)
+    }
+
+    /// String for a key with its `{name}` placeholders filled in.
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        args.iter()
+            .fold(self.get(key).to_string(), |text, (name, value)| {
+                text.replace(&format!("{{{}}}", name), &value.to_string())
+            })
+    }
+}
+
+fn english(key: &str) -> Option<&'static str> {
+    ENGLISH.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+fn flatten(prefix: &str, table: &toml::Table, out: &mut BTreeMap<String, String>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            toml::Value::String(text) => {
+                out.insert(key, text.clone());
+            }
+            toml::Value::Table(table) => flatten(&key, table, out),
+            _ => {}
+        }
+    }
+}
+
+/// Language code of a locale: `de_DE.UTF-8` gives `de`.
+fn language(locale: &str) -> String {
+    locale
+        .split(['_', '.', '@', '-'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Locale from the environment (`LC_ALL`, `LC_MESSAGES`, then `LANG`).
+pub fn locale_from_env() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .filter(|value| value != "C" && value != "POSIX")
+}
+
+/// Use a string table for the UI.
+pub fn install(strings: Strings) {
+    let strings: &'static Strings = Box::leak(Box::new(strings));
+    CURRENT.with(|current| current.set(strings));
+}
+
+/// UI string for a key.
+pub fn get(key: &'static str) -> &'static str {
+    CURRENT.with(Cell::get).get(key)
+}
+
+/// UI string for a key with its `{name}` placeholders filled in.
+pub fn format(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    CURRENT.with(Cell::get).format(key, args)
+}
+
+/// Run `f` with a string table installed, restoring the previous one after.
+#[cfg(test)]
+pub(crate) fn with_strings<R>(strings: Strings, f: impl FnOnce() -> R) -> R {
+    let previous = CURRENT.with(Cell::get);
+    install(strings);
+    let result = f();
+    CURRENT.with(|current| current.set(previous));
+    result
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Partial German locale used by the screen tests.
+    pub(crate) const GERMAN: &str = r#"
+[common]
+listening = "Höre zu..."
+
+[tuning]
+false_beats = "Falsche Schwebungen?"
+
+[step.tune_primary]
+title = "Linke Saite stimmen"
+instruction = "Dämpfen Sie die rechte Saite mit einem Filzkeil oder Gummidämpfer. Stimmen Sie die linke Saite mit Hilfe der Anzeige auf die Zieltonhöhe."
+
+[mode.concert]
+title = "Kammerton"
+description = "Alle Saiten auf die unten angegebene A4-Referenz stimmen (standardmäßig 440 Hz). Für Klaviere, die bereits nahe an der Tonhöhe sind."
+
+[meter]
+tighten = "Anziehen"
+"#;
+
+    /// The partial German locale, loaded from a file like the app does.
+    pub(crate) fn german() -> Strings {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("de.toml");
+        fs::write(&path, GERMAN).unwrap();
+        Strings::load(&path).unwrap()
+    }
+
+    #[test]
+    fn test_overrides_fall_back_to_english() {
+        let strings = german();
+        assert_eq!(strings.get("meter.tighten"), "Anziehen");
+        assert_eq!(
+            strings.get("step.tune_primary.title"),
+            "Linke Saite stimmen"
+        );
+        assert_eq!(strings.get("meter.loosen"), "Loosen");
+        assert_eq!(strings.get("no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn test_placeholders() {
+        let strings =
+            Strings::from_toml(r#""instructions.step" = "Schritt {number} von {total}: {title}""#)
+                .unwrap();
+        assert_eq!(
+            strings.format(
+                "instructions.step",
+                &[("number", &1), ("total", &2), ("title", &"Stimmen")]
+            ),
+            "Schritt 1 von 2: Stimmen"
+        );
+        assert_eq!(
+            Strings::english().format("measure.last", &[("note", &"A4"), ("cents", &"+1.5")]),
+            "A4: +1.5 cents"
+        );
+    }
+
+    #[test]
+    fn test_installed_table() {
+        assert_eq!(get("meter.tighten"), "Tighten");
+        with_strings(german(), || {
+            assert_eq!(get("meter.tighten"), "Anziehen");
+            assert_eq!(get("meter.loosen"), "Loosen");
+        });
+        assert_eq!(get("meter.tighten"), "Tighten");
+    }
+
+    #[test]
+    fn test_language_of_locale() {
+        assert_eq!(language("de_DE.UTF-8"), "de");
+        assert_eq!(language("pt-BR"), "pt");
+        assert_eq!(language("FR"), "fr");
+    }
+}
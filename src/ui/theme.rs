@@ -103,6 +103,8 @@ impl BoxChars {
     pub const LEFT_ARROW: char = '◀';
     /// Right arrow.
     pub const RIGHT_ARROW: char = '▶';
+    /// Peak-hold marker on the meter.
+    pub const PEAK_MARKER: char = '╎';
 
     /// Get block character for fill level (0.0 to 1.0).
     pub fn block_for_fill(fill: f32) -> char {
@@ -126,13 +128,68 @@ impl Shortcuts {
     pub const BACK: &'static str = "[B]";
     /// P key hint.
     pub const PIANO: &'static str = "[P]";
+    /// R key hint (pitch raise).
+    pub const PITCH_RAISE: &'static str = "[R]";
+    /// F key hint (fine tuning).
+    pub const FINE_TUNE: &'static str = "[F]";
+    /// C key hint (cents chart).
+    pub const CHART: &'static str = "[C]";
+    /// I key hint (interval check).
+    pub const INTERVAL_CHECK: &'static str = "[I]";
+    /// I key hint (instrument).
+    pub const INSTRUMENT: &'static str = "[I]";
+    /// T key hint (temperament).
+    pub const TEMPERAMENT: &'static str = "[T]";
+    /// K key hint (temperament root key).
+    pub const ROOT: &'static str = "[K]";
+    /// S key hint (stretch).
+    pub const STRETCH: &'static str = "[S]";
+    /// V key hint (view the stretch curve).
+    pub const VIEW_CURVE: &'static str = "[V]";
+    /// P key hint (A4 preset).
+    pub const PRESET: &'static str = "[P]";
+    /// H key hint (tuning history).
+    pub const HISTORY: &'static str = "[H]";
+    /// D key hint (audio devices).
+    pub const DEVICES: &'static str = "[D]";
+    /// R key hint (reference tone).
+    pub const REFERENCE: &'static str = "[R]";
+    /// W key hint (reference waveform).
+    pub const WAVEFORM: &'static str = "[W]";
+    /// [ and ] keys hint (reference volume).
+    pub const VOLUME: &'static str = "[[/]]";
+    /// R key hint (rescan devices).
+    pub const RESCAN: &'static str = "[R]";
+    /// R key hint (retry the input).
+    pub const RETRY: &'static str = "[R]";
+    /// F3/F4 keys hint (input gain).
+    pub const GAIN: &'static str = "[F3/F4]";
+    /// N key hint (room noise).
+    pub const NOISE: &'static str = "[N]";
+    /// Y key hint (accept).
+    pub const YES: &'static str = "[Y]";
+    /// N key hint (discard).
+    pub const NO: &'static str = "[N]";
+    /// Escape key hint.
+    pub const ESC: &'static str = "[Esc]";
     /// Enter key hint.
     pub const ENTER: &'static str = "[Enter]";
     /// Up/Down arrows hint.
     pub const ARROWS: &'static str = "[↑/↓]";
+    /// Left/Right arrows hint.
+    pub const LEFT_RIGHT: &'static str = "[←/→]";
 
     /// Format a shortcut with its action.
     pub fn format(key: &str, action: &str) -> String {
         format!("{} {}", key, action)
     }
+
+    /// Help bar text for shortcuts and their actions.
+    pub fn help(items: &[(&str, &str)]) -> String {
+        items
+            .iter()
+            .map(|(key, action)| Self::format(key, action))
+            .collect::<Vec<_>>()
+            .join("  ")
+    }
 }